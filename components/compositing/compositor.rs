@@ -4,6 +4,7 @@
 
 use compositor_data::{CompositorData, DoesntWantScrollEvents, WantsScrollEvents};
 use compositor_task::{Msg, CompositorTask, Exit, ChangeReadyState, SetIds, LayerProperties};
+use compositor_task::{ChangePageTitle, PromptBeforeUnload};
 use compositor_task::{GetGraphicsMetadata, CreateOrUpdateRootLayer, CreateOrUpdateDescendantLayer};
 use compositor_task::{SetLayerOrigin, Paint, ScrollFragmentPoint, LoadComplete};
 use compositor_task::{ShutdownComplete, ChangeRenderState, RenderMsgDiscarded};
@@ -13,7 +14,8 @@ use events::ScrollPositionChanged;
 use pipeline::CompositionPipeline;
 use platform::{Application, Window};
 use windowing;
-use windowing::{FinishedWindowEvent, IdleWindowEvent, LoadUrlWindowEvent, MouseWindowClickEvent};
+use windowing::{FinishedWindowEvent, IdleWindowEvent, KeyWindowEvent, LoadUrlWindowEvent};
+use windowing::MouseWindowClickEvent;
 use windowing::{MouseWindowEvent, MouseWindowEventClass, MouseWindowMouseDownEvent};
 use windowing::{MouseWindowMouseUpEvent, MouseWindowMoveEventClass, NavigationWindowEvent};
 use windowing::{QuitWindowEvent, RefreshWindowEvent, ResizeWindowEvent, ScrollWindowEvent};
@@ -36,9 +38,10 @@ use layers::rendergl::RenderContext;
 use layers::scene::Scene;
 use opengles::gl2;
 use png;
+use script_traits::Key;
 use servo_msg::compositor_msg::{Blank, Epoch, FinishedLoading, IdleRenderState, LayerId};
 use servo_msg::compositor_msg::{ReadyState, RenderingRenderState, RenderState, Scrollable};
-use servo_msg::constellation_msg::{ConstellationChan, ExitMsg, LoadUrlMsg, NavigateMsg};
+use servo_msg::constellation_msg::{ConstellationChan, ExitMsg, LoadData, LoadUrlMsg, NavigateMsg};
 use servo_msg::constellation_msg::{PipelineId, ResizedWindowMsg, WindowSizeData};
 use servo_msg::constellation_msg;
 use servo_util::geometry::{PagePx, ScreenPx, ViewportPx};
@@ -293,6 +296,14 @@ impl IOCompositor {
                     self.change_ready_state(pipeline_id, ready_state);
                 }
 
+                (Ok(ChangePageTitle(pipeline_id, title)), NotShuttingDown) => {
+                    self.change_page_title(pipeline_id, title);
+                }
+
+                (Ok(PromptBeforeUnload(pipeline_id, message)), NotShuttingDown) => {
+                    self.prompt_before_unload(pipeline_id, message);
+                }
+
                 (Ok(ChangeRenderState(pipeline_id, render_state)), NotShuttingDown) => {
                     self.change_render_state(pipeline_id, render_state);
                 }
@@ -363,6 +374,23 @@ impl IOCompositor {
 
     }
 
+    fn change_page_title(&mut self, pipeline_id: PipelineId, title: Option<String>) {
+        // Only the title of the top-level page is shown in the window.
+        let is_root = match self.root_pipeline {
+            Some(ref pipeline) => pipeline.id == pipeline_id,
+            None => false,
+        };
+        if is_root {
+            self.window.set_page_title(title);
+        }
+    }
+
+    fn prompt_before_unload(&self, pipeline_id: PipelineId, message: String) {
+        // TODO: Have the window ask the user whether to leave the page. Until
+        // windows can do that, pages leave without asking.
+        debug!("pipeline {:?} asked to prompt before unload: {:s}", pipeline_id, message);
+    }
+
     fn change_render_state(&mut self, pipeline_id: PipelineId, render_state: RenderState) {
         self.render_states.insert_or_update_with(pipeline_id,
                                                  render_state,
@@ -677,6 +705,10 @@ impl IOCompositor {
                 self.on_mouse_window_move_event_class(cursor);
             }
 
+            KeyWindowEvent(key) => {
+                self.on_key_window_event(key);
+            }
+
             ScrollWindowEvent(delta, cursor) => {
                 self.on_scroll_window_event(delta, cursor);
             }
@@ -739,7 +771,8 @@ impl IOCompositor {
                            layers"),
         };
 
-        let msg = LoadUrlMsg(root_pipeline_id, Url::parse(url_string.as_slice()).unwrap());
+        let msg = LoadUrlMsg(root_pipeline_id,
+                             LoadData::new(Url::parse(url_string.as_slice()).unwrap()));
         let ConstellationChan(ref chan) = self.constellation_chan;
         chan.send(msg);
     }
@@ -763,6 +796,13 @@ impl IOCompositor {
         }
     }
 
+    fn on_key_window_event(&self, key: Key) {
+        // FIXME: the key should go to the frame that has focus.
+        for layer in self.scene.root.iter() {
+            events::send_key_event(layer.clone(), key);
+        }
+    }
+
     fn on_scroll_window_event(&mut self,
                               delta: TypedPoint2D<DevicePixel, f32>,
                               cursor: TypedPoint2D<DevicePixel, i32>) {
@@ -43,6 +43,10 @@ impl ScriptListener for CompositorChan {
         self.chan.send(msg);
     }
 
+    fn set_title(&self, pipeline_id: PipelineId, title: Option<String>) {
+        self.chan.send(ChangePageTitle(pipeline_id, title));
+    }
+
     fn scroll_fragment_point(&self,
                              pipeline_id: PipelineId,
                              layer_id: LayerId,
@@ -56,6 +60,10 @@ impl ScriptListener for CompositorChan {
         port.recv();
     }
 
+    fn prompt_before_unload(&self, pipeline_id: PipelineId, message: String) {
+        self.chan.send(PromptBeforeUnload(pipeline_id, message));
+    }
+
     fn dup(&self) -> Box<ScriptListener+'static> {
         box self.clone() as Box<ScriptListener+'static>
     }
@@ -173,6 +181,11 @@ pub enum Msg {
     Paint(PipelineId, Epoch, Vec<(LayerId, Box<LayerBufferSet>)>),
     /// Alerts the compositor to the current status of page loading.
     ChangeReadyState(PipelineId, ReadyState),
+    /// Alerts the compositor that the title of a page has changed.
+    ChangePageTitle(PipelineId, Option<String>),
+    /// Asks the compositor to prompt the user before a page leaves, with the
+    /// message the page gave.
+    PromptBeforeUnload(PipelineId, String),
     /// Alerts the compositor to the current status of rendering.
     ChangeRenderState(PipelineId, RenderState),
     /// Alerts the compositor that the RenderMsg has been discarded.
@@ -11,20 +11,22 @@ use gfx::render_task;
 use libc;
 use pipeline::{Pipeline, CompositionPipeline};
 use layout_traits::{LayoutControlChan, LayoutTaskFactory, ExitNowMsg};
-use script_traits::{ResizeMsg, ResizeInactiveMsg, ExitPipelineMsg};
+use script_traits::{CollectMemoryReportMsg, ResizeMsg, ResizeInactiveMsg, ExitPipelineMsg};
 use script_traits::{ScriptControlChan, ScriptTaskFactory};
 use servo_msg::compositor_msg::LayerId;
-use servo_msg::constellation_msg::{ConstellationChan, ExitMsg, FailureMsg, Failure, FrameRectMsg};
-use servo_msg::constellation_msg::{IFrameSandboxState, IFrameUnsandboxed, InitLoadUrlMsg};
+use servo_msg::constellation_msg::{CollectMemoryReportMsg, ConstellationChan, CrashMsg, ExitMsg};
+use servo_msg::constellation_msg::{FailureMsg, Failure, FrameRectMsg};
+use servo_msg::constellation_msg::{IFrameSandboxState, IFrameUnsandboxed, InitLoadUrlMsg, LoadData};
 use servo_msg::constellation_msg::{LoadCompleteMsg, LoadIframeUrlMsg, LoadUrlMsg, Msg, NavigateMsg};
 use servo_msg::constellation_msg::{NavigationType, PipelineId, RendererReadyMsg, ResizedWindowMsg};
 use servo_msg::constellation_msg::{SubpageId, WindowSizeData};
 use servo_msg::constellation_msg;
-use servo_net::image_cache_task::{ImageCacheTask, ImageCacheTaskClient};
+use servo_net::image_cache_task::{GetHeapSize, ImageCacheTask, ImageCacheTaskClient};
 use gfx::font_cache_task::FontCacheTask;
 use servo_net::resource_task::ResourceTask;
 use servo_net::resource_task;
 use servo_util::geometry::PagePx;
+use servo_util::memory::{MemoryProfilerChan, MemoryReport, MemoryReporter, RegisterReporterMsg};
 use servo_util::opts::Opts;
 use servo_util::time::TimeProfilerChan;
 use servo_util::task::spawn_named;
@@ -53,6 +55,20 @@ pub struct Constellation<LTF, STF> {
     pub opts: Opts,
 }
 
+/// Lets the memory profiler ask the constellation for reports.
+struct ConstellationMemoryReporter(ConstellationChan);
+
+impl MemoryReporter for ConstellationMemoryReporter {
+    fn collect_report(&self) -> Option<MemoryReport> {
+        let ConstellationMemoryReporter(ConstellationChan(ref chan)) = *self;
+        let (response_chan, response_port) = channel();
+        match chan.send_opt(CollectMemoryReportMsg(response_chan)) {
+            Ok(()) => response_port.recv_opt().ok(),
+            Err(_) => None,
+        }
+    }
+}
+
 /// Stores the Id of the outermost frame's pipeline, along with a vector of children frames
 struct FrameTree {
     pub pipeline: Rc<Pipeline>,
@@ -246,9 +262,12 @@ impl<LTF: LayoutTaskFactory, STF: ScriptTaskFactory> Constellation<LTF, STF> {
                  image_cache_task: ImageCacheTask,
                  font_cache_task: FontCacheTask,
                  time_profiler_chan: TimeProfilerChan,
+                 memory_profiler_chan: MemoryProfilerChan,
                  devtools_chan: Option<DevtoolsControlChan>)
                  -> ConstellationChan {
         let (constellation_port, constellation_chan) = ConstellationChan::new();
+        memory_profiler_chan.send(RegisterReporterMsg(
+            box ConstellationMemoryReporter(constellation_chan.clone())));
         let constellation_chan_clone = constellation_chan.clone();
         let opts_clone = opts.clone();
         spawn_named("Constellation", proc() {
@@ -292,7 +311,7 @@ impl<LTF: LayoutTaskFactory, STF: ScriptTaskFactory> Constellation<LTF, STF> {
                     id: PipelineId,
                     subpage_id: Option<SubpageId>,
                     script_pipeline: Option<Rc<Pipeline>>,
-                    url: Url)
+                    load_data: LoadData)
                     -> Rc<Pipeline> {
             let pipe = Pipeline::create::<LTF, STF>(id,
                                                     subpage_id,
@@ -306,7 +325,7 @@ impl<LTF: LayoutTaskFactory, STF: ScriptTaskFactory> Constellation<LTF, STF> {
                                                     self.window_size,
                                                     self.opts.clone(),
                                                     script_pipeline,
-                                                    url);
+                                                    load_data);
             pipe.load();
             Rc::new(pipe)
     }
@@ -346,6 +365,10 @@ impl<LTF: LayoutTaskFactory, STF: ScriptTaskFactory> Constellation<LTF, STF> {
             FailureMsg(Failure { pipeline_id, subpage_id }) => {
                 self.handle_failure_msg(pipeline_id, subpage_id);
             }
+            CrashMsg(Failure { pipeline_id, subpage_id }) => {
+                debug!("constellation got crash message");
+                self.handle_crash_msg(pipeline_id, subpage_id);
+            }
             // This should only be called once per constellation, and only by the browser
             InitLoadUrlMsg(url) => {
                 debug!("constellation got init load URL message");
@@ -364,15 +387,15 @@ impl<LTF: LayoutTaskFactory, STF: ScriptTaskFactory> Constellation<LTF, STF> {
             // Load a new page, usually -- but not always -- from a mouse click or typed url
             // If there is already a pending page (self.pending_frames), it will not be overridden;
             // However, if the id is not encompassed by another change, it will be.
-            LoadUrlMsg(source_id, url) => {
+            LoadUrlMsg(source_id, load_data) => {
                 debug!("constellation got URL load message");
-                self.handle_load_url_msg(source_id, url);
+                self.handle_load_url_msg(source_id, load_data);
             }
             // A page loaded through one of several methods above has completed all parsing,
             // script, and reflow messages have been sent.
             LoadCompleteMsg(pipeline_id, url) => {
                 debug!("constellation got load complete message");
-                self.compositor_chan.send(LoadComplete(pipeline_id, url));
+                self.handle_load_complete_msg(pipeline_id, url);
             }
             // Handle a forward or back request
             NavigateMsg(direction) => {
@@ -388,6 +411,10 @@ impl<LTF: LayoutTaskFactory, STF: ScriptTaskFactory> Constellation<LTF, STF> {
                 debug!("constellation got window resize message");
                 self.handle_resized_window_msg(new_size);
             }
+            CollectMemoryReportMsg(response_chan) => {
+                debug!("constellation got memory report message");
+                self.handle_collect_memory_report_msg(response_chan);
+            }
         }
         true
     }
@@ -422,14 +449,62 @@ impl<LTF: LayoutTaskFactory, STF: ScriptTaskFactory> Constellation<LTF, STF> {
             Some(pipeline) => pipeline.clone()
         };
 
-        fn force_pipeline_exit(old_pipeline: &Rc<Pipeline>) {
-            let ScriptControlChan(ref old_script) = old_pipeline.script_chan;
-            let _ = old_script.send_opt(ExitPipelineMsg(old_pipeline.id));
-            let _ = old_pipeline.render_chan.send_opt(render_task::ExitMsg(None));
-            let LayoutControlChan(ref old_layout) = old_pipeline.layout_chan;
-            let _ = old_layout.send_opt(ExitNowMsg);
-        }
         force_pipeline_exit(&old_pipeline);
+        self.load_failure_page(pipeline_id, subpage_id);
+    }
+
+    /// Replaces a pipeline whose script task caught a panic with about:failure.
+    /// The script task and the other pages it runs are fine, so the pipeline
+    /// can exit the usual way, and hard-fail mode doesn't apply.
+    fn handle_crash_msg(&mut self, pipeline_id: PipelineId, subpage_id: Option<SubpageId>) {
+        debug!("handling crash message from pipeline {:?}, {:?}", pipeline_id, subpage_id);
+
+        let old_pipeline = match self.pipelines.find(&pipeline_id) {
+            None => {
+                debug!("no existing pipeline found; bailing out of crash recovery.");
+                return;
+            }
+            Some(pipeline) => pipeline.clone()
+        };
+        old_pipeline.exit();
+
+        // The script task shuts the frames inside the crashed one down along
+        // with it.
+        for old_frame in self.find_all(pipeline_id).iter() {
+            for frame in old_frame.iter() {
+                if frame.pipeline.id != pipeline_id {
+                    self.pipelines.remove(&frame.pipeline.id);
+                }
+            }
+        }
+        self.load_failure_page(pipeline_id, subpage_id);
+    }
+
+    /// Tells the compositor that the pipeline finished loading, and the
+    /// document around the frame of the pipeline, if any, which may live in
+    /// another script task.
+    fn handle_load_complete_msg(&mut self, pipeline_id: PipelineId, url: Url) {
+        self.compositor_chan.send(LoadComplete(pipeline_id, url));
+        let subpage_id = match self.pipelines.find(&pipeline_id) {
+            Some(pipeline) => pipeline.subpage_id,
+            None => return,
+        };
+        let parent = self.find_all(pipeline_id).iter().filter_map(|frame| {
+            frame.parent.borrow().clone()
+        }).next();
+        match (parent, subpage_id) {
+            (Some(parent), Some(subpage_id)) => parent.subframe_loaded(subpage_id),
+            _ => {}
+        }
+    }
+
+    /// Loads about:failure in place of a pipeline that went away, in the
+    /// frame the pipeline had.
+    fn load_failure_page(&mut self, pipeline_id: PipelineId, subpage_id: Option<SubpageId>) {
+        let parent = self.find_all(pipeline_id).iter().filter_map(|frame| {
+            frame.parent.borrow().clone()
+        }).next();
+        let current = self.current_frame().iter().any(|frame| frame.contains(pipeline_id));
         self.pipelines.remove(&pipeline_id);
 
         loop {
@@ -449,13 +524,15 @@ impl<LTF: LayoutTaskFactory, STF: ScriptTaskFactory> Constellation<LTF, STF> {
 
         let new_id = self.get_next_pipeline_id();
         let pipeline = self.new_pipeline(new_id, subpage_id, None,
-                                         Url::parse("about:failure").unwrap());
+                                         LoadData::new(Url::parse("about:failure").unwrap()));
 
+        // A pipeline that wasn't shown yet has no frame to be replaced in, so
+        // the failure page is added to its parent instead.
         self.pending_frames.push(FrameChange{
-            before: Some(pipeline_id),
+            before: if current { Some(pipeline_id) } else { None },
             after: Rc::new(FrameTree {
                 pipeline: pipeline.clone(),
-                parent: RefCell::new(None),
+                parent: RefCell::new(parent),
                 children: RefCell::new(vec!()),
             }),
             navigation_type: constellation_msg::Load,
@@ -466,7 +543,7 @@ impl<LTF: LayoutTaskFactory, STF: ScriptTaskFactory> Constellation<LTF, STF> {
 
     fn handle_init_load(&mut self, url: Url) {
         let next_pipeline_id = self.get_next_pipeline_id();
-        let pipeline = self.new_pipeline(next_pipeline_id, None, None, url);
+        let pipeline = self.new_pipeline(next_pipeline_id, None, None, LoadData::new(url));
 
         self.pending_frames.push(FrameChange {
             before: None,
@@ -573,11 +650,16 @@ impl<LTF: LayoutTaskFactory, STF: ScriptTaskFactory> Constellation<LTF, STF> {
             source Id of LoadIframeUrlMsg does have an associated pipeline in
             constellation. This should be impossible.").clone();
 
-        let source_url = source_pipeline.url.clone();
+        let source_url = source_pipeline.load_data.url.clone();
 
-        let same_script = (source_url.host() == url.host() &&
-                           source_url.port() == url.port()) && sandbox == IFrameUnsandboxed;
         // FIXME(tkuehn): Need to follow the standardized spec for checking same-origin
+        // about:blank documents get the origin of the document that created
+        // them, so they are always same-origin with it.
+        let same_origin = url.serialize().as_slice() == "about:blank" ||
+                          (source_url.scheme == url.scheme &&
+                           source_url.host() == url.host() &&
+                           source_url.port() == url.port());
+        let same_script = same_origin && sandbox == IFrameUnsandboxed;
         // Reuse the script task if the URL is same-origin
         let new_pipeline = if same_script {
             debug!("Constellation: loading same-origin iframe at {:?}", url);
@@ -591,7 +673,10 @@ impl<LTF: LayoutTaskFactory, STF: ScriptTaskFactory> Constellation<LTF, STF> {
             next_pipeline_id,
             Some(subpage_id),
             new_pipeline,
-            url
+            LoadData {
+                sandbox: sandbox,
+                ..LoadData::new(url)
+            }
         );
 
         let rect = self.pending_sizes.pop(&(source_pipeline_id, subpage_id));
@@ -608,8 +693,8 @@ impl<LTF: LayoutTaskFactory, STF: ScriptTaskFactory> Constellation<LTF, STF> {
         self.pipelines.insert(pipeline.id, pipeline);
     }
 
-    fn handle_load_url_msg(&mut self, source_id: PipelineId, url: Url) {
-        debug!("Constellation: received message to load {:s}", url.to_string());
+    fn handle_load_url_msg(&mut self, source_id: PipelineId, load_data: LoadData) {
+        debug!("Constellation: received message to load {:s}", load_data.url.to_string());
         // Make sure no pending page would be overridden.
         let source_frame = self.current_frame().as_ref().unwrap().find(source_id).expect(
             "Constellation: received a LoadUrlMsg from a pipeline_id associated
@@ -635,7 +720,7 @@ impl<LTF: LayoutTaskFactory, STF: ScriptTaskFactory> Constellation<LTF, STF> {
         let subpage_id = source_frame.pipeline.subpage_id;
         let next_pipeline_id = self.get_next_pipeline_id();
 
-        let pipeline = self.new_pipeline(next_pipeline_id, subpage_id, None, url);
+        let pipeline = self.new_pipeline(next_pipeline_id, subpage_id, None, load_data);
 
         self.pending_frames.push(FrameChange{
             before: Some(source_id),
@@ -665,6 +750,7 @@ impl<LTF: LayoutTaskFactory, STF: ScriptTaskFactory> Constellation<LTF, STF> {
                     let old = self.current_frame().as_ref().unwrap();
                     for frame in old.iter() {
                         frame.pipeline.revoke_paint_permission();
+                        frame.pipeline.unload();
                     }
                 }
                 self.navigation_context.forward()
@@ -677,6 +763,7 @@ impl<LTF: LayoutTaskFactory, STF: ScriptTaskFactory> Constellation<LTF, STF> {
                     let old = self.current_frame().as_ref().unwrap();
                     for frame in old.iter() {
                         frame.pipeline.revoke_paint_permission();
+                        frame.pipeline.unload();
                     }
                 }
                 self.navigation_context.back()
@@ -735,8 +822,11 @@ impl<LTF: LayoutTaskFactory, STF: ScriptTaskFactory> Constellation<LTF, STF> {
                         "Constellation: pending frame change refers to an old \
                         frame not contained in the current frame. This is a bug");
 
+                    // The documents that were navigated away from go
+                    // away, even though their pipelines stay in the history.
                     for frame in to_revoke.iter() {
                         frame.pipeline.revoke_paint_permission();
+                        frame.pipeline.unload();
                     }
 
                     // If to_add is not the root frame, then replace revoked_frame with it.
@@ -779,6 +869,30 @@ impl<LTF: LayoutTaskFactory, STF: ScriptTaskFactory> Constellation<LTF, STF> {
         }
     }
 
+    /// Asks every pipeline and the image cache what they use memory for, and
+    /// sends the sum of their reports on `response_chan` once they all
+    /// answered, without waiting for them here.
+    fn handle_collect_memory_report_msg(&self, response_chan: Sender<MemoryReport>) {
+        let (report_chan, report_port) = channel();
+        for pipeline in self.pipelines.values() {
+            let ScriptControlChan(ref script_chan) = pipeline.script_chan;
+            script_chan.send(CollectMemoryReportMsg(pipeline.id, report_chan.clone()));
+        }
+        // The pipelines that go away in the meantime drop their sender.
+        drop(report_chan);
+        let (image_cache_chan, image_cache_port) = channel();
+        self.image_cache_task.send(GetHeapSize(image_cache_chan));
+
+        spawn_named("Memory report collector", proc() {
+            let mut report = MemoryReport::new();
+            for pipeline_report in report_port.iter() {
+                report.add(&pipeline_report);
+            }
+            report.image_cache = image_cache_port.recv_opt().unwrap_or(0);
+            let _ = response_chan.send_opt(report);
+        });
+    }
+
     /// Called when the window is resized.
     fn handle_resized_window_msg(&mut self, new_size: WindowSizeData) {
         let mut already_seen = HashSet::new();
@@ -872,3 +986,12 @@ impl<LTF: LayoutTaskFactory, STF: ScriptTaskFactory> Constellation<LTF, STF> {
     }
 }
 
+/// Tells the tasks of a pipeline to exit right away, without waiting for the
+/// script task, which may be gone already.
+fn force_pipeline_exit(old_pipeline: &Rc<Pipeline>) {
+    let ScriptControlChan(ref old_script) = old_pipeline.script_chan;
+    let _ = old_script.send_opt(ExitPipelineMsg(old_pipeline.id));
+    let _ = old_pipeline.render_chan.send_opt(render_task::ExitMsg(None));
+    let LayoutControlChan(ref old_layout) = old_pipeline.layout_chan;
+    let _ = old_layout.send_opt(ExitNowMsg);
+}
@@ -14,7 +14,7 @@ use geom::size::TypedSize2D;
 use layers::geometry::DevicePixel;
 use layers::layers::Layer;
 use script_traits::{ClickEvent, MouseDownEvent, MouseMoveEvent, MouseUpEvent, SendEventMsg};
-use script_traits::{ScriptControlChan};
+use script_traits::{Key, KeyEvent, ScriptControlChan};
 use servo_msg::compositor_msg::FixedPosition;
 use servo_util::geometry::PagePx;
 use std::rc::Rc;
@@ -183,6 +183,13 @@ pub fn send_mouse_event(layer: Rc<Layer<CompositorData>>,
     let _ = chan.send_opt(SendEventMsg(layer.extra_data.borrow().pipeline.id.clone(), message));
 }
 
+/// Sends a key press to the pipeline of the given layer.
+pub fn send_key_event(layer: Rc<Layer<CompositorData>>, key: Key) {
+    let ScriptControlChan(ref chan) = layer.extra_data.borrow().pipeline.script_chan;
+    let _ = chan.send_opt(SendEventMsg(layer.extra_data.borrow().pipeline.id.clone(),
+                                       KeyEvent(key)));
+}
+
 pub fn send_mouse_move_event(layer: Rc<Layer<CompositorData>>,
                              cursor: TypedPoint2D<PagePx, f32>) {
     let message = MouseMoveEvent(cursor.to_untyped());
@@ -2,7 +2,8 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use compositor_task::{Msg, Exit, ChangeReadyState, SetIds};
+use compositor_task::{Msg, Exit, ChangeReadyState, ChangePageTitle, SetIds};
+use compositor_task::PromptBeforeUnload;
 use compositor_task::{GetGraphicsMetadata, CreateOrUpdateRootLayer, CreateOrUpdateDescendantLayer};
 use compositor_task::{SetLayerOrigin, Paint, ScrollFragmentPoint, LoadComplete};
 use compositor_task::{ShutdownComplete, ChangeRenderState, RenderMsgDiscarded};
@@ -91,7 +92,9 @@ impl NullCompositor {
                 CreateOrUpdateRootLayer(..) |
                 CreateOrUpdateDescendantLayer(..) |
                 SetLayerOrigin(..) | Paint(..) |
-                ChangeReadyState(..) | ChangeRenderState(..) | ScrollFragmentPoint(..) |
+                ChangeReadyState(..) | ChangePageTitle(..) | ChangeRenderState(..) |
+                PromptBeforeUnload(..) |
+                ScrollFragmentPoint(..) |
                 LoadComplete(..) | RenderMsgDiscarded(..) => ()
             }
         }
@@ -5,20 +5,27 @@
 use CompositorChan;
 use layout_traits::{LayoutTaskFactory, LayoutControlChan};
 use script_traits::{ScriptControlChan, ScriptTaskFactory};
-use script_traits::{AttachLayoutMsg, LoadMsg, NewLayoutInfo, ExitPipelineMsg};
+use script_traits::{AttachLayoutMsg, LoadMsg, NewLayoutInfo, ExitPipelineMsg, UnloadMsg};
+use script_traits::SubframeLoadedMsg;
 
 use devtools_traits::DevtoolsControlChan;
 use gfx::render_task::{PaintPermissionGranted, PaintPermissionRevoked};
 use gfx::render_task::{RenderChan, RenderTask};
-use servo_msg::constellation_msg::{ConstellationChan, Failure, PipelineId, SubpageId};
+use servo_msg::constellation_msg::{ConstellationChan, Failure, LoadData, PipelineId, SubpageId};
 use servo_msg::constellation_msg::WindowSizeData;
 use servo_net::image_cache_task::ImageCacheTask;
 use gfx::font_cache_task::FontCacheTask;
 use servo_net::resource_task::ResourceTask;
 use servo_util::opts::Opts;
 use servo_util::time::TimeProfilerChan;
+use std::comm::Select;
+use std::io::Timer;
 use std::rc::Rc;
-use url::Url;
+use std::time::duration::Duration;
+
+/// How long the script task gets to unload the documents of a pipeline that
+/// is exiting before we stop waiting for it.
+static UNLOAD_TIMEOUT_MS: i64 = 1000;
 
 /// A uniquely-identifiable pipeline of script task, layout task, and render task.
 pub struct Pipeline {
@@ -29,8 +36,8 @@ pub struct Pipeline {
     pub render_chan: RenderChan,
     pub layout_shutdown_port: Receiver<()>,
     pub render_shutdown_port: Receiver<()>,
-    /// The most recently loaded url
-    pub url: Url,
+    /// The most recently loaded page
+    pub load_data: LoadData,
 }
 
 /// The subset of the pipeline that is needed for layer composition.
@@ -58,7 +65,7 @@ impl Pipeline {
                       window_size: WindowSizeData,
                       opts: Opts,
                       script_pipeline: Option<Rc<Pipeline>>,
-                      url: Url)
+                      load_data: LoadData)
                       -> Pipeline {
         let layout_pair = ScriptTaskFactory::create_layout_channel(None::<&mut STF>);
         let (render_port, render_chan) = RenderChan::new();
@@ -134,7 +141,7 @@ impl Pipeline {
                       render_chan,
                       layout_shutdown_port,
                       render_shutdown_port,
-                      url)
+                      load_data)
     }
 
     pub fn new(id: PipelineId,
@@ -144,7 +151,7 @@ impl Pipeline {
                render_chan: RenderChan,
                layout_shutdown_port: Receiver<()>,
                render_shutdown_port: Receiver<()>,
-               url: Url)
+               load_data: LoadData)
                -> Pipeline {
         Pipeline {
             id: id,
@@ -154,13 +161,13 @@ impl Pipeline {
             render_chan: render_chan,
             layout_shutdown_port: layout_shutdown_port,
             render_shutdown_port: render_shutdown_port,
-            url: url,
+            load_data: load_data,
         }
     }
 
     pub fn load(&self) {
         let ScriptControlChan(ref chan) = self.script_chan;
-        chan.send(LoadMsg(self.id, self.url.clone()));
+        chan.send(LoadMsg(self.id, self.load_data.clone()));
     }
 
     pub fn grant_paint_permission(&self) {
@@ -172,6 +179,19 @@ impl Pipeline {
         let _ = self.render_chan.send_opt(PaintPermissionRevoked);
     }
 
+    /// Unloads the document of the pipeline, which was navigated away from.
+    pub fn unload(&self) {
+        let ScriptControlChan(ref chan) = self.script_chan;
+        let _ = chan.send_opt(UnloadMsg(self.id));
+    }
+
+    /// Tells the document of the pipeline that the document of one of its
+    /// frames finished loading.
+    pub fn subframe_loaded(&self, subpage_id: SubpageId) {
+        let ScriptControlChan(ref chan) = self.script_chan;
+        let _ = chan.send_opt(SubframeLoadedMsg(self.id, subpage_id));
+    }
+
     pub fn exit(&self) {
         debug!("pipeline {:?} exiting", self.id);
 
@@ -181,11 +201,43 @@ impl Pipeline {
         if chan.send_opt(ExitPipelineMsg(self.id)).is_ok() {
             // Wait until all slave tasks have terminated and run destructors
             // NOTE: We don't wait for script task as we don't always own it
-            let _ = self.render_shutdown_port.recv_opt();
+            if !self.wait_for_render_shutdown() {
+                // The unload handlers of the page are taking too long. The
+                // tasks will shut down on their own once they are done.
+                debug!("pipeline {:?} took too long to unload", self.id);
+                return;
+            }
             let _ = self.layout_shutdown_port.recv_opt();
         }
     }
 
+    /// Waits for the render task to shut down, which happens once the
+    /// script task has unloaded the documents and shut down layout. Returns
+    /// false if that took longer than `UNLOAD_TIMEOUT_MS`.
+    fn wait_for_render_shutdown(&self) -> bool {
+        let mut timer = match Timer::new() {
+            Ok(timer) => timer,
+            Err(_) => {
+                let _ = self.render_shutdown_port.recv_opt();
+                return true;
+            }
+        };
+        let timeout = timer.oneshot(Duration::milliseconds(UNLOAD_TIMEOUT_MS));
+        let sel = Select::new();
+        let mut render_port = sel.handle(&self.render_shutdown_port);
+        let mut timeout_port = sel.handle(&timeout);
+        unsafe {
+            render_port.add();
+            timeout_port.add();
+        }
+        if sel.wait() == render_port.id() {
+            let _ = render_port.recv_opt();
+            true
+        } else {
+            false
+        }
+    }
+
     pub fn to_sendable(&self) -> CompositionPipeline {
         CompositionPipeline {
             id: self.id.clone(),
@@ -8,8 +8,8 @@ use windowing::{ApplicationMethods, WindowEvent, WindowMethods};
 use windowing::{IdleWindowEvent, ResizeWindowEvent, LoadUrlWindowEvent, MouseWindowEventClass,  MouseWindowMoveEventClass};
 use windowing::{ScrollWindowEvent, ZoomWindowEvent, PinchZoomWindowEvent, NavigationWindowEvent, FinishedWindowEvent};
 use windowing::{QuitWindowEvent, MouseWindowClickEvent, MouseWindowMouseDownEvent, MouseWindowMouseUpEvent};
-use windowing::RefreshWindowEvent;
-use windowing::{Forward, Back};
+use windowing::{KeyWindowEvent, RefreshWindowEvent};
+use windowing::Forward;
 
 use alert::{Alert, AlertMethods};
 use libc::{exit, c_int};
@@ -26,6 +26,7 @@ use layers::geometry::DevicePixel;
 use servo_msg::compositor_msg::{IdleRenderState, RenderState, RenderingRenderState};
 use servo_msg::compositor_msg::{FinishedLoading, Blank, Loading, PerformingLayout, ReadyState};
 use servo_util::geometry::ScreenPx;
+use script_traits::{BackspaceKey, CharacterKey, EnterKey, EscapeKey, TabKey};
 
 use glfw;
 use glfw::Context;
@@ -98,6 +99,7 @@ pub struct Window {
 
     ready_state: Cell<ReadyState>,
     render_state: Cell<RenderState>,
+    page_title: RefCell<Option<String>>,
 
     last_title_set_time: Cell<Timespec>,
 }
@@ -128,6 +130,7 @@ impl WindowMethods<Application> for Window {
 
             ready_state: Cell::new(Blank),
             render_state: Cell::new(IdleRenderState),
+            page_title: RefCell::new(None),
 
             last_title_set_time: Cell::new(Timespec::new(0, 0)),
         };
@@ -136,6 +139,7 @@ impl WindowMethods<Application> for Window {
         window.glfw_window.set_framebuffer_size_polling(true);
         window.glfw_window.set_refresh_polling(true);
         window.glfw_window.set_key_polling(true);
+        window.glfw_window.set_char_polling(true);
         window.glfw_window.set_mouse_button_polling(true);
         window.glfw_window.set_cursor_pos_polling(true);
         window.glfw_window.set_scroll_polling(true);
@@ -201,6 +205,15 @@ impl WindowMethods<Application> for Window {
         self.update_window_title()
     }
 
+    /// Sets the page title.
+    fn set_page_title(&self, title: Option<String>) {
+        *self.page_title.borrow_mut() = title;
+        // Show the new title right away rather than waiting for the next
+        // state change.
+        self.last_title_set_time.set(Timespec::new(0, 0));
+        self.update_window_title()
+    }
+
     fn hidpi_factor(&self) -> ScaleFactor<ScreenPx, DevicePixel, f32> {
         let backing_size = self.framebuffer_size().width.get();
         let window_size = self.size().width.get();
@@ -216,6 +229,9 @@ impl Window {
                     self.handle_key(key, mods)
                 }
             },
+            glfw::CharEvent(character) => {
+                self.event_queue.borrow_mut().push(KeyWindowEvent(CharacterKey(character)));
+            },
             glfw::FramebufferSizeEvent(width, height) => {
                 self.event_queue.borrow_mut().push(
                     ResizeWindowEvent(TypedSize2D(width as uint, height as uint)));
@@ -300,7 +316,13 @@ impl Window {
                         self.glfw_window.set_title("Rendering — Servo")
                     }
                     IdleRenderState => {
-                        self.glfw_window.set_title("Servo")
+                        match *self.page_title.borrow() {
+                            Some(ref title) => {
+                                self.glfw_window.set_title(format!("{:s} — Servo",
+                                                                   title.as_slice()).as_slice())
+                            }
+                            None => self.glfw_window.set_title("Servo"),
+                        }
                     }
                 }
             }
@@ -310,7 +332,9 @@ impl Window {
     /// Helper function to handle keyboard events.
     fn handle_key(&self, key: glfw::Key, mods: glfw::Modifiers) {
         match key {
-            glfw::KeyEscape => self.glfw_window.set_should_close(true),
+            // Escape is the page's, to close dialogs with.
+            glfw::KeyEscape => self.event_queue.borrow_mut().push(KeyWindowEvent(EscapeKey)),
+            glfw::KeyQ if mods.contains(glfw::Control) => self.glfw_window.set_should_close(true), // Ctrl+Q
             glfw::KeyL if mods.contains(glfw::Control) => self.load_url(), // Ctrl+L
             glfw::KeyEqual if mods.contains(glfw::Control) => { // Ctrl-+
                 self.event_queue.borrow_mut().push(ZoomWindowEvent(1.1));
@@ -322,8 +346,11 @@ impl Window {
                 self.event_queue.borrow_mut().push(NavigationWindowEvent(Forward));
             }
             glfw::KeyBackspace => { // Backspace
-                self.event_queue.borrow_mut().push(NavigationWindowEvent(Back));
+                // The page navigates back itself unless it is editing text.
+                self.event_queue.borrow_mut().push(KeyWindowEvent(BackspaceKey));
             }
+            glfw::KeyTab => self.event_queue.borrow_mut().push(KeyWindowEvent(TabKey)),
+            glfw::KeyEnter => self.event_queue.borrow_mut().push(KeyWindowEvent(EnterKey)),
             glfw::KeyPageDown => {
                 let (_, height) = self.glfw_window.get_size();
                 self.scroll_window(0.0, -height as f32);
@@ -192,6 +192,11 @@ impl WindowMethods<Application> for Window {
         //self.update_window_title()
     }
 
+    /// Sets the page title.
+    fn set_page_title(&self, _title: Option<String>) {
+        //FIXME: set_window_title causes crash with Android version of freeGLUT. Temporarily blocked.
+    }
+
     fn hidpi_factor(&self) -> ScaleFactor<ScreenPx, DevicePixel, f32> {
         //FIXME: Do nothing in GLUT now.
         ScaleFactor(1.0)
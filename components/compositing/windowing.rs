@@ -10,6 +10,7 @@ use geom::size::TypedSize2D;
 use layers::geometry::DevicePixel;
 use servo_msg::compositor_msg::{ReadyState, RenderState};
 use servo_util::geometry::ScreenPx;
+use script_traits::Key;
 use std::rc::Rc;
 
 pub enum MouseWindowEvent {
@@ -40,6 +41,8 @@ pub enum WindowEvent {
     MouseWindowEventClass(MouseWindowEvent),
     /// Sent when a mouse move.
     MouseWindowMoveEventClass(TypedPoint2D<DevicePixel, f32>),
+    /// Sent when a key that the page may handle is pressed.
+    KeyWindowEvent(Key),
     /// Sent when the user scrolls. Includes the current cursor position.
     ScrollWindowEvent(TypedPoint2D<DevicePixel, f32>, TypedPoint2D<DevicePixel, i32>),
     /// Sent when the user zooms.
@@ -76,6 +79,8 @@ pub trait WindowMethods<A> {
     fn set_ready_state(&self, ready_state: ReadyState);
     /// Sets the render state of the current page.
     fn set_render_state(&self, render_state: RenderState);
+    /// Sets the title of the current page.
+    fn set_page_title(&self, title: Option<String>);
 
     /// Returns the hidpi factor of the monitor.
     fn hidpi_factor(&self) -> ScaleFactor<ScreenPx, DevicePixel, f32>;
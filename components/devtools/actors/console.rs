@@ -10,12 +10,14 @@ use actor::{Actor, ActorRegistry};
 use protocol::JsonPacketSender;
 
 use devtools_traits::{EvaluateJS, NullValue, VoidValue, NumberValue, StringValue, BooleanValue};
-use devtools_traits::{ActorValue, DevtoolScriptControlMsg};
+use devtools_traits::{ActorValue, DevtoolScriptControlMsg, ConsoleMessage};
+use devtools_traits::{LogLevel, DebugLevel, InfoLevel, WarnLevel, ErrorLevel};
 use servo_msg::constellation_msg::PipelineId;
 
 use collections::TreeMap;
 use serialize::json;
 use serialize::json::ToJson;
+use std::cell::RefCell;
 use std::io::TcpStream;
 
 #[deriving(Encodable)]
@@ -79,6 +81,23 @@ struct StopListenersReply {
     stoppedListeners: Vec<String>,
 }
 
+#[deriving(Encodable)]
+struct ConsoleAPICallMessage {
+    level: String,
+    arguments: Vec<String>,
+    filename: String,
+    lineNumber: uint,
+    functionName: String,
+    timeStamp: uint,
+}
+
+#[deriving(Encodable)]
+struct ConsoleAPICall {
+    from: String,
+    __type__: String,
+    message: ConsoleAPICallMessage,
+}
+
 #[deriving(Encodable)]
 struct AutocompleteReply {
     from: String,
@@ -97,10 +116,54 @@ struct EvaluateJSReply {
     helperResult: json::Json,
 }
 
+/// The names of the listeners that a startListeners or stopListeners request is about.
+fn listeners(msg: &json::JsonObject) -> Vec<String> {
+    msg.find(&"listeners".to_string())
+       .unwrap()
+       .as_list()
+       .unwrap_or(&vec!())
+       .iter()
+       .map(|listener| listener.as_string().unwrap().to_string())
+       .collect()
+}
+
 pub struct ConsoleActor {
     pub name: String,
     pub pipeline: PipelineId,
     pub script_chan: Sender<DevtoolScriptControlMsg>,
+    /// The clients that started the ConsoleAPI listener, which are sent the console messages
+    /// of the page as they are logged.
+    pub streams: RefCell<Vec<TcpStream>>,
+}
+
+impl ConsoleActor {
+    /// Sends a message that the page logged to every client that listens for them. Clients
+    /// that went away are forgotten.
+    pub fn handle_console_message(&self, message: ConsoleMessage) {
+        let level = match message.level {
+            LogLevel => "log",
+            DebugLevel => "debug",
+            InfoLevel => "info",
+            WarnLevel => "warn",
+            ErrorLevel => "error",
+        };
+        let msg = ConsoleAPICall {
+            from: self.name(),
+            __type__: "consoleAPICall".to_string(),
+            message: ConsoleAPICallMessage {
+                level: level.to_string(),
+                arguments: vec!(message.message),
+                //TODO: make script send along where the message was logged from
+                filename: "".to_string(),
+                lineNumber: 0,
+                functionName: "".to_string(),
+                timeStamp: 0,
+            },
+        };
+        self.streams.borrow_mut().retain(|stream| {
+            stream.clone().try_write_json_packet(&msg).is_ok()
+        });
+    }
 }
 
 impl Actor for ConsoleActor {
@@ -169,7 +232,10 @@ impl Actor for ConsoleActor {
             }
 
             "startListeners" => {
-                //TODO: actually implement listener filters that support starting/stopping
+                //TODO: actually implement listener filters for the other listeners
+                if listeners(msg).iter().any(|listener| listener.as_slice() == "ConsoleAPI") {
+                    self.streams.borrow_mut().push(stream.clone());
+                }
                 let msg = StartedListenersReply {
                     from: self.name(),
                     nativeConsoleAPI: true,
@@ -184,16 +250,15 @@ impl Actor for ConsoleActor {
             }
 
             "stopListeners" => {
-                //TODO: actually implement listener filters that support starting/stopping
+                //TODO: actually implement listener filters for the other listeners
+                let stopped = listeners(msg);
+                if stopped.iter().any(|listener| listener.as_slice() == "ConsoleAPI") {
+                    //FIXME: only forget the streams of the client that asked
+                    self.streams.borrow_mut().clear();
+                }
                 let msg = StopListenersReply {
                     from: self.name(),
-                    stoppedListeners: msg.find(&"listeners".to_string())
-                                         .unwrap()
-                                         .as_list()
-                                         .unwrap_or(&vec!())
-                                         .iter()
-                                         .map(|listener| listener.as_string().unwrap().to_string())
-                                         .collect(),
+                    stoppedListeners: stopped,
                 };
                 stream.write_json_packet(&msg);
                 true
@@ -5,7 +5,7 @@
 /// Liberally derived from the [Firefox JS implementation](http://mxr.mozilla.org/mozilla-central/source/toolkit/devtools/server/actors/inspector.js).
 
 use devtools_traits::{GetRootNode, GetDocumentElement, GetChildren, DevtoolScriptControlMsg};
-use devtools_traits::{GetLayout, NodeInfo};
+use devtools_traits::{GetLayout, GetOuterHTML, NodeInfo};
 
 use actor::{Actor, ActorRegistry};
 use protocol::JsonPacketSender;
@@ -196,6 +196,12 @@ struct DocumentElementReply {
     node: NodeActorMsg,
 }
 
+#[deriving(Encodable)]
+struct OuterHTMLReply {
+    from: String,
+    value: String,
+}
+
 #[deriving(Encodable)]
 struct ClearPseudoclassesReply {
     from: String,
@@ -271,6 +277,22 @@ impl Actor for WalkerActor {
                 true
             }
 
+            //TODO: reply with a long string actor for big subtrees, like Firefox does
+            "outerHTML" => {
+                let target = msg.find(&"node".to_string()).unwrap().as_string().unwrap();
+                let (tx, rx) = channel();
+                self.script_chan.send(GetOuterHTML(self.pipeline,
+                                                   registry.actor_to_script(target.to_string()),
+                                                   tx));
+
+                let msg = OuterHTMLReply {
+                    from: self.name(),
+                    value: rx.recv(),
+                };
+                stream.write_json_packet(&msg);
+                true
+            }
+
             _ => false,
         }
     }
@@ -36,12 +36,14 @@ use actors::tab::TabActor;
 use protocol::JsonPacketSender;
 
 use devtools_traits::{ServerExitMsg, DevtoolsControlMsg, NewGlobal, DevtoolScriptControlMsg};
+use devtools_traits::{SendConsoleMessage, ConsoleMessage};
 use servo_msg::constellation_msg::PipelineId;
 
 use std::cell::RefCell;
+use std::collections::hashmap::HashMap;
 use std::comm;
 use std::comm::{Disconnected, Empty};
-use std::io::{TcpListener, TcpStream};
+use std::io::{TcpAcceptor, TcpListener, TcpStream};
 use std::io::{Acceptor, Listener, EndOfFile, TimedOut};
 use std::num;
 use std::task::TaskBuilder;
@@ -58,23 +60,25 @@ mod actors {
 }
 mod protocol;
 
-/// Spin up a devtools server that listens for connections. Defaults to port 6000.
-/// TODO: allow specifying a port
-pub fn start_server() -> Sender<DevtoolsControlMsg> {
-    let (chan, port) = comm::channel();
+/// Spin up a devtools server that listens for connections on the given port, or on one that the
+/// system picks if it is 0. Returns the channel to the server and the port it listens on.
+pub fn start_server(port: u16) -> (Sender<DevtoolsControlMsg>, u16) {
+    // bind the listener to the specified address
+    let listener = TcpListener::bind("127.0.0.1", port);
+    let mut acceptor = listener.listen().unwrap();
+    let port = acceptor.socket_name().unwrap().port;
+    println!("devtools server listening on port {}", port);
+
+    let (chan, receiver) = comm::channel();
     TaskBuilder::new().named("devtools").spawn(proc() {
-        run_server(port)
+        run_server(receiver, acceptor)
     });
-    chan
+    (chan, port)
 }
 
 static POLL_TIMEOUT: u64 = 300;
 
-fn run_server(port: Receiver<DevtoolsControlMsg>) {
-    let listener = TcpListener::bind("127.0.0.1", 6000);
-
-    // bind the listener to the specified address
-    let mut acceptor = listener.listen().unwrap();
+fn run_server(receiver: Receiver<DevtoolsControlMsg>, mut acceptor: TcpAcceptor) {
     acceptor.set_timeout(Some(POLL_TIMEOUT));
 
     let mut registry = ActorRegistry::new();
@@ -134,9 +138,10 @@ fn run_server(port: Receiver<DevtoolsControlMsg>) {
     // We need separate actor representations for each script global that exists;
     // clients can theoretically connect to multiple globals simultaneously.
     // TODO: move this into the root or tab modules?
+    // Returns the name of the console actor of the new global.
     fn handle_new_global(actors: Arc<Mutex<ActorRegistry>>,
                          pipeline: PipelineId,
-                         sender: Sender<DevtoolScriptControlMsg>) {
+                         sender: Sender<DevtoolScriptControlMsg>) -> String {
         let mut actors = actors.lock();
 
         //TODO: move all this actor creation into a constructor method on TabActor
@@ -145,6 +150,7 @@ fn run_server(port: Receiver<DevtoolsControlMsg>) {
                 name: actors.new_name("console"),
                 script_chan: sender.clone(),
                 pipeline: pipeline,
+                streams: RefCell::new(vec!()),
             };
             let inspector = InspectorActor {
                 name: actors.new_name("inspector"),
@@ -168,11 +174,23 @@ fn run_server(port: Receiver<DevtoolsControlMsg>) {
             (tab, console, inspector)
         };
 
+        let console_name = console.name();
         actors.register(box tab);
         actors.register(box console);
         actors.register(box inspector);
+        console_name
+    }
+
+    fn handle_console_message(actors: Arc<Mutex<ActorRegistry>>,
+                              console_name: &String,
+                              message: ConsoleMessage) {
+        let actors = actors.lock();
+        actors.find::<ConsoleActor>(console_name.as_slice()).handle_console_message(message);
     }
 
+    // The console actor of each global, by the pipeline it belongs to.
+    let mut consoles: HashMap<PipelineId, String> = HashMap::new();
+
     //TODO: figure out some system that allows us to watch for new connections,
     //      shut down existing ones at arbitrary times, and also watch for messages
     //      from multiple script tasks simultaneously. Polling for new connections
@@ -185,9 +203,19 @@ fn run_server(port: Receiver<DevtoolsControlMsg>) {
     loop {
         match acceptor.accept() {
             Err(ref e) if e.kind == TimedOut => {
-                match port.try_recv() {
+                match receiver.try_recv() {
                     Ok(ServerExitMsg) | Err(Disconnected) => break,
-                    Ok(NewGlobal(id, sender)) => handle_new_global(actors.clone(), id, sender),
+                    Ok(NewGlobal(id, sender)) => {
+                        let console_name = handle_new_global(actors.clone(), id, sender);
+                        consoles.insert(id, console_name);
+                    }
+                    Ok(SendConsoleMessage(id, message)) => {
+                        match consoles.find(&id) {
+                            Some(console_name) =>
+                                handle_console_message(actors.clone(), console_name, message),
+                            None => println!("console message for unknown pipeline {:?}", id),
+                        }
+                    }
                     Err(Empty) => acceptor.set_timeout(Some(POLL_TIMEOUT)),
                 }
             }
@@ -202,3 +230,151 @@ fn run_server(port: Receiver<DevtoolsControlMsg>) {
         }
     }
 }
+
+#[cfg(test)]
+fn write_packet(stream: &mut TcpStream, packet: &str) {
+    stream.write_str(format!("{:u}:{:s}", packet.len(), packet).as_slice()).unwrap();
+}
+
+#[cfg(test)]
+fn read_packet(stream: &mut TcpStream) -> json::JsonObject {
+    let mut buffer = vec!();
+    loop {
+        match stream.read_byte().unwrap() {
+            c if c == ':' as u8 => break,
+            c => buffer.push(c),
+        }
+    }
+    let packet_len = num::from_str_radix(String::from_utf8(buffer).unwrap().as_slice(), 10).unwrap();
+    let packet = String::from_utf8(stream.read_exact(packet_len).unwrap()).unwrap();
+    json::from_str(packet.as_slice()).unwrap().as_object().unwrap().clone()
+}
+
+#[cfg(test)]
+fn request(stream: &mut TcpStream, packet: &str) -> json::JsonObject {
+    write_packet(stream, packet);
+    read_packet(stream)
+}
+
+#[cfg(test)]
+fn string_field(obj: &json::JsonObject, key: &str) -> String {
+    obj.find(&key.to_string()).unwrap().as_string().unwrap().to_string()
+}
+
+#[cfg(test)]
+fn node_info(unique_id: &str, parent: &str, node_name: &str,
+             num_children: uint) -> devtools_traits::NodeInfo {
+    devtools_traits::NodeInfo {
+        uniqueId: unique_id.to_string(),
+        baseURI: "about:blank".to_string(),
+        parent: parent.to_string(),
+        nodeType: 1,
+        namespaceURI: "".to_string(),
+        nodeName: node_name.to_string(),
+        numChildren: num_children,
+        name: "".to_string(),
+        publicId: "".to_string(),
+        systemId: "".to_string(),
+        attrs: vec!(),
+        isDocumentElement: parent == "document",
+        shortValue: "".to_string(),
+        incompleteValue: false,
+    }
+}
+
+/// Answers the requests of the server the way the script task would for a document with an empty
+/// head and a body that holds a single paragraph.
+#[cfg(test)]
+fn spawn_fake_script_task() -> Sender<DevtoolScriptControlMsg> {
+    use devtools_traits::{EvaluateJS, GetRootNode, GetDocumentElement, GetChildren};
+    use devtools_traits::{GetLayout, GetOuterHTML, NumberValue};
+
+    let (chan, port) = comm::channel();
+    spawn(proc() {
+        for msg in port.iter() {
+            match msg {
+                GetRootNode(_, reply) => reply.send(node_info("document", "", "#document", 1)),
+                GetDocumentElement(_, reply) => reply.send(node_info("html", "document", "HTML", 2)),
+                GetChildren(_, node, reply) => {
+                    assert_eq!(node.as_slice(), "html");
+                    reply.send(vec!(node_info("head", "html", "HEAD", 0),
+                                    node_info("body", "html", "BODY", 1)));
+                }
+                GetOuterHTML(_, node, reply) => {
+                    assert_eq!(node.as_slice(), "body");
+                    reply.send("<body><p>Hello</p></body>".to_string());
+                }
+                EvaluateJS(_, script, reply) => {
+                    assert_eq!(script.as_slice(), "1+1");
+                    reply.send(NumberValue(2.0));
+                }
+                GetLayout(..) => fail!("unexpected message"),
+            }
+        }
+    });
+    chan
+}
+
+#[test]
+fn test_inspect_log_and_evaluate() {
+    use devtools_traits::LogLevel;
+    use std::io::timer::sleep;
+    use std::time::Duration;
+
+    let (server, port) = start_server(0);
+    server.send(NewGlobal(PipelineId(0), spawn_fake_script_task()));
+
+    let mut stream = TcpStream::connect("127.0.0.1", port).unwrap();
+    assert_eq!(string_field(&read_packet(&mut stream), "from").as_slice(), "root");
+
+    // The server only learns about new globals while it waits for connections.
+    let mut tabs = vec!();
+    while tabs.is_empty() {
+        let reply = request(&mut stream, r#"{"to":"root","type":"listTabs"}"#);
+        tabs = reply.find(&"tabs".to_string()).unwrap().as_list().unwrap().clone();
+        sleep(Duration::milliseconds(100));
+    }
+    let tab = tabs[0].as_object().unwrap().clone();
+    let console = string_field(&tab, "consoleActor");
+    let inspector = string_field(&tab, "inspectorActor");
+
+    // The markup of the body.
+    let reply = request(&mut stream, format!(r#"{{"to":"{:s}","type":"getWalker"}}"#,
+                                             inspector).as_slice());
+    let walker = string_field(reply.find(&"walker".to_string()).unwrap().as_object().unwrap(),
+                              "actor");
+    let reply = request(&mut stream, format!(r#"{{"to":"{:s}","type":"documentElement"}}"#,
+                                             walker).as_slice());
+    let html = string_field(reply.find(&"node".to_string()).unwrap().as_object().unwrap(),
+                            "actor");
+    let reply = request(&mut stream, format!(r#"{{"to":"{:s}","type":"children","node":"{:s}"}}"#,
+                                             walker, html).as_slice());
+    let children = reply.find(&"nodes".to_string()).unwrap().as_list().unwrap().clone();
+    assert_eq!(children.len(), 2);
+    let body = children[1].as_object().unwrap().clone();
+    assert_eq!(string_field(&body, "nodeName").as_slice(), "BODY");
+    let reply = request(&mut stream, format!(r#"{{"to":"{:s}","type":"outerHTML","node":"{:s}"}}"#,
+                                             walker, string_field(&body, "actor")).as_slice());
+    assert_eq!(string_field(&reply, "value").as_slice(), "<body><p>Hello</p></body>");
+
+    // A console message that is logged once the client listens for them.
+    request(&mut stream, format!(r#"{{"to":"{:s}","type":"startListeners","listeners":["ConsoleAPI"]}}"#,
+                                 console).as_slice());
+    server.send(SendConsoleMessage(PipelineId(0), ConsoleMessage {
+        level: LogLevel,
+        message: "hello".to_string(),
+    }));
+    let packet = read_packet(&mut stream);
+    assert_eq!(string_field(&packet, "type").as_slice(), "consoleAPICall");
+    let message = packet.find(&"message".to_string()).unwrap().as_object().unwrap().clone();
+    assert_eq!(string_field(&message, "level").as_slice(), "log");
+    let arguments = message.find(&"arguments".to_string()).unwrap().as_list().unwrap().clone();
+    assert_eq!(arguments[0].as_string().unwrap(), "hello");
+
+    // The result of evaluating a script.
+    let reply = request(&mut stream, format!(r#"{{"to":"{:s}","type":"evaluateJS","text":"1+1"}}"#,
+                                             console).as_slice());
+    assert_eq!(reply.find(&"result".to_string()).unwrap().as_number(), Some(2.0));
+
+    server.send(ServerExitMsg);
+}
@@ -5,18 +5,26 @@
 /// Low-level wire protocol implementation. Currently only supports [JSON packets](https://wiki.mozilla.org/Remote_Debugging_Protocol_Stream_Transport#JSON_Packets).
 
 use serialize::{json, Encodable};
-use std::io::{IoError, TcpStream};
+use std::io::{IoError, IoResult, TcpStream};
 
 pub trait JsonPacketSender {
     fn write_json_packet<'a, T: Encodable<json::Encoder<'a>,IoError>>(&mut self, obj: &T);
+    /// Like `write_json_packet`, but hands back the error if the client went away.
+    fn try_write_json_packet<'a, T: Encodable<json::Encoder<'a>,IoError>>(&mut self, obj: &T)
+                                                                           -> IoResult<()>;
 }
 
 impl JsonPacketSender for TcpStream {
     fn write_json_packet<'a, T: Encodable<json::Encoder<'a>,IoError>>(&mut self, obj: &T) {
+        self.try_write_json_packet(obj).unwrap();
+    }
+
+    fn try_write_json_packet<'a, T: Encodable<json::Encoder<'a>,IoError>>(&mut self, obj: &T)
+                                                                           -> IoResult<()> {
         let s = json::encode(obj).replace("__type__", "type");
         println!("<- {:s}", s);
-        self.write_str(s.len().to_string().as_slice()).unwrap();
-        self.write_u8(':' as u8).unwrap();
-        self.write_str(s.as_slice()).unwrap();
+        try!(self.write_str(s.len().to_string().as_slice()));
+        try!(self.write_u8(':' as u8));
+        self.write_str(s.as_slice())
     }
 }
@@ -25,9 +25,28 @@ pub type DevtoolsControlPort = Receiver<DevtoolScriptControlMsg>;
 /// according to changes in the browser.
 pub enum DevtoolsControlMsg {
     NewGlobal(PipelineId, Sender<DevtoolScriptControlMsg>),
+    /// Forwards a message that was logged to the console of a global.
+    SendConsoleMessage(PipelineId, ConsoleMessage),
     ServerExitMsg
 }
 
+/// How serious a console message is, after the console method that logged it.
+#[deriving(Clone, PartialEq, Show)]
+pub enum ConsoleLevel {
+    LogLevel,
+    DebugLevel,
+    InfoLevel,
+    WarnLevel,
+    ErrorLevel,
+}
+
+/// A message that script logged to the console.
+#[deriving(Clone)]
+pub struct ConsoleMessage {
+    pub level: ConsoleLevel,
+    pub message: String,
+}
+
 /// Serialized JS return values
 /// TODO: generalize this beyond the EvaluateJS message?
 pub enum EvaluateJSReply {
@@ -72,6 +91,8 @@ pub enum DevtoolScriptControlMsg {
     GetRootNode(PipelineId, Sender<NodeInfo>),
     GetDocumentElement(PipelineId, Sender<NodeInfo>),
     GetChildren(PipelineId, String, Sender<Vec<NodeInfo>>),
+    /// Asks for the markup of the node with the given unique id and its descendants.
+    GetOuterHTML(PipelineId, String, Sender<String>),
     GetLayout(PipelineId, String, Sender<(f32, f32)>),
 }
 
@@ -1328,8 +1328,9 @@ impl BlockFlow {
         let fixed_static_i_offset = self.base.fixed_static_i_offset + inline_start_content_edge;
         let flags = self.base.flags.clone();
 
-        // This value is used only for table cells.
+        // These values are used only for table cells.
         let mut inline_start_margin_edge = inline_start_content_edge;
+        let column_spacing = self.fragment.style().get_inheritedtable().border_spacing.horizontal;
 
         // Remember the inline-sizes of the last left and right floats, if there were any. These
         // are used for estimating the inline-sizes of block formatting contexts. (We estimate that
@@ -1424,6 +1425,7 @@ impl BlockFlow {
                                                      i,
                                                      content_inline_size,
                                                      col_inline_sizes.as_slice(),
+                                                     column_spacing,
                                                      &mut inline_start_margin_edge)
                 }
                 None => {}
@@ -2485,6 +2487,7 @@ fn propagate_column_inline_sizes_to_child(kid: &mut Flow,
                                     child_index: uint,
                                     content_inline_size: Au,
                                     column_inline_sizes: &[Au],
+                                    column_spacing: Au,
                                     inline_start_margin_edge: &mut Au) {
     // If kid is table_rowgroup or table_row, the column inline-sizes info should be copied from its
     // parent.
@@ -2497,11 +2500,12 @@ fn propagate_column_inline_sizes_to_child(kid: &mut Flow,
         content_inline_size
     } else if kid.is_table_cell() {
         // If kid is table_cell, the x offset and inline-size for each cell should be
-        // calculated from parent's column inline-sizes info.
+        // calculated from parent's column inline-sizes info, leaving the border spacing
+        // before every column.
         *inline_start_margin_edge = if child_index == 0 {
-            Au(0)
+            column_spacing
         } else {
-            *inline_start_margin_edge + column_inline_sizes[child_index - 1]
+            *inline_start_margin_edge + column_inline_sizes[child_index - 1] + column_spacing
         };
 
         column_inline_sizes[child_index]
@@ -83,6 +83,9 @@ pub struct SharedLayoutContext {
     /// Starts at zero, and increased by one every time a layout completes.
     /// This can be used to easily check for invalid stale data.
     pub generation: uint,
+
+    /// Whether every node has to be restyled, rather than only those that script dirtied.
+    pub restyle_all: bool,
 }
 
 pub struct LayoutContext<'a> {
@@ -215,7 +215,7 @@ impl StyleSharingCandidate {
         };
 
         let element = node.as_element();
-        if element.style_attribute().is_some() {
+        if element.style_attribute().is_some() || style::has_presentational_hints(node) {
             return None
         }
 
@@ -320,6 +320,15 @@ pub trait MatchMethods {
                            parent: Option<LayoutNode>,
                            applicable_declarations: &ApplicableDeclarations,
                            applicable_declarations_cache: &mut ApplicableDeclarationsCache);
+
+    /// Returns whether the style of this node can have changed since the last reflow: because
+    /// script dirtied it or one of its ancestors, because it hasn't been styled yet, or because
+    /// the style of every node has to be recomputed.
+    fn needs_style_recalc(&self, layout_context: &LayoutContext) -> bool;
+
+    /// Clears the dirty bits of this node once it has been dealt with. The children of a node
+    /// that was restyled inherit its new style, so they are dirtied in turn.
+    fn clear_dirty_bits(&self, restyled: bool);
 }
 
 trait PrivateMatchMethods {
@@ -454,7 +463,8 @@ impl<'ln> MatchMethods for LayoutNode<'ln> {
         }
         let ok = {
             let element = self.as_element();
-            element.style_attribute().is_none() && element.get_attr(&Null, "id").is_none()
+            element.style_attribute().is_none() && element.get_attr(&Null, "id").is_none() &&
+                !style::has_presentational_hints(self)
         };
         if !ok {
             return CannotShare(false)
@@ -536,33 +546,38 @@ impl<'ln> MatchMethods for LayoutNode<'ln> {
                                 parent: Option<LayoutNode>) {
         self.initialize_layout_data(layout_context.shared.layout_chan.clone());
 
-        // First, check to see whether we can share a style with someone.
-        let sharing_result = unsafe {
-            self.share_style_if_possible(layout_context.style_sharing_candidate_cache(), parent.clone())
-        };
+        let restyle = self.needs_style_recalc(layout_context);
+        if restyle {
+            // First, check to see whether we can share a style with someone.
+            let sharing_result = unsafe {
+                self.share_style_if_possible(layout_context.style_sharing_candidate_cache(),
+                                             parent.clone())
+            };
 
-        // Otherwise, match and cascade selectors.
-        match sharing_result {
-            CannotShare(mut shareable) => {
-                if self.is_element() {
-                    self.match_node(stylist, &*parent_bf, applicable_declarations, &mut shareable);
-                }
+            // Otherwise, match and cascade selectors.
+            match sharing_result {
+                CannotShare(mut shareable) => {
+                    if self.is_element() {
+                        self.match_node(stylist, &*parent_bf, applicable_declarations, &mut shareable);
+                    }
 
-                unsafe {
-                    self.cascade_node(parent,
-                                      applicable_declarations,
-                                      layout_context.applicable_declarations_cache())
-                }
+                    unsafe {
+                        self.cascade_node(parent,
+                                          applicable_declarations,
+                                          layout_context.applicable_declarations_cache())
+                    }
 
-                applicable_declarations.clear();
+                    applicable_declarations.clear();
 
-                // Add ourselves to the LRU cache.
-                if shareable {
-                    layout_context.style_sharing_candidate_cache().insert_if_possible(self)
+                    // Add ourselves to the LRU cache.
+                    if shareable {
+                        layout_context.style_sharing_candidate_cache().insert_if_possible(self)
+                    }
                 }
+                StyleWasShared(index) => layout_context.style_sharing_candidate_cache().touch(index),
             }
-            StyleWasShared(index) => layout_context.style_sharing_candidate_cache().touch(index),
         }
+        self.clear_dirty_bits(restyle);
 
         match *parent_bf {
             None => {},
@@ -588,6 +603,27 @@ impl<'ln> MatchMethods for LayoutNode<'ln> {
         flow_constructor.process(&layout_node);
     }
 
+    fn needs_style_recalc(&self, layout_context: &LayoutContext) -> bool {
+        if layout_context.shared.restyle_all || self.is_dirty() {
+            return true
+        }
+        let layout_data_ref = self.borrow_layout_data();
+        match *layout_data_ref {
+            None => fail!("no layout data"),
+            Some(ref layout_data) => layout_data.shared_data.style.is_none(),
+        }
+    }
+
+    fn clear_dirty_bits(&self, restyled: bool) {
+        if restyled {
+            for kid in self.children() {
+                kid.set_dirty(true);
+            }
+        }
+        self.set_dirty(false);
+        self.set_dirty_descendants(false);
+    }
+
     unsafe fn cascade_node(&self,
                            parent: Option<LayoutNode>,
                            applicable_declarations: &ApplicableDeclarations,
@@ -70,7 +70,8 @@ pub fn compute_damage(old: &ComputedValues, new: &ComputedValues) -> RestyleDama
           get_padding.padding_bottom, get_padding.padding_left,
           get_box.position, get_box.width, get_box.height, get_box.float, get_box.display,
           get_font.font_family, get_font.font_size, get_font.font_style, get_font.font_weight,
-          get_inheritedtext.text_align, get_text.text_decoration, get_inheritedbox.line_height ]);
+          get_inheritedtext.text_align, get_text.text_decoration, get_inheritedbox.line_height,
+          get_inheritedtable.border_spacing ]);
 
     // FIXME: test somehow that we checked every CSS property
 
@@ -21,8 +21,6 @@ use util::{LayoutDataAccess, LayoutDataWrapper, OpaqueNodeMethods, ToGfxColor};
 use wrapper::{LayoutNode, TLayoutNode, ThreadSafeLayoutNode};
 
 use collections::dlist::DList;
-use encoding::EncodingRef;
-use encoding::all::UTF_8;
 use geom::point::Point2D;
 use geom::rect::Rect;
 use geom::size::Size2D;
@@ -35,7 +33,7 @@ use layout_traits::{LayoutControlMsg, LayoutTaskFactory};
 use script::dom::bindings::js::JS;
 use script::dom::node::{ElementNodeTypeId, LayoutDataRef, Node};
 use script::dom::element::{HTMLBodyElementTypeId, HTMLHtmlElementTypeId};
-use script::layout_interface::{AddStylesheetMsg, LoadStylesheetMsg, ScriptLayoutChan};
+use script::layout_interface::{AddStylesheetMsg, ScriptLayoutChan};
 use script::layout_interface::{TrustedNodeAddress, ContentBoxesResponse, ExitNowMsg};
 use script::layout_interface::{ContentBoxResponse, HitTestResponse, MouseOverResponse};
 use script::layout_interface::{ContentChangedDocumentDamage, LayoutChan, Msg, PrepareToExitMsg};
@@ -48,10 +46,11 @@ use servo_net::image_cache_task::{ImageCacheTask, ImageResponseMsg};
 use gfx::font_cache_task::{FontCacheTask};
 use servo_net::local_image_cache::{ImageResponder, LocalImageCache};
 use servo_util::bloom::BloomFilter;
-use servo_net::resource_task::{ResourceTask, load_bytes_iter};
+use servo_net::resource_task::ResourceTask;
 use servo_util::geometry::Au;
 use servo_util::geometry;
 use servo_util::logical_geometry::LogicalPoint;
+use servo_util::memory::HeapSizeOf;
 use servo_util::opts::Opts;
 use servo_util::smallvec::{SmallVec, SmallVec1};
 use servo_util::task::spawn_named_with_send_on_failure;
@@ -92,6 +91,14 @@ pub struct LayoutTaskData {
     /// Starts at zero, and increased by one every time a layout completes.
     /// This can be used to easily check for invalid stale data.
     pub generation: uint,
+
+    /// Set when a stylesheet was added or the viewport that media queries match against
+    /// changed, which can change the style of any node.
+    pub restyle_all: bool,
+
+    /// The memory taken up by the stylesheets that were added, which are
+    /// never removed.
+    pub stylesheet_heap_size: uint,
 }
 
 /// Information needed by the layout task.
@@ -418,6 +425,8 @@ impl LayoutTask {
                     parallel_traversal: parallel_traversal,
                     dirty: Rect::zero(),
                     generation: 0,
+                    restyle_all: true,
+                    stylesheet_heap_size: 0,
               })),
         }
     }
@@ -449,6 +458,7 @@ impl LayoutTask {
             opts: self.opts.clone(),
             dirty: Rect::zero(),
             generation: rw_data.generation,
+            restyle_all: rw_data.restyle_all,
         }
     }
 
@@ -515,7 +525,6 @@ impl LayoutTask {
     fn handle_script_request<'a>(&'a self, request: Msg, possibly_locked_rw_data: &mut Option<MutexGuard<'a, LayoutTaskData>>) -> bool {
         match request {
             AddStylesheetMsg(sheet) => self.handle_add_stylesheet(sheet, possibly_locked_rw_data),
-            LoadStylesheetMsg(url) => self.handle_load_stylesheet(url, possibly_locked_rw_data),
             GetRPCMsg(response_chan) => {
                 response_chan.send(
                     box LayoutRPCImpl(
@@ -590,18 +599,6 @@ impl LayoutTask {
         response_port.recv()
     }
 
-    fn handle_load_stylesheet<'a>(&'a self, url: Url, possibly_locked_rw_data: &mut Option<MutexGuard<'a, LayoutTaskData>>) {
-        // TODO: Get the actual value. http://dev.w3.org/csswg/css-syntax/#environment-encoding
-        let environment_encoding = UTF_8 as EncodingRef;
-
-        let (metadata, iter) = load_bytes_iter(&self.resource_task, url);
-        let protocol_encoding_label = metadata.charset.as_ref().map(|s| s.as_slice());
-        let final_url = metadata.final_url;
-
-        let sheet = Stylesheet::from_bytes_iter(iter, final_url, protocol_encoding_label, Some(environment_encoding));
-        self.handle_add_stylesheet(sheet, possibly_locked_rw_data);
-    }
-
     fn handle_add_stylesheet<'a>(&'a self, sheet: Stylesheet, possibly_locked_rw_data: &mut Option<MutexGuard<'a, LayoutTaskData>>) {
         // Find all font-face rules and notify the font cache of them.
         // GWTODO: Need to handle unloading web fonts (when we handle unloading stylesheets!)
@@ -609,7 +606,9 @@ impl LayoutTask {
             self.font_cache_task.add_web_font(family.to_string(), url.clone());
         });
         let mut rw_data = self.lock_rw_data(possibly_locked_rw_data);
+        rw_data.stylesheet_heap_size += mem::size_of::<Stylesheet>() + sheet.heap_size_of_children();
         rw_data.stylist.add_stylesheet(sheet, AuthorOrigin);
+        rw_data.restyle_all = true;
         LayoutTask::return_rw_data(possibly_locked_rw_data, rw_data);
     }
 
@@ -753,7 +752,8 @@ impl LayoutTask {
         let current_screen_size = Size2D(Au::from_frac32_px(viewport_size.width.get()),
                                          Au::from_frac32_px(viewport_size.height.get()));
         if rw_data.screen_size != current_screen_size {
-            all_style_damage = true
+            all_style_damage = true;
+            rw_data.restyle_all = true;
         }
         rw_data.screen_size = current_screen_size;
 
@@ -791,6 +791,7 @@ impl LayoutTask {
 
             self.get_layout_root((*node).clone())
         });
+        rw_data.restyle_all = false;
 
         // Verification of the flow tree, which ensures that all nodes were either marked as leaves
         // or as non-leaves. This becomes a no-op in release builds. (It is inconsequential to
@@ -1131,4 +1132,9 @@ impl LayoutRPC for LayoutRPCImpl {
             Ok(MouseOverResponse(mouse_over_list))
         }
     }
+
+    fn stylesheet_heap_size(&self) -> uint {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        rw_data.lock().stylesheet_heap_size
+    }
 }
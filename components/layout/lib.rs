@@ -30,7 +30,6 @@ extern crate "msg" as servo_msg;
 extern crate "util" as servo_util;
 
 extern crate collections;
-extern crate encoding;
 extern crate green;
 extern crate libc;
 extern crate sync;
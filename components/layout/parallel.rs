@@ -335,42 +335,48 @@ fn recalc_style_for_node(mut unsafe_layout_node: UnsafeLayoutNode,
     // Get the style bloom filter.
     let bf = take_task_local_bloom_filter(parent_opt, &layout_context);
 
-    // First, check to see whether we can share a style with someone.
-    let style_sharing_candidate_cache = layout_context.style_sharing_candidate_cache();
-    let sharing_result = unsafe {
-        node.share_style_if_possible(style_sharing_candidate_cache,
-                                     parent_opt.clone())
-    };
-
     // Just needs to be wrapped in an option for `match_node`.
     let some_bf = Some(bf);
 
-    // Otherwise, match and cascade selectors.
-    match sharing_result {
-        CannotShare(mut shareable) => {
-            let mut applicable_declarations = ApplicableDeclarations::new();
+    let restyle = node.needs_style_recalc(&layout_context);
+    if restyle {
+        // First, check to see whether we can share a style with someone.
+        let style_sharing_candidate_cache = layout_context.style_sharing_candidate_cache();
+        let sharing_result = unsafe {
+            node.share_style_if_possible(style_sharing_candidate_cache,
+                                         parent_opt.clone())
+        };
 
-            if node.is_element() {
-                // Perform the CSS selector matching.
-                let stylist = unsafe { &*layout_context.shared.stylist };
-                node.match_node(stylist, &some_bf, &mut applicable_declarations, &mut shareable);
-            }
+        // Otherwise, match and cascade selectors.
+        match sharing_result {
+            CannotShare(mut shareable) => {
+                let mut applicable_declarations = ApplicableDeclarations::new();
 
-            // Perform the CSS cascade.
-            unsafe {
-                node.cascade_node(parent_opt,
-                                  &applicable_declarations,
-                                  layout_context.applicable_declarations_cache());
-            }
+                if node.is_element() {
+                    // Perform the CSS selector matching.
+                    let stylist = unsafe { &*layout_context.shared.stylist };
+                    node.match_node(stylist, &some_bf, &mut applicable_declarations, &mut shareable);
+                }
+
+                // Perform the CSS cascade.
+                unsafe {
+                    node.cascade_node(parent_opt,
+                                      &applicable_declarations,
+                                      layout_context.applicable_declarations_cache());
+                }
 
-            // Add ourselves to the LRU cache.
-            if shareable {
-                style_sharing_candidate_cache.insert_if_possible(&node);
+                // Add ourselves to the LRU cache.
+                if shareable {
+                    style_sharing_candidate_cache.insert_if_possible(&node);
+                }
             }
+            StyleWasShared(index) => style_sharing_candidate_cache.touch(index),
         }
-        StyleWasShared(index) => style_sharing_candidate_cache.touch(index),
     }
 
+    // This has to happen before the kids are enqueued, so that they see whether to restyle.
+    node.clear_dirty_bits(restyle);
+
     // Prepare for flow construction by counting the node's children and storing that count.
     let mut child_count = 0u;
     for _ in node.children() {
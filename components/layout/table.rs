@@ -21,6 +21,7 @@ use servo_util::geometry::Au;
 use servo_util::logical_geometry::LogicalRect;
 use std::cmp::max;
 use std::fmt;
+use style::ComputedValues;
 use style::computed_values::table_layout;
 
 /// A table flow corresponded to the table's internal table fragment under a table wrapper flow.
@@ -130,6 +131,20 @@ impl TableFlow {
     #[inline(always)]
     fn assign_block_size_table_base<'a>(&mut self, layout_context: &'a LayoutContext<'a>) {
         self.block_flow.assign_block_size_block_base(layout_context, MarginsMayNotCollapse);
+
+        // Every row leaves the border spacing before itself, so the one after the last row is
+        // still missing.
+        let has_rows = self.block_flow.base.child_iter().any(|kid| {
+            kid.is_table_rowgroup() || kid.is_table_row()
+        });
+        if has_rows {
+            let row_spacing =
+                self.block_flow.fragment.style().get_inheritedtable().border_spacing.vertical;
+            self.block_flow.fragment.border_box.size.block =
+                self.block_flow.fragment.border_box.size.block + row_spacing;
+            self.block_flow.base.position.size.block =
+                self.block_flow.base.position.size.block + row_spacing;
+        }
     }
 
     pub fn build_display_list_table(&mut self, layout_context: &LayoutContext) {
@@ -239,6 +254,11 @@ impl Flow for TableFlow {
             }
         }
 
+        let spacing = total_column_spacing(self.block_flow.fragment.style(),
+                                           self.col_inline_sizes.len());
+        min_inline_size = min_inline_size + spacing;
+        pref_inline_size = pref_inline_size + spacing;
+
         let fragment_intrinsic_inline_sizes = self.block_flow.fragment.intrinsic_inline_sizes();
         self.block_flow.base.intrinsic_inline_sizes.minimum_inline_size = min_inline_size;
         self.block_flow.base.intrinsic_inline_sizes.preferred_inline_size =
@@ -276,6 +296,11 @@ impl Flow for TableFlow {
 
         match self.table_layout {
             FixedLayout => {
+                // The columns share what the border spacing leaves.
+                let content_inline_size = content_inline_size -
+                    total_column_spacing(self.block_flow.fragment.style(),
+                                         self.col_inline_sizes.len());
+
                 // In fixed table layout, we distribute extra space among the unspecified columns if there are
                 // any, or among all the columns if all are specified.
                 if (total_column_inline_size < content_inline_size) && (num_unspecified_inline_sizes == 0) {
@@ -323,6 +348,15 @@ impl fmt::Show for TableFlow {
     }
 }
 
+/// Returns the inline size taken up by the border spacing of a table with `column_count` columns,
+/// which is found before every column and after the last one.
+pub fn total_column_spacing(style: &ComputedValues, column_count: uint) -> Au {
+    if column_count == 0 {
+        return Au(0)
+    }
+    style.get_inheritedtable().border_spacing.horizontal * (column_count as i32 + 1)
+}
+
 /// Table, TableRowGroup, TableRow, TableCell types.
 /// Their inline-sizes are calculated in the same way and do not have margins.
 pub struct InternalTable;
@@ -15,7 +15,7 @@ use flow;
 use fragment::Fragment;
 use layout_debug;
 use table::InternalTable;
-use model::{MaybeAuto, Specified, Auto};
+use model::{MaybeAuto, Specified, Auto, NoCollapsibleMargins};
 use wrapper::ThreadSafeLayoutNode;
 
 use servo_util::geometry::Au;
@@ -117,6 +117,13 @@ impl TableRowFlow {
         self.block_flow.fragment.border_box = position;
         self.block_flow.base.position.size.block = block_size;
 
+        // Leave the border spacing before the row when it's laid out directly in the table. Rows
+        // don't have margins, so this is the only thing the table sees; row groups add the
+        // spacing themselves.
+        let row_spacing =
+            self.block_flow.fragment.style().get_inheritedtable().border_spacing.vertical;
+        self.block_flow.base.collapsible_margins = NoCollapsibleMargins(row_spacing, Au(0));
+
         // Assign the block-size of kid fragments, which is the same value as own block-size.
         for kid in self.block_flow.base.child_iter() {
             {
@@ -81,10 +81,15 @@ impl TableRowGroupFlow {
 
         let mut cur_y = block_start_offset;
 
+        // Every row leaves the border spacing before itself.
+        let row_spacing =
+            self.block_flow.fragment.style().get_inheritedtable().border_spacing.vertical;
+
         for kid in self.block_flow.base.child_iter() {
             kid.assign_block_size_for_inorder_child_if_necessary(layout_context);
 
             let child_node = flow::mut_base(kid);
+            cur_y = cur_y + row_spacing;
             child_node.position.start.b = cur_y;
             cur_y = cur_y + child_node.position.size.block;
         }
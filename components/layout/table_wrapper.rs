@@ -14,6 +14,7 @@ use floats::FloatKind;
 use flow::{TableWrapperFlowClass, FlowClass, Flow, ImmutableFlowUtils};
 use fragment::Fragment;
 use model::{Specified, Auto, specified};
+use table::total_column_spacing;
 use wrapper::ThreadSafeLayoutNode;
 
 use servo_util::geometry::Au;
@@ -119,7 +120,7 @@ impl TableWrapperFlow {
                                                   .iter()
                                                   .fold(Au(0), |sum, inline_size| {
                         sum.add(inline_size)
-                    });
+                    }) + total_column_spacing(style, self.col_inline_sizes.len());
 
                 let mut computed_inline_size = input.computed_inline_size.specified_or_zero();
 
@@ -41,10 +41,13 @@ use script::dom::bindings::codegen::InheritTypes::{HTMLImageElementDerived, Text
 use script::dom::bindings::js::JS;
 use script::dom::element::{Element, HTMLAreaElementTypeId, HTMLAnchorElementTypeId};
 use script::dom::element::{HTMLLinkElementTypeId, LayoutElementHelpers, RawLayoutElementHelpers};
+use script::dom::element::{InHoverState, InActiveState, InFocusState, InEnabledState};
+use script::dom::element::{InDisabledState, InCheckedState, InIndeterminateState, InTargetState};
 use script::dom::htmliframeelement::HTMLIFrameElement;
 use script::dom::htmlimageelement::{HTMLImageElement, LayoutHTMLImageElementHelpers};
 use script::dom::node::{DocumentNodeTypeId, ElementNodeTypeId, Node, NodeTypeId};
 use script::dom::node::{LayoutNodeHelpers, RawLayoutNodeHelpers, SharedLayoutData, TextNodeTypeId};
+use script::dom::node::{IsDirty, HasDirtyDescendants};
 use script::dom::text::Text;
 use script::layout_interface::LayoutChan;
 use servo_msg::constellation_msg::{PipelineId, SubpageId};
@@ -240,6 +243,25 @@ impl<'ln> LayoutNode<'ln> {
             Some(_) => {}
         }
     }
+
+    /// Returns whether script dirtied this node and its descendants since they were last styled.
+    pub fn is_dirty(&self) -> bool {
+        unsafe {
+            self.node.get_flag(IsDirty)
+        }
+    }
+
+    pub fn set_dirty(&self, value: bool) {
+        unsafe {
+            self.node.set_flag(IsDirty, value)
+        }
+    }
+
+    pub fn set_dirty_descendants(&self, value: bool) {
+        unsafe {
+            self.node.set_flag(HasDirtyDescendants, value)
+        }
+    }
 }
 
 impl<'ln> TNode<'ln, LayoutElement<'ln>> for LayoutNode<'ln> {
@@ -291,14 +313,14 @@ impl<'ln> TNode<'ln, LayoutElement<'ln>> for LayoutNode<'ln> {
     fn match_attr(&self, attr: &AttrSelector, test: |&str| -> bool) -> bool {
         assert!(self.is_element())
         let name = if self.is_html_element_in_html_document() {
-            attr.lower_name.as_slice()
+            &attr.lower_name
         } else {
-            attr.name.as_slice()
+            &attr.name
         };
         match attr.namespace {
             SpecificNamespace(ref ns) => {
                 let element = self.as_element();
-                element.get_attr(ns, name)
+                unsafe { element.element.get_attr_val_for_layout(ns, name) }
                         .map_or(false, |attr| test(attr))
             },
             // FIXME: https://github.com/mozilla/servo/issues/1558
@@ -402,7 +424,8 @@ impl<'le> TElement<'le> for LayoutElement<'le> {
 
     #[inline]
     fn get_attr(&self, namespace: &Namespace, name: &str) -> Option<&'le str> {
-        unsafe { self.element.get_attr_val_for_layout(namespace, name) }
+        let name = Atom::from_slice(name);
+        unsafe { self.element.get_attr_val_for_layout(namespace, &name) }
     }
 
     fn get_link(&self) -> Option<&'le str> {
@@ -413,7 +436,8 @@ impl<'le> TElement<'le> for LayoutElement<'le> {
             ElementNodeTypeId(HTMLAnchorElementTypeId) |
             ElementNodeTypeId(HTMLAreaElementTypeId) |
             ElementNodeTypeId(HTMLLinkElementTypeId) => {
-                unsafe { self.element.get_attr_val_for_layout(&namespace::Null, "href") }
+                let href = Atom::from_slice("href");
+                unsafe { self.element.get_attr_val_for_layout(&namespace::Null, &href) }
             }
             _ => None,
         }
@@ -421,32 +445,76 @@ impl<'le> TElement<'le> for LayoutElement<'le> {
 
     fn get_hover_state(&self) -> bool {
         unsafe {
-            self.element.node.get_hover_state_for_layout()
+            self.element.get_state_for_layout().contains(InHoverState)
+        }
+    }
+
+    fn get_active_state(&self) -> bool {
+        unsafe {
+            self.element.get_state_for_layout().contains(InActiveState)
+        }
+    }
+
+    fn get_focus_state(&self) -> bool {
+        unsafe {
+            self.element.get_state_for_layout().contains(InFocusState)
         }
     }
 
     #[inline]
     fn get_id(&self) -> Option<Atom> {
-        unsafe { self.element.get_attr_atom_for_layout(&namespace::Null, "id") }
+        let id = Atom::from_slice("id");
+        unsafe { self.element.get_attr_atom_for_layout(&namespace::Null, &id) }
     }
 
     fn get_disabled_state(&self) -> bool {
         unsafe {
-            self.element.node.get_disabled_state_for_layout()
+            self.element.get_state_for_layout().contains(InDisabledState)
         }
     }
 
     fn get_enabled_state(&self) -> bool {
         unsafe {
-            self.element.node.get_enabled_state_for_layout()
+            self.element.get_state_for_layout().contains(InEnabledState)
+        }
+    }
+
+    fn get_checked_state(&self) -> bool {
+        unsafe {
+            self.element.get_state_for_layout().contains(InCheckedState)
         }
     }
 
-    fn has_class(&self, name: &str) -> bool {
+    fn get_indeterminate_state(&self) -> bool {
+        unsafe {
+            self.element.get_state_for_layout().contains(InIndeterminateState)
+        }
+    }
+
+    fn get_target_state(&self) -> bool {
+        unsafe {
+            self.element.get_state_for_layout().contains(InTargetState)
+        }
+    }
+
+    fn has_class(&self, name: &Atom) -> bool {
         unsafe {
             self.element.has_class_for_layout(name)
         }
     }
+
+    fn each_class(&self, callback: |&Atom|) {
+        unsafe {
+            match self.element.get_classes_for_layout() {
+                Some(mut classes) => {
+                    for class in classes {
+                        callback(class)
+                    }
+                },
+                None => {},
+            }
+        }
+    }
 }
 
 fn get_content(content_list: &content::T) -> String {
@@ -792,7 +860,8 @@ pub struct ThreadSafeLayoutElement<'le> {
 impl<'le> ThreadSafeLayoutElement<'le> {
     #[inline]
     pub fn get_attr(&self, namespace: &Namespace, name: &str) -> Option<&'le str> {
-        unsafe { self.element.get_attr_val_for_layout(namespace, name) }
+        let name = Atom::from_slice(name);
+        unsafe { self.element.get_attr_val_for_layout(namespace, &name) }
     }
 }
 
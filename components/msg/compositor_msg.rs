@@ -108,11 +108,15 @@ pub trait RenderListener {
 /// which is used in displaying the appropriate message in the window's title.
 pub trait ScriptListener : Clone {
     fn set_ready_state(&self, PipelineId, ReadyState);
+    fn set_title(&self, PipelineId, Option<String>);
     fn scroll_fragment_point(&self,
                              pipeline_id: PipelineId,
                              layer_id: LayerId,
                              point: Point2D<f32>);
     fn close(&self);
+    /// Asks for the user to be prompted before the page leaves, with the
+    /// message the page gave.
+    fn prompt_before_unload(&self, PipelineId, String);
     fn dup(&self) -> Box<ScriptListener+'static>;
 }
 
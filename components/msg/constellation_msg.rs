@@ -10,6 +10,7 @@ use geom::size::TypedSize2D;
 use geom::scale_factor::ScaleFactor;
 use layers::geometry::DevicePixel;
 use servo_util::geometry::{PagePx, ViewportPx};
+use servo_util::memory::MemoryReport;
 use std::comm::{channel, Sender, Receiver};
 use url::Url;
 
@@ -23,7 +24,7 @@ impl ConstellationChan {
     }
 }
 
-#[deriving(PartialEq)]
+#[deriving(Clone, PartialEq)]
 pub enum IFrameSandboxState {
     IFrameSandboxed,
     IFrameUnsandboxed
@@ -48,18 +49,43 @@ pub struct WindowSizeData {
     pub device_pixel_ratio: ScaleFactor<ViewportPx, DevicePixel, f32>,
 }
 
+/// What a pipeline loads: a URL, and the referrer to send when fetching it.
+#[deriving(Clone)]
+pub struct LoadData {
+    pub url: Url,
+    pub referrer: Option<Url>,
+    /// Whether the document is loaded in a sandboxed iframe, which gives it
+    /// an origin of its own.
+    pub sandbox: IFrameSandboxState,
+}
+
+impl LoadData {
+    pub fn new(url: Url) -> LoadData {
+        LoadData {
+            url: url,
+            referrer: None,
+            sandbox: IFrameUnsandboxed,
+        }
+    }
+}
+
 /// Messages from the compositor and script to the constellation.
 pub enum Msg {
     ExitMsg,
     FailureMsg(Failure),
+    /// Reports that a task for the pipeline panicked, and that its script
+    /// task tore the page down but keeps running the others.
+    CrashMsg(Failure),
     InitLoadUrlMsg(Url),
     LoadCompleteMsg(PipelineId, Url),
     FrameRectMsg(PipelineId, SubpageId, Rect<f32>),
-    LoadUrlMsg(PipelineId, Url),
+    LoadUrlMsg(PipelineId, LoadData),
     LoadIframeUrlMsg(Url, PipelineId, SubpageId, IFrameSandboxState),
     NavigateMsg(NavigationDirection),
     RendererReadyMsg(PipelineId),
     ResizedWindowMsg(WindowSizeData),
+    /// Requests a report of the memory used by the pipelines and the image cache.
+    CollectMemoryReportMsg(Sender<MemoryReport>),
 }
 
 /// Represents the two different ways to which a page can be navigated
@@ -0,0 +1,144 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Loads the `blob:` URLs scripts created with `URL.createObjectURL`.
+//! http://dev.w3.org/2006/webapi/FileAPI/#url
+
+use resource_task::{Done, Payload, Metadata, LoadData, LoadResponse, start_sending};
+
+use http::headers::test_utils::from_stream_with_str;
+use http::headers::content_type::MediaType;
+use std::collections::hashmap::HashMap;
+use std::sync::atomics::{AtomicUint, INIT_ATOMIC_UINT, SeqCst};
+
+static mut next_blob_url_owner: AtomicUint = INIT_ATOMIC_UINT;
+
+/// The global that created a `blob:` URL. The URLs of a global are revoked
+/// when it goes away.
+#[deriving(Clone, PartialEq, Eq, Show)]
+pub struct BlobURLOwner(uint);
+
+impl BlobURLOwner {
+    /// Returns an owner that no other global has.
+    pub fn new() -> BlobURLOwner {
+        BlobURLOwner(unsafe { next_blob_url_owner.fetch_add(1, SeqCst) })
+    }
+}
+
+/// What a `blob:` URL refers to.
+#[deriving(Clone)]
+pub struct BlobURLEntry {
+    /// The type of the blob, which may be empty.
+    pub type_: String,
+    pub bytes: Vec<u8>,
+    pub owner: BlobURLOwner,
+}
+
+/// The `blob:` URLs that were created and not revoked yet, by their
+/// serialization without the fragment.
+pub type BlobURLStore = HashMap<String, BlobURLEntry>;
+
+/// Revokes the `blob:` URLs that `owner` created.
+pub fn revoke_owned_by(store: &mut BlobURLStore, owner: BlobURLOwner) {
+    let urls: Vec<String> = store.iter()
+                                 .filter(|&(_, entry)| entry.owner == owner)
+                                 .map(|(url, _)| url.clone())
+                                 .collect();
+    for url in urls.iter() {
+        store.remove(url);
+    }
+}
+
+pub fn load(load_data: LoadData, start_chan: Sender<LoadResponse>, store: &BlobURLStore) {
+    let url = load_data.url;
+    assert!("blob" == url.scheme.as_slice());
+
+    let mut metadata = Metadata::default(url.clone());
+    let entry = match store.find(&url.serialize_no_fragment()) {
+        Some(entry) => entry,
+        None => {
+            start_sending(start_chan, metadata).send(Done(Err("invalid blob URL".to_string())));
+            return;
+        }
+    };
+
+    if !entry.type_.is_empty() {
+        let content_type: Option<MediaType> = from_stream_with_str(entry.type_.as_slice());
+        metadata.set_content_type(&content_type);
+    }
+
+    let progress_chan = start_sending(start_chan, metadata);
+    progress_chan.send(Payload(entry.bytes.clone()));
+    progress_chan.send(Done(Ok(())));
+}
+
+#[cfg(test)]
+fn assert_load(url: &'static str, store: &BlobURLStore,
+               content_type: Option<(String, String)>, data: Option<Vec<u8>>) {
+    use std::comm;
+    use url::Url;
+
+    let (start_chan, start_port) = comm::channel();
+    load(LoadData::new(Url::parse(url).unwrap()), start_chan, store);
+
+    let response = start_port.recv();
+    assert_eq!(&response.metadata.content_type, &content_type);
+
+    let progress = response.progress_port.recv();
+    match data {
+        None => {
+            assert_eq!(progress, Done(Err("invalid blob URL".to_string())));
+        }
+        Some(data) => {
+            assert_eq!(progress, Payload(data));
+            assert_eq!(response.progress_port.recv(), Done(Ok(())));
+        }
+    }
+}
+
+#[cfg(test)]
+fn test_store() -> BlobURLStore {
+    let mut store = HashMap::new();
+    store.insert("blob:http://example.com/1234".to_string(), BlobURLEntry {
+        type_: "text/plain".to_string(),
+        bytes: vec!(0x68, 0x69),
+        owner: BlobURLOwner(1),
+    });
+    store.insert("blob:http://example.com/5678".to_string(), BlobURLEntry {
+        type_: "".to_string(),
+        bytes: vec!(),
+        owner: BlobURLOwner(2),
+    });
+    store
+}
+
+#[test]
+fn registered() {
+    assert_load("blob:http://example.com/1234", &test_store(),
+                Some(("text".to_string(), "plain".to_string())), Some(vec!(0x68, 0x69)));
+}
+
+#[test]
+fn registered_without_type() {
+    assert_load("blob:http://example.com/5678", &test_store(), None, Some(vec!()));
+}
+
+#[test]
+fn fragment_ignored() {
+    assert_load("blob:http://example.com/1234#frag", &test_store(),
+                Some(("text".to_string(), "plain".to_string())), Some(vec!(0x68, 0x69)));
+}
+
+#[test]
+fn unknown() {
+    assert_load("blob:http://example.com/0000", &test_store(), None, None);
+}
+
+#[test]
+fn revoked_with_owner() {
+    let mut store = test_store();
+    revoke_owned_by(&mut store, BlobURLOwner(1));
+    assert_load("blob:http://example.com/1234", &store, None, None);
+    assert_load("blob:http://example.com/5678", &store, None, Some(vec!()));
+}
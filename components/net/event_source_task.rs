@@ -0,0 +1,634 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! A task that keeps a connection to a stream of server-sent events open,
+//! and parses the events out of it.
+//! http://www.whatwg.org/html/#server-sent-events
+
+use resource_task::{ResourceTask, Load, LoadData, Metadata, Payload, Done};
+
+use servo_util::task::spawn_named;
+
+use http::headers::HeaderEnum;
+use http::headers::request::ExtensionHeader;
+use std::ascii::StrAsciiExt;
+use std::comm::{channel, Receiver, Select, Sender};
+use std::io::timer::Timer;
+use std::mem;
+use std::str;
+use std::time::duration::Duration;
+use url::{Url, UrlParser};
+
+/// What a connection tells the script task.
+#[deriving(PartialEq, Show)]
+pub enum EventSourceEvent {
+    /// The connection was established, or established again
+    Opened,
+    /// An event was received
+    EventReceived(ServerSentEvent),
+    /// The connection was lost, and will be established again
+    Reconnecting,
+    /// The connection failed, and won't be established again
+    Failed,
+    /// Nothing else will happen on the connection
+    Finished,
+}
+
+/// An event parsed out of an event stream.
+#[deriving(Clone, PartialEq, Show)]
+pub struct ServerSentEvent {
+    /// The event type, "message" unless the stream named another
+    pub type_: String,
+    pub data: String,
+    pub last_event_id: String,
+}
+
+/// How long to wait before reconnecting, in milliseconds, until the stream
+/// asks for another delay.
+static DEFAULT_RECONNECTION_TIME: u64 = 3000;
+
+/// Connects to `url` in a new task, on behalf of a document at
+/// `document_url`, and returns the channel that closes the connection.
+/// Everything that happens on the connection is sent to `events`, the last
+/// message being `Finished`.
+pub fn connect(resource_task: ResourceTask, url: Url, document_url: Url, with_credentials: bool,
+               events: Sender<EventSourceEvent>) -> Sender<()> {
+    let (close_chan, close_port) = channel();
+    spawn_named("EventSource", proc() {
+        let mut connection = Connection {
+            resource_task: resource_task,
+            url: url,
+            document_url: document_url,
+            with_credentials: with_credentials,
+            close: close_port,
+            events: events,
+            parser: EventStreamParser::new(),
+        };
+        connection.run()
+    });
+    close_chan
+}
+
+/// How a single request of a connection ended.
+enum Outcome {
+    /// The connection must be established again
+    Reestablish,
+    /// The connection must fail
+    FailConnection,
+    /// The connection was closed, or nobody listens to it anymore
+    Stopped,
+}
+
+struct Connection {
+    resource_task: ResourceTask,
+    url: Url,
+    document_url: Url,
+    with_credentials: bool,
+    close: Receiver<()>,
+    events: Sender<EventSourceEvent>,
+    /// The parser of the stream, which outlives each request so that the
+    /// last event ID and the reconnection time carry over to the next one.
+    parser: EventStreamParser,
+}
+
+impl Connection {
+    fn run(&mut self) {
+        let mut timer = Timer::new().unwrap();
+        loop {
+            match self.fetch() {
+                Reestablish => {},
+                FailConnection => {
+                    self.notify(Failed);
+                    break;
+                },
+                Stopped => break,
+            }
+
+            // http://www.whatwg.org/html/#reestablish-the-connection
+            if !self.notify(Reconnecting) {
+                break;
+            }
+            let delay = Duration::milliseconds(self.parser.reconnection_time() as i64);
+            let timeout = timer.oneshot(delay);
+            match self.wait(&timeout) {
+                Some(Ok(())) => {},
+                _ => break,
+            }
+        }
+        self.notify(Finished);
+    }
+
+    /// Makes one request, and reports the events in its response until it
+    /// ends.
+    /// http://www.whatwg.org/html/#processing-model-6
+    fn fetch(&mut self) -> Outcome {
+        let (start_chan, start_port) = channel();
+        self.resource_task.send(Load(self.load_data(), start_chan));
+        let response = match self.wait(&start_port) {
+            Some(Ok(response)) => response,
+            Some(Err(())) => return Reestablish,
+            None => return Stopped,
+        };
+
+        if !self.is_acceptable(&response.metadata) {
+            // Loaders report network errors as a response without metadata
+            // and with an error instead of data.
+            return match self.wait(&response.progress_port) {
+                Some(Ok(Done(Err(_)))) | Some(Err(())) => Reestablish,
+                Some(Ok(_)) => FailConnection,
+                None => Stopped,
+            };
+        }
+
+        // http://www.whatwg.org/html/#announce-the-connection
+        if !self.notify(Opened) {
+            return Stopped;
+        }
+        loop {
+            match self.wait(&response.progress_port) {
+                Some(Ok(Payload(bytes))) => {
+                    for event in self.parser.parse(bytes.as_slice()).into_iter() {
+                        if !self.notify(EventReceived(event)) {
+                            return Stopped;
+                        }
+                    }
+                },
+                Some(Ok(Done(_))) | Some(Err(())) => {
+                    self.parser.end_of_stream();
+                    return Reestablish;
+                },
+                None => return Stopped,
+            }
+        }
+    }
+
+    fn load_data(&self) -> LoadData {
+        let mut load_data = LoadData::new(self.url.clone());
+        load_data.headers.insert(ExtensionHeader("Accept".to_string(),
+                                                 "text/event-stream".to_string()));
+        load_data.headers.insert(ExtensionHeader("Cache-Control".to_string(),
+                                                 "no-cache".to_string()));
+        let last_event_id = self.parser.last_event_id();
+        if !last_event_id.is_empty() {
+            load_data.headers.insert(ExtensionHeader("Last-Event-ID".to_string(),
+                                                     last_event_id.to_string()));
+        }
+        if self.is_cross_origin() {
+            load_data.headers.insert(ExtensionHeader("Origin".to_string(),
+                                                     serialize_origin(&self.document_url)));
+        }
+        load_data
+    }
+
+    /// Whether a response is a stream of events that may be read.
+    fn is_acceptable(&self, metadata: &Metadata) -> bool {
+        let is_event_stream = match metadata.content_type {
+            Some((ref type_, ref subtype)) => {
+                type_.as_slice().eq_ignore_ascii_case("text") &&
+                subtype.as_slice().eq_ignore_ascii_case("event-stream")
+            },
+            None => false,
+        };
+        metadata.status.code() == 200 && is_event_stream && self.is_shared(metadata)
+    }
+
+    /// Whether a response may be read by the document, which for
+    /// cross-origin responses is up to the CORS headers.
+    /// http://fetch.spec.whatwg.org/#cors-check
+    fn is_shared(&self, metadata: &Metadata) -> bool {
+        if !self.is_cross_origin() {
+            return true;
+        }
+        let headers = match metadata.headers {
+            Some(ref headers) => headers,
+            None => return false,
+        };
+        let header = |name: &str| -> Option<String> {
+            headers.iter()
+                .find(|header| header.header_name().as_slice().eq_ignore_ascii_case(name))
+                .map(|header| header.header_value())
+        };
+        match header("Access-Control-Allow-Origin") {
+            Some(ref origin) if origin.as_slice() == "*" => !self.with_credentials,
+            Some(origin) => {
+                let allowed = match UrlParser::new().parse(origin.as_slice()) {
+                    Ok(origin) => same_origin(&origin, &self.document_url),
+                    Err(_) => false,
+                };
+                allowed && (!self.with_credentials ||
+                            header("Access-Control-Allow-Credentials") == Some("true".to_string()))
+            },
+            None => false,
+        }
+    }
+
+    /// Whether requests are cross-origin, and so need the server to allow
+    /// the document to read their responses. The contents of data: URLs
+    /// are shared with everyone.
+    fn is_cross_origin(&self) -> bool {
+        self.url.scheme.as_slice() != "data" && !same_origin(&self.url, &self.document_url)
+    }
+
+    /// Waits for a message on `port`, and returns what was received, or
+    /// None if the connection was closed first.
+    fn wait<T: Send>(&self, port: &Receiver<T>) -> Option<Result<T, ()>> {
+        let select = Select::new();
+        let mut close_handle = select.handle(&self.close);
+        let mut port_handle = select.handle(port);
+        unsafe {
+            close_handle.add();
+            port_handle.add();
+        }
+        let ret = select.wait();
+        if ret == close_handle.id() {
+            None
+        } else if ret == port_handle.id() {
+            Some(port_handle.recv_opt())
+        } else {
+            fail!("unexpected select result")
+        }
+    }
+
+    /// Sends `event` to the script task, and returns whether anybody still
+    /// listens to the connection.
+    fn notify(&self, event: EventSourceEvent) -> bool {
+        self.events.send_opt(event).is_ok()
+    }
+}
+
+fn same_origin(a: &Url, b: &Url) -> bool {
+    a.scheme == b.scheme && a.host() == b.host() && a.port() == b.port()
+}
+
+fn serialize_origin(url: &Url) -> String {
+    let host = url.serialize_host().unwrap_or("".to_string());
+    match url.port() {
+        Some(port) => format!("{:s}://{:s}:{:u}", url.scheme, host, port),
+        None => format!("{:s}://{:s}", url.scheme, host),
+    }
+}
+
+/// Parses an event stream as it arrives, whatever the boundaries of the
+/// chunks it arrives in.
+/// http://www.whatwg.org/html/#event-stream-interpretation
+pub struct EventStreamParser {
+    /// The bytes of the line being received
+    line: Vec<u8>,
+    /// Whether the last byte received ended a line with a CR, in which case
+    /// a LF that follows belongs to the same line ending
+    after_cr: bool,
+    /// Whether no line of the current stream was parsed yet, as the first
+    /// one may start with a byte order mark
+    first_line: bool,
+    data: String,
+    event_type: String,
+    last_event_id_buffer: String,
+    last_event_id: String,
+    reconnection_time: u64,
+}
+
+impl EventStreamParser {
+    pub fn new() -> EventStreamParser {
+        EventStreamParser {
+            line: vec!(),
+            after_cr: false,
+            first_line: true,
+            data: String::new(),
+            event_type: String::new(),
+            last_event_id_buffer: String::new(),
+            last_event_id: String::new(),
+            reconnection_time: DEFAULT_RECONNECTION_TIME,
+        }
+    }
+
+    /// The ID of the last event dispatched.
+    pub fn last_event_id<'a>(&'a self) -> &'a str {
+        self.last_event_id.as_slice()
+    }
+
+    /// How long to wait before reconnecting, in milliseconds.
+    pub fn reconnection_time(&self) -> u64 {
+        self.reconnection_time
+    }
+
+    /// Parses the next bytes of the stream, and returns the events whose
+    /// end they contain.
+    pub fn parse(&mut self, bytes: &[u8]) -> Vec<ServerSentEvent> {
+        let mut events = vec!();
+        for &byte in bytes.iter() {
+            match byte {
+                b'\n' if self.after_cr => self.after_cr = false,
+                b'\r' | b'\n' => {
+                    self.after_cr = byte == b'\r';
+                    let line = mem::replace(&mut self.line, vec!());
+                    match self.process_line(line) {
+                        Some(event) => events.push(event),
+                        None => {},
+                    }
+                },
+                _ => {
+                    self.after_cr = false;
+                    self.line.push(byte);
+                },
+            }
+        }
+        events
+    }
+
+    /// Discards the incomplete line and event of a stream that ended, so
+    /// that the parser can take the stream of another request.
+    pub fn end_of_stream(&mut self) {
+        self.line = vec!();
+        self.after_cr = false;
+        self.first_line = true;
+        self.data = String::new();
+        self.event_type = String::new();
+    }
+
+    fn process_line(&mut self, line: Vec<u8>) -> Option<ServerSentEvent> {
+        let bytes = if self.first_line && line.as_slice().starts_with(b"\xEF\xBB\xBF") {
+            line.slice_from(3)
+        } else {
+            line.as_slice()
+        };
+        self.first_line = false;
+        let line = str::from_utf8_lossy(bytes).into_string();
+
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.as_slice().starts_with(":") {
+            return None;
+        }
+        let (field, value) = match line.as_slice().find(':') {
+            Some(index) => {
+                let value = line.as_slice().slice_from(index + 1);
+                let value = if value.starts_with(" ") { value.slice_from(1) } else { value };
+                (line.as_slice().slice_to(index), value)
+            },
+            None => (line.as_slice(), ""),
+        };
+        match field {
+            "event" => self.event_type = value.to_string(),
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            },
+            "id" if !value.contains_char('\0') => self.last_event_id_buffer = value.to_string(),
+            "retry" if !value.is_empty() && value.chars().all(|c| c >= '0' && c <= '9') => {
+                match from_str(value) {
+                    Some(time) => self.reconnection_time = time,
+                    None => {},
+                }
+            },
+            _ => {},
+        }
+        None
+    }
+
+    /// Returns the event the last lines described, if they had any data.
+    /// http://www.whatwg.org/html/#dispatchMessage
+    fn dispatch(&mut self) -> Option<ServerSentEvent> {
+        self.last_event_id = self.last_event_id_buffer.clone();
+        let event_type = mem::replace(&mut self.event_type, String::new());
+        if self.data.is_empty() {
+            return None;
+        }
+        let mut data = mem::replace(&mut self.data, String::new());
+        let length = data.len();
+        data.truncate(length - 1);
+        Some(ServerSentEvent {
+            type_: if event_type.is_empty() { "message".to_string() } else { event_type },
+            data: data,
+            last_event_id: self.last_event_id.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+static FIXTURE: &'static [u8] = b"\xEF\xBB\xBF: a comment\r\n\
+data: first line\r\n\
+data:second line\r\n\
+data\r\n\
+\r\n\
+event: update\n\
+id: 7\n\
+unknown: field\n\
+data: {\"value\": 1}\n\
+\n\
+id\r\
+retry: 1500\r\
+data: caf\xC3\xA9\r\
+\r\
+data: never dispatched\n";
+
+#[cfg(test)]
+fn event(type_: &str, data: &str, last_event_id: &str) -> ServerSentEvent {
+    ServerSentEvent {
+        type_: type_.to_string(),
+        data: data.to_string(),
+        last_event_id: last_event_id.to_string(),
+    }
+}
+
+#[cfg(test)]
+fn fixture_events() -> Vec<ServerSentEvent> {
+    vec!(event("message", "first line\nsecond line\n", ""),
+         event("update", "{\"value\": 1}", "7"),
+         event("message", "café", ""))
+}
+
+#[cfg(test)]
+fn header(load_data: &LoadData, name: &str) -> Option<String> {
+    load_data.headers.iter()
+        .find(|header| header.header_name().as_slice() == name)
+        .map(|header| header.header_value())
+}
+
+#[cfg(test)]
+fn event_stream_metadata(url: Url) -> Metadata {
+    let mut metadata = Metadata::default(url);
+    metadata.content_type = Some(("text".to_string(), "event-stream".to_string()));
+    metadata
+}
+
+/// Answers the next request sent to `requests` with `metadata` and a body
+/// of `chunks`, and returns the request.
+#[cfg(test)]
+fn respond(requests: &Receiver<::resource_task::ControlMsg>, metadata: Metadata,
+           chunks: &[&[u8]]) -> LoadData {
+    use resource_task::start_sending;
+
+    match requests.recv() {
+        Load(load_data, start_chan) => {
+            let progress_chan = start_sending(start_chan, metadata);
+            for chunk in chunks.iter() {
+                progress_chan.send(Payload(chunk.to_vec()));
+            }
+            progress_chan.send(Done(Ok(())));
+            load_data
+        },
+        _ => fail!("expected a load"),
+    }
+}
+
+#[test]
+fn test_parse_whole_stream() {
+    let mut parser = EventStreamParser::new();
+    assert_eq!(parser.parse(FIXTURE), fixture_events());
+    assert_eq!(parser.last_event_id(), "");
+    assert_eq!(parser.reconnection_time(), 1500);
+}
+
+#[test]
+fn test_parse_split_stream() {
+    // Every line ending, field and multi-byte character of the fixture is
+    // split by at least one of these.
+    for split in range(0, FIXTURE.len() + 1) {
+        let mut parser = EventStreamParser::new();
+        let mut events = parser.parse(FIXTURE.slice_to(split));
+        events.push_all(parser.parse(FIXTURE.slice_from(split)).as_slice());
+        assert_eq!(events, fixture_events());
+    }
+
+    let mut parser = EventStreamParser::new();
+    let mut events = vec!();
+    for byte in FIXTURE.iter() {
+        events.push_all(parser.parse(&[*byte]).as_slice());
+    }
+    assert_eq!(events, fixture_events());
+}
+
+#[test]
+fn test_parse_fields() {
+    let mut parser = EventStreamParser::new();
+    assert_eq!(parser.parse(b"id: 1\nevent: ignored\n\n"), vec!());
+    assert_eq!(parser.last_event_id(), "1");
+    assert_eq!(parser.parse(b"data:  two spaces\nid: a\0b\nretry: 10s\n\n"),
+               vec!(event("message", " two spaces", "1")));
+    assert_eq!(parser.reconnection_time(), DEFAULT_RECONNECTION_TIME);
+    assert_eq!(parser.parse(b"\xEF\xBB\xBFdata: not a byte order mark\n\n"), vec!());
+}
+
+#[test]
+fn test_end_of_stream() {
+    let mut parser = EventStreamParser::new();
+    assert_eq!(parser.parse(b"id: 3\n\nevent: lost\ndata: lost\nda"), vec!());
+    parser.end_of_stream();
+    assert_eq!(parser.parse(b"\xEF\xBB\xBFdata: kept\n\n"), vec!(event("message", "kept", "3")));
+}
+
+#[test]
+fn test_reconnection_sends_last_event_id() {
+    let url = Url::parse("http://example.com/events").unwrap();
+    let (resource_task, requests) = channel();
+    let (events_chan, events) = channel();
+    let _close = connect(resource_task, url.clone(), url.clone(), false, events_chan);
+
+    let request = respond(&requests, event_stream_metadata(url.clone()),
+                          &[b"retry: 1\nid: 42\nda", b"ta: first\n\n"]);
+    assert_eq!(header(&request, "Accept"), Some("text/event-stream".to_string()));
+    assert_eq!(header(&request, "Cache-Control"), Some("no-cache".to_string()));
+    assert_eq!(header(&request, "Last-Event-ID"), None);
+    assert_eq!(header(&request, "Origin"), None);
+    assert_eq!(events.recv(), Opened);
+    assert_eq!(events.recv(), EventReceived(event("message", "first", "42")));
+    assert_eq!(events.recv(), Reconnecting);
+
+    let mut not_found = event_stream_metadata(url.clone());
+    not_found.status = ::http::status::NotFound;
+    let request = respond(&requests, not_found, &[]);
+    assert_eq!(header(&request, "Last-Event-ID"), Some("42".to_string()));
+    assert_eq!(events.recv(), Failed);
+    assert_eq!(events.recv(), Finished);
+}
+
+#[test]
+fn test_network_error_reconnects() {
+    use resource_task::start_sending;
+
+    let url = Url::parse("http://example.com/events").unwrap();
+    let (resource_task, requests) = channel();
+    let (events_chan, events) = channel();
+    let close = connect(resource_task, url.clone(), url.clone(), false, events_chan);
+
+    match requests.recv() {
+        Load(_, start_chan) => {
+            let progress_chan = start_sending(start_chan, Metadata::default(url.clone()));
+            progress_chan.send(Done(Err("connection refused".to_string())));
+        },
+        _ => fail!("expected a load"),
+    }
+    assert_eq!(events.recv(), Reconnecting);
+    close.send(());
+    assert_eq!(events.recv(), Finished);
+}
+
+#[test]
+fn test_wrong_content_type_fails() {
+    let url = Url::parse("http://example.com/events").unwrap();
+    let (resource_task, requests) = channel();
+    let (events_chan, events) = channel();
+    let _close = connect(resource_task, url.clone(), url.clone(), false, events_chan);
+
+    let mut metadata = Metadata::default(url.clone());
+    metadata.content_type = Some(("text".to_string(), "plain".to_string()));
+    respond(&requests, metadata, &[b"data: ignored\n\n"]);
+    assert_eq!(events.recv(), Failed);
+    assert_eq!(events.recv(), Finished);
+}
+
+#[test]
+fn test_cross_origin() {
+    use http::headers::response::ExtensionHeader as ResponseExtensionHeader;
+    use http::headers::response::HeaderCollection;
+
+    let document_url = Url::parse("http://example.com/").unwrap();
+    let url = Url::parse("http://example.org/events").unwrap();
+    let with_allow_origin = |origin: &str| -> Metadata {
+        let mut headers = HeaderCollection::new();
+        headers.insert(ResponseExtensionHeader("Access-Control-Allow-Origin".to_string(),
+                                               origin.to_string()));
+        let mut metadata = event_stream_metadata(url.clone());
+        metadata.headers = Some(headers);
+        metadata
+    };
+
+    let (resource_task, requests) = channel();
+    let (events_chan, events) = channel();
+    let close = connect(resource_task, url.clone(), document_url.clone(), false, events_chan);
+    let request = respond(&requests, with_allow_origin("http://example.com"), &[]);
+    assert_eq!(header(&request, "Origin"), Some("http://example.com".to_string()));
+    assert_eq!(events.recv(), Opened);
+    assert_eq!(events.recv(), Reconnecting);
+    close.send(());
+    assert_eq!(events.recv(), Finished);
+
+    let (resource_task, requests) = channel();
+    let (events_chan, events) = channel();
+    let _close = connect(resource_task, url.clone(), document_url.clone(), false, events_chan);
+    respond(&requests, with_allow_origin("http://example.net"), &[]);
+    assert_eq!(events.recv(), Failed);
+    assert_eq!(events.recv(), Finished);
+
+    // The contents of data: URLs are shared with every document.
+    let data_url = Url::parse("data:text/event-stream,data").unwrap();
+    let (resource_task, requests) = channel();
+    let (events_chan, events) = channel();
+    let close = connect(resource_task, data_url.clone(), document_url.clone(), false, events_chan);
+    let request = respond(&requests, event_stream_metadata(data_url), &[]);
+    assert_eq!(header(&request, "Origin"), None);
+    assert_eq!(events.recv(), Opened);
+    assert_eq!(events.recv(), Reconnecting);
+    close.send(());
+    assert_eq!(events.recv(), Finished);
+
+    // A wildcard doesn't share responses to requests with credentials.
+    let (resource_task, requests) = channel();
+    let (events_chan, events) = channel();
+    let _close = connect(resource_task, url.clone(), document_url.clone(), true, events_chan);
+    respond(&requests, with_allow_origin("*"), &[]);
+    assert_eq!(events.recv(), Failed);
+    assert_eq!(events.recv(), Finished);
+}
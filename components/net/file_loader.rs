@@ -33,7 +33,15 @@ fn read_all(reader: &mut io::Stream, progress_chan: &Sender<ProgressMsg>)
 pub fn factory(load_data: LoadData, start_chan: Sender<LoadResponse>) {
     let url = load_data.url;
     assert!("file" == url.scheme.as_slice());
-    let progress_chan = start_sending(start_chan, Metadata::default(url.clone()));
+    let mut metadata = Metadata::default(url.clone());
+    // Files have no Content-Type, so the extension stands in for HTML.
+    match url.path().and_then(|path| path.last()) {
+        Some(name) if name.as_slice().ends_with(".html") || name.as_slice().ends_with(".htm") => {
+            metadata.content_type = Some(("text".to_string(), "html".to_string()));
+        }
+        _ => {}
+    }
+    let progress_chan = start_sending(start_chan, metadata);
     spawn_named("file_loader", proc() {
         let file_path: Result<Path, ()> = url.to_file_path();
         match file_path {
@@ -0,0 +1,446 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! An in-memory HTTP cache shared by the loads of a resource task. Fresh
+//! responses are served without going to the network, and stale ones are
+//! revalidated with the server.
+//! http://tools.ietf.org/html/rfc7234
+
+use resource_task::{LoadData, Metadata};
+
+use http::headers::HeaderEnum;
+use http::headers::request::ExtensionHeader;
+use http::headers::request::HeaderCollection as RequestHeaderCollection;
+use http::headers::response::HeaderCollection as ResponseHeaderCollection;
+use http::method::Get;
+use std::ascii::{StrAsciiExt, OwnedStrAsciiExt};
+use std::cmp::max;
+use std::collections::hashmap::HashMap;
+use time;
+use url::Url;
+
+/// How many bytes of response bodies a cache holds before it evicts the
+/// least recently used responses.
+pub static DEFAULT_MAX_SIZE: uint = 32 * 1024 * 1024;
+
+/// A response stored in the cache.
+#[deriving(Clone)]
+pub struct CachedResponse {
+    pub metadata: Metadata,
+    pub body: Vec<u8>,
+}
+
+/// What the cache has for a request.
+pub enum CacheLookup {
+    /// A response that can be used without asking the server.
+    Fresh(CachedResponse),
+    /// A stale response. Validators have been added to the request, so that
+    /// the server answers 304 Not Modified if the response is still good.
+    Stale,
+    Miss,
+}
+
+struct CacheEntry {
+    response: CachedResponse,
+    /// When the response was received or last revalidated, in seconds.
+    stored_at: i64,
+    /// How long the response stays fresh after `stored_at`, in seconds.
+    freshness_lifetime: i64,
+    /// The value of the cache's clock when the entry was last used.
+    last_used: u64,
+}
+
+pub struct HttpCache {
+    entries: HashMap<Url, CacheEntry>,
+    /// The size of the stored bodies, in bytes.
+    size: uint,
+    max_size: uint,
+    /// Counts the uses of entries, to find the least recently used one.
+    clock: u64,
+}
+
+impl HttpCache {
+    pub fn new(max_size: uint) -> HttpCache {
+        HttpCache {
+            entries: HashMap::new(),
+            size: 0,
+            max_size: max_size,
+            clock: 0,
+        }
+    }
+
+    /// Looks up the response for `url`. When it is stale, the validators to
+    /// revalidate it are added to `headers`.
+    pub fn lookup(&mut self, url: &Url, headers: &mut RequestHeaderCollection) -> CacheLookup {
+        self.clock += 1;
+        let clock = self.clock;
+        let entry = match self.entries.find_mut(url) {
+            Some(entry) => entry,
+            None => return Miss,
+        };
+        entry.last_used = clock;
+        if time::get_time().sec - entry.stored_at < entry.freshness_lifetime {
+            return Fresh(entry.response.clone());
+        }
+
+        // http://tools.ietf.org/html/rfc7232#section-2.4
+        let stored = entry.response.metadata.headers.as_ref().unwrap();
+        let etag = header_value(stored, "ETag");
+        let last_modified = header_value(stored, "Last-Modified");
+        if etag.is_none() && last_modified.is_none() {
+            return Miss;
+        }
+        match etag {
+            Some(etag) => headers.insert(ExtensionHeader("If-None-Match".to_string(), etag)),
+            None => {},
+        }
+        match last_modified {
+            Some(date) => headers.insert(ExtensionHeader("If-Modified-Since".to_string(), date)),
+            None => {},
+        }
+        Stale
+    }
+
+    /// Stores the response for `url`, replacing any older one. The response
+    /// must be storable.
+    pub fn store(&mut self, url: &Url, response: CachedResponse) {
+        self.remove(url);
+        if response.body.len() > self.max_size {
+            return;
+        }
+        let now = time::get_time().sec;
+        let freshness_lifetime = freshness_lifetime(response.metadata.headers.as_ref().unwrap(),
+                                                    now);
+        self.clock += 1;
+        self.size += response.body.len();
+        self.entries.insert(url.clone(), CacheEntry {
+            response: response,
+            stored_at: now,
+            freshness_lifetime: freshness_lifetime,
+            last_used: self.clock,
+        });
+        self.evict();
+    }
+
+    /// Handles a 304 Not Modified response to the revalidation of the
+    /// response for `url`. The stored headers are updated with the new ones,
+    /// and the stored response is returned.
+    /// http://tools.ietf.org/html/rfc7234#section-4.3.4
+    pub fn revalidated(&mut self, url: &Url,
+                       not_modified: &ResponseHeaderCollection) -> Option<CachedResponse> {
+        let now = time::get_time().sec;
+        let entry = match self.entries.find_mut(url) {
+            Some(entry) => entry,
+            None => return None,
+        };
+        let freshness_lifetime = {
+            let headers = entry.response.metadata.headers.as_mut().unwrap();
+            for header in not_modified.iter() {
+                let name = header.header_name().into_ascii_lower();
+                if name.as_slice() != "content-length" && name.as_slice() != "transfer-encoding" {
+                    headers.insert(header);
+                }
+            }
+            freshness_lifetime(headers, now)
+        };
+        entry.stored_at = now;
+        entry.freshness_lifetime = freshness_lifetime;
+        Some(entry.response.clone())
+    }
+
+    /// Forgets the response for `url`, if any.
+    pub fn remove(&mut self, url: &Url) {
+        match self.entries.pop(url) {
+            Some(entry) => self.size -= entry.response.body.len(),
+            None => {},
+        }
+    }
+
+    /// Evicts the least recently used responses until the cache fits in its
+    /// maximum size.
+    fn evict(&mut self) {
+        while self.size > self.max_size {
+            let oldest = self.entries.iter()
+                .min_by(|&(_, entry)| entry.last_used)
+                .map(|(url, _)| url.clone());
+            match oldest {
+                Some(url) => self.remove(&url),
+                None => break,
+            }
+        }
+    }
+}
+
+/// Whether the cache may answer `load_data` and store the response to it.
+pub fn is_cacheable_request(load_data: &LoadData) -> bool {
+    match load_data.method {
+        Get => {},
+        _ => return false,
+    }
+    if load_data.data.is_some() {
+        return false;
+    }
+    load_data.headers.iter().all(|header| {
+        let name = header.header_name().into_ascii_lower();
+        match name.as_slice() {
+            // The requester does its own validation.
+            "if-match" | "if-none-match" | "if-modified-since" | "if-unmodified-since" |
+            "if-range" | "range" => false,
+            "cache-control" | "pragma" => {
+                let value = header.header_value();
+                directive(value.as_slice(), "no-store").is_none() &&
+                directive(value.as_slice(), "no-cache").is_none()
+            },
+            _ => true,
+        }
+    })
+}
+
+/// Whether a response may be stored.
+/// http://tools.ietf.org/html/rfc7234#section-3
+pub fn is_storable(metadata: &Metadata) -> bool {
+    let headers = match metadata.headers {
+        Some(ref headers) => headers,
+        None => return false,
+    };
+    if metadata.status.code() != 200 {
+        return false;
+    }
+    match header_value(headers, "Cache-Control") {
+        Some(ref value) if directive(value.as_slice(), "no-store").is_some() => return false,
+        _ => {},
+    }
+    // FIXME: Responses that vary with request headers could be stored along
+    // with those headers.
+    if header_value(headers, "Vary").is_some() {
+        return false;
+    }
+    freshness_lifetime(headers, time::get_time().sec) > 0 ||
+        header_value(headers, "ETag").is_some() ||
+        header_value(headers, "Last-Modified").is_some()
+}
+
+/// How long a response received at `now` stays fresh, in seconds. Without
+/// explicit freshness information it is stale right away.
+/// http://tools.ietf.org/html/rfc7234#section-4.2.1
+fn freshness_lifetime(headers: &ResponseHeaderCollection, now: i64) -> i64 {
+    let age = header_value(headers, "Age")
+        .and_then(|age| from_str::<i64>(age.as_slice().trim()))
+        .unwrap_or(0);
+    let cache_control = header_value(headers, "Cache-Control").unwrap_or(String::new());
+    if directive(cache_control.as_slice(), "no-cache").is_some() {
+        return 0;
+    }
+    match directive(cache_control.as_slice(), "max-age").and_then(|max_age| from_str::<i64>(max_age)) {
+        Some(max_age) => return max_age - age,
+        None => {},
+    }
+    // An invalid Expires header means that the response has already expired.
+    match header_value(headers, "Expires") {
+        Some(expires) => {
+            let date = header_value(headers, "Date")
+                .and_then(|date| parse_http_date(date.as_slice()))
+                .unwrap_or(now);
+            match parse_http_date(expires.as_slice()) {
+                Some(expires) => max(expires - date, 0) - age,
+                None => 0,
+            }
+        },
+        None => 0,
+    }
+}
+
+/// Returns the value of the directive `name` of a Cache-Control header, or
+/// an empty string if the directive has no value.
+fn directive<'a>(cache_control: &'a str, name: &str) -> Option<&'a str> {
+    for directive in cache_control.split(',') {
+        let directive = directive.trim();
+        let (key, value) = match directive.find('=') {
+            Some(index) => (directive.slice_to(index), directive.slice_from(index + 1)),
+            None => (directive, ""),
+        };
+        if key.trim().eq_ignore_ascii_case(name) {
+            return Some(value.trim().trim_chars('"'));
+        }
+    }
+    None
+}
+
+fn header_value(headers: &ResponseHeaderCollection, name: &str) -> Option<String> {
+    headers.iter()
+        .find(|header| header.header_name().as_slice().eq_ignore_ascii_case(name))
+        .map(|header| header.header_value())
+}
+
+/// Parses the date formats of HTTP/1.1 into seconds since the epoch.
+/// http://tools.ietf.org/html/rfc7231#section-7.1.1.1
+fn parse_http_date(date: &str) -> Option<i64> {
+    for format in ["%a, %d %b %Y %T %Z", "%A, %d-%b-%y %T %Z", "%c"].iter() {
+        match time::strptime(date, *format) {
+            Ok(tm) => return Some(tm.to_timespec().sec),
+            Err(_) => {},
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+fn response(url: &Url, body: &str, headers: &[(&str, &str)]) -> CachedResponse {
+    use http::headers::response::ExtensionHeader as ResponseExtensionHeader;
+
+    let mut collection = ResponseHeaderCollection::new();
+    for &(name, value) in headers.iter() {
+        collection.insert(ResponseExtensionHeader(name.to_string(), value.to_string()));
+    }
+    let mut metadata = Metadata::default(url.clone());
+    metadata.headers = Some(collection);
+    CachedResponse {
+        metadata: metadata,
+        body: body.as_bytes().to_vec(),
+    }
+}
+
+#[cfg(test)]
+fn request_header(headers: &RequestHeaderCollection, name: &str) -> Option<String> {
+    headers.iter()
+        .find(|header| header.header_name().as_slice() == name)
+        .map(|header| header.header_value())
+}
+
+#[test]
+fn test_freshness_lifetime() {
+    let url = Url::parse("http://example.com/").unwrap();
+    let lifetime = |headers: &[(&str, &str)]| {
+        freshness_lifetime(response(&url, "", headers).metadata.headers.as_ref().unwrap(), 0)
+    };
+    assert_eq!(lifetime(&[]), 0);
+    assert_eq!(lifetime(&[("Cache-Control", "public, max-age=60")]), 60);
+    assert_eq!(lifetime(&[("Cache-Control", "max-age=\"60\""), ("Age", "10")]), 50);
+    assert_eq!(lifetime(&[("Cache-Control", "no-cache, max-age=60")]), 0);
+    assert_eq!(lifetime(&[("Date", "Sun, 06 Nov 1994 08:49:37 GMT"),
+                         ("Expires", "Sun, 06 Nov 1994 08:59:37 GMT")]), 600);
+    assert_eq!(lifetime(&[("Cache-Control", "max-age=5"),
+                         ("Expires", "Sun, 06 Nov 1994 08:59:37 GMT")]), 5);
+    assert_eq!(lifetime(&[("Expires", "0")]), 0);
+}
+
+#[test]
+fn test_storable() {
+    let url = Url::parse("http://example.com/").unwrap();
+    let storable = |headers: &[(&str, &str)]| is_storable(&response(&url, "", headers).metadata);
+    assert!(storable(&[("Cache-Control", "max-age=60")]));
+    assert!(storable(&[("ETag", "\"1\"")]));
+    assert!(storable(&[("Last-Modified", "Sun, 06 Nov 1994 08:49:37 GMT")]));
+    assert!(!storable(&[]));
+    assert!(!storable(&[("Cache-Control", "max-age=60, no-store")]));
+    assert!(!storable(&[("Cache-Control", "max-age=60"), ("Vary", "Accept")]));
+
+    let mut not_found = response(&url, "", &[("Cache-Control", "max-age=60")]).metadata;
+    not_found.status = ::http::status::NotFound;
+    assert!(!is_storable(&not_found));
+}
+
+#[test]
+fn test_cacheable_request() {
+    let url = Url::parse("http://example.com/").unwrap();
+    assert!(is_cacheable_request(&LoadData::new(url.clone())));
+
+    let mut post = LoadData::new(url.clone());
+    post.method = ::http::method::Post;
+    assert!(!is_cacheable_request(&post));
+
+    let mut no_store = LoadData::new(url.clone());
+    no_store.headers.insert(ExtensionHeader("Cache-Control".to_string(), "no-store".to_string()));
+    assert!(!is_cacheable_request(&no_store));
+
+    let mut conditional = LoadData::new(url.clone());
+    conditional.headers.insert(ExtensionHeader("If-None-Match".to_string(), "\"1\"".to_string()));
+    assert!(!is_cacheable_request(&conditional));
+}
+
+#[test]
+fn test_lookup() {
+    let fresh = Url::parse("http://example.com/fresh").unwrap();
+    let stale = Url::parse("http://example.com/stale").unwrap();
+    let unvalidated = Url::parse("http://example.com/unvalidated").unwrap();
+    let mut cache = HttpCache::new(DEFAULT_MAX_SIZE);
+    cache.store(&fresh, response(&fresh, "fresh", &[("Cache-Control", "max-age=3600")]));
+    cache.store(&stale, response(&stale, "stale", &[("ETag", "\"1\""),
+                                                   ("Last-Modified", "Sun, 06 Nov 1994 08:49:37 GMT")]));
+    cache.store(&unvalidated, response(&unvalidated, "", &[("Cache-Control", "max-age=0")]));
+
+    let mut headers = RequestHeaderCollection::new();
+    match cache.lookup(&fresh, &mut headers) {
+        Fresh(response) => assert_eq!(response.body, b"fresh".to_vec()),
+        _ => fail!("expected a fresh response"),
+    }
+    assert_eq!(request_header(&headers, "If-None-Match"), None);
+
+    match cache.lookup(&stale, &mut headers) {
+        Stale => {},
+        _ => fail!("expected a stale response"),
+    }
+    assert_eq!(request_header(&headers, "If-None-Match"), Some("\"1\"".to_string()));
+    assert_eq!(request_header(&headers, "If-Modified-Since"),
+               Some("Sun, 06 Nov 1994 08:49:37 GMT".to_string()));
+
+    let mut headers = RequestHeaderCollection::new();
+    match cache.lookup(&unvalidated, &mut headers) {
+        Miss => {},
+        _ => fail!("a stale response without validators can't be used"),
+    }
+    match cache.lookup(&Url::parse("http://example.com/missing").unwrap(), &mut headers) {
+        Miss => {},
+        _ => fail!("expected a miss"),
+    }
+}
+
+#[test]
+fn test_revalidated() {
+    let url = Url::parse("http://example.com/").unwrap();
+    let mut cache = HttpCache::new(DEFAULT_MAX_SIZE);
+    cache.store(&url, response(&url, "body", &[("ETag", "\"1\""), ("X-Version", "1")]));
+
+    let not_modified = response(&url, "", &[("Cache-Control", "max-age=3600"), ("X-Version", "2")]);
+    let revalidated = cache.revalidated(&url, not_modified.metadata.headers.as_ref().unwrap());
+    let revalidated = revalidated.unwrap();
+    assert_eq!(revalidated.body, b"body".to_vec());
+    let headers = revalidated.metadata.headers.as_ref().unwrap();
+    assert_eq!(header_value(headers, "ETag"), Some("\"1\"".to_string()));
+    assert_eq!(header_value(headers, "X-Version"), Some("2".to_string()));
+
+    // The new headers made the response fresh.
+    match cache.lookup(&url, &mut RequestHeaderCollection::new()) {
+        Fresh(_) => {},
+        _ => fail!("expected a fresh response"),
+    }
+}
+
+#[test]
+fn test_lru_eviction() {
+    let urls: Vec<Url> = range(0u, 3).map(|i| {
+        Url::parse(format!("http://example.com/{}", i).as_slice()).unwrap()
+    }).collect();
+    let fresh = [("Cache-Control", "max-age=3600")];
+    let is_cached = |cache: &mut HttpCache, url: &Url| {
+        match cache.lookup(url, &mut RequestHeaderCollection::new()) {
+            Fresh(_) => true,
+            _ => false,
+        }
+    };
+
+    let mut cache = HttpCache::new(10);
+    cache.store(&urls[0], response(&urls[0], "0000", &fresh));
+    cache.store(&urls[1], response(&urls[1], "1111", &fresh));
+    assert!(is_cached(&mut cache, &urls[0]));
+    cache.store(&urls[2], response(&urls[2], "2222", &fresh));
+    assert!(is_cached(&mut cache, &urls[0]));
+    assert!(!is_cached(&mut cache, &urls[1]));
+    assert!(is_cached(&mut cache, &urls[2]));
+
+    // Responses bigger than the whole cache are never stored.
+    cache.store(&urls[1], response(&urls[1], "a body over ten bytes", &fresh));
+    assert!(!is_cached(&mut cache, &urls[1]));
+    assert!(is_cached(&mut cache, &urls[0]));
+}
@@ -2,17 +2,21 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use http_cache::{CachedResponse, HttpCache, Fresh, Stale, Miss};
+use http_cache::{is_cacheable_request, is_storable};
 use resource_task::{Metadata, Payload, Done, LoadResponse, LoadData, start_sending_opt};
 
 use std::collections::hashmap::HashSet;
 use http::client::{RequestWriter, NetworkStream};
 use http::headers::HeaderEnum;
-use std::io::Reader;
+use std::io::{EndOfFile, Reader};
 use servo_util::task::spawn_named;
+use sync::{Arc, Mutex};
 use url::Url;
 
-pub fn factory(load_data: LoadData, start_chan: Sender<LoadResponse>) {
-    spawn_named("http_loader", proc() load(load_data, start_chan))
+pub fn factory(load_data: LoadData, start_chan: Sender<LoadResponse>,
+               cache: Arc<Mutex<HttpCache>>) {
+    spawn_named("http_loader", proc() load(load_data, start_chan, cache))
 }
 
 fn send_error(url: Url, err: String, start_chan: Sender<LoadResponse>) {
@@ -22,7 +26,18 @@ fn send_error(url: Url, err: String, start_chan: Sender<LoadResponse>) {
     };
 }
 
-fn load(load_data: LoadData, start_chan: Sender<LoadResponse>) {
+fn send_cached(response: CachedResponse, start_chan: Sender<LoadResponse>) {
+    let progress_chan = match start_sending_opt(start_chan, response.metadata) {
+        Ok(p) => p,
+        _ => return
+    };
+    if !response.body.is_empty() {
+        let _ = progress_chan.send_opt(Payload(response.body));
+    }
+    let _ = progress_chan.send_opt(Done(Ok(())));
+}
+
+fn load(load_data: LoadData, start_chan: Sender<LoadResponse>, cache: Arc<Mutex<HttpCache>>) {
     // FIXME: At the time of writing this FIXME, servo didn't have any central
     //        location for configuration. If you're reading this and such a
     //        repository DOES exist, please update this constant to use it.
@@ -30,6 +45,7 @@ fn load(load_data: LoadData, start_chan: Sender<LoadResponse>) {
     let mut iters = 0u;
     let mut url = load_data.url.clone();
     let mut redirected_to = HashSet::new();
+    let cacheable = is_cacheable_request(&load_data);
 
     // Loop to handle redirects.
     loop {
@@ -56,6 +72,22 @@ fn load(load_data: LoadData, start_chan: Sender<LoadResponse>) {
             }
         }
 
+        let mut headers = load_data.headers.clone();
+        let revalidating = if cacheable {
+            let lookup = cache.lock().lookup(&url, &mut headers);
+            match lookup {
+                Fresh(response) => {
+                    info!("using the cached response for {:s}", url.serialize());
+                    send_cached(response, start_chan);
+                    return;
+                }
+                Stale => true,
+                Miss => false,
+            }
+        } else {
+            false
+        };
+
         info!("requesting {:s}", url.serialize());
 
         let request = RequestWriter::<NetworkStream>::new(load_data.method.clone(), url.clone());
@@ -69,7 +101,7 @@ fn load(load_data: LoadData, start_chan: Sender<LoadResponse>) {
 
         // Preserve the `host` header set automatically by RequestWriter.
         let host = writer.headers.host.clone();
-        writer.headers = load_data.headers.clone();
+        writer.headers = headers;
         writer.headers.host = host;
         if writer.headers.accept_encoding.is_none() {
             // We currently don't support HTTP Compression (FIXME #2587)
@@ -103,6 +135,16 @@ fn load(load_data: LoadData, start_chan: Sender<LoadResponse>) {
                 info!(" - {:s}: {:s}", header.header_name(), header.header_value());
             });
 
+        if revalidating && response.status.code() == 304 {
+            info!("the cached response for {:s} is still valid", url.serialize());
+            let cached = cache.lock().revalidated(&url, &response.headers);
+            match cached {
+                Some(cached) => send_cached(cached, start_chan),
+                None => send_error(url, "the cached response is gone".to_string(), start_chan),
+            }
+            return;
+        }
+
         if 3 == (response.status.code() / 100) {
             match response.headers.location {
                 Some(new_url) => {
@@ -128,11 +170,24 @@ fn load(load_data: LoadData, start_chan: Sender<LoadResponse>) {
             }
         }
 
-        let mut metadata = Metadata::default(url);
+        let mut metadata = Metadata::default(url.clone());
         metadata.set_content_type(&response.headers.content_type);
         metadata.headers = Some(response.headers.clone());
         metadata.status = response.status.clone();
 
+        // The body is kept while it is sent, to be stored once complete.
+        let mut stored = if cacheable && is_storable(&metadata) {
+            Some(CachedResponse {
+                metadata: metadata.clone(),
+                body: vec!(),
+            })
+        } else {
+            if cacheable {
+                cache.lock().remove(&url);
+            }
+            None
+        };
+
         let progress_chan = match start_sending_opt(start_chan, metadata) {
             Ok(p) => p,
             _ => return
@@ -144,6 +199,10 @@ fn load(load_data: LoadData, start_chan: Sender<LoadResponse>) {
             match response.read(buf.as_mut_slice()) {
                 Ok(len) => {
                     unsafe { buf.set_len(len); }
+                    match stored {
+                        Some(ref mut stored) => stored.body.push_all(buf.as_slice()),
+                        None => {}
+                    }
                     if progress_chan.send_opt(Payload(buf)).is_err() {
                         // The send errors when the receiver is out of scope,
                         // which will happen if the fetch has timed out (or has been aborted)
@@ -151,7 +210,11 @@ fn load(load_data: LoadData, start_chan: Sender<LoadResponse>) {
                         return;
                     }
                 }
-                Err(_) => {
+                Err(e) => {
+                    match stored.take() {
+                        Some(stored) if e.kind == EndOfFile => cache.lock().store(&url, stored),
+                        _ => {}
+                    }
                     let _ = progress_chan.send_opt(Done(Ok(())));
                     break;
                 }
@@ -162,3 +225,124 @@ fn load(load_data: LoadData, start_chan: Sender<LoadResponse>) {
         break;
     }
 }
+
+/// Serves `count` HTTP requests on a loopback port, answering each with what
+/// `respond` returns for its header lines. The header lines of each request
+/// are sent to the returned port, so that tests can count the requests.
+#[cfg(test)]
+fn serve(count: uint, respond: fn(&[String]) -> String) -> (Url, Receiver<Vec<String>>) {
+    use std::io::{Acceptor, BufferedReader, Listener, TcpListener};
+
+    let mut listener = TcpListener::bind("127.0.0.1", 0).unwrap();
+    let port = listener.socket_name().unwrap().port;
+    let mut acceptor = listener.listen().unwrap();
+    let (requests_chan, requests) = channel();
+    spawn(proc() {
+        for _ in range(0, count) {
+            let mut stream = acceptor.accept().unwrap();
+            let mut reader = BufferedReader::new(stream.clone());
+            let mut lines = vec!();
+            loop {
+                let line = reader.read_line().unwrap();
+                let line = line.as_slice().trim_right_chars(['\r', '\n'].as_slice()).to_string();
+                if line.is_empty() {
+                    break;
+                }
+                lines.push(line);
+            }
+            let response = respond(lines.as_slice());
+            requests_chan.send(lines);
+            stream.write(response.as_bytes()).unwrap();
+        }
+    });
+    (Url::parse(format!("http://127.0.0.1:{:u}/", port).as_slice()).unwrap(), requests)
+}
+
+#[cfg(test)]
+fn fetch(url: &Url, cache: &Arc<Mutex<HttpCache>>) -> (Metadata, Vec<u8>) {
+    let (start_chan, start_port) = channel();
+    load(LoadData::new(url.clone()), start_chan, cache.clone());
+    let response = start_port.recv();
+    let mut body = vec!();
+    loop {
+        match response.progress_port.recv() {
+            Payload(data) => body.push_all(data.as_slice()),
+            Done(result) => {
+                assert!(result.is_ok());
+                return (response.metadata, body);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn has_header(lines: &[String], header: &str) -> bool {
+    use std::ascii::StrAsciiExt;
+
+    lines.iter().any(|line| line.as_slice().eq_ignore_ascii_case(header))
+}
+
+#[test]
+fn test_fresh_response_is_cached() {
+    use http_cache::DEFAULT_MAX_SIZE;
+
+    fn respond(_: &[String]) -> String {
+        "HTTP/1.1 200 OK\r\nCache-Control: max-age=3600\r\nContent-Length: 5\r\n\
+         Connection: close\r\n\r\nhello".to_string()
+    }
+    let (url, requests) = serve(1, respond);
+    let cache = Arc::new(Mutex::new(HttpCache::new(DEFAULT_MAX_SIZE)));
+    let (_, body) = fetch(&url, &cache);
+    assert_eq!(body, b"hello".to_vec());
+    requests.recv();
+
+    // The second load doesn't reach the server.
+    let (metadata, body) = fetch(&url, &cache);
+    assert_eq!(body, b"hello".to_vec());
+    assert_eq!(metadata.status.code(), 200);
+    assert!(requests.try_recv().is_err());
+}
+
+#[test]
+fn test_stale_response_is_revalidated() {
+    use http_cache::DEFAULT_MAX_SIZE;
+
+    fn respond(lines: &[String]) -> String {
+        if has_header(lines, "If-None-Match: \"v1\"") {
+            "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n".to_string()
+        } else {
+            "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Type: text/plain\r\n\
+             Content-Length: 5\r\nConnection: close\r\n\r\nhello".to_string()
+        }
+    }
+    let (url, requests) = serve(2, respond);
+    let cache = Arc::new(Mutex::new(HttpCache::new(DEFAULT_MAX_SIZE)));
+    let (_, body) = fetch(&url, &cache);
+    assert_eq!(body, b"hello".to_vec());
+    assert!(!has_header(requests.recv().as_slice(), "If-None-Match: \"v1\""));
+
+    // The 304 is turned into the cached response.
+    let (metadata, body) = fetch(&url, &cache);
+    assert!(has_header(requests.recv().as_slice(), "If-None-Match: \"v1\""));
+    assert_eq!(body, b"hello".to_vec());
+    assert_eq!(metadata.status.code(), 200);
+    assert_eq!(metadata.content_type, Some(("text".to_string(), "plain".to_string())));
+}
+
+#[test]
+fn test_no_store_response_is_not_cached() {
+    use http_cache::DEFAULT_MAX_SIZE;
+
+    fn respond(_: &[String]) -> String {
+        "HTTP/1.1 200 OK\r\nCache-Control: max-age=3600, no-store\r\nContent-Length: 5\r\n\
+         Connection: close\r\n\r\nhello".to_string()
+    }
+    let (url, requests) = serve(2, respond);
+    let cache = Arc::new(Mutex::new(HttpCache::new(DEFAULT_MAX_SIZE)));
+    for _ in range(0u, 2) {
+        let (_, body) = fetch(&url, &cache);
+        assert_eq!(body, b"hello".to_vec());
+    }
+    requests.recv();
+    requests.recv();
+}
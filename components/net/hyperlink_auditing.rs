@@ -0,0 +1,87 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The requests that tell a server a hyperlink was followed.
+//! http://www.whatwg.org/html/#hyperlink-auditing
+
+use referrer_policy::{ReferrerPolicy, determine_referrer};
+use resource_task::LoadData;
+
+use http::headers::content_type::MediaType;
+use http::headers::request::ExtensionHeader;
+use http::method::Post;
+use url::Url;
+
+/// Returns the request to send to `ping` when a document at `document`
+/// follows a hyperlink to `target` under `policy`.
+pub fn ping_load_data(ping: Url, document: &Url, target: &Url, policy: ReferrerPolicy) -> LoadData {
+    let mut load_data = LoadData::new(ping);
+    load_data.method = Post;
+    load_data.data = Some(b"PING".to_vec());
+    load_data.headers.content_type = Some(MediaType {
+        type_: "text".to_string(),
+        subtype: "ping".to_string(),
+        parameters: vec!(),
+    });
+
+    // Secure documents only reveal their URL to their own origin.
+    let same_origin = document.scheme == load_data.url.scheme &&
+                      document.host() == load_data.url.host() &&
+                      document.port() == load_data.url.port();
+    if same_origin || document.scheme.as_slice() != "https" {
+        load_data.headers.insert(ExtensionHeader("Ping-From".to_string(), document.serialize()));
+        let referrer = determine_referrer(policy, document, &load_data.url);
+        load_data.headers.referer = referrer.map(|referrer| referrer.serialize());
+    }
+    load_data.headers.insert(ExtensionHeader("Ping-To".to_string(), target.serialize()));
+    load_data
+}
+
+#[cfg(test)]
+fn header(load_data: &LoadData, name: &str) -> Option<String> {
+    use http::headers::HeaderEnum;
+    load_data.headers.iter()
+        .find(|header| header.header_name().as_slice() == name)
+        .map(|header| header.header_value())
+}
+
+#[test]
+fn test_ping_load_data() {
+    use referrer_policy::NoReferrerWhenDowngrade;
+
+    let document = Url::parse("http://example.com/index.html").unwrap();
+    let target = Url::parse("http://example.org/").unwrap();
+    let pings = ["http://example.com/ping", "http://example.net/ping"];
+    let requests: Vec<LoadData> = pings.iter().map(|ping| {
+        let ping = Url::parse(*ping).unwrap();
+        ping_load_data(ping, &document, &target, NoReferrerWhenDowngrade)
+    }).collect();
+
+    assert_eq!(requests.len(), 2);
+    for request in requests.iter() {
+        assert!(request.method == Post);
+        assert_eq!(request.data, Some(b"PING".to_vec()));
+        assert_eq!(header(request, "Ping-From"), Some("http://example.com/index.html".to_string()));
+        assert_eq!(header(request, "Ping-To"), Some("http://example.org/".to_string()));
+        assert_eq!(request.headers.referer, Some("http://example.com/index.html".to_string()));
+    }
+}
+
+#[test]
+fn test_ping_load_data_from_secure_document() {
+    use referrer_policy::UnsafeUrl;
+
+    let document = Url::parse("https://example.com/index.html").unwrap();
+    let target = Url::parse("https://example.org/").unwrap();
+
+    let ping = Url::parse("https://example.net/ping").unwrap();
+    let request = ping_load_data(ping, &document, &target, UnsafeUrl);
+    assert_eq!(header(&request, "Ping-From"), None);
+    assert_eq!(header(&request, "Ping-To"), Some("https://example.org/".to_string()));
+    assert_eq!(request.headers.referer, None);
+
+    let ping = Url::parse("https://example.com/ping").unwrap();
+    let request = ping_load_data(ping, &document, &target, UnsafeUrl);
+    assert_eq!(header(&request, "Ping-From"), Some("https://example.com/index.html".to_string()));
+}
@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use std::ascii::StrAsciiExt;
 use std::iter::range_step;
 use stb_image::image as stb_image;
 use png;
@@ -41,6 +42,16 @@ fn byte_swap_and_premultiply(data: &mut [u8]) {
     }
 }
 
+/// Whether `load_from_memory` decodes images of the given MIME type, which
+/// may carry parameters.
+pub fn is_supported_mime_type(mime_type: &str) -> bool {
+    let essence = mime_type.split(';').next().unwrap().trim();
+    match essence.to_ascii_lower().as_slice() {
+        "image/png" | "image/jpeg" | "image/gif" | "image/bmp" => true,
+        _ => false,
+    }
+}
+
 pub fn load_from_memory(buffer: &[u8]) -> Option<Image> {
     if buffer.len() == 0 {
         return None;
@@ -3,7 +3,7 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use image::base::Image;
-use image_cache_task::{ImageReady, ImageNotReady, ImageFailed};
+use image_cache_task::{ImageReady, ImageNotReady, ImageFailed, ImageEvicted};
 use local_image_cache::LocalImageCache;
 
 use geom::size::Size2D;
@@ -16,11 +16,14 @@ use url::Url;
 // injection.
 
 /// A struct to store image data. The image will be loaded once the first time it is requested,
-/// and an Arc will be stored.  Clones of this Arc are given out on demand.
+/// and an Arc will be stored.  Clones of this Arc are given out on demand, until the image cache
+/// evicts the image and it has to be loaded again.
 #[deriving(Clone)]
 pub struct ImageHolder {
     url: Url,
     image: Option<Arc<Box<Image>>>,
+    /// The number of times the image cache had evicted the image when it was stored.
+    eviction_count: uint,
     cached_size: Size2D<int>,
     local_image_cache: Arc<Mutex<LocalImageCache>>,
 }
@@ -31,6 +34,7 @@ impl ImageHolder {
         let holder = ImageHolder {
             url: url,
             image: None,
+            eviction_count: 0,
             cached_size: Size2D(0,0),
             local_image_cache: local_image_cache.clone(),
         };
@@ -77,25 +81,39 @@ impl ImageHolder {
     pub fn get_image(&mut self) -> Option<Arc<Box<Image>>> {
         debug!("get_image() {}", self.url.serialize());
 
-        // If this is the first time we've called this function, load
-        // the image and store it for the future
-        if self.image.is_none() {
-            let port = {
-                let val = self.local_image_cache.lock();
-                let mut local_image_cache = val;
-                local_image_cache.get_image(&self.url)
-            };
-            match port.recv() {
+        let port = {
+            let val = self.local_image_cache.lock();
+            let mut local_image_cache = val;
+
+            // Let go of the image once the image cache has evicted it, so
+            // that it can be freed, and load it again.
+            let eviction_count = local_image_cache.eviction_count(&self.url);
+            if eviction_count != self.eviction_count {
+                self.image = None;
+                self.eviction_count = eviction_count;
+            }
+
+            // If this is the first time we've called this function, load
+            // the image and store it for the future
+            if self.image.is_none() {
+                Some(local_image_cache.get_image(&self.url))
+            } else {
+                None
+            }
+        };
+        match port {
+            Some(port) => match port.recv() {
                 ImageReady(image) => {
                     self.image = Some(image);
                 }
-                ImageNotReady => {
+                ImageNotReady | ImageEvicted => {
                     debug!("image not ready for {:s}", self.url.serialize());
                 }
                 ImageFailed => {
                     debug!("image decoding failed for {:s}", self.url.serialize());
                 }
-            }
+            },
+            None => (),
         }
 
         // Clone isn't pure so we have to swap out the mutable image option
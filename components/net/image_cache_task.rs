@@ -6,9 +6,10 @@ use image::base::{Image, load_from_memory};
 use resource_task;
 use resource_task::{LoadData, ResourceTask};
 
+use png;
 use std::comm::{channel, Receiver, Sender};
 use std::collections::hashmap::HashMap;
-use std::mem::replace;
+use std::mem::{replace, size_of};
 use std::task::spawn;
 use std::result;
 use sync::{Arc, Mutex};
@@ -30,6 +31,22 @@ pub enum Msg {
     /// Wait for an image to become available (or fail to load).
     WaitForImage(Url, Sender<ImageResponseMsg>),
 
+    /// Drop the data of an image that is no longer needed. Later requests for
+    /// the image are answered with ImageEvicted until it is prefetched again.
+    Evict(Url),
+
+    /// Send the URL of every image that is evicted from now on to the given
+    /// channel, so that whoever keeps the image can let go of it too.
+    NotifyEvictions(Sender<Url>),
+
+    /// Request the number of times the image for a URL was fetched from the
+    /// resource task.
+    GetFetchCount(Url, Sender<uint>),
+
+    /// Request the number of bytes taken up by the data of the images in the
+    /// cache, decoded or not.
+    GetHeapSize(Sender<uint>),
+
     /// Clients must wait for a response before shutting down the ResourceTask
     Exit(Sender<()>),
 
@@ -50,7 +67,10 @@ pub enum Msg {
 pub enum ImageResponseMsg {
     ImageReady(Arc<Box<Image>>),
     ImageNotReady,
-    ImageFailed
+    ImageFailed,
+    /// The image was dropped from the cache and has to be prefetched and
+    /// decoded again.
+    ImageEvicted
 }
 
 impl PartialEq for ImageResponseMsg {
@@ -59,8 +79,10 @@ impl PartialEq for ImageResponseMsg {
             (&ImageReady(..), &ImageReady(..)) => fail!("unimplemented comparison"),
             (&ImageNotReady, &ImageNotReady) => true,
             (&ImageFailed, &ImageFailed) => true,
+            (&ImageEvicted, &ImageEvicted) => true,
 
-            (&ImageReady(..), _) | (&ImageNotReady, _) | (&ImageFailed, _) => false
+            (&ImageReady(..), _) | (&ImageNotReady, _) | (&ImageFailed, _) |
+            (&ImageEvicted, _) => false
         }
     }
 }
@@ -90,6 +112,8 @@ impl ImageCacheTask {
                 chan: chan_clone,
                 state_map: HashMap::new(),
                 wait_map: HashMap::new(),
+                eviction_listeners: vec!(),
+                fetch_counts: HashMap::new(),
                 need_exit: None
             };
             cache.run();
@@ -139,6 +163,10 @@ struct ImageCache {
     state_map: HashMap<Url, ImageState>,
     /// List of clients waiting on a WaitForImage response
     wait_map: HashMap<Url, Arc<Mutex<Vec<Sender<ImageResponseMsg>>>>>,
+    /// The channels to send the URL of an evicted image to
+    eviction_listeners: Vec<Sender<Url>>,
+    /// The number of times the image for a URL was fetched
+    fetch_counts: HashMap<Url, uint>,
     need_exit: Option<Sender<()>>,
 }
 
@@ -149,7 +177,8 @@ enum ImageState {
     Prefetched(Vec<u8>),
     Decoding,
     Decoded(Arc<Box<Image>>),
-    Failed
+    Failed,
+    Evicted
 }
 
 #[deriving(Clone)]
@@ -191,6 +220,12 @@ impl ImageCache {
                 WaitForImage(url, response) => {
                     self.wait_for_image(url, response)
                 }
+                Evict(url) => self.evict(url),
+                NotifyEvictions(listener) => self.eviction_listeners.push(listener),
+                GetFetchCount(url, response) => {
+                    response.send(self.fetch_counts.find(&url).map_or(0, |count| *count))
+                }
+                GetHeapSize(response) => response.send(self.heap_size()),
                 WaitForStore(chan) => store_chan = Some(chan),
                 WaitForStorePrefetched(chan) => store_prefetched_chan = Some(chan),
                 Exit(response) => {
@@ -211,7 +246,7 @@ impl ImageCache {
                         Prefetching(..) => can_exit = false,
                         Decoding => can_exit = false,
 
-                        Init | Prefetched(..) | Decoded(..) | Failed => ()
+                        Init | Prefetched(..) | Decoded(..) | Failed | Evicted => ()
                     }
                 }
 
@@ -240,7 +275,7 @@ impl ImageCache {
 
     fn prefetch(&mut self, url: Url) {
         match self.get_state(url.clone()) {
-            Init => {
+            Init | Evicted => {
                 let to_cache = self.chan.clone();
                 let resource_task = self.resource_task.clone();
                 let url_clone = url.clone();
@@ -260,6 +295,7 @@ impl ImageCache {
                     debug!("image_cache_task: ended fetch for {:s}", url.serialize());
                 });
 
+                *self.fetch_counts.find_or_insert(url.clone(), 0) += 1;
                 self.set_state(url, Prefetching(DoNotDecode));
             }
 
@@ -291,7 +327,8 @@ impl ImageCache {
           | Prefetched(..)
           | Decoding
           | Decoded(..)
-          | Failed => {
+          | Failed
+          | Evicted => {
             fail!("wrong state for storing prefetched image")
           }
         }
@@ -299,7 +336,7 @@ impl ImageCache {
 
     fn decode(&mut self, url: Url) {
         match self.get_state(url.clone()) {
-            Init => fail!("decoding image before prefetch"),
+            Init | Evicted => fail!("decoding image before prefetch"),
 
             Prefetching(DoNotDecode) => {
                 // We don't have the data yet, queue up the decode
@@ -356,7 +393,8 @@ impl ImageCache {
           | Prefetching(..)
           | Prefetched(..)
           | Decoded(..)
-          | Failed => {
+          | Failed
+          | Evicted => {
             fail!("incorrect state in store_image")
           }
         }
@@ -383,6 +421,7 @@ impl ImageCache {
             Decoding => response.send(ImageNotReady),
             Decoded(image) => response.send(ImageReady(image.clone())),
             Failed => response.send(ImageFailed),
+            Evicted => response.send(ImageEvicted),
         }
     }
 
@@ -413,9 +452,44 @@ impl ImageCache {
             Failed => {
                 response.send(ImageFailed);
             }
+
+            Evicted => {
+                response.send(ImageEvicted);
+            }
         }
     }
 
+    fn heap_size(&self) -> uint {
+        self.state_map.values().fold(0, |size, state| {
+            size + match *state {
+                Prefetched(ref data) => data.capacity(),
+                Decoded(ref image) => {
+                    let pixels = match image.pixels {
+                        png::K8(ref data) | png::KA8(ref data) |
+                        png::RGB8(ref data) | png::RGBA8(ref data) => data.capacity(),
+                    };
+                    size_of::<Image>() + pixels
+                }
+                Init | Prefetching(..) | Decoding | Failed | Evicted => 0,
+            }
+        })
+    }
+
+    fn evict(&mut self, url: Url) {
+        match self.get_state(url.clone()) {
+            Prefetched(..) | Decoded(..) | Failed => {
+                self.set_state(url.clone(), Evicted);
+                // Forget the listeners that went away.
+                self.eviction_listeners.retain(|listener| listener.send_opt(url.clone()).is_ok());
+            }
+
+            Init | Evicted => (),
+
+            Prefetching(..) | Decoding => {
+                // Somebody is still waiting for this image
+            }
+        }
+    }
 }
 
 
@@ -520,6 +594,17 @@ mod tests {
         }
     }
 
+    struct NotifyAndSendTestImage {
+        url_requested_chan: Sender<()>,
+    }
+    impl Closure for NotifyAndSendTestImage {
+        fn invoke(&self, response: Sender<resource_task::ProgressMsg>) {
+            self.url_requested_chan.send(());
+            response.send(resource_task::Payload(test_image_bin()));
+            response.send(resource_task::Done(Ok(())));
+        }
+    }
+
     struct SendBogusImage;
     impl Closure for SendBogusImage {
         fn invoke(&self, response: Sender<resource_task::ProgressMsg>) {
@@ -571,7 +656,8 @@ mod tests {
                             Url::parse("file:///fake").unwrap()));
                         on_load.invoke(chan);
                     }
-                    resource_task::Exit => break
+                    resource_task::Exit => break,
+                    _ => {}
                 }
             }
         })
@@ -970,6 +1056,136 @@ mod tests {
         mock_resource_task.send(resource_task::Exit);
     }
 
+    #[test]
+    fn should_return_image_evicted_for_evicted_image() {
+        let mock_resource_task = mock_resource_task(box SendTestImage);
+
+        let image_cache_task = ImageCacheTask::new(mock_resource_task.clone());
+        let url = Url::parse("file:///").unwrap();
+
+        let join_port = image_cache_task.wait_for_store();
+
+        image_cache_task.send(Prefetch(url.clone()));
+        image_cache_task.send(Decode(url.clone()));
+
+        // Wait until our mock resource task has sent the image to the image cache
+        join_port.recv();
+
+        image_cache_task.send(Evict(url.clone()));
+
+        let (response_chan, response_port) = comm::channel();
+        image_cache_task.send(GetImage(url.clone(), response_chan));
+        assert!(response_port.recv() == ImageEvicted);
+
+        let (response_chan, response_port) = comm::channel();
+        image_cache_task.send(WaitForImage(url, response_chan));
+        assert!(response_port.recv() == ImageEvicted);
+
+        image_cache_task.exit();
+        mock_resource_task.send(resource_task::Exit);
+    }
+
+    #[test]
+    fn should_request_url_from_resource_task_again_after_eviction() {
+        let (url_requested_chan, url_requested) = comm::channel();
+
+        let mock_resource_task = mock_resource_task(box NotifyAndSendTestImage {
+            url_requested_chan: url_requested_chan
+        });
+
+        let image_cache_task = ImageCacheTask::new(mock_resource_task.clone());
+        let url = Url::parse("file:///").unwrap();
+
+        let join_port = image_cache_task.wait_for_store();
+
+        image_cache_task.send(Prefetch(url.clone()));
+        image_cache_task.send(Decode(url.clone()));
+        url_requested.recv();
+        join_port.recv();
+
+        image_cache_task.send(Evict(url.clone()));
+        image_cache_task.send(Prefetch(url.clone()));
+        image_cache_task.send(Decode(url.clone()));
+        url_requested.recv();
+
+        let (response_chan, response_port) = comm::channel();
+        image_cache_task.send(WaitForImage(url, response_chan));
+        match response_port.recv() {
+          ImageReady(..) => (),
+          _ => fail!("bleh")
+        }
+
+        image_cache_task.exit();
+        mock_resource_task.send(resource_task::Exit);
+        match url_requested.try_recv() {
+            Err(_) => (),
+            Ok(_) => fail!(),
+        };
+    }
+
+    #[test]
+    fn should_notify_evictions_and_count_fetches() {
+        let mock_resource_task = mock_resource_task(box SendTestImage);
+
+        let image_cache_task = ImageCacheTask::new(mock_resource_task.clone());
+        let url = Url::parse("file:///").unwrap();
+
+        fn fetch_count(image_cache_task: &ImageCacheTask, url: &Url) -> uint {
+            let (response_chan, response_port) = comm::channel();
+            image_cache_task.send(GetFetchCount(url.clone(), response_chan));
+            response_port.recv()
+        }
+
+        let (eviction_chan, eviction_port) = comm::channel();
+        image_cache_task.send(NotifyEvictions(eviction_chan));
+
+        let join_port = image_cache_task.wait_for_store();
+        image_cache_task.send(Prefetch(url.clone()));
+        image_cache_task.send(Prefetch(url.clone()));
+        image_cache_task.send(Decode(url.clone()));
+        join_port.recv();
+        assert_eq!(fetch_count(&image_cache_task, &url), 1);
+
+        image_cache_task.send(Evict(url.clone()));
+        assert!(eviction_port.recv() == url);
+
+        let join_port = image_cache_task.wait_for_store();
+        image_cache_task.send(Prefetch(url.clone()));
+        image_cache_task.send(Decode(url.clone()));
+        join_port.recv();
+        assert_eq!(fetch_count(&image_cache_task, &url), 2);
+
+        image_cache_task.exit();
+        mock_resource_task.send(resource_task::Exit);
+    }
+
+    #[test]
+    fn should_report_the_size_of_image_data_until_it_is_evicted() {
+        let mock_resource_task = mock_resource_task(box SendTestImage);
+
+        let image_cache_task = ImageCacheTask::new(mock_resource_task.clone());
+        let url = Url::parse("file:///").unwrap();
+
+        fn heap_size(image_cache_task: &ImageCacheTask) -> uint {
+            let (response_chan, response_port) = comm::channel();
+            image_cache_task.send(GetHeapSize(response_chan));
+            response_port.recv()
+        }
+        assert_eq!(heap_size(&image_cache_task), 0);
+
+        let join_port = image_cache_task.wait_for_store();
+        image_cache_task.send(Prefetch(url.clone()));
+        image_cache_task.send(Decode(url.clone()));
+        join_port.recv();
+        assert!(heap_size(&image_cache_task) > 0);
+
+        image_cache_task.send(Evict(url));
+        assert_eq!(heap_size(&image_cache_task), 0);
+
+        image_cache_task.exit();
+        mock_resource_task.send(resource_task::Exit);
+    }
+
     #[test]
     fn sync_cache_should_wait_for_images() {
         let mock_resource_task = mock_resource_task(box SendTestImage);
@@ -13,6 +13,7 @@ extern crate http;
 extern crate png;
 #[phase(plugin, link)]
 extern crate log;
+extern crate openssl;
 extern crate serialize;
 extern crate "util" as servo_util;
 extern crate stb_image;
@@ -31,12 +32,18 @@ pub mod image {
 }
 
 pub mod about_loader;
+pub mod blob_loader;
 pub mod file_loader;
+pub mod http_cache;
 pub mod http_loader;
+pub mod hyperlink_auditing;
 pub mod data_loader;
+pub mod event_source_task;
 pub mod image_cache_task;
 pub mod local_image_cache;
+pub mod referrer_policy;
 pub mod resource_task;
+pub mod websocket_task;
 
 /// An implementation of the [Fetch spec](http://fetch.spec.whatwg.org/)
 pub mod fetch {
@@ -8,8 +8,8 @@ extra message traffic, it also avoids waiting on the same image
 multiple times and thus triggering reflows multiple times.
 */
 
-use image_cache_task::{Decode, GetImage, ImageCacheTask, ImageFailed, ImageNotReady, ImageReady};
-use image_cache_task::{ImageResponseMsg, Prefetch, WaitForImage};
+use image_cache_task::{Decode, GetImage, ImageCacheTask, ImageEvicted, ImageFailed, ImageNotReady};
+use image_cache_task::{ImageReady, ImageResponseMsg, NotifyEvictions, Prefetch, WaitForImage};
 
 use std::comm::{Receiver, channel};
 use std::collections::hashmap::HashMap;
@@ -24,16 +24,21 @@ pub struct LocalImageCache {
     image_cache_task: ImageCacheTask,
     round_number: uint,
     on_image_available: Option<Box<ImageResponder+Send>>,
-    state_map: HashMap<Url, ImageState>
+    state_map: HashMap<Url, ImageState>,
+    /// The URLs of the images that the remote cache evicted
+    evictions: Receiver<Url>,
 }
 
 impl LocalImageCache {
     pub fn new(image_cache_task: ImageCacheTask) -> LocalImageCache {
+        let (eviction_chan, eviction_port) = channel();
+        image_cache_task.send(NotifyEvictions(eviction_chan));
         LocalImageCache {
             image_cache_task: image_cache_task,
             round_number: 1,
             on_image_available: None,
-            state_map: HashMap::new()
+            state_map: HashMap::new(),
+            evictions: eviction_port,
         }
     }
 }
@@ -43,7 +48,10 @@ struct ImageState {
     prefetched: bool,
     decoded: bool,
     last_request_round: uint,
-    last_response: ImageResponseMsg
+    last_response: ImageResponseMsg,
+    /// The number of times the remote cache evicted the image, as of the
+    /// start of the current round
+    eviction_count: uint,
 }
 
 impl LocalImageCache {
@@ -52,6 +60,19 @@ impl LocalImageCache {
     pub fn next_round(&mut self, on_image_available: Box<ImageResponder+Send>) {
         self.round_number += 1;
         self.on_image_available = Some(on_image_available);
+        loop {
+            match self.evictions.try_recv() {
+                Ok(url) => self.get_state(&url).eviction_count += 1,
+                Err(_) => break,
+            }
+        }
+    }
+
+    /// The number of times the remote cache evicted the image, as of the
+    /// start of the current round. Whoever keeps the image has to let go of it
+    /// once this changes, and request it again.
+    pub fn eviction_count(&mut self, url: &Url) -> uint {
+        self.get_state(url).eviction_count
     }
 
     pub fn prefetch(&mut self, url: &Url) {
@@ -92,11 +113,16 @@ impl LocalImageCache {
 
             match state.last_response {
                 ImageReady(ref image) => {
-                    let (chan, port) = channel();
-                    chan.send(ImageReady(image.clone()));
-                    return port;
+                    if last_round == round_number {
+                        let (chan, port) = channel();
+                        chan.send(ImageReady(image.clone()));
+                        return port;
+                    } else {
+                        // The remote cache may have evicted the image since
+                        // the last round
+                    }
                 }
-                ImageNotReady => {
+                ImageNotReady | ImageEvicted => {
                     if last_round == round_number {
                         let (chan, port) = channel();
                         chan.send(ImageNotReady);
@@ -117,7 +143,21 @@ impl LocalImageCache {
         let (response_chan, response_port) = channel();
         self.image_cache_task.send(GetImage((*url).clone(), response_chan));
 
-        let response = response_port.recv();
+        let response = match response_port.recv() {
+            ImageEvicted => {
+                // Start loading the image again, and treat it like any other
+                // image that isn't available yet
+                {
+                    let state = self.get_state(url);
+                    state.prefetched = false;
+                    state.decoded = false;
+                }
+                self.prefetch(url);
+                self.decode(url);
+                ImageNotReady
+            }
+            response => response
+        };
         match response {
             ImageNotReady => {
                 // Need to reflow when the image is available
@@ -142,7 +182,8 @@ impl LocalImageCache {
         let response_copy = match response {
             ImageReady(ref image) => ImageReady(image.clone()),
             ImageNotReady => ImageNotReady,
-            ImageFailed => ImageFailed
+            ImageFailed => ImageFailed,
+            ImageEvicted => ImageEvicted
         };
         self.get_state(url).last_response = response_copy;
 
@@ -157,7 +198,8 @@ impl LocalImageCache {
                 prefetched: false,
                 decoded: false,
                 last_request_round: 0,
-                last_response: ImageNotReady
+                last_response: ImageNotReady,
+                eviction_count: 0,
             };
             new_state
         });
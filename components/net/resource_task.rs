@@ -5,12 +5,19 @@
 //! A task that takes a URL and streams back the binary data.
 
 use about_loader;
+use blob_loader;
+use blob_loader::{BlobURLEntry, BlobURLOwner, BlobURLStore};
 use data_loader;
 use file_loader;
+use http_cache::{HttpCache, DEFAULT_MAX_SIZE};
 use http_loader;
 
+use servo_util::task::spawn_named;
+
+use std::collections::hashmap::HashMap;
 use std::comm::{channel, Receiver, Sender};
 use std::task::TaskBuilder;
+use sync::{Arc, Mutex};
 use http::headers::content_type::MediaType;
 use http::headers::response::HeaderCollection as ResponseHeaderCollection;
 use http::headers::request::HeaderCollection as RequestHeaderCollection;
@@ -24,6 +31,15 @@ use http::status::Status;
 pub enum ControlMsg {
     /// Request the data associated with a particular URL
     Load(LoadData, Sender<LoadResponse>),
+    /// Request the resource at a URL, discarding the response, e.g. to
+    /// notify a server rather than to use what it returns
+    LoadAndForget(LoadData),
+    /// Make a `blob:` URL refer to a blob until it is revoked
+    RegisterBlobURL(Url, BlobURLEntry),
+    /// Make a `blob:` URL refer to nothing
+    RevokeBlobURL(Url),
+    /// Make the `blob:` URLs of a global that went away refer to nothing
+    RevokeBlobURLsOf(BlobURLOwner),
     Exit
 }
 
@@ -57,6 +73,7 @@ pub struct ResourceCORSData {
 }
 
 /// Metadata about a loaded resource, such as is obtained from HTTP headers.
+#[deriving(Clone)]
 pub struct Metadata {
     /// Final URL after redirects.
     pub final_url: Url,
@@ -175,6 +192,9 @@ pub fn new_resource_task() -> ResourceTask {
 
 struct ResourceManager {
     from_client: Receiver<ControlMsg>,
+    blob_url_store: BlobURLStore,
+    /// The responses to HTTP requests, shared with the loads in progress.
+    http_cache: Arc<Mutex<HttpCache>>,
 }
 
 
@@ -182,18 +202,32 @@ impl ResourceManager {
     fn new(from_client: Receiver<ControlMsg>) -> ResourceManager {
         ResourceManager {
             from_client : from_client,
+            blob_url_store: HashMap::new(),
+            http_cache: Arc::new(Mutex::new(HttpCache::new(DEFAULT_MAX_SIZE))),
         }
     }
 }
 
 
 impl ResourceManager {
-    fn start(&self) {
+    fn start(&mut self) {
         loop {
             match self.from_client.recv() {
               Load(load_data, start_chan) => {
                 self.load(load_data, start_chan)
               }
+              LoadAndForget(load_data) => {
+                self.load_and_forget(load_data)
+              }
+              RegisterBlobURL(url, entry) => {
+                self.blob_url_store.insert(url.serialize_no_fragment(), entry);
+              }
+              RevokeBlobURL(url) => {
+                self.blob_url_store.remove(&url.serialize_no_fragment());
+              }
+              RevokeBlobURLsOf(owner) => {
+                blob_loader::revoke_owned_by(&mut self.blob_url_store, owner);
+              }
               Exit => {
                 break
               }
@@ -204,9 +238,16 @@ impl ResourceManager {
     fn load(&self, load_data: LoadData, start_chan: Sender<LoadResponse>) {
         let loader = match load_data.url.scheme.as_slice() {
             "file" => file_loader::factory,
-            "http" | "https" => http_loader::factory,
             "data" => data_loader::factory,
             "about" => about_loader::factory,
+            "http" | "https" => {
+                http_loader::factory(load_data, start_chan, self.http_cache.clone());
+                return
+            }
+            "blob" => {
+                blob_loader::load(load_data, start_chan, &self.blob_url_store);
+                return
+            }
             _ => {
                 debug!("resource_task: no loader for scheme {:s}", load_data.url.scheme);
                 start_sending(start_chan, Metadata::default(load_data.url))
@@ -217,6 +258,24 @@ impl ResourceManager {
         debug!("resource_task: loading url: {:s}", load_data.url.serialize());
         loader(load_data, start_chan);
     }
+
+    fn load_and_forget(&self, load_data: LoadData) {
+        // The loaders fail if nobody listens, so drain the response.
+        let (start_chan, start_port) = channel();
+        self.load(load_data, start_chan);
+        spawn_named("LoadAndForget", proc() {
+            let response: LoadResponse = match start_port.recv_opt() {
+                Ok(response) => response,
+                Err(()) => return,
+            };
+            loop {
+                match response.progress_port.recv_opt() {
+                    Ok(Payload(_)) => (),
+                    Ok(Done(_)) | Err(()) => break,
+                }
+            }
+        });
+    }
 }
 
 /// Load a URL asynchronously and iterate over chunks of bytes from the response.
@@ -266,3 +325,50 @@ fn test_bad_scheme() {
     }
     resource_task.send(Exit);
 }
+
+#[test]
+fn test_blob_url() {
+    let resource_task = new_resource_task();
+    let url = Url::parse("blob:http://example.com/1234").unwrap();
+    let owner = BlobURLOwner::new();
+    let entry = BlobURLEntry { type_: "".to_string(), bytes: vec!(1, 2, 3), owner: owner.clone() };
+    resource_task.send(RegisterBlobURL(url.clone(), entry.clone()));
+    match load_whole_resource(&resource_task, url.clone()) {
+        Ok((_, bytes)) => assert_eq!(bytes, vec!(1, 2, 3)),
+        Err(_) => fail!("the registered blob URL failed to load"),
+    }
+
+    resource_task.send(RevokeBlobURL(url.clone()));
+    assert!(load_whole_resource(&resource_task, url.clone()).is_err());
+
+    // The URLs of a global are gone with it.
+    resource_task.send(RegisterBlobURL(url.clone(), entry));
+    resource_task.send(RevokeBlobURLsOf(BlobURLOwner::new()));
+    assert!(load_whole_resource(&resource_task, url.clone()).is_ok());
+    resource_task.send(RevokeBlobURLsOf(owner));
+    assert!(load_whole_resource(&resource_task, url).is_err());
+    resource_task.send(Exit);
+}
+
+#[test]
+fn test_load_and_forget() {
+    let resource_task = new_resource_task();
+    let url = Url::parse("bogus://whatever").unwrap();
+    resource_task.send(LoadAndForget(LoadData::new(url)));
+
+    // The resource task keeps serving requests.
+    let (start_chan, start) = channel();
+    let url = Url::parse("about:blank").unwrap();
+    resource_task.send(Load(LoadData::new(url), start_chan));
+    let response = start.recv();
+    loop {
+        match response.progress_port.recv() {
+            Payload(_) => (),
+            Done(result) => {
+                assert!(result.is_ok());
+                break
+            }
+        }
+    }
+    resource_task.send(Exit);
+}
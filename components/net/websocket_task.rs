@@ -0,0 +1,805 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! A task that opens a WebSocket connection and exchanges messages on it.
+//! http://tools.ietf.org/html/rfc6455
+
+use servo_util::task::spawn_named;
+
+use openssl::crypto::hash::{SHA1, hash};
+
+use serialize::base64::{ToBase64, STANDARD};
+use std::ascii::StrAsciiExt;
+use std::comm::{channel, Receiver, Select, Sender};
+use std::io::{BufferedReader, IoError, IoResult, TcpStream};
+use std::rand::random;
+use std::str;
+use url::Url;
+
+/// What the script task asks of a connection.
+pub enum WebSocketCommand {
+    /// Send a text message
+    SendText(String),
+    /// Send a binary message
+    SendBinary(Vec<u8>),
+    /// Start the closing handshake, with an optional code and a reason
+    Close(Option<u16>, String),
+    /// Drop the connection without a closing handshake
+    Fail,
+}
+
+/// What a connection tells the script task.
+#[deriving(PartialEq, Show)]
+pub enum WebSocketEvent {
+    /// The opening handshake succeeded, with the subprotocol the server
+    /// selected, which may be empty
+    Opened(String),
+    /// A text message was received
+    TextMessage(String),
+    /// A binary message was received
+    BinaryMessage(Vec<u8>),
+    /// This many bytes of application data were written to the connection
+    Sent(uint),
+    /// The server started the closing handshake
+    Closing,
+    /// The connection failed, and is about to be closed
+    Failed,
+    /// The connection is closed, cleanly or not, with a code and a reason
+    Closed(bool, u16, String),
+}
+
+/// The status code of a connection closed without a close frame.
+pub static ABNORMAL_CLOSURE: u16 = 1006;
+/// The status code reported when a close frame carries no code.
+static NO_STATUS_RECEIVED: u16 = 1005;
+static PROTOCOL_ERROR: u16 = 1002;
+static INVALID_PAYLOAD: u16 = 1007;
+static MESSAGE_TOO_BIG: u16 = 1009;
+
+/// The largest message, and so frame, the client accepts, so that a server
+/// can't make it allocate whatever length a frame declares.
+static MAX_MESSAGE_SIZE: u64 = 64 * 1024 * 1024;
+
+static CONTINUATION: u8 = 0x0;
+static TEXT: u8 = 0x1;
+static BINARY: u8 = 0x2;
+static CLOSE: u8 = 0x8;
+static PING: u8 = 0x9;
+static PONG: u8 = 0xA;
+
+/// Appended to the key of the opening handshake to compute the accept value.
+static ACCEPT_GUID: &'static str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Opens a connection to `url` in a new task, and returns the channel it
+/// takes commands from. Everything that happens on the connection is sent
+/// to `events`, the last message being `Closed`.
+pub fn connect(url: Url, origin: String, protocols: Vec<String>,
+               events: Sender<WebSocketEvent>) -> Sender<WebSocketCommand> {
+    let (commands_chan, commands_port) = channel();
+    spawn_named("WebSocket", proc() {
+        run(url, origin, protocols, commands_port, events)
+    });
+    commands_chan
+}
+
+fn run(url: Url, origin: String, protocols: Vec<String>,
+       commands: Receiver<WebSocketCommand>, events: Sender<WebSocketEvent>) {
+    let (stream, reader, protocol) = match open(&url, origin.as_slice(), protocols.as_slice()) {
+        Ok(opened) => opened,
+        Err(error) => {
+            debug!("WebSocket connection to {:s} failed: {:s}", url.serialize(), error);
+            let _ = events.send_opt(Failed);
+            let _ = events.send_opt(Closed(false, ABNORMAL_CLOSURE, "".to_string()));
+            return;
+        }
+    };
+    if events.send_opt(Opened(protocol)).is_err() {
+        return;
+    }
+
+    let (incoming_chan, incoming_port) = channel();
+    spawn_named("WebSocket:reader", proc() read_messages(reader, incoming_chan));
+
+    let mut connection = Connection {
+        stream: stream,
+        events: events,
+        close_sent: false,
+    };
+    let select = Select::new();
+    let mut commands_handle = select.handle(&commands);
+    let mut incoming_handle = select.handle(&incoming_port);
+    unsafe {
+        commands_handle.add();
+        incoming_handle.add();
+    }
+    loop {
+        let ret = select.wait();
+        let done = if ret == commands_handle.id() {
+            match commands_handle.recv_opt() {
+                Ok(command) => connection.handle_command(command),
+                // Nobody can use the connection anymore.
+                Err(()) => {
+                    connection.shut_down();
+                    true
+                }
+            }
+        } else if ret == incoming_handle.id() {
+            match incoming_handle.recv_opt() {
+                Ok(incoming) => connection.handle_incoming(incoming),
+                Err(()) => connection.handle_incoming(Disconnected),
+            }
+        } else {
+            fail!("unexpected select result")
+        };
+        if done {
+            return;
+        }
+    }
+}
+
+/// Performs the opening handshake, and returns the connection, a reader
+/// for what follows the handshake and the selected subprotocol.
+/// http://tools.ietf.org/html/rfc6455#section-4.1
+fn open(url: &Url, origin: &str, protocols: &[String])
+        -> Result<(TcpStream, BufferedReader<TcpStream>, String), String> {
+    // FIXME: wss: needs TLS, which nothing outside of rust-http does yet.
+    if url.scheme.as_slice() != "ws" {
+        return Err(format!("{:s}: connections are not supported", url.scheme));
+    }
+    let host = match url.serialize_host() {
+        Some(host) => host,
+        None => return Err("no host".to_string()),
+    };
+    let port = url.port().unwrap_or(80);
+    let mut stream = try!(TcpStream::connect(host.as_slice(), port).map_err(describe));
+    let mut reader = BufferedReader::new(stream.clone());
+
+    let key = Vec::from_fn(16, |_| random::<u8>()).as_slice().to_base64(STANDARD);
+    let mut request = format!("GET {:s} HTTP/1.1\r\n", resource_name(url));
+    match url.port() {
+        Some(port) => request.push_str(format!("Host: {:s}:{:u}\r\n", host, port).as_slice()),
+        None => request.push_str(format!("Host: {:s}\r\n", host).as_slice()),
+    }
+    request.push_str("Upgrade: websocket\r\n");
+    request.push_str("Connection: Upgrade\r\n");
+    request.push_str(format!("Sec-WebSocket-Key: {:s}\r\n", key).as_slice());
+    request.push_str(format!("Origin: {:s}\r\n", origin).as_slice());
+    if !protocols.is_empty() {
+        request.push_str(format!("Sec-WebSocket-Protocol: {:s}\r\n",
+                                 protocols.connect(", ")).as_slice());
+    }
+    request.push_str("Sec-WebSocket-Version: 13\r\n\r\n");
+    try!(stream.write(request.as_bytes()).map_err(describe));
+
+    let status = try!(reader.read_line().map_err(describe));
+    let mut status = status.as_slice().split(' ');
+    if status.next() != Some("HTTP/1.1") || status.next() != Some("101") {
+        return Err("the server didn't switch protocols".to_string());
+    }
+    let mut headers = vec!();
+    loop {
+        let line = try!(reader.read_line().map_err(describe));
+        let line = line.as_slice().trim_right_chars(['\r', '\n'].as_slice());
+        if line.is_empty() {
+            break;
+        }
+        match line.find(':') {
+            Some(index) => headers.push((line.slice_to(index).trim().to_ascii_lower(),
+                                         line.slice_from(index + 1).trim().to_string())),
+            None => return Err("malformed header".to_string()),
+        }
+    }
+    let header = |name| find_header(headers.as_slice(), name);
+
+    match header("upgrade") {
+        Some(value) if value.eq_ignore_ascii_case("websocket") => {},
+        _ => return Err("missing Upgrade header".to_string()),
+    }
+    match header("connection") {
+        Some(value) if value.split(',').any(|token| {
+            token.trim().eq_ignore_ascii_case("upgrade")
+        }) => {},
+        _ => return Err("missing Connection header".to_string()),
+    }
+    if header("sec-websocket-accept") != Some(accept_key(key.as_slice()).as_slice()) {
+        return Err("wrong Sec-WebSocket-Accept header".to_string());
+    }
+    // No extensions were requested, so the server can't use any.
+    match header("sec-websocket-extensions") {
+        Some(value) if !value.is_empty() => return Err("unexpected extensions".to_string()),
+        _ => {}
+    }
+    let protocol = match header("sec-websocket-protocol") {
+        None => "".to_string(),
+        Some(protocol) if protocols.iter().any(|p| p.as_slice() == protocol) => {
+            protocol.to_string()
+        },
+        Some(_) => return Err("unexpected subprotocol".to_string()),
+    };
+    Ok((stream, reader, protocol))
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers.iter().find(|&&(ref header, _)| header.as_slice() == name)
+                  .map(|&(_, ref value)| value.as_slice())
+}
+
+fn describe(error: IoError) -> String {
+    error.desc.to_string()
+}
+
+/// The path and query of `url`, which the handshake requests.
+fn resource_name(url: &Url) -> String {
+    let mut name = url.serialize_path().unwrap_or("/".to_string());
+    match url.query {
+        Some(ref query) => {
+            name.push('?');
+            name.push_str(query.as_slice());
+        },
+        None => {}
+    }
+    name
+}
+
+/// The value of the Sec-WebSocket-Accept header the server must answer
+/// `key` with.
+fn accept_key(key: &str) -> String {
+    let mut input = key.to_string();
+    input.push_str(ACCEPT_GUID);
+    hash(SHA1, input.as_bytes()).as_slice().to_base64(STANDARD)
+}
+
+/// A connection whose opening handshake succeeded.
+struct Connection {
+    stream: TcpStream,
+    events: Sender<WebSocketEvent>,
+    /// Whether a close frame has been sent.
+    close_sent: bool,
+}
+
+impl Connection {
+    /// Handles a command, and returns whether the connection is closed.
+    fn handle_command(&mut self, command: WebSocketCommand) -> bool {
+        match command {
+            SendText(_) | SendBinary(_) if self.close_sent => false,
+            SendText(text) => self.send_data(TEXT, text.into_bytes()),
+            SendBinary(bytes) => self.send_data(BINARY, bytes),
+            Close(..) if self.close_sent => false,
+            Close(code, reason) => {
+                let mut payload = vec!();
+                match code {
+                    Some(code) => {
+                        payload.push((code >> 8) as u8);
+                        payload.push(code as u8);
+                        payload.push_all(reason.as_bytes());
+                    },
+                    None => {}
+                }
+                self.send_close(payload)
+            },
+            Fail => self.fail(None),
+        }
+    }
+
+    /// Handles what the reader task got, and returns whether the connection
+    /// is closed.
+    fn handle_incoming(&mut self, incoming: Incoming) -> bool {
+        match incoming {
+            TextReceived(text) => self.notify(TextMessage(text)),
+            BinaryReceived(bytes) => self.notify(BinaryMessage(bytes)),
+            PingReceived(_) if self.close_sent => false,
+            PingReceived(payload) => {
+                match self.write(PONG, payload.as_slice()) {
+                    Ok(()) => false,
+                    Err(_) => self.fail(None),
+                }
+            },
+            // Nothing is waiting for pongs, since no pings are sent.
+            PongReceived => false,
+            // http://tools.ietf.org/html/rfc6455#section-5.5.1
+            CloseReceived(code, reason) => {
+                if !self.close_sent {
+                    self.notify(Closing);
+                    // Echo the code of the server.
+                    let payload = match code {
+                        Some(code) => vec!((code >> 8) as u8, code as u8),
+                        None => vec!(),
+                    };
+                    let _ = self.write(CLOSE, payload.as_slice());
+                }
+                self.shut_down();
+                self.notify(Closed(true, code.unwrap_or(NO_STATUS_RECEIVED), reason));
+                true
+            },
+            Invalid(code) => self.fail(Some(code)),
+            Disconnected => {
+                self.shut_down();
+                self.notify(Closed(false, ABNORMAL_CLOSURE, "".to_string()));
+                true
+            },
+        }
+    }
+
+    fn send_data(&mut self, opcode: u8, payload: Vec<u8>) -> bool {
+        match self.write(opcode, payload.as_slice()) {
+            Ok(()) => self.notify(Sent(payload.len())),
+            Err(_) => self.fail(None),
+        }
+    }
+
+    fn send_close(&mut self, payload: Vec<u8>) -> bool {
+        self.close_sent = true;
+        match self.write(CLOSE, payload.as_slice()) {
+            Ok(()) => false,
+            Err(_) => self.fail(None),
+        }
+    }
+
+    /// Writes a frame, masked as the frames of clients must be.
+    fn write(&mut self, opcode: u8, payload: &[u8]) -> IoResult<()> {
+        let mask = [random::<u8>(), random::<u8>(), random::<u8>(), random::<u8>()];
+        write_frame(&mut self.stream, true, opcode, payload, Some(mask))
+    }
+
+    /// Fails the connection, telling the server why if there is a reason.
+    /// http://tools.ietf.org/html/rfc6455#section-7.1.7
+    fn fail(&mut self, code: Option<u16>) -> bool {
+        match code {
+            Some(code) if !self.close_sent => {
+                let _ = self.write(CLOSE, [(code >> 8) as u8, code as u8].as_slice());
+            },
+            _ => {}
+        }
+        self.shut_down();
+        self.notify(Failed);
+        self.notify(Closed(false, ABNORMAL_CLOSURE, "".to_string()));
+        true
+    }
+
+    /// Closes the TCP connection, which also stops the reader task.
+    fn shut_down(&mut self) {
+        let _ = self.stream.close_write();
+        let _ = self.stream.close_read();
+    }
+
+    /// Sends an event to the script task, and returns whether it is gone.
+    fn notify(&mut self, event: WebSocketEvent) -> bool {
+        self.events.send_opt(event).is_err()
+    }
+}
+
+/// What the reader task sends the connection task.
+enum Incoming {
+    TextReceived(String),
+    BinaryReceived(Vec<u8>),
+    PingReceived(Vec<u8>),
+    PongReceived,
+    CloseReceived(Option<u16>, String),
+    /// The server broke the protocol, and the connection must fail with
+    /// this code
+    Invalid(u16),
+    /// The connection was closed, or broke
+    Disconnected,
+}
+
+/// Reads frames until the connection closes, and sends the connection task
+/// the messages they make up.
+/// http://tools.ietf.org/html/rfc6455#section-6.2
+fn read_messages<R: Reader>(mut reader: R, incoming: Sender<Incoming>) {
+    // The opcode and the payload of the fragments of a message so far.
+    let mut fragments: Option<(u8, Vec<u8>)> = None;
+    loop {
+        let frame = match read_frame(&mut reader, false) {
+            Ok(frame) => frame,
+            Err(ProtocolError) => {
+                let _ = incoming.send_opt(Invalid(PROTOCOL_ERROR));
+                return;
+            },
+            Err(TooBig) => {
+                let _ = incoming.send_opt(Invalid(MESSAGE_TOO_BIG));
+                return;
+            },
+            Err(IoFailed(_)) => {
+                let _ = incoming.send_opt(Disconnected);
+                return;
+            },
+        };
+
+        let message = if frame.opcode >= CLOSE {
+            match control_message(frame) {
+                Ok(message) => message,
+                Err(code) => Invalid(code),
+            }
+        } else {
+            // Data frames either start a message or continue one.
+            let (opcode, mut payload) = match (fragments.take(), frame.opcode) {
+                (None, CONTINUATION) | (Some(_), TEXT) | (Some(_), BINARY) => {
+                    let _ = incoming.send_opt(Invalid(PROTOCOL_ERROR));
+                    return;
+                },
+                (Some((opcode, payload)), _) => (opcode, payload),
+                (None, opcode) => (opcode, vec!()),
+            };
+            if (payload.len() + frame.payload.len()) as u64 > MAX_MESSAGE_SIZE {
+                let _ = incoming.send_opt(Invalid(MESSAGE_TOO_BIG));
+                return;
+            }
+            payload.push_all(frame.payload.as_slice());
+            if !frame.fin {
+                fragments = Some((opcode, payload));
+                continue;
+            }
+            match opcode {
+                TEXT => match String::from_utf8(payload) {
+                    Ok(text) => TextReceived(text),
+                    Err(_) => Invalid(INVALID_PAYLOAD),
+                },
+                BINARY => BinaryReceived(payload),
+                _ => Invalid(PROTOCOL_ERROR),
+            }
+        };
+
+        let stop = match message {
+            CloseReceived(..) | Invalid(_) => true,
+            _ => false,
+        };
+        if incoming.send_opt(message).is_err() || stop {
+            return;
+        }
+    }
+}
+
+/// Turns a control frame into what it means.
+/// http://tools.ietf.org/html/rfc6455#section-5.5
+fn control_message(frame: Frame) -> Result<Incoming, u16> {
+    if !frame.fin || frame.payload.len() > 125 {
+        return Err(PROTOCOL_ERROR);
+    }
+    match frame.opcode {
+        CLOSE => {
+            let payload = frame.payload.as_slice();
+            if payload.is_empty() {
+                return Ok(CloseReceived(None, "".to_string()));
+            }
+            if payload.len() == 1 {
+                return Err(PROTOCOL_ERROR);
+            }
+            let code = (payload[0] as u16 << 8) | payload[1] as u16;
+            if !is_valid_close_code(code) {
+                return Err(PROTOCOL_ERROR);
+            }
+            match str::from_utf8(payload.slice_from(2)) {
+                Some(reason) => Ok(CloseReceived(Some(code), reason.to_string())),
+                None => Err(INVALID_PAYLOAD),
+            }
+        },
+        PING => Ok(PingReceived(frame.payload)),
+        PONG => Ok(PongReceived),
+        _ => Err(PROTOCOL_ERROR),
+    }
+}
+
+/// Whether a close frame may carry `code`.
+/// http://tools.ietf.org/html/rfc6455#section-7.4
+fn is_valid_close_code(code: u16) -> bool {
+    match code {
+        1000 | 1001 | 1002 | 1003 | 1007 | 1008 | 1009 | 1010 | 1011 => true,
+        _ => code >= 3000 && code <= 4999,
+    }
+}
+
+/// The unit messages are sent in.
+/// http://tools.ietf.org/html/rfc6455#section-5.2
+struct Frame {
+    /// Whether this is the last frame of a message.
+    fin: bool,
+    opcode: u8,
+    /// The unmasked payload.
+    payload: Vec<u8>,
+}
+
+enum ReadError {
+    /// The connection broke or was closed
+    IoFailed(IoError),
+    /// The bytes read aren't a valid frame
+    ProtocolError,
+    /// The frame is larger than MAX_MESSAGE_SIZE
+    TooBig,
+}
+
+/// Reads a frame, which must be masked if `masked` is true, and mustn't be
+/// otherwise.
+fn read_frame<R: Reader>(reader: &mut R, masked: bool) -> Result<Frame, ReadError> {
+    let first = try!(reader.read_u8().map_err(IoFailed));
+    // No extensions were negotiated, so the reserved bits must be unset.
+    if first & 0x70 != 0 {
+        return Err(ProtocolError);
+    }
+    let second = try!(reader.read_u8().map_err(IoFailed));
+    if (second & 0x80 != 0) != masked {
+        return Err(ProtocolError);
+    }
+    let length = match second & 0x7F {
+        126 => try!(reader.read_be_u16().map_err(IoFailed)) as u64,
+        127 => try!(reader.read_be_u64().map_err(IoFailed)),
+        length => length as u64,
+    };
+    if length >> 63 != 0 {
+        return Err(ProtocolError);
+    }
+    if length > MAX_MESSAGE_SIZE {
+        return Err(TooBig);
+    }
+    let mask = if masked {
+        let mask = try!(reader.read_exact(4).map_err(IoFailed));
+        Some(mask)
+    } else {
+        None
+    };
+    let mut payload = try!(reader.read_exact(length as uint).map_err(IoFailed));
+    match mask {
+        Some(mask) => {
+            for (i, byte) in payload.iter_mut().enumerate() {
+                *byte ^= mask[i % 4];
+            }
+        },
+        None => {}
+    }
+    Ok(Frame {
+        fin: first & 0x80 != 0,
+        opcode: first & 0x0F,
+        payload: payload,
+    })
+}
+
+/// Writes a frame, masking it with `mask` if there is one.
+fn write_frame<W: Writer>(writer: &mut W, fin: bool, opcode: u8, payload: &[u8],
+                          mask: Option<[u8, ..4]>) -> IoResult<()> {
+    let fin_bit = if fin { 0x80 } else { 0 };
+    try!(writer.write_u8(fin_bit | opcode));
+    let mask_bit = if mask.is_some() { 0x80 } else { 0 };
+    let length = payload.len();
+    if length < 126 {
+        try!(writer.write_u8(mask_bit | length as u8));
+    } else if length <= 0xFFFF {
+        try!(writer.write_u8(mask_bit | 126));
+        try!(writer.write_be_u16(length as u16));
+    } else {
+        try!(writer.write_u8(mask_bit | 127));
+        try!(writer.write_be_u64(length as u64));
+    }
+    match mask {
+        Some(mask) => {
+            try!(writer.write(mask.as_slice()));
+            let masked: Vec<u8> = payload.iter().enumerate().map(|(i, &byte)| {
+                byte ^ mask[i % 4]
+            }).collect();
+            try!(writer.write(masked.as_slice()));
+        },
+        None => try!(writer.write(payload)),
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+fn assert_frame_round_trip(payload: Vec<u8>) {
+    use std::io::{BufReader, MemWriter};
+
+    let mut writer = MemWriter::new();
+    write_frame(&mut writer, false, BINARY, payload.as_slice(), Some([1, 2, 3, 4])).unwrap();
+    let bytes = writer.unwrap();
+    let mut reader = BufReader::new(bytes.as_slice());
+    let frame = match read_frame(&mut reader, true) {
+        Ok(frame) => frame,
+        Err(_) => fail!("invalid frame"),
+    };
+    assert!(!frame.fin);
+    assert_eq!(frame.opcode, BINARY);
+    assert_eq!(frame.payload, payload);
+}
+
+/// Accepts one connection on a loopback port, answers its opening handshake
+/// with `status` and the subprotocol `protocol`, and then leaves the
+/// connection to `server`. Returns the URL of the server.
+#[cfg(test)]
+fn serve(status: &'static str, protocol: Option<&'static str>,
+         server: proc(TcpStream, BufferedReader<TcpStream>): Send) -> Url {
+    use std::io::{Acceptor, Listener, TcpListener};
+
+    let mut listener = TcpListener::bind("127.0.0.1", 0).unwrap();
+    let port = listener.socket_name().unwrap().port;
+    let mut acceptor = listener.listen().unwrap();
+    spawn(proc() {
+        let mut stream = acceptor.accept().unwrap();
+        let mut reader = BufferedReader::new(stream.clone());
+        let mut key = None;
+        loop {
+            let line = reader.read_line().unwrap();
+            let line = line.as_slice().trim_right_chars(['\r', '\n'].as_slice());
+            if line.is_empty() {
+                break;
+            }
+            if line.to_ascii_lower().as_slice().starts_with("sec-websocket-key:") {
+                key = Some(line.slice_from("sec-websocket-key:".len()).trim().to_string());
+            }
+        }
+        let mut response = format!("HTTP/1.1 {:s}\r\n", status);
+        response.push_str("Upgrade: websocket\r\nConnection: Upgrade\r\n");
+        response.push_str(format!("Sec-WebSocket-Accept: {:s}\r\n",
+                                  accept_key(key.unwrap().as_slice())).as_slice());
+        match protocol {
+            Some(protocol) => {
+                response.push_str(format!("Sec-WebSocket-Protocol: {:s}\r\n", protocol).as_slice());
+            },
+            None => {}
+        }
+        response.push_str("\r\n");
+        stream.write(response.as_bytes()).unwrap();
+        server(stream, reader);
+    });
+    Url::parse(format!("ws://127.0.0.1:{:u}/echo", port).as_slice()).unwrap()
+}
+
+/// Sends back the messages it receives, and answers a few of them
+/// differently to exercise the client.
+#[cfg(test)]
+fn echo(mut stream: TcpStream, mut reader: BufferedReader<TcpStream>) {
+    loop {
+        let frame = match read_frame(&mut reader, true) {
+            Ok(frame) => frame,
+            Err(_) => return,
+        };
+        let payload = frame.payload.as_slice();
+        if frame.opcode == CLOSE {
+            write_frame(&mut stream, true, CLOSE, payload, None).unwrap();
+            return;
+        } else if payload == b"close please" {
+            write_frame(&mut stream, true, CLOSE, b"\x0f\xa0bye", None).unwrap();
+            let reply = read_frame(&mut reader, true).ok().unwrap();
+            assert_eq!(reply.opcode, CLOSE);
+            assert_eq!(reply.payload.as_slice(), b"\x0f\xa0");
+            return;
+        } else if payload == b"fragment" {
+            write_frame(&mut stream, false, TEXT, b"frag", None).unwrap();
+            write_frame(&mut stream, true, PING, b"ping", None).unwrap();
+            write_frame(&mut stream, true, CONTINUATION, b"mented", None).unwrap();
+            let pong = read_frame(&mut reader, true).ok().unwrap();
+            assert_eq!(pong.opcode, PONG);
+            assert_eq!(pong.payload.as_slice(), b"ping");
+        } else {
+            write_frame(&mut stream, true, frame.opcode, payload, None).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+fn connect_to_echo(protocols: Vec<String>, protocol: Option<&'static str>)
+                   -> (Sender<WebSocketCommand>, Receiver<WebSocketEvent>) {
+    let url = serve("101 Switching Protocols", protocol, proc(stream, reader) echo(stream, reader));
+    let (events_chan, events_port) = channel();
+    let commands = connect(url, "null".to_string(), protocols, events_chan);
+    assert_eq!(events_port.recv(), Opened(protocol.unwrap_or("").to_string()));
+    (commands, events_port)
+}
+
+#[test]
+fn accept_key_of_rfc_example() {
+    assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=".to_string());
+}
+
+#[test]
+fn frame_lengths() {
+    assert_frame_round_trip(vec!());
+    assert_frame_round_trip(Vec::from_elem(125, 0x61u8));
+    assert_frame_round_trip(Vec::from_elem(126, 0x62u8));
+    assert_frame_round_trip(Vec::from_elem(65536, 0x63u8));
+}
+
+#[test]
+fn unexpected_mask() {
+    use std::io::{BufReader, MemWriter};
+
+    let mut writer = MemWriter::new();
+    write_frame(&mut writer, true, TEXT, b"masked", Some([1, 2, 3, 4])).unwrap();
+    let bytes = writer.unwrap();
+    match read_frame(&mut BufReader::new(bytes.as_slice()), false) {
+        Err(ProtocolError) => {},
+        _ => fail!("a masked frame from a server was accepted"),
+    }
+}
+
+#[test]
+fn oversized_frame() {
+    use std::io::BufReader;
+
+    let header = [0x82u8, 127, 0x40, 0, 0, 0, 0, 0, 0, 0];
+    match read_frame(&mut BufReader::new(header.as_slice()), false) {
+        Err(TooBig) => {},
+        _ => fail!("a frame of 2^62 bytes was accepted"),
+    }
+}
+
+#[test]
+fn oversized_message_fails_connection() {
+    let url = serve("101 Switching Protocols", None, proc(mut stream, mut reader) {
+        // Declares a frame of 2^62 bytes without sending any of them.
+        stream.write([0x82u8, 127, 0x40, 0, 0, 0, 0, 0, 0, 0].as_slice()).unwrap();
+        let close = read_frame(&mut reader, true).ok().unwrap();
+        assert_eq!(close.opcode, CLOSE);
+        assert_eq!(close.payload.as_slice(), b"\x03\xf1");
+    });
+    let (events_chan, events_port) = channel();
+    let _commands = connect(url, "null".to_string(), vec!(), events_chan);
+    assert_eq!(events_port.recv(), Opened("".to_string()));
+    assert_eq!(events_port.recv(), Failed);
+    assert_eq!(events_port.recv(), Closed(false, ABNORMAL_CLOSURE, "".to_string()));
+}
+
+#[test]
+fn text_echo() {
+    let (commands, events) = connect_to_echo(vec!(), None);
+    commands.send(SendText("héllo".to_string()));
+    assert_eq!(events.recv(), Sent(6));
+    assert_eq!(events.recv(), TextMessage("héllo".to_string()));
+    commands.send(Close(Some(1000), "done".to_string()));
+    assert_eq!(events.recv(), Closed(true, 1000, "done".to_string()));
+}
+
+#[test]
+fn binary_echo() {
+    let (commands, events) = connect_to_echo(vec!(), None);
+    let bytes = Vec::from_fn(70000, |i| i as u8);
+    commands.send(SendBinary(bytes.clone()));
+    assert_eq!(events.recv(), Sent(70000));
+    assert_eq!(events.recv(), BinaryMessage(bytes));
+    commands.send(Close(None, "".to_string()));
+    assert_eq!(events.recv(), Closed(true, 1005, "".to_string()));
+}
+
+#[test]
+fn fragmented_message() {
+    let (commands, events) = connect_to_echo(vec!(), None);
+    commands.send(SendText("fragment".to_string()));
+    assert_eq!(events.recv(), Sent(8));
+    assert_eq!(events.recv(), TextMessage("fragmented".to_string()));
+    commands.send(Close(Some(1000), "".to_string()));
+    assert_eq!(events.recv(), Closed(true, 1000, "".to_string()));
+}
+
+#[test]
+fn server_close() {
+    let (commands, events) = connect_to_echo(vec!(), None);
+    commands.send(SendText("close please".to_string()));
+    assert_eq!(events.recv(), Sent(12));
+    assert_eq!(events.recv(), Closing);
+    assert_eq!(events.recv(), Closed(true, 4000, "bye".to_string()));
+}
+
+#[test]
+fn selected_protocol() {
+    let (commands, events) = connect_to_echo(vec!("chat".to_string(), "superchat".to_string()),
+                                             Some("superchat"));
+    commands.send(Fail);
+    assert_eq!(events.recv(), Failed);
+    assert_eq!(events.recv(), Closed(false, ABNORMAL_CLOSURE, "".to_string()));
+}
+
+#[test]
+fn unexpected_protocol() {
+    let url = serve("101 Switching Protocols", Some("chat"),
+                    proc(stream, reader) echo(stream, reader));
+    let (events_chan, events_port) = channel();
+    let _commands = connect(url, "null".to_string(), vec!(), events_chan);
+    assert_eq!(events_port.recv(), Failed);
+    assert_eq!(events_port.recv(), Closed(false, ABNORMAL_CLOSURE, "".to_string()));
+}
+
+#[test]
+fn refused_handshake() {
+    let url = serve("200 OK", None, proc(stream, reader) echo(stream, reader));
+    let (events_chan, events_port) = channel();
+    let _commands = connect(url, "null".to_string(), vec!(), events_chan);
+    assert_eq!(events_port.recv(), Failed);
+    assert_eq!(events_port.recv(), Closed(false, ABNORMAL_CLOSURE, "".to_string()));
+}
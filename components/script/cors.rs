@@ -26,6 +26,7 @@ use http::headers::content_type::MediaType;
 use http::headers::request::{Accept, AcceptLanguage, ContentLanguage, ContentType};
 use http::method::{Method, Get, Head, Post, Options};
 
+use security::Origin;
 use url::{RelativeSchemeData, Url, UrlParser};
 
 #[deriving(Clone)]
@@ -51,11 +52,9 @@ pub enum RequestMode {
 
 impl CORSRequest {
     /// Creates a CORS request if necessary. Will return an error when fetching is forbidden
-    pub fn maybe_new(referer: Url, destination: Url, mode: RequestMode,
+    pub fn maybe_new(referer: Url, origin: &Origin, destination: Url, mode: RequestMode,
                      method: Method, headers: RequestHeaderCollection) -> Result<Option<CORSRequest>, ()> {
-        if referer.scheme == destination.scheme &&
-           referer.host() == destination.host() &&
-           referer.port() == destination.port() {
+        if origin.same_origin(&Origin::from_url(&destination)) {
             return Ok(None); // Not cross-origin, proceed with a normal fetch
         }
         match destination.scheme.as_slice() {
@@ -332,9 +331,8 @@ impl CORSCache {
         self.cleanup();
         let CORSCache(ref mut buf) = *self;
         // Credentials are not yet implemented here
-        let entry = buf.iter_mut().find(|e| e.origin.scheme == request.origin.scheme &&
-                            e.origin.host() == request.origin.host() &&
-                            e.origin.port() == request.origin.port() &&
+        let origin = Origin::from_url(&request.origin);
+        let entry = buf.iter_mut().find(|e| Origin::from_url(&e.origin).same_origin(&origin) &&
                             e.url == request.destination &&
                             e.header_or_method.match_header(header_name));
         entry
@@ -353,9 +351,8 @@ impl CORSCache {
         self.cleanup();
         let CORSCache(ref mut buf) = *self;
         // Credentials are not yet implemented here
-        let entry = buf.iter_mut().find(|e| e.origin.scheme == request.origin.scheme &&
-                            e.origin.host() == request.origin.host() &&
-                            e.origin.port() == request.origin.port() &&
+        let origin = Origin::from_url(&request.origin);
+        let entry = buf.iter_mut().find(|e| Origin::from_url(&e.origin).same_origin(&origin) &&
                             e.url == request.destination &&
                             e.header_or_method.match_method(method));
         entry
@@ -408,9 +405,7 @@ pub fn allow_cross_origin_request(req: &CORSRequest, headers: &ResponseHeaderCol
                 return true; // Not always true, depends on credentials mode
             }
             match UrlParser::new().parse(origin_str.as_slice()) {
-                Ok(parsed) => parsed.scheme == req.origin.scheme &&
-                              parsed.host() == req.origin.host() &&
-                              parsed.port() == req.origin.port(),
+                Ok(parsed) => Origin::from_url(&parsed).same_origin(&Origin::from_url(&req.origin)),
                 _ => false
             }
         },
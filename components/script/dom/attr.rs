@@ -16,6 +16,7 @@ use dom::virtualmethods::vtable_for;
 
 use devtools_traits::AttrInfo;
 use servo_util::atom::Atom;
+use servo_util::memory::HeapSizeOf;
 use servo_util::namespace;
 use servo_util::namespace::Namespace;
 use servo_util::str::{DOMString, split_html_space_chars};
@@ -62,6 +63,20 @@ impl AttrValue {
     }
 }
 
+impl HeapSizeOf for AttrValue {
+    fn heap_size_of_children(&self) -> uint {
+        match *self {
+            StringAttrValue(ref value) | UIntAttrValue(ref value, _) => {
+                value.heap_size_of_children()
+            }
+            TokenListAttrValue(ref value, ref tokens) => {
+                value.heap_size_of_children() + tokens.heap_size_of_children()
+            }
+            AtomAttrValue(_) => 0,
+        }
+    }
+}
+
 impl Str for AttrValue {
     fn as_slice<'a>(&'a self) -> &'a str {
         match *self {
@@ -201,7 +216,7 @@ pub trait AttrHelpersForLayout {
     unsafe fn value_ref_forever(&self) -> &'static str;
     unsafe fn value_atom_forever(&self) -> Option<Atom>;
     unsafe fn value_tokens_forever(&self) -> Option<Items<Atom>>;
-    unsafe fn local_name_atom_forever(&self) -> Atom;
+    unsafe fn local_name_atom_forever<'a>(&'a self) -> &'a Atom;
 }
 
 impl AttrHelpersForLayout for Attr {
@@ -229,7 +244,7 @@ impl AttrHelpersForLayout for Attr {
         }
     }
 
-    unsafe fn local_name_atom_forever(&self) -> Atom {
-        self.local_name.clone()
+    unsafe fn local_name_atom_forever<'a>(&'a self) -> &'a Atom {
+        &self.local_name
     }
 }
@@ -0,0 +1,68 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::BeforeUnloadEventBinding;
+use dom::bindings::codegen::Bindings::BeforeUnloadEventBinding::BeforeUnloadEventMethods;
+use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use dom::bindings::codegen::InheritTypes::{BeforeUnloadEventDerived, EventCast};
+use dom::bindings::global::GlobalRef;
+use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::trace::Traceable;
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::event::{Event, BeforeUnloadEventTypeId};
+use servo_util::str::DOMString;
+
+use std::cell::RefCell;
+
+/// The event fired at a window before its document is unloaded, which can
+/// ask for the user to be prompted about leaving the page.
+/// http://www.whatwg.org/html/#beforeunloadevent
+#[jstraceable]
+#[must_root]
+pub struct BeforeUnloadEvent {
+    event: Event,
+    return_value: Traceable<RefCell<DOMString>>,
+}
+
+impl BeforeUnloadEventDerived for Event {
+    fn is_beforeunloadevent(&self) -> bool {
+        self.type_id == BeforeUnloadEventTypeId
+    }
+}
+
+impl BeforeUnloadEvent {
+    fn new_inherited() -> BeforeUnloadEvent {
+        BeforeUnloadEvent {
+            event: Event::new_inherited(BeforeUnloadEventTypeId),
+            return_value: Traceable::new(RefCell::new("".to_string())),
+        }
+    }
+
+    /// Creates a beforeunload event, which doesn't bubble but can be
+    /// canceled.
+    pub fn new(global: &GlobalRef) -> Temporary<BeforeUnloadEvent> {
+        let ev = reflect_dom_object(box BeforeUnloadEvent::new_inherited(),
+                                    global,
+                                    BeforeUnloadEventBinding::Wrap).root();
+        let event: JSRef<Event> = EventCast::from_ref(*ev);
+        event.InitEvent("beforeunload".to_string(), false, true);
+        Temporary::from_rooted(*ev)
+    }
+}
+
+impl<'a> BeforeUnloadEventMethods for JSRef<'a, BeforeUnloadEvent> {
+    fn ReturnValue(self) -> DOMString {
+        self.return_value.deref().borrow().clone()
+    }
+
+    fn SetReturnValue(self, value: DOMString) {
+        *self.return_value.deref().borrow_mut() = value;
+    }
+}
+
+impl Reflectable for BeforeUnloadEvent {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        self.event.reflector()
+    }
+}
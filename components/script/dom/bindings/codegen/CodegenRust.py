@@ -253,12 +253,12 @@ class CGMethodCall(CGThing):
                     if condition is None:
                         caseBody.append(
                             getPerSignatureCall(sigs[0], distinguishingIndex,
-                                                possibleSignatures.index(sigs[0])))
+                                                signatures.index(sigs[0])))
                     else:
                         caseBody.append(CGGeneric("if " + condition + " {"))
                         caseBody.append(CGIndenter(
                                 getPerSignatureCall(sigs[0], distinguishingIndex,
-                                                    possibleSignatures.index(sigs[0]))))
+                                                    signatures.index(sigs[0]))))
                         caseBody.append(CGGeneric("}"))
                     return True
                 return False
@@ -292,7 +292,7 @@ class CGMethodCall(CGThing):
                 # above.
                 caseBody.append(CGGeneric("if (%s).is_object() {" %
                                           (distinguishingArg)))
-                for idx, sig in enumerate(interfacesSigs):
+                for sig in interfacesSigs:
                     caseBody.append(CGIndenter(CGGeneric("loop {")));
                     type = sig[1][distinguishingIndex].type
 
@@ -316,7 +316,8 @@ class CGMethodCall(CGThing):
                     # distinguishingIndex + 1, since we already converted
                     # distinguishingIndex.
                     caseBody.append(CGIndenter(
-                            getPerSignatureCall(sig, distinguishingIndex + 1, idx), 4))
+                            getPerSignatureCall(sig, distinguishingIndex + 1,
+                                                signatures.index(sig)), 4))
                     caseBody.append(CGIndenter(CGGeneric("}")))
 
                 caseBody.append(CGGeneric("}"))
@@ -579,7 +580,34 @@ def getJSToNativeConversionTemplate(type, descriptorProvider, failureCode=None,
         raise TypeError("Can't handle array arguments yet")
 
     if type.isSequence():
-        raise TypeError("Can't handle sequence arguments yet")
+        if type.nullable():
+            raise TypeError("Can't handle nullable sequence arguments yet")
+
+        # The elements are converted with their default configuration.
+        elementType = type.inner
+        if elementType.isGeckoInterface() and not elementType.nullable():
+            # Interface objects are unwrapped to unrooted pointers, which the
+            # callee roots; the array passed in keeps them alive meanwhile.
+            descriptor = descriptorProvider.getDescriptor(
+                elementType.unroll().inner.identifier.name)
+            if descriptor.interface.isCallback():
+                raise TypeError("Can't handle sequences of %s yet" % elementType)
+            elementDeclType = CGGeneric(descriptor.nativeType)
+        elif (elementType.isPrimitive() or elementType.isString() or
+              elementType.isSequence() or
+              (elementType.isUnion() and not elementType.nullable())):
+            (_, _, elementDeclType, _) = getJSToNativeConversionTemplate(
+                elementType, descriptorProvider, isMember="Sequence")
+        else:
+            raise TypeError("Can't handle sequences of %s yet" % elementType)
+        declType = CGWrapper(elementDeclType, pre="Vec<", post=">")
+
+        templateBody = ("match FromJSValConvertible::from_jsval(cx, ${val}, ()) {\n"
+                        "    Ok(value) => value,\n"
+                        "    Err(()) => { %s },\n"
+                        "}" % exceptionCode)
+
+        return handleOptional(templateBody, declType, handleDefaultNull("None"))
 
     if type.isUnion():
         declType = CGGeneric(union_native_type(type))
@@ -652,7 +680,23 @@ def getJSToNativeConversionTemplate(type, descriptorProvider, failureCode=None,
         return handleOptional(templateBody, declType, handleDefaultNull("None"))
 
     if type.isSpiderMonkeyInterface():
-        raise TypeError("Can't handle SpiderMonkey interface arguments yet")
+        assert not isEnforceRange and not isClamp
+        if not type.isArrayBuffer() or type.nullable():
+            raise TypeError("Can't handle SpiderMonkey interface arguments "
+                            "other than ArrayBuffer yet")
+
+        # The object is passed as is; the callee reads its data.
+        templateBody = (
+            "match (${val}).to_object() {\n"
+            "  obj if unsafe { JS_IsArrayBufferObject(obj, cx) } != 0 => obj,\n"
+            "  _ => {\n" +
+            CGIndenter(onFailureBadType(failureCode, "ArrayBuffer"), 4).define() +
+            "  }\n"
+            "}")
+        templateBody = wrapObjectTemplate(templateBody, isDefinitelyObject,
+                                          type, failureCode)
+        declType = CGGeneric("*mut JSObject")
+        return handleOptional(templateBody, declType, handleDefaultNull("None"))
 
     if type.isDOMString():
         assert not isEnforceRange and not isClamp
@@ -747,7 +791,6 @@ def getJSToNativeConversionTemplate(type, descriptorProvider, failureCode=None,
 
     if type.isCallback():
         assert not isEnforceRange and not isClamp
-        assert not type.treatNonCallableAsNull()
         assert not type.treatNonObjectAsNull() or type.nullable()
         assert not type.treatNonObjectAsNull() or not type.treatNonCallableAsNull()
 
@@ -759,7 +802,14 @@ def getJSToNativeConversionTemplate(type, descriptorProvider, failureCode=None,
             declType = CGTemplatedType("Option", declType)
             conversion = CGWrapper(conversion, pre="Some(", post=")")
 
-        if allowTreatNonObjectAsNull and type.treatNonObjectAsNull():
+        if type.treatNonCallableAsNull():
+            haveCallable = "JS_ObjectIsCallable(cx, ${val}.to_object()) != 0"
+            if not isDefinitelyObject:
+                haveCallable = "${val}.is_object() && " + haveCallable
+            template = CGIfElseWrapper(haveCallable,
+                                       conversion,
+                                       CGGeneric("None")).define()
+        elif allowTreatNonObjectAsNull and type.treatNonObjectAsNull():
             if not isDefinitelyObject:
                 haveObject = "${val}.is_object()"
                 template = CGIfElseWrapper(haveObject,
@@ -778,8 +828,8 @@ def getJSToNativeConversionTemplate(type, descriptorProvider, failureCode=None,
                 failureCode)
 
         if defaultValue is not None:
-            assert allowTreatNonObjectAsNull
-            assert type.treatNonObjectAsNull()
+            assert ((allowTreatNonObjectAsNull and type.treatNonObjectAsNull()) or
+                    type.treatNonCallableAsNull())
             assert type.nullable()
             assert isinstance(defaultValue, IDLNullValue)
             default = "None"
@@ -1090,7 +1140,10 @@ def getRetvalDeclarationForType(returnType, descriptorProvider):
     if returnType.isObject() or returnType.isSpiderMonkeyInterface():
         return CGGeneric("*mut JSObject")
     if returnType.isSequence():
-        raise TypeError("We don't support sequence return values")
+        if returnType.nullable():
+            raise TypeError("We don't support nullable sequence return values")
+        result = getRetvalDeclarationForType(returnType.inner, descriptorProvider)
+        return CGWrapper(result, pre="Vec<", post=">")
 
     raise TypeError("Don't know how to declare return value for %s" %
                     returnType)
@@ -1183,27 +1236,73 @@ class MethodDefiner(PropertyDefiner):
                                  "length": 0,
                                  "flags": "JSPROP_ENUMERATE" })
 
+        # Value iterators behave like the iterators of arrays, so they can use
+        # the engine's implementations of the array iteration methods.
+        # http://heycam.github.io/webidl/#idl-iterable
+        iterable = [m for m in descriptor.interface.members if m.isIterable()]
+        if not static and iterable and iterable[0].isValueIterator():
+            self.regular.append({"name": 'entries',
+                                 "methodInfo": False,
+                                 "selfHostedName": "ArrayEntries",
+                                 "length": 0,
+                                 "flags": "JSPROP_ENUMERATE" })
+            self.regular.append({"name": 'keys',
+                                 "methodInfo": False,
+                                 "selfHostedName": "ArrayKeys",
+                                 "length": 0,
+                                 "flags": "JSPROP_ENUMERATE" })
+            self.regular.append({"name": 'values',
+                                 "methodInfo": False,
+                                 "nativeName": "JS_ArrayIterator",
+                                 "length": 0,
+                                 "flags": "JSPROP_ENUMERATE" })
+            self.regular.append({"name": 'forEach',
+                                 "methodInfo": False,
+                                 "selfHostedName": "ArrayForEach",
+                                 "length": 1,
+                                 "flags": "JSPROP_ENUMERATE" })
+        # Pair iterators iterate over a snapshot of the pairs of the object.
+        if not static and iterable and iterable[0].isPairIterator():
+            for (name, length) in [('entries', 0), ('keys', 0), ('values', 0),
+                                   ('forEach', 1), ('iterator', 0)]:
+                self.regular.append({"name": name,
+                                     "methodInfo": False,
+                                     "nativeName": CGPairIterableMethod.nativeName(name),
+                                     "length": length,
+                                     "flags": "JSPROP_ENUMERATE" })
+
     def generateArray(self, array, name):
         if len(array) == 0:
             return ""
 
         def specData(m):
+            selfHostedName = "0 as *const libc::c_char"
             if m.get("methodInfo", True):
                 jitinfo = ("&%s_methodinfo" % m["name"])
-                accessor = "genericMethod"
+                accessor = "Some(genericMethod)"
+            elif "selfHostedName" in m:
+                jitinfo = "0 as *const JSJitInfo"
+                accessor = "None"
+                selfHostedName = ("&%s_selfHostedName as *const u8 as *const libc::c_char" %
+                                  m["name"])
             else:
                 jitinfo = "0 as *const JSJitInfo"
-                accessor = m.get("nativeName", m["name"])
-            return (m["name"], accessor, jitinfo, m["length"], m["flags"])
+                accessor = "Some(%s)" % m.get("nativeName", m["name"])
+            return (m["name"], accessor, jitinfo, m["length"], m["flags"], selfHostedName)
 
         def stringDecl(m):
-            return "static %s_name: [u8, ..%i] = %s;\n" % (m["name"], len(m["name"]) + 1,
+            decl = "static %s_name: [u8, ..%i] = %s;\n" % (m["name"], len(m["name"]) + 1,
                                                          str_to_const_array(m["name"]))
-        
+            if "selfHostedName" in m:
+                decl += "static %s_selfHostedName: [u8, ..%i] = %s;\n" % (
+                    m["name"], len(m["selfHostedName"]) + 1,
+                    str_to_const_array(m["selfHostedName"]))
+            return decl
+
         decls = ''.join([stringDecl(m) for m in array])
         return decls + self.generatePrefableArray(
             array, name,
-            '  JSFunctionSpec {name: &%s_name as *const u8 as *const libc::c_char, call: JSNativeWrapper {op: Some(%s), info: %s}, nargs: %s, flags: %s as u16, selfHostedName: 0 as *const libc::c_char }',
+            '  JSFunctionSpec {name: &%s_name as *const u8 as *const libc::c_char, call: JSNativeWrapper {op: %s, info: %s}, nargs: %s, flags: %s as u16, selfHostedName: %s }',
             '  JSFunctionSpec {name: 0 as *const libc::c_char, call: JSNativeWrapper {op: None, info: 0 as *const JSJitInfo}, nargs: 0, flags: 0, selfHostedName: 0 as *const libc::c_char }',
             'JSFunctionSpec',
             specData)
@@ -1672,7 +1771,8 @@ def UnionTypes(descriptors, dictionaries, callbacks, config):
         'dom::bindings::error::throw_not_in_union',
         'dom::bindings::js::JS',
         'dom::types::*',
-        'js::jsapi::JSContext',
+        'js::jsapi::{JSContext, JSObject}',
+        'js::jsfriendapi::bindgen::JS_IsArrayBufferObject',
         'js::jsval::JSVal',
         'servo_util::str::DOMString',
     ]
@@ -1780,11 +1880,32 @@ class CGAbstractMethod(CGThing):
         body = self.definition_body()
         if self.unsafe:
             body = CGWrapper(body, pre="unsafe {\n", post="\n}")
+        if self.extern:
+            body = self.catchPanics(body)
 
         return CGWrapper(CGIndenter(body),
                          pre=self.definition_prologue(),
                          post=self.definition_epilogue()).define()
 
+    def catchPanics(self, body):
+        """
+        The JS engine calls extern methods, and a panic can't unwind through
+        its frames. Methods that get a context report it as an exception
+        instead; the hooks that the garbage collector calls abort.
+        """
+        contexts = [a.name for a in self.args if a.argType == '*mut JSContext']
+        if not contexts:
+            return CGList([CGGeneric("let _guard = AbortOnUnwind;"), body], "\n")
+        failureValues = {
+            'JSBool': '0',
+            'bool': 'false',
+            '*mut JSString': 'ptr::null_mut()',
+            'void': '()',
+        }
+        return CGWrapper(CGIndenter(body),
+                         pre="catch_dom_panic(%s, %s, || {\n" % (contexts[0], failureValues[self.returnType]),
+                         post="\n})")
+
     def definition_prologue(self):
         return "%sfn %s%s(%s)%s {\n" % (self._decorators(), self.name, self._template(),
                                           self._argstring(), self._returnType())
@@ -1997,13 +2118,21 @@ class CGCreateInterfaceObjectsMethod(CGAbstractMethod):
             constructor = 'Some((%s, "%s", %d))' % (
                 constructHook, self.descriptor.interface.identifier.name,
                 constructArgs)
+
+            namedConstructors = ", ".join(
+                '(%s, "%s", %d)' % (namedConstructHookName(ctor),
+                                    ctor.identifier.name,
+                                    methodLength(ctor))
+                for ctor in self.descriptor.interface.namedConstructors)
         else:
             constructor = 'None'
+            namedConstructors = ""
 
         call = """return CreateInterfaceObjects2(aCx, aGlobal, aReceiver, parentProto,
                                &PrototypeClass, %s,
+                               &[%s],
                                %s,
-                               &sNativeProperties);""" % (constructor, domClass)
+                               &sNativeProperties);""" % (constructor, namedConstructors, domClass)
 
         return CGList([
             CGGeneric(getParentProto),
@@ -2211,15 +2340,27 @@ class CGCallGenerator(CGThing):
                 glob = "        let global = global_object_for_js_object(this.reflector().get_jsobject());\n"\
                        "        let global = global.root();\n"
 
-            self.cgRoot.append(CGGeneric(
-                "let result = match result {\n"
-                "    Ok(result) => result,\n"
-                "    Err(e) => {\n"
-                "%s"
-                "        throw_dom_exception(cx, &global.root_ref(), e);\n"
-                "        return%s;\n"
-                "    },\n"
-                "};\n" % (glob, errorResult)))
+            if returnType is not None and returnType.isPromise():
+                # Errors reject the promise the method returns rather than
+                # being thrown.
+                self.cgRoot.append(CGGeneric(
+                    "let result = match result {\n"
+                    "    Ok(result) => result,\n"
+                    "    Err(e) => {\n"
+                    "%s"
+                    "        Promise::new_rejected(&global.root_ref(), e)\n"
+                    "    },\n"
+                    "};\n" % glob))
+            else:
+                self.cgRoot.append(CGGeneric(
+                    "let result = match result {\n"
+                    "    Ok(result) => result,\n"
+                    "    Err(e) => {\n"
+                    "%s"
+                    "        throw_dom_exception(cx, &global.root_ref(), e);\n"
+                    "        return%s;\n"
+                    "    },\n"
+                    "};\n" % (glob, errorResult)))
 
         if typeRetValNeedsRooting(returnType):
             self.cgRoot.append(CGGeneric("let result = result.root();"))
@@ -2476,6 +2617,37 @@ class CGGenericMethod(CGAbstractBindingMethod):
             "let _info: *const JSJitInfo = RUST_FUNCTION_VALUE_TO_JITINFO(JS_CALLEE(cx, vp));\n"
             "return CallJitMethodOp(_info, cx, obj, this.unsafe_get() as *mut libc::c_void, argc, vp);")
 
+class CGPairIterableMethod(CGAbstractBindingMethod):
+    """
+    A class for generating the native of one of the iteration methods of an
+    interface declaring a pair iterator.
+    """
+    def __init__(self, descriptor, name):
+        self.kind = name
+        args = [Argument('*mut JSContext', 'cx'), Argument('libc::c_uint', 'argc'),
+                Argument('*mut JSVal', 'vp')]
+        CGAbstractBindingMethod.__init__(self, descriptor,
+                                         CGPairIterableMethod.nativeName(name), args)
+
+    @staticmethod
+    def nativeName(name):
+        return "%s_pairs" % name
+
+    def generate_code(self):
+        code = ("let this = this.root();\n"
+                "let pairs = this.Pairs();\n")
+        if self.kind == 'forEach':
+            code += "return iterable::for_each(cx, obj, pairs, argc, vp);"
+        else:
+            kind = {
+                'entries': 'Entries',
+                'iterator': 'Entries',
+                'keys': 'Keys',
+                'values': 'Values',
+            }[self.kind]
+            code += "return iterable::iterate(cx, pairs, iterable::%s, vp);" % kind
+        return CGGeneric(code)
+
 class CGSpecializedMethod(CGAbstractExternMethod):
     """
     A class for generating the C++ code for a specialized method that the JIT
@@ -2513,7 +2685,13 @@ class CGStaticMethod(CGAbstractStaticBindingMethod):
     def generate_code(self):
         nativeName = CGSpecializedMethod.makeNativeName(self.descriptor,
                                                         self.method)
-        return CGMethodCall([], nativeName, True, self.descriptor, self.method)
+        preamble = CGGeneric("""\
+let global = global_object_for_js_object(JS_CALLEE(cx, vp).to_object());
+let global = global.root();
+""")
+        callGenerator = CGMethodCall(["&global.root_ref()"], nativeName, True,
+                                     self.descriptor, self.method)
+        return CGList([preamble, callGenerator])
 
 
 class CGGenericGetter(CGAbstractBindingMethod):
@@ -2815,8 +2993,8 @@ def getUnionTypeTemplateVars(type, descriptorProvider):
     # for getJSToNativeConversionTemplate.
     # Also, for dictionaries we would need to handle conversion of
     # null/undefined to the dictionary correctly.
-    if type.isDictionary() or type.isSequence():
-        raise TypeError("Can't handle dictionaries or sequences in unions")
+    if type.isDictionary():
+        raise TypeError("Can't handle dictionaries in unions")
 
     if type.isGeckoInterface():
         name = type.inner.identifier.name
@@ -2824,7 +3002,14 @@ def getUnionTypeTemplateVars(type, descriptorProvider):
     elif type.isEnum():
         name = type.inner.identifier.name
         typeName = name
-    elif type.isArray() or type.isSequence():
+    elif type.isSequence():
+        name = str(type)
+        (_, _, declType, _) = getJSToNativeConversionTemplate(type, descriptorProvider)
+        typeName = declType.define()
+    elif type.isArrayBuffer():
+        name = type.name
+        typeName = "*mut JSObject"
+    elif type.isArray():
         name = str(type)
         #XXXjdm dunno about typeName here
         typeName = "/*" + type.name + "*/"
@@ -2930,7 +3115,18 @@ class CGUnionConversionStruct(CGThing):
         arrayObjectMemberTypes = filter(lambda t: t.isArray() or t.isSequence(), memberTypes)
         if len(arrayObjectMemberTypes) > 0:
             assert len(arrayObjectMemberTypes) == 1
-            raise TypeError("Can't handle arrays or sequences in unions.")
+            memberType = arrayObjectMemberTypes[0]
+            if memberType.isArray():
+                raise TypeError("Can't handle arrays in unions.")
+            # Any object that isn't one of the interfaces is a sequence.
+            name = str(memberType)
+            arrayObject = CGGeneric(
+                "match %s::TryConvertTo%s(cx, value) {\n"
+                "    Err(_) => return Err(()),\n"
+                "    Ok(Some(value)) => return Ok(e%s(value)),\n"
+                "    Ok(None) => (),\n"
+                "}\n" % (self.type, name, name))
+            names.append(name)
         else:
             arrayObject = None
 
@@ -2968,8 +3164,8 @@ class CGUnionConversionStruct(CGThing):
 
         hasObjectTypes = interfaceObject or arrayObject or dateObject or nonPlatformObject or object
         if hasObjectTypes:
-            assert interfaceObject
-            templateBody = CGList([interfaceObject], "\n")
+            assert interfaceObject or arrayObject
+            templateBody = CGList([o for o in [interfaceObject, arrayObject] if o], "\n")
             conversions.append(CGIfWrapper(templateBody, "value.is_object()"))
 
         otherMemberTypes = [
@@ -3010,7 +3206,7 @@ class CGUnionConversionStruct(CGThing):
 
         return CGWrapper(
             CGIndenter(jsConversion, 4),
-            pre="fn TryConvertTo%s(cx: *mut JSContext, value: JSVal) -> %s {\n" % (t.name, returnType),
+            pre="fn TryConvertTo%s(cx: *mut JSContext, value: JSVal) -> %s {\n" % (templateVars["name"], returnType),
             post="\n}")
 
     def define(self):
@@ -3939,15 +4135,24 @@ class CGClassTraceHook(CGAbstractClassHook):
     def generate_code(self):
         return CGGeneric("(*this).trace(%s);" % self.args[0].name)
 
+def namedConstructHookName(ctor):
+    return "%s_%s" % (CONSTRUCT_HOOK_NAME, ctor.identifier.name)
+
 class CGClassConstructHook(CGAbstractExternMethod):
     """
-    JS-visible constructor for our objects
+    JS-visible constructor for our objects. If ctor is given, this is the hook
+    for that named constructor rather than for the interface's constructor.
     """
-    def __init__(self, descriptor):
+    def __init__(self, descriptor, ctor=None):
         args = [Argument('*mut JSContext', 'cx'), Argument('u32', 'argc'), Argument('*mut JSVal', 'vp')]
-        CGAbstractExternMethod.__init__(self, descriptor, CONSTRUCT_HOOK_NAME,
+        if ctor is None:
+            name = CONSTRUCT_HOOK_NAME
+            ctor = descriptor.interface.ctor()
+        else:
+            name = namedConstructHookName(ctor)
+        CGAbstractExternMethod.__init__(self, descriptor, name,
                                         'JSBool', args)
-        self._ctor = self.descriptor.interface.ctor()
+        self._ctor = ctor
 
     def define(self):
         if not self._ctor:
@@ -4055,6 +4260,11 @@ class CGInterfaceTrait(CGThing):
                             rettype = "ErrorResult"
                         yield name, attribute_arguments(needCx, m.type), rettype
 
+                elif m.isIterable() and m.isPairIterator():
+                    keyType = getRetvalDeclarationForType(m.keyType, descriptor).define()
+                    valueType = getRetvalDeclarationForType(m.valueType, descriptor).define()
+                    yield "Pairs", [], "Vec<(%s, %s)>" % (keyType, valueType)
+
             if descriptor.proxy:
                 for name, operation in descriptor.operations.iteritems():
                     if not operation:
@@ -4131,6 +4341,11 @@ class CGDescriptor(CGThing):
 
                 if not m.isStatic():
                     cgThings.append(CGMemberJITInfo(descriptor, m))
+        iterable = [m for m in descriptor.interface.members if m.isIterable()]
+        if iterable and iterable[0].isPairIterator():
+            for name in ['entries', 'keys', 'values', 'forEach', 'iterator']:
+                cgThings.append(CGPairIterableMethod(descriptor, name))
+
         if hasMethod:
             cgThings.append(CGGenericMethod(descriptor))
         if hasGetter:
@@ -4148,6 +4363,8 @@ class CGDescriptor(CGThing):
 
         if descriptor.interface.hasInterfaceObject():
             cgThings.append(CGClassConstructHook(descriptor))
+            for ctor in descriptor.interface.namedConstructors:
+                cgThings.append(CGClassConstructHook(descriptor, ctor))
             cgThings.append(CGInterfaceObjectJSClass(descriptor))
 
         cgThings.append(CGPrototypeJSClass(descriptor))
@@ -4344,6 +4561,11 @@ class CGDictionary(CGThing):
         name = dictionary.identifier.name
         if name.endswith('Init'):
             return toBindingNamespace(name.replace('Init', ''))
+        # Otherwise, the dictionary lives in the binding of the file that
+        # defines it.
+        filename = os.path.basename(dictionary.filename())
+        if filename.endswith('.webidl'):
+            return toBindingNamespace(filename[:-len('.webidl')])
         #XXXjdm This breaks on the test webidl files, sigh.
         #raise TypeError("No idea how to find this dictionary's definition: " + name)
         return "/* uh oh */ %s" % name
@@ -4486,10 +4708,21 @@ class CGBindingRoot(CGThing):
         #curr = CGNamespace.build(['dom'],
         #                         CGWrapper(curr, pre="\n"))
 
+        # Enums are only defined in the binding of the file that declares
+        # them, so import the ones this file uses from other files.
+        enumImports = set()
+        for (t, _, _) in getAllTypes(descriptors, dictionaries, mainCallbacks):
+            t = t.unroll()
+            if t.isEnum() and t.inner.filename() != webIDLFile:
+                module = CGImports.getDeclarationFilename(t.inner).replace('.rs', '')
+                name = t.inner.identifier.name
+                enumImports.add('dom::bindings::codegen::Bindings::%s::{%s, %sValues}' %
+                                (module, name, name))
+
         # Add imports
         #XXXjdm This should only import the namespace for the current binding,
         #       not every binding ever.
-        curr = CGImports(curr, descriptors, [
+        curr = CGImports(curr, descriptors, list(enumImports) + [
             'js',
             'js::{JS_ARGV, JS_CALLEE, JS_THIS_OBJECT}',
             'js::{JSCLASS_GLOBAL_SLOT_COUNT, JSCLASS_IS_DOMJSCLASS}',
@@ -4508,6 +4741,7 @@ class CGBindingRoot(CGThing):
             'js::jsapi::{JSPropertyOpWrapper, JSPropertySpec, JS_PropertyStub}',
             'js::jsapi::{JSStrictPropertyOpWrapper, JSString, JSTracer, JS_ConvertStub}',
             'js::jsapi::{JS_StrictPropertyStub, JS_EnumerateStub, JS_ResolveStub}',
+            'js::jsfriendapi::bindgen::JS_IsArrayBufferObject',
             'js::jsval::JSVal',
             'js::jsval::{ObjectValue, ObjectOrNullValue, PrivateValue}',
             'js::jsval::{NullValue, UndefinedValue}',
@@ -4520,9 +4754,11 @@ class CGBindingRoot(CGThing):
             'dom::bindings',
             'dom::bindings::global::GlobalRef',
             'dom::bindings::global::global_object_for_js_object',
+            'dom::bindings::iterable',
             'dom::bindings::js::{JS, JSRef, Root, RootedReference, Temporary}',
             'dom::bindings::js::{OptionalRootable, OptionalRootedRootable, ResultRootable}',
             'dom::bindings::js::{OptionalRootedReference, OptionalOptionalRootedRootable}',
+            'dom::bindings::utils::{AbortOnUnwind, catch_dom_panic}',
             'dom::bindings::utils::{CreateDOMGlobal, CreateInterfaceObjects2}',
             'dom::bindings::utils::ConstantSpec',
             'dom::bindings::utils::{dom_object_slot, DOM_OBJECT_SLOT, DOMClass}',
@@ -4824,11 +5060,7 @@ class CGNativeMember(ClassMethod):
             return declType, False, False
 
         if type.isObject():
-            if isMember:
-                declType = "JSObject*"
-            else:
-                declType = "JS::Handle<JSObject*>"
-            return declType, False, False
+            return "*mut JSObject", False, False
 
         if type.isDictionary():
             typeName = CGDictionary.makeDictionaryName(type.inner)
@@ -876,6 +876,17 @@ class IDLInterface(IDLObjectWithScope):
 
             specialMembersSeen[memberType] = member
 
+        # Value iterators are defined in terms of the indexed getter.
+        iterables = [m for m in self.members if m.isIterable()]
+        if len(iterables) > 1:
+            raise WebIDLError("Multiple iterable declarations on %s" % (self),
+                              [self.location] + [m.location for m in iterables])
+        if (len(iterables) == 1 and iterables[0].isValueIterator() and
+            "indexed getters" not in specialMembersSeen):
+            raise WebIDLError("Value iterable declared on %s, which has no "
+                              "indexed property getter" % (self),
+                              [self.location, iterables[0].location])
+
         if self._isOnGlobalProtoChain:
             # Make sure we have no named setters, creators, or deleters
             for memberType in ["setter", "creator", "deleter"]:
@@ -2925,7 +2936,8 @@ class IDLInterfaceMember(IDLObjectWithIdentifier):
     Tags = enum(
         'Const',
         'Attr',
-        'Method'
+        'Method',
+        'Iterable'
     )
 
     Special = enum(
@@ -2952,6 +2964,9 @@ class IDLInterfaceMember(IDLObjectWithIdentifier):
     def isConst(self):
         return self.tag == IDLInterfaceMember.Tags.Const
 
+    def isIterable(self):
+        return self.tag == IDLInterfaceMember.Tags.Iterable
+
     def addExtendedAttributes(self, attrs):
         for attr in attrs:
             self.handleExtendedAttribute(attr)
@@ -3041,6 +3056,53 @@ class IDLConst(IDLInterfaceMember):
     def _getDependentObjects(self):
         return set([self.type, self.value])
 
+class IDLIterable(IDLInterfaceMember):
+    def __init__(self, location, identifier, keyType, valueType):
+        IDLInterfaceMember.__init__(self, location, identifier,
+                                    IDLInterfaceMember.Tags.Iterable)
+
+        assert keyType is None or isinstance(keyType, IDLType)
+        assert isinstance(valueType, IDLType)
+        self.keyType = keyType
+        self.valueType = valueType
+
+    def __str__(self):
+        return "declared iterable with key '%s' and value '%s'" % (self.keyType,
+                                                                   self.valueType)
+
+    def isValueIterator(self):
+        return self.keyType is None
+
+    def isPairIterator(self):
+        return self.keyType is not None
+
+    def finish(self, scope):
+        IDLInterfaceMember.finish(self, scope)
+
+        def completeType(t):
+            if t is None or t.isComplete():
+                return t
+            t = t.complete(scope)
+            assert not isinstance(t, IDLUnresolvedType)
+            assert not isinstance(t, IDLTypedefType)
+            return t
+
+        self.keyType = completeType(self.keyType)
+        self.valueType = completeType(self.valueType)
+
+    def validate(self):
+        IDLInterfaceMember.validate(self)
+
+    def handleExtendedAttribute(self, attr):
+        raise WebIDLError("Unknown extended attribute %s on iterable" %
+                          attr.identifier(), [attr.location])
+
+    def _getDependentObjects(self):
+        deps = set([self.valueType])
+        if self.keyType is not None:
+            deps.add(self.keyType)
+        return deps
+
 class IDLAttribute(IDLInterfaceMember):
     def __init__(self, location, identifier, type, readonly, inherit=False,
                  static=False, stringifier=False):
@@ -4033,6 +4095,7 @@ class Tokenizer(object):
         "creator": "CREATOR",
         "deleter": "DELETER",
         "legacycaller": "LEGACYCALLER",
+        "iterable": "ITERABLE",
         "optional": "OPTIONAL",
         "...": "ELLIPSIS",
         "::": "SCOPE",
@@ -4306,9 +4369,26 @@ class Parser(Tokenizer):
         """
             InterfaceMember : Const
                             | AttributeOrOperation
+                            | Iterable
         """
         p[0] = p[1]
 
+    def p_Iterable(self, p):
+        """
+            Iterable : ITERABLE LT Type GT SEMICOLON
+                     | ITERABLE LT Type COMMA Type GT SEMICOLON
+        """
+        location = self.getLocation(p, 1)
+        identifier = IDLUnresolvedIdentifier(location, "__iterable",
+                                             allowDoubleUnderscore=True)
+        if len(p) > 6:
+            keyType = p[3]
+            valueType = p[5]
+        else:
+            keyType = None
+            valueType = p[3]
+        p[0] = IDLIterable(location, identifier, keyType, valueType)
+
     def p_Dictionary(self, p):
         """
             Dictionary : DICTIONARY IDENTIFIER Inheritance LBRACE DictionaryMembers RBRACE SEMICOLON
@@ -4867,6 +4947,7 @@ class Parser(Tokenizer):
                          | IMPLEMENTS
                          | INHERIT
                          | INTERFACE
+                         | ITERABLE
                          | LEGACYCALLER
                          | PARTIAL
                          | SERIALIZER
@@ -4994,6 +5075,7 @@ class Parser(Tokenizer):
                   | IMPLEMENTS
                   | INHERIT
                   | INTERFACE
+                  | ITERABLE
                   | LONG
                   | MODULE
                   | NULL
@@ -0,0 +1,67 @@
+import WebIDL
+
+def WebIDLTest(parser, harness):
+    parser.parse("""
+        interface TestValueIterable {
+          readonly attribute unsigned long length;
+          getter long item(unsigned long index);
+          iterable<long>;
+        };
+        interface TestPairIterable {
+          iterable<DOMString, long>;
+        };
+    """)
+
+    results = parser.finish()
+
+    harness.check(len(results), 2, "Should be two productions.")
+
+    iface = results[0]
+    iterables = [m for m in iface.members if m.isIterable()]
+    harness.check(len(iterables), 1, "Should have one iterable declaration.")
+    iterable = iterables[0]
+    harness.ok(isinstance(iterable, WebIDL.IDLIterable), "Should be an IDLIterable")
+    harness.ok(iterable.isValueIterator(), "Should be a value iterator")
+    harness.ok(not iterable.isPairIterator(), "Should not be a pair iterator")
+    harness.check(iterable.keyType, None, "Value iterator has no key type")
+    harness.check(str(iterable.valueType), "Long", "Value type is long")
+    harness.ok(not iterable.isMethod() and not iterable.isAttr() and
+               not iterable.isConst(),
+               "Iterable is neither a method, an attribute nor a constant")
+
+    iface = results[1]
+    iterable = [m for m in iface.members if m.isIterable()][0]
+    harness.ok(iterable.isPairIterator(), "Should be a pair iterator")
+    harness.check(str(iterable.keyType), "String", "Key type is DOMString")
+    harness.check(str(iterable.valueType), "Long", "Value type is long")
+
+    parser = parser.reset()
+    threw = False
+    try:
+        parser.parse("""
+            interface TestValueIterableWithoutGetter {
+              iterable<long>;
+            };
+        """)
+
+        results = parser.finish()
+    except:
+        threw = True
+
+    harness.ok(threw, "Value iterable without indexed getter should have thrown.")
+
+    parser = parser.reset()
+    threw = False
+    try:
+        parser.parse("""
+            interface TestTwoIterables {
+              iterable<DOMString, long>;
+              iterable<long, long>;
+            };
+        """)
+
+        results = parser.finish()
+    except:
+        threw = True
+
+    harness.ok(threw, "Two iterable declarations should have thrown.")
@@ -4,6 +4,7 @@
 
 //! Conversions of Rust values to and from `JSVal`.
 
+use dom::bindings::error::throw_type_error;
 use dom::bindings::js::{JS, JSRef, Root};
 use dom::bindings::str::ByteString;
 use dom::bindings::utils::{Reflectable, Reflector};
@@ -18,12 +19,15 @@ use js::jsapi::{JS_ValueToUint16, JS_ValueToNumber, JS_ValueToBoolean};
 use js::jsapi::{JS_ValueToString, JS_GetStringCharsAndLength};
 use js::jsapi::{JS_NewUCStringCopyN, JS_NewStringCopyN};
 use js::jsapi::{JS_WrapValue};
+use js::jsapi::{JS_NewArrayObject, JS_IsArrayObject, JS_GetArrayLength};
+use js::jsapi::{JS_GetElement, JS_SetElement, JS_IsExceptionPending};
 use js::jsval::JSVal;
 use js::jsval::{UndefinedValue, NullValue, BooleanValue, Int32Value, UInt32Value};
 use js::jsval::{StringValue, ObjectValue, ObjectOrNullValue};
 use js::glue::RUST_JS_NumberValue;
 use libc;
 use std::default;
+use std::ptr;
 use std::slice;
 
 use dom::bindings::codegen::PrototypeList;
@@ -367,6 +371,66 @@ impl<X: default::Default, T: FromJSValConvertible<X>> FromJSValConvertible<()> f
     }
 }
 
+impl<T: ToJSValConvertible> ToJSValConvertible for Vec<T> {
+    fn to_jsval(&self, cx: *mut JSContext) -> JSVal {
+        unsafe {
+            let array = JS_NewArrayObject(cx, 0, ptr::null_mut());
+            if array.is_null() {
+                fail!("JS_NewArrayObject failed");
+            }
+            for (index, element) in self.iter().enumerate() {
+                let mut value = element.to_jsval(cx);
+                if JS_SetElement(cx, array, index as libc::c_uint, &mut value) == 0 {
+                    fail!("JS_SetElement failed");
+                }
+            }
+            ObjectValue(&*array)
+        }
+    }
+}
+
+/// Converts an array to a sequence. Other objects are not supported yet.
+/// http://heycam.github.io/webidl/#es-sequence
+impl<X: default::Default, T: FromJSValConvertible<X>> FromJSValConvertible<()> for Vec<T> {
+    fn from_jsval(cx: *mut JSContext, value: JSVal, _: ()) -> Result<Vec<T>, ()> {
+        if !value.is_object() {
+            throw_type_error(cx, "Value is not a sequence");
+            return Err(());
+        }
+        let array = value.to_object();
+        unsafe {
+            if JS_IsArrayObject(cx, array) == 0 {
+                throw_type_error(cx, "Value is not a sequence");
+                return Err(());
+            }
+            let mut length = 0;
+            if JS_GetArrayLength(cx, array, &mut length) == 0 {
+                return Err(());
+            }
+            let mut result = Vec::with_capacity(length as uint);
+            for index in range(0, length) {
+                let mut element = UndefinedValue();
+                if JS_GetElement(cx, array, index, &mut element) == 0 {
+                    return Err(());
+                }
+                let option: X = default::Default::default();
+                match FromJSValConvertible::from_jsval(cx, element, option) {
+                    Ok(element) => result.push(element),
+                    Err(()) => {
+                        // Interface objects that don't unwrap fail without
+                        // throwing anything.
+                        if JS_IsExceptionPending(cx) == 0 {
+                            throw_type_error(cx, "Value is not of the sequence's element type");
+                        }
+                        return Err(());
+                    },
+                }
+            }
+            Ok(result)
+        }
+    }
+}
+
 impl ToJSValConvertible for *mut JSObject {
     fn to_jsval(&self, cx: *mut JSContext) -> JSVal {
         let mut wrapped = ObjectOrNullValue(*self);
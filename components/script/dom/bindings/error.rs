@@ -8,9 +8,11 @@ use dom::bindings::conversions::ToJSValConvertible;
 use dom::bindings::global::GlobalRef;
 use dom::domexception::DOMException;
 
+use servo_util::str::DOMString;
+
 use js::jsapi::{JSContext, JSBool, JSObject};
 use js::jsapi::{JS_IsExceptionPending, JS_SetPendingException, JS_ReportPendingException};
-use js::jsapi::{JS_ReportErrorNumber, JSErrorFormatString, JSEXN_TYPEERR};
+use js::jsapi::{JS_ReportErrorNumber, JSErrorFormatString, JSEXN_TYPEERR, JSEXN_RANGEERR};
 use js::jsapi::{JS_SaveFrameChain, JS_RestoreFrameChain};
 use js::glue::{ReportError};
 use js::rust::with_compartment;
@@ -19,7 +21,7 @@ use libc;
 use std::ptr;
 
 /// DOM exceptions that can be thrown by a native DOM method.
-#[deriving(Show)]
+#[deriving(Clone, Show)]
 pub enum Error {
     IndexSize,
     FailureUnknown,
@@ -34,7 +36,12 @@ pub enum Error {
     Security,
     Network,
     Abort,
-    Timeout
+    Timeout,
+    DataClone,
+    /// A `TypeError` with the given message, rather than a `DOMException`.
+    Type(DOMString),
+    /// A `RangeError` with the given message, rather than a `DOMException`.
+    Range(DOMString)
 }
 
 /// The return type for IDL operations that can throw DOM exceptions.
@@ -48,6 +55,11 @@ pub type ErrorResult = Fallible<()>;
 pub fn throw_dom_exception(cx: *mut JSContext, global: &GlobalRef,
                            result: Error) {
     assert!(unsafe { JS_IsExceptionPending(cx) } == 0);
+    let result = match result {
+        Type(message) => return throw_type_error(cx, message.as_slice()),
+        Range(message) => return throw_range_error(cx, message.as_slice()),
+        result => result,
+    };
     let exception = DOMException::new_from_error(global, result).root();
     let thrown = exception.to_jsval(cx);
     unsafe {
@@ -81,7 +93,18 @@ pub fn throw_not_in_union(cx: *mut JSContext, names: &'static str) -> JSBool {
     return 0;
 }
 
-/// Format string used to throw `TypeError`s.
+/// Throw an `Error` with the given message, for failures that no DOM
+/// exception describes. An exception that is already pending is kept.
+pub fn throw_internal_error(cx: *mut JSContext, message: &str) {
+    if unsafe { JS_IsExceptionPending(cx) } != 0 {
+        return;
+    }
+    message.with_c_str(|string| {
+        unsafe { ReportError(cx, string) };
+    });
+}
+
+/// Format string used to throw `TypeError`s and `RangeError`s.
 static ERROR_FORMAT_STRING_STRING: [libc::c_char, ..4] = [
     '{' as libc::c_char,
     '0' as libc::c_char,
@@ -90,25 +113,49 @@ static ERROR_FORMAT_STRING_STRING: [libc::c_char, ..4] = [
 ];
 
 /// Format string struct used to throw `TypeError`s.
-static ERROR_FORMAT_STRING: JSErrorFormatString = JSErrorFormatString {
+static TYPE_ERROR_FORMAT_STRING: JSErrorFormatString = JSErrorFormatString {
     format: &ERROR_FORMAT_STRING_STRING as *const libc::c_char,
     argCount: 1,
     exnType: JSEXN_TYPEERR as i16,
 };
 
-/// Callback used to throw `TypeError`s.
+/// Format string struct used to throw `RangeError`s.
+static RANGE_ERROR_FORMAT_STRING: JSErrorFormatString = JSErrorFormatString {
+    format: &ERROR_FORMAT_STRING_STRING as *const libc::c_char,
+    argCount: 1,
+    exnType: JSEXN_RANGEERR as i16,
+};
+
+/// The error number of `RangeError`s; `TypeError`s use 0.
+static RANGE_ERROR_NUMBER: libc::c_uint = 1;
+
+/// Callback used to throw `TypeError`s and `RangeError`s.
 unsafe extern fn get_error_message(_user_ref: *mut libc::c_void,
                             _locale: *const libc::c_char,
                             error_number: libc::c_uint) -> *const JSErrorFormatString
 {
+    if error_number == RANGE_ERROR_NUMBER {
+        return &RANGE_ERROR_FORMAT_STRING as *const JSErrorFormatString;
+    }
     assert_eq!(error_number, 0);
-    &ERROR_FORMAT_STRING as *const JSErrorFormatString
+    &TYPE_ERROR_FORMAT_STRING as *const JSErrorFormatString
 }
 
-/// Throw a `TypeError` with the given message.
-pub fn throw_type_error(cx: *mut JSContext, error: &str) {
+/// Throw the error of the given number with the given message.
+fn throw_error(cx: *mut JSContext, error_number: libc::c_uint, error: &str) {
     let error = error.to_c_str();
     unsafe {
-        JS_ReportErrorNumber(cx, Some(get_error_message), ptr::null_mut(), 0, error.as_ptr());
+        JS_ReportErrorNumber(cx, Some(get_error_message), ptr::null_mut(),
+                             error_number, error.as_ptr());
     }
 }
+
+/// Throw a `TypeError` with the given message.
+pub fn throw_type_error(cx: *mut JSContext, error: &str) {
+    throw_error(cx, 0, error)
+}
+
+/// Throw a `RangeError` with the given message.
+pub fn throw_range_error(cx: *mut JSContext, error: &str) {
+    throw_error(cx, RANGE_ERROR_NUMBER, error)
+}
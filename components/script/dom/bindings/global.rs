@@ -7,13 +7,21 @@
 //! This module contains smart pointers to global scopes, to simplify writing
 //! code that works in workers as well as window scopes.
 
+use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use dom::bindings::conversions::FromJSValConvertible;
 use dom::bindings::js::{JS, JSRef, Root};
 use dom::bindings::utils::{Reflectable, Reflector};
+use dom::document::DocumentHelpers;
+use dom::sharedworkerglobalscope::SharedWorkerOwner;
 use dom::workerglobalscope::WorkerGlobalScope;
 use dom::window;
-use script_task::ScriptChan;
+use dom::window::{NetworkOwner, WindowHelpers};
+use script_task::{ScriptChan, ScriptMsg};
+use security::Origin;
+use task_source::TaskSource;
 
+use devtools_traits::ConsoleMessage;
+use servo_net::blob_loader::BlobURLOwner;
 use servo_net::resource_task::ResourceTask;
 
 use js::{JSCLASS_IS_GLOBAL, JSCLASS_IS_DOMJSCLASS};
@@ -79,6 +87,50 @@ impl<'a> GlobalRef<'a> {
         }
     }
 
+    /// The origin of the document of this global's window, or of the worker.
+    pub fn get_origin(&self) -> Origin {
+        match *self {
+            Window(ref window) => window.Document().root().origin(),
+            Worker(ref worker) => worker.origin().clone(),
+        }
+    }
+
+    /// What the `blob:` URLs created in this global are registered under.
+    pub fn blob_url_owner(&self) -> BlobURLOwner {
+        match *self {
+            Window(ref window) => window.blob_url_owner(),
+            Worker(ref worker) => worker.blob_url_owner(),
+        }
+    }
+
+    /// Keeps the shared worker that `owner` stands for running for as long as
+    /// this global exists.
+    pub fn add_shared_worker_owner(&self, owner: SharedWorkerOwner) {
+        match *self {
+            Window(ref window) => window.add_shared_worker_owner(owner),
+            Worker(ref worker) => worker.add_shared_worker_owner(owner),
+        }
+    }
+
+    /// Keeps track of `owner` while its fetch, connection or read goes on,
+    /// so that it is stopped when the document of this global's window is
+    /// unloaded. Workers are never unloaded.
+    #[allow(unrooted_must_root)]
+    pub fn track_network_owner(&self, owner: NetworkOwner) {
+        match *self {
+            Window(ref window) => window.track_network_owner(owner),
+            Worker(_) => {},
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn untrack_network_owner(&self, owner: NetworkOwner) {
+        match *self {
+            Window(ref window) => window.untrack_network_owner(owner),
+            Worker(_) => {},
+        }
+    }
+
     /// `ScriptChan` used to send messages to the event loop of this global's
     /// thread.
     pub fn script_chan<'b>(&'b self) -> &'b ScriptChan {
@@ -87,6 +139,22 @@ impl<'a> GlobalRef<'a> {
             Worker(ref worker) => worker.script_chan(),
         }
     }
+
+    /// Queues `msg` as a task from `source` on the event loop of this
+    /// global's thread. Returns false if that event loop is gone.
+    pub fn queue_task(&self, source: TaskSource, msg: ScriptMsg) -> bool {
+        self.script_chan().queue_task(source, msg)
+    }
+
+    /// Hands a message that was logged to the console of this global to the
+    /// devtools server, if there is one.
+    pub fn report_console_message(&self, message: ConsoleMessage) {
+        match *self {
+            Window(ref window) => window.report_console_message(message),
+            // TODO: Workers aren't known to the devtools server yet.
+            Worker(_) => {},
+        }
+    }
 }
 
 impl<'a> Reflectable for GlobalRef<'a> {
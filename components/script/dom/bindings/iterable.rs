@@ -0,0 +1,123 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The iteration methods of interfaces declaring a pair iterator.
+//! http://heycam.github.io/webidl/#idl-iterable
+//!
+//! The generated bindings ask the object for a snapshot of its pairs, and
+//! these helpers expose that snapshot through the engine's array iterators.
+
+use dom::bindings::conversions::ToJSValConvertible;
+use dom::bindings::error::throw_type_error;
+
+use js::JS_ARGV;
+use js::jsapi::{JSContext, JSObject, JSBool};
+use js::jsapi::{JS_NewArrayObject, JS_SetElement, JS_CallFunctionName};
+use js::jsapi::{JS_CallFunctionValue, JS_ObjectIsCallable};
+use js::jsval::{JSVal, ObjectValue, UndefinedValue};
+
+use libc;
+use std::ptr;
+
+/// What the iterator over a pair iterable yields.
+pub enum IterationKind {
+    /// `[key, value]` arrays.
+    Entries,
+    /// The keys alone.
+    Keys,
+    /// The values alone.
+    Values,
+}
+
+/// Returns a new, empty JS array, or null on failure.
+fn new_array(cx: *mut JSContext) -> *mut JSObject {
+    unsafe { JS_NewArrayObject(cx, 0, ptr::null_mut()) }
+}
+
+/// Appends `value` at `index` to `array`.
+fn set_element(cx: *mut JSContext, array: *mut JSObject, index: uint, value: JSVal) -> bool {
+    let mut value = value;
+    unsafe { JS_SetElement(cx, array, index as libc::c_uint, &mut value) != 0 }
+}
+
+/// Returns the `[key, value]` array of a pair, or null on failure.
+fn pair_to_array<K: ToJSValConvertible, V: ToJSValConvertible>(
+    cx: *mut JSContext, key: &K, value: &V) -> *mut JSObject {
+    let array = new_array(cx);
+    if array.is_null() ||
+       !set_element(cx, array, 0, key.to_jsval(cx)) ||
+       !set_element(cx, array, 1, value.to_jsval(cx)) {
+        return ptr::null_mut();
+    }
+    array
+}
+
+/// Sets the return value of the native called with `vp` to an iterator of
+/// the given kind over `pairs`.
+pub fn iterate<K: ToJSValConvertible, V: ToJSValConvertible>(
+    cx: *mut JSContext, pairs: Vec<(K, V)>, kind: IterationKind, vp: *mut JSVal) -> JSBool {
+    let array = new_array(cx);
+    if array.is_null() {
+        return 0;
+    }
+    for (index, &(ref key, ref value)) in pairs.iter().enumerate() {
+        let element = match kind {
+            Entries => {
+                let pair = pair_to_array(cx, key, value);
+                if pair.is_null() {
+                    return 0;
+                }
+                ObjectValue(unsafe { &*pair })
+            },
+            Keys => key.to_jsval(cx),
+            Values => value.to_jsval(cx),
+        };
+        if !set_element(cx, array, index, element) {
+            return 0;
+        }
+    }
+
+    let mut iterator = UndefinedValue();
+    let ok = "iterator".with_c_str(|name| unsafe {
+        JS_CallFunctionName(cx, array, name, 0, ptr::null_mut(), &mut iterator)
+    });
+    if ok == 0 {
+        return 0;
+    }
+    unsafe { *vp = iterator };
+    1
+}
+
+/// Implements `forEach(callback, thisArg)` for the native called with `vp`
+/// on `obj`: calls `callback` with the value, the key and `obj` of each of
+/// `pairs` in turn.
+pub fn for_each<K: ToJSValConvertible, V: ToJSValConvertible>(
+    cx: *mut JSContext, obj: *mut JSObject, pairs: Vec<(K, V)>,
+    argc: libc::c_uint, vp: *mut JSVal) -> JSBool {
+    let (callback, this_arg) = unsafe {
+        let argv = JS_ARGV(cx, vp);
+        let callback = if argc > 0 { *argv.offset(0) } else { UndefinedValue() };
+        let this_arg = if argc > 1 { *argv.offset(1) } else { UndefinedValue() };
+        (callback, this_arg)
+    };
+    if !callback.is_object() || unsafe { JS_ObjectIsCallable(cx, callback.to_object()) } == 0 {
+        throw_type_error(cx, "forEach's callback is not a function");
+        return 0;
+    }
+    let this_obj = if this_arg.is_object() { this_arg.to_object() } else { ptr::null_mut() };
+
+    for &(ref key, ref value) in pairs.iter() {
+        let mut argv = [value.to_jsval(cx), key.to_jsval(cx), ObjectValue(unsafe { &*obj })];
+        let mut rval = UndefinedValue();
+        let ok = unsafe {
+            JS_CallFunctionValue(cx, this_obj, callback, argv.len() as libc::c_uint,
+                                 argv.as_mut_ptr(), &mut rval)
+        };
+        if ok == 0 {
+            return 0;
+        }
+    }
+    unsafe { *vp = UndefinedValue() };
+    1
+}
@@ -16,6 +16,8 @@
 //! - `JS<T>`: a pointer to JS-owned memory that can automatically be traced by the GC when
 //!          encountered as a field of a Rust structure.
 //! - `Temporary<T>`: a value that will remain rooted for the duration of its lifetime.
+//! - `RootedVec<T>`: a list of values that remain rooted for as long as the list lives,
+//!                 however many of them there are.
 //!
 //! The rule of thumb is as follows:
 //!
@@ -45,17 +47,26 @@
 //! - `OptionalSettable`: allows assigning `Option` values of `JSRef`/`Temporary` to fields of `Option<JS<T>>`
 //! - `RootedReference`: makes obtaining an `Option<JSRef<T>>` from an `Option<Root<T>>` easy
 
+use dom::bindings::trace::JSTraceable;
 use dom::bindings::utils::{Reflector, Reflectable};
+use dom::eventsource::{EventSource, TrustedEventSourceAddress};
+use dom::filereader::{FileReader, TrustedFileReaderAddress};
+use dom::messageport::{MessagePort, TrustedMessagePortAddress};
 use dom::node::Node;
+use dom::promise::{Promise, TrustedPromiseAddress};
+use dom::websocket::{WebSocket, TrustedWebSocketAddress};
 use dom::xmlhttprequest::{XMLHttpRequest, TrustedXHRAddress};
 use dom::worker::{Worker, TrustedWorkerAddress};
-use js::jsapi::JSObject;
+use js::jsapi::{JSObject, JSTracer};
 use layout_interface::TrustedNodeAddress;
 use script_task::StackRoots;
+use servo_util::memory::HeapSizeOf;
 
+use libc;
 use std::cell::{Cell, RefCell};
 use std::kinds::marker::ContravariantLifetime;
 use std::mem;
+use std::slice;
 
 /// A type that represents a JS-owned value that is rooted for the lifetime of this value.
 /// Importantly, it requires explicit rooting in order to interact with the inner value.
@@ -128,6 +139,13 @@ impl <T> Clone for JS<T> {
     }
 }
 
+/// The object is owned by the JS heap, not by whatever points to it.
+impl<T> HeapSizeOf for JS<T> {
+    fn heap_size_of_children(&self) -> uint {
+        0
+    }
+}
+
 impl JS<Node> {
     /// Create a new JS-owned value wrapped from an address known to be a `Node` pointer.
     pub unsafe fn from_trusted_node_address(inner: TrustedNodeAddress) -> JS<Node> {
@@ -138,6 +156,51 @@ impl JS<Node> {
     }
 }
 
+impl JS<FileReader> {
+    pub unsafe fn from_trusted_file_reader_address(inner: TrustedFileReaderAddress) -> JS<FileReader> {
+        let TrustedFileReaderAddress(addr) = inner;
+        JS {
+            ptr: addr as *const FileReader
+        }
+    }
+}
+
+impl JS<EventSource> {
+    pub unsafe fn from_trusted_event_source_address(inner: TrustedEventSourceAddress) -> JS<EventSource> {
+        let TrustedEventSourceAddress(addr) = inner;
+        JS {
+            ptr: addr as *const EventSource
+        }
+    }
+}
+
+impl JS<MessagePort> {
+    pub unsafe fn from_trusted_message_port_address(inner: TrustedMessagePortAddress) -> JS<MessagePort> {
+        let TrustedMessagePortAddress(addr) = inner;
+        JS {
+            ptr: addr as *const MessagePort
+        }
+    }
+}
+
+impl JS<Promise> {
+    pub unsafe fn from_trusted_promise_address(inner: TrustedPromiseAddress) -> JS<Promise> {
+        let TrustedPromiseAddress(addr) = inner;
+        JS {
+            ptr: addr as *const Promise
+        }
+    }
+}
+
+impl JS<WebSocket> {
+    pub unsafe fn from_trusted_websocket_address(inner: TrustedWebSocketAddress) -> JS<WebSocket> {
+        let TrustedWebSocketAddress(addr) = inner;
+        JS {
+            ptr: addr as *const WebSocket
+        }
+    }
+}
+
 impl JS<XMLHttpRequest> {
     pub unsafe fn from_trusted_xhr_address(inner: TrustedXHRAddress) -> JS<XMLHttpRequest> {
         let TrustedXHRAddress(addr) = inner;
@@ -506,3 +569,112 @@ impl<'a, T: Reflectable> Reflectable for JSRef<'a, T> {
         self.deref().reflector()
     }
 }
+
+/// A list of the values that the `RootedVec`s of a task hold, which the
+/// garbage collector traces through `trace_rooted_collections`.
+local_data_key!(RootedCollections: RefCell<Vec<RootedCollection>>)
+
+/// The values of a `RootedVec`, with the function that traces them.
+struct RootedCollection {
+    values: *const libc::c_void,
+    trace: fn(*const libc::c_void, *mut JSTracer),
+}
+
+fn trace_rooted_values<T: Reflectable>(values: *const libc::c_void, trc: *mut JSTracer) {
+    let values = unsafe { &*(values as *const Vec<JS<T>>) };
+    values.trace(trc);
+}
+
+/// Traces the values of every `RootedVec` of this task.
+pub fn trace_rooted_collections(trc: *mut JSTracer) {
+    match RootedCollections.get() {
+        Some(collections) => {
+            for collection in collections.borrow().iter() {
+                (collection.trace)(collection.values, trc);
+            }
+        }
+        None => {}
+    }
+}
+
+/// A growable list of JS-managed values that stay rooted for as long as the
+/// list lives, even across garbage collections. Algorithms that collect any
+/// number of values, such as nodes that match a selector, should use this
+/// rather than keep `JSRef`s or `JS`s in a `Vec`, which nothing traces, or
+/// a `Root` per value.
+pub struct RootedVec<T> {
+    /// Boxed so that the values stay where the garbage collector traces them
+    /// when the `RootedVec` is moved.
+    values: Box<Vec<JS<T>>>,
+}
+
+impl<T: Reflectable> RootedVec<T> {
+    /// Create an empty list, whose values are traced until it is dropped.
+    pub fn new() -> RootedVec<T> {
+        let values: Box<Vec<JS<T>>> = box vec!();
+        if RootedCollections.get().is_none() {
+            RootedCollections.replace(Some(RefCell::new(vec!())));
+        }
+        RootedCollections.get().unwrap().borrow_mut().push(RootedCollection {
+            values: &*values as *const Vec<JS<T>> as *const libc::c_void,
+            trace: trace_rooted_values::<T>,
+        });
+        RootedVec {
+            values: values,
+        }
+    }
+
+    pub fn push(&mut self, value: JSRef<T>) {
+        self.values.push(JS::from_rooted(value));
+    }
+
+    pub fn len(&self) -> uint {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Iterates over the values, which stay rooted for as long as the list
+    /// is borrowed.
+    pub fn iter<'a>(&'a self) -> RootedVecItems<'a, T> {
+        RootedVecItems {
+            iter: self.values.iter(),
+        }
+    }
+}
+
+#[unsafe_destructor]
+impl<T: Reflectable> Drop for RootedVec<T> {
+    fn drop(&mut self) {
+        let values = &*self.values as *const Vec<JS<T>> as *const libc::c_void;
+        let collections = RootedCollections.get().unwrap();
+        let mut collections = collections.borrow_mut();
+        let index = collections.iter().rposition(|collection| collection.values == values);
+        collections.remove(index.unwrap());
+    }
+}
+
+/// An iterator over the values of a `RootedVec`.
+pub struct RootedVecItems<'a, T: 'a> {
+    iter: slice::Items<'a, JS<T>>,
+}
+
+impl<'a, T: Reflectable> Iterator<JSRef<'a, T>> for RootedVecItems<'a, T> {
+    fn next(&mut self) -> Option<JSRef<'a, T>> {
+        self.iter.next().map(|value| JSRef {
+            ptr: value.ptr,
+            chain: ContravariantLifetime,
+        })
+    }
+}
+
+impl<'a, T: Reflectable> DoubleEndedIterator<JSRef<'a, T>> for RootedVecItems<'a, T> {
+    fn next_back(&mut self) -> Option<JSRef<'a, T>> {
+        self.iter.next_back().map(|value| JSRef {
+            ptr: value.ptr,
+            chain: ContravariantLifetime,
+        })
+    }
+}
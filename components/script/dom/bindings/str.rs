@@ -49,7 +49,7 @@ impl ByteString {
     pub fn to_lower(&self) -> ByteString {
         let ByteString(ref vec) = *self;
         ByteString::new(vec.iter().map(|&x| {
-            if x > 'A' as u8 && x < 'Z' as u8 {
+            if x >= 'A' as u8 && x <= 'Z' as u8 {
                 x + ('a' as u8) - ('A' as u8)
             } else {
                 x
@@ -0,0 +1,60 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Structured cloning, which carries the messages of postMessage() from one
+//! global to another.
+//! http://www.whatwg.org/html/#safe-passing-of-structured-data
+
+use dom::bindings::error::{Fallible, DataClone};
+use dom::bindings::global::GlobalRef;
+
+use js::glue::JS_STRUCTURED_CLONE_VERSION;
+use js::jsapi::JSContext;
+use js::jsapi::{JS_WriteStructuredClone, JS_ReadStructuredClone, JS_ClearPendingException};
+use js::jsval::{JSVal, UndefinedValue};
+
+use libc::size_t;
+use std::ptr;
+
+/// The structured clone of a value, which can be sent to another task.
+pub struct StructuredCloneData {
+    data: *mut u64,
+    nbytes: size_t,
+}
+
+impl StructuredCloneData {
+    /// Writes a structured clone of `message`, or fails with a DataCloneError
+    /// if it can't be cloned.
+    pub fn write(cx: *mut JSContext, message: JSVal) -> Fallible<StructuredCloneData> {
+        let mut data = ptr::null_mut();
+        let mut nbytes = 0;
+        let result = unsafe {
+            JS_WriteStructuredClone(cx, message, &mut data, &mut nbytes,
+                                    ptr::null(), ptr::null_mut())
+        };
+        if result == 0 {
+            // The engine throws its own error, which a DataCloneError replaces.
+            unsafe {
+                JS_ClearPendingException(cx);
+            }
+            return Err(DataClone);
+        }
+        Ok(StructuredCloneData {
+            data: data,
+            nbytes: nbytes,
+        })
+    }
+
+    /// Reads the clone back as a value of `global`.
+    pub fn read(self, global: &GlobalRef) -> JSVal {
+        let mut message = UndefinedValue();
+        unsafe {
+            assert!(JS_ReadStructuredClone(
+                global.get_cx(), self.data as *const u64, self.nbytes,
+                JS_STRUCTURED_CLONE_VERSION, &mut message,
+                ptr::null(), ptr::null_mut()) != 0);
+        }
+        message
+    }
+}
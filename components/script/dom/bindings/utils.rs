@@ -7,21 +7,27 @@
 use dom::bindings::codegen::PrototypeList;
 use dom::bindings::codegen::PrototypeList::MAX_PROTO_CHAIN_LENGTH;
 use dom::bindings::conversions::IDLInterface;
-use dom::bindings::error::throw_type_error;
-use dom::bindings::global::GlobalRef;
+use dom::bindings::codegen::Bindings::WorkerGlobalScopeBinding::WorkerGlobalScopeMethods;
+use dom::bindings::error::{throw_internal_error, throw_type_error};
+use dom::bindings::global;
+use dom::bindings::global::{GlobalRef, global_object_for_js_object};
 use dom::bindings::js::{JS, Temporary, Root};
 use dom::bindings::trace::Untraceable;
 use dom::browsercontext;
 use dom::window;
+use servo_msg::constellation_msg::PipelineId;
 use servo_util::str::DOMString;
 
 use libc;
 use libc::c_uint;
+use rustrt::unwind;
 use std::cell::Cell;
+use std::intrinsics;
 use std::mem;
 use std::cmp::PartialEq;
 use std::ptr;
 use std::slice;
+use std::task;
 use js::glue::{js_IsObjectProxyClass, js_IsFunctionProxyClass, IsProxyHandlerFamily};
 use js::glue::{UnwrapObject, GetProxyHandlerExtra};
 use js::glue::{IsWrapper, RUST_JSID_TO_STRING, RUST_JSID_IS_INT};
@@ -39,8 +45,10 @@ use js::jsapi::{JSContext, JSObject, JSBool, jsid, JSClass};
 use js::jsapi::{JSFunctionSpec, JSPropertySpec};
 use js::jsapi::{JS_NewGlobalObject, JS_InitStandardClasses};
 use js::jsapi::{JSString};
+use js::jsapi::JS_GetGlobalForScopeChain;
 use js::jsapi::JS_DeletePropertyById2;
 use js::jsfriendapi::JS_ObjectToOuterObject;
+use js::jsfriendapi::bindgen::{JS_NewArrayBuffer, JS_GetArrayBufferData};
 use js::jsfriendapi::bindgen::JS_NewObjectWithUniqueType;
 use js::jsval::JSVal;
 use js::jsval::{PrivateValue, ObjectValue, NullValue};
@@ -62,6 +70,59 @@ pub fn GlobalStaticData() -> GlobalStaticData {
     }
 }
 
+/// Aborts the process if the task unwinds while it is alive. The hooks that
+/// the garbage collector calls hold one: a panic in them would unwind
+/// through the frames of the JS engine, and there is no script to report it
+/// to.
+pub struct AbortOnUnwind;
+
+impl Drop for AbortOnUnwind {
+    fn drop(&mut self) {
+        if task::failing() {
+            error!("a garbage collector hook panicked; aborting");
+            unsafe { intrinsics::abort() }
+        }
+    }
+}
+
+/// The pipelines whose pages a DOM method panicked in, until the script task
+/// marks them as crashed.
+local_data_key!(PanickedPipelines: Vec<PipelineId>)
+
+/// Returns the pipelines whose pages a DOM method panicked in since the last
+/// call, and forgets them.
+pub fn take_panicked_pipelines() -> Vec<PipelineId> {
+    PanickedPipelines.replace(None).unwrap_or(vec!())
+}
+
+/// Runs `f`, the body of a function that the JS engine called with `cx`, and
+/// catches a panic in it, which can't unwind through the frames of the
+/// engine. The panic is thrown to script as an exception and `failed` is
+/// returned instead. The page of the global that script runs in is marked as
+/// crashed once the script task gets control back, and a worker closes.
+pub fn catch_dom_panic<T>(cx: *mut JSContext, failed: T, f: || -> T) -> T {
+    let mut result = None;
+    let caught = unsafe { unwind::try(|| result = Some(f())) };
+    if caught.is_ok() {
+        return result.unwrap();
+    }
+
+    error!("a DOM method called from script panicked");
+    throw_internal_error(cx, "a DOM method panicked");
+    let global = unsafe {
+        global_object_for_js_object(JS_GetGlobalForScopeChain(cx)).root()
+    };
+    match global.root_ref() {
+        global::Window(window) => {
+            let mut ids = take_panicked_pipelines();
+            ids.push(window.page().id);
+            PanickedPipelines.replace(Some(ids));
+        },
+        global::Worker(worker) => worker.Close(),
+    }
+    failed
+}
+
 /// Returns whether the given `clasp` is one for a DOM object.
 fn is_dom_class(clasp: *const JSClass) -> bool {
     unsafe {
@@ -172,6 +233,17 @@ pub fn jsstring_to_str(cx: *mut JSContext, s: *mut JSString) -> DOMString {
     }
 }
 
+/// Creates an `ArrayBuffer` holding a copy of `bytes`.
+pub fn new_array_buffer(cx: *mut JSContext, bytes: &[u8]) -> JSVal {
+    unsafe {
+        let buffer = JS_NewArrayBuffer(cx, bytes.len() as u32);
+        assert!(buffer.is_not_null());
+        let data = JS_GetArrayBufferData(buffer, cx);
+        ptr::copy_nonoverlapping_memory(data, bytes.as_ptr(), bytes.len());
+        ObjectValue(&*buffer)
+    }
+}
+
 /// Convert the given `jsid` to a `DOMString`. Fails if the `jsid` is not a
 /// string, or if the string does not contain valid UTF-16.
 pub fn jsid_to_str(cx: *mut JSContext, id: jsid) -> DOMString {
@@ -290,6 +362,7 @@ pub fn CreateInterfaceObjects2(cx: *mut JSContext, global: *mut JSObject, receiv
                                protoProto: *mut JSObject,
                                protoClass: &'static JSClass,
                                constructor: Option<(NonNullJSNative, &'static str, u32)>,
+                               namedConstructors: &[(NonNullJSNative, &'static str, u32)],
                                domClass: *const DOMClass,
                                members: &'static NativeProperties) -> *mut JSObject {
     let proto = CreateInterfacePrototypeObject(cx, global, protoProto,
@@ -310,9 +383,47 @@ pub fn CreateInterfaceObjects2(cx: *mut JSContext, global: *mut JSObject, receiv
         None => (),
     }
 
+    for &(native, name, nargs) in namedConstructors.iter() {
+        let s = name.to_c_str();
+        CreateNamedConstructor(cx, global, receiver, native, nargs, proto, s.as_ptr());
+    }
+
     proto
 }
 
+/// Creates a named constructor, such as `Audio` for `HTMLAudioElement`, whose
+/// `prototype` is the interface prototype object `proto`.
+/// Fails on JSAPI failure.
+fn CreateNamedConstructor(cx: *mut JSContext, global: *mut JSObject, receiver: *mut JSObject,
+                          constructorNative: NonNullJSNative,
+                          ctorNargs: u32, proto: *mut JSObject,
+                          name: *const libc::c_char) {
+    unsafe {
+        let fun = JS_NewFunction(cx, Some(constructorNative), ctorNargs,
+                                 JSFUN_CONSTRUCTOR, global, name);
+        assert!(fun.is_not_null());
+
+        let constructor = JS_GetFunctionObject(fun);
+        assert!(constructor.is_not_null());
+
+        // Unlike JS_LinkConstructorAndPrototype, this leaves the prototype's
+        // `constructor` property pointing at the interface object.
+        let prototype = "prototype".to_c_str();
+        assert!(JS_DefineProperty(cx, constructor, prototype.as_ptr(),
+                                  ObjectValue(&*proto), None, None,
+                                  JSPROP_READONLY | JSPROP_PERMANENT) != 0);
+
+        let mut alreadyDefined = 0;
+        assert!(JS_AlreadyHasOwnProperty(cx, receiver, name, &mut alreadyDefined) != 0);
+
+        if alreadyDefined == 0 {
+            assert!(JS_DefineProperty(cx, receiver, name,
+                                      ObjectValue(&*constructor),
+                                      None, None, 0) != 0);
+        }
+    }
+}
+
 /// Creates the *interface object*.
 /// Fails on JSAPI failure.
 fn CreateInterfaceObject(cx: *mut JSContext, global: *mut JSObject, receiver: *mut JSObject,
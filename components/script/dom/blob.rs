@@ -3,11 +3,19 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::bindings::codegen::InheritTypes::FileDerived;
-use dom::bindings::global::GlobalRef;
-use dom::bindings::js::Temporary;
+use dom::bindings::codegen::Bindings::BlobBinding;
+use dom::bindings::codegen::Bindings::BlobBinding::{BlobMethods, BlobPropertyBag};
+use dom::bindings::codegen::UnionTypes::ArrayBufferOrBlobOrString::{ArrayBufferOrBlobOrString, eArrayBuffer, eBlob, eString};
+use dom::bindings::global::{GlobalRef, GlobalField};
+use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
 use dom::bindings::error::Fallible;
-use dom::bindings::codegen::Bindings::BlobBinding;
+use servo_util::str::DOMString;
+
+use js::jsfriendapi::bindgen::{JS_GetArrayBufferByteLength, JS_GetArrayBufferData};
+use std::ascii::OwnedStrAsciiExt;
+use std::cmp::{max, min};
+use std::slice;
 
 #[jstraceable]
 pub enum BlobType {
@@ -19,25 +27,113 @@ pub enum BlobType {
 #[must_root]
 pub struct Blob {
     reflector_: Reflector,
-    type_: BlobType
+    type_: BlobType,
+    global: GlobalField,
+    bytes: Vec<u8>,
+    /// The type of the data, lowercased, or the empty string if unknown.
+    type_string: DOMString,
 }
 
 impl Blob {
-    pub fn new_inherited() -> Blob {
+    pub fn new_inherited(global: &GlobalRef, type_: BlobType, bytes: Vec<u8>,
+                         type_string: DOMString) -> Blob {
         Blob {
             reflector_: Reflector::new(),
-            type_: BlobTypeId
+            type_: type_,
+            global: GlobalField::from_rooted(global),
+            bytes: bytes,
+            type_string: normalize_type(type_string),
         }
     }
 
-    pub fn new(global: &GlobalRef) -> Temporary<Blob> {
-        reflect_dom_object(box Blob::new_inherited(),
+    pub fn new(global: &GlobalRef, bytes: Vec<u8>, type_string: DOMString) -> Temporary<Blob> {
+        reflect_dom_object(box Blob::new_inherited(global, BlobTypeId, bytes, type_string),
                            global,
                            BlobBinding::Wrap)
     }
 
-    pub fn Constructor(global: &GlobalRef) -> Fallible<Temporary<Blob>> {
-        Ok(Blob::new(global))
+    // http://dev.w3.org/2006/webapi/FileAPI/#constructorBlob
+    pub fn Constructor(global: &GlobalRef, blob_parts: Option<Vec<ArrayBufferOrBlobOrString>>,
+                       options: &BlobPropertyBag) -> Fallible<Temporary<Blob>> {
+        let bytes = match blob_parts {
+            Some(blob_parts) => bytes_of_parts(global, blob_parts),
+            None => vec!(),
+        };
+        Ok(Blob::new(global, bytes, options.type_.clone()))
+    }
+}
+
+/// Returns the concatenated bytes of `parts`.
+/// http://dev.w3.org/2006/webapi/FileAPI/#process-blob-parts
+pub fn bytes_of_parts(global: &GlobalRef, parts: Vec<ArrayBufferOrBlobOrString>) -> Vec<u8> {
+    let mut bytes = vec!();
+    for part in parts.into_iter() {
+        match part {
+            eString(string) => bytes.push_all(string.as_bytes()),
+            eBlob(blob) => bytes.push_all(blob.root().bytes.as_slice()),
+            eArrayBuffer(buffer) => unsafe {
+                let cx = global.get_cx();
+                let length = JS_GetArrayBufferByteLength(buffer, cx);
+                let data = JS_GetArrayBufferData(buffer, cx);
+                slice::raw::buf_as_slice(data as *const u8, length as uint, |data| {
+                    bytes.push_all(data);
+                });
+            },
+        }
+    }
+    bytes
+}
+
+/// Returns the lowercased `type_`, or the empty string if it has characters
+/// outside U+0020 to U+007E.
+fn normalize_type(type_: DOMString) -> DOMString {
+    if type_.as_slice().chars().any(|c| c < '\x20' || c > '\x7E') {
+        "".to_string()
+    } else {
+        type_.into_ascii_lower()
+    }
+}
+
+pub trait BlobHelpers {
+    fn bytes(self) -> Vec<u8>;
+    fn type_string(self) -> DOMString;
+}
+
+impl<'a> BlobHelpers for JSRef<'a, Blob> {
+    fn bytes(self) -> Vec<u8> {
+        self.bytes.clone()
+    }
+
+    fn type_string(self) -> DOMString {
+        self.type_string.clone()
+    }
+}
+
+impl<'a> BlobMethods for JSRef<'a, Blob> {
+    // http://dev.w3.org/2006/webapi/FileAPI/#dfn-size
+    fn Size(self) -> u64 {
+        self.bytes.len() as u64
+    }
+
+    // http://dev.w3.org/2006/webapi/FileAPI/#dfn-type
+    fn Type(self) -> DOMString {
+        self.type_string.clone()
+    }
+
+    // http://dev.w3.org/2006/webapi/FileAPI/#slice-method-algo
+    fn Slice(self, start: Option<i64>, end: Option<i64>,
+             content_type: Option<DOMString>) -> Temporary<Blob> {
+        // Negative indices count from the end.
+        let size = self.bytes.len() as i64;
+        let relative = |index: i64| {
+            if index < 0 { max(size + index, 0) } else { min(index, size) }
+        };
+        let start = relative(start.unwrap_or(0));
+        let end = relative(end.unwrap_or(size));
+        let end = max(start, end);
+        let bytes = self.bytes.slice(start as uint, end as uint).to_vec();
+        let global = self.global.root();
+        Blob::new(&global.root_ref(), bytes, content_type.unwrap_or("".to_string()))
     }
 }
 
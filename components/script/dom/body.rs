@@ -0,0 +1,226 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! What `Request` and `Response` share through the `Body` interface.
+//! http://fetch.spec.whatwg.org/#body-mixin
+
+use dom::bindings::conversions::ToJSValConvertible;
+use dom::bindings::error::Type;
+use dom::bindings::global::GlobalRef;
+use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::str::ByteString;
+use dom::bindings::utils::new_array_buffer;
+use dom::blob::Blob;
+use dom::formdata::{FormData, header_parameter, parse_multipart};
+use dom::headers::{Headers, HeadersHelpers};
+use dom::promise::{Promise, PromiseHelpers, PromiseSettler, catch_exception};
+use dom::urlsearchparams;
+use dom::xmlhttprequest::{Extractable, SendParam};
+use servo_util::task::spawn_named;
+
+use encoding::all::UTF_8;
+use encoding::types::{DecodeReplace, Encoding};
+
+use js::jsapi::JS_ParseJSON;
+use js::jsval::UndefinedValue;
+
+use std::ascii::StrAsciiExt;
+use std::cell::{Cell, RefCell};
+use std::comm::{channel, Receiver};
+use std::mem;
+
+/// The body of a request or a response.
+pub enum BodyState {
+    NoBody,
+    CompleteBody(Vec<u8>),
+    /// The fetch of the response is still receiving the body, which it sends
+    /// on the port once all of it is there, or an error if the fetch fails.
+    PendingBody(Receiver<Result<Vec<u8>, ()>>),
+}
+
+impl BodyState {
+    pub fn from_bytes(bytes: Option<Vec<u8>>) -> BodyState {
+        match bytes {
+            Some(bytes) => CompleteBody(bytes),
+            None => NoBody,
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        match *self {
+            NoBody => true,
+            _ => false,
+        }
+    }
+}
+
+/// How the body-reading methods package the bytes of a body.
+/// http://fetch.spec.whatwg.org/#concept-body-package-data
+pub enum BodyType {
+    ArrayBufferBody,
+    BlobBody,
+    FormDataBody,
+    JsonBody,
+    TextBody,
+}
+
+/// Returns the MIME type of a body, as its `Content-Type` header gives it,
+/// or the empty string if it has none.
+/// http://fetch.spec.whatwg.org/#concept-body-mime-type
+pub fn mime_type(headers: JSRef<Headers>) -> String {
+    let name: ByteString = from_str("Content-Type").unwrap();
+    headers.get(&name).and_then(|value| value.as_str().map(|value| value.to_string()))
+           .unwrap_or("".to_string())
+}
+
+/// Returns the bytes of `init`, and gives `headers` the `Content-Type` of
+/// `init` unless they already have one.
+/// http://fetch.spec.whatwg.org/#concept-fetchbodyinit-extract
+pub fn extract_body(headers: JSRef<Headers>, init: &SendParam) -> Vec<u8> {
+    let (bytes, media_type) = init.extract();
+    let mut content_type = format!("{}/{}", media_type.type_, media_type.subtype);
+    for &(ref name, ref value) in media_type.parameters.iter() {
+        content_type.push_str(format!(";{}={}", name, value).as_slice());
+    }
+    let name: ByteString = from_str("Content-Type").unwrap();
+    if headers.get(&name).is_none() {
+        headers.append(name, from_str(content_type.as_slice()).unwrap());
+    }
+    bytes
+}
+
+/// Takes the body out of `body` for another request, which makes it used if
+/// there is one.
+pub fn take_body(body: &RefCell<BodyState>, body_used: &Cell<bool>) -> BodyState {
+    let state = mem::replace(&mut *body.borrow_mut(), NoBody);
+    if !state.is_none() {
+        body_used.set(true);
+    }
+    state
+}
+
+/// Returns a copy of `body`, for clone(). A body that is still being
+/// received is received by both copies.
+pub fn tee_body(body: &RefCell<BodyState>) -> BodyState {
+    let mut body = body.borrow_mut();
+    let port = match mem::replace(&mut *body, NoBody) {
+        NoBody => return NoBody,
+        CompleteBody(bytes) => {
+            *body = CompleteBody(bytes.clone());
+            return CompleteBody(bytes);
+        },
+        PendingBody(port) => port,
+    };
+    let (chan, tee_port) = channel();
+    let (copy_chan, copy_port) = channel();
+    spawn_named("Body:tee", proc() {
+        let bytes = port.recv_opt().unwrap_or(Err(()));
+        let _ = chan.send_opt(bytes.clone());
+        let _ = copy_chan.send_opt(bytes);
+    });
+    *body = PendingBody(tee_port);
+    PendingBody(copy_port)
+}
+
+/// Reads `body`, whose MIME type is `mime_type`, as `type_` once all of it
+/// is there. The promise rejects if the body was read already.
+/// http://fetch.spec.whatwg.org/#concept-body-consume-body
+pub fn consume_body(global: &GlobalRef, body: &RefCell<BodyState>, body_used: &Cell<bool>,
+                    type_: BodyType, mime_type: String) -> Temporary<Promise> {
+    if body_used.get() {
+        return Promise::new_rejected(global, Type("The body was already used".to_string()));
+    }
+
+    let promise = Promise::new(global).root();
+    match take_body(body, body_used) {
+        NoBody => package_body(global, *promise, type_, mime_type, Ok(vec!())),
+        CompleteBody(bytes) => package_body(global, *promise, type_, mime_type, Ok(bytes)),
+        PendingBody(port) => {
+            let trusted = promise.trusted();
+            spawn_named("Body:consume", proc() {
+                let bytes = port.recv_opt().unwrap_or(Err(()));
+                trusted.settle_with(box ConsumedBody {
+                    type_: type_,
+                    mime_type: mime_type,
+                    bytes: bytes,
+                });
+            });
+        },
+    }
+    Temporary::from_rooted(*promise)
+}
+
+/// Resolves `promise` with the body as `type_` asks, or rejects it if the
+/// body couldn't be received or doesn't parse as a form.
+/// http://fetch.spec.whatwg.org/#concept-body-package-data
+fn package_body(global: &GlobalRef, promise: JSRef<Promise>, type_: BodyType,
+                mime_type: String, bytes: Result<Vec<u8>, ()>) {
+    let cx = global.get_cx();
+    let bytes = match bytes {
+        Ok(bytes) => bytes,
+        Err(()) => return promise.reject_error(Type("The body couldn't be received".to_string())),
+    };
+    let value = match type_ {
+        BlobBody => Blob::new(global, bytes, mime_type).root().to_jsval(cx),
+        FormDataBody => {
+            match parse_form(global, bytes.as_slice(), mime_type.as_slice()) {
+                Some(form_data) => form_data.root().to_jsval(cx),
+                None => return promise.reject_error(Type("The body isn't a form".to_string())),
+            }
+        },
+        TextBody => UTF_8.decode(bytes.as_slice(), DecodeReplace).unwrap().to_jsval(cx),
+        JsonBody => {
+            let text = UTF_8.decode(bytes.as_slice(), DecodeReplace).unwrap();
+            let text: Vec<u16> = text.as_slice().utf16_units().collect();
+            let parsed = catch_exception(cx, || {
+                let mut value = UndefinedValue();
+                unsafe {
+                    JS_ParseJSON(cx, text.as_ptr(), text.len() as u32, &mut value);
+                }
+                value
+            });
+            match parsed {
+                Ok(value) => value,
+                // The SyntaxError the parser threw.
+                Err(exception) => return promise.reject_native(exception),
+            }
+        },
+        ArrayBufferBody => new_array_buffer(cx, bytes.as_slice()),
+    };
+    promise.resolve_native(cx, value);
+}
+
+/// A body that was received on another task, for the promise of the method
+/// that read it.
+struct ConsumedBody {
+    type_: BodyType,
+    mime_type: String,
+    bytes: Result<Vec<u8>, ()>,
+}
+
+impl PromiseSettler for ConsumedBody {
+    fn settle(self: Box<Self>, global: &GlobalRef, promise: JSRef<Promise>) {
+        let ConsumedBody { type_, mime_type, bytes } = *self;
+        package_body(global, promise, type_, mime_type, bytes);
+    }
+}
+
+/// Returns the entries of a body in the `multipart/form-data` or the
+/// `application/x-www-form-urlencoded` format, as `mime_type` says, or None
+/// if it is in neither or doesn't parse.
+fn parse_form(global: &GlobalRef, bytes: &[u8], mime_type: &str) -> Option<Temporary<FormData>> {
+    let essence = mime_type.split(';').next().unwrap().trim().to_ascii_lower();
+    match essence.as_slice() {
+        "multipart/form-data" => {
+            header_parameter(mime_type, "boundary").and_then(|boundary| {
+                parse_multipart(global, bytes, boundary.as_slice())
+            })
+        },
+        "application/x-www-form-urlencoded" => {
+            let text = String::from_utf8_lossy(bytes).into_string();
+            Some(FormData::new_with_entries(global, urlsearchparams::parse(text.as_slice())))
+        },
+        _ => None,
+    }
+}
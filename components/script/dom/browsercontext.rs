@@ -8,7 +8,7 @@ use dom::bindings::utils::Reflectable;
 use dom::document::Document;
 use dom::window::Window;
 
-use js::jsapi::JSObject;
+use js::jsapi::{JSObject, JS_TransplantObject};
 use js::glue::{WrapperNew, CreateWrapperProxyHandler, ProxyTraps};
 use js::rust::with_compartment;
 
@@ -24,16 +24,27 @@ pub struct BrowserContext {
 }
 
 impl BrowserContext {
-    pub fn new(document: JSRef<Document>) -> BrowserContext {
+    /// Creates the browsing context that shows `document`. If the browsing
+    /// context navigated there from another document, `previous_proxy` is the
+    /// WindowProxy of the window of that document, which is taken over so
+    /// that scripts that hold it see the new window.
+    pub fn new(document: JSRef<Document>,
+               previous_proxy: Option<*mut JSObject>) -> BrowserContext {
         let mut context = BrowserContext {
             history: vec!(SessionHistoryEntry::new(document)),
             active_index: 0,
             window_proxy: Traceable::new(ptr::null_mut()),
         };
-        context.create_window_proxy();
+        context.create_window_proxy(previous_proxy);
         context
     }
 
+    /// Makes `document` the active document in place of the initial
+    /// about:blank one, whose window it took over.
+    pub fn replace_active_document(&mut self, document: JSRef<Document>) {
+        *self.history.get_mut(self.active_index) = SessionHistoryEntry::new(document);
+    }
+
     pub fn active_document(&self) -> Temporary<Document> {
         Temporary::new(self.history[self.active_index].document.clone())
     }
@@ -48,7 +59,7 @@ impl BrowserContext {
         *self.window_proxy
     }
 
-    fn create_window_proxy(&mut self) {
+    fn create_window_proxy(&mut self, previous_proxy: Option<*mut JSObject>) {
         let win = self.active_window().root();
         let page = win.deref().page();
         let js_info = page.js_info();
@@ -59,7 +70,15 @@ impl BrowserContext {
         let parent = win.deref().reflector().get_jsobject();
         let cx = js_info.as_ref().unwrap().js_context.deref().deref().ptr;
         let wrapper = with_compartment(cx, parent, || unsafe {
-            WrapperNew(cx, parent, *handler.deref())
+            let wrapper = WrapperNew(cx, parent, *handler.deref());
+            assert!(wrapper.is_not_null());
+            // Every reference to the previous proxy now leads to the new
+            // one, which keeps the identity of the WindowProxy stable across
+            // navigations.
+            match previous_proxy {
+                Some(previous_proxy) => JS_TransplantObject(cx, previous_proxy, wrapper),
+                None => wrapper,
+            }
         });
         assert!(wrapper.is_not_null());
         self.window_proxy = Traceable::new(wrapper);
@@ -82,6 +101,10 @@ impl SessionHistoryEntry {
     }
 }
 
+/// The traps of the WindowProxy, which forward everything to the window it
+/// wraps.
+/// TODO: Only same-origin documents share a script task so far, so no trap
+/// checks for cross-origin access.
 static proxy_handler: ProxyTraps = ProxyTraps {
     getPropertyDescriptor: None,
     getOwnPropertyDescriptor: None,
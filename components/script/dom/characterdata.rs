@@ -7,12 +7,14 @@
 use dom::bindings::codegen::Bindings::CharacterDataBinding::CharacterDataMethods;
 use dom::bindings::codegen::InheritTypes::{CharacterDataDerived, NodeCast};
 use dom::bindings::error::{Fallible, ErrorResult, IndexSize};
-use dom::bindings::js::JSRef;
+use dom::bindings::js::{JSRef, OptionalRootable};
 use dom::bindings::trace::Traceable;
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::node::{CommentNodeTypeId, Node, NodeTypeId, TextNodeTypeId, ProcessingInstructionNodeTypeId, NodeHelpers};
+use dom::node::ContentDamaged;
+use dom::virtualmethods::vtable_for;
 use servo_util::str::DOMString;
 
 use std::cell::RefCell;
@@ -50,7 +52,7 @@ impl<'a> CharacterDataMethods for JSRef<'a, CharacterData> {
     }
 
     fn SetData(self, arg: DOMString) -> ErrorResult {
-        *self.data.deref().borrow_mut() = arg;
+        self.set_data(arg);
         Ok(())
     }
 
@@ -63,7 +65,9 @@ impl<'a> CharacterDataMethods for JSRef<'a, CharacterData> {
     }
 
     fn AppendData(self, arg: DOMString) -> ErrorResult {
-        self.data.deref().borrow_mut().push_str(arg.as_slice());
+        let mut data = self.data.deref().borrow().clone();
+        data.push_str(arg.as_slice());
+        self.set_data(data);
         Ok(())
     }
 
@@ -88,7 +92,7 @@ impl<'a> CharacterDataMethods for JSRef<'a, CharacterData> {
         let mut data = self.data.deref().borrow().as_slice().slice(0, offset as uint).to_string();
         data.push_str(arg.as_slice());
         data.push_str(self.data.deref().borrow().as_slice().slice((offset + count) as uint, length as uint));
-        *self.data.deref().borrow_mut() = data;
+        self.set_data(data);
         // FIXME: Once we have `Range`, we should implement step7 to step11
         Ok(())
     }
@@ -100,6 +104,23 @@ impl<'a> CharacterDataMethods for JSRef<'a, CharacterData> {
     }
 }
 
+pub trait CharacterDataHelpers {
+    fn set_data(self, data: DOMString);
+}
+
+impl<'a> CharacterDataHelpers for JSRef<'a, CharacterData> {
+    /// Replaces the data, and tells the parent about it.
+    fn set_data(self, data: DOMString) {
+        *self.data.deref().borrow_mut() = data;
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        node.dirty(ContentDamaged);
+        match node.parent_node().root() {
+            Some(parent) => vtable_for(&*parent).child_data_changed(node),
+            None => {}
+        }
+    }
+}
+
 impl Reflectable for CharacterData {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.node.reflector()
@@ -4,48 +4,67 @@
 
 use dom::bindings::codegen::Bindings::ConsoleBinding;
 use dom::bindings::codegen::Bindings::ConsoleBinding::ConsoleMethods;
-use dom::bindings::global::GlobalRef;
+use dom::bindings::global::{GlobalField, GlobalRef};
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use devtools_traits::{ConsoleLevel, ConsoleMessage, LogLevel, DebugLevel, InfoLevel, WarnLevel};
+use devtools_traits::ErrorLevel;
 use servo_util::str::DOMString;
 
 #[jstraceable]
 #[must_root]
 pub struct Console {
-    pub reflector_: Reflector
+    pub reflector_: Reflector,
+    global: GlobalField,
 }
 
 impl Console {
-    fn new_inherited() -> Console {
+    fn new_inherited(global: &GlobalRef) -> Console {
         Console {
-            reflector_: Reflector::new()
+            reflector_: Reflector::new(),
+            global: GlobalField::from_rooted(global),
         }
     }
 
     pub fn new(global: &GlobalRef) -> Temporary<Console> {
-        reflect_dom_object(box Console::new_inherited(), global, ConsoleBinding::Wrap)
+        reflect_dom_object(box Console::new_inherited(global), global, ConsoleBinding::Wrap)
+    }
+}
+
+trait PrivateConsoleHelpers {
+    fn log(self, level: ConsoleLevel, message: DOMString);
+}
+
+impl<'a> PrivateConsoleHelpers for JSRef<'a, Console> {
+    /// Prints `message`, and hands it to the devtools of the global.
+    fn log(self, level: ConsoleLevel, message: DOMString) {
+        println!("{:s}", message);
+        self.global.root().root_ref().report_console_message(ConsoleMessage {
+            level: level,
+            message: message,
+        });
     }
 }
 
 impl<'a> ConsoleMethods for JSRef<'a, Console> {
     fn Log(self, message: DOMString) {
-        println!("{:s}", message);
+        self.log(LogLevel, message);
     }
 
     fn Debug(self, message: DOMString) {
-        println!("{:s}", message);
+        self.log(DebugLevel, message);
     }
 
     fn Info(self, message: DOMString) {
-        println!("{:s}", message);
+        self.log(InfoLevel, message);
     }
 
     fn Warn(self, message: DOMString) {
-        println!("{:s}", message);
+        self.log(WarnLevel, message);
     }
 
     fn Error(self, message: DOMString) {
-        println!("{:s}", message);
+        self.log(ErrorLevel, message);
     }
 
     fn Assert(self, condition: bool, message: Option<DOMString>) {
@@ -54,7 +73,7 @@ impl<'a> ConsoleMethods for JSRef<'a, Console> {
                 Some(ref message) => message.as_slice(),
                 None => "no message",
             };
-            println!("Assertion failed: {:s}", message);
+            self.log(ErrorLevel, format!("Assertion failed: {:s}", message));
         }
     }
 }
@@ -6,41 +6,85 @@ use dom::bindings::codegen::Bindings::DedicatedWorkerGlobalScopeBinding;
 use dom::bindings::codegen::Bindings::DedicatedWorkerGlobalScopeBinding::DedicatedWorkerGlobalScopeMethods;
 use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
 use dom::bindings::codegen::InheritTypes::DedicatedWorkerGlobalScopeDerived;
-use dom::bindings::codegen::InheritTypes::{EventTargetCast, WorkerGlobalScopeCast};
+use dom::bindings::codegen::InheritTypes::{EventCast, EventTargetCast, WorkerGlobalScopeCast};
+use dom::bindings::error::ErrorResult;
 use dom::bindings::global;
-use dom::bindings::js::{JSRef, Temporary, RootCollection};
+use dom::bindings::js::{JS, JSRef, Temporary, RootCollection};
 use dom::bindings::trace::Untraceable;
 use dom::bindings::utils::{Reflectable, Reflector};
+use dom::errorevent::ErrorEvent;
+use dom::event::Event;
 use dom::eventtarget::{EventTarget, EventTargetHelpers};
 use dom::eventtarget::WorkerGlobalScopeTypeId;
 use dom::messageevent::MessageEvent;
-use dom::worker::{Worker, TrustedWorkerAddress};
+use dom::messageport::{MessagePort, write_message};
+use dom::worker::{TrustedWorkerAddress, UncaughtError};
 use dom::workerglobalscope::DedicatedGlobalScope;
-use dom::workerglobalscope::WorkerGlobalScope;
-use dom::xmlhttprequest::XMLHttpRequest;
+use dom::workerglobalscope::{WorkerGlobalScope, WorkerGlobalScopeHelpers};
+use microtask::perform_microtask_checkpoint;
 use script_task::{ScriptTask, ScriptChan};
-use script_task::{ScriptMsg, DOMMessage, XHRProgressMsg, WorkerRelease};
-use script_task::WorkerPostMessage;
+use script_task::{ScriptMsg, DOMMessage, WorkerRelease, WorkerPostMessage};
+use script_task::WorkerUncaughtError;
 use script_task::StackRootTLS;
+use task_source::{TaskSource, PostedMessageTaskSource};
 
 use servo_net::resource_task::{ResourceTask, load_whole_resource};
 
-use js::glue::JS_STRUCTURED_CLONE_VERSION;
-use js::jsapi::{JSContext, JS_ReadStructuredClone, JS_WriteStructuredClone};
-use js::jsval::{JSVal, UndefinedValue};
-use js::rust::Cx;
+use js::jsapi::{JSContext, JSErrorReport};
+use js::jsval::JSVal;
+use js::rust::{Cx, reportError};
 
+use libc::c_char;
+use std::cell::RefCell;
+use std::mem;
 use std::rc::Rc;
-use std::ptr;
+use std::string;
+use std::sync::atomics::AtomicBool;
 use std::task::TaskBuilder;
 use native::task::NativeTaskBuilder;
+use sync::Arc;
 use url::Url;
 
+/// The exceptions that scripts of the worker running on this task threw and
+/// did not catch, until they are sent to the Worker object.
+local_data_key!(UncaughtErrors: RefCell<Vec<UncaughtError>>)
+
+/// Logs the uncaught exceptions of the worker's scripts, and keeps them to
+/// be sent to the Worker object.
+extern fn report_error(cx: *mut JSContext, message: *const c_char, report: *mut JSErrorReport) {
+    reportError(cx, message, report);
+
+    let error = unsafe {
+        let filename = (*report).filename;
+        UncaughtError {
+            message: string::raw::from_buf(message as *const u8),
+            filename: if filename.is_null() {
+                "".to_string()
+            } else {
+                string::raw::from_buf(filename as *const u8)
+            },
+            lineno: (*report).lineno as u32,
+            colno: (*report).column as u32,
+        }
+    };
+    match UncaughtErrors.get() {
+        Some(errors) => errors.borrow_mut().push(error),
+        None => {},
+    }
+}
+
+fn take_uncaught_errors() -> Vec<UncaughtError> {
+    match UncaughtErrors.get() {
+        Some(errors) => mem::replace(&mut *errors.borrow_mut(), vec!()),
+        None => vec!(),
+    }
+}
+
 #[jstraceable]
 #[must_root]
 pub struct DedicatedWorkerGlobalScope {
     workerglobalscope: WorkerGlobalScope,
-    receiver: Untraceable<Receiver<ScriptMsg>>,
+    receiver: Untraceable<Receiver<(TaskSource, ScriptMsg)>>,
     /// Sender to the parent thread.
     parent_sender: ScriptChan,
     worker: Untraceable<TrustedWorkerAddress>,
@@ -53,12 +97,13 @@ impl DedicatedWorkerGlobalScope {
                          resource_task: ResourceTask,
                          parent_sender: ScriptChan,
                          own_sender: ScriptChan,
-                         receiver: Receiver<ScriptMsg>)
+                         receiver: Receiver<(TaskSource, ScriptMsg)>,
+                         closing: Arc<AtomicBool>)
                          -> DedicatedWorkerGlobalScope {
         DedicatedWorkerGlobalScope {
             workerglobalscope: WorkerGlobalScope::new_inherited(
                 DedicatedGlobalScope, worker_url, cx, resource_task,
-                own_sender),
+                own_sender, closing),
             receiver: Untraceable::new(receiver),
             parent_sender: parent_sender,
             worker: Untraceable::new(worker),
@@ -71,11 +116,12 @@ impl DedicatedWorkerGlobalScope {
                resource_task: ResourceTask,
                parent_sender: ScriptChan,
                own_sender: ScriptChan,
-               receiver: Receiver<ScriptMsg>)
+               receiver: Receiver<(TaskSource, ScriptMsg)>,
+               closing: Arc<AtomicBool>)
                -> Temporary<DedicatedWorkerGlobalScope> {
         let scope = box DedicatedWorkerGlobalScope::new_inherited(
             worker_url, worker, cx.clone(), resource_task, parent_sender,
-            own_sender, receiver);
+            own_sender, receiver, closing);
         DedicatedWorkerGlobalScopeBinding::Wrap(cx.ptr, scope)
     }
 }
@@ -86,7 +132,8 @@ impl DedicatedWorkerGlobalScope {
                             resource_task: ResourceTask,
                             parent_sender: ScriptChan,
                             own_sender: ScriptChan,
-                            receiver: Receiver<ScriptMsg>) {
+                            receiver: Receiver<(TaskSource, ScriptMsg)>,
+                            closing: Arc<AtomicBool>) {
         TaskBuilder::new()
             .native()
             .named(format!("Web Worker at {}", worker_url.serialize()))
@@ -97,6 +144,7 @@ impl DedicatedWorkerGlobalScope {
             let (url, source) = match load_whole_resource(&resource_task, worker_url.clone()) {
                 Err(_) => {
                     println!("error loading script {}", worker_url.serialize());
+                    parent_sender.queue_task(PostedMessageTaskSource, WorkerRelease(worker));
                     return;
                 }
                 Ok((metadata, bytes)) => {
@@ -105,62 +153,61 @@ impl DedicatedWorkerGlobalScope {
             };
 
             let (_js_runtime, js_context) = ScriptTask::new_rt_and_cx();
+            UncaughtErrors.replace(Some(RefCell::new(vec!())));
+            js_context.set_error_reporter(report_error);
             let global = DedicatedWorkerGlobalScope::new(
                 worker_url, worker, js_context.clone(), resource_task,
-                parent_sender, own_sender, receiver).root();
+                parent_sender, own_sender, receiver, closing).root();
             match js_context.evaluate_script(
                 global.reflector().get_jsobject(), source, url.serialize(), 1) {
                 Ok(_) => (),
                 Err(_) => println!("evaluate_script failed")
             }
-            global.delayed_release_worker();
+            perform_microtask_checkpoint();
+            global.send_uncaught_errors();
 
             let scope: JSRef<WorkerGlobalScope> =
                 WorkerGlobalScopeCast::from_ref(*global);
             let target: JSRef<EventTarget> =
                 EventTargetCast::from_ref(*global);
             loop {
-                match global.receiver.deref().recv_opt() {
-                    Ok(DOMMessage(data, nbytes)) => {
-                        let mut message = UndefinedValue();
-                        unsafe {
-                            assert!(JS_ReadStructuredClone(
-                                js_context.ptr, data as *const u64, nbytes,
-                                JS_STRUCTURED_CLONE_VERSION, &mut message,
-                                ptr::null(), ptr::null_mut()) != 0);
-                        }
-
-                        MessageEvent::dispatch_jsval(target, &global::Worker(scope), message);
-                        global.delayed_release_worker();
-                    },
-                    Ok(XHRProgressMsg(addr, progress)) => {
-                        XMLHttpRequest::handle_xhr_progress(addr, progress)
-                    },
-                    Ok(WorkerPostMessage(addr, data, nbytes)) => {
-                        Worker::handle_message(addr, data, nbytes);
-                    },
-                    Ok(WorkerRelease(addr)) => {
-                        Worker::handle_release(addr)
-                    },
-                    Ok(_) => fail!("Unexpected message"),
+                // Nothing but the worker itself uses its event loop, so
+                // tasks run in the order they came whatever their source.
+                let msg = match global.receiver.deref().recv_opt() {
+                    Ok((_, msg)) => msg,
                     Err(_) => break,
+                };
+                // Whatever was queued when close() or terminate() was called
+                // is dropped.
+                if scope.is_closing() {
+                    break;
+                }
+                match msg {
+                    DOMMessage(data, ports) => {
+                        let message = data.read(&global::Worker(scope));
+                        MessageEvent::dispatch_jsval(target, &global::Worker(scope), message,
+                                                     "".to_string(), ports);
+                    },
+                    msg => scope.handle_script_msg(msg),
                 }
+                perform_microtask_checkpoint();
+                global.send_uncaught_errors();
             }
+
+            scope.tear_down();
+            global.release_worker();
         });
     }
 }
 
 impl<'a> DedicatedWorkerGlobalScopeMethods for JSRef<'a, DedicatedWorkerGlobalScope> {
-    fn PostMessage(self, cx: *mut JSContext, message: JSVal) {
-        let mut data = ptr::null_mut();
-        let mut nbytes = 0;
-        unsafe {
-            assert!(JS_WriteStructuredClone(cx, message, &mut data, &mut nbytes,
-                                            ptr::null(), ptr::null_mut()) != 0);
-        }
-
-        let ScriptChan(ref sender) = self.parent_sender;
-        sender.send(WorkerPostMessage(*self.worker, data, nbytes));
+    // http://www.whatwg.org/html/#dom-dedicatedworkerglobalscope-postmessage
+    fn PostMessage(self, cx: *mut JSContext, message: JSVal,
+                   transfer: Option<Vec<JS<MessagePort>>>) -> ErrorResult {
+        let (data, ports) = try!(write_message(cx, message, transfer, None));
+        self.parent_sender.queue_task(PostedMessageTaskSource,
+                                      WorkerPostMessage(*self.worker, data, ports));
+        Ok(())
     }
 
     fn GetOnmessage(self) -> Option<EventHandlerNonNull> {
@@ -175,13 +222,40 @@ impl<'a> DedicatedWorkerGlobalScopeMethods for JSRef<'a, DedicatedWorkerGlobalSc
 }
 
 trait PrivateDedicatedWorkerGlobalScopeHelpers {
-    fn delayed_release_worker(self);
+    fn send_uncaught_errors(self);
+    fn release_worker(self);
 }
 
 impl<'a> PrivateDedicatedWorkerGlobalScopeHelpers for JSRef<'a, DedicatedWorkerGlobalScope> {
-    fn delayed_release_worker(self) {
-        let ScriptChan(ref sender) = self.parent_sender;
-        sender.send(WorkerRelease(*self.worker));
+    // Fires an error event at the scope for each uncaught exception, and
+    // sends those that it does not cancel on to the Worker object.
+    // http://www.whatwg.org/html/#runtime-script-errors-2
+    fn send_uncaught_errors(self) {
+        let errors = take_uncaught_errors();
+        let scope: JSRef<WorkerGlobalScope> = WorkerGlobalScopeCast::from_ref(self);
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        for error in errors.into_iter() {
+            let errorevent = ErrorEvent::new(&global::Worker(scope), "error".to_string(),
+                                             false, true, error.message.clone(),
+                                             error.filename.clone(),
+                                             error.lineno, error.colno).root();
+            let event: JSRef<Event> = EventCast::from_ref(*errorevent);
+            let not_canceled = target.dispatch_event_with_target(None, event).unwrap_or(true);
+
+            // What the error handlers throw themselves is not reported again.
+            take_uncaught_errors();
+
+            if not_canceled {
+                self.parent_sender.queue_task(PostedMessageTaskSource,
+                                              WorkerUncaughtError(*self.worker, error));
+            }
+        }
+    }
+
+    // Lets the Worker object go, once everything the worker sent to it was
+    // handled, which is why it goes through the same task source.
+    fn release_worker(self) {
+        self.parent_sender.queue_task(PostedMessageTaskSource, WorkerRelease(*self.worker));
     }
 }
 
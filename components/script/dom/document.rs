@@ -6,23 +6,26 @@ use dom::attr::AttrHelpers;
 use dom::bindings::codegen::Bindings::DocumentBinding;
 use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
 use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
+use dom::bindings::codegen::Bindings::HTMLElementBinding::HTMLElementMethods;
+use dom::bindings::codegen::Bindings::HTMLTitleElementBinding::HTMLTitleElementMethods;
 use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
 use dom::bindings::codegen::Bindings::NodeFilterBinding::NodeFilter;
 use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use dom::bindings::codegen::InheritTypes::{DocumentDerived, EventCast, HTMLElementCast};
-use dom::bindings::codegen::InheritTypes::{HTMLHeadElementCast, TextCast, ElementCast};
+use dom::bindings::codegen::InheritTypes::{HTMLHeadElementCast, ElementCast};
 use dom::bindings::codegen::InheritTypes::{DocumentTypeCast, HTMLHtmlElementCast, NodeCast};
-use dom::bindings::codegen::InheritTypes::EventTargetCast;
+use dom::bindings::codegen::InheritTypes::{EventTargetCast, HTMLDialogElementCast};
+use dom::bindings::codegen::InheritTypes::{HTMLImageElementCast, HTMLTitleElementCast};
 use dom::bindings::codegen::InheritTypes::{HTMLAnchorElementDerived, HTMLAppletElementDerived};
 use dom::bindings::codegen::InheritTypes::{HTMLAreaElementDerived, HTMLEmbedElementDerived};
 use dom::bindings::codegen::InheritTypes::{HTMLFormElementDerived, HTMLImageElementDerived};
 use dom::bindings::codegen::InheritTypes::{HTMLScriptElementDerived};
 use dom::bindings::error::{ErrorResult, Fallible, NotSupported, InvalidCharacter};
-use dom::bindings::error::{HierarchyRequest, NamespaceError};
+use dom::bindings::error::{HierarchyRequest, NamespaceError, Security};
 use dom::bindings::global::GlobalRef;
 use dom::bindings::global;
 use dom::bindings::js::{JS, JSRef, Temporary, OptionalSettable, TemporaryPushable};
-use dom::bindings::js::OptionalRootable;
+use dom::bindings::js::{OptionalRootable, RootedVec};
 use dom::bindings::trace::{Traceable, Untraceable};
 use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
 use dom::bindings::utils::{xml_name_type, InvalidXMLName, Name, QName};
@@ -31,15 +34,18 @@ use dom::customevent::CustomEvent;
 use dom::documentfragment::DocumentFragment;
 use dom::documenttype::DocumentType;
 use dom::domimplementation::DOMImplementation;
-use dom::element::{Element, AttributeHandlers, get_attribute_parts};
+use dom::element::{Element, AttributeHandlers, ElementHelpers, InFocusState, InTargetState};
+use dom::element::get_attribute_parts;
 use dom::element::{HTMLHtmlElementTypeId, HTMLHeadElementTypeId, HTMLTitleElementTypeId};
 use dom::element::{HTMLBodyElementTypeId, HTMLFrameSetElementTypeId};
 use dom::event::Event;
 use dom::eventtarget::{EventTarget, NodeTargetTypeId, EventTargetHelpers};
 use dom::htmlcollection::{HTMLCollection, CollectionFilter};
-use dom::htmlelement::HTMLElement;
+use dom::htmldialogelement::{HTMLDialogElement, HTMLDialogElementHelpers};
+use dom::htmlelement::{HTMLElement, HTMLElementHelpers};
 use dom::htmlheadelement::HTMLHeadElement;
 use dom::htmlhtmlelement::HTMLHtmlElement;
+use dom::htmlimageelement::{HTMLImageElement, HTMLImageElementHelpers};
 use dom::htmltitleelement::HTMLTitleElement;
 use dom::location::Location;
 use dom::mouseevent::MouseEvent;
@@ -54,16 +60,29 @@ use dom::uievent::UIEvent;
 use dom::window::{Window, WindowHelpers};
 use html::hubbub_html_parser::build_element_from_tag;
 use hubbub::hubbub::{QuirksMode, NoQuirks, LimitedQuirks, FullQuirks};
-use layout_interface::{DocumentDamageLevel, ContentChangedDocumentDamage};
+use layout_interface::DocumentDamageLevel;
+use script_task::ImageLoadedMsg;
+use security::Origin;
+use task_source::NetworkingTaskSource;
+use servo_msg::compositor_msg::ScriptListener;
+use servo_net::hyperlink_auditing::ping_load_data;
+use servo_net::image_cache_task::{Decode, Evict, ImageReady, Prefetch, WaitForImage};
+use servo_net::referrer_policy::{ReferrerPolicy, NoReferrerWhenDowngrade};
+use servo_net::resource_task::LoadAndForget;
 use servo_util::atom::Atom;
+use servo_util::memory::{HeapSizeOf, MemoryReport};
 use servo_util::namespace;
 use servo_util::namespace::{Namespace, Null};
 use servo_util::str::{DOMString, split_html_space_chars};
+use servo_util::task::spawn_named;
 
 use std::collections::hashmap::HashMap;
 use std::ascii::StrAsciiExt;
 use std::cell::{Cell, RefCell};
-use url::Url;
+use std::comm::channel;
+use std::i32;
+use std::mem;
+use url::{Url, UrlParser};
 use time;
 
 #[deriving(PartialEq)]
@@ -86,6 +105,8 @@ pub struct Document {
     pub encoding_name: Traceable<RefCell<DOMString>>,
     pub is_html_document: bool,
     url: Untraceable<Url>,
+    /// http://www.whatwg.org/html/#origin
+    origin: Untraceable<RefCell<Origin>>,
     quirks_mode: Untraceable<Cell<QuirksMode>>,
     images: Cell<Option<JS<HTMLCollection>>>,
     embeds: Cell<Option<JS<HTMLCollection>>>,
@@ -94,6 +115,49 @@ pub struct Document {
     scripts: Cell<Option<JS<HTMLCollection>>>,
     anchors: Cell<Option<JS<HTMLCollection>>>,
     applets: Cell<Option<JS<HTMLCollection>>>,
+    /// http://fullscreen.spec.whatwg.org/#top-layer
+    top_layer: RefCell<Vec<JS<Element>>>,
+    /// The element that currently has focus, if any.
+    focused: Cell<Option<JS<Element>>>,
+    /// The element that the fragment of the document's URL identifies, if any.
+    target_element: Cell<Option<JS<Element>>>,
+    /// The text node holding the caret of editable content and the caret's
+    /// offset in it.
+    /// FIXME: this should be the collapsed selection once there is one.
+    caret_node: Cell<Option<JS<Text>>>,
+    caret_offset: Cell<u32>,
+    /// The images requested by the img elements of the document, by URL.
+    image_requests: RefCell<HashMap<Url, ImageRequest>>,
+    /// Increased by one every time a node of the document is dirtied.
+    dom_generation: Cell<uint>,
+    /// Whether the document is the about:blank one that an iframe shows
+    /// until something else is loaded in it, whose window a same-origin
+    /// document takes over.
+    initial_about_blank: Cell<bool>,
+    /// The title the embedder was last told about, so that it's only told
+    /// when the title changes.
+    notified_title: Traceable<RefCell<Option<DOMString>>>,
+    /// The URLs pinged when a hyperlink of the document was last followed.
+    last_pings: Traceable<RefCell<Vec<Url>>>,
+}
+
+/// An image requested by the img elements of a document.
+#[jstraceable]
+struct ImageRequest {
+    /// The elements showing the image. The image cache is told to evict the
+    /// image once there are none left.
+    users: Vec<JS<HTMLImageElement>>,
+    /// Whether the image has been decoded, or has failed to load if false,
+    /// once the image cache is done with it.
+    result: Option<bool>,
+}
+
+/// The answer to an img element asking its document for an image.
+pub enum ImageRequestResponse {
+    /// The image is already available, or already failed to load if false.
+    ImageRequestCompleted(bool),
+    /// The element will be notified when the image is done loading.
+    ImageRequestPending,
 }
 
 impl DocumentDerived for EventTarget {
@@ -160,16 +224,43 @@ impl CollectionFilter for AppletsFilter {
 
 pub trait DocumentHelpers<'a> {
     fn url(self) -> &'a Url;
+    fn origin(self) -> Origin;
+    fn set_origin(self, origin: Origin);
+    fn is_initial_about_blank(self) -> bool;
+    fn set_initial_about_blank(self, initial: bool);
+    fn has_browsing_context(self) -> bool;
     fn quirks_mode(self) -> QuirksMode;
     fn set_quirks_mode(self, mode: QuirksMode);
     fn set_last_modified(self, value: DOMString);
     fn set_encoding_name(self, name: DOMString);
-    fn content_changed(self);
+    fn dom_generation(self) -> uint;
+    fn increment_dom_generation(self);
+    fn memory_report(self) -> MemoryReport;
     fn damage_and_reflow(self, damage: DocumentDamageLevel);
     fn wait_until_safe_to_modify_dom(self);
     fn unregister_named_element(self, to_unregister: JSRef<Element>, id: Atom);
     fn register_named_element(self, element: JSRef<Element>, id: Atom);
-    fn load_anchor_href(self, href: DOMString);
+    fn load_anchor_href(self, href: DOMString, ping: DOMString,
+                        referrer_policy: Option<ReferrerPolicy>);
+    fn add_to_top_layer(self, element: JSRef<Element>);
+    fn remove_from_top_layer(self, element: JSRef<Element>);
+    fn is_inert(self, node: JSRef<Node>) -> bool;
+    fn cancel_modal_dialog(self);
+    fn get_focused_element(self) -> Option<Temporary<Element>>;
+    fn set_focused_element(self, element: Option<JSRef<Element>>);
+    fn focus_next(self);
+    fn set_target_element(self, element: Option<JSRef<Element>>);
+    fn get_caret(self) -> Option<(Temporary<Text>, u32)>;
+    fn set_caret(self, node: Option<JSRef<Text>>, offset: u32);
+    fn title_element(self) -> Option<Temporary<HTMLTitleElement>>;
+    fn title_changed(self);
+    fn notified_title(self) -> Option<DOMString>;
+    fn last_pings(self) -> Vec<Url>;
+    fn request_image(self, url: Url, element: JSRef<HTMLImageElement>) -> ImageRequestResponse;
+    fn complete_image_request(self, url: Url, succeeded: bool);
+    fn release_image(self, url: &Url, element: JSRef<HTMLImageElement>);
+    fn release_images(self);
+    fn viewport_changed(self);
 }
 
 impl<'a> DocumentHelpers<'a> for JSRef<'a, Document> {
@@ -177,6 +268,33 @@ impl<'a> DocumentHelpers<'a> for JSRef<'a, Document> {
         &*self.extended_deref().url
     }
 
+    fn origin(self) -> Origin {
+        self.origin.deref().borrow().clone()
+    }
+
+    fn set_origin(self, origin: Origin) {
+        *self.origin.deref().borrow_mut() = origin;
+    }
+
+    fn is_initial_about_blank(self) -> bool {
+        self.initial_about_blank.get()
+    }
+
+    fn set_initial_about_blank(self, initial: bool) {
+        self.initial_about_blank.set(initial);
+    }
+
+    /// Whether the document is the active document of its window's browsing
+    /// context. Documents created by scripts, such as with DOMParser, are not.
+    fn has_browsing_context(self) -> bool {
+        let window = self.window.root();
+        let context = window.browser_context.deref().borrow();
+        match *context {
+            Some(ref context) => *context.active_document().root() == self,
+            None => false,
+        }
+    }
+
     fn quirks_mode(self) -> QuirksMode {
         self.quirks_mode.deref().get()
     }
@@ -193,8 +311,28 @@ impl<'a> DocumentHelpers<'a> for JSRef<'a, Document> {
         *self.encoding_name.deref().borrow_mut() = name;
     }
 
-    fn content_changed(self) {
-        self.damage_and_reflow(ContentChangedDocumentDamage);
+    fn dom_generation(self) -> uint {
+        self.dom_generation.get()
+    }
+
+    fn increment_dom_generation(self) {
+        self.dom_generation.set(self.dom_generation.get() + 1);
+    }
+
+    /// What the nodes of this document use memory for. The nodes that
+    /// aren't in the document are left out.
+    fn memory_report(self) -> MemoryReport {
+        let mut report = MemoryReport::new();
+        let root: JSRef<Node> = NodeCast::from_ref(self);
+        for node in root.traverse_preorder() {
+            node.report_memory(&mut report);
+        }
+        report.dom_nodes += self.idmap.deref().heap_size_of_children() +
+            self.top_layer.heap_size_of_children();
+        report.strings += self.content_type.heap_size_of_children() +
+            self.last_modified.deref().heap_size_of_children() +
+            self.encoding_name.deref().heap_size_of_children();
+        report
     }
 
     fn damage_and_reflow(self, damage: DocumentDamageLevel) {
@@ -271,9 +409,293 @@ impl<'a> DocumentHelpers<'a> for JSRef<'a, Document> {
         idmap.insert(id, elements);
     }
 
-    fn load_anchor_href(self, href: DOMString) {
+    /// Follows a hyperlink to `href`, auditing it by pinging the URLs in
+    /// `ping`.
+    fn load_anchor_href(self, href: DOMString, ping: DOMString,
+                        referrer_policy: Option<ReferrerPolicy>) {
+        // FIXME: Fall back to the document's referrer policy instead.
+        let referrer_policy = referrer_policy.unwrap_or(NoReferrerWhenDowngrade);
         let window = self.window.root();
-        window.load_url(href);
+
+        // http://www.whatwg.org/html/#hyperlink-auditing
+        let target = UrlParser::new().base_url(self.url()).parse(href.as_slice());
+        match target {
+            Ok(target) => {
+                let resource_task = window.page().resource_task.deref().clone();
+                let mut last_pings = self.last_pings.deref().borrow_mut();
+                last_pings.clear();
+                for ping in split_html_space_chars(ping.as_slice()) {
+                    let ping = match UrlParser::new().base_url(self.url()).parse(ping) {
+                        Ok(ping) => ping,
+                        Err(_) => continue,
+                    };
+                    match ping.scheme.as_slice() {
+                        "http" | "https" => (),
+                        _ => continue,
+                    }
+                    last_pings.push(ping.clone());
+                    let load_data = ping_load_data(ping, self.url(), &target, referrer_policy);
+                    resource_task.send(LoadAndForget(load_data));
+                }
+            },
+            Err(_) => (),
+        }
+
+        // A link to a URL that can't be parsed goes nowhere.
+        let _ = window.load_url(href, referrer_policy);
+    }
+
+    /// Moves `element` to the top of the top layer.
+    fn add_to_top_layer(self, element: JSRef<Element>) {
+        self.remove_from_top_layer(element);
+        self.top_layer.borrow_mut().push_unrooted(&element);
+    }
+
+    fn remove_from_top_layer(self, element: JSRef<Element>) {
+        let mut top_layer = self.top_layer.borrow_mut();
+        let position = top_layer.iter().position(|candidate| *candidate.root() == element);
+        match position {
+            Some(position) => { top_layer.remove(position); },
+            None => (),
+        }
+    }
+
+    /// Returns whether `node` is blocked by the topmost modal dialog, and is
+    /// therefore inert with respect to user interaction.
+    /// http://www.whatwg.org/html/#blocked-by-a-modal-dialog
+    fn is_inert(self, node: JSRef<Node>) -> bool {
+        match self.top_layer.borrow().last() {
+            Some(dialog) => {
+                let dialog = dialog.root();
+                let dialog: JSRef<Node> = NodeCast::from_ref(*dialog);
+                !dialog.is_inclusive_ancestor_of(node)
+            }
+            None => false,
+        }
+    }
+
+    /// Cancels the topmost modal dialog, if any. This is what happens when the
+    /// user presses Escape while a modal dialog is showing.
+    fn cancel_modal_dialog(self) {
+        let dialog = self.top_layer.borrow().last().map(|dialog| dialog.root());
+        match dialog {
+            Some(dialog) => {
+                let dialog: Option<JSRef<HTMLDialogElement>> = HTMLDialogElementCast::to_ref(*dialog);
+                dialog.map(|dialog| dialog.cancel());
+            }
+            None => (),
+        }
+    }
+
+    fn get_focused_element(self) -> Option<Temporary<Element>> {
+        self.focused.get().map(|element| Temporary::new(element))
+    }
+
+    fn set_focused_element(self, element: Option<JSRef<Element>>) {
+        match self.get_focused_element().root() {
+            Some(focused) => focused.set_state(InFocusState, false),
+            None => (),
+        }
+        self.focused.assign(element);
+        match element {
+            Some(element) => element.set_state(InFocusState, true),
+            None => (),
+        }
+    }
+
+    /// Moves the focus to the element following the focused one in the
+    /// sequential focus navigation order; past the last element, the focus
+    /// goes back to the document.
+    /// http://www.whatwg.org/html/#sequential-focus-navigation-order
+    fn focus_next(self) {
+        // Elements with a positive tabindex come first, in increasing order,
+        // followed by all the others in tree order.
+        let root: JSRef<Node> = NodeCast::from_ref(self);
+        let mut order: Vec<JSRef<HTMLElement>> =
+            root.traverse_preorder()
+                .filter_map(|node| HTMLElementCast::to_ref(node))
+                .filter(|element| element.is_sequentially_focusable())
+                .collect();
+        order.sort_by(|a, b| {
+            let key = |index: i32| if index > 0 { index } else { i32::MAX };
+            key(a.TabIndex()).cmp(&key(b.TabIndex()))
+        });
+
+        let order: Vec<JSRef<Element>> =
+            order.into_iter().map(|element| ElementCast::from_ref(element)).collect();
+        let focused = self.get_focused_element().root();
+        let position = focused.and_then(|focused| {
+            order.iter().position(|&element| element == *focused)
+        });
+        let next = match position {
+            Some(position) => order.as_slice().get(position + 1),
+            None => order.as_slice().head(),
+        };
+        self.set_focused_element(next.map(|&element| element));
+    }
+
+    // http://www.whatwg.org/html/#target-element
+    fn set_target_element(self, element: Option<JSRef<Element>>) {
+        match self.target_element.get().map(|target| target.root()) {
+            Some(target) => target.set_state(InTargetState, false),
+            None => (),
+        }
+        self.target_element.assign(element);
+        match element {
+            Some(element) => element.set_state(InTargetState, true),
+            None => (),
+        }
+    }
+
+    fn get_caret(self) -> Option<(Temporary<Text>, u32)> {
+        self.caret_node.get().map(|node| (Temporary::new(node), self.caret_offset.get()))
+    }
+
+    fn set_caret(self, node: Option<JSRef<Text>>, offset: u32) {
+        self.caret_node.assign(node);
+        self.caret_offset.set(offset);
+    }
+
+    /// Returns the first title element in the document, if any.
+    /// http://www.whatwg.org/html/#the-title-element-0
+    fn title_element(self) -> Option<Temporary<HTMLTitleElement>> {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        node.traverse_preorder()
+            .find(|node| node.type_id() == ElementNodeTypeId(HTMLTitleElementTypeId))
+            .map(|title| Temporary::from_rooted(HTMLTitleElementCast::to_ref(title).unwrap()))
+    }
+
+    /// Tells the embedder that the document's title may have changed, so that
+    /// it can update the window title.
+    fn title_changed(self) {
+        // Only the title of the document a window shows is the page's.
+        if !self.has_browsing_context() {
+            return;
+        }
+        let title = self.Title();
+        let title = if title.is_empty() { None } else { Some(title) };
+        if *self.notified_title.deref().borrow() == title {
+            return;
+        }
+        *self.notified_title.deref().borrow_mut() = title.clone();
+        let window = self.window.root();
+        window.compositor.set_title(window.page().id, title);
+    }
+
+    fn notified_title(self) -> Option<DOMString> {
+        self.notified_title.deref().borrow().clone()
+    }
+
+    fn last_pings(self) -> Vec<Url> {
+        self.last_pings.deref().borrow().clone()
+    }
+
+    /// Asks the image cache for the image at `url` on behalf of `element`,
+    /// unless another element of the document already did, so that every
+    /// image is fetched and decoded only once.
+    fn request_image(self, url: Url, element: JSRef<HTMLImageElement>) -> ImageRequestResponse {
+        let mut image_requests = self.image_requests.borrow_mut();
+        match image_requests.find_mut(&url) {
+            Some(request) => {
+                let element = JS::from_rooted(element);
+                if !request.users.contains(&element) {
+                    request.users.push(element);
+                }
+                return match request.result {
+                    Some(succeeded) => ImageRequestCompleted(succeeded),
+                    None => ImageRequestPending,
+                };
+            },
+            None => (),
+        }
+
+        // TODO (Issue #84): don't prefetch if we are within a <noscript> tag.
+        let window = self.window.root();
+        let image_cache = window.image_cache_task.clone();
+        image_cache.send(Prefetch(url.clone()));
+        image_cache.send(Decode(url.clone()));
+
+        let script_chan = window.script_chan.clone();
+        let id = window.page().id;
+        let image_url = url.clone();
+        spawn_named("ImageRequest", proc() {
+            let (response_chan, response_port) = channel();
+            image_cache.send(WaitForImage(image_url.clone(), response_chan));
+            let succeeded = match response_port.recv() {
+                ImageReady(..) => true,
+                _ => false,
+            };
+            script_chan.queue_task(NetworkingTaskSource, ImageLoadedMsg(id, image_url, succeeded));
+        });
+
+        image_requests.insert(url, ImageRequest {
+            users: vec!(JS::from_rooted(element)),
+            result: None,
+        });
+        ImageRequestPending
+    }
+
+    /// Notifies the elements waiting for the image at `url` that it is done
+    /// loading.
+    fn complete_image_request(self, url: Url, succeeded: bool) {
+        let waiting = match self.image_requests.borrow_mut().find_mut(&url) {
+            Some(request) => {
+                if request.result.is_some() {
+                    return;
+                }
+                request.result = Some(succeeded);
+                request.users.clone()
+            },
+            None => {
+                // Every element let go of the image while it was loading, and
+                // the image cache couldn't evict it then.
+                let window = self.window.root();
+                window.image_cache_task.send(Evict(url.clone()));
+                return;
+            },
+        };
+        for element in waiting.iter() {
+            element.root().image_request_completed(&url, succeeded);
+        }
+    }
+
+    /// Tells the document that `element` no longer shows the image at `url`,
+    /// and the image cache that it may drop the image if no other element of
+    /// the document does.
+    fn release_image(self, url: &Url, element: JSRef<HTMLImageElement>) {
+        let mut image_requests = self.image_requests.borrow_mut();
+        let unused = match image_requests.find_mut(url) {
+            Some(request) => {
+                let element = JS::from_rooted(element);
+                request.users.retain(|user| *user != element);
+                request.users.is_empty()
+            },
+            None => return,
+        };
+        if unused {
+            image_requests.remove(url);
+            let window = self.window.root();
+            window.image_cache_task.send(Evict(url.clone()));
+        }
+    }
+
+    /// Lets the image cache drop every image of the document once the
+    /// document goes away.
+    fn release_images(self) {
+        let image_requests = mem::replace(&mut *self.image_requests.borrow_mut(), HashMap::new());
+        let window = self.window.root();
+        for (url, _) in image_requests.into_iter() {
+            window.image_cache_task.send(Evict(url));
+        }
+    }
+
+    /// Makes the images choose their image again once the viewport changed
+    /// size, since media queries and srcset widths depend on it.
+    fn viewport_changed(self) {
+        let root: JSRef<Node> = NodeCast::from_ref(self);
+        for img in root.traverse_preorder().filter_map(|node| HTMLImageElementCast::to_ref(node)) {
+            img.reselect_image_source();
+        }
     }
 }
 
@@ -282,7 +704,14 @@ impl Document {
                          url: Option<Url>,
                          is_html_document: IsHTMLDocument,
                          content_type: Option<DOMString>) -> Document {
+        let created_by_script = url.is_none();
         let url = url.unwrap_or_else(|| Url::parse("about:blank").unwrap());
+        // Documents created by scripts have the origin of the document whose
+        // script created them.
+        let origin = match *window.browser_context.deref().borrow() {
+            Some(ref context) if created_by_script => context.active_document().root().origin(),
+            _ => Origin::from_url(&url),
+        };
 
         Document {
             node: Node::new_without_doc(DocumentNodeTypeId),
@@ -301,6 +730,7 @@ impl Document {
             },
             last_modified: Traceable::new(RefCell::new(None)),
             url: Untraceable::new(url),
+            origin: Untraceable::new(RefCell::new(origin)),
             // http://dom.spec.whatwg.org/#concept-document-quirks
             quirks_mode: Untraceable::new(Cell::new(NoQuirks)),
             // http://dom.spec.whatwg.org/#concept-document-encoding
@@ -313,6 +743,16 @@ impl Document {
             scripts: Cell::new(None),
             anchors: Cell::new(None),
             applets: Cell::new(None),
+            top_layer: RefCell::new(vec!()),
+            focused: Cell::new(None),
+            target_element: Cell::new(None),
+            caret_node: Cell::new(None),
+            caret_offset: Cell::new(0),
+            image_requests: RefCell::new(HashMap::new()),
+            dom_generation: Cell::new(0),
+            initial_about_blank: Cell::new(false),
+            notified_title: Traceable::new(RefCell::new(None)),
+            last_pings: Traceable::new(RefCell::new(vec!())),
         }
     }
 
@@ -349,17 +789,17 @@ impl<'a> PrivateDocumentHelpers for JSRef<'a, Document> {
 
         match self.GetDocumentElement().root() {
             None => {
-                NodeList::new_simple_list(*window, vec!())
+                NodeList::new_simple_list(*window, &RootedVec::new())
             },
             Some(root) => {
-                let mut nodes = vec!();
+                let mut nodes = RootedVec::new();
                 let root: JSRef<Node> = NodeCast::from_ref(*root);
                 for child in root.traverse_preorder() {
                     if callback(child) {
                         nodes.push(child);
                     }
                 }
-                NodeList::new_simple_list(*window, nodes)
+                NodeList::new_simple_list(*window, &nodes)
             }
         }
 
@@ -465,7 +905,11 @@ impl<'a> DocumentMethods for JSRef<'a, Document> {
             debug!("Not a valid element name");
             return Err(InvalidCharacter);
         }
-        let local_name = local_name.as_slice().to_ascii_lower();
+        let local_name = if self.is_html_document {
+            local_name.as_slice().to_ascii_lower()
+        } else {
+            local_name
+        };
         Ok(build_element_from_tag(local_name, namespace::HTML, self))
     }
 
@@ -597,6 +1041,21 @@ impl<'a> DocumentMethods for JSRef<'a, Document> {
         }
     }
 
+    // http://www.whatwg.org/html/#dom-document-domain
+    fn Domain(self) -> DOMString {
+        self.origin.deref().borrow().effective_domain().unwrap_or("".to_string())
+    }
+
+    // http://www.whatwg.org/html/#dom-document-domain
+    fn SetDomain(self, value: DOMString) -> ErrorResult {
+        // Step 1
+        if !self.has_browsing_context() {
+            return Err(Security);
+        }
+        // Steps 2-5
+        self.origin.deref().borrow_mut().set_domain(value.as_slice()).map_err(|_| Security)
+    }
+
     // http://www.whatwg.org/html/#dom-document-lastmodified
     fn LastModified(self) -> DOMString {
         match *self.last_modified.borrow() {
@@ -618,59 +1077,28 @@ impl<'a> DocumentMethods for JSRef<'a, Document> {
 
     // http://www.whatwg.org/specs/web-apps/current-work/#document.title
     fn Title(self) -> DOMString {
-        let mut title = String::new();
-        self.GetDocumentElement().root().map(|root| {
-            let root: JSRef<Node> = NodeCast::from_ref(*root);
-            root.traverse_preorder()
-                .find(|node| node.type_id() == ElementNodeTypeId(HTMLTitleElementTypeId))
-                .map(|title_elem| {
-                    for child in title_elem.children() {
-                        if child.is_text() {
-                            let text: JSRef<Text> = TextCast::to_ref(child).unwrap();
-                            title.push_str(text.deref().characterdata.data.deref().borrow().as_slice());
-                        }
-                    }
-                });
-        });
+        let title = match self.title_element().root() {
+            Some(title_elem) => title_elem.Text(),
+            None => return "".to_string(),
+        };
         let v: Vec<&str> = split_html_space_chars(title.as_slice()).collect();
         v.connect(" ")
     }
 
     // http://www.whatwg.org/specs/web-apps/current-work/#document.title
     fn SetTitle(self, title: DOMString) -> ErrorResult {
-        self.GetDocumentElement().root().map(|root| {
-            let root: JSRef<Node> = NodeCast::from_ref(*root);
-            let head_node = root.traverse_preorder().find(|child| {
-                child.type_id() == ElementNodeTypeId(HTMLHeadElementTypeId)
-            });
-            head_node.map(|head| {
-                let title_node = head.children().find(|child| {
-                    child.type_id() == ElementNodeTypeId(HTMLTitleElementTypeId)
+        match self.title_element().root() {
+            Some(title_elem) => title_elem.SetText(title),
+            None => {
+                let head = self.GetHead().root();
+                head.map(|head| {
+                    let new_title = HTMLTitleElement::new("title".to_string(), self).root();
+                    new_title.SetText(title);
+                    let head: JSRef<Node> = NodeCast::from_ref(*head);
+                    assert!(head.AppendChild(NodeCast::from_ref(*new_title)).is_ok());
                 });
-
-                match title_node {
-                    Some(ref title_node) => {
-                        for title_child in title_node.children() {
-                            assert!(title_node.RemoveChild(title_child).is_ok());
-                        }
-                        if !title.is_empty() {
-                            let new_text = self.CreateTextNode(title.clone()).root();
-                            assert!(title_node.AppendChild(NodeCast::from_ref(*new_text)).is_ok());
-                        }
-                    },
-                    None => {
-                        let new_title = HTMLTitleElement::new("title".to_string(), self).root();
-                        let new_title: JSRef<Node> = NodeCast::from_ref(*new_title);
-
-                        if !title.is_empty() {
-                            let new_text = self.CreateTextNode(title.clone()).root();
-                            assert!(new_title.AppendChild(NodeCast::from_ref(*new_text)).is_ok());
-                        }
-                        assert!(head.AppendChild(new_title).is_ok());
-                    },
-                }
-            });
-        });
+            }
+        }
         Ok(())
     }
 
@@ -747,6 +1175,23 @@ impl<'a> DocumentMethods for JSRef<'a, Document> {
         Ok(())
     }
 
+    // http://www.whatwg.org/html/#dom-document-activeelement
+    fn GetActiveElement(self) -> Option<Temporary<Element>> {
+        match self.get_focused_element().root() {
+            Some(element) => {
+                let node: JSRef<Node> = NodeCast::from_ref(*element);
+                if node.is_in_doc() {
+                    return Some(Temporary::from_rooted(*element));
+                }
+            }
+            None => (),
+        }
+        match self.GetBody() {
+            Some(body) => Some(ElementCast::from_temporary(body)),
+            None => self.GetDocumentElement(),
+        }
+    }
+
     // http://www.whatwg.org/specs/web-apps/current-work/#dom-document-getelementsbyname
     fn GetElementsByName(self, name: DOMString) -> Temporary<NodeList> {
         self.createNodeList(|node| {
@@ -878,4 +1323,7 @@ impl<'a> DocumentMethods for JSRef<'a, Document> {
         let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(self);
         eventtarget.set_event_handler_common("load", listener)
     }
+
+    event_handler!(resize, GetOnresize, SetOnresize)
+    event_handler!(scroll, GetOnscroll, SetOnscroll)
 }
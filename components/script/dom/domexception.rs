@@ -55,7 +55,9 @@ impl DOMErrorName {
             error::Network => NetworkError,
             error::Abort => AbortError,
             error::Timeout => TimeoutError,
+            error::DataClone => DataCloneError,
             error::FailureUnknown => fail!(),
+            error::Type(..) | error::Range(..) => fail!("not a DOMException"),
         }
     }
 }
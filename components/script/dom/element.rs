@@ -27,21 +27,24 @@ use dom::htmlcollection::HTMLCollection;
 use dom::htmlserializer::serialize;
 use dom::node::{ElementNodeTypeId, Node, NodeHelpers, NodeIterator, document_from_node};
 use dom::node::{window_from_node, LayoutNodeHelpers};
+use dom::node::{NodeStyleDamaged, ContentDamaged};
 use dom::nodelist::NodeList;
 use dom::virtualmethods::{VirtualMethods, vtable_for};
-use layout_interface::ContentChangedDocumentDamage;
-use layout_interface::MatchSelectorsDocumentDamage;
 use devtools_traits::AttrInfo;
 use style::{matches, parse_selector_list_from_str};
 use style;
+use servo_net::referrer_policy::ReferrerPolicy;
 use servo_util::atom::Atom;
+use servo_util::memory::{HeapSizeOf, MemoryReport};
 use servo_util::namespace;
 use servo_util::namespace::{Namespace, Null};
-use servo_util::str::DOMString;
+use servo_util::str::{DOMString, parse_floating_point_number};
 
 use std::ascii::StrAsciiExt;
 use std::cell::{Cell, RefCell};
 use std::mem;
+use std::slice::Items;
+use url::UrlParser;
 
 #[jstraceable]
 #[must_root]
@@ -52,8 +55,49 @@ pub struct Element {
     pub prefix: Option<DOMString>,
     pub attrs: RefCell<Vec<JS<Attr>>>,
     pub style_attribute: Traceable<RefCell<Option<style::PropertyDeclarationBlock>>>,
-    pub attr_list: Cell<Option<JS<NamedNodeMap>>>,
-    class_list: Cell<Option<JS<DOMTokenList>>>,
+    state: Cell<ElementState>,
+}
+
+bitflags! {
+    #[doc = "The dynamic states of an element, as matched by the state pseudo-classes."]
+    #[jstraceable]
+    flags ElementState: u16 {
+        #[doc = "The pointer is over the element or one of its descendants. :hover"]
+        static InHoverState = 0x01,
+        #[doc = "The element or one of its descendants is being activated. :active"]
+        static InActiveState = 0x02,
+        #[doc = "The element has the focus. :focus"]
+        static InFocusState = 0x04,
+        #[doc = "The element is an enabled form control. :enabled"]
+        static InEnabledState = 0x08,
+        #[doc = "The element is a disabled form control. :disabled"]
+        static InDisabledState = 0x10,
+        #[doc = "The element is a checked checkbox or radio button. :checked"]
+        static InCheckedState = 0x20,
+        #[doc = "The element is in an indeterminate state. :indeterminate"]
+        static InIndeterminateState = 0x40,
+        #[doc = "The element is the target of the document's URL. :target"]
+        static InTargetState = 0x80
+    }
+}
+
+impl ElementState {
+    pub fn new(type_id: ElementTypeId) -> ElementState {
+        match type_id {
+            // The following elements are enabled by default.
+            HTMLButtonElementTypeId |
+            HTMLInputElementTypeId |
+            HTMLSelectElementTypeId |
+            HTMLTextAreaElementTypeId |
+            HTMLOptGroupElementTypeId |
+            HTMLOptionElementTypeId |
+            //HTMLMenuItemElementTypeId |
+            HTMLFieldSetElementTypeId => InEnabledState,
+            // A progress element without a value attribute is indeterminate.
+            HTMLProgressElementTypeId => InIndeterminateState,
+            _ => ElementState::empty(),
+        }
+    }
 }
 
 impl ElementDerived for EventTarget {
@@ -86,6 +130,8 @@ pub enum ElementTypeId {
     HTMLCanvasElementTypeId,
     HTMLDataElementTypeId,
     HTMLDataListElementTypeId,
+    HTMLDetailsElementTypeId,
+    HTMLDialogElementTypeId,
     HTMLDirectoryElementTypeId,
     HTMLDListElementTypeId,
     HTMLDivElementTypeId,
@@ -118,6 +164,7 @@ pub enum ElementTypeId {
     HTMLOutputElementTypeId,
     HTMLParagraphElementTypeId,
     HTMLParamElementTypeId,
+    HTMLPictureElementTypeId,
     HTMLPreElementTypeId,
     HTMLProgressElementTypeId,
     HTMLQuoteElementTypeId,
@@ -157,9 +204,8 @@ impl Element {
             namespace: namespace,
             prefix: prefix,
             attrs: RefCell::new(vec!()),
-            attr_list: Cell::new(None),
-            class_list: Cell::new(None),
             style_attribute: Traceable::new(RefCell::new(None)),
+            state: Cell::new(ElementState::new(type_id)),
         }
     }
 
@@ -170,21 +216,23 @@ impl Element {
 }
 
 pub trait RawLayoutElementHelpers {
-    unsafe fn get_attr_val_for_layout<'a>(&'a self, namespace: &Namespace, name: &str) -> Option<&'a str>;
-    unsafe fn get_attr_atom_for_layout(&self, namespace: &Namespace, name: &str) -> Option<Atom>;
-    unsafe fn has_class_for_layout(&self, name: &str) -> bool;
+    unsafe fn get_attr_val_for_layout<'a>(&'a self, namespace: &Namespace, name: &Atom) -> Option<&'a str>;
+    unsafe fn get_attr_atom_for_layout(&self, namespace: &Namespace, name: &Atom) -> Option<Atom>;
+    unsafe fn get_classes_for_layout(&self) -> Option<Items<'static, Atom>>;
+    unsafe fn has_class_for_layout(&self, name: &Atom) -> bool;
+    unsafe fn get_state_for_layout(&self) -> ElementState;
 }
 
 impl RawLayoutElementHelpers for Element {
     #[inline]
     #[allow(unrooted_must_root)]
-    unsafe fn get_attr_val_for_layout<'a>(&'a self, namespace: &Namespace, name: &str)
+    unsafe fn get_attr_val_for_layout<'a>(&'a self, namespace: &Namespace, name: &Atom)
                                           -> Option<&'a str> {
         // cast to point to T in RefCell<T> directly
         let attrs: *const Vec<JS<Attr>> = mem::transmute(&self.attrs);
         (*attrs).iter().find(|attr: & &JS<Attr>| {
             let attr = attr.unsafe_get();
-            name == (*attr).local_name_atom_forever().as_slice() &&
+            *name == *(*attr).local_name_atom_forever() &&
             (*attr).namespace == *namespace
         }).map(|attr| {
             let attr = attr.unsafe_get();
@@ -194,13 +242,13 @@ impl RawLayoutElementHelpers for Element {
 
     #[inline]
     #[allow(unrooted_must_root)]
-    unsafe fn get_attr_atom_for_layout(&self, namespace: &Namespace, name: &str)
+    unsafe fn get_attr_atom_for_layout(&self, namespace: &Namespace, name: &Atom)
                                       -> Option<Atom> {
         // cast to point to T in RefCell<T> directly
         let attrs: *const Vec<JS<Attr>> = mem::transmute(&self.attrs);
         (*attrs).iter().find(|attr: & &JS<Attr>| {
             let attr = attr.unsafe_get();
-            name == (*attr).local_name_atom_forever().as_slice() &&
+            *name == *(*attr).local_name_atom_forever() &&
             (*attr).namespace == *namespace
         }).and_then(|attr| {
             let attr = attr.unsafe_get();
@@ -210,15 +258,28 @@ impl RawLayoutElementHelpers for Element {
 
     #[inline]
     #[allow(unrooted_must_root)]
-    unsafe fn has_class_for_layout(&self, name: &str) -> bool {
+    unsafe fn get_classes_for_layout(&self) -> Option<Items<'static, Atom>> {
+        let class = Atom::from_slice("class");
         let attrs: *const Vec<JS<Attr>> = mem::transmute(&self.attrs);
         (*attrs).iter().find(|attr: & &JS<Attr>| {
             let attr = attr.unsafe_get();
-            (*attr).local_name_atom_forever().as_slice() == "class"
-        }).map_or(false, |attr| {
+            *(*attr).local_name_atom_forever() == class && (*attr).namespace == Null
+        }).and_then(|attr| {
             let attr = attr.unsafe_get();
-            (*attr).value_tokens_forever().map(|mut tokens| { tokens.any(|atom| atom.as_slice() == name) })
-        }.take().unwrap())
+            (*attr).value_tokens_forever()
+        })
+    }
+
+    #[inline]
+    unsafe fn has_class_for_layout(&self, name: &Atom) -> bool {
+        self.get_classes_for_layout().map_or(false, |mut classes| {
+            classes.any(|class| *class == *name)
+        })
+    }
+
+    #[inline]
+    unsafe fn get_state_for_layout(&self) -> ElementState {
+        self.state.get()
     }
 }
 
@@ -244,6 +305,12 @@ pub trait ElementHelpers {
     fn get_namespace<'a>(&'a self) -> &'a Namespace;
     fn summarize(self) -> Vec<AttrInfo>;
     fn is_void(self) -> bool;
+    fn get_state(self) -> ElementState;
+    fn set_state(self, which: ElementState, value: bool);
+    fn get_referrer_policy(self) -> Option<ReferrerPolicy>;
+    /// Adds the memory taken up by the attributes and the style attribute of
+    /// this element to `report`.
+    fn report_memory(self, report: &mut MemoryReport);
 }
 
 impl<'a> ElementHelpers for JSRef<'a, Element> {
@@ -285,6 +352,46 @@ impl<'a> ElementHelpers for JSRef<'a, Element> {
             _ => false
         }
     }
+
+    fn get_state(self) -> ElementState {
+        self.state.get()
+    }
+
+    /// Adds or removes the given states, restyling the document if that
+    /// changes anything.
+    fn set_state(self, which: ElementState, value: bool) {
+        let mut state = self.state.get();
+        if state.contains(which) == value {
+            return;
+        }
+        if value {
+            state.insert(which);
+        } else {
+            state.remove(which);
+        }
+        self.state.set(state);
+
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        node.dirty(NodeStyleDamaged);
+    }
+
+    /// The policy given by the referrerpolicy attribute, if it has a known value.
+    /// http://w3c.github.io/webappsec/specs/referrer-policy/#referrer-policy-attribute
+    fn get_referrer_policy(self) -> Option<ReferrerPolicy> {
+        ReferrerPolicy::from_token(self.get_string_attribute("referrerpolicy").as_slice())
+    }
+
+    fn report_memory(self, report: &mut MemoryReport) {
+        let attrs = self.attrs.borrow();
+        report.dom_nodes += attrs.heap_size_of_children() + attrs.len() * mem::size_of::<Attr>() +
+            self.style_attribute.deref().heap_size_of_children();
+        report.strings += self.prefix.heap_size_of_children();
+        for attr in attrs.iter() {
+            let attr = attr.root();
+            report.strings += attr.deref().value().heap_size_of_children() +
+                attr.prefix.heap_size_of_children();
+        }
+    }
 }
 
 pub trait AttributeHandlers {
@@ -292,6 +399,9 @@ pub trait AttributeHandlers {
     /// name, if any.
     fn get_attribute(self, namespace: Namespace, local_name: &str)
                      -> Option<Temporary<Attr>>;
+    /// Like get_attribute, for a name that was already interned.
+    fn get_attribute_atom(self, namespace: &Namespace, local_name: &Atom)
+                          -> Option<Temporary<Attr>>;
     fn set_attribute_from_parser(self, local_name: Atom,
                                  value: DOMString, namespace: Namespace,
                                  prefix: Option<DOMString>);
@@ -304,7 +414,7 @@ pub trait AttributeHandlers {
 
     fn remove_attribute(self, namespace: Namespace, name: &str);
     fn notify_attribute_changed(self, local_name: &Atom);
-    fn has_class(&self, name: &str) -> bool;
+    fn has_class(&self, name: &Atom) -> bool;
 
     fn set_atomic_attribute(self, name: &str, value: DOMString);
 
@@ -318,13 +428,19 @@ pub trait AttributeHandlers {
     fn set_tokenlist_attribute(self, name: &str, value: DOMString);
     fn get_uint_attribute(self, name: &str) -> u32;
     fn set_uint_attribute(self, name: &str, value: u32);
+    fn get_float_attribute(self, name: &str) -> Option<f64>;
+    fn set_float_attribute(self, name: &str, value: f64);
 }
 
 impl<'a> AttributeHandlers for JSRef<'a, Element> {
     fn get_attribute(self, namespace: Namespace, local_name: &str) -> Option<Temporary<Attr>> {
-        let local_name = Atom::from_slice(local_name);
+        self.get_attribute_atom(&namespace, &Atom::from_slice(local_name))
+    }
+
+    fn get_attribute_atom(self, namespace: &Namespace, local_name: &Atom)
+                          -> Option<Temporary<Attr>> {
         self.attrs.borrow().iter().map(|attr| attr.root()).find(|attr| {
-            *attr.local_name() == local_name && attr.namespace == namespace
+            *attr.local_name() == *local_name && attr.namespace == *namespace
         }).map(|x| Temporary::from_rooted(*x))
     }
 
@@ -408,26 +524,29 @@ impl<'a> AttributeHandlers for JSRef<'a, Element> {
                 }
 
                 self.deref().attrs.borrow_mut().remove(idx);
+
+                if namespace == namespace::Null {
+                    vtable_for(&NodeCast::from_ref(self)).after_remove_attr(&local_name);
+                }
             }
         };
     }
 
     fn notify_attribute_changed(self, local_name: &Atom) {
         let node: JSRef<Node> = NodeCast::from_ref(self);
-        if node.is_in_doc() {
-            let damage = match local_name.as_slice() {
-                "style" | "id" | "class" => MatchSelectorsDocumentDamage,
-                _ => ContentChangedDocumentDamage
-            };
-            let document = node.owner_doc().root();
-            document.deref().damage_and_reflow(damage);
+        node.dirty(NodeStyleDamaged);
+        match local_name.as_slice() {
+            // Selectors can match any attribute, but these don't change
+            // anything else about how the element is laid out.
+            "style" | "id" | "class" => {},
+            _ => node.dirty(ContentDamaged),
         }
     }
 
-    fn has_class(&self, name: &str) -> bool {
+    fn has_class(&self, name: &Atom) -> bool {
         self.get_attribute(Null, "class").root().map(|attr| {
             attr.deref().value().tokens().map(|mut tokens| {
-                tokens.any(|atom| atom.as_slice() == name)
+                tokens.any(|atom| *atom == *name)
             }).unwrap_or(false)
         }).unwrap_or(false)
     }
@@ -457,10 +576,20 @@ impl<'a> AttributeHandlers for JSRef<'a, Element> {
         }
     }
 
+    // http://www.whatwg.org/html/#reflecting-content-attributes-in-idl-attributes
     fn get_url_attribute(self, name: &str) -> DOMString {
         assert!(name == name.to_ascii_lower().as_slice());
-        // XXX Resolve URL.
-        self.get_string_attribute(name)
+        if !self.has_attribute(name) {
+            return "".to_string();
+        }
+        let url = self.get_string_attribute(name);
+        let doc = document_from_node(self).root();
+        let base = doc.url();
+        // XXX Resolve against the document base URL once <base> is supported.
+        match UrlParser::new().base_url(base).parse(url.as_slice()) {
+            Ok(parsed) => parsed.serialize(),
+            Err(_) => url
+        }
     }
     fn set_url_attribute(self, name: &str, value: DOMString) {
         self.set_string_attribute(name, value);
@@ -503,6 +632,17 @@ impl<'a> AttributeHandlers for JSRef<'a, Element> {
         assert!(name == name.to_ascii_lower().as_slice());
         self.set_attribute(name, UIntAttrValue(value.to_string(), value));
     }
+
+    fn get_float_attribute(self, name: &str) -> Option<f64> {
+        assert!(name == name.to_ascii_lower().as_slice());
+        self.get_attribute(Null, name).root().and_then(|attribute| {
+            parse_floating_point_number(attribute.deref().value().as_slice())
+        })
+    }
+    fn set_float_attribute(self, name: &str, value: f64) {
+        assert!(name == name.to_ascii_lower().as_slice());
+        self.set_attribute(name, StringAttrValue(value.to_string()));
+    }
 }
 
 impl<'a> ElementMethods for JSRef<'a, Element> {
@@ -558,31 +698,34 @@ impl<'a> ElementMethods for JSRef<'a, Element> {
 
     // http://dom.spec.whatwg.org/#dom-element-classlist
     fn ClassList(self) -> Temporary<DOMTokenList> {
-        match self.class_list.get() {
-            Some(class_list) => Temporary::new(class_list),
-            None => {
-                let class_list = DOMTokenList::new(self, "class").root();
-                self.class_list.assign(Some(class_list.deref().clone()));
-                Temporary::from_rooted(*class_list)
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        node.with_rare_data(|rare_data| {
+            match rare_data.class_list.get() {
+                Some(class_list) => Temporary::new(class_list),
+                None => {
+                    let class_list = DOMTokenList::new(self, "class").root();
+                    rare_data.class_list.assign(Some(class_list.deref().clone()));
+                    Temporary::from_rooted(*class_list)
+                }
             }
-        }
+        })
     }
 
     // http://dom.spec.whatwg.org/#dom-element-attributes
     fn Attributes(self) -> Temporary<NamedNodeMap> {
-        match self.attr_list.get() {
-            None => (),
-            Some(ref list) => return Temporary::new(list.clone()),
-        }
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        node.with_rare_data(|rare_data| {
+            match rare_data.attr_list.get() {
+                None => (),
+                Some(ref list) => return Temporary::new(list.clone()),
+            }
 
-        let doc = {
-            let node: JSRef<Node> = NodeCast::from_ref(self);
-            node.owner_doc().root()
-        };
-        let window = doc.deref().window.root();
-        let list = NamedNodeMap::new(*window, self);
-        self.attr_list.assign(Some(list));
-        Temporary::new(self.attr_list.get().as_ref().unwrap().clone())
+            let doc = node.owner_doc().root();
+            let window = doc.deref().window.root();
+            let list = NamedNodeMap::new(*window, self);
+            rare_data.attr_list.assign(Some(list));
+            Temporary::new(rare_data.attr_list.get().as_ref().unwrap().clone())
+        })
     }
 
     // http://dom.spec.whatwg.org/#dom-element-getattribute
@@ -973,8 +1116,13 @@ impl<'a> style::TElement<'a> for JSRef<'a, Element> {
         (self as &ElementHelpers).get_namespace()
     }
     fn get_hover_state(&self) -> bool {
-        let node: JSRef<Node> = NodeCast::from_ref(*self);
-        node.get_hover_state()
+        self.get_state().contains(InHoverState)
+    }
+    fn get_active_state(&self) -> bool {
+        self.get_state().contains(InActiveState)
+    }
+    fn get_focus_state(&self) -> bool {
+        self.get_state().contains(InFocusState)
     }
     fn get_id<'a>(&self) -> Option<Atom> {
         self.get_attribute(namespace::Null, "id").map(|attr| {
@@ -986,14 +1134,36 @@ impl<'a> style::TElement<'a> for JSRef<'a, Element> {
         })
     }
     fn get_disabled_state(&self) -> bool {
-        let node: JSRef<Node> = NodeCast::from_ref(*self);
-        node.get_disabled_state()
+        self.get_state().contains(InDisabledState)
     }
     fn get_enabled_state(&self) -> bool {
-        let node: JSRef<Node> = NodeCast::from_ref(*self);
-        node.get_enabled_state()
+        self.get_state().contains(InEnabledState)
+    }
+    fn get_checked_state(&self) -> bool {
+        self.get_state().contains(InCheckedState)
+    }
+    fn get_indeterminate_state(&self) -> bool {
+        self.get_state().contains(InIndeterminateState)
     }
-    fn has_class(&self, name: &str) -> bool {
+    fn get_target_state(&self) -> bool {
+        self.get_state().contains(InTargetState)
+    }
+    fn has_class(&self, name: &Atom) -> bool {
         (self as &AttributeHandlers).has_class(name)
     }
+    fn each_class(&self, callback: |&Atom|) {
+        match self.get_attribute(Null, "class").root() {
+            Some(attr) => {
+                match attr.deref().value().tokens() {
+                    Some(mut tokens) => {
+                        for token in tokens {
+                            callback(token)
+                        }
+                    },
+                    None => {},
+                }
+            },
+            None => {},
+        }
+    }
 }
@@ -0,0 +1,90 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::ErrorEventBinding;
+use dom::bindings::codegen::Bindings::ErrorEventBinding::ErrorEventMethods;
+use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use dom::bindings::codegen::InheritTypes::{ErrorEventDerived, EventCast};
+use dom::bindings::error::Fallible;
+use dom::bindings::global::GlobalRef;
+use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::event::{Event, ErrorEventTypeId};
+use servo_util::str::DOMString;
+
+#[jstraceable]
+#[must_root]
+pub struct ErrorEvent {
+    event: Event,
+    message: DOMString,
+    filename: DOMString,
+    lineno: u32,
+    colno: u32,
+}
+
+impl ErrorEventDerived for Event {
+    fn is_errorevent(&self) -> bool {
+        self.type_id == ErrorEventTypeId
+    }
+}
+
+impl ErrorEvent {
+    fn new_inherited(message: DOMString, filename: DOMString,
+                     lineno: u32, colno: u32) -> ErrorEvent {
+        ErrorEvent {
+            event: Event::new_inherited(ErrorEventTypeId),
+            message: message,
+            filename: filename,
+            lineno: lineno,
+            colno: colno,
+        }
+    }
+
+    pub fn new(global: &GlobalRef, type_: DOMString,
+               can_bubble: bool, cancelable: bool,
+               message: DOMString, filename: DOMString,
+               lineno: u32, colno: u32) -> Temporary<ErrorEvent> {
+        let ev = reflect_dom_object(box ErrorEvent::new_inherited(message, filename,
+                                                                  lineno, colno),
+                                    global,
+                                    ErrorEventBinding::Wrap).root();
+        let event: JSRef<Event> = EventCast::from_ref(*ev);
+        event.InitEvent(type_, can_bubble, cancelable);
+        Temporary::from_rooted(*ev)
+    }
+
+    pub fn Constructor(global: &GlobalRef,
+                       type_: DOMString,
+                       init: &ErrorEventBinding::ErrorEventInit)
+                       -> Fallible<Temporary<ErrorEvent>> {
+        let ev = ErrorEvent::new(global, type_, init.parent.bubbles, init.parent.cancelable,
+                                 init.message.clone(), init.filename.clone(),
+                                 init.lineno, init.colno);
+        Ok(ev)
+    }
+}
+
+impl<'a> ErrorEventMethods for JSRef<'a, ErrorEvent> {
+    fn Message(self) -> DOMString {
+        self.message.clone()
+    }
+
+    fn Filename(self) -> DOMString {
+        self.filename.clone()
+    }
+
+    fn Lineno(self) -> u32 {
+        self.lineno
+    }
+
+    fn Colno(self) -> u32 {
+        self.colno
+    }
+}
+
+impl Reflectable for ErrorEvent {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        self.event.reflector()
+    }
+}
@@ -26,11 +26,15 @@ pub enum EventPhase {
 #[deriving(PartialEq)]
 #[jstraceable]
 pub enum EventTypeId {
+    BeforeUnloadEventTypeId,
+    CloseEventTypeId,
     CustomEventTypeId,
+    ErrorEventTypeId,
     HTMLEventTypeId,
-    KeyEventTypeId,
+    KeyboardEventTypeId,
     MessageEventTypeId,
     MouseEventTypeId,
+    PageTransitionEventTypeId,
     ProgressEventTypeId,
     UIEventTypeId
 }
@@ -2,14 +2,62 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use dom::bindings::callback::ReportExceptions;
+use dom::bindings::callback::{CallbackContainer, ReportExceptions};
+use dom::bindings::codegen::Bindings::BeforeUnloadEventBinding::BeforeUnloadEventMethods;
 use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
-use dom::bindings::codegen::InheritTypes::{EventTargetCast, NodeCast, NodeDerived};
-use dom::bindings::js::{JS, JSRef, OptionalSettable, OptionalRootable, Root};
-use dom::eventtarget::{Capturing, Bubbling, EventTarget};
+use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
+use dom::bindings::codegen::InheritTypes::{BeforeUnloadEventCast, EventTargetCast, NodeCast};
+use dom::bindings::codegen::InheritTypes::NodeDerived;
+use dom::bindings::conversions;
+use dom::bindings::conversions::FromJSValConvertible;
+use dom::bindings::global::global_object_for_js_object;
+use dom::bindings::js::{JSRef, OptionalSettable, OptionalRootable, RootedVec};
+use dom::bindings::utils::Reflectable;
+use dom::beforeunloadevent::BeforeUnloadEvent;
+use dom::eventtarget::{Capturing, Bubbling, EventTarget, EventListenerType, Additive, Inline};
 use dom::event::{Event, PhaseAtTarget, PhaseNone, PhaseBubbling, PhaseCapturing};
 use dom::node::{Node, NodeHelpers};
 use dom::virtualmethods::vtable_for;
+use servo_util::str::DOMString;
+
+use js::jsval::JSVal;
+
+/// Calls `listener` for `event` at `target`, dropping any exception on the
+/// floor.
+fn invoke_listener(listener: EventListenerType, target: JSRef<EventTarget>, event: JSRef<Event>) {
+    match listener {
+        Additive(listener) => {
+            let _ = listener.HandleEvent_(target, event, ReportExceptions);
+        },
+        Inline(listener) => {
+            let handler: EventHandlerNonNull = CallbackContainer::new(listener.parent.callback());
+            match handler.Call_(target, event, ReportExceptions) {
+                Ok(value) => process_handler_return_value(target, event, value),
+                Err(_) => {},
+            }
+        },
+    }
+}
+
+/// Does what the value an event handler returned asks for. Only
+/// beforeunload events look at it so far.
+/// http://www.whatwg.org/html/#the-event-handler-processing-algorithm
+fn process_handler_return_value(target: JSRef<EventTarget>, event: JSRef<Event>, value: JSVal) {
+    let beforeunload: Option<JSRef<BeforeUnloadEvent>> = BeforeUnloadEventCast::to_ref(event);
+    match beforeunload {
+        Some(beforeunload) if !value.is_null_or_undefined() => {
+            event.PreventDefault();
+            if beforeunload.ReturnValue().is_empty() {
+                let global = global_object_for_js_object(target.reflector().get_jsobject()).root();
+                let cx = global.root_ref().get_cx();
+                let message: Result<DOMString, ()> =
+                    FromJSValConvertible::from_jsval(cx, value, conversions::Default);
+                beforeunload.SetReturnValue(message.unwrap_or("".to_string()));
+            }
+        },
+        _ => {},
+    }
+}
 
 // See http://dom.spec.whatwg.org/#concept-event-dispatch for the full dispatch algorithm
 pub fn dispatch_event<'a, 'b>(target: JSRef<'a, EventTarget>,
@@ -26,28 +74,26 @@ pub fn dispatch_event<'a, 'b>(target: JSRef<'a, EventTarget>,
     let type_ = event.Type();
 
     //TODO: no chain if not participating in a tree
-    let mut chain: Vec<Root<EventTarget>> = if target.deref().is_node() {
+    let mut chain: RootedVec<EventTarget> = RootedVec::new();
+    if target.deref().is_node() {
         let target_node: JSRef<Node> = NodeCast::to_ref(target).unwrap();
-        target_node.ancestors().map(|ancestor| {
+        for ancestor in target_node.ancestors() {
             let ancestor_target: JSRef<EventTarget> = EventTargetCast::from_ref(ancestor);
-            JS::from_rooted(ancestor_target).root()
-        }).collect()
-    } else {
-        vec!()
-    };
+            chain.push(ancestor_target);
+        }
+    }
 
     event.deref().phase.deref().set(PhaseCapturing);
 
     //FIXME: The "callback this value" should be currentTarget
 
     /* capturing */
-    for cur_target in chain.as_slice().iter().rev() {
+    for cur_target in chain.iter().rev() {
         let stopped = match cur_target.get_listeners_for(type_.as_slice(), Capturing) {
             Some(listeners) => {
-                event.current_target.assign(Some(cur_target.deref().clone()));
+                event.current_target.assign(Some(cur_target));
                 for listener in listeners.iter() {
-                    // Explicitly drop any exception on the floor.
-                    let _ = listener.HandleEvent_(**cur_target, event, ReportExceptions);
+                    invoke_listener(*listener, cur_target, event);
 
                     if event.deref().stop_immediate.deref().get() {
                         break;
@@ -72,8 +118,7 @@ pub fn dispatch_event<'a, 'b>(target: JSRef<'a, EventTarget>,
         let opt_listeners = target.deref().get_listeners(type_.as_slice());
         for listeners in opt_listeners.iter() {
             for listener in listeners.iter() {
-                // Explicitly drop any exception on the floor.
-                let _ = listener.HandleEvent_(target, event, ReportExceptions);
+                invoke_listener(*listener, target, event);
 
                 if event.deref().stop_immediate.deref().get() {
                     break;
@@ -89,10 +134,9 @@ pub fn dispatch_event<'a, 'b>(target: JSRef<'a, EventTarget>,
         for cur_target in chain.iter() {
             let stopped = match cur_target.deref().get_listeners_for(type_.as_slice(), Bubbling) {
                 Some(listeners) => {
-                    event.deref().current_target.assign(Some(cur_target.deref().clone()));
+                    event.deref().current_target.assign(Some(cur_target));
                     for listener in listeners.iter() {
-                        // Explicitly drop any exception on the floor.
-                        let _ = listener.HandleEvent_(**cur_target, event, ReportExceptions);
+                        invoke_listener(*listener, cur_target, event);
 
                         if event.deref().stop_immediate.deref().get() {
                             break;
@@ -125,12 +169,6 @@ pub fn dispatch_event<'a, 'b>(target: JSRef<'a, EventTarget>,
         None => {}
     }
 
-    // Root ordering restrictions mean we need to unroot the chain entries
-    // in the same order they were rooted.
-    while chain.len() > 0 {
-        let _ = chain.pop();
-    }
-
     event.dispatching.deref().set(false);
     event.phase.deref().set(PhaseNone);
     event.current_target.set(None);
@@ -0,0 +1,232 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
+use dom::bindings::codegen::Bindings::EventSourceBinding;
+use dom::bindings::codegen::Bindings::EventSourceBinding::{EventSourceInit, EventSourceMethods};
+use dom::bindings::codegen::InheritTypes::{EventCast, EventTargetCast, EventSourceDerived};
+use dom::bindings::conversions::ToJSValConvertible;
+use dom::bindings::error::{Fallible, Syntax};
+use dom::bindings::global::{GlobalRef, GlobalField};
+use dom::bindings::js::{JS, JSRef, Temporary};
+use dom::bindings::trace::{Traceable, Untraceable};
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::event::Event;
+use dom::eventtarget::{EventTarget, EventTargetHelpers, EventSourceTypeId};
+use dom::messageevent::MessageEvent;
+use dom::window::EventSourceOwner;
+use script_task::EventSourceMsg;
+use security::Origin;
+use task_source::NetworkingTaskSource;
+use servo_net::event_source_task;
+use servo_net::event_source_task::{EventSourceEvent, ServerSentEvent};
+use servo_util::str::DOMString;
+use servo_util::task::spawn_named;
+
+use js::jsapi::{JS_AddObjectRoot, JS_RemoveObjectRoot};
+
+use libc::c_void;
+use std::cell::Cell;
+use std::comm::{channel, Sender};
+use url::{Url, UrlParser};
+
+#[deriving(PartialEq)]
+#[jstraceable]
+enum EventSourceReadyState {
+    Connecting = 0,
+    Open = 1,
+    Closed = 2,
+}
+
+pub struct TrustedEventSourceAddress(pub *const c_void);
+
+#[jstraceable]
+#[must_root]
+pub struct EventSource {
+    eventtarget: EventTarget,
+    global: GlobalField,
+    url: Untraceable<Url>,
+    with_credentials: bool,
+    ready_state: Traceable<Cell<EventSourceReadyState>>,
+    /// Closes the connection when sent to.
+    close: Untraceable<Sender<()>>,
+}
+
+impl EventSource {
+    fn new_inherited(global: &GlobalRef, url: Url, with_credentials: bool,
+                     close: Sender<()>) -> EventSource {
+        EventSource {
+            eventtarget: EventTarget::new_inherited(EventSourceTypeId),
+            global: GlobalField::from_rooted(global),
+            url: Untraceable::new(url),
+            with_credentials: with_credentials,
+            ready_state: Traceable::new(Cell::new(Connecting)),
+            close: Untraceable::new(close),
+        }
+    }
+
+    fn new(global: &GlobalRef, url: Url, with_credentials: bool) -> Temporary<EventSource> {
+        let (events_chan, events_port) = channel();
+        let close = event_source_task::connect(global.resource_task(), url.clone(),
+                                               global.get_url(), with_credentials, events_chan);
+        let event_source = reflect_dom_object(box EventSource::new_inherited(global, url,
+                                                                             with_credentials,
+                                                                             close),
+                                              global,
+                                              EventSourceBinding::Wrap).root();
+
+        // The object stays alive until the connection is finished, which is
+        // the last thing the connection tells it about.
+        let addr = unsafe { event_source.to_trusted() };
+        let script_chan = global.script_chan().clone();
+        spawn_named("EventSource:events", proc() {
+            for event in events_port.iter() {
+                script_chan.queue_task(NetworkingTaskSource, EventSourceMsg(addr, event));
+            }
+        });
+        Temporary::from_rooted(*event_source)
+    }
+
+    // http://www.whatwg.org/html/#dom-eventsource
+    pub fn Constructor(global: &GlobalRef, url: DOMString,
+                       init: &EventSourceInit) -> Fallible<Temporary<EventSource>> {
+        // Steps 2-3
+        let base = global.get_url();
+        let url = match UrlParser::new().base_url(&base).parse(url.as_slice()) {
+            Ok(url) => url,
+            Err(_) => return Err(Syntax),
+        };
+
+        // Steps 4-12
+        Ok(EventSource::new(global, url, init.withCredentials))
+    }
+
+    pub fn handle_event(addr: TrustedEventSourceAddress, event: EventSourceEvent) {
+        unsafe {
+            let event_source = JS::from_trusted_event_source_address(addr).root();
+            event_source.process_event(event);
+        }
+    }
+}
+
+trait PrivateEventSourceHelpers {
+    unsafe fn to_trusted(self) -> TrustedEventSourceAddress;
+    fn release(self);
+    fn process_event(self, event: EventSourceEvent);
+    fn dispatch_simple_event(self, type_: DOMString);
+    fn dispatch_message(self, event: ServerSentEvent);
+}
+
+impl<'a> PrivateEventSourceHelpers for JSRef<'a, EventSource> {
+    // Creates a trusted address to the object, and roots it until release()
+    // is called, when the connection is finished.
+    unsafe fn to_trusted(self) -> TrustedEventSourceAddress {
+        let global = self.global.root();
+        JS_AddObjectRoot(global.root_ref().get_cx(), self.reflector().rootable());
+        global.root_ref().track_network_owner(EventSourceOwner(JS::from_rooted(self)));
+        TrustedEventSourceAddress(self.deref() as *const EventSource as *const c_void)
+    }
+
+    fn release(self) {
+        let global = self.global.root();
+        unsafe {
+            JS_RemoveObjectRoot(global.root_ref().get_cx(), self.reflector().rootable());
+        }
+        global.root_ref().untrack_network_owner(EventSourceOwner(JS::from_rooted(self)));
+    }
+
+    fn process_event(self, event: EventSourceEvent) {
+        // Whatever was queued before close() was called is dropped.
+        if self.ready_state.deref().get() == Closed {
+            match event {
+                event_source_task::Finished => self.release(),
+                _ => {},
+            }
+            return;
+        }
+        match event {
+            // http://www.whatwg.org/html/#announce-the-connection
+            event_source_task::Opened => {
+                self.ready_state.deref().set(Open);
+                self.dispatch_simple_event("open".to_string());
+            },
+            event_source_task::EventReceived(event) => self.dispatch_message(event),
+            // http://www.whatwg.org/html/#reestablish-the-connection
+            event_source_task::Reconnecting => {
+                self.ready_state.deref().set(Connecting);
+                self.dispatch_simple_event("error".to_string());
+            },
+            // http://www.whatwg.org/html/#fail-the-connection
+            event_source_task::Failed => {
+                self.ready_state.deref().set(Closed);
+                self.dispatch_simple_event("error".to_string());
+            },
+            event_source_task::Finished => self.release(),
+        }
+    }
+
+    fn dispatch_simple_event(self, type_: DOMString) {
+        let global = self.global.root();
+        let event = Event::new(&global.root_ref(), type_, false, false).root();
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        target.dispatch_event_with_target(None, *event).ok();
+    }
+
+    // http://www.whatwg.org/html/#dispatchMessage
+    fn dispatch_message(self, event: ServerSentEvent) {
+        let global = self.global.root();
+        let data = event.data.to_jsval(global.root_ref().get_cx());
+        let origin = Origin::from_url(&*self.url).serialize();
+        let messageevent = MessageEvent::new(&global.root_ref(), event.type_, false, false,
+                                             data, origin, event.last_event_id).root();
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        let event: JSRef<Event> = EventCast::from_ref(*messageevent);
+        target.dispatch_event_with_target(None, event).ok();
+    }
+}
+
+impl<'a> EventSourceMethods for JSRef<'a, EventSource> {
+    // http://www.whatwg.org/html/#dom-eventsource-url
+    fn Url(self) -> DOMString {
+        self.url.serialize()
+    }
+
+    // http://www.whatwg.org/html/#dom-eventsource-withcredentials
+    fn WithCredentials(self) -> bool {
+        self.with_credentials
+    }
+
+    // http://www.whatwg.org/html/#dom-eventsource-readystate
+    fn ReadyState(self) -> u16 {
+        self.ready_state.deref().get() as u16
+    }
+
+    // http://www.whatwg.org/html/#dom-eventsource-close
+    fn Close(self) {
+        if self.ready_state.deref().get() == Closed {
+            return;
+        }
+        self.ready_state.deref().set(Closed);
+        let _ = self.close.send_opt(());
+    }
+
+    event_handler!(open, GetOnopen, SetOnopen)
+    event_handler!(message, GetOnmessage, SetOnmessage)
+    event_handler!(error, GetOnerror, SetOnerror)
+}
+
+impl Reflectable for EventSource {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        self.eventtarget.reflector()
+    }
+}
+
+impl EventSourceDerived for EventTarget {
+    fn is_eventsource(&self) -> bool {
+        match self.type_id {
+            EventSourceTypeId => true,
+            _ => false
+        }
+    }
+}
@@ -18,6 +18,7 @@ use dom::xmlhttprequest::XMLHttpRequestId;
 use dom::virtualmethods::VirtualMethods;
 use js::jsapi::{JS_CompileUCFunction, JS_GetFunctionObject, JS_CloneFunctionObject};
 use js::jsapi::{JSContext, JSObject};
+use servo_util::memory::HeapSizeOf;
 use servo_util::str::DOMString;
 use libc::{c_char, size_t};
 use std::cell::RefCell;
@@ -36,7 +37,12 @@ pub enum ListenerPhase {
 #[deriving(PartialEq)]
 #[jstraceable]
 pub enum EventTargetTypeId {
+    EventSourceTypeId,
+    FileReaderTypeId,
+    MessagePortTypeId,
     NodeTargetTypeId(NodeTypeId),
+    SharedWorkerTypeId,
+    WebSocketTypeId,
     WindowTypeId,
     WorkerTypeId,
     WorkerGlobalScopeTypeId(WorkerGlobalScopeId),
@@ -65,34 +71,59 @@ pub struct EventListenerEntry {
     pub listener: EventListenerType
 }
 
+impl HeapSizeOf for EventListenerEntry {
+    fn heap_size_of_children(&self) -> uint {
+        0
+    }
+}
+
+type EventListenerMap = HashMap<DOMString, Vec<EventListenerEntry>>;
+
+/// Returns the map that `handlers` holds, which is only allocated once the
+/// first listener is added since most event targets never get any.
+fn listener_map<'a>(handlers: &'a mut Option<Box<EventListenerMap>>) -> &'a mut EventListenerMap {
+    if handlers.is_none() {
+        *handlers = Some(box HashMap::new());
+    }
+    &mut **handlers.as_mut().unwrap()
+}
+
 #[jstraceable]
 #[must_root]
 pub struct EventTarget {
     pub type_id: EventTargetTypeId,
     reflector_: Reflector,
-    handlers: Traceable<RefCell<HashMap<DOMString, Vec<EventListenerEntry>>>>,
+    handlers: Traceable<RefCell<Option<Box<EventListenerMap>>>>,
 }
 
 impl EventTarget {
+    /// The memory taken up by the listeners of this target, leaving out the
+    /// functions themselves, which live on the JS heap.
+    pub fn heap_size_of_listeners(&self) -> uint {
+        self.handlers.deref().heap_size_of_children()
+    }
+
     pub fn new_inherited(type_id: EventTargetTypeId) -> EventTarget {
         EventTarget {
             type_id: type_id,
             reflector_: Reflector::new(),
-            handlers: Traceable::new(RefCell::new(HashMap::new())),
+            handlers: Traceable::new(RefCell::new(None)),
         }
     }
 
-    pub fn get_listeners(&self, type_: &str) -> Option<Vec<EventListener>> {
-        self.handlers.deref().borrow().find_equiv(&type_).map(|listeners| {
-            listeners.iter().map(|entry| entry.listener.get_listener()).collect()
+    pub fn get_listeners(&self, type_: &str) -> Option<Vec<EventListenerType>> {
+        let handlers = self.handlers.deref().borrow();
+        handlers.as_ref().and_then(|handlers| handlers.find_equiv(&type_)).map(|listeners| {
+            listeners.iter().map(|entry| entry.listener).collect()
         })
     }
 
     pub fn get_listeners_for(&self, type_: &str, desired_phase: ListenerPhase)
-        -> Option<Vec<EventListener>> {
-        self.handlers.deref().borrow().find_equiv(&type_).map(|listeners| {
+        -> Option<Vec<EventListenerType>> {
+        let handlers = self.handlers.deref().borrow();
+        handlers.as_ref().and_then(|handlers| handlers.find_equiv(&type_)).map(|listeners| {
             let filtered = listeners.iter().filter(|entry| entry.phase == desired_phase);
-            filtered.map(|entry| entry.listener.get_listener()).collect()
+            filtered.map(|entry| entry.listener).collect()
         })
     }
 }
@@ -132,7 +163,7 @@ impl<'a> EventTargetHelpers for JSRef<'a, EventTarget> {
                                  ty: DOMString,
                                  listener: Option<EventListener>) {
         let mut handlers = self.handlers.deref().borrow_mut();
-        let entries = handlers.find_or_insert_with(ty, |_| vec!());
+        let entries = listener_map(&mut *handlers).find_or_insert_with(ty, |_| vec!());
         let idx = entries.iter().position(|&entry| {
             match entry.listener {
                 Inline(_) => true,
@@ -162,7 +193,7 @@ impl<'a> EventTargetHelpers for JSRef<'a, EventTarget> {
 
     fn get_inline_event_listener(self, ty: DOMString) -> Option<EventListener> {
         let handlers = self.handlers.deref().borrow();
-        let entries = handlers.find(&ty);
+        let entries = handlers.as_ref().and_then(|handlers| handlers.find(&ty));
         entries.and_then(|entries| entries.iter().find(|entry| {
             match entry.listener {
                 Inline(_) => true,
@@ -224,7 +255,7 @@ impl<'a> EventTargetHelpers for JSRef<'a, EventTarget> {
     }
 
     fn has_handlers(self) -> bool {
-        !self.handlers.deref().borrow().is_empty()
+        self.handlers.deref().borrow().as_ref().map_or(false, |handlers| !handlers.is_empty())
     }
 }
 
@@ -236,7 +267,7 @@ impl<'a> EventTargetMethods for JSRef<'a, EventTarget> {
         match listener {
             Some(listener) => {
                 let mut handlers = self.handlers.deref().borrow_mut();
-                let entry = handlers.find_or_insert_with(ty, |_| vec!());
+                let entry = listener_map(&mut *handlers).find_or_insert_with(ty, |_| vec!());
                 let phase = if capture { Capturing } else { Bubbling };
                 let new_entry = EventListenerEntry {
                     phase: phase,
@@ -257,7 +288,7 @@ impl<'a> EventTargetMethods for JSRef<'a, EventTarget> {
         match listener {
             Some(listener) => {
                 let mut handlers = self.handlers.deref().borrow_mut();
-                let mut entry = handlers.find_mut(&ty);
+                let mut entry = handlers.as_mut().and_then(|handlers| handlers.find_mut(&ty));
                 for entry in entry.iter_mut() {
                     let phase = if capture { Capturing } else { Bubbling };
                     let old_entry = EventListenerEntry {
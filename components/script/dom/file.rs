@@ -3,43 +3,72 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::bindings::codegen::Bindings::FileBinding;
-use dom::bindings::codegen::Bindings::FileBinding::FileMethods;
+use dom::bindings::codegen::Bindings::FileBinding::{FileMethods, FilePropertyBag};
+use dom::bindings::codegen::UnionTypes::ArrayBufferOrBlobOrString::ArrayBufferOrBlobOrString;
+use dom::bindings::error::Fallible;
 use dom::bindings::global::GlobalRef;
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
-use dom::blob::{Blob, BlobType, FileTypeId};
+use dom::blob::{Blob, FileTypeId, bytes_of_parts};
 use servo_util::str::DOMString;
 
+use time;
+
 #[jstraceable]
 #[must_root]
 pub struct File {
     pub blob: Blob,
     pub name: DOMString,
-    pub type_: BlobType
+    /// The time of the last modification, in milliseconds since the epoch.
+    last_modified: i64,
 }
 
 impl File {
-    fn new_inherited(_file_bits: JSRef<Blob>, name: DOMString) -> File {
+    fn new_inherited(global: &GlobalRef, bytes: Vec<u8>, type_string: DOMString,
+                     name: DOMString, last_modified: i64) -> File {
         File {
-            blob: Blob::new_inherited(),
+            blob: Blob::new_inherited(global, FileTypeId, bytes, type_string),
             name: name,
-            type_: FileTypeId
+            last_modified: last_modified,
         }
-        // XXXManishearth Once Blob is able to store data
-        // the relevant subfields of file_bits should be copied over
     }
 
-    pub fn new(global: &GlobalRef, file_bits: JSRef<Blob>, name: DOMString) -> Temporary<File> {
-        reflect_dom_object(box File::new_inherited(file_bits, name),
+    pub fn new(global: &GlobalRef, bytes: Vec<u8>, type_string: DOMString, name: DOMString,
+               last_modified: i64) -> Temporary<File> {
+        reflect_dom_object(box File::new_inherited(global, bytes, type_string, name,
+                                                   last_modified),
                            global,
                            FileBinding::Wrap)
     }
+
+    // http://dev.w3.org/2006/webapi/FileAPI/#dfn-file
+    pub fn Constructor(global: &GlobalRef, file_bits: Vec<ArrayBufferOrBlobOrString>,
+                       file_name: DOMString, options: &FilePropertyBag) -> Fallible<Temporary<File>> {
+        let bytes = bytes_of_parts(global, file_bits);
+        let last_modified = match options.lastModified {
+            Some(last_modified) => last_modified,
+            None => now(),
+        };
+        Ok(File::new(global, bytes, options.parent.type_.clone(), file_name, last_modified))
+    }
+}
+
+/// Returns the current time, in milliseconds since the epoch.
+pub fn now() -> i64 {
+    let now = time::get_time();
+    now.sec * 1000 + (now.nsec / 1000000) as i64
 }
 
 impl<'a> FileMethods for JSRef<'a, File> {
+    // http://dev.w3.org/2006/webapi/FileAPI/#dfn-name
     fn Name(self) -> DOMString {
         self.name.clone()
     }
+
+    // http://dev.w3.org/2006/webapi/FileAPI/#dfn-lastModified
+    fn LastModified(self) -> i64 {
+        self.last_modified
+    }
 }
 
 impl Reflectable for File {
@@ -0,0 +1,373 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
+use dom::bindings::codegen::Bindings::FileReaderBinding;
+use dom::bindings::codegen::Bindings::FileReaderBinding::FileReaderMethods;
+use dom::bindings::codegen::InheritTypes::{EventCast, EventTargetCast, FileReaderDerived};
+use dom::bindings::conversions::ToJSValConvertible;
+use dom::bindings::error::{ErrorResult, Fallible, InvalidState};
+use dom::bindings::global::{GlobalRef, GlobalField};
+use dom::bindings::js::{JS, JSRef, Temporary};
+use dom::bindings::trace::Traceable;
+use dom::bindings::utils::{Reflectable, Reflector, new_array_buffer, reflect_dom_object};
+use dom::blob::{Blob, BlobHelpers};
+use dom::domexception::DOMException;
+use dom::event::Event;
+use dom::eventtarget::{EventTarget, EventTargetHelpers, FileReaderTypeId};
+use dom::progressevent::ProgressEvent;
+use dom::window::FileReaderOwner;
+use script_task::FileReaderMsg;
+use task_source::NetworkingTaskSource;
+use servo_util::str::DOMString;
+use servo_util::task::spawn_named;
+
+use encoding::all::{UTF_8, UTF_16BE, UTF_16LE};
+use encoding::label::encoding_from_whatwg_label;
+use encoding::types::{DecodeReplace, EncodingRef};
+
+use js::jsapi::{JSContext, JS_AddObjectRoot, JS_RemoveObjectRoot};
+use js::jsval::{JSVal, NullValue};
+
+use libc::c_void;
+use serialize::base64::{ToBase64, STANDARD};
+use std::ascii::StrAsciiExt;
+use std::cell::Cell;
+
+#[deriving(PartialEq)]
+#[jstraceable]
+enum FileReaderReadyState {
+    Empty = 0,
+    Loading = 1,
+    Done = 2,
+}
+
+/// Identifies the read a progress message belongs to, so that messages still
+/// queued for a read that abort() terminated can be ignored.
+#[deriving(PartialEq, Clone, Show)]
+pub struct GenerationId(uint);
+
+/// What the result of a read is made of.
+pub enum ReadKind {
+    /// readAsText, with the encoding label and the type of the blob
+    TextRead(Option<DOMString>, DOMString),
+    /// readAsDataURL, with the type of the blob
+    DataURLRead(DOMString),
+    /// readAsArrayBuffer
+    ArrayBufferRead,
+}
+
+pub enum FileReaderProgress {
+    /// The read has started, on a blob of the given size
+    LoadStartMsg(GenerationId, u64),
+    /// Some of the blob has been read
+    LoadingMsg(GenerationId, u64, u64),
+    /// The read is done, with the contents of the blob
+    DoneMsg(GenerationId, ReadKind, Vec<u8>),
+}
+
+pub struct TrustedFileReaderAddress(pub *const c_void);
+
+#[jstraceable]
+#[must_root]
+pub struct FileReader {
+    eventtarget: EventTarget,
+    global: GlobalField,
+    ready_state: Traceable<Cell<FileReaderReadyState>>,
+    result: Traceable<Cell<Traceable<JSVal>>>,
+    pinned_count: Traceable<Cell<uint>>,
+    generation_id: Traceable<Cell<GenerationId>>,
+}
+
+impl FileReader {
+    fn new_inherited(global: &GlobalRef) -> FileReader {
+        FileReader {
+            eventtarget: EventTarget::new_inherited(FileReaderTypeId),
+            global: GlobalField::from_rooted(global),
+            ready_state: Traceable::new(Cell::new(Empty)),
+            result: Traceable::new(Cell::new(Traceable::new(NullValue()))),
+            pinned_count: Traceable::new(Cell::new(0)),
+            generation_id: Traceable::new(Cell::new(GenerationId(0))),
+        }
+    }
+
+    pub fn new(global: &GlobalRef) -> Temporary<FileReader> {
+        reflect_dom_object(box FileReader::new_inherited(global),
+                           global,
+                           FileReaderBinding::Wrap)
+    }
+
+    pub fn Constructor(global: &GlobalRef) -> Fallible<Temporary<FileReader>> {
+        Ok(FileReader::new(global))
+    }
+
+    pub fn handle_progress(addr: TrustedFileReaderAddress, progress: FileReaderProgress) {
+        unsafe {
+            let reader = JS::from_trusted_file_reader_address(addr).root();
+            reader.process_progress(progress);
+        }
+    }
+}
+
+pub trait FileReaderHelpers {
+    fn terminate_for_unload(self);
+}
+
+impl<'a> FileReaderHelpers for JSRef<'a, FileReader> {
+    /// Terminates the read that is going on, without firing any event,
+    /// because the document that started it is being unloaded. The reader is
+    /// released once the read is done.
+    fn terminate_for_unload(self) {
+        if self.ready_state.deref().get() != Loading {
+            return;
+        }
+        self.ready_state.deref().set(Done);
+        self.set_result(NullValue());
+        self.terminate_ongoing_read();
+    }
+}
+
+trait PrivateFileReaderHelpers {
+    unsafe fn to_trusted(self) -> TrustedFileReaderAddress;
+    fn release_once(self);
+    fn read(self, blob: JSRef<Blob>, kind: ReadKind) -> ErrorResult;
+    fn process_progress(self, progress: FileReaderProgress);
+    fn terminate_ongoing_read(self);
+    fn dispatch_progress_event(self, type_: DOMString, loaded: u64, total: u64);
+    fn set_result(self, value: JSVal);
+}
+
+impl<'a> PrivateFileReaderHelpers for JSRef<'a, FileReader> {
+    // Creates a trusted address to the object, and roots it. Always pair this with a release()
+    unsafe fn to_trusted(self) -> TrustedFileReaderAddress {
+        if self.pinned_count.deref().get() == 0 {
+            let global = self.global.root();
+            JS_AddObjectRoot(global.root_ref().get_cx(), self.reflector().rootable());
+            global.root_ref().track_network_owner(FileReaderOwner(JS::from_rooted(self)));
+        }
+        let pinned_count = self.pinned_count.deref().get();
+        self.pinned_count.deref().set(pinned_count + 1);
+        TrustedFileReaderAddress(self.deref() as *const FileReader as *const c_void)
+    }
+
+    fn release_once(self) {
+        assert!(self.pinned_count.deref().get() > 0)
+        let pinned_count = self.pinned_count.deref().get();
+        self.pinned_count.deref().set(pinned_count - 1);
+        if self.pinned_count.deref().get() == 0 {
+            let global = self.global.root();
+            unsafe {
+                JS_RemoveObjectRoot(global.root_ref().get_cx(), self.reflector().rootable());
+            }
+            global.root_ref().untrack_network_owner(FileReaderOwner(JS::from_rooted(self)));
+        }
+    }
+
+    // http://dev.w3.org/2006/webapi/FileAPI/#dfn-readAsArrayBuffer and the
+    // equivalent steps of the other read methods
+    fn read(self, blob: JSRef<Blob>, kind: ReadKind) -> ErrorResult {
+        // Step 1
+        if self.ready_state.deref().get() == Loading {
+            return Err(InvalidState);
+        }
+
+        // Step 3
+        self.ready_state.deref().set(Loading);
+        self.set_result(NullValue());
+
+        // Step 4, the read itself happens in parallel; the reader stays
+        // alive until the last message about it has been handled.
+        let addr = unsafe { self.to_trusted() };
+        let gen_id = self.generation_id.deref().get();
+        let bytes = blob.bytes();
+        let global = self.global.root();
+        let script_chan = global.root_ref().script_chan().clone();
+        spawn_named("FileReader:read", proc() {
+            let total = bytes.len() as u64;
+            script_chan.queue_task(NetworkingTaskSource,
+                                   FileReaderMsg(addr, LoadStartMsg(gen_id, total)));
+            script_chan.queue_task(NetworkingTaskSource,
+                                   FileReaderMsg(addr, LoadingMsg(gen_id, total, total)));
+            script_chan.queue_task(NetworkingTaskSource,
+                                   FileReaderMsg(addr, DoneMsg(gen_id, kind, bytes)));
+        });
+        Ok(())
+    }
+
+    fn process_progress(self, progress: FileReaderProgress) {
+        match progress {
+            // Ignore whatever is left of a read that has been terminated
+            LoadStartMsg(gen_id, _) | LoadingMsg(gen_id, _, _)
+                    if gen_id != self.generation_id.deref().get() => {},
+            LoadStartMsg(_, total) => {
+                self.dispatch_progress_event("loadstart".to_string(), 0, total);
+            },
+            LoadingMsg(_, loaded, total) => {
+                self.dispatch_progress_event("progress".to_string(), loaded, total);
+            },
+            DoneMsg(gen_id, kind, bytes) => {
+                if gen_id == self.generation_id.deref().get() {
+                    let global = self.global.root();
+                    let cx = global.root_ref().get_cx();
+                    let total = bytes.len() as u64;
+                    self.ready_state.deref().set(Done);
+                    self.set_result(result_of_read(cx, kind, bytes));
+                    self.dispatch_progress_event("load".to_string(), total, total);
+                    // A load event handler may have started another read
+                    if self.ready_state.deref().get() != Loading {
+                        self.dispatch_progress_event("loadend".to_string(), total, total);
+                    }
+                }
+                self.release_once();
+            },
+        }
+    }
+
+    fn terminate_ongoing_read(self) {
+        let GenerationId(prev_id) = self.generation_id.deref().get();
+        self.generation_id.deref().set(GenerationId(prev_id + 1));
+    }
+
+    fn dispatch_progress_event(self, type_: DOMString, loaded: u64, total: u64) {
+        let global = self.global.root();
+        let progressevent = ProgressEvent::new(&global.root_ref(),
+                                               type_, false, false,
+                                               true, loaded, total).root();
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        let event: JSRef<Event> = EventCast::from_ref(*progressevent);
+        target.dispatch_event_with_target(None, event).ok();
+    }
+
+    fn set_result(self, value: JSVal) {
+        self.result.deref().set(Traceable::new(value));
+    }
+}
+
+/// Returns the value of `result` once a read of `bytes` is done.
+fn result_of_read(cx: *mut JSContext, kind: ReadKind, bytes: Vec<u8>) -> JSVal {
+    match kind {
+        TextRead(label, type_) => {
+            // http://dev.w3.org/2006/webapi/FileAPI/#encoding-determination
+            let fallback = label.and_then(|label| encoding_from_whatwg_label(label.as_slice()))
+                                .or_else(|| charset_of_type(type_.as_slice()))
+                                .unwrap_or(UTF_8 as EncodingRef);
+            decode(bytes.as_slice(), fallback).to_jsval(cx)
+        },
+        DataURLRead(type_) => {
+            // http://dev.w3.org/2006/webapi/FileAPI/#dfn-readAsDataURL
+            let type_ = if type_.is_empty() {
+                "application/octet-stream".to_string()
+            } else {
+                type_
+            };
+            format!("data:{};base64,{}", type_, bytes.as_slice().to_base64(STANDARD)).to_jsval(cx)
+        },
+        ArrayBufferRead => new_array_buffer(cx, bytes.as_slice()),
+    }
+}
+
+/// Returns the encoding named by the charset parameter of the MIME type
+/// `type_`, if any.
+fn charset_of_type(type_: &str) -> Option<EncodingRef> {
+    for parameter in type_.split(';').skip(1) {
+        match parameter.find('=') {
+            Some(index) if parameter.slice_to(index).trim().eq_ignore_ascii_case("charset") => {
+                return encoding_from_whatwg_label(parameter.slice_from(index + 1).trim());
+            },
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Decodes `bytes` with the encoding its byte order mark names, or
+/// `fallback` if it has none.
+/// http://encoding.spec.whatwg.org/#decode
+fn decode(bytes: &[u8], fallback: EncodingRef) -> DOMString {
+    let (encoding, bytes) = if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        (UTF_8 as EncodingRef, bytes.slice_from(3))
+    } else if bytes.starts_with(&[0xFE, 0xFF]) {
+        (UTF_16BE as EncodingRef, bytes.slice_from(2))
+    } else if bytes.starts_with(&[0xFF, 0xFE]) {
+        (UTF_16LE as EncodingRef, bytes.slice_from(2))
+    } else {
+        (fallback, bytes)
+    };
+    // Decoding with DecodeReplace never fails.
+    encoding.decode(bytes, DecodeReplace).unwrap()
+}
+
+impl<'a> FileReaderMethods for JSRef<'a, FileReader> {
+    // http://dev.w3.org/2006/webapi/FileAPI/#dfn-readAsArrayBuffer
+    fn ReadAsArrayBuffer(self, blob: JSRef<Blob>) -> ErrorResult {
+        self.read(blob, ArrayBufferRead)
+    }
+
+    // http://dev.w3.org/2006/webapi/FileAPI/#dfn-readAsText
+    fn ReadAsText(self, blob: JSRef<Blob>, label: Option<DOMString>) -> ErrorResult {
+        self.read(blob, TextRead(label, blob.type_string()))
+    }
+
+    // http://dev.w3.org/2006/webapi/FileAPI/#dfn-readAsDataURL
+    fn ReadAsDataURL(self, blob: JSRef<Blob>) -> ErrorResult {
+        self.read(blob, DataURLRead(blob.type_string()))
+    }
+
+    // http://dev.w3.org/2006/webapi/FileAPI/#dfn-abort
+    fn Abort(self) {
+        // Step 1
+        if self.ready_state.deref().get() != Loading {
+            self.set_result(NullValue());
+            return;
+        }
+
+        // Steps 2-3
+        self.ready_state.deref().set(Done);
+        self.set_result(NullValue());
+
+        // Step 4
+        self.terminate_ongoing_read();
+
+        // Steps 5-6
+        self.dispatch_progress_event("abort".to_string(), 0, 0);
+        if self.ready_state.deref().get() != Loading {
+            self.dispatch_progress_event("loadend".to_string(), 0, 0);
+        }
+    }
+
+    fn ReadyState(self) -> u16 {
+        self.ready_state.deref().get() as u16
+    }
+
+    fn Result(self, _cx: *mut JSContext) -> JSVal {
+        *self.result.deref().get()
+    }
+
+    // Reading a blob from memory can't fail, so there is never an error.
+    fn GetError(self) -> Option<Temporary<DOMException>> {
+        None
+    }
+
+    event_handler!(loadstart, GetOnloadstart, SetOnloadstart)
+    event_handler!(progress, GetOnprogress, SetOnprogress)
+    event_handler!(load, GetOnload, SetOnload)
+    event_handler!(abort, GetOnabort, SetOnabort)
+    event_handler!(error, GetOnerror, SetOnerror)
+    event_handler!(loadend, GetOnloadend, SetOnloadend)
+}
+
+impl Reflectable for FileReader {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        self.eventtarget.reflector()
+    }
+}
+
+impl FileReaderDerived for EventTarget {
+    fn is_filereader(&self) -> bool {
+        match self.type_id {
+            FileReaderTypeId => true,
+            _ => false
+        }
+    }
+}
@@ -4,19 +4,20 @@
 
 use dom::bindings::codegen::Bindings::FormDataBinding;
 use dom::bindings::codegen::Bindings::FormDataBinding::FormDataMethods;
-use dom::bindings::codegen::InheritTypes::FileCast;
+use dom::bindings::codegen::InheritTypes::{BlobCast, FileCast};
 use dom::bindings::codegen::UnionTypes::FileOrString::{FileOrString, eFile, eString};
 use dom::bindings::error::{Fallible};
 use dom::bindings::global::{GlobalRef, GlobalField};
 use dom::bindings::js::{JS, JSRef, Temporary};
 use dom::bindings::trace::Traceable;
 use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
-use dom::blob::Blob;
-use dom::file::File;
-use dom::htmlformelement::HTMLFormElement;
+use dom::blob::{Blob, BlobHelpers};
+use dom::file::{File, now};
+use dom::htmlformelement::{HTMLFormElement, HTMLFormElementHelpers, FormEntry};
+use dom::htmlformelement::{StringEntry, EmptyFileEntry};
 use servo_util::str::DOMString;
+use std::ascii::StrAsciiExt;
 use std::cell::RefCell;
-use std::collections::hashmap::HashMap;
 
 #[deriving(Clone)]
 #[jstraceable]
@@ -26,76 +27,127 @@ pub enum FormDatum {
     FileData(JS<File>)
 }
 
+impl FormDatum {
+    fn to_entry_value(&self) -> FileOrString {
+        match *self {
+            StringData(ref s) => eString(s.clone()),
+            FileData(ref f) => eFile(f.clone()),
+        }
+    }
+}
+
 #[jstraceable]
 #[must_root]
 pub struct FormData {
-    data: Traceable<RefCell<HashMap<DOMString, Vec<FormDatum>>>>,
+    /// The entries, in the order they were added.
+    data: Traceable<RefCell<Vec<(DOMString, FormDatum)>>>,
     reflector_: Reflector,
     global: GlobalField,
-    form: Option<JS<HTMLFormElement>>
 }
 
 impl FormData {
-    fn new_inherited(form: Option<JSRef<HTMLFormElement>>, global: &GlobalRef) -> FormData {
+    fn new_inherited(global: &GlobalRef) -> FormData {
         FormData {
-            data: Traceable::new(RefCell::new(HashMap::new())),
+            data: Traceable::new(RefCell::new(vec!())),
             reflector_: Reflector::new(),
             global: GlobalField::from_rooted(global),
-            form: form.map(|f| JS::from_rooted(f)),
         }
     }
 
-    pub fn new(form: Option<JSRef<HTMLFormElement>>, global: &GlobalRef) -> Temporary<FormData> {
-        reflect_dom_object(box FormData::new_inherited(form, global),
+    pub fn new(global: &GlobalRef) -> Temporary<FormData> {
+        reflect_dom_object(box FormData::new_inherited(global),
                            global, FormDataBinding::Wrap)
     }
 
+    /// Returns a new FormData with the given string entries.
+    pub fn new_with_entries(global: &GlobalRef, entries: Vec<(DOMString, DOMString)>)
+                            -> Temporary<FormData> {
+        let form_data = FormData::new(global).root();
+        *form_data.data.deref().borrow_mut() = entries.into_iter().map(|(name, value)| {
+            (name, StringData(value))
+        }).collect();
+        Temporary::from_rooted(*form_data)
+    }
+
+    // http://xhr.spec.whatwg.org/#dom-formdata
+    #[allow(unrooted_must_root)]
     pub fn Constructor(global: &GlobalRef, form: Option<JSRef<HTMLFormElement>>) -> Fallible<Temporary<FormData>> {
-        Ok(FormData::new(form, global))
+        let form_data = FormData::new(global).root();
+        match form {
+            Some(form) => {
+                let entries = form.entry_list().into_iter().map(|FormEntry { name, value }| {
+                    let datum = match value {
+                        StringEntry(value) => StringData(value),
+                        EmptyFileEntry => {
+                            let file = File::new(global, vec!(), "application/octet-stream".to_string(),
+                                                 "".to_string(), now());
+                            FileData(JS::from_rooted(file))
+                        },
+                    };
+                    (name, datum)
+                }).collect();
+                *form_data.data.deref().borrow_mut() = entries;
+            },
+            None => {}
+        }
+        Ok(Temporary::from_rooted(*form_data))
     }
 }
 
 impl<'a> FormDataMethods for JSRef<'a, FormData> {
+    // http://xhr.spec.whatwg.org/#dom-formdata-append
     #[allow(unrooted_must_root)]
     fn Append(self, name: DOMString, value: JSRef<Blob>, filename: Option<DOMString>) {
         let file = FileData(JS::from_rooted(self.get_file_from_blob(value, filename)));
-        self.data.deref().borrow_mut().insert_or_update_with(name.clone(), vec!(file.clone()),
-                                        |_k, v| {v.push(file.clone());});
+        self.data.deref().borrow_mut().push((name, file));
     }
 
+    // http://xhr.spec.whatwg.org/#dom-formdata-append
     fn Append_(self, name: DOMString, value: DOMString) {
-        self.data.deref().borrow_mut().insert_or_update_with(name, vec!(StringData(value.clone())),
-                                        |_k, v| {v.push(StringData(value.clone()));});
+        self.data.deref().borrow_mut().push((name, StringData(value)));
     }
 
+    // http://xhr.spec.whatwg.org/#dom-formdata-delete
     fn Delete(self, name: DOMString) {
-        self.data.deref().borrow_mut().remove(&name);
+        self.data.deref().borrow_mut().retain(|&(ref entry_name, _)| *entry_name != name);
     }
 
+    // http://xhr.spec.whatwg.org/#dom-formdata-get
     fn Get(self, name: DOMString) -> Option<FileOrString> {
-        if self.data.deref().borrow().contains_key_equiv(&name) {
-            match (*self.data.deref().borrow())[name][0].clone() {
-                StringData(ref s) => Some(eString(s.clone())),
-                FileData(ref f) => {
-                    Some(eFile(f.clone()))
-                }
-            }
-        } else {
-            None
-        }
+        self.data.deref().borrow().iter()
+            .find(|&&(ref entry_name, _)| *entry_name == name)
+            .map(|&(_, ref datum)| datum.to_entry_value())
+    }
+
+    // http://xhr.spec.whatwg.org/#dom-formdata-getall
+    fn GetAll(self, name: DOMString) -> Vec<FileOrString> {
+        self.data.deref().borrow().iter()
+            .filter(|&&(ref entry_name, _)| *entry_name == name)
+            .map(|&(_, ref datum)| datum.to_entry_value())
+            .collect()
     }
 
+    // http://xhr.spec.whatwg.org/#dom-formdata-has
     fn Has(self, name: DOMString) -> bool {
-        self.data.deref().borrow().contains_key_equiv(&name)
+        self.data.deref().borrow().iter().any(|&(ref entry_name, _)| *entry_name == name)
     }
+
+    // http://xhr.spec.whatwg.org/#dom-formdata-set
     #[allow(unrooted_must_root)]
     fn Set(self, name: DOMString, value: JSRef<Blob>, filename: Option<DOMString>) {
         let file = FileData(JS::from_rooted(self.get_file_from_blob(value, filename)));
-        self.data.deref().borrow_mut().insert(name, vec!(file));
+        self.set(name, file);
     }
 
+    // http://xhr.spec.whatwg.org/#dom-formdata-set
     fn Set_(self, name: DOMString, value: DOMString) {
-        self.data.deref().borrow_mut().insert(name, vec!(StringData(value)));
+        self.set(name, StringData(value));
+    }
+
+    fn Pairs(self) -> Vec<(DOMString, FileOrString)> {
+        self.data.deref().borrow().iter()
+            .map(|&(ref name, ref datum)| (name.clone(), datum.to_entry_value()))
+            .collect()
     }
 }
 
@@ -105,15 +157,192 @@ impl Reflectable for FormData {
     }
 }
 
-trait PrivateFormDataHelpers{
-  fn get_file_from_blob(&self, value: JSRef<Blob>, filename: Option<DOMString>) -> Temporary<File>;
+pub trait FormDataHelpers {
+    fn encode_multipart(self, boundary: &str) -> Vec<u8>;
+}
+
+impl<'a> FormDataHelpers for JSRef<'a, FormData> {
+    /// Returns the multipart/form-data encoding of the entries, with the
+    /// given boundary.
+    /// http://www.whatwg.org/html/#multipart/form-data-encoding-algorithm
+    fn encode_multipart(self, boundary: &str) -> Vec<u8> {
+        let mut bytes = vec!();
+        for &(ref name, ref datum) in self.data.deref().borrow().iter() {
+            bytes.push_all(format!("--{}\r\n", boundary).as_bytes());
+            let disposition = format!("Content-Disposition: form-data; name=\"{}\"",
+                                      escape_multipart_name(name.as_slice()));
+            bytes.push_all(disposition.as_bytes());
+            match *datum {
+                StringData(ref value) => {
+                    bytes.push_all(b"\r\n\r\n");
+                    bytes.push_all(value.as_bytes());
+                },
+                FileData(ref file) => {
+                    let file = file.root();
+                    let blob: JSRef<Blob> = BlobCast::from_ref(*file);
+                    let type_ = blob.type_string();
+                    let type_ = if type_.is_empty() {
+                        "application/octet-stream".to_string()
+                    } else {
+                        type_
+                    };
+                    let headers = format!("; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                                          escape_multipart_name(file.name.as_slice()), type_);
+                    bytes.push_all(headers.as_bytes());
+                    bytes.push_all(blob.bytes().as_slice());
+                },
+            }
+            bytes.push_all(b"\r\n");
+        }
+        bytes.push_all(format!("--{}--\r\n", boundary).as_bytes());
+        bytes
+    }
+}
+
+/// Escapes the quotes and line breaks of a name or filename in a
+/// Content-Disposition header.
+fn escape_multipart_name(name: &str) -> String {
+    name.replace("\"", "%22").replace("\r", "%0D").replace("\n", "%0A")
+}
+
+/// Undoes `escape_multipart_name`.
+fn unescape_multipart_name(name: &str) -> String {
+    name.replace("%22", "\"").replace("%0D", "\r").replace("%0A", "\n")
 }
 
-impl PrivateFormDataHelpers for FormData {
-    fn get_file_from_blob(&self, value: JSRef<Blob>, filename: Option<DOMString>) -> Temporary<File> {
+/// Returns the index of the first occurrence of `needle` in `haystack`.
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<uint> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+/// Returns the value of the parameter called `name` in a header value made
+/// of `;`-separated parameters, without its quotes.
+pub fn header_parameter(value: &str, name: &str) -> Option<String> {
+    value.split(';').skip(1).filter_map(|param| {
+        let param = param.trim();
+        match param.find('=') {
+            Some(index) if param.slice_to(index).trim().eq_ignore_ascii_case(name) => {
+                let value = param.slice_from(index + 1).trim().trim_chars('"');
+                Some(unescape_multipart_name(value))
+            },
+            _ => None,
+        }
+    }).next()
+}
+
+/// Returns the entries of `bytes`, in the multipart/form-data format with
+/// the given boundary, or None if they aren't well-formed. A part with a
+/// filename becomes a file entry.
+/// http://fetch.spec.whatwg.org/#concept-body-package-data
+#[allow(unrooted_must_root)]
+pub fn parse_multipart(global: &GlobalRef, bytes: &[u8], boundary: &str)
+                       -> Option<Temporary<FormData>> {
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let separator = format!("\r\n--{}", boundary).into_bytes();
+
+    // Anything before the first delimiter is a preamble to ignore.
+    let mut rest = match find_bytes(bytes, delimiter.as_slice()) {
+        Some(index) => bytes.slice_from(index + delimiter.len()),
+        None => return None,
+    };
+    let mut entries = vec!();
+    loop {
+        if rest.starts_with(b"--") {
+            break;
+        }
+        if !rest.starts_with(b"\r\n") {
+            return None;
+        }
+        rest = rest.slice_from(2);
+
+        let headers_end = match find_bytes(rest, b"\r\n\r\n") {
+            Some(index) => index,
+            None => return None,
+        };
+        let headers = String::from_utf8_lossy(rest.slice_to(headers_end)).into_string();
+        rest = rest.slice_from(headers_end + 4);
+        let content_end = match find_bytes(rest, separator.as_slice()) {
+            Some(index) => index,
+            None => return None,
+        };
+        let content = rest.slice_to(content_end);
+        rest = rest.slice_from(content_end + separator.len());
+
+        let mut name = None;
+        let mut filename = None;
+        let mut content_type = None;
+        for header in headers.as_slice().split_str("\r\n") {
+            let index = match header.find(':') {
+                Some(index) => index,
+                None => return None,
+            };
+            let value = header.slice_from(index + 1).trim();
+            match header.slice_to(index).trim().to_ascii_lower().as_slice() {
+                "content-disposition" => {
+                    name = header_parameter(value, "name");
+                    filename = header_parameter(value, "filename");
+                },
+                "content-type" => content_type = Some(value.to_string()),
+                _ => {},
+            }
+        }
+        let name = match name {
+            Some(name) => name,
+            None => return None,
+        };
+        let datum = match filename {
+            Some(filename) => {
+                let type_ = content_type.unwrap_or("text/plain".to_string());
+                let file = File::new(global, content.to_vec(), type_, filename, now());
+                FileData(JS::from_rooted(file))
+            },
+            None => StringData(String::from_utf8_lossy(content).into_string()),
+        };
+        entries.push((name, datum));
+    }
+
+    let form_data = FormData::new(global).root();
+    *form_data.data.deref().borrow_mut() = entries;
+    Some(Temporary::from_rooted(*form_data))
+}
+
+trait PrivateFormDataHelpers {
+    fn get_file_from_blob(self, value: JSRef<Blob>, filename: Option<DOMString>) -> Temporary<File>;
+    fn set(self, name: DOMString, datum: FormDatum);
+}
+
+impl<'a> PrivateFormDataHelpers for JSRef<'a, FormData> {
+    fn get_file_from_blob(self, value: JSRef<Blob>, filename: Option<DOMString>) -> Temporary<File> {
         let global = self.global.root();
         let f: Option<JSRef<File>> = FileCast::to_ref(value);
-        let name = filename.unwrap_or(f.map(|inner| inner.name.clone()).unwrap_or("blob".to_string()));
-        File::new(&global.root_ref(), value, name)
+        match (f, filename) {
+            // A file without a new name is added as it is
+            (Some(file), None) => Temporary::from_rooted(file),
+            (f, filename) => {
+                let name = filename.unwrap_or(f.map(|inner| inner.name.clone()).unwrap_or("blob".to_string()));
+                File::new(&global.root_ref(), value.bytes(), value.type_string(), name, now())
+            },
+        }
+    }
+
+    /// Replaces the first entry named `name` with `datum` and removes the
+    /// others, or appends it if there is none.
+    #[allow(unrooted_must_root)]
+    fn set(self, name: DOMString, datum: FormDatum) {
+        let mut data = self.data.deref().borrow_mut();
+        match data.iter().position(|&(ref entry_name, _)| *entry_name == name) {
+            Some(index) => {
+                *data.get_mut(index) = (name.clone(), datum);
+                let mut seen = 0u;
+                data.retain(|&(ref entry_name, _)| {
+                    if *entry_name != name {
+                        return true;
+                    }
+                    seen += 1;
+                    seen == 1
+                });
+            },
+            None => data.push((name, datum)),
+        }
     }
 }
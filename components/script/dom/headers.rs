@@ -0,0 +1,294 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::HeadersBinding;
+use dom::bindings::codegen::Bindings::HeadersBinding::HeadersMethods;
+use dom::bindings::error::{ErrorResult, Fallible, Type};
+use dom::bindings::global::GlobalRef;
+use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::str::ByteString;
+use dom::bindings::trace::Traceable;
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+
+use std::cell::{Cell, RefCell};
+
+/// The [guard](http://fetch.spec.whatwg.org/#concept-headers-guard) of a
+/// `Headers` object, which restricts the changes scripts may make to it.
+#[deriving(PartialEq, Show)]
+#[jstraceable]
+pub enum Guard {
+    ImmutableGuard,
+    RequestGuard,
+    RequestNoCorsGuard,
+    ResponseGuard,
+    NoGuard,
+}
+
+#[jstraceable]
+#[must_root]
+pub struct Headers {
+    reflector_: Reflector,
+    guard: Traceable<Cell<Guard>>,
+    /// The header list, in insertion order, with the names as given.
+    list: Traceable<RefCell<Vec<(ByteString, ByteString)>>>,
+}
+
+impl Headers {
+    fn new_inherited(guard: Guard) -> Headers {
+        Headers {
+            reflector_: Reflector::new(),
+            guard: Traceable::new(Cell::new(guard)),
+            list: Traceable::new(RefCell::new(vec!())),
+        }
+    }
+
+    pub fn new(global: &GlobalRef, guard: Guard) -> Temporary<Headers> {
+        reflect_dom_object(box Headers::new_inherited(guard), global, HeadersBinding::Wrap)
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-headers
+    pub fn Constructor(global: &GlobalRef, init: Option<JSRef<Headers>>) -> Fallible<Temporary<Headers>> {
+        let headers = Headers::new(global, NoGuard).root();
+        match init {
+            Some(init) => try!(headers.fill(init)),
+            None => {}
+        }
+        Ok(Temporary::from_rooted(*headers))
+    }
+}
+
+/// Returns whether the header `name` may not be set by scripts on requests.
+/// http://fetch.spec.whatwg.org/#forbidden-header-name
+pub fn is_forbidden_header_name(name: &ByteString) -> bool {
+    let name = name.to_lower();
+    let name = match name.as_str() {
+        Some(name) => name,
+        None => return false,
+    };
+    match name {
+        "accept-charset" | "accept-encoding" |
+        "access-control-request-headers" |
+        "access-control-request-method" |
+        "connection" | "content-length" |
+        "cookie" | "cookie2" | "date" | "dnt" |
+        "expect" | "host" | "keep-alive" | "origin" |
+        "referer" | "te" | "trailer" | "transfer-encoding" |
+        "upgrade" | "user-agent" | "via" => true,
+        _ => name.starts_with("proxy-") || name.starts_with("sec-"),
+    }
+}
+
+/// Returns whether the header `name` may not be read by scripts on responses.
+/// http://fetch.spec.whatwg.org/#forbidden-response-header-name
+fn is_forbidden_response_header_name(name: &ByteString) -> bool {
+    match name.to_lower().as_str() {
+        Some("set-cookie") | Some("set-cookie2") => true,
+        _ => false,
+    }
+}
+
+/// Returns whether a header may be set on a request made without CORS.
+/// http://fetch.spec.whatwg.org/#simple-header
+fn is_simple_header(name: &ByteString, value: &ByteString) -> bool {
+    match name.to_lower().as_str() {
+        Some("accept") | Some("accept-language") | Some("content-language") => true,
+        Some("content-type") => {
+            let value = value.to_lower();
+            let essence = value.as_slice().splitn(1, |&b| b == b';').next().unwrap();
+            let essence = ByteString::new(trim_whitespace(essence).to_vec());
+            match essence.as_str() {
+                Some("application/x-www-form-urlencoded") |
+                Some("multipart/form-data") |
+                Some("text/plain") => true,
+                _ => false,
+            }
+        },
+        _ => false,
+    }
+}
+
+/// Removes the leading and trailing HTTP whitespace of `value`.
+fn trim_whitespace<'a>(value: &'a [u8]) -> &'a [u8] {
+    let is_whitespace = |b: &u8| *b == b' ' || *b == b'\t' || *b == b'\r' || *b == b'\n';
+    let start = value.iter().position(|b| !is_whitespace(b));
+    let end = value.iter().rposition(|b| !is_whitespace(b));
+    match (start, end) {
+        (Some(start), Some(end)) => value.slice(start, end + 1),
+        _ => value.slice(0, 0),
+    }
+}
+
+/// Returns the [normalized](http://fetch.spec.whatwg.org/#concept-header-value-normalize)
+/// `value`.
+fn normalize_value(value: ByteString) -> ByteString {
+    ByteString::new(trim_whitespace(value.as_slice()).to_vec())
+}
+
+pub trait HeadersHelpers {
+    fn guard(self) -> Guard;
+    fn set_guard(self, guard: Guard);
+    fn fill(self, init: JSRef<Headers>) -> ErrorResult;
+    fn copy_from(self, other: JSRef<Headers>);
+    fn append(self, name: ByteString, value: ByteString);
+    fn get(self, name: &ByteString) -> Option<ByteString>;
+    fn list(self) -> Vec<(ByteString, ByteString)>;
+    fn check_name(self, name: &ByteString) -> ErrorResult;
+}
+
+impl<'a> HeadersHelpers for JSRef<'a, Headers> {
+    fn guard(self) -> Guard {
+        self.guard.deref().get()
+    }
+
+    fn set_guard(self, guard: Guard) {
+        self.guard.deref().set(guard);
+    }
+
+    // http://fetch.spec.whatwg.org/#concept-headers-fill
+    fn fill(self, init: JSRef<Headers>) -> ErrorResult {
+        for (name, value) in init.list().into_iter() {
+            try!(self.Append(name, value));
+        }
+        Ok(())
+    }
+
+    /// Replaces the header list with a copy of the one of `other`, bypassing
+    /// the guard.
+    fn copy_from(self, other: JSRef<Headers>) {
+        *self.list.deref().borrow_mut() = other.list();
+    }
+
+    /// Appends a header to the list, bypassing the guard.
+    fn append(self, name: ByteString, value: ByteString) {
+        self.list.deref().borrow_mut().push((name, value));
+    }
+
+    /// Returns the values of the header `name` combined, or None if there
+    /// is no such header.
+    /// http://fetch.spec.whatwg.org/#concept-header-list-combine
+    fn get(self, name: &ByteString) -> Option<ByteString> {
+        let list = self.list.deref().borrow();
+        let mut combined = vec!();
+        let mut found = false;
+        for &(_, ref value) in list.iter().filter(|&&(ref n, _)| n.eq_ignore_case(name)) {
+            if found {
+                combined.push_all(b", ");
+            }
+            combined.push_all(value.as_slice());
+            found = true;
+        }
+        if found { Some(ByteString::new(combined)) } else { None }
+    }
+
+    fn list(self) -> Vec<(ByteString, ByteString)> {
+        self.list.deref().borrow().clone()
+    }
+
+    /// The checks common to all the methods that change headers.
+    fn check_name(self, name: &ByteString) -> ErrorResult {
+        if !name.is_token() {
+            return Err(Type("Invalid header name".to_string()));
+        }
+        match self.guard() {
+            ImmutableGuard => Err(Type("Headers are immutable".to_string())),
+            RequestGuard if is_forbidden_header_name(name) =>
+                Err(Type("Forbidden request header".to_string())),
+            ResponseGuard if is_forbidden_response_header_name(name) =>
+                Err(Type("Forbidden response header".to_string())),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<'a> HeadersMethods for JSRef<'a, Headers> {
+    // http://fetch.spec.whatwg.org/#dom-headers-append
+    fn Append(self, name: ByteString, value: ByteString) -> ErrorResult {
+        let value = normalize_value(value);
+        if !value.is_field_value() {
+            return Err(Type("Invalid header value".to_string()));
+        }
+        try!(self.check_name(&name));
+        if self.guard() == RequestNoCorsGuard && !is_simple_header(&name, &value) {
+            return Err(Type("Forbidden header for a no-cors request".to_string()));
+        }
+        self.append(name, value);
+        Ok(())
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-headers-delete
+    fn Delete(self, name: ByteString) -> ErrorResult {
+        try!(self.check_name(&name));
+        let value = ByteString::new(vec!());
+        if self.guard() == RequestNoCorsGuard && !is_simple_header(&name, &value) {
+            return Err(Type("Forbidden header for a no-cors request".to_string()));
+        }
+        self.list.deref().borrow_mut().retain(|&(ref n, _)| !n.eq_ignore_case(&name));
+        Ok(())
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-headers-get
+    fn Get(self, name: ByteString) -> Fallible<Option<ByteString>> {
+        if !name.is_token() {
+            return Err(Type("Invalid header name".to_string()));
+        }
+        Ok(self.get(&name))
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-headers-has
+    fn Has(self, name: ByteString) -> Fallible<bool> {
+        if !name.is_token() {
+            return Err(Type("Invalid header name".to_string()));
+        }
+        Ok(self.list.deref().borrow().iter().any(|&(ref n, _)| n.eq_ignore_case(&name)))
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-headers-set
+    fn Set(self, name: ByteString, value: ByteString) -> ErrorResult {
+        let value = normalize_value(value);
+        if !value.is_field_value() {
+            return Err(Type("Invalid header value".to_string()));
+        }
+        try!(self.check_name(&name));
+        if self.guard() == RequestNoCorsGuard && !is_simple_header(&name, &value) {
+            return Err(Type("Forbidden header for a no-cors request".to_string()));
+        }
+
+        // The first header of that name takes the new value, and the others
+        // go away.
+        let mut list = self.list.deref().borrow_mut();
+        match list.iter().position(|&(ref n, _)| n.eq_ignore_case(&name)) {
+            Some(index) => {
+                *list.get_mut(index) = (name.clone(), value);
+                let mut i = 0;
+                list.retain(|&(ref n, _)| {
+                    i += 1;
+                    i - 1 <= index || !n.eq_ignore_case(&name)
+                });
+            },
+            None => list.push((name, value)),
+        }
+        Ok(())
+    }
+
+    /// The pairs iterated over are the lowercased names, sorted, with the
+    /// combined values of each.
+    /// http://fetch.spec.whatwg.org/#concept-header-list-sort-and-combine
+    fn Pairs(self) -> Vec<(ByteString, ByteString)> {
+        let mut names: Vec<ByteString> = self.list.deref().borrow().iter()
+            .map(|&(ref name, _)| name.to_lower())
+            .collect();
+        names.sort_by(|a, b| a.as_slice().cmp(&b.as_slice()));
+        names.dedup();
+        names.into_iter().map(|name| {
+            let value = self.get(&name).unwrap();
+            (name, value)
+        }).collect()
+    }
+}
+
+impl Reflectable for Headers {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        &self.reflector_
+    }
+}
@@ -12,7 +12,7 @@ use dom::bindings::codegen::InheritTypes::{ElementCast, HTMLElementCast, NodeCas
 use dom::bindings::js::{JSRef, Temporary, OptionalRootable};
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::{Document, DocumentHelpers};
-use dom::element::{Element, AttributeHandlers, HTMLAnchorElementTypeId};
+use dom::element::{Element, AttributeHandlers, ElementHelpers, HTMLAnchorElementTypeId};
 use dom::event::Event;
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
@@ -63,7 +63,8 @@ impl<'a> PrivateHTMLAnchorElementHelpers for JSRef<'a, HTMLAnchorElement> {
                     debug!("clicked on link to {:s}", value);
                     let node: JSRef<Node> = NodeCast::from_ref(self);
                     let doc = node.owner_doc().root();
-                    doc.load_anchor_href(value);
+                    doc.load_anchor_href(value, element.get_string_attribute("ping"),
+                                         element.get_referrer_policy());
                 }
                 None => ()
             }
@@ -104,4 +105,25 @@ impl<'a> HTMLAnchorElementMethods for JSRef<'a, HTMLAnchorElement> {
         let node: JSRef<Node> = NodeCast::from_ref(self);
         node.SetTextContent(Some(value))
     }
+
+    // http://www.whatwg.org/html/#dom-a-ping
+    make_getter!(Ping)
+
+    // http://www.whatwg.org/html/#dom-a-ping
+    fn SetPing(self, ping: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("ping", ping)
+    }
+
+    // http://w3c.github.io/webappsec/specs/referrer-policy/#referrer-policy-delivery-referrer-attribute
+    fn ReferrerPolicy(self) -> DOMString {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.get_referrer_policy().map_or("".to_string(), |policy| policy.to_token().to_string())
+    }
+
+    // http://w3c.github.io/webappsec/specs/referrer-policy/#referrer-policy-delivery-referrer-attribute
+    fn SetReferrerPolicy(self, referrer_policy: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("referrerpolicy", referrer_policy)
+    }
 }
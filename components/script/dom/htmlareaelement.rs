@@ -2,17 +2,26 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
 use dom::bindings::codegen::Bindings::HTMLAreaElementBinding;
-use dom::bindings::codegen::InheritTypes::HTMLAreaElementDerived;
+use dom::bindings::codegen::Bindings::HTMLAreaElementBinding::HTMLAreaElementMethods;
+use dom::bindings::codegen::InheritTypes::{ElementCast, HTMLAreaElementDerived, HTMLElementCast};
+use dom::bindings::error::ErrorResult;
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector};
-use dom::document::Document;
-use dom::element::HTMLAreaElementTypeId;
+use dom::document::{Document, DocumentHelpers};
+use dom::element::{AttributeHandlers, Element, ElementHelpers, HTMLAreaElementTypeId};
+use dom::event::Event;
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
-use dom::node::{Node, NodeHelpers, ElementNodeTypeId};
+use dom::node::{Node, ElementNodeTypeId, document_from_node};
+use dom::urlhelper::UrlHelper;
+use dom::virtualmethods::VirtualMethods;
 
-use servo_util::str::DOMString;
+use servo_util::str::{DOMString, parse_floating_point_number_list};
+
+use std::ascii::StrAsciiExt;
+use url::UrlParser;
 
 #[jstraceable]
 #[must_root]
@@ -40,6 +49,228 @@ impl HTMLAreaElement {
     }
 }
 
+/// The region of an image covered by an area element, in CSS pixels
+/// relative to the top left corner of the image.
+/// http://www.whatwg.org/html/#attr-area-shape
+#[deriving(PartialEq, Show)]
+pub enum AreaShape {
+    /// A circle, given by its center and radius.
+    CircleShape(f64, f64, f64),
+    /// The whole image.
+    DefaultShape,
+    /// A polygon, given by its vertices.
+    PolygonShape(Vec<(f64, f64)>),
+    /// A rectangle, given by its left, top, right and bottom edges.
+    RectangleShape(f64, f64, f64, f64),
+}
+
+impl AreaShape {
+    /// Builds the shape described by the `shape` and `coords` attributes, or
+    /// `None` if there aren't enough coordinates for the shape.
+    /// http://www.whatwg.org/html/#image-map-processing-model
+    pub fn parse(shape: &str, coords: &str) -> Option<AreaShape> {
+        let coords = parse_floating_point_number_list(coords);
+        match shape.to_ascii_lower().as_slice() {
+            "circle" | "circ" => {
+                if coords.len() < 3 || coords[2] <= 0. {
+                    return None;
+                }
+                Some(CircleShape(coords[0], coords[1], coords[2]))
+            },
+            "default" => Some(DefaultShape),
+            "poly" | "polygon" => {
+                if coords.len() < 6 {
+                    return None;
+                }
+                // A trailing unpaired coordinate is ignored.
+                let points = range(0, coords.len() / 2).map(|i| {
+                    (coords[2 * i], coords[2 * i + 1])
+                }).collect();
+                Some(PolygonShape(points))
+            },
+            // Missing and invalid shapes are rectangles.
+            _ => {
+                if coords.len() < 4 {
+                    return None;
+                }
+                let (left, right) = if coords[0] <= coords[2] {
+                    (coords[0], coords[2])
+                } else {
+                    (coords[2], coords[0])
+                };
+                let (top, bottom) = if coords[1] <= coords[3] {
+                    (coords[1], coords[3])
+                } else {
+                    (coords[3], coords[1])
+                };
+                Some(RectangleShape(left, top, right, bottom))
+            },
+        }
+    }
+
+    /// Returns whether the point `(x, y)` lies within this shape.
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        match *self {
+            CircleShape(center_x, center_y, radius) => {
+                let (dx, dy) = (x - center_x, y - center_y);
+                dx * dx + dy * dy <= radius * radius
+            },
+            DefaultShape => true,
+            PolygonShape(ref points) => {
+                // Even-odd rule: count the edges crossed by a ray going to
+                // the right of the point.
+                let mut inside = false;
+                let mut previous = points[points.len() - 1];
+                for &(point_x, point_y) in points.iter() {
+                    let (previous_x, previous_y) = previous;
+                    if (point_y > y) != (previous_y > y) &&
+                       x < (previous_x - point_x) * (y - point_y) / (previous_y - point_y) + point_x {
+                        inside = !inside;
+                    }
+                    previous = (point_x, point_y);
+                }
+                inside
+            },
+            RectangleShape(left, top, right, bottom) => {
+                left <= x && x <= right && top <= y && y <= bottom
+            },
+        }
+    }
+}
+
+pub trait HTMLAreaElementHelpers {
+    fn get_shape(self) -> Option<AreaShape>;
+}
+
+impl<'a> HTMLAreaElementHelpers for JSRef<'a, HTMLAreaElement> {
+    fn get_shape(self) -> Option<AreaShape> {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        AreaShape::parse(element.get_string_attribute("shape").as_slice(),
+                         element.get_string_attribute("coords").as_slice())
+    }
+}
+
+trait PrivateHTMLAreaElementHelpers {
+    fn handle_event_impl(self, event: JSRef<Event>);
+}
+
+impl<'a> PrivateHTMLAreaElementHelpers for JSRef<'a, HTMLAreaElement> {
+    fn handle_event_impl(self, event: JSRef<Event>) {
+        if "click" == event.Type().as_slice() && !event.DefaultPrevented() {
+            let element: JSRef<Element> = ElementCast::from_ref(self);
+            if element.has_attribute("href") {
+                let href = element.get_string_attribute("href");
+                debug!("clicked on area linking to {:s}", href);
+                let document = document_from_node(self).root();
+                document.load_anchor_href(href, element.get_string_attribute("ping"),
+                                          element.get_referrer_policy());
+            }
+        }
+    }
+}
+
+impl<'a> HTMLAreaElementMethods for JSRef<'a, HTMLAreaElement> {
+    // http://www.whatwg.org/html/#dom-area-alt
+    make_getter!(Alt)
+
+    // http://www.whatwg.org/html/#dom-area-alt
+    fn SetAlt(self, alt: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("alt", alt)
+    }
+
+    // http://www.whatwg.org/html/#dom-area-coords
+    make_getter!(Coords)
+
+    // http://www.whatwg.org/html/#dom-area-coords
+    fn SetCoords(self, coords: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("coords", coords)
+    }
+
+    // http://www.whatwg.org/html/#dom-area-shape
+    make_getter!(Shape)
+
+    // http://www.whatwg.org/html/#dom-area-shape
+    fn SetShape(self, shape: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("shape", shape)
+    }
+
+    // http://www.whatwg.org/html/#dom-area-target
+    make_getter!(Target)
+
+    // http://www.whatwg.org/html/#dom-area-target
+    fn SetTarget(self, target: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("target", target)
+    }
+
+    // http://www.whatwg.org/html/#dom-area-ping
+    make_getter!(Ping)
+
+    // http://www.whatwg.org/html/#dom-area-ping
+    fn SetPing(self, ping: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("ping", ping)
+    }
+
+    // http://w3c.github.io/webappsec/specs/referrer-policy/#referrer-policy-delivery-referrer-attribute
+    fn ReferrerPolicy(self) -> DOMString {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.get_referrer_policy().map_or("".to_string(), |policy| policy.to_token().to_string())
+    }
+
+    // http://w3c.github.io/webappsec/specs/referrer-policy/#referrer-policy-delivery-referrer-attribute
+    fn SetReferrerPolicy(self, referrer_policy: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("referrerpolicy", referrer_policy)
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-href
+    make_url_getter!(Href)
+
+    // http://url.spec.whatwg.org/#dom-urlutils-href
+    fn SetHref(self, href: DOMString) -> ErrorResult {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_url_attribute("href", href);
+        Ok(())
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-search
+    fn Search(self) -> DOMString {
+        match UrlParser::new().parse(self.Href().as_slice()) {
+            Ok(url) => UrlHelper::Search(&url),
+            Err(_) => "".to_string(),
+        }
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-hash
+    fn Hash(self) -> DOMString {
+        match UrlParser::new().parse(self.Href().as_slice()) {
+            Ok(url) => UrlHelper::Hash(&url),
+            Err(_) => "".to_string(),
+        }
+    }
+}
+
+impl<'a> VirtualMethods for JSRef<'a, HTMLAreaElement> {
+    fn super_type<'a>(&'a self) -> Option<&'a VirtualMethods> {
+        let htmlelement: &JSRef<HTMLElement> = HTMLElementCast::from_borrowed_ref(self);
+        Some(htmlelement as &VirtualMethods)
+    }
+
+    fn handle_event(&self, event: JSRef<Event>) {
+        match self.super_type() {
+            Some(s) => {
+                s.handle_event(event);
+            }
+            None => {}
+        }
+        self.handle_event_impl(event);
+    }
+}
+
 impl Reflectable for HTMLAreaElement {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.htmlelement.reflector()
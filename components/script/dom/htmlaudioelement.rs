@@ -3,14 +3,18 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::bindings::codegen::Bindings::HTMLAudioElementBinding;
-use dom::bindings::codegen::InheritTypes::HTMLAudioElementDerived;
+use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use dom::bindings::codegen::InheritTypes::{ElementCast, HTMLAudioElementDerived, HTMLMediaElementCast};
+use dom::bindings::error::Fallible;
+use dom::bindings::global::GlobalRef;
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
-use dom::element::HTMLAudioElementTypeId;
+use dom::element::{AttributeHandlers, Element, HTMLAudioElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlmediaelement::HTMLMediaElement;
 use dom::node::{Node, ElementNodeTypeId};
+use dom::virtualmethods::VirtualMethods;
 use servo_util::str::DOMString;
 
 #[jstraceable]
@@ -37,6 +41,26 @@ impl HTMLAudioElement {
         let element = HTMLAudioElement::new_inherited(localName, document);
         Node::reflect_node(box element, document, HTMLAudioElementBinding::Wrap)
     }
+
+    // http://www.whatwg.org/html/#dom-audio
+    pub fn Audio(global: &GlobalRef, src: Option<DOMString>) -> Fallible<Temporary<HTMLAudioElement>> {
+        let document = global.as_window().Document().root();
+        let audio = HTMLAudioElement::new("audio".to_string(), *document).root();
+        let element: JSRef<Element> = ElementCast::from_ref(*audio);
+        element.set_string_attribute("preload", "auto".to_string());
+        match src {
+            Some(src) => element.set_string_attribute("src", src),
+            None => (),
+        }
+        Ok(Temporary::from_rooted(*audio))
+    }
+}
+
+impl<'a> VirtualMethods for JSRef<'a, HTMLAudioElement> {
+    fn super_type<'a>(&'a self) -> Option<&'a VirtualMethods> {
+        let htmlmediaelement: &JSRef<HTMLMediaElement> = HTMLMediaElementCast::from_borrowed_ref(self);
+        Some(htmlmediaelement as &VirtualMethods)
+    }
 }
 
 impl Reflectable for HTMLAudioElement {
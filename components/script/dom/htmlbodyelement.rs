@@ -5,19 +5,16 @@
 use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
 use dom::bindings::codegen::Bindings::HTMLBodyElementBinding;
 use dom::bindings::codegen::Bindings::HTMLBodyElementBinding::HTMLBodyElementMethods;
-use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
-use dom::bindings::codegen::InheritTypes::EventTargetCast;
 use dom::bindings::codegen::InheritTypes::{HTMLBodyElementDerived, HTMLElementCast};
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
 use dom::element::HTMLBodyElementTypeId;
-use dom::eventtarget::{EventTarget, NodeTargetTypeId, EventTargetHelpers};
+use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
-use dom::node::{Node, ElementNodeTypeId, window_from_node};
+use dom::node::{Node, ElementNodeTypeId};
 use dom::virtualmethods::VirtualMethods;
 
-use servo_util::atom::Atom;
 use servo_util::str::DOMString;
 
 #[jstraceable]
@@ -47,15 +44,18 @@ impl HTMLBodyElement {
 }
 
 impl<'a> HTMLBodyElementMethods for JSRef<'a, HTMLBodyElement> {
-    fn GetOnunload(self) -> Option<EventHandlerNonNull> {
-        let win = window_from_node(self).root();
-        win.deref().GetOnunload()
-    }
-
-    fn SetOnunload(self, listener: Option<EventHandlerNonNull>) {
-        let win = window_from_node(self).root();
-        win.deref().SetOnunload(listener)
-    }
+    window_owned_event_handler!(afterprint, GetOnafterprint, SetOnafterprint)
+    window_owned_event_handler!(beforeprint, GetOnbeforeprint, SetOnbeforeprint)
+    window_owned_event_handler!(beforeunload, GetOnbeforeunload, SetOnbeforeunload)
+    window_owned_event_handler!(hashchange, GetOnhashchange, SetOnhashchange)
+    window_owned_event_handler!(message, GetOnmessage, SetOnmessage)
+    window_owned_event_handler!(offline, GetOnoffline, SetOnoffline)
+    window_owned_event_handler!(online, GetOnonline, SetOnonline)
+    window_owned_event_handler!(pagehide, GetOnpagehide, SetOnpagehide)
+    window_owned_event_handler!(pageshow, GetOnpageshow, SetOnpageshow)
+    window_owned_event_handler!(popstate, GetOnpopstate, SetOnpopstate)
+    window_owned_event_handler!(storage, GetOnstorage, SetOnstorage)
+    window_owned_event_handler!(unload, GetOnunload, SetOnunload)
 }
 
 impl<'a> VirtualMethods for JSRef<'a, HTMLBodyElement> {
@@ -63,34 +63,6 @@ impl<'a> VirtualMethods for JSRef<'a, HTMLBodyElement> {
         let element: &JSRef<HTMLElement> = HTMLElementCast::from_borrowed_ref(self);
         Some(element as &VirtualMethods)
     }
-
-    fn after_set_attr(&self, name: &Atom, value: DOMString) {
-        match self.super_type() {
-            Some(ref s) => s.after_set_attr(name, value.clone()),
-            _ => (),
-        }
-
-        if name.as_slice().starts_with("on") {
-            static forwarded_events: &'static [&'static str] =
-                &["onfocus", "onload", "onscroll", "onafterprint", "onbeforeprint",
-                  "onbeforeunload", "onhashchange", "onlanguagechange", "onmessage",
-                  "onoffline", "ononline", "onpagehide", "onpageshow", "onpopstate",
-                  "onstorage", "onresize", "onunload", "onerror"];
-            let window = window_from_node(*self).root();
-            let (cx, url, reflector) = (window.get_cx(),
-                                        window.get_url(),
-                                        window.reflector().get_jsobject());
-            let evtarget: JSRef<EventTarget> =
-                if forwarded_events.iter().any(|&event| name.as_slice() == event) {
-                    EventTargetCast::from_ref(*window)
-                } else {
-                    EventTargetCast::from_ref(*self)
-                };
-            evtarget.set_event_handler_uncompiled(cx, url, reflector,
-                                                  name.as_slice().slice_from(2),
-                                                  value);
-        }
-    }
 }
 
 impl Reflectable for HTMLBodyElement {
@@ -12,7 +12,9 @@ use dom::document::Document;
 use dom::element::{AttributeHandlers, Element, HTMLButtonElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
+use dom::htmllabelelement::LabelableElementHelpers;
 use dom::node::{DisabledStateHelpers, Node, NodeHelpers, ElementNodeTypeId, window_from_node};
+use dom::nodelist::NodeList;
 use dom::validitystate::ValidityState;
 use dom::virtualmethods::VirtualMethods;
 
@@ -59,6 +61,12 @@ impl<'a> HTMLButtonElementMethods for JSRef<'a, HTMLButtonElement> {
         let elem: JSRef<Element> = ElementCast::from_ref(self);
         elem.set_bool_attribute("disabled", disabled)
     }
+
+    // http://www.whatwg.org/html/#dom-lfe-labels
+    fn Labels(self) -> Temporary<NodeList> {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.labels()
+    }
 }
 
 impl<'a> VirtualMethods for JSRef<'a, HTMLButtonElement> {
@@ -37,7 +37,7 @@ pub struct HTMLCollection {
 }
 
 impl HTMLCollection {
-    fn new_inherited(collection: CollectionTypeId) -> HTMLCollection {
+    pub fn new_inherited(collection: CollectionTypeId) -> HTMLCollection {
         HTMLCollection {
             collection: collection,
             reflector_: Reflector::new(),
@@ -138,15 +138,15 @@ impl HTMLCollection {
                          -> Temporary<HTMLCollection> {
         #[jstraceable]
         struct ClassNameFilter {
-            classes: Vec<DOMString>
+            classes: Vec<Atom>
         }
         impl CollectionFilter for ClassNameFilter {
             fn filter(&self, elem: JSRef<Element>, _root: JSRef<Node>) -> bool {
-                self.classes.iter().all(|class| elem.has_class(class.as_slice()))
+                self.classes.iter().all(|class| elem.has_class(class))
             }
         }
         let filter = ClassNameFilter {
-            classes: split_html_space_chars(classes.as_slice()).map(|class| class.to_string()).collect()
+            classes: split_html_space_chars(classes.as_slice()).map(|class| Atom::from_slice(class)).collect()
         };
         HTMLCollection::create(window, root, box filter)
     }
@@ -3,11 +3,13 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::bindings::codegen::Bindings::HTMLDataElementBinding;
+use dom::bindings::codegen::Bindings::HTMLDataElementBinding::HTMLDataElementMethods;
+use dom::bindings::codegen::InheritTypes::ElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLDataElementDerived;
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
-use dom::element::HTMLDataElementTypeId;
+use dom::element::{AttributeHandlers, Element, HTMLDataElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
 use dom::node::{Node, ElementNodeTypeId};
@@ -39,6 +41,17 @@ impl HTMLDataElement {
     }
 }
 
+impl<'a> HTMLDataElementMethods for JSRef<'a, HTMLDataElement> {
+    // http://www.whatwg.org/html/#dom-data-value
+    make_getter!(Value)
+
+    // http://www.whatwg.org/html/#dom-data-value
+    fn SetValue(self, value: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("value", value)
+    }
+}
+
 impl Reflectable for HTMLDataElement {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.htmlelement.reflector()
@@ -0,0 +1,175 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use dom::bindings::codegen::Bindings::HTMLDetailsElementBinding;
+use dom::bindings::codegen::Bindings::HTMLDetailsElementBinding::HTMLDetailsElementMethods;
+use dom::bindings::codegen::InheritTypes::{ElementCast, EventTargetCast, HTMLElementCast, NodeCast};
+use dom::bindings::codegen::InheritTypes::HTMLDetailsElementDerived;
+use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::trace::Traceable;
+use dom::bindings::utils::{Reflectable, Reflector};
+use dom::document::Document;
+use dom::element::{AttributeHandlers, Element, ElementHelpers, HTMLDetailsElementTypeId};
+use dom::event::Event;
+use dom::eventtarget::{EventTarget, NodeTargetTypeId};
+use dom::htmlelement::HTMLElement;
+use dom::node::{Node, NodeHelpers, ElementNodeTypeId, window_from_node};
+use dom::virtualmethods::VirtualMethods;
+use dom::window::WindowHelpers;
+
+use servo_util::atom::Atom;
+use servo_util::namespace;
+use servo_util::str::DOMString;
+
+use std::cell::Cell;
+
+#[jstraceable]
+#[must_root]
+pub struct HTMLDetailsElement {
+    pub htmlelement: HTMLElement,
+    /// Whether the open attribute was set, as of the last change to it.
+    was_open: Traceable<Cell<bool>>,
+}
+
+impl HTMLDetailsElementDerived for EventTarget {
+    fn is_htmldetailselement(&self) -> bool {
+        self.type_id == NodeTargetTypeId(ElementNodeTypeId(HTMLDetailsElementTypeId))
+    }
+}
+
+impl HTMLDetailsElement {
+    fn new_inherited(localName: DOMString, document: JSRef<Document>) -> HTMLDetailsElement {
+        HTMLDetailsElement {
+            htmlelement: HTMLElement::new_inherited(HTMLDetailsElementTypeId, localName, document),
+            was_open: Traceable::new(Cell::new(false)),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(localName: DOMString, document: JSRef<Document>) -> Temporary<HTMLDetailsElement> {
+        let element = HTMLDetailsElement::new_inherited(localName, document);
+        Node::reflect_node(box element, document, HTMLDetailsElementBinding::Wrap)
+    }
+}
+
+pub trait HTMLDetailsElementHelpers {
+    fn is_summary_for_details(self, summary: JSRef<Element>) -> bool;
+    fn toggle(self);
+}
+
+impl<'a> HTMLDetailsElementHelpers for JSRef<'a, HTMLDetailsElement> {
+    /// Returns whether `summary` is the first summary element child of this
+    /// details element, i.e. the one that represents its summary.
+    /// http://www.whatwg.org/html/#the-summary-element
+    fn is_summary_for_details(self, summary: JSRef<Element>) -> bool {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        node.child_elements().find(|child| {
+            *child.get_namespace() == namespace::HTML &&
+                child.get_local_name().as_slice() == "summary"
+        }).map_or(false, |first_summary| first_summary == summary)
+    }
+
+    fn toggle(self) {
+        let open = self.Open();
+        self.SetOpen(!open);
+    }
+}
+
+trait PrivateHTMLDetailsElementHelpers {
+    fn queue_toggle_event(self, open: bool);
+    fn handle_event_impl(self, event: JSRef<Event>);
+}
+
+impl<'a> PrivateHTMLDetailsElementHelpers for JSRef<'a, HTMLDetailsElement> {
+    // http://www.whatwg.org/html/#details-notification-task-steps
+    fn queue_toggle_event(self, open: bool) {
+        // Setting the attribute again while it is set doesn't toggle.
+        if self.was_open.deref().get() == open {
+            return;
+        }
+        self.was_open.deref().set(open);
+
+        // Only one toggle event is fired for all the changes made to the open
+        // attribute before the queued task runs.
+        let window = window_from_node(self).root();
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        if !window.has_queued_event(target, "toggle") {
+            window.queue_simple_event(target, "toggle".to_string());
+        }
+    }
+
+    fn handle_event_impl(self, event: JSRef<Event>) {
+        // Without a summary child, the details element provides its own
+        // summary, which toggles it when activated.
+        if "click" == event.Type().as_slice() && !event.DefaultPrevented() {
+            let node: JSRef<Node> = NodeCast::from_ref(self);
+            let has_summary = node.child_elements().any(|child| {
+                *child.get_namespace() == namespace::HTML &&
+                    child.get_local_name().as_slice() == "summary"
+            });
+            if !has_summary {
+                self.toggle();
+            }
+        }
+    }
+}
+
+impl<'a> HTMLDetailsElementMethods for JSRef<'a, HTMLDetailsElement> {
+    // http://www.whatwg.org/html/#dom-details-open
+    make_bool_getter!(Open)
+
+    // http://www.whatwg.org/html/#dom-details-open
+    fn SetOpen(self, open: bool) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_bool_attribute("open", open)
+    }
+}
+
+impl<'a> VirtualMethods for JSRef<'a, HTMLDetailsElement> {
+    fn super_type<'a>(&'a self) -> Option<&'a VirtualMethods> {
+        let htmlelement: &JSRef<HTMLElement> = HTMLElementCast::from_borrowed_ref(self);
+        Some(htmlelement as &VirtualMethods)
+    }
+
+    fn after_set_attr(&self, name: &Atom, value: DOMString) {
+        match self.super_type() {
+            Some(ref s) => s.after_set_attr(name, value.clone()),
+            _ => (),
+        }
+
+        match name.as_slice() {
+            "open" => self.queue_toggle_event(true),
+            _ => ()
+        }
+    }
+
+    fn before_remove_attr(&self, name: &Atom, value: DOMString) {
+        match self.super_type() {
+            Some(ref s) => s.before_remove_attr(name, value),
+            _ => (),
+        }
+
+        match name.as_slice() {
+            "open" => self.queue_toggle_event(false),
+            _ => ()
+        }
+    }
+
+    fn handle_event(&self, event: JSRef<Event>) {
+        match self.super_type() {
+            Some(s) => {
+                s.handle_event(event);
+            }
+            None => {}
+        }
+        self.handle_event_impl(event);
+    }
+}
+
+impl Reflectable for HTMLDetailsElement {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        self.htmlelement.reflector()
+    }
+}
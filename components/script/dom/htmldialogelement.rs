@@ -0,0 +1,231 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use dom::bindings::codegen::Bindings::HTMLDialogElementBinding;
+use dom::bindings::codegen::Bindings::HTMLDialogElementBinding::HTMLDialogElementMethods;
+use dom::bindings::codegen::InheritTypes::{ElementCast, EventTargetCast, HTMLElementCast, NodeCast};
+use dom::bindings::codegen::InheritTypes::HTMLDialogElementDerived;
+use dom::bindings::error::{ErrorResult, InvalidState};
+use dom::bindings::global;
+use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::trace::Traceable;
+use dom::bindings::utils::{Reflectable, Reflector};
+use dom::document::{Document, DocumentHelpers};
+use dom::element::{AttributeHandlers, Element, HTMLDialogElementTypeId};
+use dom::element::{HTMLAnchorElementTypeId, HTMLButtonElementTypeId, HTMLInputElementTypeId};
+use dom::element::{HTMLSelectElementTypeId, HTMLTextAreaElementTypeId};
+use dom::event::Event;
+use dom::eventtarget::{EventTarget, EventTargetHelpers, NodeTargetTypeId};
+use dom::htmlelement::HTMLElement;
+use dom::node::{Node, NodeHelpers, ElementNodeTypeId, document_from_node, window_from_node};
+use dom::virtualmethods::VirtualMethods;
+use dom::window::WindowHelpers;
+
+use servo_util::str::DOMString;
+
+use std::cell::RefCell;
+
+#[jstraceable]
+#[must_root]
+pub struct HTMLDialogElement {
+    pub htmlelement: HTMLElement,
+    return_value: Traceable<RefCell<DOMString>>,
+}
+
+impl HTMLDialogElementDerived for EventTarget {
+    fn is_htmldialogelement(&self) -> bool {
+        self.type_id == NodeTargetTypeId(ElementNodeTypeId(HTMLDialogElementTypeId))
+    }
+}
+
+impl HTMLDialogElement {
+    fn new_inherited(localName: DOMString, document: JSRef<Document>) -> HTMLDialogElement {
+        HTMLDialogElement {
+            htmlelement: HTMLElement::new_inherited(HTMLDialogElementTypeId, localName, document),
+            return_value: Traceable::new(RefCell::new("".to_string())),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(localName: DOMString, document: JSRef<Document>) -> Temporary<HTMLDialogElement> {
+        let element = HTMLDialogElement::new_inherited(localName, document);
+        Node::reflect_node(box element, document, HTMLDialogElementBinding::Wrap)
+    }
+}
+
+pub trait HTMLDialogElementHelpers {
+    fn cancel(self);
+}
+
+impl<'a> HTMLDialogElementHelpers for JSRef<'a, HTMLDialogElement> {
+    /// Fires a cancelable `cancel` event at the dialog, whose default action
+    /// closes it.
+    /// http://www.whatwg.org/html/#canceling-dialogs
+    fn cancel(self) {
+        let window = window_from_node(self).root();
+        let event = Event::new(&global::Window(*window), "cancel".to_string(),
+                               false, true).root();
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        let _ = target.dispatch_event_with_target(None, *event);
+    }
+}
+
+trait PrivateHTMLDialogElementHelpers {
+    fn run_focusing_steps(self);
+    fn handle_event_impl(self, event: JSRef<Event>);
+}
+
+impl<'a> PrivateHTMLDialogElementHelpers for JSRef<'a, HTMLDialogElement> {
+    // http://www.whatwg.org/html/#dialog-focusing-steps
+    fn run_focusing_steps(self) {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        let control = node.traverse_preorder().skip(1).find(|candidate| {
+            match candidate.type_id() {
+                ElementNodeTypeId(HTMLButtonElementTypeId) |
+                ElementNodeTypeId(HTMLInputElementTypeId) |
+                ElementNodeTypeId(HTMLSelectElementTypeId) |
+                ElementNodeTypeId(HTMLTextAreaElementTypeId) => !candidate.get_disabled_state(),
+                ElementNodeTypeId(HTMLAnchorElementTypeId) => {
+                    let element: JSRef<Element> = ElementCast::to_ref(*candidate).unwrap();
+                    element.has_attribute("href")
+                },
+                _ => false,
+            }
+        });
+
+        let element: JSRef<Element> = match control {
+            Some(control) => ElementCast::to_ref(control).unwrap(),
+            None => ElementCast::from_ref(self),
+        };
+        let document = document_from_node(self).root();
+        document.set_focused_element(Some(element));
+    }
+
+    fn handle_event_impl(self, event: JSRef<Event>) {
+        // The default action of a cancel event is to close the dialog.
+        if "cancel" == event.Type().as_slice() && !event.DefaultPrevented() {
+            self.Close(None);
+        }
+    }
+}
+
+impl<'a> HTMLDialogElementMethods for JSRef<'a, HTMLDialogElement> {
+    // http://www.whatwg.org/html/#dom-dialog-open
+    make_bool_getter!(Open)
+
+    // http://www.whatwg.org/html/#dom-dialog-open
+    fn SetOpen(self, open: bool) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_bool_attribute("open", open)
+    }
+
+    // http://www.whatwg.org/html/#dom-dialog-returnvalue
+    fn ReturnValue(self) -> DOMString {
+        self.return_value.deref().borrow().clone()
+    }
+
+    // http://www.whatwg.org/html/#dom-dialog-returnvalue
+    fn SetReturnValue(self, return_value: DOMString) {
+        *self.return_value.deref().borrow_mut() = return_value;
+    }
+
+    // http://www.whatwg.org/html/#dom-dialog-show
+    fn Show(self) -> ErrorResult {
+        if self.Open() {
+            return Err(InvalidState);
+        }
+        self.SetOpen(true);
+        self.run_focusing_steps();
+        Ok(())
+    }
+
+    // http://www.whatwg.org/html/#dom-dialog-showmodal
+    fn ShowModal(self) -> ErrorResult {
+        // Step 1.
+        if self.Open() {
+            return Err(InvalidState);
+        }
+
+        // Step 2.
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        if !node.is_in_doc() {
+            return Err(InvalidState);
+        }
+
+        // Step 3.
+        self.SetOpen(true);
+
+        // Steps 4-5.
+        let document = document_from_node(self).root();
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        document.add_to_top_layer(element);
+
+        // Step 6.
+        self.run_focusing_steps();
+        Ok(())
+    }
+
+    // http://www.whatwg.org/html/#dom-dialog-close
+    fn Close(self, return_value: Option<DOMString>) {
+        // Step 1.
+        if !self.Open() {
+            return;
+        }
+
+        // Step 2.
+        self.SetOpen(false);
+
+        // Step 3.
+        match return_value {
+            Some(return_value) => self.SetReturnValue(return_value),
+            None => (),
+        }
+
+        // Step 4.
+        let document = document_from_node(self).root();
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        document.remove_from_top_layer(element);
+
+        // Step 5.
+        let window = window_from_node(self).root();
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        window.queue_simple_event(target, "close".to_string());
+    }
+}
+
+impl<'a> VirtualMethods for JSRef<'a, HTMLDialogElement> {
+    fn super_type<'a>(&'a self) -> Option<&'a VirtualMethods> {
+        let htmlelement: &JSRef<HTMLElement> = HTMLElementCast::from_borrowed_ref(self);
+        Some(htmlelement as &VirtualMethods)
+    }
+
+    fn unbind_from_tree(&self, tree_in_doc: bool) {
+        match self.super_type() {
+            Some(ref s) => s.unbind_from_tree(tree_in_doc),
+            _ => (),
+        }
+
+        // A dialog that leaves the document can no longer block the rest of it.
+        let document = document_from_node(*self).root();
+        let element: JSRef<Element> = ElementCast::from_ref(*self);
+        document.remove_from_top_layer(element);
+    }
+
+    fn handle_event(&self, event: JSRef<Event>) {
+        match self.super_type() {
+            Some(s) => {
+                s.handle_event(event);
+            }
+            None => {}
+        }
+        self.handle_event_impl(event);
+    }
+}
+
+impl Reflectable for HTMLDialogElement {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        self.htmlelement.reflector()
+    }
+}
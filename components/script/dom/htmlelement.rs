@@ -2,29 +2,51 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use dom::bindings::codegen::Bindings::CharacterDataBinding::CharacterDataMethods;
+use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
 use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
 use dom::bindings::codegen::Bindings::HTMLElementBinding;
 use dom::bindings::codegen::Bindings::HTMLElementBinding::HTMLElementMethods;
+use dom::bindings::codegen::Bindings::KeyboardEventBinding::KeyboardEventMethods;
+use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
 use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
-use dom::bindings::codegen::InheritTypes::{ElementCast, HTMLFrameSetElementDerived};
-use dom::bindings::codegen::InheritTypes::EventTargetCast;
+use dom::bindings::codegen::InheritTypes::{CharacterDataCast, ElementCast, HTMLFrameSetElementDerived};
+use dom::bindings::codegen::InheritTypes::{EventCast, EventTargetCast, HTMLElementCast};
+use dom::bindings::codegen::InheritTypes::KeyboardEventCast;
 use dom::bindings::codegen::InheritTypes::{HTMLElementDerived, HTMLBodyElementDerived};
-use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::codegen::InheritTypes::{HTMLDetailsElementCast, HTMLLabelElementCast, NodeCast};
+use dom::bindings::codegen::InheritTypes::TextCast;
+use dom::bindings::error::{ErrorResult, Syntax};
+use dom::bindings::global;
+use dom::bindings::js::{JSRef, Temporary, OptionalRootable};
 use dom::bindings::utils::{Reflectable, Reflector};
-use dom::document::Document;
-use dom::element::{Element, ElementTypeId, ElementTypeId_, HTMLElementTypeId};
+use dom::characterdata::CharacterData;
+use dom::document::{Document, DocumentHelpers};
+use dom::element::{AttributeHandlers, Element, ElementHelpers, ElementTypeId, ElementTypeId_};
+use dom::element::{HTMLElementTypeId, HTMLAnchorElementTypeId, HTMLAreaElementTypeId};
+use dom::element::{HTMLButtonElementTypeId, HTMLFrameElementTypeId, HTMLIFrameElementTypeId};
+use dom::element::{HTMLInputElementTypeId, HTMLLinkElementTypeId, HTMLObjectElementTypeId};
+use dom::element::{HTMLSelectElementTypeId, HTMLTextAreaElementTypeId};
+use dom::event::Event;
 use dom::eventtarget::{EventTarget, EventTargetHelpers, NodeTargetTypeId};
-use dom::node::{Node, ElementNodeTypeId, window_from_node};
+use dom::htmldetailselement::HTMLDetailsElementHelpers;
+use dom::htmllabelelement::HTMLLabelElementHelpers;
+use dom::mouseevent::MouseEvent;
+use dom::node::{Node, NodeHelpers, ElementNodeTypeId, ClickInProgress};
+use dom::node::{document_from_node, window_from_node};
+use dom::text::Text;
 use dom::virtualmethods::VirtualMethods;
 
 use servo_util::atom::Atom;
 use servo_util::namespace;
-use servo_util::str::DOMString;
+use servo_util::str::{DOMString, parse_integer};
+
+use std::ascii::StrAsciiExt;
 
 #[jstraceable]
 #[must_root]
 pub struct HTMLElement {
-    pub element: Element
+    pub element: Element,
 }
 
 impl HTMLElementDerived for EventTarget {
@@ -40,7 +62,7 @@ impl HTMLElementDerived for EventTarget {
 impl HTMLElement {
     pub fn new_inherited(type_id: ElementTypeId, tag_name: DOMString, document: JSRef<Document>) -> HTMLElement {
         HTMLElement {
-            element: Element::new_inherited(type_id, tag_name, namespace::HTML, None, document)
+            element: Element::new_inherited(type_id, tag_name, namespace::HTML, None, document),
         }
     }
 
@@ -51,8 +73,108 @@ impl HTMLElement {
     }
 }
 
+pub trait HTMLElementHelpers {
+    fn is_editable(self) -> bool;
+    fn editing_host(self) -> Option<Temporary<HTMLElement>>;
+    fn focus_editing_host(self) -> Option<Temporary<HTMLElement>>;
+    fn is_sequentially_focusable(self) -> bool;
+}
+
+impl<'a> HTMLElementHelpers for JSRef<'a, HTMLElement> {
+    // http://www.whatwg.org/html/#editable
+    fn is_editable(self) -> bool {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        match self.content_editable_state() {
+            Some(editable) => editable,
+            None => match node.parent_node().root() {
+                Some(parent) => match HTMLElementCast::to_ref(*parent) {
+                    Some(parent) => parent.is_editable(),
+                    None => false,
+                },
+                None => false,
+            },
+        }
+    }
+
+    /// Returns the outermost editable element this element is part of.
+    /// http://www.whatwg.org/html/#editing-host
+    fn editing_host(self) -> Option<Temporary<HTMLElement>> {
+        if !self.is_editable() {
+            return None;
+        }
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        let host = node.ancestors()
+                       .filter_map(|ancestor| HTMLElementCast::to_ref(ancestor))
+                       .take_while(|ancestor| ancestor.is_editable())
+                       .last()
+                       .unwrap_or(self);
+        Some(Temporary::from_rooted(host))
+    }
+
+    /// Focuses the editing host of this element, if it is editable, making
+    /// sure the caret is inside it.
+    fn focus_editing_host(self) -> Option<Temporary<HTMLElement>> {
+        let host = match self.editing_host().root() {
+            Some(host) => host,
+            None => return None,
+        };
+        let document = document_from_node(self).root();
+        let element: JSRef<Element> = ElementCast::from_ref(*host);
+        document.set_focused_element(Some(element));
+
+        let host_node: JSRef<Node> = NodeCast::from_ref(*host);
+        let caret_in_host = match document.get_caret() {
+            Some((text, _)) => {
+                let text = text.root();
+                let text: JSRef<Node> = NodeCast::from_ref(*text);
+                host_node.is_inclusive_ancestor_of(text)
+            },
+            None => false,
+        };
+        if !caret_in_host {
+            host.place_caret_at_end();
+        }
+        Some(Temporary::from_rooted(*host))
+    }
+
+    /// Whether the element takes part in sequential focus navigation, i.e.
+    /// whether the Tab key can move the focus to it.
+    /// http://www.whatwg.org/html/#sequentially-focusable
+    fn is_sequentially_focusable(self) -> bool {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        if node.get_disabled_state() || !node.is_in_doc() {
+            return false;
+        }
+        if element.has_attribute("tabindex") {
+            return self.TabIndex() >= 0;
+        }
+
+        // http://www.whatwg.org/html/#focusable-area
+        match node.type_id() {
+            ElementNodeTypeId(HTMLAnchorElementTypeId) |
+            ElementNodeTypeId(HTMLAreaElementTypeId) |
+            ElementNodeTypeId(HTMLLinkElementTypeId) => element.has_attribute("href"),
+            ElementNodeTypeId(HTMLInputElementTypeId) => {
+                !element.get_string_attribute("type").as_slice().eq_ignore_ascii_case("hidden")
+            },
+            ElementNodeTypeId(HTMLButtonElementTypeId) |
+            ElementNodeTypeId(HTMLIFrameElementTypeId) |
+            ElementNodeTypeId(HTMLSelectElementTypeId) |
+            ElementNodeTypeId(HTMLTextAreaElementTypeId) => true,
+            _ => self.is_summary_for_details() || self.is_editing_host(),
+        }
+    }
+}
+
 trait PrivateHTMLElementHelpers {
     fn is_body_or_frameset(self) -> bool;
+    fn is_summary_for_details(self) -> bool;
+    fn is_editing_host(self) -> bool;
+    fn content_editable_state(self) -> Option<bool>;
+    fn place_caret_at_end(self);
+    fn edit(self, key: DOMString);
+    fn handle_event_impl(self, event: JSRef<Event>);
 }
 
 impl<'a> PrivateHTMLElementHelpers for JSRef<'a, HTMLElement> {
@@ -60,9 +182,290 @@ impl<'a> PrivateHTMLElementHelpers for JSRef<'a, HTMLElement> {
         let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(self);
         eventtarget.is_htmlbodyelement() || eventtarget.is_htmlframesetelement()
     }
+
+    /// Whether this is the summary element that toggles its parent details
+    /// element.
+    fn is_summary_for_details(self) -> bool {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        if element.get_local_name().as_slice() != "summary" {
+            return false;
+        }
+        match node.parent_node().root() {
+            Some(parent) => match HTMLDetailsElementCast::to_ref(*parent) {
+                Some(details) => details.is_summary_for_details(element),
+                None => false,
+            },
+            None => false,
+        }
+    }
+
+    /// Whether this element is editable while its parent isn't.
+    fn is_editing_host(self) -> bool {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        self.is_editable() && match node.parent_node().root() {
+            Some(parent) => match HTMLElementCast::to_ref(*parent) {
+                Some(parent) => !parent.is_editable(),
+                None => true,
+            },
+            None => true,
+        }
+    }
+
+    /// Moves the caret to the end of the text of this editing host, adding a
+    /// text node to hold it if there is none. Text inside non-editable
+    /// descendants, or inside another editing host, is skipped.
+    fn place_caret_at_end(self) {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        let document = document_from_node(self).root();
+        let last_text = node.traverse_preorder().filter(|node| {
+            node.is_text() && match node.parent_node().root() {
+                Some(parent) => match HTMLElementCast::to_ref(*parent) {
+                    Some(parent) => parent.editing_host().root().map_or(false, |host| *host == self),
+                    None => false,
+                },
+                None => false,
+            }
+        }).last();
+        match last_text {
+            Some(text) => {
+                let characterdata: JSRef<CharacterData> = CharacterDataCast::to_ref(text).unwrap();
+                let text: JSRef<Text> = TextCast::to_ref(text).unwrap();
+                document.set_caret(Some(text), characterdata.Length());
+            },
+            None => {
+                let text = Text::new("".to_string(), *document).root();
+                let text_node: JSRef<Node> = NodeCast::from_ref(*text);
+                assert!(node.AppendChild(text_node).is_ok());
+                document.set_caret(Some(*text), 0);
+            },
+        }
+    }
+
+    /// Returns the state of the contenteditable attribute: whether it makes
+    /// the element editable or not, or None if it inherits the state.
+    fn content_editable_state(self) -> Option<bool> {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        if !element.has_attribute("contenteditable") {
+            return None;
+        }
+        let value = element.get_string_attribute("contenteditable");
+        match value.as_slice().to_ascii_lower().as_slice() {
+            "" | "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Inserts the typed character at the caret, or deletes the character
+    /// before it for Backspace.
+    /// FIXME: this is only the minimal editing loop, without any of the
+    /// editing commands.
+    fn edit(self, key: DOMString) {
+        let host = match self.focus_editing_host().root() {
+            Some(host) => host,
+            None => return,
+        };
+        let document = document_from_node(self).root();
+        let (text, offset) = match document.get_caret() {
+            Some((text, offset)) => (text.root(), offset),
+            None => return,
+        };
+        let characterdata: JSRef<CharacterData> = CharacterDataCast::from_ref(*text);
+
+        if key.as_slice() == "Backspace" {
+            let data = characterdata.Data();
+            let previous = data.as_slice().slice_to(offset as uint)
+                               .char_indices().last().map(|(index, _)| index as u32);
+            match previous {
+                Some(previous) => {
+                    assert!(characterdata.DeleteData(previous, offset - previous).is_ok());
+                    document.set_caret(Some(*text), previous);
+                },
+                // FIXME: deleting across nodes isn't supported.
+                None => return,
+            }
+        } else if key.as_slice().char_len() == 1 {
+            assert!(characterdata.InsertData(offset, key.clone()).is_ok());
+            document.set_caret(Some(*text), offset + key.len() as u32);
+        } else {
+            return;
+        }
+
+        // http://www.whatwg.org/html/#event-input-input
+        let window = window_from_node(self).root();
+        let event = Event::new(&global::Window(*window), "input".to_string(),
+                               true, false).root();
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(*host);
+        let _ = target.dispatch_event_with_target(None, *event);
+    }
+
+    fn handle_event_impl(self, event: JSRef<Event>) {
+        // Characters are typed by keypress events, while Backspace only
+        // has a keydown event.
+        match KeyboardEventCast::to_ref(event) {
+            Some(keyboard_event) if !event.DefaultPrevented() => {
+                let key = keyboard_event.Key();
+                match (event.Type().as_slice(), key.as_slice()) {
+                    // A focused summary is activated from the keyboard too.
+                    // http://www.whatwg.org/html/#the-summary-element
+                    ("keydown", "Enter") | ("keypress", " ") if self.is_summary_for_details() => {
+                        self.Click()
+                    },
+                    ("keypress", key) if key != "Backspace" => self.edit(key.to_string()),
+                    ("keydown", "Backspace") => self.edit(key.clone()),
+                    _ => (),
+                }
+                return;
+            },
+            _ => (),
+        }
+
+        if "click" != event.Type().as_slice() || event.DefaultPrevented() {
+            return;
+        }
+
+        // Clicks anywhere inside a summary toggle its details element.
+        // http://www.whatwg.org/html/#the-summary-element
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        let summary = Some(node).into_iter().chain(node.ancestors())
+                                .filter_map(|node| HTMLElementCast::to_ref(node))
+                                .find(|element| element.is_summary_for_details());
+        match summary {
+            Some(summary) => {
+                let summary: JSRef<Node> = NodeCast::from_ref(summary);
+                let parent = summary.parent_node().root().unwrap();
+                HTMLDetailsElementCast::to_ref(*parent).unwrap().toggle();
+            },
+            None => (),
+        }
+
+        // Clicks anywhere inside a label activate its labeled control.
+        let label = match HTMLLabelElementCast::to_ref(node) {
+            Some(label) => Some(label),
+            None => node.ancestors()
+                        .filter_map(|ancestor| HTMLLabelElementCast::to_ref(ancestor))
+                        .next(),
+        };
+        match label {
+            Some(label) => label.activate(node),
+            None => (),
+        }
+
+        // Clicking editable content focuses it.
+        self.focus_editing_host();
+    }
 }
 
 impl<'a> HTMLElementMethods for JSRef<'a, HTMLElement> {
+    make_getter!(Title)
+
+    fn SetTitle(self, title: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("title", title)
+    }
+
+    make_getter!(Lang)
+
+    fn SetLang(self, lang: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("lang", lang)
+    }
+
+    // http://www.whatwg.org/html/#the-dir-attribute
+    fn Dir(self) -> DOMString {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        let dir = element.get_string_attribute("dir").as_slice().to_ascii_lower();
+        match dir.as_slice() {
+            "ltr" | "rtl" | "auto" => dir.clone(),
+            _ => "".to_string(),
+        }
+    }
+
+    fn SetDir(self, dir: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("dir", dir)
+    }
+
+    make_bool_getter!(Hidden)
+
+    fn SetHidden(self, hidden: bool) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_bool_attribute("hidden", hidden)
+    }
+
+    // http://www.whatwg.org/html/#dom-click
+    fn Click(self) {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        if node.get_disabled_state() || node.get_flag(ClickInProgress) {
+            return;
+        }
+
+        // The event isn't trusted, but it still runs the activation behavior.
+        node.set_flag(ClickInProgress, true);
+        let window = window_from_node(self).root();
+        let event = MouseEvent::new(*window, "click".to_string(), true, true, Some(*window), 0,
+                                    0, 0, 0, 0, false, false, false, false, 0, None).root();
+        let event: JSRef<Event> = EventCast::from_ref(*event);
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        let _ = target.dispatch_event_with_target(None, event);
+        node.set_flag(ClickInProgress, false);
+    }
+
+    // http://www.whatwg.org/html/#dom-tabindex
+    fn TabIndex(self) -> i32 {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        let value = element.get_string_attribute("tabindex");
+        match parse_integer(value.as_slice().chars()) {
+            Some(index) => index,
+            None => match node.type_id() {
+                ElementNodeTypeId(HTMLAnchorElementTypeId) |
+                ElementNodeTypeId(HTMLAreaElementTypeId) |
+                ElementNodeTypeId(HTMLButtonElementTypeId) |
+                ElementNodeTypeId(HTMLFrameElementTypeId) |
+                ElementNodeTypeId(HTMLIFrameElementTypeId) |
+                ElementNodeTypeId(HTMLInputElementTypeId) |
+                ElementNodeTypeId(HTMLObjectElementTypeId) |
+                ElementNodeTypeId(HTMLSelectElementTypeId) |
+                ElementNodeTypeId(HTMLTextAreaElementTypeId) => 0,
+                _ if self.is_summary_for_details() => 0,
+                _ => -1,
+            },
+        }
+    }
+
+    fn SetTabIndex(self, index: i32) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("tabindex", index.to_string())
+    }
+
+    // http://www.whatwg.org/html/#dom-contenteditable
+    fn ContentEditable(self) -> DOMString {
+        match self.content_editable_state() {
+            Some(true) => "true",
+            Some(false) => "false",
+            None => "inherit",
+        }.to_string()
+    }
+
+    // http://www.whatwg.org/html/#dom-contenteditable
+    fn SetContentEditable(self, value: DOMString) -> ErrorResult {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        match value.as_slice().to_ascii_lower().as_slice() {
+            "inherit" => element.remove_attribute(namespace::Null, "contenteditable"),
+            "true" => element.set_string_attribute("contenteditable", "true".to_string()),
+            "false" => element.set_string_attribute("contenteditable", "false".to_string()),
+            _ => return Err(Syntax),
+        }
+        Ok(())
+    }
+
+    // http://www.whatwg.org/html/#dom-iscontenteditable
+    fn IsContentEditable(self) -> bool {
+        self.is_editable()
+    }
+
     fn GetOnclick(self) -> Option<EventHandlerNonNull> {
         let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(self);
         eventtarget.get_event_handler_common("click")
@@ -88,6 +491,46 @@ impl<'a> HTMLElementMethods for JSRef<'a, HTMLElement> {
             win.deref().SetOnload(listener)
         }
     }
+
+    fn GetOnresize(self) -> Option<EventHandlerNonNull> {
+        if self.is_body_or_frameset() {
+            let win = window_from_node(self).root();
+            win.deref().GetOnresize()
+        } else {
+            let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+            eventtarget.get_event_handler_common("resize")
+        }
+    }
+
+    fn SetOnresize(self, listener: Option<EventHandlerNonNull>) {
+        if self.is_body_or_frameset() {
+            let win = window_from_node(self).root();
+            win.deref().SetOnresize(listener)
+        } else {
+            let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+            eventtarget.set_event_handler_common("resize", listener)
+        }
+    }
+
+    fn GetOnscroll(self) -> Option<EventHandlerNonNull> {
+        if self.is_body_or_frameset() {
+            let win = window_from_node(self).root();
+            win.deref().GetOnscroll()
+        } else {
+            let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+            eventtarget.get_event_handler_common("scroll")
+        }
+    }
+
+    fn SetOnscroll(self, listener: Option<EventHandlerNonNull>) {
+        if self.is_body_or_frameset() {
+            let win = window_from_node(self).root();
+            win.deref().SetOnscroll(listener)
+        } else {
+            let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+            eventtarget.set_event_handler_common("scroll", listener)
+        }
+    }
 }
 
 impl<'a> VirtualMethods for JSRef<'a, HTMLElement> {
@@ -103,16 +546,40 @@ impl<'a> VirtualMethods for JSRef<'a, HTMLElement> {
         }
 
         if name.as_slice().starts_with("on") {
+            // The body and frameset elements set some of the handlers of the
+            // Window rather than their own.
+            // http://www.whatwg.org/html/#window-reflecting-body-element-event-handler-set
+            static window_owned_events: &'static [&'static str] =
+                &["onblur", "onerror", "onfocus", "onload", "onresize", "onscroll",
+                  "onafterprint", "onbeforeprint", "onbeforeunload", "onhashchange",
+                  "onlanguagechange", "onmessage", "onoffline", "ononline", "onpagehide",
+                  "onpageshow", "onpopstate", "onstorage", "onunload"];
             let window = window_from_node(*self).root();
             let (cx, url, reflector) = (window.get_cx(),
                                         window.get_url(),
                                         window.reflector().get_jsobject());
-            let evtarget: JSRef<EventTarget> = EventTargetCast::from_ref(*self);
+            let evtarget: JSRef<EventTarget> =
+                if self.is_body_or_frameset() &&
+                   window_owned_events.iter().any(|&event| name.as_slice() == event) {
+                    EventTargetCast::from_ref(*window)
+                } else {
+                    EventTargetCast::from_ref(*self)
+                };
             evtarget.set_event_handler_uncompiled(cx, url, reflector,
                                                   name.as_slice().slice_from(2),
                                                   value);
         }
     }
+
+    fn handle_event(&self, event: JSRef<Event>) {
+        match self.super_type() {
+            Some(s) => {
+                s.handle_event(event);
+            }
+            None => {}
+        }
+        self.handle_event_impl(event);
+    }
 }
 
 impl Reflectable for HTMLElement {
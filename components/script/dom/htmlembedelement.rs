@@ -3,11 +3,13 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::bindings::codegen::Bindings::HTMLEmbedElementBinding;
+use dom::bindings::codegen::Bindings::HTMLEmbedElementBinding::HTMLEmbedElementMethods;
+use dom::bindings::codegen::InheritTypes::ElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLEmbedElementDerived;
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
-use dom::element::HTMLEmbedElementTypeId;
+use dom::element::{AttributeHandlers, Element, HTMLEmbedElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
 use dom::node::{Node, ElementNodeTypeId};
@@ -39,6 +41,26 @@ impl HTMLEmbedElement {
     }
 }
 
+impl<'a> HTMLEmbedElementMethods for JSRef<'a, HTMLEmbedElement> {
+    // http://www.whatwg.org/html/#dom-embed-src
+    make_url_getter!(Src)
+
+    // http://www.whatwg.org/html/#dom-embed-src
+    fn SetSrc(self, src: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_url_attribute("src", src)
+    }
+
+    // http://www.whatwg.org/html/#dom-embed-type
+    make_getter!(Type)
+
+    // http://www.whatwg.org/html/#dom-embed-type
+    fn SetType(self, type_: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("type", type_)
+    }
+}
+
 impl Reflectable for HTMLEmbedElement {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.htmlelement.reflector()
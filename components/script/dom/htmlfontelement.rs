@@ -3,11 +3,12 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::bindings::codegen::Bindings::HTMLFontElementBinding;
-use dom::bindings::codegen::InheritTypes::HTMLFontElementDerived;
+use dom::bindings::codegen::Bindings::HTMLFontElementBinding::HTMLFontElementMethods;
+use dom::bindings::codegen::InheritTypes::{ElementCast, HTMLFontElementDerived};
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
-use dom::element::HTMLFontElementTypeId;
+use dom::element::{AttributeHandlers, Element, HTMLFontElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
 use dom::node::{Node, ElementNodeTypeId};
@@ -39,6 +40,29 @@ impl HTMLFontElement {
     }
 }
 
+impl<'a> HTMLFontElementMethods for JSRef<'a, HTMLFontElement> {
+    make_getter!(Color)
+
+    fn SetColor(self, color: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("color", color)
+    }
+
+    make_getter!(Face)
+
+    fn SetFace(self, face: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("face", face)
+    }
+
+    make_getter!(Size)
+
+    fn SetSize(self, size: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("size", size)
+    }
+}
+
 impl Reflectable for HTMLFontElement {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.htmlelement.reflector()
@@ -0,0 +1,125 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::HTMLCollectionBinding::HTMLCollectionMethods;
+use dom::bindings::codegen::Bindings::HTMLFormControlsCollectionBinding;
+use dom::bindings::codegen::Bindings::HTMLFormControlsCollectionBinding::HTMLFormControlsCollectionMethods;
+use dom::bindings::codegen::InheritTypes::{ElementCast, HTMLCollectionCast, NodeCast};
+use dom::bindings::codegen::UnionTypes::RadioNodeListOrElement::{RadioNodeListOrElement, eElement, eRadioNodeList};
+use dom::bindings::global;
+use dom::bindings::js::{JS, JSRef, Temporary, OptionalRootable};
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::element::{AttributeHandlers, Element};
+use dom::htmlcollection::{HTMLCollection, CollectionFilter, Live};
+use dom::htmlformelement::{HTMLFormElement, FormControlHelpers};
+use dom::node::{Node, NodeHelpers, window_from_node};
+use dom::radionodelist::RadioNodeList;
+use dom::window::Window;
+use servo_util::str::DOMString;
+
+/// The listed elements whose form owner is the root.
+#[jstraceable]
+struct FormControlsFilter;
+
+impl CollectionFilter for FormControlsFilter {
+    fn filter(&self, elem: JSRef<Element>, root: JSRef<Node>) -> bool {
+        if !elem.is_listed_element() {
+            return false;
+        }
+        match elem.form_owner().root() {
+            Some(owner) => {
+                let owner: JSRef<Node> = NodeCast::from_ref(*owner);
+                owner == root
+            },
+            None => false,
+        }
+    }
+}
+
+/// The controls of the root that have the given id or name.
+#[jstraceable]
+struct NamedFormControlsFilter {
+    name: DOMString,
+}
+
+impl CollectionFilter for NamedFormControlsFilter {
+    fn filter(&self, elem: JSRef<Element>, root: JSRef<Node>) -> bool {
+        FormControlsFilter.filter(elem, root) &&
+            (elem.get_string_attribute("id") == self.name ||
+             elem.get_string_attribute("name") == self.name)
+    }
+}
+
+#[jstraceable]
+#[must_root]
+pub struct HTMLFormControlsCollection {
+    collection: HTMLCollection,
+    form: JS<HTMLFormElement>,
+}
+
+impl HTMLFormControlsCollection {
+    fn new_inherited(form: JSRef<HTMLFormElement>) -> HTMLFormControlsCollection {
+        // FIXME: Controls outside of the form that are associated with it
+        // through their form attribute are missing.
+        let node: JSRef<Node> = NodeCast::from_ref(form);
+        HTMLFormControlsCollection {
+            collection: HTMLCollection::new_inherited(Live(JS::from_rooted(node),
+                                                           box FormControlsFilter)),
+            form: JS::from_rooted(form),
+        }
+    }
+
+    pub fn new(window: JSRef<Window>,
+               form: JSRef<HTMLFormElement>) -> Temporary<HTMLFormControlsCollection> {
+        reflect_dom_object(box HTMLFormControlsCollection::new_inherited(form),
+                           &global::Window(window), HTMLFormControlsCollectionBinding::Wrap)
+    }
+}
+
+impl<'a> HTMLFormControlsCollectionMethods for JSRef<'a, HTMLFormControlsCollection> {
+    // http://www.whatwg.org/html/#dom-htmlformcontrolscollection-nameditem
+    fn NamedItem(self, name: DOMString) -> Option<RadioNodeListOrElement> {
+        // Step 1.
+        if name.is_empty() {
+            return None;
+        }
+
+        // Steps 2-4.
+        let form = self.form.root();
+        let root: JSRef<Node> = NodeCast::from_ref(*form);
+        let filter = NamedFormControlsFilter { name: name.clone() };
+        {
+            let mut matches = root.traverse_preorder()
+                                  .filter_map(|node| ElementCast::to_ref(node))
+                                  .filter(|elem| filter.filter(*elem, root));
+            match (matches.next(), matches.next()) {
+                (None, _) => return None,
+                (Some(elem), None) => return Some(eElement(JS::from_rooted(elem))),
+                _ => (),
+            }
+        }
+
+        // Step 5.
+        let window = window_from_node(*form).root();
+        let list = RadioNodeList::new(*window, root, box filter).root();
+        Some(eRadioNodeList(JS::from_rooted(*list)))
+    }
+
+    fn IndexedGetter(self, index: u32, found: &mut bool) -> Option<Temporary<Element>> {
+        let collection: JSRef<HTMLCollection> = HTMLCollectionCast::from_ref(self);
+        collection.IndexedGetter(index, found)
+    }
+
+    fn NamedGetter(self, name: DOMString, found: &mut bool) -> Option<RadioNodeListOrElement> {
+        let item = self.NamedItem(name);
+        *found = item.is_some();
+        item
+    }
+}
+
+impl Reflectable for HTMLFormControlsCollection {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        self.collection.reflector()
+    }
+}
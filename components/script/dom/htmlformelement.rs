@@ -2,17 +2,37 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
+use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
 use dom::bindings::codegen::Bindings::HTMLFormElementBinding;
-use dom::bindings::codegen::InheritTypes::HTMLFormElementDerived;
-use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::codegen::Bindings::HTMLFormElementBinding::HTMLFormElementMethods;
+use dom::bindings::codegen::Bindings::HTMLInputElementBinding::HTMLInputElementMethods;
+use dom::bindings::codegen::Bindings::HTMLOptionElementBinding::HTMLOptionElementMethods;
+use dom::bindings::codegen::Bindings::HTMLTextAreaElementBinding::HTMLTextAreaElementMethods;
+use dom::bindings::codegen::InheritTypes::{ElementCast, EventTargetCast, HTMLFormElementCast};
+use dom::bindings::codegen::InheritTypes::{HTMLFormElementDerived, HTMLInputElementCast};
+use dom::bindings::codegen::InheritTypes::{HTMLOptionElementCast, HTMLOutputElementCast};
+use dom::bindings::codegen::InheritTypes::{HTMLTextAreaElementCast, NodeCast};
+use dom::bindings::global;
+use dom::bindings::js::{JSRef, Temporary, OptionalRootable};
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
-use dom::element::HTMLFormElementTypeId;
-use dom::eventtarget::{EventTarget, NodeTargetTypeId};
+use dom::element::{AttributeHandlers, Element, HTMLFormElementTypeId};
+use dom::element::{HTMLButtonElementTypeId, HTMLFieldSetElementTypeId, HTMLInputElementTypeId};
+use dom::element::{HTMLObjectElementTypeId, HTMLOutputElementTypeId, HTMLSelectElementTypeId};
+use dom::element::{HTMLDataListElementTypeId, HTMLTextAreaElementTypeId};
+use dom::event::Event;
+use dom::eventtarget::{EventTarget, EventTargetHelpers, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
-use dom::node::{Node, ElementNodeTypeId};
+use dom::htmlformcontrolscollection::HTMLFormControlsCollection;
+use dom::htmlinputelement::HTMLInputElementHelpers;
+use dom::htmloutputelement::HTMLOutputElementHelpers;
+use dom::htmltextareaelement::HTMLTextAreaElementHelpers;
+use dom::node::{Node, NodeHelpers, ElementNodeTypeId, document_from_node, window_from_node};
 use servo_util::str::DOMString;
 
+use std::ascii::StrAsciiExt;
+
 #[jstraceable]
 #[must_root]
 pub struct HTMLFormElement {
@@ -39,6 +59,219 @@ impl HTMLFormElement {
     }
 }
 
+/// An entry of the entry list of a form.
+pub struct FormEntry {
+    pub name: DOMString,
+    pub value: FormEntryValue,
+}
+
+pub enum FormEntryValue {
+    StringEntry(DOMString),
+    /// The entry of a file input without selected files, which is an empty
+    /// file.
+    EmptyFileEntry,
+}
+
+pub trait HTMLFormElementHelpers {
+    fn reset(self);
+    fn entry_list(self) -> Vec<FormEntry>;
+}
+
+/// Returns whether `node` is an element whose form owner is `form`.
+fn is_owned_by(node: JSRef<Node>, form: JSRef<HTMLFormElement>) -> bool {
+    match ElementCast::to_ref(node) {
+        Some(element) => match element.form_owner().root() {
+            Some(owner) => *owner == form,
+            None => false,
+        },
+        None => false,
+    }
+}
+
+impl<'a> HTMLFormElementHelpers for JSRef<'a, HTMLFormElement> {
+    // http://www.whatwg.org/html/#concept-form-reset
+    fn reset(self) {
+        // Step 1.
+        let window = window_from_node(self).root();
+        let event = Event::new(&global::Window(*window), "reset".to_string(),
+                               true, true).root();
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        let _ = target.dispatch_event_with_target(None, *event);
+        if event.DefaultPrevented() {
+            return;
+        }
+
+        // Step 2.
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        let root = node.ancestors().last().unwrap_or(node);
+        for candidate in root.traverse_preorder() {
+            if !is_owned_by(candidate, self) {
+                continue;
+            }
+
+            match candidate.type_id() {
+                ElementNodeTypeId(HTMLInputElementTypeId) => {
+                    HTMLInputElementCast::to_ref(candidate).unwrap().reset();
+                },
+                ElementNodeTypeId(HTMLOutputElementTypeId) => {
+                    HTMLOutputElementCast::to_ref(candidate).unwrap().reset();
+                },
+                ElementNodeTypeId(HTMLTextAreaElementTypeId) => {
+                    HTMLTextAreaElementCast::to_ref(candidate).unwrap().reset();
+                },
+                _ => (),
+            }
+        }
+    }
+
+    // http://www.whatwg.org/html/#constructing-the-form-data-set
+    fn entry_list(self) -> Vec<FormEntry> {
+        // Step 2.
+        let mut entries = vec!();
+        let string_entry = |name: DOMString, value: DOMString| {
+            FormEntry { name: name, value: StringEntry(value) }
+        };
+
+        // Step 3.
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        let root = node.ancestors().last().unwrap_or(node);
+        for candidate in root.traverse_preorder() {
+            if !is_owned_by(candidate, self) {
+                continue;
+            }
+
+            // Step 3.1.
+            if candidate.get_disabled_state() ||
+               candidate.ancestors().any(|ancestor| {
+                   ancestor.type_id() == ElementNodeTypeId(HTMLDataListElementTypeId)
+               }) {
+                continue;
+            }
+
+            // Step 3.4.
+            let element: JSRef<Element> = ElementCast::to_ref(candidate).unwrap();
+            let name = element.get_string_attribute("name");
+            if name.is_empty() {
+                continue;
+            }
+
+            match candidate.type_id() {
+                ElementNodeTypeId(HTMLInputElementTypeId) => {
+                    let input = HTMLInputElementCast::to_ref(candidate).unwrap();
+                    match input.Type().as_slice() {
+                        // Steps 3.1 and 3.3: there is no submitter, and only
+                        // the submitter of a form is part of its entry list.
+                        "button" | "image" | "reset" | "submit" => {},
+                        // Step 3.2.
+                        "checkbox" | "radio" if !input.Checked() => {},
+                        // Step 3.7. FIXME: there is no file selection yet.
+                        "file" => entries.push(FormEntry { name: name, value: EmptyFileEntry }),
+                        // Step 3.8.
+                        "hidden" if name.as_slice().eq_ignore_ascii_case("_charset_") => {
+                            entries.push(string_entry(name, "UTF-8".to_string()));
+                        },
+                        _ => entries.push(string_entry(name, input.Value())),
+                    }
+                },
+                // Step 3.5.
+                ElementNodeTypeId(HTMLSelectElementTypeId) => {
+                    // FIXME: option elements only have their default
+                    // selectedness, and a single select without selected
+                    // options displays its first one.
+                    let options: Vec<JSRef<Node>> = candidate.traverse_preorder().filter(|option| {
+                        HTMLOptionElementCast::to_ref(*option).is_some() && !option.get_disabled_state()
+                    }).collect();
+                    let mut selected: Vec<&JSRef<Node>> = options.iter().filter(|option| {
+                        let option: JSRef<Element> = ElementCast::to_ref(**option).unwrap();
+                        option.has_attribute("selected")
+                    }).collect();
+                    if selected.is_empty() && !element.has_attribute("multiple") {
+                        selected = options.iter().take(1).collect();
+                    }
+                    for option in selected.into_iter() {
+                        let option_element: JSRef<Element> = ElementCast::to_ref(*option).unwrap();
+                        let value = if option_element.has_attribute("value") {
+                            option_element.get_string_attribute("value")
+                        } else {
+                            HTMLOptionElementCast::to_ref(*option).unwrap().Text()
+                        };
+                        entries.push(string_entry(name.clone(), value));
+                    }
+                },
+                // Step 3.9.
+                ElementNodeTypeId(HTMLTextAreaElementTypeId) => {
+                    let textarea = HTMLTextAreaElementCast::to_ref(candidate).unwrap();
+                    entries.push(string_entry(name, textarea.Value()));
+                },
+                // Buttons are only part of the entry list as the submitter,
+                // and objects don't have plugins to ask for an entry.
+                _ => (),
+            }
+        }
+
+        // Step 4.
+        entries
+    }
+}
+
+impl<'a> HTMLFormElementMethods for JSRef<'a, HTMLFormElement> {
+    // http://www.whatwg.org/html/#dom-form-elements
+    fn Elements(self) -> Temporary<HTMLFormControlsCollection> {
+        let window = window_from_node(self).root();
+        HTMLFormControlsCollection::new(*window, self)
+    }
+
+    // http://www.whatwg.org/html/#dom-form-reset
+    fn Reset(self) {
+        self.reset();
+    }
+}
+
+pub trait FormControlHelpers {
+    fn is_listed_element(self) -> bool;
+    fn form_owner(self) -> Option<Temporary<HTMLFormElement>>;
+}
+
+impl<'a> FormControlHelpers for JSRef<'a, Element> {
+    // http://www.whatwg.org/html/#category-listed
+    fn is_listed_element(self) -> bool {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        match node.type_id() {
+            // Image buttons are excluded from the form's elements for
+            // historical reasons.
+            ElementNodeTypeId(HTMLInputElementTypeId) => {
+                !self.get_string_attribute("type").as_slice().eq_ignore_ascii_case("image")
+            },
+            ElementNodeTypeId(HTMLButtonElementTypeId) |
+            ElementNodeTypeId(HTMLFieldSetElementTypeId) |
+            ElementNodeTypeId(HTMLObjectElementTypeId) |
+            ElementNodeTypeId(HTMLOutputElementTypeId) |
+            ElementNodeTypeId(HTMLSelectElementTypeId) |
+            ElementNodeTypeId(HTMLTextAreaElementTypeId) => true,
+            _ => false,
+        }
+    }
+
+    // http://www.whatwg.org/html/#form-owner
+    fn form_owner(self) -> Option<Temporary<HTMLFormElement>> {
+        // An explicit form attribute wins, even if it doesn't name a form.
+        if self.has_attribute("form") {
+            let document = document_from_node(self).root();
+            let owner = document.GetElementById(self.get_string_attribute("form")).root();
+            return match owner {
+                Some(owner) => HTMLFormElementCast::to_ref(*owner).map(Temporary::from_rooted),
+                None => None,
+            };
+        }
+
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        node.ancestors()
+            .filter_map(|ancestor| HTMLFormElementCast::to_ref(ancestor))
+            .next()
+            .map(Temporary::from_rooted)
+    }
+}
+
 impl Reflectable for HTMLFormElement {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.htmlelement.reflector()
@@ -2,7 +2,9 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
 use dom::bindings::codegen::Bindings::HTMLFrameSetElementBinding;
+use dom::bindings::codegen::Bindings::HTMLFrameSetElementBinding::HTMLFrameSetElementMethods;
 use dom::bindings::codegen::InheritTypes::HTMLFrameSetElementDerived;
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector};
@@ -39,6 +41,21 @@ impl HTMLFrameSetElement {
     }
 }
 
+impl<'a> HTMLFrameSetElementMethods for JSRef<'a, HTMLFrameSetElement> {
+    window_owned_event_handler!(afterprint, GetOnafterprint, SetOnafterprint)
+    window_owned_event_handler!(beforeprint, GetOnbeforeprint, SetOnbeforeprint)
+    window_owned_event_handler!(beforeunload, GetOnbeforeunload, SetOnbeforeunload)
+    window_owned_event_handler!(hashchange, GetOnhashchange, SetOnhashchange)
+    window_owned_event_handler!(message, GetOnmessage, SetOnmessage)
+    window_owned_event_handler!(offline, GetOnoffline, SetOnoffline)
+    window_owned_event_handler!(online, GetOnonline, SetOnonline)
+    window_owned_event_handler!(pagehide, GetOnpagehide, SetOnpagehide)
+    window_owned_event_handler!(pageshow, GetOnpageshow, SetOnpageshow)
+    window_owned_event_handler!(popstate, GetOnpopstate, SetOnpopstate)
+    window_owned_event_handler!(storage, GetOnstorage, SetOnstorage)
+    window_owned_event_handler!(unload, GetOnunload, SetOnunload)
+}
+
 impl Reflectable for HTMLFrameSetElement {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.htmlelement.reflector()
@@ -5,17 +5,18 @@
 use dom::attr::AttrHelpers;
 use dom::bindings::codegen::Bindings::HTMLIFrameElementBinding;
 use dom::bindings::codegen::Bindings::HTMLIFrameElementBinding::HTMLIFrameElementMethods;
+use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use dom::bindings::codegen::InheritTypes::{NodeCast, ElementCast};
 use dom::bindings::codegen::InheritTypes::{HTMLElementCast, HTMLIFrameElementDerived};
-use dom::bindings::js::{JSRef, Temporary, OptionalRootable};
+use dom::bindings::js::{JS, JSRef, Temporary, OptionalRootable, OptionalSettable};
 use dom::bindings::trace::Traceable;
 use dom::bindings::utils::{Reflectable, Reflector};
-use dom::document::Document;
+use dom::document::{Document, DocumentHelpers};
 use dom::element::{HTMLIFrameElementTypeId, Element};
 use dom::element::AttributeHandlers;
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
-use dom::node::{Node, NodeHelpers, ElementNodeTypeId, window_from_node};
+use dom::node::{Node, NodeHelpers, ElementNodeTypeId, document_from_node, window_from_node};
 use dom::virtualmethods::VirtualMethods;
 use dom::window::Window;
 use page::IterablePage;
@@ -47,6 +48,9 @@ pub struct HTMLIFrameElement {
     pub htmlelement: HTMLElement,
     pub size: Traceable<Cell<Option<IFrameSize>>>,
     pub sandbox: Traceable<Cell<Option<u8>>>,
+    /// The window of the document that the nested browsing context shows, if
+    /// one was loaded in this task.
+    content_window: Cell<Option<JS<Window>>>,
 }
 
 impl HTMLIFrameElementDerived for EventTarget {
@@ -66,6 +70,8 @@ pub trait HTMLIFrameElementHelpers {
     fn get_url(self) -> Option<Url>;
     /// http://www.whatwg.org/html/#process-the-iframe-attributes
     fn process_the_iframe_attributes(self);
+    fn content_window(self) -> Option<Temporary<Window>>;
+    fn set_content_window(self, window: Option<JSRef<Window>>);
 }
 
 impl<'a> HTMLIFrameElementHelpers for JSRef<'a, HTMLIFrameElement> {
@@ -112,6 +118,17 @@ impl<'a> HTMLIFrameElementHelpers for JSRef<'a, HTMLIFrameElement> {
         let ConstellationChan(ref chan) = *page.constellation_chan.deref();
         chan.send(LoadIframeUrlMsg(url, page.id, subpage_id, sandboxed));
     }
+
+    fn content_window(self) -> Option<Temporary<Window>> {
+        self.content_window.get().map(|window| Temporary::new(window))
+    }
+
+    /// Makes `window` the one the nested browsing context shows, once a
+    /// document was loaded in it, or forgets the last one once its page
+    /// left this task.
+    fn set_content_window(self, window: Option<JSRef<Window>>) {
+        self.content_window.assign(window);
+    }
 }
 
 impl HTMLIFrameElement {
@@ -120,6 +137,7 @@ impl HTMLIFrameElement {
             htmlelement: HTMLElement::new_inherited(HTMLIFrameElementTypeId, localName, document),
             size: Traceable::new(Cell::new(None)),
             sandbox: Traceable::new(Cell::new(None)),
+            content_window: Cell::new(None),
         }
     }
 
@@ -151,18 +169,23 @@ impl<'a> HTMLIFrameElementMethods for JSRef<'a, HTMLIFrameElement> {
         element.set_string_attribute("sandbox", sandbox);
     }
 
+    // The window is wrapped in the WindowProxy of the nested browsing
+    // context when it is handed to script, so this stays the same object
+    // when the iframe navigates.
     fn GetContentWindow(self) -> Option<Temporary<Window>> {
-        self.size.deref().get().and_then(|size| {
-            let window = window_from_node(self).root();
-            let children = window.deref().page.children.deref().borrow();
-            let child = children.iter().find(|child| {
-                child.subpage_id.unwrap() == size.subpage_id
-            });
-            child.and_then(|page| {
-                page.frame.deref().borrow().as_ref().map(|frame| {
-                    Temporary::new(frame.window.clone())
-                })
-            })
+        self.content_window()
+    }
+
+    // http://www.whatwg.org/html/#dom-iframe-contentdocument
+    fn GetContentDocument(self) -> Option<Temporary<Document>> {
+        self.GetContentWindow().root().and_then(|window| {
+            let document = window.Document().root();
+            let owner = document_from_node(self).root();
+            if owner.origin().same_origin_domain(&document.origin()) {
+                Some(Temporary::from_rooted(*document))
+            } else {
+                None
+            }
         })
     }
 }
@@ -3,27 +3,36 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::attr::AttrValue;
+use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
 use dom::bindings::codegen::Bindings::HTMLImageElementBinding;
 use dom::bindings::codegen::Bindings::HTMLImageElementBinding::HTMLImageElementMethods;
+use dom::bindings::codegen::Bindings::MouseEventBinding::MouseEventMethods;
 use dom::bindings::codegen::InheritTypes::{NodeCast, ElementCast, HTMLElementCast, HTMLImageElementDerived};
-use dom::bindings::js::{JS, JSRef, Temporary};
+use dom::bindings::codegen::InheritTypes::{EventCast, EventTargetCast, HTMLMapElementCast, MouseEventCast};
+use dom::bindings::codegen::InheritTypes::{HTMLPictureElementCast, HTMLSourceElementCast};
+use dom::bindings::js::{JS, JSRef, Temporary, OptionalRootable};
 use dom::bindings::trace::Untraceable;
 use dom::bindings::utils::{Reflectable, Reflector};
-use dom::document::Document;
+use dom::document::{Document, DocumentHelpers, ImageRequestCompleted, ImageRequestPending};
 use dom::element::{Element, HTMLImageElementTypeId};
 use dom::element::AttributeHandlers;
-use dom::eventtarget::{EventTarget, NodeTargetTypeId};
+use dom::event::Event;
+use dom::eventtarget::{EventTarget, EventTargetHelpers, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
+use dom::htmlmapelement::{HTMLMapElement, HTMLMapElementHelpers};
+use dom::mouseevent::MouseEvent;
 use dom::node::{Node, ElementNodeTypeId, NodeHelpers, window_from_node};
 use dom::virtualmethods::VirtualMethods;
-use servo_net::image_cache_task;
+use dom::window::{Window, WindowHelpers};
+use servo_net::image::base::is_supported_mime_type;
 use servo_util::atom::Atom;
-use servo_util::geometry::to_px;
-use servo_util::str::DOMString;
+use servo_util::geometry::{to_frac_px, to_px};
+use servo_util::str::{DOMString, ImageCandidate, PixelDensity, Width, parse_srcset};
 
 use url::{Url, UrlParser};
 
 use std::cell::RefCell;
+use std::mem;
 
 #[jstraceable]
 #[must_root]
@@ -38,37 +47,230 @@ impl HTMLImageElementDerived for EventTarget {
     }
 }
 
+pub trait HTMLImageElementHelpers {
+    fn image_request_completed(self, url: &Url, succeeded: bool);
+    fn reselect_image_source(self);
+}
+
+impl<'a> HTMLImageElementHelpers for JSRef<'a, HTMLImageElement> {
+    /// Fires a load or error event at the element once its document got the
+    /// image at `url`, unless the element has moved on to another image.
+    fn image_request_completed(self, url: &Url, succeeded: bool) {
+        if self.image.deref().borrow().as_ref() != Some(url) {
+            return;
+        }
+        let window = window_from_node(self).root();
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        let type_ = if succeeded { "load" } else { "error" };
+        window.queue_simple_event(target, type_.to_string());
+    }
+
+    /// Chooses the image again after a change to the parent picture element or
+    /// to the viewport, and requests it if the choice changed.
+    /// http://www.whatwg.org/html/#relevant-mutations
+    fn reselect_image_source(self) {
+        let img_url = self.select_image_source();
+        if *self.image.deref().borrow() != img_url {
+            self.set_image(img_url);
+        }
+    }
+}
+
+/// Picks the image candidate with the lowest pixel density that is still at
+/// least the device pixel ratio, or else the one with the highest density.
+/// http://www.whatwg.org/html/#select-an-image-source
+fn choose_image_candidate(window: JSRef<Window>, candidates: Vec<ImageCandidate>)
+                          -> Option<DOMString> {
+    let window_size = window.page().window_size.deref().get();
+    let viewport_width = window_size.initial_viewport.width.get() as f64;
+    let device_pixel_ratio = window_size.device_pixel_ratio.get() as f64;
+
+    let mut best: Option<(f64, DOMString)> = None;
+    for candidate in candidates.into_iter() {
+        // FIXME: Widths should be relative to the sizes attribute rather than
+        // to the viewport.
+        let density = match candidate.descriptor {
+            PixelDensity(density) => density,
+            Width(width) => width as f64 / viewport_width,
+        };
+        let is_better = match best {
+            None => true,
+            Some((best_density, _)) if best_density >= device_pixel_ratio => {
+                density >= device_pixel_ratio && density < best_density
+            },
+            Some((best_density, _)) => density > best_density,
+        };
+        if is_better {
+            best = Some((density, candidate.url));
+        }
+    }
+    best.map(|(_, url)| url)
+}
+
 trait PrivateHTMLImageElementHelpers {
-    fn update_image(self, value: Option<(DOMString, &Url)>);
+    fn select_image_source(self) -> Option<Url>;
+    fn set_image(self, img_url: Option<Url>);
+    fn update_image(self);
+    fn get_image_map(self) -> Option<Temporary<HTMLMapElement>>;
+    fn handle_event_impl(self, event: JSRef<Event>);
 }
 
 impl<'a> PrivateHTMLImageElementHelpers for JSRef<'a, HTMLImageElement> {
-    /// Makes the local `image` member match the status of the `src` attribute and starts
-    /// prefetching the image. This method must be called after `src` is changed.
-    fn update_image(self, value: Option<(DOMString, &Url)>) {
+    /// Returns the URL of the image to show, taken from the first source
+    /// element of the parent picture element that applies, or else from the
+    /// `srcset` and `src` attributes.
+    /// http://www.whatwg.org/html/#select-an-image-source
+    fn select_image_source(self) -> Option<Url> {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        let window = window_from_node(self).root();
+
+        let mut src = None;
+        match node.parent_node().root() {
+            Some(ref parent) if HTMLPictureElementCast::to_ref(**parent).is_some() => {
+                let sources = parent.children()
+                                    .take_while(|child| *child != node)
+                                    .filter_map(|child| HTMLSourceElementCast::to_ref(child));
+                for source in sources {
+                    let source: JSRef<Element> = ElementCast::from_ref(source);
+                    if !source.has_attribute("srcset") {
+                        continue;
+                    }
+                    if source.has_attribute("media") &&
+                       !window.media_matches(source.get_string_attribute("media").as_slice()) {
+                        continue;
+                    }
+                    if source.has_attribute("type") &&
+                       !is_supported_mime_type(source.get_string_attribute("type").as_slice()) {
+                        continue;
+                    }
+                    let candidates = parse_srcset(source.get_string_attribute("srcset").as_slice());
+                    src = choose_image_candidate(*window, candidates);
+                    if src.is_some() {
+                        break;
+                    }
+                }
+            },
+            _ => (),
+        }
+
+        if src.is_none() && element.has_attribute("srcset") {
+            let candidates = parse_srcset(element.get_string_attribute("srcset").as_slice());
+            src = choose_image_candidate(*window, candidates);
+        }
+        if src.is_none() && element.has_attribute("src") {
+            src = Some(element.get_string_attribute("src"));
+        }
+
+        // An inert document, such as an XMLHttpRequest response, resolves
+        // against its own URL rather than the window's.
+        let document = node.owner_doc().root();
+        let base_url = if document.has_browsing_context() {
+            window.get_url()
+        } else {
+            document.url().clone()
+        };
+        src.and_then(|src| {
+            UrlParser::new().base_url(&base_url).parse(src.as_slice()).ok()
+        })
+    }
+
+    /// Makes the local `image` member point to `img_url` and requests the
+    /// image from the document, which may let go of the previous one.
+    fn set_image(self, img_url: Option<Url>) {
         let node: JSRef<Node> = NodeCast::from_ref(self);
         let document = node.owner_doc().root();
-        let window = document.deref().window.root();
-        let image_cache = &window.image_cache_task;
-        match value {
-            None => {
-                *self.image.deref().borrow_mut() = None;
-            }
-            Some((src, base_url)) => {
-                let img_url = UrlParser::new().base_url(base_url).parse(src.as_slice());
-                // FIXME: handle URL parse errors more gracefully.
-                let img_url = img_url.unwrap();
-                *self.image.deref().borrow_mut() = Some(img_url.clone());
-
-                // inform the image cache to load this, but don't store a
-                // handle.
-                //
-                // TODO (Issue #84): don't prefetch if we are within a
-                // <noscript> tag.
-                image_cache.send(image_cache_task::Prefetch(img_url));
+        let old_url = mem::replace(&mut *self.image.deref().borrow_mut(), img_url.clone());
+        match old_url {
+            Some(ref old_url) if Some(old_url) != img_url.as_ref() => {
+                document.release_image(old_url, self)
+            },
+            _ => (),
+        }
+        match img_url {
+            None => (),
+            Some(img_url) => {
+                // Documents without a browsing context don't fetch images.
+                if !document.has_browsing_context() {
+                    return;
+                }
+                match document.request_image(img_url.clone(), self) {
+                    ImageRequestCompleted(succeeded) => {
+                        self.image_request_completed(&img_url, succeeded)
+                    }
+                    ImageRequestPending => (),
+                }
             }
         }
     }
+
+    /// Chooses and requests the image again. This method must be called after
+    /// `src`, `srcset` or `sizes` is changed.
+    fn update_image(self) {
+        let img_url = self.select_image_source();
+        self.set_image(img_url);
+    }
+
+    /// Returns the map element named by the `usemap` attribute, if any.
+    /// http://www.whatwg.org/html/#rules-for-parsing-a-hash-name-reference
+    fn get_image_map(self) -> Option<Temporary<HTMLMapElement>> {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        let usemap = element.get_string_attribute("usemap");
+        if !node.is_in_doc() || !usemap.as_slice().starts_with("#") {
+            return None;
+        }
+
+        let name = usemap.as_slice().slice_from(1);
+        let document = node.owner_doc().root();
+        let root: JSRef<Node> = NodeCast::from_ref(*document);
+        root.traverse_preorder()
+            .filter_map(|candidate| HTMLMapElementCast::to_ref(candidate))
+            .find(|map| {
+                let map: JSRef<Element> = ElementCast::from_ref(*map);
+                map.get_string_attribute("id").as_slice() == name ||
+                    map.get_string_attribute("name").as_slice() == name
+            })
+            .map(Temporary::from_rooted)
+    }
+
+    /// Forwards clicks on an image map to the area under the pointer.
+    /// http://www.whatwg.org/html/#image-map-processing-model
+    fn handle_event_impl(self, event: JSRef<Event>) {
+        if "click" != event.Type().as_slice() || event.DefaultPrevented() {
+            return;
+        }
+        let mouse_event: JSRef<MouseEvent> = match MouseEventCast::to_ref(event) {
+            Some(mouse_event) => mouse_event,
+            None => return,
+        };
+        let map = match self.get_image_map().root() {
+            Some(map) => map,
+            None => return,
+        };
+
+        // FIXME: This assumes the viewport isn't scrolled.
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        let rect = node.get_bounding_content_box();
+        let x = mouse_event.ClientX() as f64 - to_frac_px(rect.origin.x);
+        let y = mouse_event.ClientY() as f64 - to_frac_px(rect.origin.y);
+        match map.area_at(x, y).root() {
+            Some(area) => {
+                let window = window_from_node(self).root();
+                let area_event = MouseEvent::new(*window, "click".to_string(), true, true,
+                                                 Some(*window), 0,
+                                                 mouse_event.ScreenX(), mouse_event.ScreenY(),
+                                                 mouse_event.ClientX(), mouse_event.ClientY(),
+                                                 mouse_event.CtrlKey(), mouse_event.AltKey(),
+                                                 mouse_event.ShiftKey(), mouse_event.MetaKey(),
+                                                 mouse_event.Button(), None).root();
+                let area_event: JSRef<Event> = EventCast::from_ref(*area_event);
+                let target: JSRef<EventTarget> = EventTargetCast::from_ref(*area);
+                let _ = target.dispatch_event_with_target(None, area_event);
+            },
+            None => (),
+        }
+    }
 }
 
 impl HTMLImageElement {
@@ -111,6 +313,25 @@ impl<'a> HTMLImageElementMethods for JSRef<'a, HTMLImageElement> {
         element.set_url_attribute("src", src)
     }
 
+    make_getter!(Srcset)
+
+    fn SetSrcset(self, srcset: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("srcset", srcset)
+    }
+
+    make_getter!(Sizes)
+
+    fn SetSizes(self, sizes: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("sizes", sizes)
+    }
+
+    // http://www.whatwg.org/html/#dom-img-currentsrc
+    fn CurrentSrc(self) -> DOMString {
+        self.image.deref().borrow().as_ref().map_or("".to_string(), |url| url.serialize())
+    }
+
     make_getter!(UseMap)
 
     fn SetUseMap(self, use_map: DOMString) {
@@ -202,21 +423,21 @@ impl<'a> VirtualMethods for JSRef<'a, HTMLImageElement> {
             _ => (),
         }
 
-        if "src" == name.as_slice() {
-            let window = window_from_node(*self).root();
-            let url = window.deref().get_url();
-            self.update_image(Some((value, &url)));
+        match name.as_slice() {
+            "src" | "srcset" | "sizes" => self.update_image(),
+            _ => (),
         }
     }
 
-    fn before_remove_attr(&self, name: &Atom, value: DOMString) {
+    fn after_remove_attr(&self, name: &Atom) {
         match self.super_type() {
-            Some(ref s) => s.before_remove_attr(name, value.clone()),
+            Some(ref s) => s.after_remove_attr(name),
             _ => (),
         }
 
-        if "src" == name.as_slice() {
-            self.update_image(None);
+        match name.as_slice() {
+            "src" | "srcset" | "sizes" => self.update_image(),
+            _ => (),
         }
     }
 
@@ -226,6 +447,16 @@ impl<'a> VirtualMethods for JSRef<'a, HTMLImageElement> {
             _ => self.super_type().unwrap().parse_plain_attribute(name, value),
         }
     }
+
+    fn handle_event(&self, event: JSRef<Event>) {
+        match self.super_type() {
+            Some(s) => {
+                s.handle_event(event);
+            }
+            None => {}
+        }
+        self.handle_event_impl(event);
+    }
 }
 
 impl Reflectable for HTMLImageElement {
@@ -2,26 +2,58 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
+use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
 use dom::bindings::codegen::Bindings::HTMLInputElementBinding;
 use dom::bindings::codegen::Bindings::HTMLInputElementBinding::HTMLInputElementMethods;
-use dom::bindings::codegen::InheritTypes::{ElementCast, HTMLElementCast, NodeCast};
+use dom::bindings::codegen::Bindings::HTMLInputElementBinding::{SelectionMode, SelectionModeValues};
+use dom::bindings::codegen::InheritTypes::{ElementCast, EventTargetCast, HTMLElementCast, NodeCast};
+use dom::bindings::codegen::InheritTypes::HTMLInputElementCast;
 use dom::bindings::codegen::InheritTypes::{HTMLInputElementDerived, HTMLFieldSetElementDerived};
-use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::codegen::InheritTypes::HTMLDataListElementDerived;
+use dom::bindings::conversions::FromJSValConvertible;
+use dom::bindings::error::{ErrorResult, Fallible, InvalidState};
+use dom::bindings::js::{JSRef, Temporary, OptionalRootable};
+use dom::bindings::trace::Traceable;
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
-use dom::element::{AttributeHandlers, Element, HTMLInputElementTypeId};
+use dom::element::{AttributeHandlers, Element, ElementHelpers, HTMLInputElementTypeId};
+use dom::element::InCheckedState;
+use dom::event::Event;
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
+use dom::htmlformelement::FormControlHelpers;
+use dom::htmllabelelement::LabelableElementHelpers;
 use dom::node::{DisabledStateHelpers, Node, NodeHelpers, ElementNodeTypeId};
+use dom::node::{document_from_node, window_from_node};
+use dom::nodelist::NodeList;
 use dom::virtualmethods::VirtualMethods;
+use dom::window::WindowHelpers;
+use textinput::{SelectionDirection, SelectionNone, TextSelection, utf16_length};
 
 use servo_util::atom::Atom;
-use servo_util::str::DOMString;
+use servo_util::memory::HeapSizeOf;
+use servo_util::str::{DOMString, is_valid_floating_point_number};
+use servo_util::str::{parse_date_string, parse_time_string};
+
+use js::jsapi::{JSContext, JS_NewDateObjectMsec, JS_ObjectIsDate};
+use js::jsval::{JSVal, NullValue, ObjectValue};
+
+use std::ascii::StrAsciiExt;
+use std::cell::{Cell, RefCell};
+use std::f64;
 
 #[jstraceable]
 #[must_root]
 pub struct HTMLInputElement {
     pub htmlelement: HTMLElement,
+    checked: Cell<bool>,
+    checked_changed: Cell<bool>,
+    value: Traceable<RefCell<DOMString>>,
+    /// The dirty value flag: whether the value no longer reflects the value
+    /// content attribute.
+    value_changed: Cell<bool>,
+    selection: TextSelection,
 }
 
 impl HTMLInputElementDerived for EventTarget {
@@ -33,7 +65,12 @@ impl HTMLInputElementDerived for EventTarget {
 impl HTMLInputElement {
     fn new_inherited(localName: DOMString, document: JSRef<Document>) -> HTMLInputElement {
         HTMLInputElement {
-            htmlelement: HTMLElement::new_inherited(HTMLInputElementTypeId, localName, document)
+            htmlelement: HTMLElement::new_inherited(HTMLInputElementTypeId, localName, document),
+            checked: Cell::new(false),
+            checked_changed: Cell::new(false),
+            value: Traceable::new(RefCell::new("".to_string())),
+            value_changed: Cell::new(false),
+            selection: TextSelection::new(),
         }
     }
 
@@ -44,6 +81,419 @@ impl HTMLInputElement {
     }
 }
 
+static MS_PER_DAY: f64 = 86400000.;
+
+/// The types of input elements this implementation knows about.
+static INPUT_TYPES: &'static [&'static str] = &[
+    "hidden", "text", "search", "tel", "url", "email", "password", "date", "month", "week",
+    "time", "datetime-local", "number", "range", "color", "checkbox", "radio", "file",
+    "submit", "image", "reset", "button",
+];
+
+/// http://www.whatwg.org/html/#dom-input-value
+#[deriving(PartialEq)]
+enum ValueMode {
+    ValueModeValue,
+    ValueModeDefault,
+    ValueModeDefaultOn,
+    ValueModeFilename,
+}
+
+fn value_mode(input_type: &str) -> ValueMode {
+    match input_type {
+        "hidden" | "submit" | "image" | "reset" | "button" => ValueModeDefault,
+        "checkbox" | "radio" => ValueModeDefaultOn,
+        "file" => ValueModeFilename,
+        _ => ValueModeValue,
+    }
+}
+
+/// Whether the selection APIs apply to the given type.
+fn supports_selection(input_type: &str) -> bool {
+    match input_type {
+        "text" | "search" | "url" | "tel" | "password" => true,
+        _ => false,
+    }
+}
+
+/// Whether valueAsNumber and stepUp()/stepDown() apply to the given type.
+fn supports_numeric_value(input_type: &str) -> bool {
+    match input_type {
+        "number" | "range" | "date" | "time" => true,
+        _ => false,
+    }
+}
+
+/// Returns the number of days between 1970-01-01 and the given date of the
+/// proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The inverse of `days_from_civil`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = (if days >= 0 { days } else { days - 146096 }) / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 -
+                       day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + (if month <= 2 { 1 } else { 0 });
+    (year, month, day)
+}
+
+/// The algorithm to convert a string to a number for the given type, which
+/// yields milliseconds for dates and times.
+fn convert_string_to_number(input_type: &str, value: &str) -> Option<f64> {
+    match input_type {
+        "number" | "range" => {
+            if is_valid_floating_point_number(value) {
+                from_str::<f64>(value)
+            } else {
+                None
+            }
+        },
+        "date" => parse_date_string(value).map(|(year, month, day)| {
+            days_from_civil(year as i64, month as i64, day as i64) as f64 * MS_PER_DAY
+        }),
+        "time" => parse_time_string(value),
+        _ => None,
+    }
+}
+
+/// The algorithm to convert a number to a string for the given type.
+fn convert_number_to_string(input_type: &str, value: f64) -> Option<DOMString> {
+    match input_type {
+        "date" => {
+            let (year, month, day) = civil_from_days((value / MS_PER_DAY).floor() as i64);
+            if year < 1 {
+                None
+            } else {
+                Some(format!("{:04}-{:02}-{:02}", year, month, day))
+            }
+        },
+        "time" => {
+            let ms = (value % MS_PER_DAY + MS_PER_DAY) % MS_PER_DAY;
+            let ms = ms.round() as u32;
+            let (hours, minutes) = (ms / 3600000, ms / 60000 % 60);
+            let (seconds, millis) = (ms / 1000 % 60, ms % 1000);
+            Some(if millis != 0 {
+                format!("{:02}:{:02}:{:02}.{:03}", hours, minutes, seconds, millis)
+            } else if seconds != 0 {
+                format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+            } else {
+                format!("{:02}:{:02}", hours, minutes)
+            })
+        },
+        _ => Some(value.to_string()),
+    }
+}
+
+pub trait HTMLInputElementHelpers {
+    fn reset(self);
+    fn is_radio_button(self) -> bool;
+}
+
+impl<'a> HTMLInputElementHelpers for JSRef<'a, HTMLInputElement> {
+    // http://www.whatwg.org/html/#the-input-element:concept-form-reset-control
+    fn reset(self) {
+        let default_checked = self.DefaultChecked();
+        self.set_checkedness(default_checked);
+        self.checked_changed.set(false);
+        *self.value.deref().borrow_mut() = "".to_string();
+        self.value_changed.set(false);
+    }
+
+    fn is_radio_button(self) -> bool {
+        self.input_type().as_slice() == "radio"
+    }
+}
+
+trait PrivateHTMLInputElementHelpers {
+    fn input_type(self) -> DOMString;
+    fn number_attribute(self, name: &str) -> Option<f64>;
+    fn minimum(self) -> Option<f64>;
+    fn maximum(self) -> Option<f64>;
+    fn allowed_value_step(self) -> Option<f64>;
+    fn step_base(self) -> f64;
+    fn sanitize_value(self, value: DOMString) -> DOMString;
+    fn set_number_value(self, value: f64);
+    fn step(self, n: i32) -> ErrorResult;
+    fn check_selection(self) -> ErrorResult;
+    fn handle_event_impl(self, event: JSRef<Event>);
+    fn uncheck_radio_button_group(self);
+    fn set_checkedness(self, checked: bool);
+    fn update_checked_state(self);
+}
+
+impl<'a> PrivateHTMLInputElementHelpers for JSRef<'a, HTMLInputElement> {
+    // http://www.whatwg.org/html/#attr-input-type
+    fn input_type(self) -> DOMString {
+        let elem: JSRef<Element> = ElementCast::from_ref(self);
+        let input_type = elem.get_string_attribute("type").as_slice().to_ascii_lower();
+        if INPUT_TYPES.iter().any(|&known| known == input_type.as_slice()) {
+            input_type
+        } else {
+            "text".to_string()
+        }
+    }
+
+    fn number_attribute(self, name: &str) -> Option<f64> {
+        let elem: JSRef<Element> = ElementCast::from_ref(self);
+        let input_type = self.input_type();
+        convert_string_to_number(input_type.as_slice(), elem.get_string_attribute(name).as_slice())
+    }
+
+    // http://www.whatwg.org/html/#concept-input-min
+    fn minimum(self) -> Option<f64> {
+        match self.number_attribute("min") {
+            None if self.input_type().as_slice() == "range" => Some(0.),
+            min => min,
+        }
+    }
+
+    // http://www.whatwg.org/html/#concept-input-max
+    fn maximum(self) -> Option<f64> {
+        match self.number_attribute("max") {
+            None if self.input_type().as_slice() == "range" => Some(100.),
+            max => max,
+        }
+    }
+
+    // http://www.whatwg.org/html/#concept-input-step
+    fn allowed_value_step(self) -> Option<f64> {
+        let (default_step, scale) = match self.input_type().as_slice() {
+            "number" | "range" => (1., 1.),
+            "date" => (1., MS_PER_DAY),
+            "time" => (60., 1000.),
+            _ => return None,
+        };
+        let elem: JSRef<Element> = ElementCast::from_ref(self);
+        let step = elem.get_string_attribute("step");
+        if step.as_slice().eq_ignore_ascii_case("any") {
+            return None;
+        }
+        let step = if is_valid_floating_point_number(step.as_slice()) {
+            match from_str::<f64>(step.as_slice()) {
+                Some(step) if step > 0. => step,
+                _ => default_step,
+            }
+        } else {
+            default_step
+        };
+        Some(step * scale)
+    }
+
+    // http://www.whatwg.org/html/#concept-input-min-zero
+    fn step_base(self) -> f64 {
+        match self.number_attribute("min") {
+            Some(min) => min,
+            None => self.number_attribute("value").unwrap_or(0.),
+        }
+    }
+
+    // http://www.whatwg.org/html/#value-sanitization-algorithm
+    fn sanitize_value(self, value: DOMString) -> DOMString {
+        let input_type = self.input_type();
+        match input_type.as_slice() {
+            "text" | "search" | "tel" | "password" => {
+                value.as_slice().chars().filter(|&c| c != '\n' && c != '\r').collect()
+            },
+            "url" | "email" => {
+                let value: String = value.as_slice().chars().filter(|&c| c != '\n' && c != '\r').collect();
+                value.as_slice().trim().to_string()
+            },
+            "number" | "date" | "time" => {
+                match convert_string_to_number(input_type.as_slice(), value.as_slice()) {
+                    Some(_) => value,
+                    None => "".to_string(),
+                }
+            },
+            "range" => {
+                let min = self.minimum().unwrap();
+                let max = self.maximum().unwrap();
+                let max = if max < min { min } else { max };
+                // Values that aren't numbers become the default value, halfway
+                // between the minimum and the maximum.
+                let mut number = match convert_string_to_number("range", value.as_slice()) {
+                    Some(number) => number,
+                    None => min + (max - min) / 2.,
+                };
+                if number < min {
+                    number = min;
+                }
+                if number > max {
+                    number = max;
+                }
+                match self.allowed_value_step() {
+                    Some(step) => {
+                        // Round to the nearest step, preferring the larger one
+                        // when halfway, but without overflowing the maximum.
+                        let base = self.step_base();
+                        let mut snapped = base + ((number - base) / step + 0.5).floor() * step;
+                        if snapped > max {
+                            snapped -= step;
+                        }
+                        if snapped >= min {
+                            number = snapped;
+                        }
+                    },
+                    None => (),
+                }
+                number.to_string()
+            },
+            "color" => {
+                let valid = value.len() == 7 && value.as_slice().starts_with("#") &&
+                    value.as_slice().slice_from(1).chars().all(|c| c.is_digit_radix(16));
+                if valid {
+                    value.as_slice().to_ascii_lower()
+                } else {
+                    "#000000".to_string()
+                }
+            },
+            _ => value,
+        }
+    }
+
+    fn set_number_value(self, value: f64) {
+        let input_type = self.input_type();
+        let value = if value.is_finite() {
+            convert_number_to_string(input_type.as_slice(), value).unwrap_or("".to_string())
+        } else {
+            "".to_string()
+        };
+        let _ = self.SetValue(value);
+    }
+
+    // http://www.whatwg.org/html/#dom-input-stepup
+    fn step(self, n: i32) -> ErrorResult {
+        let input_type = self.input_type();
+        if !supports_numeric_value(input_type.as_slice()) {
+            return Err(InvalidState);
+        }
+        let step = match self.allowed_value_step() {
+            Some(step) => step,
+            None => return Err(InvalidState),
+        };
+        let (min, max) = (self.minimum(), self.maximum());
+        match (min, max) {
+            (Some(min), Some(max)) if min > max => return Ok(()),
+            _ => (),
+        }
+
+        let value_before_stepping =
+            convert_string_to_number(input_type.as_slice(), self.Value().as_slice()).unwrap_or(0.);
+        let base = self.step_base();
+        let steps = (value_before_stepping - base) / step;
+        let mut value = if steps != steps.floor() {
+            // Values that don't match the step snap to the nearest step in the
+            // requested direction instead of moving by n steps.
+            base + (if n > 0 { steps.ceil() } else { steps.floor() }) * step
+        } else {
+            value_before_stepping + step * n as f64
+        };
+
+        match min {
+            Some(min) if value < min => value = base + ((min - base) / step).ceil() * step,
+            _ => (),
+        }
+        match max {
+            Some(max) if value > max => value = base + ((max - base) / step).floor() * step,
+            _ => (),
+        }
+
+        if (n < 0 && value > value_before_stepping) || (n > 0 && value < value_before_stepping) {
+            return Ok(());
+        }
+        self.set_number_value(value);
+        Ok(())
+    }
+
+    /// Throws if the selection APIs don't apply to this input, and otherwise
+    /// keeps the selection within the current value.
+    fn check_selection(self) -> ErrorResult {
+        if !supports_selection(self.input_type().as_slice()) {
+            return Err(InvalidState);
+        }
+        self.selection.clamp(utf16_length(self.Value().as_slice()));
+        Ok(())
+    }
+
+    fn handle_event_impl(self, event: JSRef<Event>) {
+        // The activation behavior of checkboxes toggles their checkedness,
+        // while radio buttons can only become checked.
+        if "click" != event.Type().as_slice() || event.DefaultPrevented() {
+            return;
+        }
+        match self.input_type().as_slice() {
+            "checkbox" => {
+                let checked = self.Checked();
+                self.SetChecked(!checked);
+            },
+            "radio" => self.SetChecked(true),
+            _ => ()
+        }
+    }
+
+    /// Unchecks the other radio buttons in the group of this one, so that at
+    /// most one of them is checked.
+    /// http://www.whatwg.org/html/#radio-button-group
+    fn uncheck_radio_button_group(self) {
+        let elem: JSRef<Element> = ElementCast::from_ref(self);
+        let name = elem.get_string_attribute("name");
+        if !self.is_radio_button() || name.is_empty() {
+            return;
+        }
+
+        let owner = elem.form_owner().root();
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        let tree_root = node.ancestors().last().unwrap_or(node);
+        let group = tree_root.traverse_preorder()
+            .filter_map(|node| HTMLInputElementCast::to_ref(node))
+            .filter(|&other| {
+                if other == self || !other.is_radio_button() {
+                    return false;
+                }
+                let other_elem: JSRef<Element> = ElementCast::from_ref(other);
+                if other_elem.get_string_attribute("name") != name {
+                    return false;
+                }
+                let other_owner = other_elem.form_owner().root();
+                match (&owner, &other_owner) {
+                    (&Some(ref owner), &Some(ref other_owner)) => **owner == **other_owner,
+                    (&None, &None) => true,
+                    _ => false,
+                }
+            });
+        for other in group {
+            other.set_checkedness(false);
+        }
+    }
+
+    fn set_checkedness(self, checked: bool) {
+        self.checked.set(checked);
+        self.update_checked_state();
+    }
+
+    /// Only checkboxes and radio buttons match :checked.
+    fn update_checked_state(self) {
+        let elem: JSRef<Element> = ElementCast::from_ref(self);
+        let checkable = match self.input_type().as_slice() {
+            "checkbox" | "radio" => true,
+            _ => false,
+        };
+        elem.set_state(InCheckedState, checkable && self.checked.get());
+    }
+}
+
 impl<'a> HTMLInputElementMethods for JSRef<'a, HTMLInputElement> {
     // http://www.whatwg.org/html/#dom-fe-disabled
     make_bool_getter!(Disabled)
@@ -53,6 +503,299 @@ impl<'a> HTMLInputElementMethods for JSRef<'a, HTMLInputElement> {
         let elem: JSRef<Element> = ElementCast::from_ref(self);
         elem.set_bool_attribute("disabled", disabled)
     }
+
+    // http://www.whatwg.org/html/#dom-input-defaultchecked
+    fn DefaultChecked(self) -> bool {
+        let elem: JSRef<Element> = ElementCast::from_ref(self);
+        elem.has_attribute("checked")
+    }
+
+    // http://www.whatwg.org/html/#dom-input-defaultchecked
+    fn SetDefaultChecked(self, default_checked: bool) {
+        let elem: JSRef<Element> = ElementCast::from_ref(self);
+        elem.set_bool_attribute("checked", default_checked)
+    }
+
+    // http://www.whatwg.org/html/#dom-input-checked
+    fn Checked(self) -> bool {
+        self.checked.get()
+    }
+
+    // http://www.whatwg.org/html/#dom-input-checked
+    fn SetChecked(self, checked: bool) {
+        self.set_checkedness(checked);
+        self.checked_changed.set(true);
+        if checked {
+            self.uncheck_radio_button_group();
+        }
+    }
+
+    // http://www.whatwg.org/html/#dom-input-type
+    fn Type(self) -> DOMString {
+        self.input_type()
+    }
+
+    // http://www.whatwg.org/html/#dom-input-type
+    fn SetType(self, input_type: DOMString) {
+        let elem: JSRef<Element> = ElementCast::from_ref(self);
+        elem.set_string_attribute("type", input_type)
+    }
+
+    // http://www.whatwg.org/html/#dom-input-defaultvalue
+    fn DefaultValue(self) -> DOMString {
+        let elem: JSRef<Element> = ElementCast::from_ref(self);
+        elem.get_string_attribute("value")
+    }
+
+    // http://www.whatwg.org/html/#dom-input-defaultvalue
+    fn SetDefaultValue(self, default_value: DOMString) {
+        let elem: JSRef<Element> = ElementCast::from_ref(self);
+        elem.set_string_attribute("value", default_value)
+    }
+
+    // http://www.whatwg.org/html/#dom-input-value
+    fn Value(self) -> DOMString {
+        let elem: JSRef<Element> = ElementCast::from_ref(self);
+        match value_mode(self.input_type().as_slice()) {
+            ValueModeValue => {
+                let value = if self.value_changed.get() {
+                    self.value.deref().borrow().clone()
+                } else {
+                    elem.get_string_attribute("value")
+                };
+                self.sanitize_value(value)
+            },
+            ValueModeDefault => elem.get_string_attribute("value"),
+            ValueModeDefaultOn => {
+                if elem.has_attribute("value") {
+                    elem.get_string_attribute("value")
+                } else {
+                    "on".to_string()
+                }
+            },
+            // FIXME: there is no file selection yet.
+            ValueModeFilename => "".to_string(),
+        }
+    }
+
+    // http://www.whatwg.org/html/#dom-input-value
+    fn SetValue(self, value: DOMString) -> ErrorResult {
+        let elem: JSRef<Element> = ElementCast::from_ref(self);
+        match value_mode(self.input_type().as_slice()) {
+            ValueModeValue => {
+                let value = self.sanitize_value(value);
+                self.selection.clamp(utf16_length(value.as_slice()));
+                *self.value.deref().borrow_mut() = value;
+                self.value_changed.set(true);
+            },
+            ValueModeDefault | ValueModeDefaultOn => elem.set_string_attribute("value", value),
+            ValueModeFilename => {
+                if !value.is_empty() {
+                    return Err(InvalidState);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    // http://www.whatwg.org/html/#dom-input-valueasdate
+    fn ValueAsDate(self, cx: *mut JSContext) -> JSVal {
+        let input_type = self.input_type();
+        match input_type.as_slice() {
+            "date" | "time" => (),
+            _ => return NullValue(),
+        }
+        match convert_string_to_number(input_type.as_slice(), self.Value().as_slice()) {
+            Some(ms) => unsafe {
+                let date = JS_NewDateObjectMsec(cx, ms);
+                assert!(date.is_not_null());
+                ObjectValue(&*date)
+            },
+            None => NullValue(),
+        }
+    }
+
+    // http://www.whatwg.org/html/#dom-input-valueasdate
+    fn SetValueAsDate(self, cx: *mut JSContext, value: JSVal) -> ErrorResult {
+        match self.input_type().as_slice() {
+            "date" | "time" => (),
+            _ => return Err(InvalidState),
+        }
+        if value.is_null() {
+            return self.SetValue("".to_string());
+        }
+        // FIXME: values other than dates should throw a TypeError.
+        if !value.is_object() || unsafe { JS_ObjectIsDate(cx, value.to_object()) } == 0 {
+            return Ok(());
+        }
+        let ms: f64 = FromJSValConvertible::from_jsval(cx, value, ()).unwrap_or(f64::NAN);
+        self.set_number_value(ms);
+        Ok(())
+    }
+
+    // http://www.whatwg.org/html/#dom-input-valueasnumber
+    fn ValueAsNumber(self) -> f64 {
+        let input_type = self.input_type();
+        convert_string_to_number(input_type.as_slice(), self.Value().as_slice())
+            .unwrap_or(f64::NAN)
+    }
+
+    // http://www.whatwg.org/html/#dom-input-valueasnumber
+    fn SetValueAsNumber(self, value: f64) -> ErrorResult {
+        if !supports_numeric_value(self.input_type().as_slice()) {
+            return Err(InvalidState);
+        }
+        // FIXME: infinite values should throw a TypeError; they are treated
+        // like NaN for now.
+        self.set_number_value(value);
+        Ok(())
+    }
+
+    // http://www.whatwg.org/html/#dom-input-min
+    make_getter!(Min)
+
+    // http://www.whatwg.org/html/#dom-input-min
+    fn SetMin(self, min: DOMString) {
+        let elem: JSRef<Element> = ElementCast::from_ref(self);
+        elem.set_string_attribute("min", min)
+    }
+
+    // http://www.whatwg.org/html/#dom-input-max
+    make_getter!(Max)
+
+    // http://www.whatwg.org/html/#dom-input-max
+    fn SetMax(self, max: DOMString) {
+        let elem: JSRef<Element> = ElementCast::from_ref(self);
+        elem.set_string_attribute("max", max)
+    }
+
+    // http://www.whatwg.org/html/#dom-input-step
+    make_getter!(Step)
+
+    // http://www.whatwg.org/html/#dom-input-step
+    fn SetStep(self, step: DOMString) {
+        let elem: JSRef<Element> = ElementCast::from_ref(self);
+        elem.set_string_attribute("step", step)
+    }
+
+    // http://www.whatwg.org/html/#dom-input-stepup
+    fn StepUp(self, n: i32) -> ErrorResult {
+        self.step(n)
+    }
+
+    // http://www.whatwg.org/html/#dom-input-stepdown
+    fn StepDown(self, n: i32) -> ErrorResult {
+        self.step(-n)
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-select
+    fn Select(self) {
+        if !supports_selection(self.input_type().as_slice()) {
+            return;
+        }
+        self.selection.select_all(utf16_length(self.Value().as_slice()));
+        let window = window_from_node(self).root();
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        window.queue_simple_event(target, "select".to_string());
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-selectionstart
+    fn SelectionStart(self) -> Fallible<u32> {
+        try!(self.check_selection());
+        Ok(self.selection.start())
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-selectionstart
+    fn SetSelectionStart(self, start: u32) -> ErrorResult {
+        try!(self.check_selection());
+        let end = if self.selection.end() < start { start } else { self.selection.end() };
+        self.selection.set_range(start, end, self.selection.direction(),
+                                 utf16_length(self.Value().as_slice()));
+        Ok(())
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-selectionend
+    fn SelectionEnd(self) -> Fallible<u32> {
+        try!(self.check_selection());
+        Ok(self.selection.end())
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-selectionend
+    fn SetSelectionEnd(self, end: u32) -> ErrorResult {
+        try!(self.check_selection());
+        self.selection.set_range(self.selection.start(), end, self.selection.direction(),
+                                 utf16_length(self.Value().as_slice()));
+        Ok(())
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-selectiondirection
+    fn SelectionDirection(self) -> Fallible<DOMString> {
+        try!(self.check_selection());
+        Ok(self.selection.direction().to_dom_string())
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-selectiondirection
+    fn SetSelectionDirection(self, direction: DOMString) -> ErrorResult {
+        try!(self.check_selection());
+        self.selection.set_range(self.selection.start(), self.selection.end(),
+                                 SelectionDirection::from_dom_string(direction.as_slice()),
+                                 utf16_length(self.Value().as_slice()));
+        Ok(())
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-setrangetext
+    fn SetRangeText(self, replacement: DOMString) -> ErrorResult {
+        try!(self.check_selection());
+        let (start, end) = (self.selection.start(), self.selection.end());
+        self.SetRangeText_(replacement, start, end, SelectionModeValues::Preserve)
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-setrangetext
+    fn SetRangeText_(self, replacement: DOMString, start: u32, end: u32,
+                     selection_mode: SelectionMode) -> ErrorResult {
+        try!(self.check_selection());
+        let value = try!(self.selection.set_range_text(self.Value().as_slice(),
+                                                       replacement.as_slice(),
+                                                       start, end, selection_mode));
+        self.SetValue(value)
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-setselectionrange
+    fn SetSelectionRange(self, start: u32, end: u32, direction: Option<DOMString>) -> ErrorResult {
+        try!(self.check_selection());
+        let direction = direction.map_or(SelectionNone, |direction| {
+            SelectionDirection::from_dom_string(direction.as_slice())
+        });
+        self.selection.set_range(start, end, direction, utf16_length(self.Value().as_slice()));
+        Ok(())
+    }
+
+    // http://www.whatwg.org/html/#dom-input-list
+    fn GetList(self) -> Option<Temporary<HTMLElement>> {
+        let elem: JSRef<Element> = ElementCast::from_ref(self);
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        if !elem.has_attribute("list") || !node.is_in_doc() {
+            return None;
+        }
+
+        let document = document_from_node(self).root();
+        match document.GetElementById(elem.get_string_attribute("list")).root() {
+            Some(list) => {
+                if list.is_htmldatalistelement() {
+                    HTMLElementCast::to_ref(*list).map(Temporary::from_rooted)
+                } else {
+                    None
+                }
+            },
+            None => None,
+        }
+    }
+
+    // http://www.whatwg.org/html/#dom-lfe-labels
+    fn Labels(self) -> Temporary<NodeList> {
+        let elem: JSRef<Element> = ElementCast::from_ref(self);
+        elem.labels()
+    }
 }
 
 impl<'a> VirtualMethods for JSRef<'a, HTMLInputElement> {
@@ -61,6 +804,14 @@ impl<'a> VirtualMethods for JSRef<'a, HTMLInputElement> {
         Some(htmlelement as &VirtualMethods)
     }
 
+    fn heap_size_of_strings(&self) -> uint {
+        let inherited = match self.super_type() {
+            Some(ref s) => s.heap_size_of_strings(),
+            _ => 0,
+        };
+        inherited + self.value.deref().heap_size_of_children()
+    }
+
     fn after_set_attr(&self, name: &Atom, value: DOMString) {
         match self.super_type() {
             Some(ref s) => s.after_set_attr(name, value.clone()),
@@ -73,6 +824,12 @@ impl<'a> VirtualMethods for JSRef<'a, HTMLInputElement> {
                 node.set_disabled_state(true);
                 node.set_enabled_state(false);
             },
+            // The checked content attribute only sets the default checkedness.
+            "checked" if !self.checked_changed.get() => {
+                self.set_checkedness(true);
+                self.uncheck_radio_button_group();
+            },
+            "type" => self.update_checked_state(),
             _ => ()
         }
     }
@@ -90,6 +847,12 @@ impl<'a> VirtualMethods for JSRef<'a, HTMLInputElement> {
                 node.set_enabled_state(true);
                 node.check_ancestors_disabled_state_for_form_control();
             },
+            "checked" if !self.checked_changed.get() => self.set_checkedness(false),
+            // Without a type attribute, the input is a text field.
+            "type" => {
+                let elem: JSRef<Element> = ElementCast::from_ref(*self);
+                elem.set_state(InCheckedState, false);
+            },
             _ => ()
         }
     }
@@ -117,6 +880,16 @@ impl<'a> VirtualMethods for JSRef<'a, HTMLInputElement> {
             node.check_disabled_attribute();
         }
     }
+
+    fn handle_event(&self, event: JSRef<Event>) {
+        match self.super_type() {
+            Some(s) => {
+                s.handle_event(event);
+            }
+            None => {}
+        }
+        self.handle_event_impl(event);
+    }
 }
 
 impl Reflectable for HTMLInputElement {
@@ -2,17 +2,29 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
 use dom::bindings::codegen::Bindings::HTMLLabelElementBinding;
-use dom::bindings::codegen::InheritTypes::HTMLLabelElementDerived;
-use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::codegen::Bindings::HTMLLabelElementBinding::HTMLLabelElementMethods;
+use dom::bindings::codegen::InheritTypes::{ElementCast, EventTargetCast, HTMLElementCast};
+use dom::bindings::codegen::InheritTypes::{HTMLLabelElementCast, HTMLLabelElementDerived, NodeCast};
+use dom::bindings::global;
+use dom::bindings::js::{JSRef, Temporary, OptionalRootable, RootedVec};
 use dom::bindings::utils::{Reflectable, Reflector};
-use dom::document::Document;
-use dom::element::HTMLLabelElementTypeId;
-use dom::eventtarget::{EventTarget, NodeTargetTypeId};
+use dom::document::{Document, DocumentHelpers};
+use dom::element::{AttributeHandlers, Element, HTMLLabelElementTypeId};
+use dom::element::{HTMLButtonElementTypeId, HTMLInputElementTypeId, HTMLMeterElementTypeId};
+use dom::element::{HTMLOutputElementTypeId, HTMLProgressElementTypeId, HTMLSelectElementTypeId};
+use dom::element::HTMLTextAreaElementTypeId;
+use dom::event::Event;
+use dom::eventtarget::{EventTarget, EventTargetHelpers, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
-use dom::node::{Node, ElementNodeTypeId};
+use dom::htmlformelement::{FormControlHelpers, HTMLFormElement};
+use dom::node::{Node, NodeHelpers, ElementNodeTypeId, document_from_node, window_from_node};
+use dom::nodelist::NodeList;
 use servo_util::str::DOMString;
 
+use std::ascii::StrAsciiExt;
+
 #[jstraceable]
 #[must_root]
 pub struct HTMLLabelElement {
@@ -39,6 +51,151 @@ impl HTMLLabelElement {
     }
 }
 
+pub trait HTMLLabelElementHelpers {
+    fn activate(self, target: JSRef<Node>);
+}
+
+impl<'a> HTMLLabelElementHelpers for JSRef<'a, HTMLLabelElement> {
+    /// Forwards a click on `target`, an inclusive descendant of this label, to
+    /// the labeled control.
+    /// http://www.whatwg.org/html/#the-label-element
+    fn activate(self, target: JSRef<Node>) {
+        let control = match self.GetControl().root() {
+            Some(control) => control,
+            None => return,
+        };
+
+        // Clicks on the control itself already ran its activation behavior.
+        let control_node: JSRef<Node> = NodeCast::from_ref(*control);
+        if control_node.is_inclusive_ancestor_of(target) || control_node.get_disabled_state() {
+            return;
+        }
+
+        let document = document_from_node(self).root();
+        let control_element: JSRef<Element> = ElementCast::from_ref(*control);
+        document.set_focused_element(Some(control_element));
+
+        let window = window_from_node(self).root();
+        let event = Event::new(&global::Window(*window), "click".to_string(),
+                               true, true).root();
+        let control_target: JSRef<EventTarget> = EventTargetCast::from_ref(*control);
+        let _ = control_target.dispatch_event_with_target(None, *event);
+    }
+}
+
+pub trait LabelableElementHelpers {
+    fn is_labelable(self) -> bool;
+    fn labels(self) -> Temporary<NodeList>;
+    fn label_nodes(self) -> RootedVec<Node>;
+}
+
+impl<'a> LabelableElementHelpers for JSRef<'a, Element> {
+    // http://www.whatwg.org/html/#category-label
+    fn is_labelable(self) -> bool {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        match node.type_id() {
+            ElementNodeTypeId(HTMLInputElementTypeId) => {
+                !self.get_string_attribute("type").as_slice().eq_ignore_ascii_case("hidden")
+            },
+            ElementNodeTypeId(HTMLButtonElementTypeId) |
+            ElementNodeTypeId(HTMLMeterElementTypeId) |
+            ElementNodeTypeId(HTMLOutputElementTypeId) |
+            ElementNodeTypeId(HTMLProgressElementTypeId) |
+            ElementNodeTypeId(HTMLSelectElementTypeId) |
+            ElementNodeTypeId(HTMLTextAreaElementTypeId) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns a live list of the label elements in this element's tree
+    /// whose labeled control is this element.
+    fn labels(self) -> Temporary<NodeList> {
+        let window = window_from_node(self).root();
+        NodeList::new_labels_list(*window, self)
+    }
+
+    /// Returns the label elements in this element's tree whose labeled
+    /// control is this element, in tree order.
+    fn label_nodes(self) -> RootedVec<Node> {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        let root = node.ancestors().last().unwrap_or(node);
+        let mut labels = RootedVec::new();
+        for candidate in root.traverse_preorder() {
+            let label: Option<JSRef<HTMLLabelElement>> = HTMLLabelElementCast::to_ref(candidate);
+            let is_label = match label.and_then(|label| label.GetControl()).root() {
+                Some(control) => {
+                    let control: JSRef<Node> = NodeCast::from_ref(*control);
+                    control == node
+                },
+                None => false,
+            };
+            if is_label {
+                labels.push(candidate);
+            }
+        }
+        labels
+    }
+}
+
+impl<'a> HTMLLabelElementMethods for JSRef<'a, HTMLLabelElement> {
+    // http://www.whatwg.org/html/#dom-label-form
+    fn GetForm(self) -> Option<Temporary<HTMLFormElement>> {
+        match self.GetControl().root() {
+            Some(control) => {
+                let control: JSRef<Element> = ElementCast::from_ref(*control);
+                control.form_owner()
+            },
+            None => None,
+        }
+    }
+
+    // http://www.whatwg.org/html/#dom-label-htmlfor
+    fn HtmlFor(self) -> DOMString {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.get_string_attribute("for")
+    }
+
+    // http://www.whatwg.org/html/#dom-label-htmlfor
+    fn SetHtmlFor(self, html_for: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("for", html_for)
+    }
+
+    // http://www.whatwg.org/html/#labeled-control
+    fn GetControl(self) -> Option<Temporary<HTMLElement>> {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+
+        // With a for attribute, the control is the labelable element with
+        // that ID, if any.
+        if element.has_attribute("for") {
+            if !node.is_in_doc() {
+                return None;
+            }
+            let document = document_from_node(self).root();
+            let control = document.GetElementById(element.get_string_attribute("for")).root();
+            return match control {
+                Some(control) => {
+                    if control.is_labelable() {
+                        HTMLElementCast::to_ref(*control).map(Temporary::from_rooted)
+                    } else {
+                        None
+                    }
+                },
+                None => None,
+            };
+        }
+
+        // Otherwise, it is the first labelable descendant.
+        node.traverse_preorder()
+            .skip(1)
+            .filter_map(|candidate| ElementCast::to_ref(candidate))
+            .find(|candidate| candidate.is_labelable())
+            .and_then(|control| HTMLElementCast::to_ref(control))
+            .map(Temporary::from_rooted)
+    }
+}
+
 impl Reflectable for HTMLLabelElement {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.htmlelement.reflector()
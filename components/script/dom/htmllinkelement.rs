@@ -5,16 +5,16 @@
 use dom::attr::AttrHelpers;
 use dom::bindings::codegen::Bindings::HTMLLinkElementBinding;
 use dom::bindings::codegen::InheritTypes::HTMLLinkElementDerived;
-use dom::bindings::codegen::InheritTypes::{ElementCast, HTMLElementCast};
+use dom::bindings::codegen::InheritTypes::{ElementCast, HTMLElementCast, NodeCast};
 use dom::bindings::js::{JSRef, Temporary, OptionalRootable};
 use dom::bindings::utils::{Reflectable, Reflector};
-use dom::document::Document;
+use dom::document::{Document, DocumentHelpers};
 use dom::element::{AttributeHandlers, Element, HTMLLinkElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
 use dom::node::{Node, NodeHelpers, ElementNodeTypeId, window_from_node};
 use dom::virtualmethods::VirtualMethods;
-use layout_interface::{LayoutChan, LoadStylesheetMsg};
+use html::cssparse::{UrlProvenance, spawn_css_parser};
 use servo_util::atom::Atom;
 use servo_util::str::{DOMString, HTML_SPACE_CHARACTERS};
 use servo_util::namespace::Null;
@@ -75,6 +75,11 @@ impl<'a> VirtualMethods for JSRef<'a, HTMLLinkElement> {
             _ => (),
         }
 
+        let node: JSRef<Node> = NodeCast::from_ref(*self);
+        if !node.is_in_doc() {
+            return;
+        }
+
         let element: JSRef<Element> = ElementCast::from_ref(*self);
         let rel = get_attr(element, "rel");
 
@@ -116,11 +121,20 @@ trait PrivateHTMLLinkElementHelpers {
 
 impl<'a> PrivateHTMLLinkElementHelpers for JSRef<'a, HTMLLinkElement> {
     fn handle_stylesheet_url(self, href: &str) {
+        // Documents without a browsing context don't load stylesheets.
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        let document = node.owner_doc().root();
+        if !document.has_browsing_context() {
+            return;
+        }
+
         let window = window_from_node(self).root();
         match UrlParser::new().base_url(&window.deref().page().get_url()).parse(href) {
             Ok(url) => {
-                let LayoutChan(ref layout_chan) = *window.deref().page().layout_chan;
-                layout_chan.send(LoadStylesheetMsg(url));
+                let page = window.deref().page();
+                let provenance = UrlProvenance(url, page.resource_task.deref().clone());
+                let sheet = spawn_css_parser(provenance, window.deref().script_chan.clone(), page.id);
+                page.add_stylesheet(sheet);
             }
             Err(e) => debug!("Parsing url {:s} failed: {:?}", href, e)
         }
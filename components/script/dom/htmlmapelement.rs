@@ -3,14 +3,18 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::bindings::codegen::Bindings::HTMLMapElementBinding;
-use dom::bindings::codegen::InheritTypes::HTMLMapElementDerived;
+use dom::bindings::codegen::Bindings::HTMLMapElementBinding::HTMLMapElementMethods;
+use dom::bindings::codegen::InheritTypes::{ElementCast, HTMLAreaElementCast, HTMLAreaElementDerived};
+use dom::bindings::codegen::InheritTypes::{HTMLMapElementDerived, NodeCast};
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
-use dom::element::HTMLMapElementTypeId;
+use dom::element::{AttributeHandlers, Element, HTMLMapElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
+use dom::htmlareaelement::{HTMLAreaElement, HTMLAreaElementHelpers};
+use dom::htmlcollection::{HTMLCollection, CollectionFilter};
 use dom::htmlelement::HTMLElement;
-use dom::node::{Node, ElementNodeTypeId};
+use dom::node::{Node, NodeHelpers, ElementNodeTypeId, window_from_node};
 use servo_util::str::DOMString;
 
 #[jstraceable]
@@ -39,6 +43,49 @@ impl HTMLMapElement {
     }
 }
 
+pub trait HTMLMapElementHelpers {
+    fn area_at(self, x: f64, y: f64) -> Option<Temporary<HTMLAreaElement>>;
+}
+
+impl<'a> HTMLMapElementHelpers for JSRef<'a, HTMLMapElement> {
+    /// Returns the first area of this map, in tree order, whose shape covers
+    /// the point `(x, y)` of an image using the map.
+    /// http://www.whatwg.org/html/#image-map-processing-model
+    fn area_at(self, x: f64, y: f64) -> Option<Temporary<HTMLAreaElement>> {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        node.traverse_preorder()
+            .filter_map(|candidate| HTMLAreaElementCast::to_ref(candidate))
+            .find(|area| area.get_shape().map_or(false, |shape| shape.contains(x, y)))
+            .map(Temporary::from_rooted)
+    }
+}
+
+impl<'a> HTMLMapElementMethods for JSRef<'a, HTMLMapElement> {
+    // http://www.whatwg.org/html/#dom-map-name
+    make_getter!(Name)
+
+    // http://www.whatwg.org/html/#dom-map-name
+    fn SetName(self, name: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("name", name)
+    }
+
+    // http://www.whatwg.org/html/#dom-map-areas
+    fn Areas(self) -> Temporary<HTMLCollection> {
+        #[jstraceable]
+        struct HTMLMapAreasFilter;
+        impl CollectionFilter for HTMLMapAreasFilter {
+            fn filter(&self, elem: JSRef<Element>, _root: JSRef<Node>) -> bool {
+                elem.is_htmlareaelement()
+            }
+        }
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        let filter = box HTMLMapAreasFilter;
+        let window = window_from_node(node).root();
+        HTMLCollection::create(*window, node, filter)
+    }
+}
+
 impl Reflectable for HTMLMapElement {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.htmlelement.reflector()
@@ -2,20 +2,85 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use dom::bindings::js::{JSRef};
+use dom::bindings::codegen::Bindings::HTMLMediaElementBinding::CanPlayTypeResult;
+use dom::bindings::codegen::Bindings::HTMLMediaElementBinding::CanPlayTypeResultValues::{_empty, Maybe, Probably};
+use dom::bindings::codegen::Bindings::HTMLMediaElementBinding::HTMLMediaElementConstants;
+use dom::bindings::codegen::Bindings::HTMLMediaElementBinding::HTMLMediaElementMethods;
+use dom::bindings::codegen::Bindings::MediaErrorBinding::MediaErrorConstants;
+use dom::bindings::codegen::InheritTypes::{ElementCast, EventTargetCast, HTMLElementCast, NodeCast};
+use dom::bindings::codegen::InheritTypes::{HTMLMediaElementDerived, HTMLSourceElementDerived};
+use dom::bindings::error::{ErrorResult, Fallible, IndexSize, NotSupported};
+use dom::bindings::js::{JS, JSRef, Temporary, OptionalSettable};
+use dom::bindings::trace::Traceable;
 use dom::bindings::utils::{Reflectable, Reflector};
-use dom::bindings::codegen::InheritTypes::HTMLMediaElementDerived;
-use dom::document::Document;
-use dom::element::{ElementTypeId, HTMLAudioElementTypeId, HTMLVideoElementTypeId};
+use dom::document::{Document, DocumentHelpers};
+use dom::element::{AttributeHandlers, Element, ElementTypeId};
+use dom::element::{HTMLAudioElementTypeId, HTMLVideoElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
-use dom::node::ElementNodeTypeId;
+use dom::mediaerror::MediaError;
+use dom::node::{Node, NodeHelpers, ElementNodeTypeId, document_from_node, window_from_node};
+use dom::promise::Promise;
+use dom::virtualmethods::VirtualMethods;
+use dom::window::WindowHelpers;
+
+use servo_util::atom::Atom;
 use servo_util::str::DOMString;
 
+use std::ascii::StrAsciiExt;
+use std::cell::{Cell, RefCell};
+use url::{Url, UrlParser};
+
+/// The containers, and the codecs within them, that `canPlayType()` admits
+/// to. Nothing is decoded yet; this only gives pages a consistent answer.
+static SUPPORTED_TYPES: &'static [(&'static str, &'static [&'static str])] = &[
+    ("audio/ogg", &["vorbis", "opus"]),
+    ("video/ogg", &["theora", "vorbis", "opus"]),
+    ("audio/webm", &["vorbis", "opus"]),
+    ("video/webm", &["vp8", "vp8.0", "vp9", "vorbis", "opus"]),
+    ("audio/wav", &["1"]),
+];
+
+/// Answers whether a resource of the MIME type `type_` could be played.
+/// http://www.whatwg.org/html/#dom-navigator-canplaytype
+pub fn can_play_type(type_: &str) -> CanPlayTypeResult {
+    let mut parts = type_.split(';');
+    let container = parts.next().unwrap().trim().to_ascii_lower();
+    let codecs = match SUPPORTED_TYPES.iter().find(|&&(name, _)| name == container.as_slice()) {
+        Some(&(_, codecs)) => codecs,
+        None => return _empty,
+    };
+
+    let codecs_parameter = parts.map(|parameter| parameter.trim()).find(|parameter| {
+        parameter.to_ascii_lower().as_slice().starts_with("codecs=")
+    });
+    match codecs_parameter {
+        None => Maybe,
+        Some(parameter) => {
+            let value = parameter.slice_from("codecs=".len()).trim_chars('"');
+            let all_supported = value.split(',').all(|codec| {
+                let codec = codec.trim().to_ascii_lower();
+                codecs.iter().any(|&supported| supported == codec.as_slice())
+            });
+            if all_supported { Probably } else { _empty }
+        }
+    }
+}
+
 #[jstraceable]
 #[must_root]
 pub struct HTMLMediaElement {
     pub htmlelement: HTMLElement,
+    network_state: Cell<u16>,
+    ready_state: Cell<u16>,
+    current_src: Traceable<RefCell<DOMString>>,
+    error: Cell<Option<JS<MediaError>>>,
+    paused: Cell<bool>,
+    muted: Cell<bool>,
+    volume: Cell<f64>,
+    /// Whether the resource selection algorithm has run out of source
+    /// children, and is waiting for another one to be inserted.
+    waiting_for_source: Cell<bool>,
 }
 
 impl HTMLMediaElementDerived for EventTarget {
@@ -31,7 +96,356 @@ impl HTMLMediaElementDerived for EventTarget {
 impl HTMLMediaElement {
     pub fn new_inherited(type_id: ElementTypeId, tag_name: DOMString, document: JSRef<Document>) -> HTMLMediaElement {
         HTMLMediaElement {
-            htmlelement: HTMLElement::new_inherited(type_id, tag_name, document)
+            htmlelement: HTMLElement::new_inherited(type_id, tag_name, document),
+            network_state: Cell::new(HTMLMediaElementConstants::NETWORK_EMPTY),
+            ready_state: Cell::new(HTMLMediaElementConstants::HAVE_NOTHING),
+            current_src: Traceable::new(RefCell::new("".to_string())),
+            error: Cell::new(None),
+            paused: Cell::new(true),
+            muted: Cell::new(false),
+            volume: Cell::new(1.0),
+            waiting_for_source: Cell::new(false),
+        }
+    }
+}
+
+trait PrivateHTMLMediaElementHelpers {
+    fn queue_event(self, target: JSRef<EventTarget>, type_: &str);
+    fn resolve_url(self, url: &str) -> Option<Url>;
+    fn select_resource(self);
+    fn select_from_sources(self, candidate: JSRef<Node>);
+    fn fetch_resource(self, url: Url);
+    fn fail(self);
+}
+
+impl<'a> PrivateHTMLMediaElementHelpers for JSRef<'a, HTMLMediaElement> {
+    fn queue_event(self, target: JSRef<EventTarget>, type_: &str) {
+        let window = window_from_node(self).root();
+        window.queue_simple_event(target, type_.to_string());
+    }
+
+    fn resolve_url(self, url: &str) -> Option<Url> {
+        if url.is_empty() {
+            return None;
+        }
+        let document = document_from_node(self).root();
+        UrlParser::new().base_url(document.url()).parse(url).ok()
+    }
+
+    // http://www.whatwg.org/html/#concept-media-select-resource
+    fn select_resource(self) {
+        // Step 1.
+        self.network_state.set(HTMLMediaElementConstants::NETWORK_NO_SOURCE);
+        self.waiting_for_source.set(false);
+
+        // Step 4. There is no stable state to await, as this runs
+        // synchronously from the task that triggered it.
+
+        // Step 5.
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        let first_source = node.children().find(|child| child.is_htmlsourceelement());
+        let src = if element.has_attribute("src") {
+            Some(element.get_string_attribute("src"))
+        } else {
+            None
+        };
+        if src.is_none() && first_source.is_none() {
+            self.network_state.set(HTMLMediaElementConstants::NETWORK_EMPTY);
+            return;
+        }
+
+        // Steps 6-7.
+        self.network_state.set(HTMLMediaElementConstants::NETWORK_LOADING);
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        self.queue_event(target, "loadstart");
+
+        // Steps 8-9.
+        match src {
+            Some(src) => {
+                match self.resolve_url(src.as_slice()) {
+                    Some(url) => self.fetch_resource(url),
+                    None => self.fail(),
+                }
+            }
+            None => self.select_from_sources(first_source.unwrap()),
+        }
+    }
+
+    /// Runs the children mode of the resource selection algorithm, trying
+    /// `candidate` and then the source elements that follow it.
+    fn select_from_sources(self, candidate: JSRef<Node>) {
+        let candidates = Some(candidate).into_iter().chain(candidate.following_siblings());
+        for candidate in candidates.filter(|node| node.is_htmlsourceelement()) {
+            let source: JSRef<Element> = ElementCast::to_ref(candidate).unwrap();
+            let url = self.resolve_url(source.get_string_attribute("src").as_slice());
+            let supported = !source.has_attribute("type") ||
+                can_play_type(source.get_string_attribute("type").as_slice()) != _empty;
+            match url {
+                Some(url) if supported => {
+                    self.fetch_resource(url);
+                    return;
+                }
+                _ => {
+                    let target: JSRef<EventTarget> = EventTargetCast::from_ref(candidate);
+                    self.queue_event(target, "error");
+                }
+            }
+        }
+
+        // Wait for another source element to be inserted.
+        self.network_state.set(HTMLMediaElementConstants::NETWORK_NO_SOURCE);
+        self.waiting_for_source.set(true);
+    }
+
+    // http://www.whatwg.org/html/#concept-media-load-resource
+    fn fetch_resource(self, url: Url) {
+        *self.current_src.deref().borrow_mut() = url.serialize();
+
+        // Without a media backend nothing can be fetched or decoded, so act as
+        // if the download were suspended straight away, as with
+        // preload=none.
+        self.network_state.set(HTMLMediaElementConstants::NETWORK_IDLE);
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        self.queue_event(target, "suspend");
+    }
+
+    // http://www.whatwg.org/html/#dedicated-media-source-failure-steps
+    fn fail(self) {
+        let window = window_from_node(self).root();
+        self.error.assign(Some(MediaError::new(*window,
+                                               MediaErrorConstants::MEDIA_ERR_SRC_NOT_SUPPORTED)));
+        self.network_state.set(HTMLMediaElementConstants::NETWORK_NO_SOURCE);
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        self.queue_event(target, "error");
+    }
+}
+
+impl<'a> HTMLMediaElementMethods for JSRef<'a, HTMLMediaElement> {
+    // http://www.whatwg.org/html/#dom-media-error
+    fn GetError(self) -> Option<Temporary<MediaError>> {
+        self.error.get().map(|error| Temporary::new(error))
+    }
+
+    // http://www.whatwg.org/html/#dom-media-src
+    make_url_getter!(Src)
+
+    // http://www.whatwg.org/html/#dom-media-src
+    fn SetSrc(self, src: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_url_attribute("src", src)
+    }
+
+    // http://www.whatwg.org/html/#dom-media-currentsrc
+    fn CurrentSrc(self) -> DOMString {
+        self.current_src.deref().borrow().clone()
+    }
+
+    // http://www.whatwg.org/html/#dom-media-networkstate
+    fn NetworkState(self) -> u16 {
+        self.network_state.get()
+    }
+
+    // http://www.whatwg.org/html/#dom-media-preload
+    fn Preload(self) -> DOMString {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        if !element.has_attribute("preload") {
+            return "auto".to_string();
+        }
+        let preload = element.get_string_attribute("preload").as_slice().to_ascii_lower();
+        match preload.as_slice() {
+            "none" | "metadata" => preload.clone(),
+            "" | "auto" => "auto".to_string(),
+            _ => "metadata".to_string(),
+        }
+    }
+
+    // http://www.whatwg.org/html/#dom-media-preload
+    fn SetPreload(self, preload: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("preload", preload)
+    }
+
+    // http://www.whatwg.org/html/#concept-media-load-algorithm
+    fn Load(self) {
+        // Step 3.
+        let network_state = self.network_state.get();
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        if network_state == HTMLMediaElementConstants::NETWORK_LOADING ||
+           network_state == HTMLMediaElementConstants::NETWORK_IDLE {
+            self.queue_event(target, "abort");
+        }
+
+        // Step 4.
+        if network_state != HTMLMediaElementConstants::NETWORK_EMPTY {
+            self.queue_event(target, "emptied");
+            self.network_state.set(HTMLMediaElementConstants::NETWORK_EMPTY);
+            self.ready_state.set(HTMLMediaElementConstants::HAVE_NOTHING);
+            self.paused.set(true);
+        }
+
+        // Step 6.
+        self.error.assign(None::<Temporary<MediaError>>);
+
+        // Step 7.
+        self.select_resource();
+    }
+
+    // http://www.whatwg.org/html/#dom-navigator-canplaytype
+    fn CanPlayType(self, type_: DOMString) -> CanPlayTypeResult {
+        can_play_type(type_.as_slice())
+    }
+
+    // http://www.whatwg.org/html/#dom-media-readystate
+    fn ReadyState(self) -> u16 {
+        self.ready_state.get()
+    }
+
+    // http://www.whatwg.org/html/#dom-media-seeking
+    fn Seeking(self) -> bool {
+        false
+    }
+
+    // http://www.whatwg.org/html/#dom-media-paused
+    fn Paused(self) -> bool {
+        self.paused.get()
+    }
+
+    // http://www.whatwg.org/html/#dom-media-autoplay
+    make_bool_getter!(Autoplay)
+
+    // http://www.whatwg.org/html/#dom-media-autoplay
+    fn SetAutoplay(self, autoplay: bool) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_bool_attribute("autoplay", autoplay)
+    }
+
+    // http://www.whatwg.org/html/#dom-media-loop
+    make_bool_getter!(Loop)
+
+    // http://www.whatwg.org/html/#dom-media-loop
+    fn SetLoop(self, loop_: bool) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_bool_attribute("loop", loop_)
+    }
+
+    // http://www.whatwg.org/html/#dom-media-play
+    fn Play(self) -> Fallible<Temporary<Promise>> {
+        // Until media can be decoded, playback never starts, and the promise
+        // rejects with a NotSupportedError.
+        if self.network_state.get() == HTMLMediaElementConstants::NETWORK_EMPTY {
+            self.select_resource();
+        }
+        Err(NotSupported)
+    }
+
+    // http://www.whatwg.org/html/#dom-media-pause
+    fn Pause(self) {
+        // Step 1.
+        if self.network_state.get() == HTMLMediaElementConstants::NETWORK_EMPTY {
+            self.select_resource();
+        }
+
+        // Step 2.
+        if !self.paused.get() {
+            self.paused.set(true);
+            let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+            self.queue_event(target, "timeupdate");
+            self.queue_event(target, "pause");
+        }
+    }
+
+    // http://www.whatwg.org/html/#dom-media-controls
+    make_bool_getter!(Controls)
+
+    // http://www.whatwg.org/html/#dom-media-controls
+    fn SetControls(self, controls: bool) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_bool_attribute("controls", controls)
+    }
+
+    // http://www.whatwg.org/html/#dom-media-volume
+    fn Volume(self) -> f64 {
+        self.volume.get()
+    }
+
+    // http://www.whatwg.org/html/#dom-media-volume
+    fn SetVolume(self, volume: f64) -> ErrorResult {
+        if !(volume >= 0.0 && volume <= 1.0) {
+            return Err(IndexSize);
+        }
+        if volume != self.volume.get() {
+            self.volume.set(volume);
+            let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+            self.queue_event(target, "volumechange");
+        }
+        Ok(())
+    }
+
+    // http://www.whatwg.org/html/#dom-media-muted
+    fn Muted(self) -> bool {
+        self.muted.get()
+    }
+
+    // http://www.whatwg.org/html/#dom-media-muted
+    fn SetMuted(self, muted: bool) {
+        if muted != self.muted.get() {
+            self.muted.set(muted);
+            let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+            self.queue_event(target, "volumechange");
+        }
+    }
+
+    // http://www.whatwg.org/html/#dom-media-defaultmuted
+    fn DefaultMuted(self) -> bool {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.has_attribute("muted")
+    }
+
+    // http://www.whatwg.org/html/#dom-media-defaultmuted
+    fn SetDefaultMuted(self, muted: bool) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_bool_attribute("muted", muted)
+    }
+}
+
+impl<'a> VirtualMethods for JSRef<'a, HTMLMediaElement> {
+    fn super_type<'a>(&'a self) -> Option<&'a VirtualMethods> {
+        let htmlelement: &JSRef<HTMLElement> = HTMLElementCast::from_borrowed_ref(self);
+        Some(htmlelement as &VirtualMethods)
+    }
+
+    fn after_set_attr(&self, name: &Atom, value: DOMString) {
+        match self.super_type() {
+            Some(ref s) => s.after_set_attr(name, value.clone()),
+            _ => (),
+        }
+
+        match name.as_slice() {
+            "src" => self.Load(),
+            _ => ()
+        }
+    }
+
+    fn child_inserted(&self, child: JSRef<Node>) {
+        match self.super_type() {
+            Some(ref s) => s.child_inserted(child),
+            _ => (),
+        }
+
+        if !child.is_htmlsourceelement() {
+            return;
+        }
+        let element: JSRef<Element> = ElementCast::from_ref(*self);
+        if element.has_attribute("src") {
+            return;
+        }
+        if self.network_state.get() == HTMLMediaElementConstants::NETWORK_EMPTY {
+            self.select_resource();
+        } else if self.waiting_for_source.get() {
+            self.waiting_for_source.set(false);
+            self.network_state.set(HTMLMediaElementConstants::NETWORK_LOADING);
+            self.select_from_sources(child);
         }
     }
 }
@@ -3,14 +3,17 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::bindings::codegen::Bindings::HTMLMeterElementBinding;
-use dom::bindings::codegen::InheritTypes::HTMLMeterElementDerived;
+use dom::bindings::codegen::Bindings::HTMLMeterElementBinding::HTMLMeterElementMethods;
+use dom::bindings::codegen::InheritTypes::{ElementCast, HTMLMeterElementDerived};
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
-use dom::element::HTMLMeterElementTypeId;
+use dom::element::{AttributeHandlers, Element, HTMLMeterElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
+use dom::htmllabelelement::LabelableElementHelpers;
 use dom::node::{Node, ElementNodeTypeId};
+use dom::nodelist::NodeList;
 use servo_util::str::DOMString;
 
 #[jstraceable]
@@ -39,6 +42,125 @@ impl HTMLMeterElement {
     }
 }
 
+fn clamp(value: f64, lower: f64, upper: f64) -> f64 {
+    if value < lower {
+        lower
+    } else if value > upper {
+        upper
+    } else {
+        value
+    }
+}
+
+trait PrivateHTMLMeterElementHelpers {
+    fn float_attribute(self, name: &str) -> Option<f64>;
+    fn minimum(self) -> f64;
+    fn maximum(self) -> f64;
+    fn low_boundary(self) -> f64;
+}
+
+impl<'a> PrivateHTMLMeterElementHelpers for JSRef<'a, HTMLMeterElement> {
+    fn float_attribute(self, name: &str) -> Option<f64> {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.get_float_attribute(name)
+    }
+
+    // http://www.whatwg.org/html/#concept-meter-minimum
+    fn minimum(self) -> f64 {
+        self.float_attribute("min").unwrap_or(0.0)
+    }
+
+    // http://www.whatwg.org/html/#concept-meter-maximum
+    fn maximum(self) -> f64 {
+        let minimum = self.minimum();
+        let maximum = self.float_attribute("max").unwrap_or(1.0);
+        if maximum < minimum { minimum } else { maximum }
+    }
+
+    // http://www.whatwg.org/html/#concept-meter-low
+    fn low_boundary(self) -> f64 {
+        let minimum = self.minimum();
+        clamp(self.float_attribute("low").unwrap_or(minimum), minimum, self.maximum())
+    }
+}
+
+impl<'a> HTMLMeterElementMethods for JSRef<'a, HTMLMeterElement> {
+    // http://www.whatwg.org/html/#dom-meter-value
+    fn Value(self) -> f64 {
+        clamp(self.float_attribute("value").unwrap_or(0.0), self.minimum(), self.maximum())
+    }
+
+    // http://www.whatwg.org/html/#dom-meter-value
+    fn SetValue(self, value: f64) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_float_attribute("value", value)
+    }
+
+    // http://www.whatwg.org/html/#dom-meter-min
+    fn Min(self) -> f64 {
+        self.minimum()
+    }
+
+    // http://www.whatwg.org/html/#dom-meter-min
+    fn SetMin(self, min: f64) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_float_attribute("min", min)
+    }
+
+    // http://www.whatwg.org/html/#dom-meter-max
+    fn Max(self) -> f64 {
+        self.maximum()
+    }
+
+    // http://www.whatwg.org/html/#dom-meter-max
+    fn SetMax(self, max: f64) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_float_attribute("max", max)
+    }
+
+    // http://www.whatwg.org/html/#dom-meter-low
+    fn Low(self) -> f64 {
+        self.low_boundary()
+    }
+
+    // http://www.whatwg.org/html/#dom-meter-low
+    fn SetLow(self, low: f64) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_float_attribute("low", low)
+    }
+
+    // http://www.whatwg.org/html/#dom-meter-high
+    fn High(self) -> f64 {
+        let maximum = self.maximum();
+        clamp(self.float_attribute("high").unwrap_or(maximum), self.low_boundary(), maximum)
+    }
+
+    // http://www.whatwg.org/html/#dom-meter-high
+    fn SetHigh(self, high: f64) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_float_attribute("high", high)
+    }
+
+    // http://www.whatwg.org/html/#dom-meter-optimum
+    fn Optimum(self) -> f64 {
+        let (minimum, maximum) = (self.minimum(), self.maximum());
+        let midpoint = minimum + (maximum - minimum) / 2.0;
+        clamp(self.float_attribute("optimum").unwrap_or(midpoint), minimum, maximum)
+    }
+
+    // http://www.whatwg.org/html/#dom-meter-optimum
+    fn SetOptimum(self, optimum: f64) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_float_attribute("optimum", optimum)
+    }
+
+    // http://www.whatwg.org/html/#dom-lfe-labels
+    fn Labels(self) -> Temporary<NodeList> {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.labels()
+    }
+}
+
 impl Reflectable for HTMLMeterElement {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.htmlelement.reflector()
@@ -3,11 +3,13 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::bindings::codegen::Bindings::HTMLModElementBinding;
+use dom::bindings::codegen::Bindings::HTMLModElementBinding::HTMLModElementMethods;
+use dom::bindings::codegen::InheritTypes::ElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLModElementDerived;
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
-use dom::element::HTMLModElementTypeId;
+use dom::element::{AttributeHandlers, Element, HTMLModElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
 use dom::node::{Node, ElementNodeTypeId};
@@ -39,6 +41,26 @@ impl HTMLModElement {
     }
 }
 
+impl<'a> HTMLModElementMethods for JSRef<'a, HTMLModElement> {
+    // http://www.whatwg.org/html/#dom-mod-cite
+    make_url_getter!(Cite)
+
+    // http://www.whatwg.org/html/#dom-mod-cite
+    fn SetCite(self, cite: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_url_attribute("cite", cite)
+    }
+
+    // http://www.whatwg.org/html/#dom-mod-datetime
+    make_getter!(DateTime)
+
+    // http://www.whatwg.org/html/#dom-mod-datetime
+    fn SetDateTime(self, date_time: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("datetime", date_time)
+    }
+}
+
 impl Reflectable for HTMLModElement {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.htmlelement.reflector()
@@ -14,9 +14,11 @@ use dom::element::{Element, HTMLObjectElementTypeId};
 use dom::element::AttributeHandlers;
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
+use dom::htmlformelement::{FormControlHelpers, HTMLFormElement};
 use dom::node::{Node, ElementNodeTypeId, NodeHelpers, window_from_node};
 use dom::validitystate::ValidityState;
 use dom::virtualmethods::VirtualMethods;
+use dom::window::Window;
 
 use servo_net::image_cache_task;
 use servo_net::image_cache_task::ImageCacheTask;
@@ -83,10 +85,74 @@ pub fn is_image_data(uri: &str) -> bool {
 }
 
 impl<'a> HTMLObjectElementMethods for JSRef<'a, HTMLObjectElement> {
+    // http://www.whatwg.org/html/#dom-object-data
+    make_url_getter!(Data)
+
+    // http://www.whatwg.org/html/#dom-object-data
+    fn SetData(self, data: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_url_attribute("data", data)
+    }
+
+    // http://www.whatwg.org/html/#dom-object-type
+    make_getter!(Type)
+
+    // http://www.whatwg.org/html/#dom-object-type
+    fn SetType(self, type_: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("type", type_)
+    }
+
+    // http://www.whatwg.org/html/#dom-object-name
+    make_getter!(Name)
+
+    // http://www.whatwg.org/html/#dom-object-name
+    fn SetName(self, name: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("name", name)
+    }
+
+    // http://www.whatwg.org/html/#dom-object-usemap
+    make_getter!(UseMap)
+
+    // http://www.whatwg.org/html/#dom-object-usemap
+    fn SetUseMap(self, use_map: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("usemap", use_map)
+    }
+
+    // http://www.whatwg.org/html/#dom-fae-form
+    fn GetForm(self) -> Option<Temporary<HTMLFormElement>> {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.form_owner()
+    }
+
+    // Servo doesn't support plugins or nested browsing contexts in objects.
+    // http://www.whatwg.org/html/#dom-object-contentdocument
+    fn GetContentDocument(self) -> Option<Temporary<Document>> {
+        None
+    }
+
+    // http://www.whatwg.org/html/#dom-object-contentwindow
+    fn GetContentWindow(self) -> Option<Temporary<Window>> {
+        None
+    }
+
+    // Object elements are barred from constraint validation.
+    // http://www.whatwg.org/html/#dom-cva-willvalidate
+    fn WillValidate(self) -> bool {
+        false
+    }
+
     fn Validity(self) -> Temporary<ValidityState> {
         let window = window_from_node(self).root();
         ValidityState::new(*window)
     }
+
+    // http://www.whatwg.org/html/#dom-cva-checkvalidity
+    fn CheckValidity(self) -> bool {
+        true
+    }
 }
 
 impl<'a> VirtualMethods for JSRef<'a, HTMLObjectElement> {
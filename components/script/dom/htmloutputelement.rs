@@ -2,23 +2,36 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use dom::attr::AttrValue;
 use dom::bindings::codegen::Bindings::HTMLOutputElementBinding;
 use dom::bindings::codegen::Bindings::HTMLOutputElementBinding::HTMLOutputElementMethods;
+use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
+use dom::bindings::codegen::InheritTypes::{ElementCast, HTMLElementCast, NodeCast};
 use dom::bindings::codegen::InheritTypes::HTMLOutputElementDerived;
-use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::js::{JS, JSRef, Temporary, OptionalSettable};
+use dom::bindings::trace::Traceable;
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
-use dom::element::HTMLOutputElementTypeId;
+use dom::domtokenlist::DOMTokenList;
+use dom::element::{AttributeHandlers, Element, HTMLOutputElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
+use dom::htmlformelement::{FormControlHelpers, HTMLFormElement};
+use dom::htmllabelelement::LabelableElementHelpers;
 use dom::node::{Node, ElementNodeTypeId, window_from_node};
+use dom::nodelist::NodeList;
 use dom::validitystate::ValidityState;
+use dom::virtualmethods::VirtualMethods;
 use servo_util::str::DOMString;
 
+use std::cell::{Cell, RefCell};
+
 #[jstraceable]
 #[must_root]
 pub struct HTMLOutputElement {
-    pub htmlelement: HTMLElement
+    pub htmlelement: HTMLElement,
+    html_for: Cell<Option<JS<DOMTokenList>>>,
+    default_value_override: Traceable<RefCell<Option<DOMString>>>,
 }
 
 impl HTMLOutputElementDerived for EventTarget {
@@ -30,7 +43,9 @@ impl HTMLOutputElementDerived for EventTarget {
 impl HTMLOutputElement {
     fn new_inherited(localName: DOMString, document: JSRef<Document>) -> HTMLOutputElement {
         HTMLOutputElement {
-            htmlelement: HTMLElement::new_inherited(HTMLOutputElementTypeId, localName, document)
+            htmlelement: HTMLElement::new_inherited(HTMLOutputElementTypeId, localName, document),
+            html_for: Cell::new(None),
+            default_value_override: Traceable::new(RefCell::new(None)),
         }
     }
 
@@ -41,11 +56,115 @@ impl HTMLOutputElement {
     }
 }
 
+pub trait HTMLOutputElementHelpers {
+    fn reset(self);
+}
+
+impl<'a> HTMLOutputElementHelpers for JSRef<'a, HTMLOutputElement> {
+    // http://www.whatwg.org/html/#the-output-element:concept-form-reset-control
+    fn reset(self) {
+        let default_value = self.DefaultValue();
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        node.SetTextContent(Some(default_value));
+        *self.default_value_override.deref().borrow_mut() = None;
+    }
+}
+
 impl<'a> HTMLOutputElementMethods for JSRef<'a, HTMLOutputElement> {
+    // http://www.whatwg.org/html/#dom-output-htmlfor
+    fn HtmlFor(self) -> Temporary<DOMTokenList> {
+        match self.html_for.get() {
+            Some(html_for) => Temporary::new(html_for),
+            None => {
+                let element: JSRef<Element> = ElementCast::from_ref(self);
+                let html_for = DOMTokenList::new(element, "for").root();
+                self.html_for.assign(Some(html_for.deref().clone()));
+                Temporary::from_rooted(*html_for)
+            }
+        }
+    }
+
+    // http://www.whatwg.org/html/#dom-fae-form
+    fn GetForm(self) -> Option<Temporary<HTMLFormElement>> {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.form_owner()
+    }
+
+    // http://www.whatwg.org/html/#dom-fe-name
+    make_getter!(Name)
+
+    // http://www.whatwg.org/html/#dom-fe-name
+    fn SetName(self, name: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("name", name)
+    }
+
+    // http://www.whatwg.org/html/#dom-output-type
+    fn Type(self) -> DOMString {
+        "output".to_string()
+    }
+
+    // http://www.whatwg.org/html/#dom-output-defaultvalue
+    fn DefaultValue(self) -> DOMString {
+        match *self.default_value_override.deref().borrow() {
+            Some(ref default_value) => return default_value.clone(),
+            None => (),
+        }
+        self.Value()
+    }
+
+    // http://www.whatwg.org/html/#dom-output-defaultvalue
+    fn SetDefaultValue(self, default_value: DOMString) {
+        if self.default_value_override.deref().borrow().is_none() {
+            let node: JSRef<Node> = NodeCast::from_ref(self);
+            node.SetTextContent(Some(default_value));
+        } else {
+            *self.default_value_override.deref().borrow_mut() = Some(default_value);
+        }
+    }
+
+    // http://www.whatwg.org/html/#dom-output-value
+    fn Value(self) -> DOMString {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        node.GetTextContent().unwrap()
+    }
+
+    // http://www.whatwg.org/html/#dom-output-value
+    fn SetValue(self, value: DOMString) {
+        // The first change to the value remembers the current contents as the
+        // default value.
+        if self.default_value_override.deref().borrow().is_none() {
+            let default_value = self.Value();
+            *self.default_value_override.deref().borrow_mut() = Some(default_value);
+        }
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        node.SetTextContent(Some(value));
+    }
+
     fn Validity(self) -> Temporary<ValidityState> {
         let window = window_from_node(self).root();
         ValidityState::new(*window)
     }
+
+    // http://www.whatwg.org/html/#dom-lfe-labels
+    fn Labels(self) -> Temporary<NodeList> {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.labels()
+    }
+}
+
+impl<'a> VirtualMethods for JSRef<'a, HTMLOutputElement> {
+    fn super_type<'a>(&'a self) -> Option<&'a VirtualMethods> {
+        let htmlelement: &JSRef<HTMLElement> = HTMLElementCast::from_borrowed_ref(self);
+        Some(htmlelement as &VirtualMethods)
+    }
+
+    fn parse_plain_attribute(&self, name: &str, value: DOMString) -> AttrValue {
+        match name {
+            "for" => AttrValue::from_tokenlist(value),
+            _ => self.super_type().unwrap().parse_plain_attribute(name, value),
+        }
+    }
 }
 
 impl Reflectable for HTMLOutputElement {
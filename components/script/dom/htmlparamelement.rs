@@ -3,11 +3,13 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::bindings::codegen::Bindings::HTMLParamElementBinding;
+use dom::bindings::codegen::Bindings::HTMLParamElementBinding::HTMLParamElementMethods;
+use dom::bindings::codegen::InheritTypes::ElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLParamElementDerived;
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
-use dom::element::HTMLParamElementTypeId;
+use dom::element::{AttributeHandlers, Element, HTMLParamElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
 use dom::node::{Node, ElementNodeTypeId};
@@ -39,6 +41,26 @@ impl HTMLParamElement {
     }
 }
 
+impl<'a> HTMLParamElementMethods for JSRef<'a, HTMLParamElement> {
+    // http://www.whatwg.org/html/#dom-param-name
+    make_getter!(Name)
+
+    // http://www.whatwg.org/html/#dom-param-name
+    fn SetName(self, name: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("name", name)
+    }
+
+    // http://www.whatwg.org/html/#dom-param-value
+    make_getter!(Value)
+
+    // http://www.whatwg.org/html/#dom-param-value
+    fn SetValue(self, value: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("value", value)
+    }
+}
+
 impl Reflectable for HTMLParamElement {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.htmlelement.reflector()
@@ -0,0 +1,96 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::HTMLPictureElementBinding;
+use dom::bindings::codegen::InheritTypes::{HTMLElementCast, HTMLImageElementCast, NodeCast};
+use dom::bindings::codegen::InheritTypes::HTMLPictureElementDerived;
+use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::utils::{Reflectable, Reflector};
+use dom::document::Document;
+use dom::element::HTMLPictureElementTypeId;
+use dom::eventtarget::{EventTarget, NodeTargetTypeId};
+use dom::htmlelement::HTMLElement;
+use dom::htmlimageelement::HTMLImageElementHelpers;
+use dom::node::{Node, NodeHelpers, ElementNodeTypeId};
+use dom::virtualmethods::VirtualMethods;
+
+use servo_util::str::DOMString;
+
+#[jstraceable]
+#[must_root]
+pub struct HTMLPictureElement {
+    pub htmlelement: HTMLElement
+}
+
+impl HTMLPictureElementDerived for EventTarget {
+    fn is_htmlpictureelement(&self) -> bool {
+        self.type_id == NodeTargetTypeId(ElementNodeTypeId(HTMLPictureElementTypeId))
+    }
+}
+
+impl HTMLPictureElement {
+    fn new_inherited(localName: DOMString, document: JSRef<Document>) -> HTMLPictureElement {
+        HTMLPictureElement {
+            htmlelement: HTMLElement::new_inherited(HTMLPictureElementTypeId, localName, document)
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(localName: DOMString, document: JSRef<Document>) -> Temporary<HTMLPictureElement> {
+        let element = HTMLPictureElement::new_inherited(localName, document);
+        Node::reflect_node(box element, document, HTMLPictureElementBinding::Wrap)
+    }
+}
+
+pub trait HTMLPictureElementHelpers {
+    fn sources_changed(self);
+}
+
+impl<'a> HTMLPictureElementHelpers for JSRef<'a, HTMLPictureElement> {
+    /// Makes the img children of this picture element choose their image
+    /// again, following a change to its source elements.
+    /// http://www.whatwg.org/html/#relevant-mutations
+    fn sources_changed(self) {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        for img in node.children().filter_map(|child| HTMLImageElementCast::to_ref(child)) {
+            img.reselect_image_source();
+        }
+    }
+}
+
+impl<'a> VirtualMethods for JSRef<'a, HTMLPictureElement> {
+    fn super_type<'a>(&'a self) -> Option<&'a VirtualMethods> {
+        let htmlelement: &JSRef<HTMLElement> = HTMLElementCast::from_borrowed_ref(self);
+        Some(htmlelement as &VirtualMethods)
+    }
+
+    fn child_inserted(&self, child: JSRef<Node>) {
+        match self.super_type() {
+            Some(ref s) => s.child_inserted(child),
+            _ => (),
+        }
+
+        self.sources_changed();
+    }
+
+    fn child_removed(&self, child: JSRef<Node>) {
+        match self.super_type() {
+            Some(ref s) => s.child_removed(child),
+            _ => (),
+        }
+
+        // The img left the picture, and falls back on its own sources.
+        match HTMLImageElementCast::to_ref(child) {
+            Some(img) => img.reselect_image_source(),
+            None => (),
+        }
+        self.sources_changed();
+    }
+}
+
+impl Reflectable for HTMLPictureElement {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        self.htmlelement.reflector()
+    }
+}
@@ -3,14 +3,21 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::bindings::codegen::Bindings::HTMLProgressElementBinding;
+use dom::bindings::codegen::Bindings::HTMLProgressElementBinding::HTMLProgressElementMethods;
+use dom::bindings::codegen::InheritTypes::{ElementCast, HTMLElementCast, NodeCast};
 use dom::bindings::codegen::InheritTypes::HTMLProgressElementDerived;
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
-use dom::element::HTMLProgressElementTypeId;
+use dom::element::{AttributeHandlers, Element, HTMLProgressElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
-use dom::node::{Node, ElementNodeTypeId};
+use dom::htmllabelelement::LabelableElementHelpers;
+use dom::node::{Node, NodeHelpers, ElementNodeTypeId};
+use dom::nodelist::NodeList;
+use dom::virtualmethods::VirtualMethods;
+
+use servo_util::atom::Atom;
 use servo_util::str::DOMString;
 
 #[jstraceable]
@@ -39,6 +46,111 @@ impl HTMLProgressElement {
     }
 }
 
+trait PrivateHTMLProgressElementHelpers {
+    fn maximum(self) -> f64;
+    fn current_value(self) -> f64;
+}
+
+impl<'a> PrivateHTMLProgressElementHelpers for JSRef<'a, HTMLProgressElement> {
+    // http://www.whatwg.org/html/#concept-progress-maximum
+    fn maximum(self) -> f64 {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        match element.get_float_attribute("max") {
+            Some(max) if max > 0.0 => max,
+            _ => 1.0,
+        }
+    }
+
+    // http://www.whatwg.org/html/#concept-progress-value
+    fn current_value(self) -> f64 {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        let value = match element.get_float_attribute("value") {
+            Some(value) if value >= 0.0 => value,
+            _ => 0.0,
+        };
+        let maximum = self.maximum();
+        if value > maximum { maximum } else { value }
+    }
+}
+
+impl<'a> HTMLProgressElementMethods for JSRef<'a, HTMLProgressElement> {
+    // http://www.whatwg.org/html/#dom-progress-value
+    fn Value(self) -> f64 {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        if node.get_indeterminate_state() {
+            return 0.0;
+        }
+        self.current_value()
+    }
+
+    // http://www.whatwg.org/html/#dom-progress-value
+    fn SetValue(self, value: f64) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_float_attribute("value", value)
+    }
+
+    // http://www.whatwg.org/html/#dom-progress-max
+    fn Max(self) -> f64 {
+        self.maximum()
+    }
+
+    // http://www.whatwg.org/html/#dom-progress-max
+    fn SetMax(self, max: f64) {
+        if max > 0.0 {
+            let element: JSRef<Element> = ElementCast::from_ref(self);
+            element.set_float_attribute("max", max)
+        }
+    }
+
+    // http://www.whatwg.org/html/#dom-progress-position
+    fn Position(self) -> f64 {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        if node.get_indeterminate_state() {
+            return -1.0;
+        }
+        self.current_value() / self.maximum()
+    }
+
+    // http://www.whatwg.org/html/#dom-lfe-labels
+    fn Labels(self) -> Temporary<NodeList> {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.labels()
+    }
+}
+
+impl<'a> VirtualMethods for JSRef<'a, HTMLProgressElement> {
+    fn super_type<'a>(&'a self) -> Option<&'a VirtualMethods> {
+        let htmlelement: &JSRef<HTMLElement> = HTMLElementCast::from_borrowed_ref(self);
+        Some(htmlelement as &VirtualMethods)
+    }
+
+    fn after_set_attr(&self, name: &Atom, value: DOMString) {
+        match self.super_type() {
+            Some(ref s) => s.after_set_attr(name, value.clone()),
+            _ => (),
+        }
+
+        let node: JSRef<Node> = NodeCast::from_ref(*self);
+        match name.as_slice() {
+            "value" => node.set_indeterminate_state(false),
+            _ => ()
+        }
+    }
+
+    fn before_remove_attr(&self, name: &Atom, value: DOMString) {
+        match self.super_type() {
+            Some(ref s) => s.before_remove_attr(name, value),
+            _ => (),
+        }
+
+        let node: JSRef<Node> = NodeCast::from_ref(*self);
+        match name.as_slice() {
+            "value" => node.set_indeterminate_state(true),
+            _ => ()
+        }
+    }
+}
+
 impl Reflectable for HTMLProgressElement {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.htmlelement.reflector()
@@ -3,11 +3,13 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::bindings::codegen::Bindings::HTMLQuoteElementBinding;
+use dom::bindings::codegen::Bindings::HTMLQuoteElementBinding::HTMLQuoteElementMethods;
+use dom::bindings::codegen::InheritTypes::ElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLQuoteElementDerived;
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
-use dom::element::HTMLQuoteElementTypeId;
+use dom::element::{AttributeHandlers, Element, HTMLQuoteElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
 use dom::node::{Node, ElementNodeTypeId};
@@ -39,6 +41,17 @@ impl HTMLQuoteElement {
     }
 }
 
+impl<'a> HTMLQuoteElementMethods for JSRef<'a, HTMLQuoteElement> {
+    // http://www.whatwg.org/html/#dom-quote-cite
+    make_url_getter!(Cite)
+
+    // http://www.whatwg.org/html/#dom-quote-cite
+    fn SetCite(self, cite: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_url_attribute("cite", cite)
+    }
+}
+
 impl Reflectable for HTMLQuoteElement {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.htmlelement.reflector()
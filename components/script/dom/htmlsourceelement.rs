@@ -3,14 +3,19 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::bindings::codegen::Bindings::HTMLSourceElementBinding;
-use dom::bindings::codegen::InheritTypes::HTMLSourceElementDerived;
-use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::codegen::Bindings::HTMLSourceElementBinding::HTMLSourceElementMethods;
+use dom::bindings::codegen::InheritTypes::{ElementCast, HTMLElementCast, HTMLPictureElementCast};
+use dom::bindings::codegen::InheritTypes::{HTMLSourceElementDerived, NodeCast};
+use dom::bindings::js::{JSRef, Temporary, OptionalRootable};
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
-use dom::element::HTMLSourceElementTypeId;
+use dom::element::{AttributeHandlers, Element, HTMLSourceElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
-use dom::node::{Node, ElementNodeTypeId};
+use dom::htmlpictureelement::HTMLPictureElementHelpers;
+use dom::node::{Node, NodeHelpers, ElementNodeTypeId};
+use dom::virtualmethods::VirtualMethods;
+use servo_util::atom::Atom;
 use servo_util::str::DOMString;
 
 #[jstraceable]
@@ -39,6 +44,102 @@ impl HTMLSourceElement {
     }
 }
 
+trait PrivateHTMLSourceElementHelpers {
+    fn notify_picture(self);
+}
+
+impl<'a> PrivateHTMLSourceElementHelpers for JSRef<'a, HTMLSourceElement> {
+    /// Lets the parent picture element, if any, choose its image again.
+    fn notify_picture(self) {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        match node.parent_node().root() {
+            Some(parent) => match HTMLPictureElementCast::to_ref(*parent) {
+                Some(picture) => picture.sources_changed(),
+                None => (),
+            },
+            None => (),
+        }
+    }
+}
+
+impl<'a> HTMLSourceElementMethods for JSRef<'a, HTMLSourceElement> {
+    // http://www.whatwg.org/html/#dom-source-src
+    make_url_getter!(Src)
+
+    // http://www.whatwg.org/html/#dom-source-src
+    fn SetSrc(self, src: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_url_attribute("src", src)
+    }
+
+    // http://www.whatwg.org/html/#dom-source-type
+    make_getter!(Type)
+
+    // http://www.whatwg.org/html/#dom-source-type
+    fn SetType(self, type_: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("type", type_)
+    }
+
+    // http://www.whatwg.org/html/#dom-source-srcset
+    make_getter!(Srcset)
+
+    // http://www.whatwg.org/html/#dom-source-srcset
+    fn SetSrcset(self, srcset: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("srcset", srcset)
+    }
+
+    // http://www.whatwg.org/html/#dom-source-sizes
+    make_getter!(Sizes)
+
+    // http://www.whatwg.org/html/#dom-source-sizes
+    fn SetSizes(self, sizes: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("sizes", sizes)
+    }
+
+    // http://www.whatwg.org/html/#dom-source-media
+    make_getter!(Media)
+
+    // http://www.whatwg.org/html/#dom-source-media
+    fn SetMedia(self, media: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("media", media)
+    }
+}
+
+impl<'a> VirtualMethods for JSRef<'a, HTMLSourceElement> {
+    fn super_type<'a>(&'a self) -> Option<&'a VirtualMethods> {
+        let htmlelement: &JSRef<HTMLElement> = HTMLElementCast::from_borrowed_ref(self);
+        Some(htmlelement as &VirtualMethods)
+    }
+
+    fn after_set_attr(&self, name: &Atom, value: DOMString) {
+        match self.super_type() {
+            Some(ref s) => s.after_set_attr(name, value),
+            _ => (),
+        }
+
+        match name.as_slice() {
+            "srcset" | "sizes" | "media" | "type" => self.notify_picture(),
+            _ => (),
+        }
+    }
+
+    fn after_remove_attr(&self, name: &Atom) {
+        match self.super_type() {
+            Some(ref s) => s.after_remove_attr(name),
+            _ => (),
+        }
+
+        match name.as_slice() {
+            "srcset" | "sizes" | "media" | "type" => self.notify_picture(),
+            _ => (),
+        }
+    }
+}
+
 impl Reflectable for HTMLSourceElement {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.htmlelement.reflector()
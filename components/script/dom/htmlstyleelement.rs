@@ -7,16 +7,20 @@ use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
 use dom::bindings::codegen::InheritTypes::{HTMLElementCast, HTMLStyleElementDerived, NodeCast};
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector};
-use dom::document::Document;
+use dom::document::{Document, DocumentHelpers};
 use dom::element::HTMLStyleElementTypeId;
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
 use dom::node::{Node, NodeHelpers, ElementNodeTypeId, window_from_node};
 use dom::virtualmethods::VirtualMethods;
-use layout_interface::{AddStylesheetMsg, LayoutChan};
+use html::cssparse::{InlineProvenance, parsed_stylesheet, spawn_css_parser};
 use servo_util::str::DOMString;
 use style::Stylesheet;
 
+/// The length of the text above which a `<style>` element is parsed on a
+/// task of its own; smaller ones aren't worth spawning for.
+static ASYNC_PARSE_THRESHOLD: uint = 32 * 1024;
+
 #[jstraceable]
 #[must_root]
 pub struct HTMLStyleElement {
@@ -52,13 +56,23 @@ impl<'a> StyleElementHelpers for JSRef<'a, HTMLStyleElement> {
         let node: JSRef<Node> = NodeCast::from_ref(self);
         assert!(node.is_in_doc());
 
+        // Stylesheets of documents without a browsing context apply to nothing.
+        let document = node.owner_doc().root();
+        if !document.has_browsing_context() {
+            return;
+        }
+
         let win = window_from_node(node).root();
-        let url = win.deref().page().get_url();
+        let page = win.deref().page();
+        let url = page.get_url();
 
         let data = node.GetTextContent().expect("Element.textContent must be a string");
-        let sheet = Stylesheet::from_str(data.as_slice(), url);
-        let LayoutChan(ref layout_chan) = *win.deref().page().layout_chan;
-        layout_chan.send(AddStylesheetMsg(sheet));
+        let sheet = if data.len() > ASYNC_PARSE_THRESHOLD {
+            spawn_css_parser(InlineProvenance(url, data), win.deref().script_chan.clone(), page.id)
+        } else {
+            parsed_stylesheet(Stylesheet::from_str(data.as_slice(), url))
+        };
+        page.add_stylesheet(sheet);
     }
 }
 
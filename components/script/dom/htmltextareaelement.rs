@@ -2,26 +2,42 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use dom::bindings::codegen::Bindings::HTMLInputElementBinding::{SelectionMode, SelectionModeValues};
 use dom::bindings::codegen::Bindings::HTMLTextAreaElementBinding;
 use dom::bindings::codegen::Bindings::HTMLTextAreaElementBinding::HTMLTextAreaElementMethods;
-use dom::bindings::codegen::InheritTypes::{ElementCast, HTMLElementCast, NodeCast};
+use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
+use dom::bindings::codegen::InheritTypes::{ElementCast, EventTargetCast, HTMLElementCast, NodeCast};
 use dom::bindings::codegen::InheritTypes::{HTMLTextAreaElementDerived, HTMLFieldSetElementDerived};
+use dom::bindings::error::ErrorResult;
 use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::trace::Traceable;
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
 use dom::element::{AttributeHandlers, Element, HTMLTextAreaElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
-use dom::node::{DisabledStateHelpers, Node, NodeHelpers, ElementNodeTypeId};
+use dom::htmllabelelement::LabelableElementHelpers;
+use dom::node::{DisabledStateHelpers, Node, NodeHelpers, ElementNodeTypeId, window_from_node};
+use dom::nodelist::NodeList;
 use dom::virtualmethods::VirtualMethods;
+use dom::window::WindowHelpers;
+use textinput::{SelectionDirection, SelectionNone, TextSelection, utf16_length};
 
 use servo_util::atom::Atom;
+use servo_util::memory::HeapSizeOf;
 use servo_util::str::DOMString;
 
+use std::cell::{Cell, RefCell};
+
 #[jstraceable]
 #[must_root]
 pub struct HTMLTextAreaElement {
     pub htmlelement: HTMLElement,
+    /// The raw value, which is only used once the dirty value flag is set;
+    /// until then the raw value is the text content.
+    value: Traceable<RefCell<DOMString>>,
+    value_changed: Cell<bool>,
+    selection: TextSelection,
 }
 
 impl HTMLTextAreaElementDerived for EventTarget {
@@ -33,7 +49,10 @@ impl HTMLTextAreaElementDerived for EventTarget {
 impl HTMLTextAreaElement {
     fn new_inherited(localName: DOMString, document: JSRef<Document>) -> HTMLTextAreaElement {
         HTMLTextAreaElement {
-            htmlelement: HTMLElement::new_inherited(HTMLTextAreaElementTypeId, localName, document)
+            htmlelement: HTMLElement::new_inherited(HTMLTextAreaElementTypeId, localName, document),
+            value: Traceable::new(RefCell::new("".to_string())),
+            value_changed: Cell::new(false),
+            selection: TextSelection::new(),
         }
     }
 
@@ -44,6 +63,38 @@ impl HTMLTextAreaElement {
     }
 }
 
+/// Normalizes the line breaks of `value` to LF, as the API value of textarea
+/// elements does.
+fn normalize_line_breaks(value: &str) -> DOMString {
+    value.replace("\r\n", "\n").replace("\r", "\n")
+}
+
+pub trait HTMLTextAreaElementHelpers {
+    fn reset(self);
+}
+
+impl<'a> HTMLTextAreaElementHelpers for JSRef<'a, HTMLTextAreaElement> {
+    // http://www.whatwg.org/html/#the-textarea-element:concept-form-reset-control
+    fn reset(self) {
+        self.value_changed.set(false);
+        *self.value.deref().borrow_mut() = "".to_string();
+        self.selection.clamp(utf16_length(self.Value().as_slice()));
+    }
+}
+
+trait PrivateHTMLTextAreaElementHelpers {
+    fn value_length(self) -> u32;
+}
+
+impl<'a> PrivateHTMLTextAreaElementHelpers for JSRef<'a, HTMLTextAreaElement> {
+    /// Returns the length of the value, keeping the selection within it.
+    fn value_length(self) -> u32 {
+        let length = utf16_length(self.Value().as_slice());
+        self.selection.clamp(length);
+        length
+    }
+}
+
 impl<'a> HTMLTextAreaElementMethods for JSRef<'a, HTMLTextAreaElement> {
     // http://www.whatwg.org/html/#dom-fe-disabled
     make_bool_getter!(Disabled)
@@ -53,6 +104,123 @@ impl<'a> HTMLTextAreaElementMethods for JSRef<'a, HTMLTextAreaElement> {
         let elem: JSRef<Element> = ElementCast::from_ref(self);
         elem.set_bool_attribute("disabled", disabled)
     }
+
+    // http://www.whatwg.org/html/#dom-textarea-type
+    fn Type(self) -> DOMString {
+        "textarea".to_string()
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea-defaultvalue
+    fn DefaultValue(self) -> DOMString {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        node.GetTextContent().unwrap()
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea-defaultvalue
+    fn SetDefaultValue(self, default_value: DOMString) {
+        let node: JSRef<Node> = NodeCast::from_ref(self);
+        node.SetTextContent(Some(default_value))
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea-value
+    fn Value(self) -> DOMString {
+        if self.value_changed.get() {
+            normalize_line_breaks(self.value.deref().borrow().as_slice())
+        } else {
+            normalize_line_breaks(self.DefaultValue().as_slice())
+        }
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea-value
+    fn SetValue(self, value: DOMString) {
+        self.selection.clamp(utf16_length(normalize_line_breaks(value.as_slice()).as_slice()));
+        *self.value.deref().borrow_mut() = value;
+        self.value_changed.set(true);
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea-textlength
+    fn TextLength(self) -> u32 {
+        utf16_length(self.Value().as_slice())
+    }
+
+    // http://www.whatwg.org/html/#dom-lfe-labels
+    fn Labels(self) -> Temporary<NodeList> {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.labels()
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-select
+    fn Select(self) {
+        self.selection.select_all(self.value_length());
+        let window = window_from_node(self).root();
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        window.queue_simple_event(target, "select".to_string());
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-selectionstart
+    fn SelectionStart(self) -> u32 {
+        self.value_length();
+        self.selection.start()
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-selectionstart
+    fn SetSelectionStart(self, start: u32) {
+        let length = self.value_length();
+        let end = if self.selection.end() < start { start } else { self.selection.end() };
+        self.selection.set_range(start, end, self.selection.direction(), length);
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-selectionend
+    fn SelectionEnd(self) -> u32 {
+        self.value_length();
+        self.selection.end()
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-selectionend
+    fn SetSelectionEnd(self, end: u32) {
+        let length = self.value_length();
+        self.selection.set_range(self.selection.start(), end, self.selection.direction(), length);
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-selectiondirection
+    fn SelectionDirection(self) -> DOMString {
+        self.selection.direction().to_dom_string()
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-selectiondirection
+    fn SetSelectionDirection(self, direction: DOMString) {
+        let length = self.value_length();
+        self.selection.set_range(self.selection.start(), self.selection.end(),
+                                 SelectionDirection::from_dom_string(direction.as_slice()),
+                                 length);
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-setrangetext
+    fn SetRangeText(self, replacement: DOMString) -> ErrorResult {
+        self.value_length();
+        let (start, end) = (self.selection.start(), self.selection.end());
+        self.SetRangeText_(replacement, start, end, SelectionModeValues::Preserve)
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-setrangetext
+    fn SetRangeText_(self, replacement: DOMString, start: u32, end: u32,
+                     selection_mode: SelectionMode) -> ErrorResult {
+        self.value_length();
+        let value = try!(self.selection.set_range_text(self.Value().as_slice(),
+                                                       replacement.as_slice(),
+                                                       start, end, selection_mode));
+        self.SetValue(value);
+        Ok(())
+    }
+
+    // http://www.whatwg.org/html/#dom-textarea/input-setselectionrange
+    fn SetSelectionRange(self, start: u32, end: u32, direction: Option<DOMString>) {
+        let length = self.value_length();
+        let direction = direction.map_or(SelectionNone, |direction| {
+            SelectionDirection::from_dom_string(direction.as_slice())
+        });
+        self.selection.set_range(start, end, direction, length);
+    }
 }
 
 impl<'a> VirtualMethods for JSRef<'a, HTMLTextAreaElement> {
@@ -61,6 +229,14 @@ impl<'a> VirtualMethods for JSRef<'a, HTMLTextAreaElement> {
         Some(htmlelement as &VirtualMethods)
     }
 
+    fn heap_size_of_strings(&self) -> uint {
+        let inherited = match self.super_type() {
+            Some(ref s) => s.heap_size_of_strings(),
+            _ => 0,
+        };
+        inherited + self.value.deref().heap_size_of_children()
+    }
+
     fn after_set_attr(&self, name: &Atom, value: DOMString) {
         match self.super_type() {
             Some(ref s) => s.after_set_attr(name, value.clone()),
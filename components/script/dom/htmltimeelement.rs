@@ -3,11 +3,13 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::bindings::codegen::Bindings::HTMLTimeElementBinding;
+use dom::bindings::codegen::Bindings::HTMLTimeElementBinding::HTMLTimeElementMethods;
+use dom::bindings::codegen::InheritTypes::ElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLTimeElementDerived;
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
-use dom::element::HTMLTimeElementTypeId;
+use dom::element::{AttributeHandlers, Element, HTMLTimeElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
 use dom::node::{Node, ElementNodeTypeId};
@@ -39,6 +41,17 @@ impl HTMLTimeElement {
     }
 }
 
+impl<'a> HTMLTimeElementMethods for JSRef<'a, HTMLTimeElement> {
+    // http://www.whatwg.org/html/#dom-time-datetime
+    make_getter!(DateTime)
+
+    // http://www.whatwg.org/html/#dom-time-datetime
+    fn SetDateTime(self, date_time: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("datetime", date_time)
+    }
+}
+
 impl Reflectable for HTMLTimeElement {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.htmlelement.reflector()
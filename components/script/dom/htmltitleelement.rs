@@ -5,15 +5,17 @@
 use dom::bindings::codegen::Bindings::HTMLTitleElementBinding;
 use dom::bindings::codegen::Bindings::HTMLTitleElementBinding::HTMLTitleElementMethods;
 use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
-use dom::bindings::codegen::InheritTypes::{HTMLTitleElementDerived, NodeCast, TextCast};
+use dom::bindings::codegen::InheritTypes::{HTMLElementCast, HTMLTitleElementDerived, NodeCast};
+use dom::bindings::codegen::InheritTypes::TextCast;
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector};
-use dom::document::Document;
+use dom::document::{Document, DocumentHelpers};
 use dom::element::HTMLTitleElementTypeId;
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
-use dom::node::{Node, NodeHelpers, ElementNodeTypeId};
+use dom::node::{Node, NodeHelpers, ElementNodeTypeId, document_from_node};
 use dom::text::Text;
+use dom::virtualmethods::VirtualMethods;
 use servo_util::str::DOMString;
 
 #[jstraceable]
@@ -64,6 +66,79 @@ impl<'a> HTMLTitleElementMethods for JSRef<'a, HTMLTitleElement> {
     }
 }
 
+impl<'a> VirtualMethods for JSRef<'a, HTMLTitleElement> {
+    fn super_type<'a>(&'a self) -> Option<&'a VirtualMethods> {
+        let htmlelement: &JSRef<HTMLElement> = HTMLElementCast::from_borrowed_ref(self);
+        Some(htmlelement as &VirtualMethods)
+    }
+
+    fn child_inserted(&self, child: JSRef<Node>) {
+        match self.super_type() {
+            Some(ref s) => s.child_inserted(child),
+            _ => (),
+        }
+
+        // The parser appends the text of a title element as it arrives, so
+        // this tells the embedder about the new title without waiting for
+        // the rest of the document.
+        let node: JSRef<Node> = NodeCast::from_ref(*self);
+        if node.is_in_doc() {
+            let document = document_from_node(*self).root();
+            document.title_changed();
+        }
+    }
+
+    fn child_removed(&self, child: JSRef<Node>) {
+        match self.super_type() {
+            Some(ref s) => s.child_removed(child),
+            _ => (),
+        }
+
+        let node: JSRef<Node> = NodeCast::from_ref(*self);
+        if node.is_in_doc() {
+            let document = document_from_node(*self).root();
+            document.title_changed();
+        }
+    }
+
+    fn child_data_changed(&self, child: JSRef<Node>) {
+        match self.super_type() {
+            Some(ref s) => s.child_data_changed(child),
+            _ => (),
+        }
+
+        let node: JSRef<Node> = NodeCast::from_ref(*self);
+        if node.is_in_doc() {
+            let document = document_from_node(*self).root();
+            document.title_changed();
+        }
+    }
+
+    fn bind_to_tree(&self, tree_in_doc: bool) {
+        match self.super_type() {
+            Some(ref s) => s.bind_to_tree(tree_in_doc),
+            _ => (),
+        }
+
+        if tree_in_doc {
+            let document = document_from_node(*self).root();
+            document.title_changed();
+        }
+    }
+
+    fn unbind_from_tree(&self, tree_in_doc: bool) {
+        match self.super_type() {
+            Some(ref s) => s.unbind_from_tree(tree_in_doc),
+            _ => (),
+        }
+
+        if tree_in_doc {
+            let document = document_from_node(*self).root();
+            document.title_changed();
+        }
+    }
+}
+
 impl Reflectable for HTMLTitleElement {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.htmlelement.reflector()
@@ -3,16 +3,19 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::bindings::codegen::Bindings::HTMLTrackElementBinding;
-use dom::bindings::codegen::InheritTypes::HTMLTrackElementDerived;
+use dom::bindings::codegen::Bindings::HTMLTrackElementBinding::{HTMLTrackElementConstants, HTMLTrackElementMethods};
+use dom::bindings::codegen::InheritTypes::{ElementCast, HTMLTrackElementDerived};
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
-use dom::element::HTMLTrackElementTypeId;
+use dom::element::{AttributeHandlers, Element, HTMLTrackElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlelement::HTMLElement;
 use dom::node::{Node, ElementNodeTypeId};
 use servo_util::str::DOMString;
 
+use std::ascii::StrAsciiExt;
+
 #[jstraceable]
 #[must_root]
 pub struct HTMLTrackElement {
@@ -39,6 +42,71 @@ impl HTMLTrackElement {
     }
 }
 
+impl<'a> HTMLTrackElementMethods for JSRef<'a, HTMLTrackElement> {
+    // http://www.whatwg.org/html/#dom-track-kind
+    fn Kind(self) -> DOMString {
+        // The missing value default is subtitles, and the invalid value
+        // default is metadata.
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        if !element.has_attribute("kind") {
+            return "subtitles".to_string();
+        }
+        let kind = element.get_string_attribute("kind").as_slice().to_ascii_lower();
+        match kind.as_slice() {
+            "subtitles" | "captions" | "descriptions" | "chapters" | "metadata" => kind.clone(),
+            _ => "metadata".to_string(),
+        }
+    }
+
+    // http://www.whatwg.org/html/#dom-track-kind
+    fn SetKind(self, kind: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("kind", kind)
+    }
+
+    // http://www.whatwg.org/html/#dom-track-src
+    make_url_getter!(Src)
+
+    // http://www.whatwg.org/html/#dom-track-src
+    fn SetSrc(self, src: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_url_attribute("src", src)
+    }
+
+    // http://www.whatwg.org/html/#dom-track-srclang
+    make_getter!(Srclang)
+
+    // http://www.whatwg.org/html/#dom-track-srclang
+    fn SetSrclang(self, srclang: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("srclang", srclang)
+    }
+
+    // http://www.whatwg.org/html/#dom-track-label
+    make_getter!(Label)
+
+    // http://www.whatwg.org/html/#dom-track-label
+    fn SetLabel(self, label: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_string_attribute("label", label)
+    }
+
+    // http://www.whatwg.org/html/#dom-track-default
+    make_bool_getter!(Default)
+
+    // http://www.whatwg.org/html/#dom-track-default
+    fn SetDefault(self, default: bool) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_bool_attribute("default", default)
+    }
+
+    // http://www.whatwg.org/html/#dom-track-readystate
+    fn ReadyState(self) -> u16 {
+        // Text tracks are never loaded, since nothing would render them.
+        HTMLTrackElementConstants::NONE
+    }
+}
+
 impl Reflectable for HTMLTrackElement {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.htmlelement.reflector()
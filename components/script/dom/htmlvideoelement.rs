@@ -2,15 +2,18 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use dom::attr::AttrValue;
 use dom::bindings::codegen::Bindings::HTMLVideoElementBinding;
-use dom::bindings::codegen::InheritTypes::HTMLVideoElementDerived;
+use dom::bindings::codegen::Bindings::HTMLVideoElementBinding::HTMLVideoElementMethods;
+use dom::bindings::codegen::InheritTypes::{ElementCast, HTMLMediaElementCast, HTMLVideoElementDerived};
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::document::Document;
-use dom::element::HTMLVideoElementTypeId;
+use dom::element::{AttributeHandlers, Element, HTMLVideoElementTypeId};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
 use dom::htmlmediaelement::HTMLMediaElement;
 use dom::node::{Node, ElementNodeTypeId};
+use dom::virtualmethods::VirtualMethods;
 use servo_util::str::DOMString;
 
 #[jstraceable]
@@ -39,6 +42,60 @@ impl HTMLVideoElement {
     }
 }
 
+impl<'a> HTMLVideoElementMethods for JSRef<'a, HTMLVideoElement> {
+    // http://www.whatwg.org/html/#dom-dim-width
+    make_uint_getter!(Width)
+
+    // http://www.whatwg.org/html/#dom-dim-width
+    fn SetWidth(self, width: u32) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_uint_attribute("width", width)
+    }
+
+    // http://www.whatwg.org/html/#dom-dim-height
+    make_uint_getter!(Height)
+
+    // http://www.whatwg.org/html/#dom-dim-height
+    fn SetHeight(self, height: u32) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_uint_attribute("height", height)
+    }
+
+    // http://www.whatwg.org/html/#dom-video-videowidth
+    fn VideoWidth(self) -> u32 {
+        // Nothing is decoded, so the intrinsic size is never known.
+        0
+    }
+
+    // http://www.whatwg.org/html/#dom-video-videoheight
+    fn VideoHeight(self) -> u32 {
+        0
+    }
+
+    // http://www.whatwg.org/html/#dom-video-poster
+    make_url_getter!(Poster)
+
+    // http://www.whatwg.org/html/#dom-video-poster
+    fn SetPoster(self, poster: DOMString) {
+        let element: JSRef<Element> = ElementCast::from_ref(self);
+        element.set_url_attribute("poster", poster)
+    }
+}
+
+impl<'a> VirtualMethods for JSRef<'a, HTMLVideoElement> {
+    fn super_type<'a>(&'a self) -> Option<&'a VirtualMethods> {
+        let htmlmediaelement: &JSRef<HTMLMediaElement> = HTMLMediaElementCast::from_borrowed_ref(self);
+        Some(htmlmediaelement as &VirtualMethods)
+    }
+
+    fn parse_plain_attribute(&self, name: &str, value: DOMString) -> AttrValue {
+        match name {
+            "width" | "height" => AttrValue::from_u32(value, 0),
+            _ => self.super_type().unwrap().parse_plain_attribute(name, value),
+        }
+    }
+}
+
 impl Reflectable for HTMLVideoElement {
     fn reflector<'a>(&'a self) -> &'a Reflector {
         self.htmlmediaelement.reflector()
@@ -0,0 +1,127 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::KeyboardEventBinding;
+use dom::bindings::codegen::Bindings::KeyboardEventBinding::KeyboardEventMethods;
+use dom::bindings::codegen::Bindings::UIEventBinding::UIEventMethods;
+use dom::bindings::codegen::InheritTypes::{UIEventCast, KeyboardEventDerived};
+use dom::bindings::error::Fallible;
+use dom::bindings::global::GlobalRef;
+use dom::bindings::global;
+use dom::bindings::js::{JSRef, RootedReference, Temporary};
+use dom::bindings::trace::Traceable;
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::event::{Event, KeyboardEventTypeId};
+use dom::uievent::UIEvent;
+use dom::window::Window;
+use servo_util::str::DOMString;
+use std::cell::{Cell, RefCell};
+
+#[jstraceable]
+#[must_root]
+pub struct KeyboardEvent {
+    pub uievent: UIEvent,
+    key: Traceable<RefCell<DOMString>>,
+    ctrl_key: Traceable<Cell<bool>>,
+    shift_key: Traceable<Cell<bool>>,
+    alt_key: Traceable<Cell<bool>>,
+    meta_key: Traceable<Cell<bool>>,
+    repeat: Traceable<Cell<bool>>,
+}
+
+impl KeyboardEventDerived for Event {
+    fn is_keyboardevent(&self) -> bool {
+        self.type_id == KeyboardEventTypeId
+    }
+}
+
+impl KeyboardEvent {
+    fn new_inherited() -> KeyboardEvent {
+        KeyboardEvent {
+            uievent: UIEvent::new_inherited(KeyboardEventTypeId),
+            key: Traceable::new(RefCell::new("".to_string())),
+            ctrl_key: Traceable::new(Cell::new(false)),
+            shift_key: Traceable::new(Cell::new(false)),
+            alt_key: Traceable::new(Cell::new(false)),
+            meta_key: Traceable::new(Cell::new(false)),
+            repeat: Traceable::new(Cell::new(false)),
+        }
+    }
+
+    pub fn new_uninitialized(window: JSRef<Window>) -> Temporary<KeyboardEvent> {
+        reflect_dom_object(box KeyboardEvent::new_inherited(),
+                           &global::Window(window),
+                           KeyboardEventBinding::Wrap)
+    }
+
+    pub fn new(window: JSRef<Window>,
+               type_: DOMString,
+               canBubble: bool,
+               cancelable: bool,
+               view: Option<JSRef<Window>>,
+               detail: i32,
+               key: DOMString,
+               ctrlKey: bool,
+               altKey: bool,
+               shiftKey: bool,
+               metaKey: bool,
+               repeat: bool) -> Temporary<KeyboardEvent> {
+        let ev = KeyboardEvent::new_uninitialized(window).root();
+        let uievent: JSRef<UIEvent> = UIEventCast::from_ref(*ev);
+        uievent.InitUIEvent(type_, canBubble, cancelable, view, detail);
+        *ev.key.deref().borrow_mut() = key;
+        ev.ctrl_key.deref().set(ctrlKey);
+        ev.alt_key.deref().set(altKey);
+        ev.shift_key.deref().set(shiftKey);
+        ev.meta_key.deref().set(metaKey);
+        ev.repeat.deref().set(repeat);
+        Temporary::from_rooted(*ev)
+    }
+
+    pub fn Constructor(global: &GlobalRef,
+                       type_: DOMString,
+                       init: &KeyboardEventBinding::KeyboardEventInit) -> Fallible<Temporary<KeyboardEvent>> {
+        let event = KeyboardEvent::new(global.as_window(), type_,
+                                       init.parent.parent.bubbles,
+                                       init.parent.parent.cancelable,
+                                       init.parent.view.root_ref(),
+                                       init.parent.detail,
+                                       init.key.clone(), init.ctrlKey,
+                                       init.altKey, init.shiftKey, init.metaKey,
+                                       init.repeat);
+        Ok(event)
+    }
+}
+
+impl<'a> KeyboardEventMethods for JSRef<'a, KeyboardEvent> {
+    fn Key(self) -> DOMString {
+        self.key.deref().borrow().clone()
+    }
+
+    fn CtrlKey(self) -> bool {
+        self.ctrl_key.deref().get()
+    }
+
+    fn ShiftKey(self) -> bool {
+        self.shift_key.deref().get()
+    }
+
+    fn AltKey(self) -> bool {
+        self.alt_key.deref().get()
+    }
+
+    fn MetaKey(self) -> bool {
+        self.meta_key.deref().get()
+    }
+
+    fn Repeat(self) -> bool {
+        self.repeat.deref().get()
+    }
+}
+
+impl Reflectable for KeyboardEvent {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        self.uievent.reflector()
+    }
+}
@@ -4,13 +4,15 @@
 
 use dom::bindings::codegen::Bindings::LocationBinding;
 use dom::bindings::codegen::Bindings::LocationBinding::LocationMethods;
+use dom::bindings::error::ErrorResult;
 use dom::bindings::global;
 use dom::bindings::js::{JSRef, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
 use dom::urlhelper::UrlHelper;
-use dom::window::Window;
+use dom::window::{Window, WindowHelpers};
 use page::Page;
 
+use servo_net::referrer_policy::NoReferrerWhenDowngrade;
 use servo_util::str::DOMString;
 
 use std::rc::Rc;
@@ -42,6 +44,14 @@ impl<'a> LocationMethods for JSRef<'a, Location> {
         UrlHelper::Href(&self.page.get_url())
     }
 
+    // http://www.whatwg.org/html/#dom-location-href
+    fn SetHref(self, href: DOMString) -> ErrorResult {
+        match *self.page.frame() {
+            Some(ref frame) => frame.window.root().load_url(href, NoReferrerWhenDowngrade),
+            None => Ok(()),
+        }
+    }
+
     fn Search(self) -> DOMString {
         UrlHelper::Search(&self.page.get_url())
     }
@@ -28,6 +28,19 @@ macro_rules! make_bool_getter(
     );
 )
 
+#[macro_export]
+macro_rules! make_url_getter(
+    ( $attr:ident ) => (
+        fn $attr(self) -> DOMString {
+            use dom::element::{Element, AttributeHandlers};
+            use dom::bindings::codegen::InheritTypes::ElementCast;
+            use std::ascii::StrAsciiExt;
+            let element: JSRef<Element> = ElementCast::from_ref(self);
+            element.get_url_attribute(stringify!($attr).to_ascii_lower().as_slice())
+        }
+    );
+)
+
 #[macro_export]
 macro_rules! make_uint_getter(
     ( $attr:ident ) => (
@@ -41,6 +54,69 @@ macro_rules! make_uint_getter(
     );
 )
 
+/// Defines the getter and setter of an event handler IDL attribute that lives
+/// on the object itself. The setter calls `$after_set` on the object once the
+/// handler is set, if given.
+#[macro_export]
+macro_rules! event_handler(
+    ( $event_type:ident, $getter:ident, $setter:ident ) => (
+        fn $getter(self) -> Option<EventHandlerNonNull> {
+            use dom::bindings::codegen::InheritTypes::EventTargetCast;
+            use dom::eventtarget::{EventTarget, EventTargetHelpers};
+            let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+            eventtarget.get_event_handler_common(stringify!($event_type))
+        }
+
+        fn $setter(self, listener: Option<EventHandlerNonNull>) {
+            use dom::bindings::codegen::InheritTypes::EventTargetCast;
+            use dom::eventtarget::{EventTarget, EventTargetHelpers};
+            let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+            eventtarget.set_event_handler_common(stringify!($event_type), listener)
+        }
+    );
+    ( $event_type:ident, $getter:ident, $setter:ident, $after_set:ident ) => (
+        fn $getter(self) -> Option<EventHandlerNonNull> {
+            use dom::bindings::codegen::InheritTypes::EventTargetCast;
+            use dom::eventtarget::{EventTarget, EventTargetHelpers};
+            let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+            eventtarget.get_event_handler_common(stringify!($event_type))
+        }
+
+        fn $setter(self, listener: Option<EventHandlerNonNull>) {
+            use dom::bindings::codegen::InheritTypes::EventTargetCast;
+            use dom::eventtarget::{EventTarget, EventTargetHelpers};
+            let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+            eventtarget.set_event_handler_common(stringify!($event_type), listener);
+            self.$after_set();
+        }
+    );
+)
+
+/// Defines the getter and setter of an event handler IDL attribute of a body
+/// or frameset element, which forward to the handler of the Window.
+/// http://www.whatwg.org/html/#window-reflecting-body-element-event-handler-set
+#[macro_export]
+macro_rules! window_owned_event_handler(
+    ( $event_type:ident, $getter:ident, $setter:ident ) => (
+        fn $getter(self) -> Option<EventHandlerNonNull> {
+            use dom::bindings::codegen::InheritTypes::EventTargetCast;
+            use dom::eventtarget::{EventTarget, EventTargetHelpers};
+            use dom::node::window_from_node;
+            let window = window_from_node(self).root();
+            let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(*window);
+            eventtarget.get_event_handler_common(stringify!($event_type))
+        }
+
+        fn $setter(self, listener: Option<EventHandlerNonNull>) {
+            use dom::bindings::codegen::InheritTypes::EventTargetCast;
+            use dom::eventtarget::{EventTarget, EventTargetHelpers};
+            use dom::node::window_from_node;
+            let window = window_from_node(self).root();
+            let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(*window);
+            eventtarget.set_event_handler_common(stringify!($event_type), listener)
+        }
+    );
+)
 
 /// For use on non-jsmanaged types
 /// Use #[jstraceable] on JS managed types
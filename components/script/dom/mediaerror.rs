@@ -0,0 +1,45 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::MediaErrorBinding;
+use dom::bindings::codegen::Bindings::MediaErrorBinding::MediaErrorMethods;
+use dom::bindings::global;
+use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::window::Window;
+
+#[jstraceable]
+#[must_root]
+pub struct MediaError {
+    reflector_: Reflector,
+    code: u16,
+}
+
+impl MediaError {
+    fn new_inherited(code: u16) -> MediaError {
+        MediaError {
+            reflector_: Reflector::new(),
+            code: code,
+        }
+    }
+
+    pub fn new(window: JSRef<Window>, code: u16) -> Temporary<MediaError> {
+        reflect_dom_object(box MediaError::new_inherited(code),
+                           &global::Window(window),
+                           MediaErrorBinding::Wrap)
+    }
+}
+
+impl<'a> MediaErrorMethods for JSRef<'a, MediaError> {
+    // http://www.whatwg.org/html/#dom-mediaerror-code
+    fn Code(self) -> u16 {
+        self.code
+    }
+}
+
+impl Reflectable for MediaError {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        &self.reflector_
+    }
+}
@@ -6,13 +6,16 @@ use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
 use dom::bindings::codegen::Bindings::MessageEventBinding;
 use dom::bindings::codegen::Bindings::MessageEventBinding::MessageEventMethods;
 use dom::bindings::codegen::InheritTypes::{EventCast, MessageEventDerived};
+use dom::bindings::conversions::ToJSValConvertible;
 use dom::bindings::error::Fallible;
 use dom::bindings::global::GlobalRef;
-use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::js::{JS, JSRef, Temporary};
 use dom::bindings::trace::Traceable;
 use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
 use dom::event::{Event, MessageEventTypeId};
 use dom::eventtarget::{EventTarget, EventTargetHelpers};
+use dom::messageport::PortEndpoint;
+use dom::messageportlist::MessagePortList;
 
 use servo_util::str::DOMString;
 
@@ -26,6 +29,7 @@ pub struct MessageEvent {
     data: Traceable<JSVal>,
     origin: DOMString,
     lastEventId: DOMString,
+    ports: Option<JS<MessagePortList>>,
 }
 
 impl MessageEventDerived for Event {
@@ -35,13 +39,15 @@ impl MessageEventDerived for Event {
 }
 
 impl MessageEvent {
-    fn new_inherited(data: JSVal, origin: DOMString, lastEventId: DOMString)
+    fn new_inherited(data: JSVal, origin: DOMString, lastEventId: DOMString,
+                     ports: Option<JSRef<MessagePortList>>)
                          -> MessageEvent {
         MessageEvent {
             event: Event::new_inherited(MessageEventTypeId),
             data: Traceable::new(data),
             origin: origin,
             lastEventId: lastEventId,
+            ports: ports.map(|ports| JS::from_rooted(ports)),
         }
     }
 
@@ -49,7 +55,17 @@ impl MessageEvent {
                bubbles: bool, cancelable: bool,
                data: JSVal, origin: DOMString, lastEventId: DOMString)
                -> Temporary<MessageEvent> {
-        let ev = reflect_dom_object(box MessageEvent::new_inherited(data, origin, lastEventId),
+        MessageEvent::new_with_ports(global, type_, bubbles, cancelable,
+                                     data, origin, lastEventId, None)
+    }
+
+    fn new_with_ports(global: &GlobalRef, type_: DOMString,
+                      bubbles: bool, cancelable: bool,
+                      data: JSVal, origin: DOMString, lastEventId: DOMString,
+                      ports: Option<JSRef<MessagePortList>>)
+                      -> Temporary<MessageEvent> {
+        let ev = reflect_dom_object(box MessageEvent::new_inherited(data, origin, lastEventId,
+                                                                    ports),
                                     global,
                                     MessageEventBinding::Wrap).root();
         let event: JSRef<Event> = EventCast::from_ref(*ev);
@@ -68,12 +84,32 @@ impl MessageEvent {
 }
 
 impl MessageEvent {
+    /// Dispatches a message that was posted to `target` with postMessage(),
+    /// creating the ports it transferred in `scope`.
     pub fn dispatch_jsval(target: JSRef<EventTarget>,
                           scope: &GlobalRef,
-                          message: JSVal) {
-        let messageevent = MessageEvent::new(
+                          message: JSVal,
+                          origin: DOMString,
+                          ports: Vec<PortEndpoint>) {
+        let ports = MessagePortList::new(scope, ports).root();
+        let messageevent = MessageEvent::new_with_ports(
             scope, "message".to_string(), false, false, message,
-            "".to_string(), "".to_string()).root();
+            origin, "".to_string(), Some(*ports)).root();
+        let event: JSRef<Event> = EventCast::from_ref(*messageevent);
+        target.dispatch_event_with_target(None, event).unwrap();
+    }
+
+    /// Dispatches the connect event of a shared worker, which carries the
+    /// port its new client talks to it through.
+    /// http://www.whatwg.org/html/#sharedworker
+    pub fn dispatch_connect(target: JSRef<EventTarget>,
+                            scope: &GlobalRef,
+                            port: PortEndpoint) {
+        let ports = MessagePortList::new(scope, vec!(port)).root();
+        let data = "".to_string().to_jsval(scope.get_cx());
+        let messageevent = MessageEvent::new_with_ports(
+            scope, "connect".to_string(), false, false, data,
+            "".to_string(), "".to_string(), Some(*ports)).root();
         let event: JSRef<Event> = EventCast::from_ref(*messageevent);
         target.dispatch_event_with_target(None, event).unwrap();
     }
@@ -91,6 +127,10 @@ impl<'a> MessageEventMethods for JSRef<'a, MessageEvent> {
     fn LastEventId(self) -> DOMString {
         self.lastEventId.clone()
     }
+
+    fn GetPorts(self) -> Option<Temporary<MessagePortList>> {
+        self.ports.as_ref().map(|ports| Temporary::new(ports.clone()))
+    }
 }
 
 impl Reflectable for MessageEvent {
@@ -0,0 +1,325 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
+use dom::bindings::codegen::Bindings::MessagePortBinding;
+use dom::bindings::codegen::Bindings::MessagePortBinding::MessagePortMethods;
+use dom::bindings::codegen::InheritTypes::{EventTargetCast, MessagePortDerived};
+use dom::bindings::error::{ErrorResult, Fallible, DataClone};
+use dom::bindings::global::{GlobalRef, GlobalField};
+use dom::bindings::js::{JS, JSRef, Temporary};
+use dom::bindings::structuredclone::StructuredCloneData;
+use dom::bindings::trace::Untraceable;
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::eventtarget::{EventTarget, MessagePortTypeId};
+use dom::messageevent::MessageEvent;
+use script_task::{ScriptChan, MessagePortMsg, MessagePortRelease};
+use task_source::PostedMessageTaskSource;
+
+use js::jsapi::{JSContext, JS_AddObjectRoot, JS_RemoveObjectRoot};
+use js::jsval::JSVal;
+
+use libc::c_void;
+use std::cell::{Cell, RefCell};
+use sync::{Arc, Mutex};
+
+pub struct TrustedMessagePortAddress(pub *const c_void);
+
+/// A message posted to a port, with the ports it transfers.
+type PortMessage = (StructuredCloneData, Vec<PortEndpoint>);
+
+/// What a channel keeps for each of its two ends.
+struct PortState {
+    /// The messages posted to this end and not dispatched yet.
+    queue: Vec<PortMessage>,
+    /// The event loop of the global that owns this end and the port object
+    /// there, which are told about every message that arrives. None until
+    /// the port is started, and once it stopped receiving messages.
+    owner: Option<(ScriptChan, TrustedMessagePortAddress)>,
+}
+
+/// The state of a channel, which its two ports share whichever tasks they
+/// were transferred to.
+struct ChannelState {
+    /// Whether the ports are entangled still, until one of them is closed.
+    entangled: bool,
+    ports: [PortState, ..2],
+}
+
+/// One end of a channel. A MessagePort object owns it until it is
+/// transferred, and it is sent with the message that transfers it.
+pub struct PortEndpoint {
+    channel: Arc<Mutex<ChannelState>>,
+    side: uint,
+}
+
+impl PortEndpoint {
+    /// Returns the two ends of a new channel.
+    pub fn new_pair() -> (PortEndpoint, PortEndpoint) {
+        let channel = Arc::new(Mutex::new(ChannelState {
+            entangled: true,
+            ports: [
+                PortState { queue: vec!(), owner: None },
+                PortState { queue: vec!(), owner: None },
+            ],
+        }));
+        (PortEndpoint { channel: channel.clone(), side: 0 },
+         PortEndpoint { channel: channel, side: 1 })
+    }
+
+    fn same_channel(&self, other: &PortEndpoint) -> bool {
+        &*self.channel as *const Mutex<ChannelState> == &*other.channel as *const Mutex<ChannelState>
+    }
+}
+
+#[jstraceable]
+#[must_root]
+pub struct MessagePort {
+    eventtarget: EventTarget,
+    global: GlobalField,
+    /// The end of the channel this port is, until the port is closed or
+    /// transferred to another global.
+    endpoint: Untraceable<RefCell<Option<PortEndpoint>>>,
+    /// Whether the port message queue is enabled. The port is rooted and
+    /// registered as the owner of its end meanwhile.
+    started: Cell<bool>,
+}
+
+impl MessagePort {
+    fn new_inherited(global: &GlobalRef, endpoint: PortEndpoint) -> MessagePort {
+        MessagePort {
+            eventtarget: EventTarget::new_inherited(MessagePortTypeId),
+            global: GlobalField::from_rooted(global),
+            endpoint: Untraceable::new(RefCell::new(Some(endpoint))),
+            started: Cell::new(false),
+        }
+    }
+
+    /// Creates the port object that owns `endpoint` in `global`, either for a
+    /// new channel or at the receiving end of a transfer.
+    pub fn new(global: &GlobalRef, endpoint: PortEndpoint) -> Temporary<MessagePort> {
+        reflect_dom_object(box MessagePort::new_inherited(global, endpoint),
+                           global,
+                           MessagePortBinding::Wrap)
+    }
+
+    pub fn handle_message(addr: TrustedMessagePortAddress) {
+        let port = unsafe { JS::from_trusted_message_port_address(addr).root() };
+        port.dispatch_next_message();
+    }
+
+    pub fn handle_release(addr: TrustedMessagePortAddress) {
+        let port = unsafe { JS::from_trusted_message_port_address(addr).root() };
+        // The port may have been started again in the meantime.
+        if !port.started.get() {
+            let cx = port.global.root().root_ref().get_cx();
+            unsafe {
+                JS_RemoveObjectRoot(cx, port.reflector().rootable());
+            }
+        }
+    }
+}
+
+/// Writes a structured clone of `message` and takes the ports in `transfer`
+/// away from their global, which is what every postMessage() does. `source`
+/// is the port whose postMessage() was called, if any; neither it nor the
+/// port entangled with it can be transferred.
+/// http://www.whatwg.org/html/#transferable-objects
+pub fn write_message(cx: *mut JSContext, message: JSVal,
+                     transfer: Option<Vec<JS<MessagePort>>>,
+                     source: Option<JSRef<MessagePort>>)
+                     -> Fallible<(StructuredCloneData, Vec<PortEndpoint>)> {
+    let transfer = transfer.unwrap_or(vec!());
+
+    for (index, port) in transfer.iter().enumerate() {
+        if transfer.slice_to(index).contains(port) {
+            return Err(DataClone);
+        }
+        let port = port.root();
+        let endpoint = port.endpoint.borrow();
+        let endpoint = match *endpoint {
+            Some(ref endpoint) => endpoint,
+            None => return Err(DataClone),
+        };
+        match source {
+            Some(source) => match *source.endpoint.borrow() {
+                Some(ref source_endpoint) if source_endpoint.same_channel(endpoint) => {
+                    return Err(DataClone);
+                },
+                _ => {},
+            },
+            None => {},
+        }
+    }
+
+    let data = try!(StructuredCloneData::write(cx, message));
+    let ports: Vec<PortEndpoint> = transfer.iter().map(|port| {
+        let port = port.root();
+        port.stop();
+        port.endpoint.borrow_mut().take().unwrap()
+    }).collect();
+    Ok((data, ports))
+}
+
+trait PrivateMessagePortHelpers {
+    unsafe fn to_trusted(self) -> TrustedMessagePortAddress;
+    fn start(self);
+    fn stop(self);
+    fn dispatch_next_message(self);
+}
+
+impl<'a> PrivateMessagePortHelpers for JSRef<'a, MessagePort> {
+    // Creates a trusted address to the object, and roots it until a
+    // MessagePortRelease for it is handled.
+    unsafe fn to_trusted(self) -> TrustedMessagePortAddress {
+        JS_AddObjectRoot(self.global.root().root_ref().get_cx(), self.reflector().rootable());
+        TrustedMessagePortAddress(self.deref() as *const MessagePort as *const c_void)
+    }
+
+    // http://www.whatwg.org/html/#dom-messageport-start
+    fn start(self) {
+        if self.started.get() {
+            return;
+        }
+        let endpoint = self.endpoint.borrow();
+        let endpoint = match *endpoint {
+            Some(ref endpoint) => endpoint,
+            None => return,
+        };
+        let mut channel = endpoint.channel.lock();
+        let queued = channel.ports[endpoint.side].queue.len();
+        if !channel.entangled && queued == 0 {
+            return;
+        }
+
+        // The messages that arrived before now are dispatched first.
+        self.started.set(true);
+        let global = self.global.root();
+        let script_chan = global.root_ref().script_chan().clone();
+        let addr = unsafe { self.to_trusted() };
+        for _ in range(0, queued) {
+            script_chan.queue_task(PostedMessageTaskSource, MessagePortMsg(addr));
+        }
+        channel.ports[endpoint.side].owner = Some((script_chan.clone(), addr));
+    }
+
+    // Stops telling the port about new messages, and releases it once the
+    // MessagePortMsgs already on their way to it are handled.
+    fn stop(self) {
+        if !self.started.get() {
+            return;
+        }
+        self.started.set(false);
+        match *self.endpoint.borrow() {
+            Some(ref endpoint) => {
+                endpoint.channel.lock().ports[endpoint.side].owner = None;
+            },
+            None => {},
+        }
+        let global = self.global.root();
+        let addr = TrustedMessagePortAddress(self.deref() as *const MessagePort as *const c_void);
+        global.root_ref().queue_task(PostedMessageTaskSource, MessagePortRelease(addr));
+    }
+
+    fn dispatch_next_message(self) {
+        // The channel is unlocked before the event is dispatched, as its
+        // listeners may well post messages themselves.
+        let (message, finished) = {
+            let endpoint = self.endpoint.borrow();
+            let endpoint = match *endpoint {
+                Some(ref endpoint) => endpoint,
+                None => return,
+            };
+            let mut channel = endpoint.channel.lock();
+            let message = channel.ports[endpoint.side].queue.remove(0);
+            let finished = !channel.entangled && channel.ports[endpoint.side].queue.is_empty();
+            (message, finished)
+        };
+
+        match message {
+            Some((data, ports)) => {
+                let global = self.global.root();
+                let message = data.read(&global.root_ref());
+                let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+                MessageEvent::dispatch_jsval(target, &global.root_ref(), message,
+                                             "".to_string(), ports);
+            },
+            None => {},
+        }
+
+        // Nothing can arrive any more once the other port was closed.
+        if finished {
+            self.stop();
+        }
+    }
+}
+
+impl<'a> MessagePortMethods for JSRef<'a, MessagePort> {
+    // http://www.whatwg.org/html/#dom-messageport-postmessage
+    fn PostMessage(self, cx: *mut JSContext, message: JSVal,
+                   transfer: Option<Vec<JS<MessagePort>>>) -> ErrorResult {
+        let (data, ports) = try!(write_message(cx, message, transfer, Some(self)));
+
+        let endpoint = self.endpoint.borrow();
+        let endpoint = match *endpoint {
+            Some(ref endpoint) => endpoint,
+            None => return Ok(()),
+        };
+        let mut channel = endpoint.channel.lock();
+        if !channel.entangled {
+            return Ok(());
+        }
+        let other = &mut channel.ports[1 - endpoint.side];
+        other.queue.push((data, ports));
+        match other.owner {
+            Some((ref script_chan, addr)) => {
+                script_chan.queue_task(PostedMessageTaskSource, MessagePortMsg(addr));
+            },
+            None => {},
+        }
+        Ok(())
+    }
+
+    fn Start(self) {
+        self.start();
+    }
+
+    // http://www.whatwg.org/html/#dom-messageport-close
+    fn Close(self) {
+        self.stop();
+        let endpoint = match self.endpoint.borrow_mut().take() {
+            Some(endpoint) => endpoint,
+            None => return,
+        };
+        let mut channel = endpoint.channel.lock();
+        channel.entangled = false;
+        channel.ports[endpoint.side].queue.clear();
+
+        // The other port stops once it handled what is left for it.
+        match channel.ports[1 - endpoint.side].owner {
+            Some((ref script_chan, addr)) => {
+                script_chan.queue_task(PostedMessageTaskSource, MessagePortMsg(addr));
+            },
+            None => {},
+        }
+    }
+
+    // Setting onmessage starts the port, unlike addEventListener().
+    event_handler!(message, GetOnmessage, SetOnmessage, start)
+}
+
+impl Reflectable for MessagePort {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        self.eventtarget.reflector()
+    }
+}
+
+impl MessagePortDerived for EventTarget {
+    fn is_messageport(&self) -> bool {
+        match self.type_id {
+            MessagePortTypeId => true,
+            _ => false
+        }
+    }
+}
@@ -0,0 +1,64 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::MessagePortListBinding;
+use dom::bindings::codegen::Bindings::MessagePortListBinding::MessagePortListMethods;
+use dom::bindings::global::GlobalRef;
+use dom::bindings::js::{JS, JSRef, Temporary};
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::messageport::{MessagePort, PortEndpoint};
+
+use std::cell::RefCell;
+
+#[jstraceable]
+#[must_root]
+pub struct MessagePortList {
+    reflector_: Reflector,
+    ports: RefCell<Vec<JS<MessagePort>>>,
+}
+
+impl MessagePortList {
+    fn new_inherited() -> MessagePortList {
+        MessagePortList {
+            reflector_: Reflector::new(),
+            ports: RefCell::new(vec!()),
+        }
+    }
+
+    /// Creates the ports that a message transferred into `global`, and the
+    /// list of them for its MessageEvent.
+    pub fn new(global: &GlobalRef, endpoints: Vec<PortEndpoint>) -> Temporary<MessagePortList> {
+        let list = reflect_dom_object(box MessagePortList::new_inherited(),
+                                      global,
+                                      MessagePortListBinding::Wrap).root();
+        // Each port is kept alive by the list before the next one is created.
+        for endpoint in endpoints.into_iter() {
+            let port = MessagePort::new(global, endpoint).root();
+            list.ports.borrow_mut().push(JS::from_rooted(*port));
+        }
+        Temporary::from_rooted(*list)
+    }
+}
+
+impl<'a> MessagePortListMethods for JSRef<'a, MessagePortList> {
+    fn Length(self) -> u32 {
+        self.ports.borrow().len() as u32
+    }
+
+    fn Item(self, index: u32) -> Option<Temporary<MessagePort>> {
+        self.ports.borrow().as_slice().get(index as uint).map(|port| Temporary::new(port.clone()))
+    }
+
+    fn IndexedGetter(self, index: u32, found: &mut bool) -> Option<Temporary<MessagePort>> {
+        let item = self.Item(index);
+        *found = item.is_some();
+        item
+    }
+}
+
+impl Reflectable for MessagePortList {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        &self.reflector_
+    }
+}
@@ -51,6 +51,18 @@ impl<'a> NavigatorMethods for JSRef<'a, Navigator> {
     fn Platform(self) -> DOMString {
         NavigatorInfo::Platform()
     }
+
+    fn UserAgent(self) -> DOMString {
+        NavigatorInfo::UserAgent()
+    }
+
+    fn GetLanguage(self) -> Option<DOMString> {
+        NavigatorInfo::Language()
+    }
+
+    fn OnLine(self) -> bool {
+        NavigatorInfo::OnLine()
+    }
 }
 
 impl Reflectable for Navigator {
@@ -4,6 +4,10 @@
 
 use servo_util::str::DOMString;
 
+/// What navigator.userAgent says, which follows what the other engines send
+/// closely enough for sites that sniff it.
+static USER_AGENT: &'static str = "Mozilla/5.0 (compatible; Servo/1.0) Gecko/20100101 Firefox/31.0";
+
 pub struct NavigatorInfo;
 
 impl NavigatorInfo {
@@ -26,4 +30,17 @@ impl NavigatorInfo {
     pub fn Platform() -> DOMString {
         "".to_string()
     }
+
+    pub fn UserAgent() -> DOMString {
+        USER_AGENT.to_string()
+    }
+
+    pub fn Language() -> Option<DOMString> {
+        Some("en-US".to_string())
+    }
+
+    // The network is never known to be unavailable.
+    pub fn OnLine() -> bool {
+        true
+    }
 }
@@ -24,21 +24,22 @@ use dom::bindings::global::GlobalRef;
 use dom::bindings::global;
 use dom::bindings::js::{JS, JSRef, RootedReference, Temporary, Root, OptionalUnrootable};
 use dom::bindings::js::{OptionalSettable, TemporaryPushable, OptionalRootedRootable};
-use dom::bindings::js::{ResultRootable, OptionalRootable};
-use dom::bindings::trace::{Traceable, Untraceable};
+use dom::bindings::js::{ResultRootable, OptionalRootable, RootedVec};
+use dom::bindings::trace::Untraceable;
 use dom::bindings::utils;
 use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
-use dom::characterdata::CharacterData;
+use dom::characterdata::{CharacterData, CharacterDataHelpers};
 use dom::comment::Comment;
 use dom::document::{Document, DocumentHelpers, HTMLDocument, NonHTMLDocument};
 use dom::documentfragment::DocumentFragment;
 use dom::documenttype::DocumentType;
+use dom::domtokenlist::DOMTokenList;
 use dom::element::{AttributeHandlers, Element, ElementTypeId};
-use dom::element::{HTMLAnchorElementTypeId, HTMLButtonElementTypeId, ElementHelpers};
-use dom::element::{HTMLInputElementTypeId, HTMLSelectElementTypeId};
-use dom::element::{HTMLTextAreaElementTypeId, HTMLOptGroupElementTypeId};
-use dom::element::{HTMLOptionElementTypeId, HTMLFieldSetElementTypeId};
+use dom::element::{HTMLAnchorElementTypeId, ElementHelpers, ElementState};
+use dom::element::{InHoverState, InDisabledState, InEnabledState, InIndeterminateState};
 use dom::eventtarget::{EventTarget, NodeTargetTypeId};
+use dom::htmlelement::HTMLElement;
+use dom::namednodemap::NamedNodeMap;
 use dom::nodelist::NodeList;
 use dom::processinginstruction::ProcessingInstruction;
 use dom::text::Text;
@@ -48,8 +49,10 @@ use geom::rect::Rect;
 use html::hubbub_html_parser::build_element_from_tag;
 use layout_interface::{ContentBoxResponse, ContentBoxesResponse, LayoutRPC,
                        LayoutChan, ReapLayoutDataMsg, TrustedNodeAddress, UntrustedNodeAddress};
+use layout_interface::{ContentChangedDocumentDamage, MatchSelectorsDocumentDamage};
 use devtools_traits::NodeInfo;
 use servo_util::geometry::Au;
+use servo_util::memory::{HeapSizeOf, MemoryReport};
 use servo_util::str::{DOMString, null_str_as_empty};
 use style::{parse_selector_list_from_str, matches};
 
@@ -79,6 +82,10 @@ pub struct Node {
     /// The type of node that this is.
     type_id: NodeTypeId,
 
+    /// A bitfield of flags for node items. Kept next to the type, which
+    /// leaves room for it in the same word.
+    flags: Cell<NodeFlags>,
+
     /// The parent of this node.
     parent_node: Cell<Option<JS<Node>>>,
 
@@ -97,21 +104,52 @@ pub struct Node {
     /// The document that this node belongs to.
     owner_doc: Cell<Option<JS<Document>>>,
 
-    /// The live list of children return by .childNodes.
-    child_list: Cell<Option<JS<NodeList>>>,
-
-    /// A bitfield of flags for node items.
-    flags: Traceable<RefCell<NodeFlags>>,
-
     /// Layout information. Only the layout task may touch this data.
     ///
     /// Must be sent back to the layout task to be destroyed when this
     /// node is finalized.
     pub layout_data: Untraceable<LayoutDataRef>,
 
+    /// The members that most nodes never use, allocated once one of them is
+    /// needed.
+    rare_data: RefCell<Option<Box<NodeRareData>>>,
+}
+
+/// The members of a node that are seldom used.
+#[jstraceable]
+#[must_root]
+pub struct NodeRareData {
+    /// The live list of children return by .childNodes.
+    pub child_list: Cell<Option<JS<NodeList>>>,
+
+    /// The map returned by .attributes, if the node is an element.
+    pub attr_list: Cell<Option<JS<NamedNodeMap>>>,
+
+    /// The list returned by .classList, if the node is an element.
+    pub class_list: Cell<Option<JS<DOMTokenList>>>,
+
+    /// The identifier that devtools know the node by, empty until they ask
+    /// for it.
     unique_id: RefCell<String>,
 }
 
+impl NodeRareData {
+    fn new() -> NodeRareData {
+        NodeRareData {
+            child_list: Cell::new(None),
+            attr_list: Cell::new(None),
+            class_list: Cell::new(None),
+            unique_id: RefCell::new("".to_string()),
+        }
+    }
+}
+
+impl HeapSizeOf for NodeRareData {
+    fn heap_size_of_children(&self) -> uint {
+        self.unique_id.heap_size_of_children()
+    }
+}
+
 impl NodeDerived for EventTarget {
     fn is_node(&self) -> bool {
         match self.type_id {
@@ -127,33 +165,37 @@ bitflags! {
     flags NodeFlags: u8 {
         #[doc = "Specifies whether this node is in a document."]
         static IsInDoc = 0x01,
-        #[doc = "Specifies whether this node is in hover state."]
-        static InHoverState = 0x02,
-        #[doc = "Specifies whether this node is in disabled state."]
-        static InDisabledState = 0x04,
-        #[doc = "Specifies whether this node is in enabled state."]
-        static InEnabledState = 0x08
+        #[doc = "Specifies whether this node and its descendants need to be restyled."]
+        static IsDirty = 0x02,
+        #[doc = "Specifies whether some descendant of this node needs to be restyled."]
+        static HasDirtyDescendants = 0x04,
+        #[doc = "Specifies whether this element is dispatching the event of its click() method."]
+        static ClickInProgress = 0x08
     }
 }
 
 impl NodeFlags {
     pub fn new(type_id: NodeTypeId) -> NodeFlags {
         match type_id {
-            DocumentNodeTypeId => IsInDoc,
-            // The following elements are enabled by default.
-            ElementNodeTypeId(HTMLButtonElementTypeId) |
-            ElementNodeTypeId(HTMLInputElementTypeId) |
-            ElementNodeTypeId(HTMLSelectElementTypeId) |
-            ElementNodeTypeId(HTMLTextAreaElementTypeId) |
-            ElementNodeTypeId(HTMLOptGroupElementTypeId) |
-            ElementNodeTypeId(HTMLOptionElementTypeId) |
-            //ElementNodeTypeId(HTMLMenuItemElementTypeId) |
-            ElementNodeTypeId(HTMLFieldSetElementTypeId) => InEnabledState,
-            _ => NodeFlags::empty(),
+            DocumentNodeTypeId => IsInDoc | IsDirty,
+            _ => IsDirty,
         }
     }
 }
 
+/// What a mutation changed about a node, which tells `Node::dirty` which
+/// nodes layout has to restyle.
+#[deriving(PartialEq, Show)]
+pub enum NodeDamage {
+    /// The style of the node may be different: one of its attributes or its
+    /// state changed.
+    NodeStyleDamaged,
+    /// Children were added to or removed from the node.
+    SubtreeStructureDamaged,
+    /// The text of the node changed.
+    ContentDamaged,
+}
+
 #[unsafe_destructor]
 impl Drop for Node {
     fn drop(&mut self) {
@@ -250,7 +292,7 @@ pub enum NodeTypeId {
 
 trait PrivateNodeHelpers {
     fn node_inserted(self);
-    fn node_removed(self, parent_in_doc: bool);
+    fn node_removed(self, parent: JSRef<Node>);
     fn add_child(self, new_child: JSRef<Node>, before: Option<JSRef<Node>>);
     fn remove_child(self, child: JSRef<Node>);
 }
@@ -259,29 +301,30 @@ impl<'a> PrivateNodeHelpers for JSRef<'a, Node> {
     // http://dom.spec.whatwg.org/#node-is-inserted
     fn node_inserted(self) {
         assert!(self.parent_node().is_some());
-        let document = document_from_node(self).root();
         let is_in_doc = self.is_in_doc();
 
         for node in self.traverse_preorder() {
             vtable_for(&node).bind_to_tree(is_in_doc);
         }
 
-        let parent = self.parent_node().root();
-        parent.map(|parent| vtable_for(&*parent).child_inserted(self));
+        let parent = self.parent_node().root().unwrap();
+        vtable_for(&*parent).child_inserted(self);
 
-        document.deref().content_changed();
+        parent.dirty(SubtreeStructureDamaged);
     }
 
     // http://dom.spec.whatwg.org/#node-is-removed
-    fn node_removed(self, parent_in_doc: bool) {
+    fn node_removed(self, parent: JSRef<Node>) {
         assert!(self.parent_node().is_none());
-        let document = document_from_node(self).root();
+        let parent_in_doc = parent.is_in_doc();
 
         for node in self.traverse_preorder() {
             vtable_for(&node).unbind_from_tree(parent_in_doc);
         }
 
-        document.deref().content_changed();
+        vtable_for(&parent).child_removed(self);
+
+        parent.dirty(SubtreeStructureDamaged);
     }
 
     //
@@ -402,6 +445,33 @@ pub trait NodeHelpers<'m, 'n> {
     fn get_enabled_state(&self) -> bool;
     fn set_enabled_state(&self, state: bool);
 
+    fn get_indeterminate_state(&self) -> bool;
+    fn set_indeterminate_state(&self, state: bool);
+
+    /// Returns whether this node is an element in the given states.
+    fn get_element_state(&self, which: ElementState) -> bool;
+    /// Adds or removes the given states if this node is an element.
+    fn set_element_state(&self, which: ElementState, state: bool);
+
+    fn get_flag(&self, flag: NodeFlags) -> bool;
+    fn set_flag(&self, flag: NodeFlags, value: bool);
+
+    /// Calls `f` with the rare data of this node, which gets allocated the
+    /// first time.
+    fn with_rare_data<R>(&self, f: |&NodeRareData| -> R) -> R;
+
+    /// Adds the memory this node takes up to `report`, leaving out its
+    /// children.
+    fn report_memory(&self, report: &mut MemoryReport);
+
+    /// Returns whether layout has to restyle this node and its descendants.
+    fn is_dirty(&self) -> bool;
+    /// Returns whether layout has to restyle some descendant of this node.
+    fn has_dirty_descendants(&self) -> bool;
+    /// Marks the nodes whose style `damage` to this node can change as
+    /// needing a restyle, and tells layout that the document changed.
+    fn dirty(&self, damage: NodeDamage);
+
     fn dump(&self);
     fn dump_indent(&self, indent: uint);
     fn debug_str(&self) -> String;
@@ -452,7 +522,110 @@ impl<'m, 'n> NodeHelpers<'m, 'n> for JSRef<'n, Node> {
     }
 
     fn is_in_doc(&self) -> bool {
-        self.deref().flags.deref().borrow().contains(IsInDoc)
+        self.get_flag(IsInDoc)
+    }
+
+    fn get_flag(&self, flag: NodeFlags) -> bool {
+        self.deref().flags.get().contains(flag)
+    }
+
+    fn set_flag(&self, flag: NodeFlags, value: bool) {
+        let mut flags = self.deref().flags.get();
+        if value {
+            flags.insert(flag);
+        } else {
+            flags.remove(flag);
+        }
+        self.deref().flags.set(flags);
+    }
+
+    fn with_rare_data<R>(&self, f: |&NodeRareData| -> R) -> R {
+        if self.deref().rare_data.borrow().is_none() {
+            *self.deref().rare_data.borrow_mut() = Some(box NodeRareData::new());
+        }
+        let rare_data = self.deref().rare_data.borrow();
+        f(&**rare_data.as_ref().unwrap())
+    }
+
+    fn report_memory(&self, report: &mut MemoryReport) {
+        // Most elements add next to nothing to HTMLElement.
+        report.dom_nodes += match self.type_id() {
+            ElementNodeTypeId(_) => mem::size_of::<HTMLElement>(),
+            TextNodeTypeId => mem::size_of::<Text>(),
+            CommentNodeTypeId => mem::size_of::<Comment>(),
+            ProcessingInstructionNodeTypeId => mem::size_of::<ProcessingInstruction>(),
+            DoctypeNodeTypeId => mem::size_of::<DocumentType>(),
+            DocumentFragmentNodeTypeId => mem::size_of::<DocumentFragment>(),
+            DocumentNodeTypeId => mem::size_of::<Document>(),
+        };
+        report.dom_nodes += self.deref().rare_data.heap_size_of_children();
+
+        let target: &JSRef<EventTarget> = EventTargetCast::from_borrowed_ref(self);
+        report.event_listeners += target.heap_size_of_listeners();
+        let node = *self;
+        report.strings += vtable_for(&node).heap_size_of_strings();
+
+        match self.type_id() {
+            ElementNodeTypeId(_) => {
+                let element: &JSRef<Element> = ElementCast::to_borrowed_ref(self).unwrap();
+                element.report_memory(report);
+            }
+            TextNodeTypeId | CommentNodeTypeId | ProcessingInstructionNodeTypeId => {
+                let characterdata: &JSRef<CharacterData> =
+                    CharacterDataCast::to_borrowed_ref(self).unwrap();
+                report.strings += characterdata.data.deref().heap_size_of_children();
+            }
+            DoctypeNodeTypeId => {
+                let doctype: &JSRef<DocumentType> = DocumentTypeCast::to_borrowed_ref(self).unwrap();
+                report.strings += doctype.name.heap_size_of_children() +
+                    doctype.public_id.heap_size_of_children() +
+                    doctype.system_id.heap_size_of_children();
+            }
+            // The document reports what it owns itself, see
+            // DocumentHelpers::memory_report.
+            DocumentFragmentNodeTypeId | DocumentNodeTypeId => {}
+        }
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.get_flag(IsDirty)
+    }
+
+    fn has_dirty_descendants(&self) -> bool {
+        self.get_flag(HasDirtyDescendants)
+    }
+
+    fn dirty(&self, damage: NodeDamage) {
+        self.wait_until_safe_to_modify_dom();
+
+        match damage {
+            ContentDamaged => self.set_flag(IsDirty, true),
+            // Selectors can match the following siblings of the node on it,
+            // through `+` and `~`.
+            NodeStyleDamaged | SubtreeStructureDamaged => {
+                for sibling in self.inclusively_following_siblings() {
+                    sibling.set_flag(IsDirty, true);
+                }
+            }
+        }
+
+        // Layout finds the dirty nodes through their ancestors. An ancestor
+        // that is marked already has its own ancestors marked too.
+        for ancestor in self.ancestors() {
+            if ancestor.has_dirty_descendants() {
+                break;
+            }
+            ancestor.set_flag(HasDirtyDescendants, true);
+        }
+
+        if self.is_in_doc() {
+            let document = self.owner_doc().root();
+            document.increment_dom_generation();
+            document.damage_and_reflow(match damage {
+                NodeStyleDamaged => MatchSelectorsDocumentDamage,
+                SubtreeStructureDamaged | ContentDamaged => ContentChangedDocumentDamage,
+            });
+        }
     }
 
     /// Returns the type ID of this node. Fails if this node is borrowed mutably.
@@ -511,38 +684,47 @@ impl<'m, 'n> NodeHelpers<'m, 'n> for JSRef<'n, Node> {
     }
 
     fn get_hover_state(&self) -> bool {
-        self.flags.deref().borrow().contains(InHoverState)
+        self.get_element_state(InHoverState)
     }
 
     fn set_hover_state(&self, state: bool) {
-        if state {
-            self.flags.deref().borrow_mut().insert(InHoverState);
-        } else {
-            self.flags.deref().borrow_mut().remove(InHoverState);
-        }
+        self.set_element_state(InHoverState, state)
     }
 
     fn get_disabled_state(&self) -> bool {
-        self.flags.deref().borrow().contains(InDisabledState)
+        self.get_element_state(InDisabledState)
     }
 
     fn set_disabled_state(&self, state: bool) {
-        if state {
-            self.flags.deref().borrow_mut().insert(InDisabledState);
-        } else {
-            self.flags.deref().borrow_mut().remove(InDisabledState);
-        }
+        self.set_element_state(InDisabledState, state)
     }
 
     fn get_enabled_state(&self) -> bool {
-        self.flags.deref().borrow().contains(InEnabledState)
+        self.get_element_state(InEnabledState)
     }
 
     fn set_enabled_state(&self, state: bool) {
-        if state {
-            self.flags.deref().borrow_mut().insert(InEnabledState);
-        } else {
-            self.flags.deref().borrow_mut().remove(InEnabledState);
+        self.set_element_state(InEnabledState, state)
+    }
+
+    fn get_indeterminate_state(&self) -> bool {
+        self.get_element_state(InIndeterminateState)
+    }
+
+    fn set_indeterminate_state(&self, state: bool) {
+        self.set_element_state(InIndeterminateState, state)
+    }
+
+    fn get_element_state(&self, which: ElementState) -> bool {
+        let element: Option<JSRef<Element>> = ElementCast::to_ref(*self);
+        element.map_or(false, |element| element.get_state().contains(which))
+    }
+
+    fn set_element_state(&self, which: ElementState, state: bool) {
+        let element: Option<JSRef<Element>> = ElementCast::to_ref(*self);
+        match element {
+            Some(element) => element.set_state(which, state),
+            None => (),
         }
     }
 
@@ -627,21 +809,24 @@ impl<'m, 'n> NodeHelpers<'m, 'n> for JSRef<'n, Node> {
     // http://dom.spec.whatwg.org/#dom-parentnode-queryselectorall
     fn query_selector_all(&self, selectors: DOMString) -> Fallible<Temporary<NodeList>> {
         // Step 1.
-        let nodes;
+        let mut nodes = RootedVec::new();
         let root = self.ancestors().last().unwrap_or(self.clone());
         match parse_selector_list_from_str(selectors.as_slice()) {
             // Step 2.
             Err(()) => return Err(Syntax),
             // Step 3.
             Ok(ref selectors) => {
-                nodes = root.traverse_preorder().filter(
+                for node in root.traverse_preorder() {
                     // TODO(cgaebel): Is it worth it to build a bloom filter here
                     // (instead of passing `None`)? Probably.
-                    |node| node.is_element() && matches(selectors, node, &mut None)).collect()
+                    if node.is_element() && matches(selectors, &node, &mut None) {
+                        nodes.push(node);
+                    }
+                }
             }
         }
         let window = window_from_node(*self).root();
-        Ok(NodeList::new_simple_list(*window, nodes))
+        Ok(NodeList::new_simple_list(*window, &nodes))
     }
 
     fn ancestors(&self) -> AncestorIterator<'n> {
@@ -692,17 +877,25 @@ impl<'m, 'n> NodeHelpers<'m, 'n> for JSRef<'n, Node> {
     }
 
     fn get_unique_id(&self) -> String {
-        self.unique_id.borrow().clone()
+        // Devtools look nodes up by going through all of them, which
+        // shouldn't allocate rare data for those that never had an id.
+        match *self.deref().rare_data.borrow() {
+            Some(ref rare_data) => rare_data.unique_id.borrow().clone(),
+            None => "".to_string(),
+        }
     }
 
     fn summarize(&self) -> NodeInfo {
-        if self.unique_id.borrow().is_empty() {
-            let mut unique_id = self.unique_id.borrow_mut();
-            *unique_id = uuid::Uuid::new_v4().to_simple_string();
-        }
+        let unique_id = self.with_rare_data(|rare_data| {
+            let mut unique_id = rare_data.unique_id.borrow_mut();
+            if unique_id.is_empty() {
+                *unique_id = uuid::Uuid::new_v4().to_simple_string();
+            }
+            unique_id.clone()
+        });
 
         NodeInfo {
-            uniqueId: self.unique_id.borrow().clone(),
+            uniqueId: unique_id,
             baseURI: self.GetBaseURI().unwrap_or("".to_string()),
             parent: self.GetParentNode().root().map(|node| node.get_unique_id()).unwrap_or("".to_string()),
             nodeType: self.NodeType() as uint,
@@ -762,6 +955,9 @@ pub trait LayoutNodeHelpers {
     unsafe fn owner_doc_for_layout(&self) -> JS<Document>;
 
     unsafe fn is_element_for_layout(&self) -> bool;
+
+    unsafe fn get_flag(&self, flag: NodeFlags) -> bool;
+    unsafe fn set_flag(&self, flag: NodeFlags, value: bool);
 }
 
 impl LayoutNodeHelpers for JS<Node> {
@@ -804,25 +1000,30 @@ impl LayoutNodeHelpers for JS<Node> {
     unsafe fn owner_doc_for_layout(&self) -> JS<Document> {
         (*self.unsafe_get()).owner_doc.get().unwrap()
     }
+
+    #[inline]
+    unsafe fn get_flag(&self, flag: NodeFlags) -> bool {
+        (*self.unsafe_get()).flags.get().contains(flag)
+    }
+
+    #[inline]
+    unsafe fn set_flag(&self, flag: NodeFlags, value: bool) {
+        let this = self.unsafe_get();
+        let mut flags = (*this).flags.get();
+        if value {
+            flags.insert(flag);
+        } else {
+            flags.remove(flag);
+        }
+        (*this).flags.set(flags);
+    }
 }
 
 pub trait RawLayoutNodeHelpers {
-    unsafe fn get_hover_state_for_layout(&self) -> bool;
-    unsafe fn get_disabled_state_for_layout(&self) -> bool;
-    unsafe fn get_enabled_state_for_layout(&self) -> bool;
     fn type_id_for_layout(&self) -> NodeTypeId;
 }
 
 impl RawLayoutNodeHelpers for Node {
-    unsafe fn get_hover_state_for_layout(&self) -> bool {
-        (*self.unsafe_get_flags()).contains(InHoverState)
-    }
-    unsafe fn get_disabled_state_for_layout(&self) -> bool {
-        (*self.unsafe_get_flags()).contains(InDisabledState)
-    }
-    unsafe fn get_enabled_state_for_layout(&self) -> bool {
-        (*self.unsafe_get_flags()).contains(InEnabledState)
-    }
 
     fn type_id_for_layout(&self) -> NodeTypeId {
         self.type_id
@@ -1024,6 +1225,7 @@ impl Node {
         Node {
             eventtarget: EventTarget::new_inherited(NodeTargetTypeId(type_id)),
             type_id: type_id,
+            flags: Cell::new(NodeFlags::new(type_id)),
 
             parent_node: Cell::new(None),
             first_child: Cell::new(None),
@@ -1031,13 +1233,10 @@ impl Node {
             next_sibling: Cell::new(None),
             prev_sibling: Cell::new(None),
             owner_doc: Cell::new(doc.unrooted()),
-            child_list: Cell::new(None),
-
-            flags: Traceable::new(RefCell::new(NodeFlags::new(type_id))),
 
             layout_data: Untraceable::new(LayoutDataRef::new()),
 
-            unique_id: RefCell::new("".to_string()),
+            rare_data: RefCell::new(None),
         }
     }
 
@@ -1234,11 +1433,7 @@ impl Node {
             parent.add_child(*node, child);
             let is_in_doc = parent.is_in_doc();
             for kid in node.traverse_preorder() {
-                if is_in_doc {
-                    kid.flags.deref().borrow_mut().insert(IsInDoc);
-                } else {
-                    kid.flags.deref().borrow_mut().remove(IsInDoc);
-                }
+                kid.set_flag(IsInDoc, is_in_doc);
             }
         }
 
@@ -1291,9 +1486,8 @@ impl Node {
         // Step 6: mutation records.
 
         // Step 7.
-        let parent_in_doc = parent.is_in_doc();
         for removedNode in removedNodes.iter() {
-            removedNode.node_removed(parent_in_doc);
+            removedNode.node_removed(parent);
         }
         for addedNode in addedNodes.iter() {
             addedNode.node_inserted();
@@ -1324,12 +1518,12 @@ impl Node {
         // Step 8.
         parent.remove_child(node);
 
-        node.deref().flags.deref().borrow_mut().remove(IsInDoc);
+        node.set_flag(IsInDoc, false);
 
         // Step 9.
         match suppress_observers {
             Suppressed => (),
-            Unsuppressed => node.node_removed(parent.is_in_doc()),
+            Unsuppressed => node.node_removed(parent),
         }
     }
 
@@ -1371,9 +1565,10 @@ impl Node {
                     false => NonHTMLDocument
                 };
                 let window = document.window.root();
-                let document = Document::new(*window, Some(document.url().clone()),
-                                             is_html_doc, None);
-                NodeCast::from_temporary(document)
+                let copy = Document::new(*window, Some(document.url().clone()),
+                                         is_html_doc, None).root();
+                copy.set_origin(document.origin());
+                NodeCast::from_temporary(Temporary::from_rooted(*copy))
             },
             ElementNodeTypeId(..) => {
                 let element: JSRef<Element> = ElementCast::to_ref(node).unwrap();
@@ -1556,16 +1751,18 @@ impl<'a> NodeMethods for JSRef<'a, Node> {
 
     // http://dom.spec.whatwg.org/#dom-node-childnodes
     fn ChildNodes(self) -> Temporary<NodeList> {
-        match self.child_list.get() {
-            None => (),
-            Some(ref list) => return Temporary::new(list.clone()),
-        }
+        self.with_rare_data(|rare_data| {
+            match rare_data.child_list.get() {
+                None => (),
+                Some(ref list) => return Temporary::new(list.clone()),
+            }
 
-        let doc = self.owner_doc().root();
-        let window = doc.deref().window.root();
-        let child_list = NodeList::new_child_list(*window, self);
-        self.child_list.assign(Some(child_list));
-        Temporary::new(self.child_list.get().as_ref().unwrap().clone())
+            let doc = self.owner_doc().root();
+            let window = doc.deref().window.root();
+            let child_list = NodeList::new_child_list(*window, self);
+            rare_data.child_list.assign(Some(child_list));
+            Temporary::new(rare_data.child_list.get().as_ref().unwrap().clone())
+        })
     }
 
     // http://dom.spec.whatwg.org/#dom-node-firstchild
@@ -1659,11 +1856,7 @@ impl<'a> NodeMethods for JSRef<'a, Node> {
                 self.wait_until_safe_to_modify_dom();
 
                 let characterdata: JSRef<CharacterData> = CharacterDataCast::to_ref(self).unwrap();
-                *characterdata.data.deref().borrow_mut() = value;
-
-                // Notify the document that the content of this node is different
-                let document = self.owner_doc().root();
-                document.deref().content_changed();
+                characterdata.set_data(value);
             }
             DoctypeNodeTypeId |
             DocumentNodeTypeId => {}
@@ -1796,7 +1989,7 @@ impl<'a> NodeMethods for JSRef<'a, Node> {
 
         // Step 12-14.
         // Step 13: mutation records.
-        child.node_removed(self.is_in_doc());
+        child.node_removed(self);
         if node.type_id() == DocumentFragmentNodeTypeId {
             for child_node in node.children() {
                 child_node.node_inserted();
@@ -2068,14 +2261,14 @@ impl<'a> style::TNode<'a, JSRef<'a, Element>> for JSRef<'a, Node> {
     fn match_attr(&self, attr: &style::AttrSelector, test: |&str| -> bool) -> bool {
         let name = {
             if self.is_html_element_in_html_document() {
-                attr.lower_name.as_slice()
+                &attr.lower_name
             } else {
-                attr.name.as_slice()
+                &attr.name
             }
         };
         match attr.namespace {
             style::SpecificNamespace(ref ns) => {
-                self.as_element().get_attribute(ns.clone(), name).root()
+                self.as_element().get_attribute_atom(ns, name).root()
                     .map_or(false, |attr| test(attr.deref().Value().as_slice()))
             },
             // FIXME: https://github.com/mozilla/servo/issues/1558
@@ -2138,3 +2331,16 @@ impl<'a> DisabledStateHelpers for JSRef<'a, Node> {
         self.set_enabled_state(!has_disabled_attrib);
     }
 }
+
+#[test]
+fn test_size_of_node() {
+    // The type and the flags share a word, and everything else that most
+    // nodes leave empty lives in NodeRareData, so this only fails when a
+    // member that every node needs is added.
+    let expected = mem::size_of::<EventTarget>() +
+                   mem::size_of::<uint>() +
+                   6 * mem::size_of::<Cell<Option<JS<Node>>>>() +
+                   mem::size_of::<LayoutDataRef>() +
+                   mem::size_of::<RefCell<Option<Box<NodeRareData>>>>();
+    assert!(mem::size_of::<Node>() <= expected);
+}
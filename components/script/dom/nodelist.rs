@@ -4,17 +4,40 @@
 
 use dom::bindings::codegen::Bindings::NodeListBinding;
 use dom::bindings::codegen::Bindings::NodeListBinding::NodeListMethods;
+use dom::bindings::codegen::InheritTypes::{ElementCast, NodeCast};
 use dom::bindings::global;
-use dom::bindings::js::{JS, JSRef, Temporary};
+use dom::bindings::js::{JS, JSRef, RootedVec, Temporary};
 use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::document::DocumentHelpers;
+use dom::element::Element;
+use dom::htmlcollection::CollectionFilter;
+use dom::htmllabelelement::LabelableElementHelpers;
 use dom::node::{Node, NodeHelpers};
 use dom::window::Window;
 
+use std::cell::RefCell;
+
 #[jstraceable]
 #[must_root]
 pub enum NodeListType {
     Simple(Vec<JS<Node>>),
-    Children(JS<Node>)
+    Children(JS<Node>),
+    /// The label elements whose labeled control is the element, in tree
+    /// order, as they were last found.
+    Labels(JS<Element>, RefCell<Option<LabelsSnapshot>>),
+    /// The elements among the descendants of the node that match the filter,
+    /// in tree order.
+    Filtered(JS<Node>, Box<CollectionFilter+'static>)
+}
+
+/// The labels of a control, and the DOM generation of its document when they
+/// were found. Out of a document, nothing tells when the tree changes, so
+/// they are found again on every access.
+#[jstraceable]
+#[must_root]
+pub struct LabelsSnapshot {
+    generation: Option<uint>,
+    labels: Vec<JS<Node>>,
 }
 
 #[jstraceable]
@@ -25,7 +48,7 @@ pub struct NodeList {
 }
 
 impl NodeList {
-    fn new_inherited(list_type: NodeListType) -> NodeList {
+    pub fn new_inherited(list_type: NodeListType) -> NodeList {
         NodeList {
             list_type: list_type,
             reflector_: Reflector::new(),
@@ -38,13 +61,38 @@ impl NodeList {
                            &global::Window(window), NodeListBinding::Wrap)
     }
 
-    pub fn new_simple_list(window: JSRef<Window>, elements: Vec<JSRef<Node>>) -> Temporary<NodeList> {
-        NodeList::new(window, Simple(elements.iter().map(|element| JS::from_rooted(*element)).collect()))
+    pub fn new_simple_list(window: JSRef<Window>, elements: &RootedVec<Node>) -> Temporary<NodeList> {
+        NodeList::new(window, Simple(elements.iter().map(|element| JS::from_rooted(element)).collect()))
     }
 
     pub fn new_child_list(window: JSRef<Window>, node: JSRef<Node>) -> Temporary<NodeList> {
         NodeList::new(window, Children(JS::from_rooted(node)))
     }
+
+    pub fn new_labels_list(window: JSRef<Window>, control: JSRef<Element>) -> Temporary<NodeList> {
+        NodeList::new(window, Labels(JS::from_rooted(control), RefCell::new(None)))
+    }
+}
+
+/// Finds the labels of `control` again unless its document has not changed
+/// since `snapshot` was taken.
+#[allow(unrooted_must_root)]
+fn update_labels(control: JSRef<Element>, snapshot: &RefCell<Option<LabelsSnapshot>>) {
+    let node: JSRef<Node> = NodeCast::from_ref(control);
+    let generation = if node.is_in_doc() {
+        Some(node.owner_doc().root().dom_generation())
+    } else {
+        None
+    };
+    match *snapshot.borrow() {
+        Some(ref taken) if generation.is_some() && taken.generation == generation => return,
+        _ => {}
+    }
+    let labels = control.label_nodes();
+    *snapshot.borrow_mut() = Some(LabelsSnapshot {
+        generation: generation,
+        labels: labels.iter().map(|label| JS::from_rooted(label)).collect(),
+    });
 }
 
 impl<'a> NodeListMethods for JSRef<'a, NodeList> {
@@ -54,6 +102,17 @@ impl<'a> NodeListMethods for JSRef<'a, NodeList> {
             Children(ref node) => {
                 let node = node.root();
                 node.deref().children().count() as u32
+            },
+            Labels(ref control, ref snapshot) => {
+                update_labels(*control.root(), snapshot);
+                snapshot.borrow().as_ref().unwrap().labels.len() as u32
+            },
+            Filtered(ref root, ref filter) => {
+                let root = root.root();
+                root.deref().traverse_preorder()
+                            .filter_map(|node| ElementCast::to_ref(node))
+                            .filter(|elem| filter.filter(*elem, *root))
+                            .count() as u32
             }
         }
     }
@@ -66,6 +125,22 @@ impl<'a> NodeListMethods for JSRef<'a, NodeList> {
                 let node = node.root();
                 node.deref().children().nth(index as uint)
                                        .map(|child| Temporary::from_rooted(child))
+            },
+            Labels(_, ref snapshot) => {
+                // Length found the labels.
+                let snapshot = snapshot.borrow();
+                Some(Temporary::new(snapshot.as_ref().unwrap().labels[index as uint].clone()))
+            },
+            Filtered(ref root, ref filter) => {
+                let root = root.root();
+                root.deref().traverse_preorder()
+                            .filter_map(|node| ElementCast::to_ref(node))
+                            .filter(|elem| filter.filter(*elem, *root))
+                            .nth(index as uint)
+                            .map(|elem| {
+                                let node: JSRef<Node> = NodeCast::from_ref(elem);
+                                Temporary::from_rooted(node)
+                            })
             }
         }
     }
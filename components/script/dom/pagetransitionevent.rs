@@ -0,0 +1,68 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use dom::bindings::codegen::Bindings::PageTransitionEventBinding;
+use dom::bindings::codegen::Bindings::PageTransitionEventBinding::PageTransitionEventMethods;
+use dom::bindings::codegen::InheritTypes::{EventCast, PageTransitionEventDerived};
+use dom::bindings::error::Fallible;
+use dom::bindings::global::GlobalRef;
+use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::event::{Event, PageTransitionEventTypeId};
+use servo_util::str::DOMString;
+
+#[jstraceable]
+#[must_root]
+pub struct PageTransitionEvent {
+    event: Event,
+    persisted: bool,
+}
+
+impl PageTransitionEventDerived for Event {
+    fn is_pagetransitionevent(&self) -> bool {
+        self.type_id == PageTransitionEventTypeId
+    }
+}
+
+impl PageTransitionEvent {
+    fn new_inherited(persisted: bool) -> PageTransitionEvent {
+        PageTransitionEvent {
+            event: Event::new_inherited(PageTransitionEventTypeId),
+            persisted: persisted,
+        }
+    }
+
+    pub fn new(global: &GlobalRef, type_: DOMString,
+               can_bubble: bool, cancelable: bool,
+               persisted: bool) -> Temporary<PageTransitionEvent> {
+        let ev = reflect_dom_object(box PageTransitionEvent::new_inherited(persisted),
+                                    global,
+                                    PageTransitionEventBinding::Wrap).root();
+        let event: JSRef<Event> = EventCast::from_ref(*ev);
+        event.InitEvent(type_, can_bubble, cancelable);
+        Temporary::from_rooted(*ev)
+    }
+
+    pub fn Constructor(global: &GlobalRef,
+                       type_: DOMString,
+                       init: &PageTransitionEventBinding::PageTransitionEventInit)
+                       -> Fallible<Temporary<PageTransitionEvent>> {
+        let ev = PageTransitionEvent::new(global, type_, init.parent.bubbles,
+                                          init.parent.cancelable, init.persisted);
+        Ok(ev)
+    }
+}
+
+impl<'a> PageTransitionEventMethods for JSRef<'a, PageTransitionEvent> {
+    fn Persisted(self) -> bool {
+        self.persisted
+    }
+}
+
+impl Reflectable for PageTransitionEvent {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        self.event.reflector()
+    }
+}
@@ -0,0 +1,612 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Promises, which are what the methods that return `Promise<T>` return.
+//! Rust code settles them with `resolve_native` and `reject_native`, or from
+//! another task through a `TrustedPromise`.
+//! http://people.mozilla.org/~jorendorff/es6-draft.html#sec-promise-objects
+
+use dom::bindings::callback::RethrowExceptions;
+use dom::bindings::codegen::Bindings::PromiseBinding;
+use dom::bindings::codegen::Bindings::PromiseBinding::{AnyCallback, PromiseInit, PromiseMethods};
+use dom::bindings::conversions::{FromJSValConvertible, ToJSValConvertible};
+use dom::bindings::error::{Error, Fallible, throw_dom_exception, throw_type_error};
+use dom::bindings::global::{GlobalRef, GlobalField};
+use dom::bindings::global;
+use dom::bindings::js::{JS, JSRef, Temporary};
+use dom::bindings::trace::Traceable;
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::window::WindowHelpers;
+use microtask::{enqueue_microtask, PromiseJobMicrotask};
+use script_task::{ScriptChan, PromiseMsg};
+use task_source::DOMManipulationTaskSource;
+
+use js::glue::GetObjectParent;
+use js::jsapi::{JSBool, JSClass, JSContext, JSObject};
+use js::jsapi::{JS_AddObjectRoot, JS_RemoveObjectRoot, JS_CallFunctionValue};
+use js::jsapi::{JS_NewObject, JS_NewFunction, JS_GetFunctionObject, JS_GetProperty};
+use js::jsapi::{JS_GetReservedSlot, JS_SetReservedSlot, JS_ObjectIsCallable};
+use js::jsapi::{JS_GetOptions, JS_SetOptions, JS_GetPendingException, JS_ClearPendingException};
+use js::jsapi::{JS_PropertyStub, JS_StrictPropertyStub, JS_EnumerateStub};
+use js::jsapi::{JS_ResolveStub, JS_ConvertStub};
+use js::jsval::{JSVal, BooleanValue, ObjectValue, UndefinedValue};
+use js::rust::with_compartment;
+use js::{JSCLASS_RESERVED_SLOTS_MASK, JSCLASS_RESERVED_SLOTS_SHIFT, JS_ARGV, JS_CALLEE};
+
+use libc;
+use libc::c_void;
+use std::cell::{Cell, RefCell};
+use std::mem;
+use std::ptr;
+
+pub struct TrustedPromiseAddress(pub *const c_void);
+
+/// From jsapi.h: leaves the exception that script did not catch pending
+/// rather than reporting it, when no script is running.
+static JSOPTION_DONT_REPORT_UNCAUGHT: u32 = 1 << 8;
+
+/// Calls `f` and returns the exception it left pending on `cx`, if any,
+/// without reporting it.
+pub fn catch_exception<T>(cx: *mut JSContext, f: || -> T) -> Result<T, JSVal> {
+    unsafe {
+        let options = JS_GetOptions(cx);
+        JS_SetOptions(cx, options | JSOPTION_DONT_REPORT_UNCAUGHT);
+        let result = f();
+        JS_SetOptions(cx, options);
+
+        let mut exception = UndefinedValue();
+        if JS_GetPendingException(cx, &mut exception) != 0 {
+            JS_ClearPendingException(cx);
+            return Err(exception);
+        }
+        Ok(result)
+    }
+}
+
+/// What the resolving functions of a promise share: the promise itself, and
+/// whether one of them was called already.
+/// http://people.mozilla.org/~jorendorff/es6-draft.html#sec-createresolvingfunctions
+static RECORD_PROMISE_SLOT: u32 = 0;
+static RECORD_ALREADY_RESOLVED_SLOT: u32 = 1;
+
+static RecordClassName: [u8, ..16] = ['R' as u8, 'e' as u8, 's' as u8, 'o' as u8, 'l' as u8,
+                                      'v' as u8, 'i' as u8, 'n' as u8, 'g' as u8, 'R' as u8,
+                                      'e' as u8, 'c' as u8, 'o' as u8, 'r' as u8, 'd' as u8,
+                                      0 as u8];
+static RecordClass: JSClass = JSClass {
+    name: &RecordClassName as *const u8 as *const libc::c_char,
+    flags: (2 & JSCLASS_RESERVED_SLOTS_MASK) << JSCLASS_RESERVED_SLOTS_SHIFT as uint,
+    addProperty: Some(JS_PropertyStub),
+    delProperty: Some(JS_PropertyStub),
+    getProperty: Some(JS_PropertyStub),
+    setProperty: Some(JS_StrictPropertyStub),
+    enumerate: Some(JS_EnumerateStub),
+    resolve: Some(JS_ResolveStub),
+    convert: Some(JS_ConvertStub),
+    finalize: None,
+    checkAccess: None,
+    call: None,
+    hasInstance: None,
+    construct: None,
+    trace: None,
+    reserved: [0 as *mut libc::c_void, ..40]
+};
+
+/// Creates the resolve and reject functions of `promise`. Their parent is
+/// the record they share.
+fn create_resolving_functions(cx: *mut JSContext, promise: JSRef<Promise>)
+                              -> (*mut JSObject, *mut JSObject) {
+    unsafe {
+        let global = promise.global.root();
+        let parent = global.root_ref().reflector().get_jsobject();
+        let record = JS_NewObject(cx, &RecordClass, ptr::null(), &*parent);
+        assert!(record.is_not_null());
+        JS_SetReservedSlot(record, RECORD_PROMISE_SLOT,
+                           ObjectValue(&*promise.reflector().get_jsobject()));
+        JS_SetReservedSlot(record, RECORD_ALREADY_RESOLVED_SLOT, BooleanValue(false));
+
+        let new_function = |native: unsafe extern fn(*mut JSContext, libc::c_uint, *mut JSVal) -> JSBool| {
+            let fun = JS_NewFunction(cx, Some(native), 1, 0, record, ptr::null());
+            assert!(fun.is_not_null());
+            JS_GetFunctionObject(fun)
+        };
+        (new_function(resolve_function), new_function(reject_function))
+    }
+}
+
+unsafe extern fn resolve_function(cx: *mut JSContext, argc: libc::c_uint, vp: *mut JSVal) -> JSBool {
+    call_resolving_function(cx, argc, vp, true)
+}
+
+unsafe extern fn reject_function(cx: *mut JSContext, argc: libc::c_uint, vp: *mut JSVal) -> JSBool {
+    call_resolving_function(cx, argc, vp, false)
+}
+
+// http://people.mozilla.org/~jorendorff/es6-draft.html#sec-promise-resolve-functions
+// http://people.mozilla.org/~jorendorff/es6-draft.html#sec-promise-reject-functions
+unsafe fn call_resolving_function(cx: *mut JSContext, argc: libc::c_uint, vp: *mut JSVal,
+                                  resolve: bool) -> JSBool {
+    let record = GetObjectParent(JS_CALLEE(cx, vp).to_object());
+    let value = if argc > 0 {
+        *JS_ARGV(cx, vp).offset(0)
+    } else {
+        UndefinedValue()
+    };
+    *vp = UndefinedValue();
+
+    if JS_GetReservedSlot(record, RECORD_ALREADY_RESOLVED_SLOT).to_boolean() {
+        return 1;
+    }
+    JS_SetReservedSlot(record, RECORD_ALREADY_RESOLVED_SLOT, BooleanValue(true));
+
+    let promise: Result<JS<Promise>, ()> =
+        FromJSValConvertible::from_jsval(cx, JS_GetReservedSlot(record, RECORD_PROMISE_SLOT), ());
+    let promise = promise.unwrap().root();
+    if resolve {
+        promise.resolve_promise(cx, value);
+    } else {
+        promise.reject_promise(value);
+    }
+    1
+}
+
+/// Calls `function`, one of the resolving functions of a promise, with
+/// `value`.
+fn call_function(cx: *mut JSContext, function: *mut JSObject, mut value: JSVal) {
+    let mut rval = UndefinedValue();
+    unsafe {
+        JS_CallFunctionValue(cx, ptr::null_mut(), ObjectValue(&*function),
+                             1, &mut value, &mut rval);
+    }
+}
+
+#[jstraceable]
+enum PromiseState {
+    Pending,
+    Fulfilled(Traceable<JSVal>),
+    Rejected(Traceable<JSVal>),
+}
+
+/// Which of its handlers the promise calls.
+#[jstraceable]
+enum ReactionKind {
+    FulfillReaction,
+    RejectReaction,
+}
+
+/// What then() asks a promise to do once it settles.
+/// http://people.mozilla.org/~jorendorff/es6-draft.html#sec-promisereaction-records
+#[jstraceable]
+#[must_root]
+struct PromiseReaction {
+    /// The promise then() returned, which the result of the handler resolves.
+    derived: JS<Promise>,
+    /// The handler; without one, the derived promise settles like this one.
+    handler: Option<AnyCallback>,
+    kind: ReactionKind,
+}
+
+/// The jobs a promise queues on the microtask queue.
+#[jstraceable]
+#[must_root]
+enum PromiseJob {
+    /// Calls the handler of a reaction with the value the promise settled
+    /// with.
+    /// http://people.mozilla.org/~jorendorff/es6-draft.html#sec-promisereactionjob
+    ReactionJob(PromiseReaction, Traceable<JSVal>),
+    /// Makes the promise follow the given thenable, by calling its given
+    /// then().
+    /// http://people.mozilla.org/~jorendorff/es6-draft.html#sec-promiseresolvethenablejob
+    ResolveThenableJob(Traceable<*mut JSObject>, Traceable<JSVal>),
+}
+
+#[jstraceable]
+#[must_root]
+pub struct Promise {
+    reflector_: Reflector,
+    global: GlobalField,
+    state: RefCell<PromiseState>,
+    fulfill_reactions: RefCell<Vec<PromiseReaction>>,
+    reject_reactions: RefCell<Vec<PromiseReaction>>,
+    /// The jobs queued on the microtask queue and not run yet, in order.
+    jobs: RefCell<Vec<PromiseJob>>,
+    /// Whether `resolve_native` or `reject_native` was called already.
+    resolved: Cell<bool>,
+    /// The number of queued jobs and TrustedPromises, for which the promise
+    /// is rooted.
+    pinned_count: Cell<uint>,
+}
+
+impl Promise {
+    fn new_inherited(global: &GlobalRef) -> Promise {
+        Promise {
+            reflector_: Reflector::new(),
+            global: GlobalField::from_rooted(global),
+            state: RefCell::new(Pending),
+            fulfill_reactions: RefCell::new(vec!()),
+            reject_reactions: RefCell::new(vec!()),
+            jobs: RefCell::new(vec!()),
+            resolved: Cell::new(false),
+            pinned_count: Cell::new(0),
+        }
+    }
+
+    /// Creates a pending promise, for Rust code to settle.
+    pub fn new(global: &GlobalRef) -> Temporary<Promise> {
+        reflect_dom_object(box Promise::new_inherited(global),
+                           global,
+                           PromiseBinding::Wrap)
+    }
+
+    /// Creates a promise rejected with `error`, which is what a method that
+    /// returns a promise returns when it fails.
+    pub fn new_rejected(global: &GlobalRef, error: Error) -> Temporary<Promise> {
+        let promise = Promise::new(global).root();
+        promise.reject_error(error);
+        Temporary::from_rooted(*promise)
+    }
+
+    // http://people.mozilla.org/~jorendorff/es6-draft.html#sec-promise-executor
+    pub fn Constructor(global: &GlobalRef, init: PromiseInit) -> Fallible<Temporary<Promise>> {
+        let promise = Promise::new(global).root();
+        let cx = global.get_cx();
+        let (resolve, reject) = create_resolving_functions(cx, *promise);
+        match catch_exception(cx, || init.clone().Call__(resolve, reject, RethrowExceptions)) {
+            Ok(_) => {},
+            Err(exception) => call_function(cx, reject, exception),
+        }
+        Ok(Temporary::from_rooted(*promise))
+    }
+
+    // http://people.mozilla.org/~jorendorff/es6-draft.html#sec-promise.resolve
+    pub fn Resolve(cx: *mut JSContext, global: &GlobalRef, value: JSVal) -> Temporary<Promise> {
+        let existing: Result<JS<Promise>, ()> = FromJSValConvertible::from_jsval(cx, value, ());
+        match existing {
+            Ok(promise) => return Temporary::new(promise),
+            Err(()) => {},
+        }
+        let promise = Promise::new(global).root();
+        promise.resolve_native(cx, value);
+        Temporary::from_rooted(*promise)
+    }
+
+    // http://people.mozilla.org/~jorendorff/es6-draft.html#sec-promise.reject
+    pub fn Reject(_cx: *mut JSContext, global: &GlobalRef, value: JSVal) -> Temporary<Promise> {
+        let promise = Promise::new(global).root();
+        promise.reject_native(value);
+        Temporary::from_rooted(*promise)
+    }
+
+    /// Runs the first job `addr` queued, and releases it.
+    pub fn run_job(addr: TrustedPromiseAddress) {
+        let promise = unsafe { JS::from_trusted_promise_address(addr).root() };
+        let global = promise.global.root();
+        let cx = global.root_ref().get_cx();
+        with_compartment(cx, promise.reflector().get_jsobject(), || {
+            let job = promise.jobs.borrow_mut().remove(0);
+            match job {
+                Some(ReactionJob(reaction, argument)) => run_reaction(cx, reaction, *argument),
+                Some(ResolveThenableJob(thenable, then)) => {
+                    promise.resolve_thenable(cx, *thenable, *then);
+                },
+                None => {},
+            }
+        });
+        promise.unpin();
+    }
+
+    /// Settles `addr` as a TrustedPromise asked, and releases it.
+    pub fn handle_settlement(addr: TrustedPromiseAddress, settlement: PromiseSettlement) {
+        let promise = unsafe { JS::from_trusted_promise_address(addr).root() };
+        let global = promise.global.root();
+        let cx = global.root_ref().get_cx();
+        // The promises of a document that was unloaded stay pending, since
+        // it never runs script again.
+        let settlement = match global.root_ref() {
+            global::Window(window) if window.is_unloaded() => Abandoned,
+            _ => settlement,
+        };
+        with_compartment(cx, promise.reflector().get_jsobject(), || {
+            match settlement {
+                ResolvedWith(value) => promise.resolve_native(cx, value.to_jsval(cx)),
+                RejectedWith(error) => promise.reject_error(error),
+                SettledBy(settler) => settler.settle(&global.root_ref(), *promise),
+                Abandoned => {},
+            }
+        });
+        promise.unpin();
+    }
+}
+
+#[allow(unrooted_must_root)]
+fn run_reaction(cx: *mut JSContext, reaction: PromiseReaction, argument: JSVal) {
+    let derived = reaction.derived.root();
+    let result = match reaction.handler {
+        Some(ref handler) => {
+            match catch_exception(cx, || handler.clone().Call__(argument, RethrowExceptions)) {
+                Ok(Ok(value)) => Ok(value),
+                Ok(Err(_)) => Err(UndefinedValue()),
+                Err(exception) => Err(exception),
+            }
+        },
+        None => match reaction.kind {
+            FulfillReaction => Ok(argument),
+            RejectReaction => Err(argument),
+        },
+    };
+    match result {
+        Ok(value) => derived.resolve_native(cx, value),
+        Err(reason) => derived.reject_native(reason),
+    }
+}
+
+pub trait PromiseHelpers {
+    /// Resolves the promise with `value`, which it follows if it is a
+    /// thenable. Does nothing if the promise was resolved already.
+    fn resolve_native(self, cx: *mut JSContext, value: JSVal);
+    /// Rejects the promise with `value`, unless it was resolved already.
+    fn reject_native(self, value: JSVal);
+    /// Rejects the promise with the exception that throwing `error` would
+    /// throw, unless it was resolved already.
+    fn reject_error(self, error: Error);
+    /// Returns a handle that settles the promise from another task.
+    fn trusted(self) -> TrustedPromise;
+}
+
+impl<'a> PromiseHelpers for JSRef<'a, Promise> {
+    fn resolve_native(self, cx: *mut JSContext, value: JSVal) {
+        if self.resolved.get() {
+            return;
+        }
+        self.resolved.set(true);
+        self.resolve_promise(cx, value);
+    }
+
+    fn reject_native(self, value: JSVal) {
+        if self.resolved.get() {
+            return;
+        }
+        self.resolved.set(true);
+        self.reject_promise(value);
+    }
+
+    fn reject_error(self, error: Error) {
+        let global = self.global.root();
+        let cx = global.root_ref().get_cx();
+        let exception = catch_exception(cx, || {
+            throw_dom_exception(cx, &global.root_ref(), error.clone())
+        });
+        self.reject_native(exception.err().unwrap_or(UndefinedValue()));
+    }
+
+    fn trusted(self) -> TrustedPromise {
+        self.pin();
+        let global = self.global.root();
+        TrustedPromise {
+            addr: TrustedPromiseAddress(self.deref() as *const Promise as *const c_void),
+            script_chan: global.root_ref().script_chan().clone(),
+            settled: false,
+        }
+    }
+}
+
+trait PrivatePromiseHelpers {
+    fn resolve_promise(self, cx: *mut JSContext, resolution: JSVal);
+    fn fulfill_promise(self, value: JSVal);
+    fn reject_promise(self, reason: JSVal);
+    fn resolve_thenable(self, cx: *mut JSContext, thenable: *mut JSObject, then: JSVal);
+    fn enqueue_job(self, job: PromiseJob);
+    fn pin(self);
+    fn unpin(self);
+}
+
+impl<'a> PrivatePromiseHelpers for JSRef<'a, Promise> {
+    // Steps 6-13 of the resolve functions.
+    // http://people.mozilla.org/~jorendorff/es6-draft.html#sec-promise-resolve-functions
+    fn resolve_promise(self, cx: *mut JSContext, resolution: JSVal) {
+        // Step 6.
+        if resolution.is_object() && resolution.to_object() == self.reflector().get_jsobject() {
+            let error = catch_exception(cx, || {
+                throw_type_error(cx, "A promise can't be resolved with itself.")
+            });
+            return self.reject_promise(error.err().unwrap_or(UndefinedValue()));
+        }
+
+        // Step 7.
+        if !resolution.is_object() {
+            return self.fulfill_promise(resolution);
+        }
+
+        // Steps 8-9.
+        let then = catch_exception(cx, || {
+            let mut then = UndefinedValue();
+            "then".with_c_str(|name| unsafe {
+                JS_GetProperty(cx, resolution.to_object(), name, &mut then);
+            });
+            then
+        });
+        let then = match then {
+            Ok(then) => then,
+            Err(exception) => return self.reject_promise(exception),
+        };
+
+        // Steps 10-13.
+        let callable = then.is_object() && unsafe {
+            JS_ObjectIsCallable(cx, then.to_object()) != 0
+        };
+        if callable {
+            self.enqueue_job(ResolveThenableJob(Traceable::new(resolution.to_object()),
+                                                Traceable::new(then)));
+        } else {
+            self.fulfill_promise(resolution);
+        }
+    }
+
+    // http://people.mozilla.org/~jorendorff/es6-draft.html#sec-fulfillpromise
+    fn fulfill_promise(self, value: JSVal) {
+        let reactions = mem::replace(&mut *self.fulfill_reactions.borrow_mut(), vec!());
+        self.reject_reactions.borrow_mut().clear();
+        *self.state.borrow_mut() = Fulfilled(Traceable::new(value));
+        for reaction in reactions.into_iter() {
+            self.enqueue_job(ReactionJob(reaction, Traceable::new(value)));
+        }
+    }
+
+    // http://people.mozilla.org/~jorendorff/es6-draft.html#sec-rejectpromise
+    fn reject_promise(self, reason: JSVal) {
+        let reactions = mem::replace(&mut *self.reject_reactions.borrow_mut(), vec!());
+        self.fulfill_reactions.borrow_mut().clear();
+        *self.state.borrow_mut() = Rejected(Traceable::new(reason));
+        for reaction in reactions.into_iter() {
+            self.enqueue_job(ReactionJob(reaction, Traceable::new(reason)));
+        }
+    }
+
+    // http://people.mozilla.org/~jorendorff/es6-draft.html#sec-promiseresolvethenablejob
+    fn resolve_thenable(self, cx: *mut JSContext, thenable: *mut JSObject, then: JSVal) {
+        let (resolve, reject) = create_resolving_functions(cx, self);
+        let result = catch_exception(cx, || unsafe {
+            let mut argv = [ObjectValue(&*resolve), ObjectValue(&*reject)];
+            let mut rval = UndefinedValue();
+            JS_CallFunctionValue(cx, thenable, then, 2, argv.as_mut_ptr(), &mut rval);
+        });
+        match result {
+            Ok(()) => {},
+            Err(exception) => call_function(cx, reject, exception),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    fn enqueue_job(self, job: PromiseJob) {
+        self.jobs.borrow_mut().push(job);
+        self.pin();
+        enqueue_microtask(PromiseJobMicrotask(
+            TrustedPromiseAddress(self.deref() as *const Promise as *const c_void)));
+    }
+
+    // Roots the promise until as many unpin()s were called.
+    fn pin(self) {
+        if self.pinned_count.get() == 0 {
+            let global = self.global.root();
+            unsafe {
+                JS_AddObjectRoot(global.root_ref().get_cx(), self.reflector().rootable());
+            }
+        }
+        self.pinned_count.set(self.pinned_count.get() + 1);
+    }
+
+    fn unpin(self) {
+        assert!(self.pinned_count.get() > 0);
+        self.pinned_count.set(self.pinned_count.get() - 1);
+        if self.pinned_count.get() == 0 {
+            let global = self.global.root();
+            unsafe {
+                JS_RemoveObjectRoot(global.root_ref().get_cx(), self.reflector().rootable());
+            }
+        }
+    }
+}
+
+impl<'a> PromiseMethods for JSRef<'a, Promise> {
+    // http://people.mozilla.org/~jorendorff/es6-draft.html#sec-promise.prototype.then
+    #[allow(unrooted_must_root)]
+    fn Then(self, onFulfilled: Option<AnyCallback>,
+            onRejected: Option<AnyCallback>) -> Temporary<Promise> {
+        let global = self.global.root();
+        let derived = Promise::new(&global.root_ref()).root();
+        let fulfill_reaction = PromiseReaction {
+            derived: JS::from_rooted(*derived),
+            handler: onFulfilled,
+            kind: FulfillReaction,
+        };
+        let reject_reaction = PromiseReaction {
+            derived: JS::from_rooted(*derived),
+            handler: onRejected,
+            kind: RejectReaction,
+        };
+
+        let settled = match *self.state.borrow() {
+            Pending => None,
+            Fulfilled(ref value) => Some((FulfillReaction, **value)),
+            Rejected(ref reason) => Some((RejectReaction, **reason)),
+        };
+        match settled {
+            None => {
+                self.fulfill_reactions.borrow_mut().push(fulfill_reaction);
+                self.reject_reactions.borrow_mut().push(reject_reaction);
+            },
+            Some((FulfillReaction, value)) => {
+                self.enqueue_job(ReactionJob(fulfill_reaction, Traceable::new(value)));
+            },
+            Some((RejectReaction, reason)) => {
+                self.enqueue_job(ReactionJob(reject_reaction, Traceable::new(reason)));
+            },
+        }
+        Temporary::from_rooted(*derived)
+    }
+
+    // http://people.mozilla.org/~jorendorff/es6-draft.html#sec-promise.prototype.catch
+    fn Catch(self, onRejected: Option<AnyCallback>) -> Temporary<Promise> {
+        self.Then(None, onRejected)
+    }
+}
+
+impl Reflectable for Promise {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        &self.reflector_
+    }
+}
+
+/// Settles a promise with what only the event loop of its global can make,
+/// such as DOM objects.
+pub trait PromiseSettler {
+    fn settle(self: Box<Self>, global: &GlobalRef, promise: JSRef<Promise>);
+}
+
+/// What a TrustedPromise settles its promise with.
+pub enum PromiseSettlement {
+    ResolvedWith(Box<ToJSValConvertible+Send>),
+    RejectedWith(Error),
+    SettledBy(Box<PromiseSettler+Send>),
+    /// The handle was dropped without settling the promise, which is only
+    /// released.
+    Abandoned,
+}
+
+/// A handle to a promise that can be sent to another task, such as one that
+/// waits on the network. The promise is settled on the event loop of its
+/// global, and stays alive until then.
+pub struct TrustedPromise {
+    addr: TrustedPromiseAddress,
+    script_chan: ScriptChan,
+    settled: bool,
+}
+
+impl TrustedPromise {
+    pub fn resolve(mut self, value: Box<ToJSValConvertible+Send>) {
+        self.settle(ResolvedWith(value));
+    }
+
+    pub fn reject(mut self, error: Error) {
+        self.settle(RejectedWith(error));
+    }
+
+    pub fn settle_with(mut self, settler: Box<PromiseSettler+Send>) {
+        self.settle(SettledBy(settler));
+    }
+
+    fn settle(&mut self, settlement: PromiseSettlement) {
+        self.settled = true;
+        // The global may be gone already, and the promise with it.
+        self.script_chan.queue_task(DOMManipulationTaskSource, PromiseMsg(self.addr, settlement));
+    }
+}
+
+impl Drop for TrustedPromise {
+    fn drop(&mut self) {
+        if !self.settled {
+            self.settle(Abandoned);
+        }
+    }
+}
@@ -0,0 +1,101 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::HTMLInputElementBinding::HTMLInputElementMethods;
+use dom::bindings::codegen::Bindings::NodeListBinding::NodeListMethods;
+use dom::bindings::codegen::Bindings::RadioNodeListBinding;
+use dom::bindings::codegen::Bindings::RadioNodeListBinding::RadioNodeListMethods;
+use dom::bindings::codegen::InheritTypes::{ElementCast, HTMLInputElementCast, NodeListCast};
+use dom::bindings::global;
+use dom::bindings::js::{JS, JSRef, Temporary};
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::element::{AttributeHandlers, Element};
+use dom::htmlcollection::CollectionFilter;
+use dom::htmlinputelement::{HTMLInputElement, HTMLInputElementHelpers};
+use dom::node::Node;
+use dom::nodelist::{NodeList, Filtered};
+use dom::window::Window;
+use servo_util::str::DOMString;
+
+#[jstraceable]
+#[must_root]
+pub struct RadioNodeList {
+    nodelist: NodeList,
+}
+
+impl RadioNodeList {
+    fn new_inherited(root: JSRef<Node>, filter: Box<CollectionFilter+'static>) -> RadioNodeList {
+        RadioNodeList {
+            nodelist: NodeList::new_inherited(Filtered(JS::from_rooted(root), filter)),
+        }
+    }
+
+    /// A live list of the elements among the descendants of `root` that match
+    /// `filter`.
+    pub fn new(window: JSRef<Window>, root: JSRef<Node>,
+               filter: Box<CollectionFilter+'static>) -> Temporary<RadioNodeList> {
+        reflect_dom_object(box RadioNodeList::new_inherited(root, filter),
+                           &global::Window(window), RadioNodeListBinding::Wrap)
+    }
+}
+
+trait PrivateRadioNodeListHelpers {
+    fn radio_buttons(self) -> Vec<Temporary<HTMLInputElement>>;
+}
+
+impl<'a> PrivateRadioNodeListHelpers for JSRef<'a, RadioNodeList> {
+    /// The radio buttons in the list, in tree order.
+    fn radio_buttons(self) -> Vec<Temporary<HTMLInputElement>> {
+        let list: JSRef<NodeList> = NodeListCast::from_ref(self);
+        range(0, list.Length()).filter_map(|index| {
+            let node = list.Item(index).unwrap().root();
+            let input: Option<JSRef<HTMLInputElement>> = HTMLInputElementCast::to_ref(*node);
+            match input {
+                Some(input) if input.is_radio_button() => Some(Temporary::from_rooted(input)),
+                _ => None,
+            }
+        }).collect()
+    }
+}
+
+impl<'a> RadioNodeListMethods for JSRef<'a, RadioNodeList> {
+    // http://www.whatwg.org/html/#dom-radionodelist-value
+    fn Value(self) -> DOMString {
+        self.radio_buttons().into_iter()
+            .map(|radio| radio.root())
+            .find(|radio| radio.Checked())
+            .map(|radio| radio.Value())
+            .unwrap_or("".to_string())
+    }
+
+    // http://www.whatwg.org/html/#dom-radionodelist-value
+    fn SetValue(self, value: DOMString) {
+        // A radio button without a value attribute has the value "on".
+        let radio = self.radio_buttons().into_iter()
+            .map(|radio| radio.root())
+            .find(|radio| {
+                let elem: JSRef<Element> = ElementCast::from_ref(**radio);
+                if elem.has_attribute("value") {
+                    elem.get_string_attribute("value") == value
+                } else {
+                    value.as_slice() == "on"
+                }
+            });
+        match radio {
+            Some(radio) => radio.SetChecked(true),
+            None => (),
+        }
+    }
+
+    fn IndexedGetter(self, index: u32, found: &mut bool) -> Option<Temporary<Node>> {
+        let list: JSRef<NodeList> = NodeListCast::from_ref(self);
+        list.IndexedGetter(index, found)
+    }
+}
+
+impl Reflectable for RadioNodeList {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        self.nodelist.reflector()
+    }
+}
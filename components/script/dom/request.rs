@@ -0,0 +1,291 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::RequestBinding;
+use dom::bindings::codegen::Bindings::RequestBinding::{RequestCredentials, RequestInit};
+use dom::bindings::codegen::Bindings::RequestBinding::{RequestMethods, RequestMode};
+use dom::bindings::codegen::Bindings::RequestBinding::RequestCredentialsValues::Omit;
+use dom::bindings::codegen::Bindings::RequestBinding::RequestModeValues::{Cors, No_cors};
+use dom::bindings::codegen::UnionTypes::RequestOrString::{RequestOrString, eRequest, eString};
+use dom::bindings::error::{Fallible, Type};
+use dom::bindings::global::{GlobalRef, GlobalField};
+use dom::bindings::js::{JS, JSRef, Temporary};
+use dom::bindings::str::ByteString;
+use dom::bindings::trace::{Traceable, Untraceable};
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::body::{BodyState, BodyType, NoBody, CompleteBody, PendingBody};
+use dom::body::{ArrayBufferBody, BlobBody, FormDataBody, JsonBody, TextBody};
+use dom::body::{consume_body, extract_body, mime_type, take_body, tee_body};
+use dom::headers::{Headers, HeadersHelpers, RequestGuard, RequestNoCorsGuard};
+use dom::promise::Promise;
+use servo_net::resource_task::LoadData;
+use servo_util::str::DOMString;
+
+use http::headers::{HeaderEnum, HeaderValueByteIterator};
+use http::headers::request::Header;
+use http::method::Method;
+
+use std::ascii::StrAsciiExt;
+use std::cell::{Cell, RefCell};
+use std::io::BufReader;
+use url::{Url, UrlParser};
+
+#[jstraceable]
+#[must_root]
+pub struct Request {
+    reflector_: Reflector,
+    global: GlobalField,
+    method: ByteString,
+    url: Untraceable<Url>,
+    headers: JS<Headers>,
+    mode: RequestMode,
+    credentials: RequestCredentials,
+    body: Untraceable<RefCell<BodyState>>,
+    body_used: Traceable<Cell<bool>>,
+}
+
+impl Request {
+    fn new_inherited(global: &GlobalRef, method: ByteString, url: Url, headers: JSRef<Headers>,
+                     mode: RequestMode, credentials: RequestCredentials,
+                     body: BodyState) -> Request {
+        Request {
+            reflector_: Reflector::new(),
+            global: GlobalField::from_rooted(global),
+            method: method,
+            url: Untraceable::new(url),
+            headers: JS::from_rooted(headers),
+            mode: mode,
+            credentials: credentials,
+            body: Untraceable::new(RefCell::new(body)),
+            body_used: Traceable::new(Cell::new(false)),
+        }
+    }
+
+    pub fn new(global: &GlobalRef, method: ByteString, url: Url, headers: JSRef<Headers>,
+               mode: RequestMode, credentials: RequestCredentials,
+               body: BodyState) -> Temporary<Request> {
+        reflect_dom_object(box Request::new_inherited(global, method, url, headers, mode,
+                                                      credentials, body),
+                           global, RequestBinding::Wrap)
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-request
+    pub fn Constructor(global: &GlobalRef, input: RequestOrString,
+                       init: &RequestInit) -> Fallible<Temporary<Request>> {
+        // The request to start from.
+        let (mut method, url, mut mode, mut credentials, mut body, old_headers) = match input {
+            eRequest(input) => {
+                let input = input.root();
+                if input.body_used.deref().get() {
+                    return Err(Type("The body of the request was already used".to_string()));
+                }
+                // The new request takes the body of the input.
+                let body = take_body(input.body.deref(), input.body_used.deref());
+                let old_headers = input.headers.root().list();
+                (input.method.clone(), input.url.deref().clone(), input.mode,
+                 input.credentials, body, old_headers)
+            },
+            eString(url) => {
+                let base = global.get_url();
+                let url = match UrlParser::new().base_url(&base).parse(url.as_slice()) {
+                    Ok(url) => url,
+                    Err(_) => return Err(Type("Invalid URL".to_string())),
+                };
+                (from_str("GET").unwrap(), url, Cors, Omit, NoBody, vec!())
+            },
+        };
+
+        match init.mode {
+            Some(init_mode) => mode = init_mode,
+            None => {}
+        }
+        match init.credentials {
+            Some(init_credentials) => credentials = init_credentials,
+            None => {}
+        }
+        match init.method {
+            Some(ref init_method) => method = try!(normalize_method(init_method)),
+            None => {}
+        }
+
+        let headers = Headers::new(global, RequestGuard).root();
+        if mode == No_cors {
+            match method.as_str() {
+                Some("GET") | Some("HEAD") | Some("POST") => {},
+                _ => return Err(Type("Invalid method for a no-cors request".to_string())),
+            }
+            headers.set_guard(RequestNoCorsGuard);
+        }
+        match init.headers {
+            Some(ref init_headers) => try!(headers.fill(**init_headers)),
+            None => {
+                for (name, value) in old_headers.into_iter() {
+                    try!(headers.Append(name, value));
+                }
+            }
+        }
+
+        match init.body {
+            Some(ref init_body) => {
+                match method.as_str() {
+                    Some("GET") | Some("HEAD") =>
+                        return Err(Type("GET and HEAD requests have no body".to_string())),
+                    _ => {}
+                }
+                body = CompleteBody(extract_body(*headers, init_body));
+            },
+            None => {}
+        }
+
+        Ok(Request::new(global, method, url, *headers, mode, credentials, body))
+    }
+}
+
+/// Returns the [normalized](http://fetch.spec.whatwg.org/#concept-method-normalize)
+/// `method`, or a `TypeError` if it is not a method scripts may use.
+fn normalize_method(method: &ByteString) -> Fallible<ByteString> {
+    if !method.is_token() {
+        return Err(Type("Invalid method".to_string()));
+    }
+    // Tokens are ASCII.
+    let method = method.as_str().unwrap();
+    let upper = method.to_ascii_upper();
+    match upper.as_slice() {
+        // http://fetch.spec.whatwg.org/#forbidden-method
+        "CONNECT" | "TRACE" | "TRACK" => Err(Type("Forbidden method".to_string())),
+        "DELETE" | "GET" | "HEAD" | "OPTIONS" | "POST" | "PUT" => Ok(from_str(upper.as_slice()).unwrap()),
+        _ => Ok(from_str(method).unwrap()),
+    }
+}
+
+pub trait RequestHelpers {
+    fn load_data(self) -> LoadData;
+}
+
+impl<'a> RequestHelpers for JSRef<'a, Request> {
+    /// Returns what the resource task needs to load the request, which
+    /// takes its body.
+    fn load_data(self) -> LoadData {
+        let mut load_data = LoadData::new(self.url.deref().clone());
+        // The method is normalized already.
+        load_data.method = Method::from_str_or_new(self.method.as_str().unwrap()).unwrap();
+        for (name, value) in self.headers.root().list().into_iter() {
+            let name = match name.to_lower().as_str() {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            let mut reader = BufReader::new(value.as_slice());
+            let header: Option<Header> =
+                HeaderEnum::value_from_stream(name, &mut HeaderValueByteIterator::new(&mut reader));
+            match header {
+                Some(header) => load_data.headers.insert(header),
+                None => {}
+            }
+        }
+        load_data.data = match take_body(self.body.deref(), self.body_used.deref()) {
+            NoBody => None,
+            CompleteBody(bytes) => Some(bytes),
+            // Only the body of a response can still be being received.
+            PendingBody(_) => unreachable!(),
+        };
+        load_data
+    }
+}
+
+trait PrivateRequestHelpers {
+    fn consume(self, type_: BodyType) -> Temporary<Promise>;
+}
+
+impl<'a> PrivateRequestHelpers for JSRef<'a, Request> {
+    /// Reads the body as `type_`, for the body-reading methods.
+    fn consume(self, type_: BodyType) -> Temporary<Promise> {
+        let global = self.global.root();
+        let headers = self.headers.root();
+        consume_body(&global.root_ref(), self.body.deref(), self.body_used.deref(), type_,
+                     mime_type(*headers))
+    }
+}
+
+impl<'a> RequestMethods for JSRef<'a, Request> {
+    // http://fetch.spec.whatwg.org/#dom-request-method
+    fn Method(self) -> ByteString {
+        self.method.clone()
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-request-url
+    fn Url(self) -> DOMString {
+        self.url.deref().serialize()
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-request-headers
+    fn Headers(self) -> Temporary<Headers> {
+        Temporary::new(self.headers)
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-request-referrer
+    fn Referrer(self) -> DOMString {
+        // FIXME: Requests always have their client as referrer for now.
+        "about:client".to_string()
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-request-mode
+    fn Mode(self) -> RequestMode {
+        self.mode
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-request-credentials
+    fn Credentials(self) -> RequestCredentials {
+        self.credentials
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-request-clone
+    fn Clone(self) -> Fallible<Temporary<Request>> {
+        if self.body_used.deref().get() {
+            return Err(Type("The body of the request was already used".to_string()));
+        }
+        let global = self.global.root();
+        let old_headers = self.headers.root();
+        let headers = Headers::new(&global.root_ref(), old_headers.guard()).root();
+        headers.copy_from(*old_headers);
+        Ok(Request::new(&global.root_ref(), self.method.clone(), self.url.deref().clone(),
+                        *headers, self.mode, self.credentials, tee_body(self.body.deref())))
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-body-bodyused
+    fn BodyUsed(self) -> bool {
+        self.body_used.deref().get()
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-body-arraybuffer
+    fn ArrayBuffer(self) -> Temporary<Promise> {
+        self.consume(ArrayBufferBody)
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-body-blob
+    fn Blob(self) -> Temporary<Promise> {
+        self.consume(BlobBody)
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-body-formdata
+    fn FormData(self) -> Temporary<Promise> {
+        self.consume(FormDataBody)
+    }
+
+
+    // http://fetch.spec.whatwg.org/#dom-body-json
+    fn Json(self) -> Temporary<Promise> {
+        self.consume(JsonBody)
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-body-text
+    fn Text(self) -> Temporary<Promise> {
+        self.consume(TextBody)
+    }
+}
+
+impl Reflectable for Request {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        &self.reflector_
+    }
+}
@@ -0,0 +1,229 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::ResponseBinding;
+use dom::bindings::codegen::Bindings::ResponseBinding::{ResponseInit, ResponseMethods};
+use dom::bindings::codegen::Bindings::ResponseBinding::{ResponseType, ResponseTypeValues};
+use dom::bindings::error::{Fallible, Range, Type};
+use dom::bindings::global::{GlobalRef, GlobalField};
+use dom::bindings::js::{JS, JSRef, Temporary};
+use dom::bindings::str::ByteString;
+use dom::bindings::trace::{Traceable, Untraceable};
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::body::{BodyState, BodyType, NoBody, CompleteBody};
+use dom::body::{ArrayBufferBody, BlobBody, FormDataBody, JsonBody, TextBody};
+use dom::body::{consume_body, extract_body, mime_type, tee_body};
+use dom::headers::{Headers, HeadersHelpers, ImmutableGuard, ResponseGuard};
+use dom::promise::Promise;
+use dom::xmlhttprequest::SendParam;
+use servo_util::str::DOMString;
+
+use std::cell::{Cell, RefCell};
+use url::{Url, UrlParser};
+
+#[jstraceable]
+#[must_root]
+pub struct Response {
+    reflector_: Reflector,
+    global: GlobalField,
+    type_: ResponseType,
+    url: Untraceable<Option<Url>>,
+    status: u16,
+    status_text: ByteString,
+    headers: JS<Headers>,
+    body: Untraceable<RefCell<BodyState>>,
+    body_used: Traceable<Cell<bool>>,
+}
+
+impl Response {
+    fn new_inherited(global: &GlobalRef, type_: ResponseType, url: Option<Url>, status: u16,
+                     status_text: ByteString, headers: JSRef<Headers>,
+                     body: BodyState) -> Response {
+        Response {
+            reflector_: Reflector::new(),
+            global: GlobalField::from_rooted(global),
+            type_: type_,
+            url: Untraceable::new(url),
+            status: status,
+            status_text: status_text,
+            headers: JS::from_rooted(headers),
+            body: Untraceable::new(RefCell::new(body)),
+            body_used: Traceable::new(Cell::new(false)),
+        }
+    }
+
+    pub fn new(global: &GlobalRef, type_: ResponseType, url: Option<Url>, status: u16,
+               status_text: ByteString, headers: JSRef<Headers>,
+               body: BodyState) -> Temporary<Response> {
+        reflect_dom_object(box Response::new_inherited(global, type_, url, status, status_text,
+                                                       headers, body),
+                           global, ResponseBinding::Wrap)
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-response
+    pub fn Constructor(global: &GlobalRef, body: Option<SendParam>,
+                       init: &ResponseInit) -> Fallible<Temporary<Response>> {
+        if init.status < 200 || init.status > 599 {
+            return Err(Range("The status of a response must be in the range 200 to 599".to_string()));
+        }
+        let status_text = match init.statusText {
+            Some(ref status_text) => status_text.clone(),
+            None => from_str("OK").unwrap(),
+        };
+        if !is_reason_phrase(&status_text) {
+            return Err(Type("Invalid status text".to_string()));
+        }
+
+        let headers = Headers::new(global, ResponseGuard).root();
+        match init.headers {
+            Some(ref init_headers) => try!(headers.fill(**init_headers)),
+            None => {}
+        }
+
+        let body = match body {
+            Some(ref body) => {
+                if is_null_body_status(init.status) {
+                    return Err(Type("Responses of this status have no body".to_string()));
+                }
+                CompleteBody(extract_body(*headers, body))
+            },
+            None => NoBody,
+        };
+
+        Ok(Response::new(global, ResponseTypeValues::Default, None, init.status, status_text,
+                         *headers, body))
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-response-error
+    pub fn Error(global: &GlobalRef) -> Temporary<Response> {
+        let headers = Headers::new(global, ImmutableGuard).root();
+        Response::new(global, ResponseTypeValues::Error, None, 0, ByteString::new(vec!()),
+                      *headers, NoBody)
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-response-redirect
+    pub fn Redirect(global: &GlobalRef, url: DOMString, status: u16) -> Fallible<Temporary<Response>> {
+        let base = global.get_url();
+        let url = match UrlParser::new().base_url(&base).parse(url.as_slice()) {
+            Ok(url) => url,
+            Err(_) => return Err(Type("Invalid URL".to_string())),
+        };
+        match status {
+            301 | 302 | 303 | 307 | 308 => {},
+            _ => return Err(Range("Not a redirect status".to_string())),
+        }
+        let headers = Headers::new(global, ImmutableGuard).root();
+        headers.append(from_str("Location").unwrap(), from_str(url.serialize().as_slice()).unwrap());
+        Ok(Response::new(global, ResponseTypeValues::Default, None, status,
+                         ByteString::new(vec!()), *headers, NoBody))
+    }
+}
+
+/// Returns whether `text` matches the `Reason-Phrase` production of
+/// [RFC 2616](http://tools.ietf.org/html/rfc2616#section-6.1.1).
+fn is_reason_phrase(text: &ByteString) -> bool {
+    text.as_slice().iter().all(|&b| b != b'\r' && b != b'\n')
+}
+
+/// http://fetch.spec.whatwg.org/#null-body-status
+fn is_null_body_status(status: u16) -> bool {
+    match status {
+        101 | 204 | 205 | 304 => true,
+        _ => false,
+    }
+}
+
+trait PrivateResponseHelpers {
+    fn consume(self, type_: BodyType) -> Temporary<Promise>;
+}
+
+impl<'a> PrivateResponseHelpers for JSRef<'a, Response> {
+    /// Reads the body as `type_`, for the body-reading methods.
+    fn consume(self, type_: BodyType) -> Temporary<Promise> {
+        let global = self.global.root();
+        let headers = self.headers.root();
+        consume_body(&global.root_ref(), self.body.deref(), self.body_used.deref(), type_,
+                     mime_type(*headers))
+    }
+}
+
+impl<'a> ResponseMethods for JSRef<'a, Response> {
+    // http://fetch.spec.whatwg.org/#dom-response-type
+    fn Type(self) -> ResponseType {
+        self.type_
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-response-url
+    fn Url(self) -> DOMString {
+        match *self.url.deref() {
+            Some(ref url) => url.serialize(),
+            None => "".to_string(),
+        }
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-response-status
+    fn Status(self) -> u16 {
+        self.status
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-response-statustext
+    fn StatusText(self) -> ByteString {
+        self.status_text.clone()
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-response-headers
+    fn Headers(self) -> Temporary<Headers> {
+        Temporary::new(self.headers)
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-response-clone
+    fn Clone(self) -> Fallible<Temporary<Response>> {
+        if self.body_used.deref().get() {
+            return Err(Type("The body of the response was already used".to_string()));
+        }
+        let global = self.global.root();
+        let old_headers = self.headers.root();
+        let headers = Headers::new(&global.root_ref(), old_headers.guard()).root();
+        headers.copy_from(*old_headers);
+        Ok(Response::new(&global.root_ref(), self.type_, self.url.deref().clone(), self.status,
+                         self.status_text.clone(), *headers, tee_body(self.body.deref())))
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-body-bodyused
+    fn BodyUsed(self) -> bool {
+        self.body_used.deref().get()
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-body-arraybuffer
+    fn ArrayBuffer(self) -> Temporary<Promise> {
+        self.consume(ArrayBufferBody)
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-body-blob
+    fn Blob(self) -> Temporary<Promise> {
+        self.consume(BlobBody)
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-body-formdata
+    fn FormData(self) -> Temporary<Promise> {
+        self.consume(FormDataBody)
+    }
+
+
+    // http://fetch.spec.whatwg.org/#dom-body-json
+    fn Json(self) -> Temporary<Promise> {
+        self.consume(JsonBody)
+    }
+
+    // http://fetch.spec.whatwg.org/#dom-body-text
+    fn Text(self) -> Temporary<Promise> {
+        self.consume(TextBody)
+    }
+}
+
+impl Reflectable for Response {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        &self.reflector_
+    }
+}
@@ -0,0 +1,90 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
+use dom::bindings::codegen::Bindings::SharedWorkerBinding;
+use dom::bindings::codegen::Bindings::SharedWorkerBinding::SharedWorkerMethods;
+use dom::bindings::codegen::InheritTypes::SharedWorkerDerived;
+use dom::bindings::error::{Fallible, Security, Syntax};
+use dom::bindings::global::GlobalRef;
+use dom::bindings::js::{JS, JSRef, Temporary};
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::eventtarget::{EventTarget, SharedWorkerTypeId};
+use dom::messageport::{MessagePort, PortEndpoint};
+use dom::sharedworkerglobalscope::SharedWorkerGlobalScope;
+use security::Origin;
+use servo_util::str::DOMString;
+
+use url::UrlParser;
+
+#[jstraceable]
+#[must_root]
+pub struct SharedWorker {
+    eventtarget: EventTarget,
+    port: JS<MessagePort>,
+}
+
+impl SharedWorker {
+    fn new_inherited(port: JSRef<MessagePort>) -> SharedWorker {
+        SharedWorker {
+            eventtarget: EventTarget::new_inherited(SharedWorkerTypeId),
+            port: JS::from_rooted(port),
+        }
+    }
+
+    // http://www.whatwg.org/html/#dom-sharedworker
+    pub fn Constructor(global: &GlobalRef, scriptURL: DOMString,
+                       name: Option<DOMString>) -> Fallible<Temporary<SharedWorker>> {
+        // Steps 2-4.
+        let worker_url = match UrlParser::new().base_url(&global.get_url())
+                .parse(scriptURL.as_slice()) {
+            Ok(url) => url,
+            Err(_) => return Err(Syntax),
+        };
+
+        // Step 5.
+        let origin = global.get_origin();
+        if !origin.same_origin(&Origin::from_url(&worker_url)) {
+            return Err(Security);
+        }
+
+        // Steps 6-7. The port of the worker object is entangled with the one
+        // its connect event carries.
+        let (outside, inside) = PortEndpoint::new_pair();
+        let port = MessagePort::new(global, outside).root();
+        let worker = reflect_dom_object(box SharedWorker::new_inherited(*port),
+                                        global,
+                                        SharedWorkerBinding::Wrap);
+
+        // Steps 8-9.
+        let owner = SharedWorkerGlobalScope::connect(&origin, worker_url,
+                                                     name.unwrap_or("".to_string()),
+                                                     global.resource_task(), inside);
+        global.add_shared_worker_owner(owner);
+        Ok(worker)
+    }
+}
+
+impl<'a> SharedWorkerMethods for JSRef<'a, SharedWorker> {
+    fn Port(self) -> Temporary<MessagePort> {
+        Temporary::new(self.port.clone())
+    }
+
+    event_handler!(error, GetOnerror, SetOnerror)
+}
+
+impl Reflectable for SharedWorker {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        self.eventtarget.reflector()
+    }
+}
+
+impl SharedWorkerDerived for EventTarget {
+    fn is_sharedworker(&self) -> bool {
+        match self.type_id {
+            SharedWorkerTypeId => true,
+            _ => false
+        }
+    }
+}
@@ -0,0 +1,293 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
+use dom::bindings::codegen::Bindings::SharedWorkerGlobalScopeBinding;
+use dom::bindings::codegen::Bindings::SharedWorkerGlobalScopeBinding::SharedWorkerGlobalScopeMethods;
+use dom::bindings::codegen::InheritTypes::SharedWorkerGlobalScopeDerived;
+use dom::bindings::codegen::InheritTypes::{EventTargetCast, WorkerGlobalScopeCast};
+use dom::bindings::global;
+use dom::bindings::js::{JSRef, Temporary, RootCollection};
+use dom::bindings::trace::Untraceable;
+use dom::bindings::utils::{Reflectable, Reflector};
+use dom::eventtarget::EventTarget;
+use dom::eventtarget::WorkerGlobalScopeTypeId;
+use dom::messageevent::MessageEvent;
+use dom::messageport::PortEndpoint;
+use dom::workerglobalscope::SharedGlobalScope;
+use dom::workerglobalscope::{WorkerGlobalScope, WorkerGlobalScopeHelpers};
+use microtask::perform_microtask_checkpoint;
+use script_task::{ScriptTask, ScriptChan, ScriptMsg, ConnectMsg, CloseWorkerMsg};
+use script_task::StackRootTLS;
+use security::Origin;
+use task_source::{TaskSource, DOMManipulationTaskSource};
+
+use servo_net::resource_task::{ResourceTask, load_whole_resource};
+use servo_util::str::DOMString;
+
+use js::rust::Cx;
+
+use std::collections::hashmap::HashMap;
+use std::rc::Rc;
+use std::sync::atomics::{AtomicBool, AtomicUint, INIT_ATOMIC_UINT, SeqCst};
+use std::task::TaskBuilder;
+use native::task::NativeTaskBuilder;
+use sync::{Arc, Mutex};
+use url::Url;
+
+/// What shared workers are found by: the serialized origin of the global
+/// that connects, the URL of the script and the name of the worker.
+type SharedWorkerKey = (String, String, DOMString);
+
+/// A shared worker that is running.
+struct SharedWorkerEntry {
+    /// Tells an entry apart from those of the workers that ran under the
+    /// same key before.
+    id: uint,
+    /// The event loop of the worker.
+    script_chan: ScriptChan,
+    /// Set once the worker closed itself or lost its last owner.
+    closing: Arc<AtomicBool>,
+    /// The number of SharedWorkerOwners for the worker.
+    owners: uint,
+}
+
+static mut next_shared_worker_id: AtomicUint = INIT_ATOMIC_UINT;
+
+lazy_static! {
+    /// The shared workers of every script task, which all connect to the same
+    /// ones.
+    static ref REGISTRY: Mutex<HashMap<SharedWorkerKey, SharedWorkerEntry>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Forgets the worker `id`, unless another one already replaced it.
+fn unregister(key: &SharedWorkerKey, id: uint) {
+    let mut workers = REGISTRY.lock();
+    let current = match workers.find(key) {
+        Some(entry) => entry.id == id,
+        None => false,
+    };
+    if current {
+        workers.remove(key);
+    }
+}
+
+/// Keeps a shared worker running for as long as the global that connected
+/// to it, which holds on to it.
+/// http://www.whatwg.org/html/#the-worker's-owners
+pub struct SharedWorkerOwner {
+    key: SharedWorkerKey,
+    id: uint,
+}
+
+impl Drop for SharedWorkerOwner {
+    fn drop(&mut self) {
+        let mut workers = REGISTRY.lock();
+        let last = match workers.find_mut(&self.key) {
+            Some(entry) => {
+                if entry.id != self.id {
+                    return;
+                }
+                entry.owners -= 1;
+                entry.owners == 0
+            },
+            None => false,
+        };
+        if last {
+            let entry = workers.pop(&self.key).unwrap();
+            entry.closing.store(true, SeqCst);
+            entry.script_chan.queue_task(DOMManipulationTaskSource, CloseWorkerMsg);
+        }
+    }
+}
+
+#[jstraceable]
+#[must_root]
+pub struct SharedWorkerGlobalScope {
+    workerglobalscope: WorkerGlobalScope,
+    receiver: Untraceable<Receiver<(TaskSource, ScriptMsg)>>,
+    name: DOMString,
+    key: Untraceable<SharedWorkerKey>,
+    id: uint,
+}
+
+impl SharedWorkerGlobalScope {
+    fn new_inherited(worker_url: Url,
+                     name: DOMString,
+                     key: SharedWorkerKey,
+                     id: uint,
+                     cx: Rc<Cx>,
+                     resource_task: ResourceTask,
+                     own_sender: ScriptChan,
+                     receiver: Receiver<(TaskSource, ScriptMsg)>,
+                     closing: Arc<AtomicBool>)
+                     -> SharedWorkerGlobalScope {
+        SharedWorkerGlobalScope {
+            workerglobalscope: WorkerGlobalScope::new_inherited(
+                SharedGlobalScope, worker_url, cx, resource_task,
+                own_sender, closing),
+            receiver: Untraceable::new(receiver),
+            name: name,
+            key: Untraceable::new(key),
+            id: id,
+        }
+    }
+
+    fn new(worker_url: Url,
+           name: DOMString,
+           key: SharedWorkerKey,
+           id: uint,
+           cx: Rc<Cx>,
+           resource_task: ResourceTask,
+           own_sender: ScriptChan,
+           receiver: Receiver<(TaskSource, ScriptMsg)>,
+           closing: Arc<AtomicBool>)
+           -> Temporary<SharedWorkerGlobalScope> {
+        let scope = box SharedWorkerGlobalScope::new_inherited(
+            worker_url, name, key, id, cx.clone(), resource_task, own_sender,
+            receiver, closing);
+        SharedWorkerGlobalScopeBinding::Wrap(cx.ptr, scope)
+    }
+
+    /// Connects `endpoint` to the shared worker that the global of `origin`
+    /// knows as `name` and runs `worker_url`, and starts that worker first if
+    /// it is not running yet. The worker keeps running for as long as the
+    /// returned owner is alive.
+    /// http://www.whatwg.org/html/#dom-sharedworker
+    pub fn connect(origin: &Origin, worker_url: Url, name: DOMString,
+                   resource_task: ResourceTask, endpoint: PortEndpoint) -> SharedWorkerOwner {
+        let key = (origin.serialize(), worker_url.serialize(), name.clone());
+        let mut workers = REGISTRY.lock();
+
+        // A worker that is closing can't be connected to any more, and the
+        // new one replaces it.
+        let running = match workers.find_mut(&key) {
+            Some(entry) => {
+                if entry.closing.load(SeqCst) {
+                    None
+                } else {
+                    entry.owners += 1;
+                    Some((entry.id, entry.script_chan.clone()))
+                }
+            },
+            None => None,
+        };
+        match running {
+            Some((id, script_chan)) => {
+                script_chan.queue_task(DOMManipulationTaskSource, ConnectMsg(endpoint));
+                return SharedWorkerOwner { key: key, id: id };
+            },
+            None => {},
+        }
+
+        let id = unsafe { next_shared_worker_id.fetch_add(1, SeqCst) };
+        let (receiver, sender) = ScriptChan::new();
+        let closing = Arc::new(AtomicBool::new(false));
+        // The connection is handled as soon as the script ran.
+        sender.queue_task(DOMManipulationTaskSource, ConnectMsg(endpoint));
+        workers.insert(key.clone(), SharedWorkerEntry {
+            id: id,
+            script_chan: sender.clone(),
+            closing: closing.clone(),
+            owners: 1,
+        });
+        SharedWorkerGlobalScope::run_worker_scope(worker_url, name, key.clone(), id,
+                                                  resource_task, sender, receiver, closing);
+        SharedWorkerOwner { key: key, id: id }
+    }
+
+    fn run_worker_scope(worker_url: Url,
+                        name: DOMString,
+                        key: SharedWorkerKey,
+                        id: uint,
+                        resource_task: ResourceTask,
+                        own_sender: ScriptChan,
+                        receiver: Receiver<(TaskSource, ScriptMsg)>,
+                        closing: Arc<AtomicBool>) {
+        TaskBuilder::new()
+            .native()
+            .named(format!("Shared Worker at {}", worker_url.serialize()))
+            .spawn(proc() {
+            let roots = RootCollection::new();
+            let _stack_roots_tls = StackRootTLS::new(&roots);
+
+            let (url, source) = match load_whole_resource(&resource_task, worker_url.clone()) {
+                Err(_) => {
+                    println!("error loading script {}", worker_url.serialize());
+                    unregister(&key, id);
+                    return;
+                }
+                Ok((metadata, bytes)) => {
+                    (metadata.final_url, String::from_utf8(bytes).unwrap())
+                }
+            };
+
+            let (_js_runtime, js_context) = ScriptTask::new_rt_and_cx();
+            let global = SharedWorkerGlobalScope::new(
+                worker_url, name, key, id, js_context.clone(), resource_task,
+                own_sender, receiver, closing).root();
+            match js_context.evaluate_script(
+                global.reflector().get_jsobject(), source, url.serialize(), 1) {
+                Ok(_) => (),
+                Err(_) => println!("evaluate_script failed")
+            }
+            perform_microtask_checkpoint();
+
+            let scope: JSRef<WorkerGlobalScope> =
+                WorkerGlobalScopeCast::from_ref(*global);
+            let target: JSRef<EventTarget> =
+                EventTargetCast::from_ref(*global);
+            loop {
+                // A worker that closed itself is forgotten right away rather
+                // than when its next task comes.
+                if scope.is_closing() {
+                    break;
+                }
+                // Nothing but the worker itself uses its event loop, so
+                // tasks run in the order they came whatever their source.
+                let msg = match global.receiver.deref().recv_opt() {
+                    Ok((_, msg)) => msg,
+                    Err(_) => break,
+                };
+                if scope.is_closing() {
+                    break;
+                }
+                match msg {
+                    ConnectMsg(endpoint) => {
+                        MessageEvent::dispatch_connect(target, &global::Worker(scope), endpoint);
+                    },
+                    msg => scope.handle_script_msg(msg),
+                }
+                perform_microtask_checkpoint();
+            }
+
+            scope.tear_down();
+            unregister(&*global.key, global.id);
+        });
+    }
+}
+
+impl<'a> SharedWorkerGlobalScopeMethods for JSRef<'a, SharedWorkerGlobalScope> {
+    fn Name(self) -> DOMString {
+        self.name.clone()
+    }
+
+    event_handler!(connect, GetOnconnect, SetOnconnect)
+}
+
+impl Reflectable for SharedWorkerGlobalScope {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        self.workerglobalscope.reflector()
+    }
+}
+
+impl SharedWorkerGlobalScopeDerived for EventTarget {
+    fn is_sharedworkerglobalscope(&self) -> bool {
+        match self.type_id {
+            WorkerGlobalScopeTypeId(SharedGlobalScope) => true,
+            _ => false
+        }
+    }
+}
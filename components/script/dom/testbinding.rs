@@ -2,22 +2,52 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use dom::bindings::codegen::Bindings::CharacterDataBinding::CharacterDataMethods;
+use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
 use dom::bindings::codegen::Bindings::TestBindingBinding::TestBindingMethods;
 use dom::bindings::codegen::Bindings::TestBindingBinding::TestEnum;
 use dom::bindings::codegen::Bindings::TestBindingBinding::TestEnumValues::_empty;
+use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use dom::bindings::codegen::InheritTypes::CharacterDataCast;
 use dom::bindings::codegen::UnionTypes::BlobOrString::BlobOrString;
 use dom::bindings::codegen::UnionTypes::EventOrString::{EventOrString, eString};
 use dom::bindings::codegen::UnionTypes::HTMLElementOrLong::{HTMLElementOrLong, eLong};
-use dom::bindings::global::GlobalField;
-use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::error::{Abort, ErrorResult, Fallible, NotSupported, Syntax};
+use dom::bindings::global::{GlobalField, GlobalRef};
+use dom::bindings::js::{JSRef, RootedVec, Temporary};
 use dom::bindings::str::ByteString;
 use dom::bindings::utils::{Reflector, Reflectable};
 use dom::blob::Blob;
+use dom::characterdata::CharacterData;
+use dom::document::{Document, DocumentHelpers};
+use dom::node::{Node, NodeHelpers};
+use dom::promise::{Promise, PromiseHelpers};
+use dom::window::WindowHelpers;
+use script_task::{PanicMsg, SendTestEventMsg};
+use script_traits::{CompositorEvent, KeyEvent, CharacterKey, BackspaceKey, TabKey, EnterKey};
+use script_traits::EscapeKey;
+use script_traits::{ClickEvent, MouseDownEvent, MouseUpEvent, ResizeEvent};
+use script_traits::{ExitPipelineMsg, ScriptControlChan};
+use servo_msg::compositor_msg::{LayerId, ReadyState, ScriptListener};
+use servo_msg::constellation_msg::PipelineId;
+use servo_net::image_cache_task::{Evict, GetFetchCount};
 use servo_util::str::DOMString;
+use servo_util::task::spawn_named;
+use url::UrlParser;
+use task_source::{DOMManipulationTaskSource, UserInteractionTaskSource};
+
+use geom::point::Point2D;
+use geom::size::TypedSize2D;
 
 use js::jsapi::JSContext;
 use js::jsval::{JSVal, NullValue};
 
+use std::cell::RefCell;
+use std::comm::channel;
+use std::io::timer::sleep;
+use std::rc::Rc;
+use std::time::duration::Duration;
+
 #[jstraceable]
 #[must_root]
 pub struct TestBinding {
@@ -56,7 +86,7 @@ impl<'a> TestBindingMethods for JSRef<'a, TestBinding> {
     fn SetEnumAttribute(self, _: TestEnum) {}
     fn InterfaceAttribute(self) -> Temporary<Blob> {
         let global = self.global.root();
-        Blob::new(&global.root_ref())
+        Blob::new(&global.root_ref(), vec!(), "".to_string())
     }
     fn SetInterfaceAttribute(self, _: JSRef<Blob>) {}
     fn UnionAttribute(self) -> HTMLElementOrLong { eLong(0) }
@@ -95,7 +125,7 @@ impl<'a> TestBindingMethods for JSRef<'a, TestBinding> {
     fn GetEnumAttributeNullable(self) -> Option<TestEnum> { Some(_empty) }
     fn GetInterfaceAttributeNullable(self) -> Option<Temporary<Blob>> {
         let global = self.global.root();
-        Some(Blob::new(&global.root_ref()))
+        Some(Blob::new(&global.root_ref(), vec!(), "".to_string()))
     }
     fn SetInterfaceAttributeNullable(self, _: Option<JSRef<Blob>>) {}
     fn GetUnionAttributeNullable(self) -> Option<HTMLElementOrLong> { Some(eLong(0)) }
@@ -119,7 +149,7 @@ impl<'a> TestBindingMethods for JSRef<'a, TestBinding> {
     fn ReceiveEnum(self) -> TestEnum { _empty }
     fn ReceiveInterface(self) -> Temporary<Blob> {
         let global = self.global.root();
-        Blob::new(&global.root_ref())
+        Blob::new(&global.root_ref(), vec!(), "".to_string())
     }
     fn ReceiveAny(self, _: *mut JSContext) -> JSVal { NullValue() }
     fn ReceiveUnion(self) -> HTMLElementOrLong { eLong(0) }
@@ -141,7 +171,7 @@ impl<'a> TestBindingMethods for JSRef<'a, TestBinding> {
     fn ReceiveNullableEnum(self) -> Option<TestEnum> { Some(_empty) }
     fn ReceiveNullableInterface(self) -> Option<Temporary<Blob>> {
         let global = self.global.root();
-        Some(Blob::new(&global.root_ref()))
+        Some(Blob::new(&global.root_ref(), vec!(), "".to_string()))
     }
     fn ReceiveNullableUnion(self) -> Option<HTMLElementOrLong> { Some(eLong(0)) }
     fn ReceiveNullableUnion2(self) -> Option<EventOrString> { Some(eString("".to_string())) }
@@ -290,7 +320,196 @@ impl<'a> TestBindingMethods for JSRef<'a, TestBinding> {
 impl TestBinding {
     pub fn BooleanAttributeStatic() -> bool { false }
     pub fn SetBooleanAttributeStatic(_: bool) {}
-    pub fn ReceiveVoidStatic() {}
+    pub fn ReceiveVoidStatic(_: &GlobalRef) {}
+
+    pub fn ResolvePromiseAfter(global: &GlobalRef, value: DOMString, ms: u32) -> Temporary<Promise> {
+        let promise = Promise::new(global).root();
+        let trusted = promise.trusted();
+        spawn_named("TestBinding:ResolvePromiseAfter", proc() {
+            sleep(Duration::milliseconds(ms as i64));
+            trusted.resolve(box value);
+        });
+        Temporary::from_rooted(*promise)
+    }
+
+    pub fn RejectPromiseAfter(global: &GlobalRef, ms: u32) -> Temporary<Promise> {
+        let promise = Promise::new(global).root();
+        let trusted = promise.trusted();
+        spawn_named("TestBinding:RejectPromiseAfter", proc() {
+            sleep(Duration::milliseconds(ms as i64));
+            trusted.reject(Abort);
+        });
+        Temporary::from_rooted(*promise)
+    }
+
+    pub fn RejectPromiseWithError(_: &GlobalRef) -> Fallible<Temporary<Promise>> {
+        Err(NotSupported)
+    }
+
+    pub fn IsDirty(_: &GlobalRef, node: JSRef<Node>) -> bool {
+        node.is_dirty()
+    }
+
+    pub fn HasDirtyDescendants(_: &GlobalRef, node: JSRef<Node>) -> bool {
+        node.has_dirty_descendants()
+    }
+
+    pub fn DomGeneration(_: &GlobalRef, document: JSRef<Document>) -> u32 {
+        document.dom_generation() as u32
+    }
+
+    pub fn ReflowCount(_: &GlobalRef, document: JSRef<Document>) -> u32 {
+        let window = document.window.root();
+        window.page().last_reflow_id.deref().get() as u32
+    }
+
+    pub fn PendingStylesheets(_: &GlobalRef, document: JSRef<Document>) -> u32 {
+        let window = document.window.root();
+        window.page().pending_stylesheet_count() as u32
+    }
+
+    pub fn MemoryUsage(_: &GlobalRef, document: JSRef<Document>, category: DOMString) -> u32 {
+        let report = document.memory_report();
+        let size = match category.as_slice() {
+            "dom-nodes" => report.dom_nodes,
+            "strings" => report.strings,
+            "event-listeners" => report.event_listeners,
+            _ => 0,
+        };
+        size as u32
+    }
+
+    pub fn UnloadDocument(_: &GlobalRef, document: JSRef<Document>) -> Option<DOMString> {
+        let window = document.window.root();
+        let recorder = PromptRecorder { message: Rc::new(RefCell::new(None)) };
+        window.unload(&recorder);
+        let message = recorder.message.borrow().clone();
+        message
+    }
+
+    pub fn RootedNodesAfterGC(_: &GlobalRef, document: JSRef<Document>, count: u32) -> u32 {
+        let mut nodes = RootedVec::new();
+        for i in range(0, count) {
+            let text = document.CreateTextNode(i.to_string()).root();
+            nodes.push(*text);
+        }
+        let window = document.window.root();
+        window.Gc();
+        nodes.iter().enumerate().filter(|&(i, text)| {
+            let data: JSRef<CharacterData> = CharacterDataCast::from_ref(text);
+            data.Data() == i.to_string()
+        }).count() as u32
+    }
+
+    pub fn PanicSoon(_: &GlobalRef, document: JSRef<Document>) {
+        let window = document.window.root();
+        window.script_chan.queue_task(DOMManipulationTaskSource, PanicMsg(window.page().id));
+    }
+
+    pub fn Panic(_: &GlobalRef) {
+        fail!("panicking as asked");
+    }
+
+    pub fn PipelineId(_: &GlobalRef, document: JSRef<Document>) -> u32 {
+        let window = document.window.root();
+        let PipelineId(id) = window.page().id;
+        id as u32
+    }
+
+    pub fn ExitPipeline(_: &GlobalRef, document: JSRef<Document>, id: u32) {
+        let window = document.window.root();
+        let ScriptControlChan(ref chan) = window.control_chan;
+        chan.send(ExitPipelineMsg(PipelineId(id as uint)));
+    }
+
+    pub fn ImageFetchCount(_: &GlobalRef, document: JSRef<Document>, url: DOMString) -> Fallible<u32> {
+        let url = try!(UrlParser::new().base_url(document.url()).parse(url.as_slice())
+                                       .map_err(|_| Syntax));
+        let window = document.window.root();
+        let (chan, port) = channel();
+        window.image_cache_task.send(GetFetchCount(url, chan));
+        Ok(port.recv() as u32)
+    }
+
+    pub fn EvictImage(_: &GlobalRef, document: JSRef<Document>, url: DOMString) -> ErrorResult {
+        let url = try!(UrlParser::new().base_url(document.url()).parse(url.as_slice())
+                                       .map_err(|_| Syntax));
+        let window = document.window.root();
+        window.image_cache_task.send(Evict(url));
+        Ok(())
+    }
+
+    pub fn NotifiedTitle(_: &GlobalRef, document: JSRef<Document>) -> Option<DOMString> {
+        document.notified_title()
+    }
+
+    pub fn LastPings(_: &GlobalRef, document: JSRef<Document>) -> DOMString {
+        let pings: Vec<String> = document.last_pings().iter().map(|ping| ping.serialize()).collect();
+        pings.connect(" ")
+    }
+
+    pub fn SendKey(_: &GlobalRef, document: JSRef<Document>, key: DOMString) -> ErrorResult {
+        let key = match key.as_slice() {
+            "Backspace" => BackspaceKey,
+            "Tab" => TabKey,
+            "Enter" => EnterKey,
+            "Escape" => EscapeKey,
+            key if key.char_len() == 1 => CharacterKey(key.char_at(0)),
+            _ => return Err(Syntax),
+        };
+        send_test_event(document, KeyEvent(key));
+        Ok(())
+    }
+
+    pub fn SendMouseEvent(_: &GlobalRef, document: JSRef<Document>, type_: DOMString,
+                          x: f32, y: f32) -> ErrorResult {
+        let point = Point2D(x, y);
+        let event = match type_.as_slice() {
+            "click" => ClickEvent(0, point),
+            "mousedown" => MouseDownEvent(0, point),
+            "mouseup" => MouseUpEvent(0, point),
+            _ => return Err(Syntax),
+        };
+        send_test_event(document, event);
+        Ok(())
+    }
+
+    pub fn ResizeViewport(_: &GlobalRef, document: JSRef<Document>, width: f32, height: f32) {
+        let window = document.window.root();
+        let mut size = window.page().window_size.deref().get();
+        size.initial_viewport = TypedSize2D(width, height);
+        size.visible_viewport = TypedSize2D(width, height);
+        send_test_event(document, ResizeEvent(size));
+    }
+}
+
+/// Queues `event` for the page of `document` as a user interaction task.
+fn send_test_event(document: JSRef<Document>, event: CompositorEvent) {
+    let window = document.window.root();
+    window.script_chan.queue_task(UserInteractionTaskSource,
+                                  SendTestEventMsg(window.page().id, event));
+}
+
+/// Stands in for the compositor while a test unloads a document, and keeps
+/// the message the user would have been prompted with.
+#[deriving(Clone)]
+struct PromptRecorder {
+    message: Rc<RefCell<Option<String>>>,
+}
+
+impl ScriptListener for PromptRecorder {
+    fn set_ready_state(&self, _: PipelineId, _: ReadyState) {}
+    fn set_title(&self, _: PipelineId, _: Option<String>) {}
+    fn scroll_fragment_point(&self, _: PipelineId, _: LayerId, _: Point2D<f32>) {}
+    fn close(&self) {}
+
+    fn prompt_before_unload(&self, _: PipelineId, message: String) {
+        *self.message.borrow_mut() = Some(message);
+    }
+
+    fn dup(&self) -> Box<ScriptListener+'static> {
+        box self.clone() as Box<ScriptListener+'static>
+    }
 }
 
 impl Reflectable for TestBinding {
@@ -0,0 +1,224 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::URLBinding;
+use dom::bindings::codegen::Bindings::URLBinding::URLMethods;
+use dom::bindings::error::{ErrorResult, Fallible, Type};
+use dom::bindings::global::GlobalRef;
+use dom::bindings::js::{JS, JSRef, OptionalSettable, Temporary};
+use dom::bindings::trace::Untraceable;
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::blob::{Blob, BlobHelpers};
+use dom::urlhelper::UrlHelper;
+use dom::urlsearchparams::{URLSearchParams, URLSearchParamsHelpers, parse};
+use servo_net::blob_loader::BlobURLEntry;
+use servo_net::resource_task::{RegisterBlobURL, RevokeBlobURL};
+use servo_util::str::DOMString;
+
+use std::cell::{Cell, RefCell};
+use url::{Url, UrlParser};
+use uuid::Uuid;
+
+#[jstraceable]
+#[must_root]
+pub struct URL {
+    reflector_: Reflector,
+    url: Untraceable<RefCell<Url>>,
+    /// The `URLSearchParams` reflecting the query of the URL.
+    search_params: Cell<Option<JS<URLSearchParams>>>,
+}
+
+impl URL {
+    fn new_inherited(url: Url) -> URL {
+        URL {
+            reflector_: Reflector::new(),
+            url: Untraceable::new(RefCell::new(url)),
+            search_params: Cell::new(None),
+        }
+    }
+
+    pub fn new(global: &GlobalRef, url: Url) -> Temporary<URL> {
+        let url = reflect_dom_object(box URL::new_inherited(url), global, URLBinding::Wrap).root();
+        let search_params = URLSearchParams::new(global).root();
+        search_params.set_url(*url);
+        url.search_params.assign(Some(*search_params));
+        url.update_search_params();
+        Temporary::from_rooted(*url)
+    }
+
+    // http://url.spec.whatwg.org/#dom-url-url
+    pub fn Constructor(global: &GlobalRef, url: DOMString,
+                       base: Option<DOMString>) -> Fallible<Temporary<URL>> {
+        let base = match base {
+            Some(base) => match Url::parse(base.as_slice()) {
+                Ok(base) => Some(base),
+                Err(_) => return Err(Type("Invalid base URL".to_string())),
+            },
+            None => None,
+        };
+        let mut parser = UrlParser::new();
+        match base {
+            Some(ref base) => { parser.base_url(base); },
+            None => {}
+        }
+        match parser.parse(url.as_slice()) {
+            Ok(url) => Ok(URL::new(global, url)),
+            Err(_) => Err(Type("Invalid URL".to_string())),
+        }
+    }
+
+    // http://dev.w3.org/2006/webapi/FileAPI/#dfn-createObjectURL
+    pub fn CreateObjectURL(global: &GlobalRef, blob: JSRef<Blob>) -> DOMString {
+        let origin = global.get_origin().serialize();
+        let url = format!("blob:{}/{}", origin, Uuid::new_v4().to_hyphenated_string());
+        let entry = BlobURLEntry {
+            type_: blob.type_string(),
+            bytes: blob.bytes(),
+            owner: global.blob_url_owner(),
+        };
+        global.resource_task().send(RegisterBlobURL(Url::parse(url.as_slice()).unwrap(), entry));
+        url
+    }
+
+    // http://dev.w3.org/2006/webapi/FileAPI/#dfn-revokeObjectURL
+    pub fn RevokeObjectURL(global: &GlobalRef, url: DOMString) {
+        // Only the origin that created a URL can revoke it.
+        let prefix = format!("{}/", global.get_origin().serialize());
+        match Url::parse(url.as_slice()) {
+            Ok(ref url) if url.scheme.as_slice() == "blob" &&
+                           url.non_relative_scheme_data().map_or(false, |data| {
+                               data.starts_with(prefix.as_slice())
+                           }) => {
+                global.resource_task().send(RevokeBlobURL(url.clone()));
+            },
+            _ => {}
+        }
+    }
+}
+
+pub trait URLHelpers {
+    fn set_query(self, query: String);
+    fn update_search_params(self);
+}
+
+impl<'a> URLHelpers for JSRef<'a, URL> {
+    /// Sets the query of the URL from its `URLSearchParams`.
+    fn set_query(self, query: String) {
+        self.url.deref().borrow_mut().query = Some(query);
+    }
+
+    /// Makes the `URLSearchParams` reflect the current query of the URL.
+    fn update_search_params(self) {
+        let list = match self.url.deref().borrow().query {
+            Some(ref query) => parse(query.as_slice()),
+            None => vec!(),
+        };
+        self.search_params.get().unwrap().root().set_list(list);
+    }
+}
+
+impl<'a> URLMethods for JSRef<'a, URL> {
+    // http://url.spec.whatwg.org/#dom-urlutils-href
+    fn Href(self) -> DOMString {
+        UrlHelper::Href(&*self.url.deref().borrow())
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-href
+    fn SetHref(self, value: DOMString) -> ErrorResult {
+        match Url::parse(value.as_slice()) {
+            Ok(url) => *self.url.deref().borrow_mut() = url,
+            Err(_) => return Err(Type("Invalid URL".to_string())),
+        }
+        self.update_search_params();
+        Ok(())
+    }
+
+    fn Origin(self) -> DOMString {
+        UrlHelper::Origin(&*self.url.deref().borrow())
+    }
+
+    fn Protocol(self) -> DOMString {
+        UrlHelper::Protocol(&*self.url.deref().borrow())
+    }
+
+    fn SetProtocol(self, value: DOMString) {
+        UrlHelper::SetProtocol(&mut *self.url.deref().borrow_mut(), value);
+    }
+
+    fn Username(self) -> DOMString {
+        UrlHelper::Username(&*self.url.deref().borrow())
+    }
+
+    fn SetUsername(self, value: DOMString) {
+        UrlHelper::SetUsername(&mut *self.url.deref().borrow_mut(), value);
+    }
+
+    fn Password(self) -> DOMString {
+        UrlHelper::Password(&*self.url.deref().borrow())
+    }
+
+    fn SetPassword(self, value: DOMString) {
+        UrlHelper::SetPassword(&mut *self.url.deref().borrow_mut(), value);
+    }
+
+    fn Host(self) -> DOMString {
+        UrlHelper::Host(&*self.url.deref().borrow())
+    }
+
+    fn SetHost(self, value: DOMString) {
+        UrlHelper::SetHost(&mut *self.url.deref().borrow_mut(), value);
+    }
+
+    fn Hostname(self) -> DOMString {
+        UrlHelper::Hostname(&*self.url.deref().borrow())
+    }
+
+    fn SetHostname(self, value: DOMString) {
+        UrlHelper::SetHostname(&mut *self.url.deref().borrow_mut(), value);
+    }
+
+    fn Port(self) -> DOMString {
+        UrlHelper::Port(&*self.url.deref().borrow())
+    }
+
+    fn SetPort(self, value: DOMString) {
+        UrlHelper::SetPort(&mut *self.url.deref().borrow_mut(), value);
+    }
+
+    fn Pathname(self) -> DOMString {
+        UrlHelper::Pathname(&*self.url.deref().borrow())
+    }
+
+    fn SetPathname(self, value: DOMString) {
+        UrlHelper::SetPathname(&mut *self.url.deref().borrow_mut(), value);
+    }
+
+    fn Search(self) -> DOMString {
+        UrlHelper::Search(&*self.url.deref().borrow())
+    }
+
+    fn SetSearch(self, value: DOMString) {
+        UrlHelper::SetSearch(&mut *self.url.deref().borrow_mut(), value);
+        self.update_search_params();
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-searchparams
+    fn SearchParams(self) -> Temporary<URLSearchParams> {
+        Temporary::new(self.search_params.get().unwrap())
+    }
+
+    fn Hash(self) -> DOMString {
+        UrlHelper::Hash(&*self.url.deref().borrow())
+    }
+
+    fn SetHash(self, value: DOMString) {
+        UrlHelper::SetHash(&mut *self.url.deref().borrow_mut(), value);
+    }
+}
+
+impl Reflectable for URL {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        &self.reflector_
+    }
+}
@@ -3,7 +3,7 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use servo_util::str::DOMString;
-use url::Url;
+use url::{Host, Url, UrlParser, RelativeSchemeData, NonRelativeSchemeData};
 
 pub struct UrlHelper;
 
@@ -12,6 +12,76 @@ impl UrlHelper {
         url.serialize()
     }
 
+    // http://url.spec.whatwg.org/#dom-urlutils-origin
+    pub fn Origin(url: &Url) -> DOMString {
+        match url.scheme.as_slice() {
+            "ftp" | "gopher" | "http" | "https" | "ws" | "wss" => {
+                let host = UrlHelper::Host(url);
+                format!("{}://{}", url.scheme, host)
+            },
+            _ => "null".to_string(),
+        }
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-protocol
+    pub fn Protocol(url: &Url) -> DOMString {
+        format!("{}:", url.scheme)
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-username
+    pub fn Username(url: &Url) -> DOMString {
+        match url.scheme_data {
+            RelativeSchemeData(ref data) => data.username.clone(),
+            NonRelativeSchemeData(_) => "".to_string(),
+        }
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-password
+    pub fn Password(url: &Url) -> DOMString {
+        match url.scheme_data {
+            RelativeSchemeData(ref data) => data.password.clone().unwrap_or("".to_string()),
+            NonRelativeSchemeData(_) => "".to_string(),
+        }
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-host
+    pub fn Host(url: &Url) -> DOMString {
+        match url.scheme_data {
+            RelativeSchemeData(ref data) => match data.port {
+                Some(port) => format!("{}:{}", data.host.serialize(), port),
+                None => data.host.serialize(),
+            },
+            NonRelativeSchemeData(_) => "".to_string(),
+        }
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-hostname
+    pub fn Hostname(url: &Url) -> DOMString {
+        match url.scheme_data {
+            RelativeSchemeData(ref data) => data.host.serialize(),
+            NonRelativeSchemeData(_) => "".to_string(),
+        }
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-port
+    pub fn Port(url: &Url) -> DOMString {
+        match url.scheme_data {
+            RelativeSchemeData(ref data) => match data.port {
+                Some(port) => port.to_string(),
+                None => "".to_string(),
+            },
+            NonRelativeSchemeData(_) => "".to_string(),
+        }
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-pathname
+    pub fn Pathname(url: &Url) -> DOMString {
+        match url.scheme_data {
+            RelativeSchemeData(ref data) => format!("/{}", data.path.connect("/")),
+            NonRelativeSchemeData(ref data) => data.clone(),
+        }
+    }
+
     pub fn Search(url: &Url) -> DOMString {
         match url.query {
             None => "".to_string(),
@@ -27,4 +97,212 @@ impl UrlHelper {
             Some(ref hash) => format!("#{}", hash)
         }
     }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-protocol
+    pub fn SetProtocol(url: &mut Url, value: DOMString) {
+        // Only the scheme of the value is used, and a relative URL only
+        // becomes another relative one.
+        let scheme = value.as_slice().split(':').next().unwrap();
+        let is_scheme_char = |c: char| {
+            (c as u32) < 0x80 && (c.is_alphanumeric() || c == '+' || c == '-' || c == '.')
+        };
+        if scheme.is_empty() || !scheme.char_at(0).is_alphabetic() ||
+           !scheme.chars().all(is_scheme_char) {
+            return;
+        }
+        let serialized = url.serialize();
+        let rest = serialized.as_slice().slice_from(url.scheme.len() + 1);
+        match Url::parse(format!("{}:{}", scheme, rest).as_slice()) {
+            Ok(new_url) => {
+                if is_relative(&new_url) == is_relative(url) {
+                    *url = new_url;
+                }
+            },
+            Err(_) => {}
+        }
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-username
+    pub fn SetUsername(url: &mut Url, value: DOMString) {
+        match url.scheme_data {
+            RelativeSchemeData(ref mut data) => {
+                if !is_empty_host(&data.host) {
+                    data.username = percent_encode(value.as_slice(), in_userinfo_encode_set);
+                }
+            },
+            NonRelativeSchemeData(_) => {}
+        }
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-password
+    pub fn SetPassword(url: &mut Url, value: DOMString) {
+        match url.scheme_data {
+            RelativeSchemeData(ref mut data) => {
+                if !is_empty_host(&data.host) {
+                    data.password = if value.is_empty() {
+                        None
+                    } else {
+                        Some(percent_encode(value.as_slice(), in_userinfo_encode_set))
+                    };
+                }
+            },
+            NonRelativeSchemeData(_) => {}
+        }
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-host
+    pub fn SetHost(url: &mut Url, value: DOMString) {
+        let (host, port) = split_host_and_port(value.as_slice());
+        UrlHelper::SetHostname(url, host.to_string());
+        match port {
+            Some(port) => UrlHelper::SetPort(url, port.to_string()),
+            None => {}
+        }
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-hostname
+    pub fn SetHostname(url: &mut Url, value: DOMString) {
+        let (host, _) = split_host_and_port(value.as_slice());
+        if host.is_empty() {
+            return;
+        }
+        match url.scheme_data {
+            RelativeSchemeData(ref mut data) => match Host::parse(host) {
+                Ok(host) => data.host = host,
+                Err(_) => {}
+            },
+            NonRelativeSchemeData(_) => {}
+        }
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-port
+    pub fn SetPort(url: &mut Url, value: DOMString) {
+        if url.scheme.as_slice() == "file" {
+            return;
+        }
+        match url.scheme_data {
+            RelativeSchemeData(ref mut data) => {
+                // Setting the empty string removes the port; otherwise only
+                // the leading digits count.
+                if value.is_empty() {
+                    data.port = None;
+                    return;
+                }
+                let digits: String = value.as_slice().chars().take_while(|c| c.is_digit()).collect();
+                match from_str::<u16>(digits.as_slice()) {
+                    Some(port) if Some(port) == data.default_port => data.port = None,
+                    Some(port) => data.port = Some(port),
+                    None => {}
+                }
+            },
+            NonRelativeSchemeData(_) => {}
+        }
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-pathname
+    pub fn SetPathname(url: &mut Url, value: DOMString) {
+        if !is_relative(url) {
+            return;
+        }
+        // Parse the value as an absolute path against the URL itself, so that
+        // the dot segments get resolved and the characters encoded.
+        let path = value.as_slice().trim_left_chars('/').replace("?", "%3F").replace("#", "%23");
+        let path = format!("/{}", path);
+        let new_path = match UrlParser::new().base_url(url).parse(path.as_slice()) {
+            Ok(Url { scheme_data: RelativeSchemeData(data), .. }) => data.path,
+            _ => return,
+        };
+        match url.scheme_data {
+            RelativeSchemeData(ref mut data) => data.path = new_path,
+            NonRelativeSchemeData(_) => {}
+        }
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-search
+    pub fn SetSearch(url: &mut Url, value: DOMString) {
+        url.query = if value.is_empty() {
+            None
+        } else {
+            let value = value.as_slice();
+            let value = if value.starts_with("?") { value.slice_from(1) } else { value };
+            Some(percent_encode(value, in_query_encode_set))
+        };
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlutils-hash
+    pub fn SetHash(url: &mut Url, value: DOMString) {
+        url.fragment = if value.is_empty() {
+            None
+        } else {
+            let value = value.as_slice();
+            let value = if value.starts_with("#") { value.slice_from(1) } else { value };
+            Some(percent_encode(value, in_simple_encode_set))
+        };
+    }
+}
+
+fn is_relative(url: &Url) -> bool {
+    match url.scheme_data {
+        RelativeSchemeData(_) => true,
+        NonRelativeSchemeData(_) => false,
+    }
+}
+
+fn is_empty_host(host: &Host) -> bool {
+    host.serialize().is_empty()
+}
+
+/// Splits `value` into a host and what follows its `:`, ignoring the colons
+/// of an IPv6 address and anything after a `/`, `?` or `#`.
+fn split_host_and_port<'a>(value: &'a str) -> (&'a str, Option<&'a str>) {
+    let end = value.find(|c: char| c == '/' || c == '?' || c == '#' || c == '\\');
+    let value = match end {
+        Some(end) => value.slice_to(end),
+        None => value,
+    };
+    let after_ipv6 = match value.find(']') {
+        Some(index) => index + 1,
+        None => 0,
+    };
+    match value.slice_from(after_ipv6).find(':') {
+        Some(index) => (value.slice_to(after_ipv6 + index),
+                        Some(value.slice_from(after_ipv6 + index + 1))),
+        None => (value, None),
+    }
+}
+
+/// http://url.spec.whatwg.org/#simple-encode-set
+fn in_simple_encode_set(byte: u8) -> bool {
+    byte < 0x20 || byte > 0x7E
+}
+
+/// The bytes the query state of the URL parser percent-encodes.
+/// http://url.spec.whatwg.org/#query-state
+fn in_query_encode_set(byte: u8) -> bool {
+    byte < 0x21 || byte > 0x7E || byte == b'"' || byte == b'#' || byte == b'<' || byte == b'>'
+}
+
+/// http://url.spec.whatwg.org/#default-encode-set, plus the separators of
+/// the credentials.
+fn in_userinfo_encode_set(byte: u8) -> bool {
+    in_simple_encode_set(byte) ||
+    match byte {
+        b' ' | b'"' | b'#' | b'<' | b'>' | b'?' | b'`' |
+        b'/' | b'@' | b'\\' | b':' | b';' | b'=' | b'[' | b']' | b'^' | b'|' => true,
+        _ => false,
+    }
+}
+
+/// Returns the UTF-8 encoding of `value` with the bytes for which
+/// `encode` is true [percent-encoded](http://url.spec.whatwg.org/#percent-encode).
+fn percent_encode(value: &str, encode: fn(u8) -> bool) -> String {
+    let mut encoded = String::new();
+    for &byte in value.as_bytes().iter() {
+        if encode(byte) {
+            encoded.push_str(format!("%{:02X}", byte).as_slice());
+        } else {
+            encoded.push(byte as char);
+        }
+    }
+    encoded
 }
@@ -4,34 +4,40 @@
 
 use dom::bindings::codegen::Bindings::URLSearchParamsBinding;
 use dom::bindings::codegen::Bindings::URLSearchParamsBinding::URLSearchParamsMethods;
-use dom::bindings::codegen::UnionTypes::StringOrURLSearchParams::{StringOrURLSearchParams, eURLSearchParams, eString};
-use dom::bindings::error::{Fallible};
+use dom::bindings::codegen::UnionTypes::StringSequenceSequenceOrStringOrURLSearchParams::{StringSequenceSequenceOrStringOrURLSearchParams, eStringSequenceSequence, eString, eURLSearchParams};
+use dom::bindings::error::{Fallible, Type};
 use dom::bindings::global::GlobalRef;
-use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::js::{JS, JSRef, OptionalRootedRootable, OptionalSettable, Temporary};
 use dom::bindings::trace::Traceable;
 use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::url::{URL, URLHelpers};
 
 use servo_util::str::DOMString;
 
 use encoding::all::UTF_8;
 use encoding::types::{EncodingRef, EncodeReplace};
 
-use std::cell::RefCell;
-use std::collections::hashmap::HashMap;
+use std::cell::{Cell, RefCell};
 use std::fmt::radix;
 use std::ascii::OwnedStrAsciiExt;
+use std::iter;
+use url::percent_decode;
 
 #[jstraceable]
 #[must_root]
 pub struct URLSearchParams {
-    data: Traceable<RefCell<HashMap<DOMString, Vec<DOMString>>>>,
+    /// The name-value pairs, in order.
+    list: Traceable<RefCell<Vec<(DOMString, DOMString)>>>,
+    /// The `URL` whose query this object reflects, if any.
+    url: Cell<Option<JS<URL>>>,
     reflector_: Reflector,
 }
 
 impl URLSearchParams {
     fn new_inherited() -> URLSearchParams {
         URLSearchParams {
-            data: Traceable::new(RefCell::new(HashMap::new())),
+            list: Traceable::new(RefCell::new(vec!())),
+            url: Cell::new(None),
             reflector_: Reflector::new(),
         }
     }
@@ -40,19 +46,30 @@ impl URLSearchParams {
         reflect_dom_object(box URLSearchParams::new_inherited(), global, URLSearchParamsBinding::Wrap)
     }
 
-    pub fn Constructor(global: &GlobalRef, init: Option<StringOrURLSearchParams>) -> Fallible<Temporary<URLSearchParams>> {
+    // http://url.spec.whatwg.org/#dom-urlsearchparams-urlsearchparams
+    pub fn Constructor(global: &GlobalRef, init: Option<StringSequenceSequenceOrStringOrURLSearchParams>) -> Fallible<Temporary<URLSearchParams>> {
         let usp = URLSearchParams::new(global).root();
         match init {
-            Some(eString(_s)) => {
-                // XXXManishearth we need to parse the input here
-                // http://url.spec.whatwg.org/#concept-urlencoded-parser
-                // We can use rust-url's implementation here:
-                // https://github.com/SimonSapin/rust-url/blob/master/form_urlencoded.rs#L29
+            Some(eStringSequenceSequence(pairs)) => {
+                let mut list = usp.list.deref().borrow_mut();
+                for pair in pairs.into_iter() {
+                    if pair.len() != 2 {
+                        return Err(Type("Each pair must have a name and a value".to_string()));
+                    }
+                    let mut pair = pair.into_iter();
+                    let name = pair.next().unwrap();
+                    let value = pair.next().unwrap();
+                    list.push((name, value));
+                }
+            },
+            Some(eString(s)) => {
+                let s = s.as_slice();
+                let s = if s.starts_with("?") { s.slice_from(1) } else { s };
+                *usp.list.deref().borrow_mut() = parse(s);
             },
             Some(eURLSearchParams(u)) => {
                 let u = u.root();
-                let mut map = usp.deref().data.deref().borrow_mut();
-                *map = u.data.deref().borrow().clone();
+                *usp.list.deref().borrow_mut() = u.list.deref().borrow().clone();
             },
             None => {}
         }
@@ -61,29 +78,71 @@ impl URLSearchParams {
 }
 
 impl<'a> URLSearchParamsMethods for JSRef<'a, URLSearchParams> {
+    // http://url.spec.whatwg.org/#dom-urlsearchparams-append
     fn Append(self, name: DOMString, value: DOMString) {
-        self.data.deref().borrow_mut().insert_or_update_with(name, vec!(value.clone()),
-                                                             |_k, v| v.push(value.clone()));
+        self.list.deref().borrow_mut().push((name, value));
         self.update_steps();
     }
 
+    // http://url.spec.whatwg.org/#dom-urlsearchparams-delete
     fn Delete(self, name: DOMString) {
-        self.data.deref().borrow_mut().remove(&name);
+        self.list.deref().borrow_mut().retain(|&(ref n, _)| *n != name);
         self.update_steps();
     }
 
+    // http://url.spec.whatwg.org/#dom-urlsearchparams-get
     fn Get(self, name: DOMString) -> Option<DOMString> {
-        self.data.deref().borrow().find_equiv(&name).map(|v| v[0].clone())
+        self.list.deref().borrow().iter()
+            .find(|&&(ref n, _)| *n == name)
+            .map(|&(_, ref value)| value.clone())
     }
 
+    // http://url.spec.whatwg.org/#dom-urlsearchparams-getall
+    fn GetAll(self, name: DOMString) -> Vec<DOMString> {
+        self.list.deref().borrow().iter()
+            .filter(|&&(ref n, _)| *n == name)
+            .map(|&(_, ref value)| value.clone())
+            .collect()
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlsearchparams-has
     fn Has(self, name: DOMString) -> bool {
-        self.data.deref().borrow().contains_key_equiv(&name)
+        self.list.deref().borrow().iter().any(|&(ref n, _)| *n == name)
     }
 
+    // http://url.spec.whatwg.org/#dom-urlsearchparams-set
     fn Set(self, name: DOMString, value: DOMString) {
-        self.data.deref().borrow_mut().insert(name, vec!(value));
+        {
+            // The first pair of that name takes the new value, and the others
+            // go away.
+            let mut list = self.list.deref().borrow_mut();
+            match list.iter().position(|&(ref n, _)| *n == name) {
+                Some(index) => {
+                    *list.get_mut(index) = (name.clone(), value);
+                    let mut i = 0;
+                    list.retain(|&(ref n, _)| {
+                        i += 1;
+                        i - 1 <= index || *n != name
+                    });
+                },
+                None => list.push((name, value)),
+            }
+        }
+        self.update_steps();
+    }
+
+    // http://url.spec.whatwg.org/#dom-urlsearchparams-sort
+    fn Sort(self) {
+        // The sort is stable, and compares the names by code units.
+        self.list.deref().borrow_mut().sort_by(|&(ref a, _), &(ref b, _)| {
+            iter::order::cmp(a.as_slice().utf16_units(), b.as_slice().utf16_units())
+        });
         self.update_steps();
     }
+
+    fn Pairs(self) -> Vec<(DOMString, DOMString)> {
+        self.list.deref().borrow().clone()
+    }
 }
 
 impl Reflectable for URLSearchParams {
@@ -92,13 +151,31 @@ impl Reflectable for URLSearchParams {
     }
 }
 
+/// Returns the name-value pairs of a string in the
+/// `application/x-www-form-urlencoded` format.
+/// http://url.spec.whatwg.org/#concept-urlencoded-parser
+pub fn parse(input: &str) -> Vec<(DOMString, DOMString)> {
+    fn decode(bytes: &str) -> DOMString {
+        let bytes = bytes.replace("+", " ");
+        String::from_utf8_lossy(percent_decode(bytes.as_bytes()).as_slice()).into_string()
+    }
+    input.split('&').filter(|sequence| !sequence.is_empty()).map(|sequence| {
+        match sequence.find('=') {
+            Some(index) => (decode(sequence.slice_to(index)), decode(sequence.slice_from(index + 1))),
+            None => (decode(sequence), "".to_string()),
+        }
+    }).collect()
+}
+
 pub trait URLSearchParamsHelpers {
-    fn serialize(&self, encoding: Option<EncodingRef>) -> Vec<u8>;
-    fn update_steps(&self);
+    fn serialize(self, encoding: Option<EncodingRef>) -> Vec<u8>;
+    fn set_list(self, list: Vec<(DOMString, DOMString)>);
+    fn set_url(self, url: JSRef<URL>);
+    fn update_steps(self);
 }
 
-impl URLSearchParamsHelpers for URLSearchParams {
-    fn serialize(&self, encoding: Option<EncodingRef>) -> Vec<u8> {
+impl<'a> URLSearchParamsHelpers for JSRef<'a, URLSearchParams> {
+    fn serialize(self, encoding: Option<EncodingRef>) -> Vec<u8> {
         // http://url.spec.whatwg.org/#concept-urlencoded-serializer
         fn serialize_string(value: &DOMString, encoding: EncodingRef) -> Vec<u8> {
             // http://url.spec.whatwg.org/#concept-urlencoded-byte-serializer
@@ -116,7 +193,7 @@ impl URLSearchParamsHelpers for URLSearchParams {
                     a => {
                         // http://url.spec.whatwg.org/#percent-encode
                         let mut encoded = vec!(0x25); // %
-                        let s = format!("{}", radix(a, 16)).into_ascii_upper();
+                        let s = format!("{:02}", radix(a, 16)).into_ascii_upper();
                         let bytes = s.as_bytes();
                         encoded.push_all(bytes);
                         encoded
@@ -129,25 +206,40 @@ impl URLSearchParamsHelpers for URLSearchParams {
         let encoding = encoding.unwrap_or(UTF_8 as EncodingRef);
         let mut buf = vec!();
         let mut first_pair = true;
-        for (k, v) in self.data.deref().borrow().iter() {
+        for &(ref k, ref v) in self.list.deref().borrow().iter() {
             let name = serialize_string(k, encoding);
-            for val in v.iter() {
-                let value = serialize_string(val, encoding);
-                if first_pair {
-                    first_pair = false;
-                } else {
-                    buf.push(0x26); // &
-                }
-                buf.push_all(name.as_slice());
-                buf.push(0x3D); // =
-                buf.push_all(value.as_slice())
+            let value = serialize_string(v, encoding);
+            if first_pair {
+                first_pair = false;
+            } else {
+                buf.push(0x26); // &
             }
+            buf.push_all(name.as_slice());
+            buf.push(0x3D); // =
+            buf.push_all(value.as_slice())
         }
         buf
     }
 
-    fn update_steps(&self) {
-        // XXXManishearth Implement this when the URL interface is implemented
-        // http://url.spec.whatwg.org/#concept-uq-update
+    /// Replaces the pairs with those of a newly parsed query, without
+    /// running the update steps.
+    fn set_list(self, list: Vec<(DOMString, DOMString)>) {
+        *self.list.deref().borrow_mut() = list;
+    }
+
+    fn set_url(self, url: JSRef<URL>) {
+        self.url.assign(Some(url));
+    }
+
+    // http://url.spec.whatwg.org/#concept-uq-update
+    fn update_steps(self) {
+        match self.url.get().root() {
+            Some(url) => {
+                // The serialization is ASCII.
+                let query = String::from_utf8(self.serialize(None)).unwrap();
+                url.set_query(query);
+            },
+            None => {}
+        }
     }
 }
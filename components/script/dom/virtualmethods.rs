@@ -5,9 +5,13 @@
 use dom::attr::{AttrValue, StringAttrValue};
 use dom::bindings::codegen::InheritTypes::ElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLAnchorElementCast;
+use dom::bindings::codegen::InheritTypes::HTMLAreaElementCast;
+use dom::bindings::codegen::InheritTypes::HTMLAudioElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLBodyElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLButtonElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLCanvasElementCast;
+use dom::bindings::codegen::InheritTypes::HTMLDetailsElementCast;
+use dom::bindings::codegen::InheritTypes::HTMLDialogElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLFieldSetElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLIFrameElementCast;
@@ -17,16 +21,26 @@ use dom::bindings::codegen::InheritTypes::HTMLLinkElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLObjectElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLOptGroupElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLOptionElementCast;
+use dom::bindings::codegen::InheritTypes::HTMLOutputElementCast;
+use dom::bindings::codegen::InheritTypes::HTMLPictureElementCast;
+use dom::bindings::codegen::InheritTypes::HTMLProgressElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLSelectElementCast;
+use dom::bindings::codegen::InheritTypes::HTMLSourceElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLStyleElementCast;
 use dom::bindings::codegen::InheritTypes::HTMLTextAreaElementCast;
+use dom::bindings::codegen::InheritTypes::HTMLTitleElementCast;
+use dom::bindings::codegen::InheritTypes::HTMLVideoElementCast;
 use dom::bindings::js::JSRef;
 use dom::element::Element;
 use dom::element::ElementTypeId_;
 use dom::element::HTMLAnchorElementTypeId;
+use dom::element::HTMLAreaElementTypeId;
+use dom::element::HTMLAudioElementTypeId;
 use dom::element::HTMLBodyElementTypeId;
 use dom::element::HTMLButtonElementTypeId;
 use dom::element::HTMLCanvasElementTypeId;
+use dom::element::HTMLDetailsElementTypeId;
+use dom::element::HTMLDialogElementTypeId;
 use dom::element::HTMLFieldSetElementTypeId;
 use dom::element::HTMLIFrameElementTypeId;
 use dom::element::HTMLImageElementTypeId;
@@ -35,14 +49,24 @@ use dom::element::HTMLLinkElementTypeId;
 use dom::element::HTMLObjectElementTypeId;
 use dom::element::HTMLOptGroupElementTypeId;
 use dom::element::HTMLOptionElementTypeId;
+use dom::element::HTMLOutputElementTypeId;
+use dom::element::HTMLPictureElementTypeId;
+use dom::element::HTMLProgressElementTypeId;
 use dom::element::HTMLSelectElementTypeId;
+use dom::element::HTMLSourceElementTypeId;
 use dom::element::HTMLStyleElementTypeId;
 use dom::element::HTMLTextAreaElementTypeId;
+use dom::element::HTMLTitleElementTypeId;
+use dom::element::HTMLVideoElementTypeId;
 use dom::event::Event;
 use dom::htmlanchorelement::HTMLAnchorElement;
+use dom::htmlareaelement::HTMLAreaElement;
+use dom::htmlaudioelement::HTMLAudioElement;
 use dom::htmlbodyelement::HTMLBodyElement;
 use dom::htmlbuttonelement::HTMLButtonElement;
 use dom::htmlcanvaselement::HTMLCanvasElement;
+use dom::htmldetailselement::HTMLDetailsElement;
+use dom::htmldialogelement::HTMLDialogElement;
 use dom::htmlelement::HTMLElement;
 use dom::htmlfieldsetelement::HTMLFieldSetElement;
 use dom::htmliframeelement::HTMLIFrameElement;
@@ -52,9 +76,15 @@ use dom::htmllinkelement::HTMLLinkElement;
 use dom::htmlobjectelement::HTMLObjectElement;
 use dom::htmloptgroupelement::HTMLOptGroupElement;
 use dom::htmloptionelement::HTMLOptionElement;
+use dom::htmloutputelement::HTMLOutputElement;
+use dom::htmlpictureelement::HTMLPictureElement;
+use dom::htmlprogresselement::HTMLProgressElement;
 use dom::htmlselectelement::HTMLSelectElement;
+use dom::htmlsourceelement::HTMLSourceElement;
 use dom::htmlstyleelement::HTMLStyleElement;
 use dom::htmltextareaelement::HTMLTextAreaElement;
+use dom::htmltitleelement::HTMLTitleElement;
+use dom::htmlvideoelement::HTMLVideoElement;
 use dom::node::{Node, NodeHelpers, ElementNodeTypeId};
 
 use servo_util::atom::Atom;
@@ -85,6 +115,14 @@ pub trait VirtualMethods {
         }
     }
 
+    /// Called when removing attributes, after the attribute has been removed.
+    fn after_remove_attr(&self, name: &Atom) {
+        match self.super_type() {
+            Some(ref s) => s.after_remove_attr(name),
+            _ => (),
+        }
+    }
+
     /// Returns the right AttrValue variant for the attribute with name `name`
     /// on this element.
     fn parse_plain_attribute(&self, name: &str, value: DOMString) -> AttrValue {
@@ -120,6 +158,23 @@ pub trait VirtualMethods {
         }
     }
 
+    /// Called on the former parent when a node is removed from its child list.
+    fn child_removed(&self, child: JSRef<Node>) {
+        match self.super_type() {
+            Some(ref s) => s.child_removed(child),
+            _ => (),
+        }
+    }
+
+    /// Called on the parent when the data of a text, comment or processing
+    /// instruction child changed.
+    fn child_data_changed(&self, child: JSRef<Node>) {
+        match self.super_type() {
+            Some(ref s) => s.child_data_changed(child),
+            _ => (),
+        }
+    }
+
     /// Called during event dispatch after the bubbling phase completes.
     fn handle_event(&self, event: JSRef<Event>) {
         match self.super_type() {
@@ -129,6 +184,15 @@ pub trait VirtualMethods {
             _ => (),
         }
     }
+
+    /// Returns the heap memory taken up by the strings that this node holds
+    /// besides its attributes and its text, like the value of a form control.
+    fn heap_size_of_strings(&self) -> uint {
+        match self.super_type() {
+            Some(ref s) => s.heap_size_of_strings(),
+            _ => 0,
+        }
+    }
 }
 
 /// Obtain a VirtualMethods instance for a given Node-derived object. Any
@@ -141,6 +205,14 @@ pub fn vtable_for<'a>(node: &'a JSRef<'a, Node>) -> &'a VirtualMethods + 'a {
             let element: &'a JSRef<'a, HTMLAnchorElement> = HTMLAnchorElementCast::to_borrowed_ref(node).unwrap();
             element as &'a VirtualMethods + 'a
         }
+        ElementNodeTypeId(HTMLAreaElementTypeId) => {
+            let element: &'a JSRef<'a, HTMLAreaElement> = HTMLAreaElementCast::to_borrowed_ref(node).unwrap();
+            element as &'a VirtualMethods + 'a
+        }
+        ElementNodeTypeId(HTMLAudioElementTypeId) => {
+            let element: &'a JSRef<'a, HTMLAudioElement> = HTMLAudioElementCast::to_borrowed_ref(node).unwrap();
+            element as &'a VirtualMethods + 'a
+        }
         ElementNodeTypeId(HTMLBodyElementTypeId) => {
             let element: &'a JSRef<'a, HTMLBodyElement> = HTMLBodyElementCast::to_borrowed_ref(node).unwrap();
             element as &'a VirtualMethods + 'a
@@ -153,6 +225,14 @@ pub fn vtable_for<'a>(node: &'a JSRef<'a, Node>) -> &'a VirtualMethods + 'a {
             let element: &'a JSRef<'a, HTMLCanvasElement> = HTMLCanvasElementCast::to_borrowed_ref(node).unwrap();
             element as &'a VirtualMethods + 'a
         }
+        ElementNodeTypeId(HTMLDetailsElementTypeId) => {
+            let element: &'a JSRef<'a, HTMLDetailsElement> = HTMLDetailsElementCast::to_borrowed_ref(node).unwrap();
+            element as &'a VirtualMethods + 'a
+        }
+        ElementNodeTypeId(HTMLDialogElementTypeId) => {
+            let element: &'a JSRef<'a, HTMLDialogElement> = HTMLDialogElementCast::to_borrowed_ref(node).unwrap();
+            element as &'a VirtualMethods + 'a
+        }
         ElementNodeTypeId(HTMLFieldSetElementTypeId) => {
             let element: &'a JSRef<'a, HTMLFieldSetElement> = HTMLFieldSetElementCast::to_borrowed_ref(node).unwrap();
             element as &'a VirtualMethods + 'a
@@ -185,10 +265,26 @@ pub fn vtable_for<'a>(node: &'a JSRef<'a, Node>) -> &'a VirtualMethods + 'a {
             let element: &'a JSRef<'a, HTMLOptionElement> = HTMLOptionElementCast::to_borrowed_ref(node).unwrap();
             element as &'a VirtualMethods + 'a
         }
+        ElementNodeTypeId(HTMLOutputElementTypeId) => {
+            let element: &'a JSRef<'a, HTMLOutputElement> = HTMLOutputElementCast::to_borrowed_ref(node).unwrap();
+            element as &'a VirtualMethods + 'a
+        }
+        ElementNodeTypeId(HTMLPictureElementTypeId) => {
+            let element: &'a JSRef<'a, HTMLPictureElement> = HTMLPictureElementCast::to_borrowed_ref(node).unwrap();
+            element as &'a VirtualMethods + 'a
+        }
+        ElementNodeTypeId(HTMLProgressElementTypeId) => {
+            let element: &'a JSRef<'a, HTMLProgressElement> = HTMLProgressElementCast::to_borrowed_ref(node).unwrap();
+            element as &'a VirtualMethods + 'a
+        }
         ElementNodeTypeId(HTMLSelectElementTypeId) => {
             let element: &'a JSRef<'a, HTMLSelectElement> = HTMLSelectElementCast::to_borrowed_ref(node).unwrap();
             element as &'a VirtualMethods + 'a
         }
+        ElementNodeTypeId(HTMLSourceElementTypeId) => {
+            let element: &'a JSRef<'a, HTMLSourceElement> = HTMLSourceElementCast::to_borrowed_ref(node).unwrap();
+            element as &'a VirtualMethods + 'a
+        }
         ElementNodeTypeId(HTMLStyleElementTypeId) => {
             let element: &'a JSRef<'a, HTMLStyleElement> = HTMLStyleElementCast::to_borrowed_ref(node).unwrap();
             element as &'a VirtualMethods + 'a
@@ -197,6 +293,14 @@ pub fn vtable_for<'a>(node: &'a JSRef<'a, Node>) -> &'a VirtualMethods + 'a {
             let element: &'a JSRef<'a, HTMLTextAreaElement> = HTMLTextAreaElementCast::to_borrowed_ref(node).unwrap();
             element as &'a VirtualMethods + 'a
         }
+        ElementNodeTypeId(HTMLTitleElementTypeId) => {
+            let element: &'a JSRef<'a, HTMLTitleElement> = HTMLTitleElementCast::to_borrowed_ref(node).unwrap();
+            element as &'a VirtualMethods + 'a
+        }
+        ElementNodeTypeId(HTMLVideoElementTypeId) => {
+            let element: &'a JSRef<'a, HTMLVideoElement> = HTMLVideoElementCast::to_borrowed_ref(node).unwrap();
+            element as &'a VirtualMethods + 'a
+        }
         ElementNodeTypeId(ElementTypeId_) => {
             let element: &'a JSRef<'a, Element> = ElementCast::to_borrowed_ref(node).unwrap();
             element as &'a VirtualMethods + 'a
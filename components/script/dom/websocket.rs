@@ -0,0 +1,430 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
+use dom::bindings::codegen::Bindings::WebSocketBinding;
+use dom::bindings::codegen::Bindings::WebSocketBinding::{BinaryType, BinaryTypeValues};
+use dom::bindings::codegen::Bindings::WebSocketBinding::WebSocketMethods;
+use dom::bindings::codegen::InheritTypes::{EventCast, EventTargetCast, WebSocketDerived};
+use dom::bindings::codegen::UnionTypes::StringOrStringSequence::{StringOrStringSequence, eString};
+use dom::bindings::codegen::UnionTypes::StringOrStringSequence::eStringSequence;
+use dom::bindings::conversions::ToJSValConvertible;
+use dom::bindings::error::{ErrorResult, Fallible, InvalidAccess, InvalidState, Syntax};
+use dom::bindings::global::{GlobalRef, GlobalField};
+use dom::bindings::js::{JS, JSRef, Temporary};
+use dom::bindings::trace::{Traceable, Untraceable};
+use dom::bindings::utils::{Reflectable, Reflector, new_array_buffer, reflect_dom_object};
+use dom::blob::{Blob, BlobHelpers};
+use dom::closeevent::CloseEvent;
+use dom::event::Event;
+use dom::eventtarget::{EventTarget, EventTargetHelpers, WebSocketTypeId};
+use dom::messageevent::MessageEvent;
+use dom::window::WebSocketOwner;
+use script_task::WebSocketMsg;
+use security::Origin;
+use task_source::NetworkingTaskSource;
+use servo_net::websocket_task;
+use servo_net::websocket_task::{WebSocketCommand, SendText, SendBinary, Close, Fail};
+use servo_net::websocket_task::WebSocketEvent;
+use servo_util::str::DOMString;
+use servo_util::task::spawn_named;
+
+use js::jsapi::{JSObject, JS_AddObjectRoot, JS_RemoveObjectRoot};
+use js::jsfriendapi::bindgen::{JS_GetArrayBufferByteLength, JS_GetArrayBufferData};
+use js::jsval::JSVal;
+
+use libc::c_void;
+use std::cell::{Cell, RefCell};
+use std::comm::{channel, Sender};
+use std::slice;
+use url::Url;
+
+#[deriving(PartialEq)]
+#[jstraceable]
+enum WebSocketReadyState {
+    Connecting = 0,
+    Open = 1,
+    Closing = 2,
+    Closed = 3,
+}
+
+pub struct TrustedWebSocketAddress(pub *const c_void);
+
+#[jstraceable]
+#[must_root]
+pub struct WebSocket {
+    eventtarget: EventTarget,
+    global: GlobalField,
+    url: Untraceable<Url>,
+    ready_state: Traceable<Cell<WebSocketReadyState>>,
+    buffered_amount: Traceable<Cell<u32>>,
+    protocol: Traceable<RefCell<DOMString>>,
+    binary_type: Traceable<Cell<BinaryType>>,
+    /// Where the commands for the connection go.
+    commands: Untraceable<Sender<WebSocketCommand>>,
+    /// Whether the document that opened the connection was unloaded, after
+    /// which no more events are fired.
+    terminated: Traceable<Cell<bool>>,
+}
+
+impl WebSocket {
+    fn new_inherited(global: &GlobalRef, url: Url,
+                     commands: Sender<WebSocketCommand>) -> WebSocket {
+        WebSocket {
+            eventtarget: EventTarget::new_inherited(WebSocketTypeId),
+            global: GlobalField::from_rooted(global),
+            url: Untraceable::new(url),
+            ready_state: Traceable::new(Cell::new(Connecting)),
+            buffered_amount: Traceable::new(Cell::new(0)),
+            protocol: Traceable::new(RefCell::new("".to_string())),
+            binary_type: Traceable::new(Cell::new(BinaryTypeValues::Blob)),
+            commands: Untraceable::new(commands),
+            terminated: Traceable::new(Cell::new(false)),
+        }
+    }
+
+    fn new(global: &GlobalRef, url: Url, protocols: Vec<DOMString>) -> Temporary<WebSocket> {
+        let (events_chan, events_port) = channel();
+        let origin = global.get_origin().serialize();
+        let commands = websocket_task::connect(url.clone(), origin, protocols, events_chan);
+        let websocket = reflect_dom_object(box WebSocket::new_inherited(global, url, commands),
+                                           global,
+                                           WebSocketBinding::Wrap).root();
+
+        // The object stays alive until its connection is closed, which is
+        // the last thing the connection tells it about.
+        let addr = unsafe { websocket.to_trusted() };
+        let script_chan = global.script_chan().clone();
+        spawn_named("WebSocket:events", proc() {
+            for event in events_port.iter() {
+                script_chan.queue_task(NetworkingTaskSource, WebSocketMsg(addr, event));
+            }
+        });
+        Temporary::from_rooted(*websocket)
+    }
+
+    // http://www.whatwg.org/html/#dom-websocket
+    pub fn Constructor(global: &GlobalRef, url: DOMString,
+                       protocols: Option<StringOrStringSequence>)
+                       -> Fallible<Temporary<WebSocket>> {
+        // Steps 1-4
+        let url = match Url::parse(url.as_slice()) {
+            Ok(url) => url,
+            Err(_) => return Err(Syntax),
+        };
+        match url.scheme.as_slice() {
+            "ws" | "wss" => {},
+            _ => return Err(Syntax),
+        }
+        if url.fragment.is_some() {
+            return Err(Syntax);
+        }
+
+        // Steps 6-7
+        let protocols = match protocols {
+            None => vec!(),
+            Some(eString(protocol)) => vec!(protocol),
+            Some(eStringSequence(protocols)) => protocols,
+        };
+        for (i, protocol) in protocols.iter().enumerate() {
+            if !is_valid_protocol(protocol.as_slice()) ||
+               protocols.slice_to(i).contains(protocol) {
+                return Err(Syntax);
+            }
+        }
+
+        // Step 8
+        Ok(WebSocket::new(global, url, protocols))
+    }
+
+    pub fn handle_event(addr: TrustedWebSocketAddress, event: WebSocketEvent) {
+        unsafe {
+            let websocket = JS::from_trusted_websocket_address(addr).root();
+            websocket.process_event(event);
+        }
+    }
+}
+
+/// Whether `protocol` is a token, as subprotocol names must be.
+/// http://tools.ietf.org/html/rfc2616#section-2.2
+fn is_valid_protocol(protocol: &str) -> bool {
+    !protocol.is_empty() && protocol.chars().all(|c| {
+        c >= '\x21' && c <= '\x7E' && !"()<>@,;:\\\"/[]?={}".contains_char(c)
+    })
+}
+
+pub trait WebSocketHelpers {
+    fn terminate_for_unload(self);
+}
+
+impl<'a> WebSocketHelpers for JSRef<'a, WebSocket> {
+    /// Closes the connection without firing any more events, because the
+    /// document that opened it is being unloaded. The object is released
+    /// once the connection is closed.
+    /// http://www.whatwg.org/html/#make-disappear
+    fn terminate_for_unload(self) {
+        self.terminated.deref().set(true);
+        match self.ready_state.deref().get() {
+            Connecting => {
+                self.ready_state.deref().set(Closing);
+                let _ = self.commands.send_opt(Fail);
+            },
+            Open => {
+                // 1001 tells the server that the page is going away.
+                self.ready_state.deref().set(Closing);
+                let _ = self.commands.send_opt(Close(Some(1001), "".to_string()));
+            },
+            Closing | Closed => {},
+        }
+    }
+}
+
+trait PrivateWebSocketHelpers {
+    unsafe fn to_trusted(self) -> TrustedWebSocketAddress;
+    fn release(self);
+    fn process_event(self, event: WebSocketEvent);
+    fn dispatch_simple_event(self, type_: DOMString);
+    fn dispatch_message(self, data: JSVal);
+    fn binary_message(self, bytes: Vec<u8>) -> JSVal;
+    fn send_data(self, command: WebSocketCommand, length: uint) -> ErrorResult;
+}
+
+impl<'a> PrivateWebSocketHelpers for JSRef<'a, WebSocket> {
+    // Creates a trusted address to the object, and roots it until release()
+    // is called, when the connection is closed.
+    unsafe fn to_trusted(self) -> TrustedWebSocketAddress {
+        let global = self.global.root();
+        JS_AddObjectRoot(global.root_ref().get_cx(), self.reflector().rootable());
+        global.root_ref().track_network_owner(WebSocketOwner(JS::from_rooted(self)));
+        TrustedWebSocketAddress(self.deref() as *const WebSocket as *const c_void)
+    }
+
+    fn release(self) {
+        let global = self.global.root();
+        unsafe {
+            JS_RemoveObjectRoot(global.root_ref().get_cx(), self.reflector().rootable());
+        }
+        global.root_ref().untrack_network_owner(WebSocketOwner(JS::from_rooted(self)));
+    }
+
+    // http://www.whatwg.org/html/#feedback-from-the-protocol
+    fn process_event(self, event: WebSocketEvent) {
+        // Nothing is fired once the document was unloaded.
+        if self.terminated.deref().get() {
+            match event {
+                websocket_task::Closed(..) => {
+                    self.ready_state.deref().set(Closed);
+                    self.release();
+                },
+                _ => {},
+            }
+            return;
+        }
+        match event {
+            websocket_task::Opened(protocol) => {
+                // close() may have been called in the meantime.
+                if self.ready_state.deref().get() != Connecting {
+                    return;
+                }
+                self.ready_state.deref().set(Open);
+                *self.protocol.deref().borrow_mut() = protocol;
+                self.dispatch_simple_event("open".to_string());
+            },
+            websocket_task::TextMessage(_) |
+            websocket_task::BinaryMessage(_) if self.ready_state.deref().get() != Open => {},
+            websocket_task::TextMessage(text) => {
+                let global = self.global.root();
+                let data = text.to_jsval(global.root_ref().get_cx());
+                self.dispatch_message(data);
+            },
+            websocket_task::BinaryMessage(bytes) => {
+                let data = self.binary_message(bytes);
+                self.dispatch_message(data);
+            },
+            websocket_task::Sent(length) => {
+                let buffered_amount = self.buffered_amount.deref().get();
+                self.buffered_amount.deref().set(buffered_amount - length as u32);
+            },
+            websocket_task::Closing => {
+                self.ready_state.deref().set(Closing);
+            },
+            websocket_task::Failed => {
+                self.dispatch_simple_event("error".to_string());
+            },
+            websocket_task::Closed(was_clean, code, reason) => {
+                self.ready_state.deref().set(Closed);
+                let global = self.global.root();
+                let closeevent = CloseEvent::new(&global.root_ref(), "close".to_string(),
+                                                 false, false, was_clean, code, reason).root();
+                let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+                let event: JSRef<Event> = EventCast::from_ref(*closeevent);
+                target.dispatch_event_with_target(None, event).ok();
+                self.release();
+            },
+        }
+    }
+
+    fn dispatch_simple_event(self, type_: DOMString) {
+        let global = self.global.root();
+        let event = Event::new(&global.root_ref(), type_, false, false).root();
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        target.dispatch_event_with_target(None, *event).ok();
+    }
+
+    fn dispatch_message(self, data: JSVal) {
+        let global = self.global.root();
+        let origin = Origin::from_url(&*self.url).serialize();
+        let messageevent = MessageEvent::new(&global.root_ref(), "message".to_string(),
+                                             false, false, data, origin, "".to_string()).root();
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        let event: JSRef<Event> = EventCast::from_ref(*messageevent);
+        target.dispatch_event_with_target(None, event).ok();
+    }
+
+    /// Returns the data of a message event for a binary message, as the
+    /// binaryType attribute says.
+    fn binary_message(self, bytes: Vec<u8>) -> JSVal {
+        let global = self.global.root();
+        match self.binary_type.deref().get() {
+            BinaryTypeValues::Blob => {
+                let blob = Blob::new(&global.root_ref(), bytes, "".to_string()).root();
+                blob.to_jsval(global.root_ref().get_cx())
+            },
+            BinaryTypeValues::Arraybuffer => {
+                new_array_buffer(global.root_ref().get_cx(), bytes.as_slice())
+            },
+        }
+    }
+
+    // http://www.whatwg.org/html/#dom-websocket-send
+    fn send_data(self, command: WebSocketCommand, length: uint) -> ErrorResult {
+        match self.ready_state.deref().get() {
+            Connecting => return Err(InvalidState),
+            Open => {
+                let _ = self.commands.send_opt(command);
+            },
+            // Data sent once the connection is closing is counted, but
+            // never sent.
+            Closing | Closed => {},
+        }
+        let buffered_amount = self.buffered_amount.deref().get();
+        self.buffered_amount.deref().set(buffered_amount + length as u32);
+        Ok(())
+    }
+}
+
+impl<'a> WebSocketMethods for JSRef<'a, WebSocket> {
+    // http://www.whatwg.org/html/#dom-websocket-url
+    fn Url(self) -> DOMString {
+        self.url.serialize()
+    }
+
+    // http://www.whatwg.org/html/#dom-websocket-readystate
+    fn ReadyState(self) -> u16 {
+        self.ready_state.deref().get() as u16
+    }
+
+    // http://www.whatwg.org/html/#dom-websocket-bufferedamount
+    fn BufferedAmount(self) -> u32 {
+        self.buffered_amount.deref().get()
+    }
+
+    // http://www.whatwg.org/html/#dom-websocket-extensions
+    fn Extensions(self) -> DOMString {
+        // No extensions are supported, so none are ever in use.
+        "".to_string()
+    }
+
+    // http://www.whatwg.org/html/#dom-websocket-protocol
+    fn Protocol(self) -> DOMString {
+        self.protocol.deref().borrow().clone()
+    }
+
+    // http://www.whatwg.org/html/#dom-websocket-close
+    fn Close(self, code: Option<u16>, reason: Option<DOMString>) -> ErrorResult {
+        // Step 1
+        match code {
+            Some(code) if code != 1000 && (code < 3000 || code > 4999) => {
+                return Err(InvalidAccess);
+            },
+            _ => {}
+        }
+
+        // Step 2
+        let reason = reason.unwrap_or("".to_string());
+        if reason.len() > 123 {
+            return Err(Syntax);
+        }
+
+        // Step 3
+        match self.ready_state.deref().get() {
+            Closing | Closed => {},
+            Connecting => {
+                self.ready_state.deref().set(Closing);
+                let _ = self.commands.send_opt(Fail);
+            },
+            Open => {
+                self.ready_state.deref().set(Closing);
+                // A reason can only be sent along with a code.
+                let code = match code {
+                    None if !reason.is_empty() => Some(1000),
+                    code => code,
+                };
+                let _ = self.commands.send_opt(Close(code, reason));
+            },
+        }
+        Ok(())
+    }
+
+    fn BinaryType(self) -> BinaryType {
+        self.binary_type.deref().get()
+    }
+
+    fn SetBinaryType(self, binary_type: BinaryType) {
+        self.binary_type.deref().set(binary_type)
+    }
+
+    fn Send(self, data: DOMString) -> ErrorResult {
+        let length = data.len();
+        self.send_data(SendText(data), length)
+    }
+
+    fn Send_(self, data: JSRef<Blob>) -> ErrorResult {
+        let bytes = data.bytes();
+        let length = bytes.len();
+        self.send_data(SendBinary(bytes), length)
+    }
+
+    fn Send__(self, data: *mut JSObject) -> ErrorResult {
+        let global = self.global.root();
+        let cx = global.root_ref().get_cx();
+        let bytes = unsafe {
+            let length = JS_GetArrayBufferByteLength(data, cx);
+            let data = JS_GetArrayBufferData(data, cx);
+            slice::raw::buf_as_slice(data as *const u8, length as uint, |data| data.to_vec())
+        };
+        let length = bytes.len();
+        self.send_data(SendBinary(bytes), length)
+    }
+
+    event_handler!(open, GetOnopen, SetOnopen)
+    event_handler!(error, GetOnerror, SetOnerror)
+    event_handler!(close, GetOnclose, SetOnclose)
+    event_handler!(message, GetOnmessage, SetOnmessage)
+}
+
+impl Reflectable for WebSocket {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        self.eventtarget.reflector()
+    }
+}
+
+impl WebSocketDerived for EventTarget {
+    fn is_websocket(&self) -> bool {
+        match self.type_id {
+            WebSocketTypeId => true,
+            _ => false
+        }
+    }
+}
@@ -5,76 +5,74 @@
 use dom::bindings::codegen::Bindings::EventHandlerBinding::{OnErrorEventHandlerNonNull, EventHandlerNonNull};
 use dom::bindings::codegen::Bindings::WindowBinding;
 use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
-use dom::bindings::codegen::InheritTypes::EventTargetCast;
-use dom::bindings::error::{Fallible, InvalidCharacter};
+use dom::bindings::codegen::Bindings::BeforeUnloadEventBinding::BeforeUnloadEventMethods;
+use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use dom::bindings::codegen::Bindings::EventSourceBinding::EventSourceMethods;
+use dom::bindings::codegen::Bindings::RequestBinding::RequestInit;
+use dom::bindings::codegen::InheritTypes::{EventCast, EventTargetCast};
+use dom::bindings::codegen::UnionTypes::RequestOrString::RequestOrString;
+use dom::bindings::error::{ErrorResult, Fallible, InvalidCharacter, Syntax};
 use dom::bindings::global;
-use dom::bindings::js::{JS, JSRef, Temporary, OptionalSettable};
+use dom::bindings::global::global_object_for_js_object;
+use dom::bindings::js::{JS, JSRef, Temporary, OptionalRootable, OptionalSettable};
 use dom::bindings::trace::{Traceable, Untraceable};
 use dom::bindings::utils::{Reflectable, Reflector};
+use dom::beforeunloadevent::BeforeUnloadEvent;
 use dom::browsercontext::BrowserContext;
 use dom::console::Console;
-use dom::document::Document;
+use dom::document::{Document, DocumentHelpers};
+use dom::event::Event;
+use dom::eventsource::EventSource;
 use dom::eventtarget::{EventTarget, WindowTypeId, EventTargetHelpers};
+use dom::filereader::{FileReader, FileReaderHelpers};
 use dom::location::Location;
+use dom::messageport::{MessagePort, write_message};
 use dom::navigator::Navigator;
+use dom::pagetransitionevent::PageTransitionEvent;
 use dom::performance::Performance;
+use dom::promise::Promise;
 use dom::screen::Screen;
+use dom::sharedworkerglobalscope::SharedWorkerOwner;
+use dom::websocket::{WebSocket, WebSocketHelpers};
+use dom::xmlhttprequest::{XMLHttpRequest, XMLHttpRequestHelpers};
+use fetch;
+use fetch::FetchCanceller;
 use layout_interface::{ReflowGoal, DocumentDamageLevel};
 use page::Page;
-use script_task::{ExitWindowMsg, FireTimerMsg, ScriptChan, TriggerLoadMsg, TriggerFragmentMsg};
+use script_task::{ExitWindowMsg, FireQueuedEventsMsg, ScriptChan};
+use script_task::{TriggerLoadMsg, TriggerFragmentMsg, WindowPostMessage};
+use security::Origin;
+use task_source::{DOMManipulationTaskSource, PostedMessageTaskSource};
 use script_traits::ScriptControlChan;
+use timers::{FromWindow, TimerId, TimerManager, TimerSchedulerChan};
 
+use devtools_traits::{ConsoleMessage, DevtoolsControlChan, SendConsoleMessage};
 use servo_msg::compositor_msg::ScriptListener;
+use servo_msg::constellation_msg::LoadData;
+use servo_net::blob_loader::BlobURLOwner;
 use servo_net::image_cache_task::ImageCacheTask;
+use servo_net::referrer_policy::{ReferrerPolicy, determine_referrer};
+use servo_net::resource_task::RevokeBlobURLsOf;
+use servo_util::geometry::Au;
 use servo_util::str::{DOMString,HTML_SPACE_CHARACTERS};
-use servo_util::task::{spawn_named};
 
-use js::jsapi::{JS_CallFunctionValue, JS_EvaluateUCScript};
-use js::jsapi::JSContext;
+use js::jsapi::JS_EvaluateUCScript;
+use js::jsapi::{JSContext, JSObject, JS_GetGlobalForScopeChain};
 use js::jsapi::{JS_GC, JS_GetRuntime};
 use js::jsval::JSVal;
-use js::jsval::{UndefinedValue, NullValue};
+use js::jsval::UndefinedValue;
 use js::rust::with_compartment;
+use geom::size::Size2D;
+use style::{Device, Screen, parse_media_query_list_from_str};
 use url::{Url, UrlParser};
 
 use libc;
 use serialize::base64::{FromBase64, ToBase64, STANDARD};
-use std::collections::hashmap::HashMap;
 use std::cell::{Cell, RefCell};
-use std::cmp;
-use std::comm::{channel, Sender};
-use std::comm::Select;
-use std::hash::{Hash, sip};
-use std::io::timer::Timer;
-use std::ptr;
+use std::mem;
 use std::rc::Rc;
-use std::time::duration::Duration;
 use time;
 
-#[deriving(PartialEq, Eq)]
-#[jstraceable]
-pub struct TimerId(i32);
-
-#[jstraceable]
-pub struct TimerHandle {
-    handle: TimerId,
-    pub data: TimerData,
-    cancel_chan: Untraceable<Option<Sender<()>>>,
-}
-
-impl Hash for TimerId {
-    fn hash(&self, state: &mut sip::SipState) {
-        let TimerId(id) = *self;
-        id.hash(state);
-    }
-}
-
-impl TimerHandle {
-    fn cancel(&mut self) {
-        self.cancel_chan.as_ref().map(|chan| chan.send_opt(()).ok());
-    }
-}
-
 #[jstraceable]
 #[must_root]
 pub struct Window {
@@ -85,47 +83,99 @@ pub struct Window {
     location: Cell<Option<JS<Location>>>,
     navigator: Cell<Option<JS<Navigator>>>,
     pub image_cache_task: ImageCacheTask,
-    pub active_timers: Traceable<RefCell<HashMap<TimerId, TimerHandle>>>,
-    next_timer_handle: Traceable<Cell<i32>>,
+    timers: TimerManager,
     pub compositor: Untraceable<Box<ScriptListener+'static>>,
+    /// For telling the devtools server what is logged to the console, if
+    /// there is a server.
+    devtools_chan: Untraceable<Option<DevtoolsControlChan>>,
     pub browser_context: Traceable<RefCell<Option<BrowserContext>>>,
-    pub page: Rc<Page>,
+    /// The page of the document the window shows, which only changes when a
+    /// document takes the window over from the initial about:blank one.
+    page: RefCell<Rc<Page>>,
     performance: Cell<Option<JS<Performance>>>,
     pub navigationStart: u64,
     pub navigationStartPrecise: f64,
     screen: Cell<Option<JS<Screen>>>,
+    queued_events: RefCell<Vec<QueuedEvent>>,
+    /// The shared workers this window connected to, which keep running for
+    /// as long as it does.
+    shared_worker_owners: Untraceable<RefCell<Vec<SharedWorkerOwner>>>,
+    /// The objects whose fetches, connections or reads are still going on,
+    /// which are stopped when the document is unloaded.
+    network_owners: RefCell<Vec<NetworkOwner>>,
+    /// Stops the fetches that fetch() started, which may still go on.
+    fetch_cancellers: Untraceable<RefCell<Vec<FetchCanceller>>>,
+    /// What the `blob:` URLs created in this window are registered under,
+    /// which are revoked when the document is unloaded.
+    blob_url_owner: Untraceable<BlobURLOwner>,
+    /// Whether the document the window shows was unloaded, after which it
+    /// never runs script again.
+    unloaded: Cell<bool>,
 }
 
 impl Window {
     pub fn get_cx(&self) -> *mut JSContext {
-        let js_info = self.page().js_info();
+        let page = self.page();
+        let js_info = page.js_info();
         (**js_info.as_ref().unwrap().js_context).ptr
     }
 
-    pub fn page<'a>(&'a self) -> &'a Page {
-        &*self.page
+    pub fn page(&self) -> Rc<Page> {
+        self.page.borrow().clone()
     }
     pub fn get_url(&self) -> Url {
         self.page().get_url()
     }
-}
 
-#[unsafe_destructor]
-impl Drop for Window {
-    fn drop(&mut self) {
-        for (_, timer_handle) in self.active_timers.borrow_mut().iter_mut() {
-            timer_handle.cancel();
+    pub fn add_shared_worker_owner(&self, owner: SharedWorkerOwner) {
+        self.shared_worker_owners.borrow_mut().push(owner);
+    }
+
+    pub fn blob_url_owner(&self) -> BlobURLOwner {
+        self.blob_url_owner.deref().clone()
+    }
+
+    pub fn report_console_message(&self, message: ConsoleMessage) {
+        match *self.devtools_chan {
+            Some(ref chan) => {
+                let _ = chan.send_opt(SendConsoleMessage(self.page().id, message));
+            },
+            None => {},
         }
     }
 }
 
-// Holder for the various JS values associated with setTimeout
-// (ie. function value to invoke and all arguments to pass
-//      to the function when calling it)
+/// An event that was queued to be fired at a target from a later task.
 #[jstraceable]
-pub struct TimerData {
-    pub is_interval: bool,
-    pub funval: Traceable<JSVal>,
+struct QueuedEvent {
+    target: JS<EventTarget>,
+    type_: DOMString,
+}
+
+/// A DOM object that stays alive while its fetch, connection or read goes
+/// on, and that the window stops when its document is unloaded.
+#[jstraceable]
+#[must_root]
+#[deriving(PartialEq)]
+pub enum NetworkOwner {
+    XMLHttpRequestOwner(JS<XMLHttpRequest>),
+    WebSocketOwner(JS<WebSocket>),
+    EventSourceOwner(JS<EventSource>),
+    FileReaderOwner(JS<FileReader>),
+}
+
+impl NetworkOwner {
+    /// Stops what the object is doing without firing any more events.
+    fn terminate_for_unload(&self) {
+        match *self {
+            XMLHttpRequestOwner(ref xhr) => xhr.root().terminate_for_unload(),
+            WebSocketOwner(ref websocket) => websocket.root().terminate_for_unload(),
+            // Once closed, an EventSource ignores whatever the connection
+            // still has to say.
+            EventSourceOwner(ref source) => source.root().Close(),
+            FileReaderOwner(ref reader) => reader.root().terminate_for_unload(),
+        }
+    }
 }
 
 // http://www.whatwg.org/html/#atob
@@ -210,19 +260,18 @@ impl<'a> WindowMethods for JSRef<'a, Window> {
     }
 
     fn Close(self) {
-        let ScriptChan(ref chan) = self.script_chan;
-        chan.send(ExitWindowMsg(self.page.id.clone()));
+        self.script_chan.queue_task(DOMManipulationTaskSource, ExitWindowMsg(self.page().id));
     }
 
     fn Document(self) -> Temporary<Document> {
-        let frame = self.page().frame();
+        let page = self.page();
+        let frame = page.frame();
         Temporary::new(frame.as_ref().unwrap().document.clone())
     }
 
     fn Location(self) -> Temporary<Location> {
         if self.location.get().is_none() {
-            let page = self.deref().page.clone();
-            let location = Location::new(self, page);
+            let location = Location::new(self, self.page());
             self.location.assign(Some(location));
         }
         Temporary::new(self.location.get().as_ref().unwrap().clone())
@@ -245,21 +294,15 @@ impl<'a> WindowMethods for JSRef<'a, Window> {
     }
 
     fn SetTimeout(self, _cx: *mut JSContext, callback: JSVal, timeout: i32) -> i32 {
-        self.set_timeout_or_interval(callback, timeout, false)
+        self.timers.set_timeout_or_interval(callback, timeout, false)
     }
 
     fn ClearTimeout(self, handle: i32) {
-        let mut timers = self.active_timers.deref().borrow_mut();
-        let mut timer_handle = timers.pop(&TimerId(handle));
-        match timer_handle {
-            Some(ref mut handle) => handle.cancel(),
-            None => { }
-        }
-        timers.remove(&TimerId(handle));
+        self.timers.clear_timeout_or_interval(handle);
     }
 
     fn SetInterval(self, _cx: *mut JSContext, callback: JSVal, timeout: i32) -> i32 {
-        self.set_timeout_or_interval(callback, timeout, true)
+        self.timers.set_timeout_or_interval(callback, timeout, true)
     }
 
     fn ClearInterval(self, handle: i32) {
@@ -322,6 +365,20 @@ impl<'a> WindowMethods for JSRef<'a, Window> {
         eventtarget.set_event_handler_common("unload", listener)
     }
 
+    event_handler!(resize, GetOnresize, SetOnresize)
+    event_handler!(scroll, GetOnscroll, SetOnscroll)
+    event_handler!(afterprint, GetOnafterprint, SetOnafterprint)
+    event_handler!(beforeprint, GetOnbeforeprint, SetOnbeforeprint)
+    event_handler!(beforeunload, GetOnbeforeunload, SetOnbeforeunload)
+    event_handler!(hashchange, GetOnhashchange, SetOnhashchange)
+    event_handler!(message, GetOnmessage, SetOnmessage)
+    event_handler!(offline, GetOnoffline, SetOnoffline)
+    event_handler!(online, GetOnonline, SetOnonline)
+    event_handler!(pagehide, GetOnpagehide, SetOnpagehide)
+    event_handler!(pageshow, GetOnpageshow, SetOnpageshow)
+    event_handler!(popstate, GetOnpopstate, SetOnpopstate)
+    event_handler!(storage, GetOnstorage, SetOnstorage)
+
     fn GetOnerror(self) -> Option<OnErrorEventHandlerNonNull> {
         let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(self);
         eventtarget.get_event_handler_common("error")
@@ -357,6 +414,40 @@ impl<'a> WindowMethods for JSRef<'a, Window> {
     fn Atob(self, atob: DOMString) -> Fallible<DOMString> {
         base64_atob(atob)
     }
+
+    fn Fetch(self, input: RequestOrString, init: &RequestInit) -> Fallible<Temporary<Promise>> {
+        fetch::fetch(&global::Window(self), input, init)
+    }
+
+    // http://www.whatwg.org/html/#dom-window-postmessage
+    fn PostMessage(self, cx: *mut JSContext, message: JSVal, targetOrigin: DOMString,
+                   transfer: Option<Vec<JS<MessagePort>>>) -> ErrorResult {
+        // The origin of the script that called us, which the message is
+        // tagged with.
+        let incumbent = unsafe {
+            global_object_for_js_object(JS_GetGlobalForScopeChain(cx)).root()
+        };
+        let source_origin = incumbent.root_ref().get_origin();
+
+        // Steps 2-4.
+        let target_origin = match targetOrigin.as_slice() {
+            "*" => None,
+            "/" => Some(source_origin.clone()),
+            url => match Url::parse(url) {
+                Ok(url) => Some(Origin::from_url(&url)),
+                Err(_) => return Err(Syntax),
+            },
+        };
+
+        // Steps 5-6. The message is dispatched from the event loop, once the
+        // origin of the document is known to still match.
+        let (data, ports) = try!(write_message(cx, message, transfer, None));
+        self.script_chan.queue_task(PostedMessageTaskSource,
+                                    WindowPostMessage(self.page().id, data,
+                                                      source_origin.serialize(),
+                                                      target_origin, ports));
+        Ok(())
+    }
 }
 
 impl Reflectable for Window {
@@ -369,14 +460,22 @@ pub trait WindowHelpers {
     fn damage_and_reflow(self, damage: DocumentDamageLevel);
     fn flush_layout(self, goal: ReflowGoal);
     fn wait_until_safe_to_modify_dom(self);
-    fn init_browser_context(self, doc: JSRef<Document>);
-    fn load_url(self, href: DOMString);
+    fn init_browser_context(self, doc: JSRef<Document>, previous_proxy: Option<*mut JSObject>);
+    fn replace_document(self, page: Rc<Page>, document: JSRef<Document>);
+    fn window_proxy(self) -> *mut JSObject;
+    fn load_url(self, href: DOMString, referrer_policy: ReferrerPolicy) -> ErrorResult;
     fn handle_fire_timer(self, timer_id: TimerId, cx: *mut JSContext);
     fn evaluate_js_with_result(self, code: &str) -> JSVal;
-}
-
-trait PrivateWindowHelpers {
-    fn set_timeout_or_interval(self, callback: JSVal, timeout: i32, is_interval: bool) -> i32;
+    fn queue_simple_event(self, target: JSRef<EventTarget>, type_: DOMString);
+    fn has_queued_event(self, target: JSRef<EventTarget>, type_: &str) -> bool;
+    fn fire_queued_events(self);
+    fn media_matches(self, query: &str) -> bool;
+    fn track_network_owner(self, owner: NetworkOwner);
+    fn untrack_network_owner(self, owner: NetworkOwner);
+    fn track_fetch(self, canceller: FetchCanceller);
+    fn is_unloaded(self) -> bool;
+    fn unload(self, compositor: &ScriptListener);
+    fn tear_down(self);
 }
 
 impl<'a> WindowHelpers for JSRef<'a, Window> {
@@ -414,106 +513,181 @@ impl<'a> WindowHelpers for JSRef<'a, Window> {
         self.page().join_layout();
     }
 
-    fn init_browser_context(self, doc: JSRef<Document>) {
-        *self.browser_context.deref().borrow_mut() = Some(BrowserContext::new(doc));
+    fn init_browser_context(self, doc: JSRef<Document>, previous_proxy: Option<*mut JSObject>) {
+        *self.browser_context.deref().borrow_mut() = Some(BrowserContext::new(doc, previous_proxy));
+    }
+
+    /// Makes the window show `document`, which is loaded in `page`, in place
+    /// of the initial about:blank document that was unloaded. The global
+    /// object stays the same, along with its properties and event listeners,
+    /// but the location is the one of the new page.
+    /// http://www.whatwg.org/html/#initialise-the-document-object
+    fn replace_document(self, page: Rc<Page>, document: JSRef<Document>) {
+        self.timers.set_source(FromWindow(page.id));
+        *self.page.borrow_mut() = page;
+        self.location.set(None);
+        self.browser_context.deref().borrow_mut().as_mut().unwrap().replace_active_document(document);
+        self.unloaded.set(false);
+    }
+
+    /// The WindowProxy of the browsing context of this window, which scripts
+    /// get whenever they ask for the window.
+    fn window_proxy(self) -> *mut JSObject {
+        self.browser_context.deref().borrow().as_ref().unwrap().window_proxy()
     }
 
     /// Commence a new URL load which will either replace this window or scroll to a fragment.
-    fn load_url(self, href: DOMString) {
+    /// Fails without loading anything if `href` can't be parsed.
+    fn load_url(self, href: DOMString, referrer_policy: ReferrerPolicy) -> ErrorResult {
         let base_url = self.page().get_url();
         debug!("current page url is {:?}", base_url);
-        let url = UrlParser::new().base_url(&base_url).parse(href.as_slice());
-        // FIXME: handle URL parse errors more gracefully.
-        let url = url.unwrap();
-        let ScriptChan(ref script_chan) = self.script_chan;
+        let url = match UrlParser::new().base_url(&base_url).parse(href.as_slice()) {
+            Ok(url) => url,
+            Err(_) => return Err(Syntax),
+        };
         if href.as_slice().starts_with("#") {
-            script_chan.send(TriggerFragmentMsg(self.page.id, url));
+            self.script_chan.queue_task(DOMManipulationTaskSource,
+                                        TriggerFragmentMsg(self.page().id, url));
         } else {
-            script_chan.send(TriggerLoadMsg(self.page.id, url));
+            let mut load_data = LoadData::new(url);
+            load_data.referrer = determine_referrer(referrer_policy, &base_url, &load_data.url);
+            self.script_chan.queue_task(DOMManipulationTaskSource,
+                                        TriggerLoadMsg(self.page().id, load_data));
         }
+        Ok(())
     }
 
     fn handle_fire_timer(self, timer_id: TimerId, cx: *mut JSContext) {
-        let this_value = self.reflector().get_jsobject();
-
-        let data = match self.active_timers.deref().borrow().find(&timer_id) {
-            None => return,
-            Some(timer_handle) => timer_handle.data,
-        };
+        self.timers.fire_timer(timer_id, self, cx);
+    }
 
-        // TODO: Support extra arguments. This requires passing a `*JSVal` array as `argv`.
-        with_compartment(cx, this_value, || {
-            let mut rval = NullValue();
-            unsafe {
-                JS_CallFunctionValue(cx, this_value, *data.funval,
-                                     0, ptr::null_mut(), &mut rval);
-            }
+    /// Queues a task to fire a simple event named `type_` at `target`.
+    /// http://www.whatwg.org/html/#fire-a-simple-event
+    fn queue_simple_event(self, target: JSRef<EventTarget>, type_: DOMString) {
+        let mut queued_events = self.queued_events.borrow_mut();
+        if queued_events.is_empty() {
+            self.script_chan.queue_task(DOMManipulationTaskSource,
+                                        FireQueuedEventsMsg(self.page().id));
+        }
+        queued_events.push(QueuedEvent {
+            target: JS::from_rooted(target),
+            type_: type_,
         });
+    }
+
+    /// Returns whether an event named `type_` is still waiting to be fired at `target`.
+    fn has_queued_event(self, target: JSRef<EventTarget>, type_: &str) -> bool {
+        let target = JS::from_rooted(target);
+        self.queued_events.borrow().iter().any(|event| {
+            event.target == target && event.type_.as_slice() == type_
+        })
+    }
 
-        if !data.is_interval {
-            self.active_timers.deref().borrow_mut().remove(&timer_id);
+    /// Fires all the events that have been queued so far, in order.
+    fn fire_queued_events(self) {
+        let queued_events = mem::replace(&mut *self.queued_events.borrow_mut(), vec!());
+        for queued_event in queued_events.into_iter() {
+            let target = queued_event.target.root();
+            let event = Event::new(&global::Window(self), queued_event.type_,
+                                   false, false).root();
+            let _ = target.dispatch_event_with_target(None, *event);
         }
     }
-}
 
-impl<'a> PrivateWindowHelpers for JSRef<'a, Window> {
-    fn set_timeout_or_interval(self, callback: JSVal, timeout: i32, is_interval: bool) -> i32 {
-        let timeout = cmp::max(0, timeout) as u64;
-        let handle = self.next_timer_handle.deref().get();
-        self.next_timer_handle.deref().set(handle + 1);
-
-        // Post a delayed message to the per-window timer task; it will dispatch it
-        // to the relevant script handler that will deal with it.
-        let tm = Timer::new().unwrap();
-        let (cancel_chan, cancel_port) = channel();
-        let chan = self.script_chan.clone();
-        let page_id = self.page.id.clone();
-        let spawn_name = if is_interval {
-            "Window:SetInterval"
-        } else {
-            "Window:SetTimeout"
-        };
-        spawn_named(spawn_name, proc() {
-            let mut tm = tm;
-            let duration = Duration::milliseconds(timeout as i64);
-            let timeout_port = if is_interval {
-                tm.periodic(duration)
-            } else {
-                tm.oneshot(duration)
-            };
-            let cancel_port = cancel_port;
-
-            let select = Select::new();
-            let mut timeout_handle = select.handle(&timeout_port);
-            unsafe { timeout_handle.add() };
-            let mut cancel_handle = select.handle(&cancel_port);
-            unsafe { cancel_handle.add() };
-
-            loop {
-                let id = select.wait();
-                if id == timeout_handle.id() {
-                    timeout_port.recv();
-                    let ScriptChan(ref chan) = chan;
-                    chan.send(FireTimerMsg(page_id, TimerId(handle)));
-                    if !is_interval {
-                        break;
-                    }
-                } else if id == cancel_handle.id() {
-                    break;
-                }
-            }
-        });
-        let timer_id = TimerId(handle);
-        let timer = TimerHandle {
-            handle: timer_id,
-            cancel_chan: Untraceable::new(Some(cancel_chan)),
-            data: TimerData {
-                is_interval: is_interval,
-                funval: Traceable::new(callback),
-            }
+    /// Whether the media query list `query` matches the viewport.
+    fn media_matches(self, query: &str) -> bool {
+        let viewport_size = self.page().window_size.deref().get().initial_viewport;
+        let device = Device {
+            media_type: Screen,
+            viewport_size: Some(Size2D(Au::from_frac32_px(viewport_size.width.get()),
+                                       Au::from_frac32_px(viewport_size.height.get()))),
         };
-        self.active_timers.deref().borrow_mut().insert(timer_id, timer);
-        handle
+        parse_media_query_list_from_str(query).evaluate(&device)
+    }
+
+    /// Keeps track of `owner` while its fetch, connection or read goes on.
+    #[allow(unrooted_must_root)]
+    fn track_network_owner(self, owner: NetworkOwner) {
+        self.network_owners.borrow_mut().push(owner);
+    }
+
+    #[allow(unrooted_must_root)]
+    fn untrack_network_owner(self, owner: NetworkOwner) {
+        self.network_owners.borrow_mut().retain(|tracked| *tracked != owner);
+    }
+
+    /// Keeps track of a fetch that fetch() started, forgetting the ones that
+    /// are over by now.
+    fn track_fetch(self, canceller: FetchCanceller) {
+        let mut cancellers = self.fetch_cancellers.borrow_mut();
+        cancellers.retain(|canceller| !canceller.is_done());
+        cancellers.push(canceller);
+    }
+
+    fn is_unloaded(self) -> bool {
+        self.unloaded.get()
+    }
+
+    /// Unloads the document of this window, which happens only once, when
+    /// the document is replaced or its pipeline exits. If a beforeunload
+    /// handler asks for it, `compositor` is asked to prompt the user first.
+    /// http://www.whatwg.org/html/#unloading-documents
+    fn unload(self, compositor: &ScriptListener) {
+        if self.unloaded.get() {
+            return;
+        }
+        self.unloaded.set(true);
+
+        let document = self.Document().root();
+        let doctarget: JSRef<EventTarget> = EventTargetCast::from_ref(*document);
+        let wintarget: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+
+        // Prompt to unload, if the page asks for it. Until the embedder can
+        // tell us what the user picked, the document unloads either way.
+        let beforeunload = BeforeUnloadEvent::new(&global::Window(self)).root();
+        let event: JSRef<Event> = EventCast::from_ref(*beforeunload);
+        let _ = wintarget.dispatch_event_with_target(Some(doctarget), event);
+        let message = beforeunload.ReturnValue();
+        if event.DefaultPrevented() || !message.is_empty() {
+            compositor.prompt_before_unload(self.page().id, message);
+        }
+
+        let pagehide = PageTransitionEvent::new(&global::Window(self), "pagehide".to_string(),
+                                                false, false, false).root();
+        let event: JSRef<Event> = EventCast::from_ref(*pagehide);
+        let _ = wintarget.dispatch_event_with_target(Some(doctarget), event);
+
+        let unload = Event::new(&global::Window(self), "unload".to_string(),
+                                false, false).root();
+        let _ = wintarget.dispatch_event_with_target(Some(doctarget), *unload);
+
+        self.tear_down();
+    }
+
+    /// Stops whatever the document started without running any more of its
+    /// script, as when the page crashed, and keeps it from being unloaded
+    /// afterwards.
+    fn tear_down(self) {
+        self.unloaded.set(true);
+
+        // Nothing the document started may run once it's gone.
+        self.timers.cancel_all();
+        self.queued_events.borrow_mut().clear();
+        let owners = mem::replace(&mut *self.network_owners.borrow_mut(), vec!());
+        for owner in owners.iter() {
+            owner.terminate_for_unload();
+        }
+        let cancellers = mem::replace(&mut *self.fetch_cancellers.borrow_mut(), vec!());
+        for canceller in cancellers.iter() {
+            canceller.cancel();
+        }
+        self.page().resource_task.send(RevokeBlobURLsOf(self.blob_url_owner()));
+        let document = self.page().frame().as_ref()
+                           .map(|frame| Temporary::new(frame.document.clone()));
+        match document.root() {
+            Some(document) => document.release_images(),
+            None => (),
+        }
     }
 }
 
@@ -523,25 +697,34 @@ impl Window {
                script_chan: ScriptChan,
                control_chan: ScriptControlChan,
                compositor: Box<ScriptListener+'static>,
+               devtools_chan: Option<DevtoolsControlChan>,
+               timer_scheduler: TimerSchedulerChan,
                image_cache_task: ImageCacheTask)
                -> Temporary<Window> {
+        let timers = TimerManager::new(FromWindow(page.id.clone()), timer_scheduler);
         let win = box Window {
             eventtarget: EventTarget::new_inherited(WindowTypeId),
             script_chan: script_chan,
             control_chan: control_chan,
             console: Cell::new(None),
             compositor: Untraceable::new(compositor),
-            page: page,
+            devtools_chan: Untraceable::new(devtools_chan),
+            page: RefCell::new(page),
             location: Cell::new(None),
             navigator: Cell::new(None),
             image_cache_task: image_cache_task,
-            active_timers: Traceable::new(RefCell::new(HashMap::new())),
-            next_timer_handle: Traceable::new(Cell::new(0)),
+            timers: timers,
             browser_context: Traceable::new(RefCell::new(None)),
             performance: Cell::new(None),
             navigationStart: time::get_time().sec as u64,
             navigationStartPrecise: time::precise_time_s(),
             screen: Cell::new(None),
+            queued_events: RefCell::new(vec!()),
+            shared_worker_owners: Untraceable::new(RefCell::new(vec!())),
+            network_owners: RefCell::new(vec!()),
+            fetch_cancellers: Untraceable::new(RefCell::new(vec!())),
+            blob_url_owner: Untraceable::new(BlobURLOwner::new()),
+            unloaded: Cell::new(false),
         };
 
         WindowBinding::Wrap(cx, win)
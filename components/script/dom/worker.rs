@@ -5,30 +5,44 @@
 use dom::bindings::codegen::Bindings::WorkerBinding;
 use dom::bindings::codegen::Bindings::WorkerBinding::WorkerMethods;
 use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
-use dom::bindings::codegen::InheritTypes::EventTargetCast;
-use dom::bindings::error::{Fallible, Syntax};
+use dom::bindings::codegen::InheritTypes::{EventCast, EventTargetCast};
+use dom::bindings::error::{ErrorResult, Fallible, Security, Syntax};
 use dom::bindings::global::{GlobalRef, GlobalField};
 use dom::bindings::js::{JS, JSRef, Temporary};
+use dom::bindings::structuredclone::StructuredCloneData;
+use dom::bindings::trace::Untraceable;
 use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
 use dom::dedicatedworkerglobalscope::DedicatedWorkerGlobalScope;
+use dom::errorevent::ErrorEvent;
+use dom::event::Event;
 use dom::eventtarget::{EventTarget, EventTargetHelpers, WorkerTypeId};
 use dom::messageevent::MessageEvent;
-use script_task::{ScriptChan, DOMMessage};
+use dom::messageport::{MessagePort, PortEndpoint, write_message};
+use script_task::{ScriptChan, DOMMessage, CloseWorkerMsg};
+use security::Origin;
+use task_source::{DOMManipulationTaskSource, PostedMessageTaskSource};
 
 use servo_util::str::DOMString;
 
-use js::glue::JS_STRUCTURED_CLONE_VERSION;
 use js::jsapi::{JSContext, JS_AddObjectRoot, JS_RemoveObjectRoot};
-use js::jsapi::{JS_ReadStructuredClone, JS_WriteStructuredClone};
-use js::jsval::{JSVal, UndefinedValue};
+use js::jsval::JSVal;
 use url::UrlParser;
 
-use libc::{c_void, size_t};
+use libc::c_void;
 use std::cell::Cell;
-use std::ptr;
+use std::sync::atomics::{AtomicBool, SeqCst};
+use sync::Arc;
 
 pub struct TrustedWorkerAddress(pub *const c_void);
 
+/// An exception that a worker's script threw and did not catch.
+pub struct UncaughtError {
+    pub message: DOMString,
+    pub filename: DOMString,
+    pub lineno: u32,
+    pub colno: u32,
+}
+
 #[jstraceable]
 #[must_root]
 pub struct Worker {
@@ -38,20 +52,30 @@ pub struct Worker {
     /// Sender to the Receiver associated with the DedicatedWorkerGlobalScope
     /// this Worker created.
     sender: ScriptChan,
+    /// Set by terminate() and by the worker's close(); the worker's event
+    /// loop stops as soon as it sees it.
+    closing: Untraceable<Arc<AtomicBool>>,
+    /// Whether terminate() was called, after which nothing the worker sent
+    /// is dispatched any more.
+    terminated: Cell<bool>,
 }
 
 impl Worker {
-    fn new_inherited(global: &GlobalRef, sender: ScriptChan) -> Worker {
+    fn new_inherited(global: &GlobalRef, sender: ScriptChan,
+                     closing: Arc<AtomicBool>) -> Worker {
         Worker {
             eventtarget: EventTarget::new_inherited(WorkerTypeId),
             refcount: Cell::new(0),
             global: GlobalField::from_rooted(global),
             sender: sender,
+            closing: Untraceable::new(closing),
+            terminated: Cell::new(false),
         }
     }
 
-    pub fn new(global: &GlobalRef, sender: ScriptChan) -> Temporary<Worker> {
-        reflect_dom_object(box Worker::new_inherited(global, sender),
+    pub fn new(global: &GlobalRef, sender: ScriptChan,
+               closing: Arc<AtomicBool>) -> Temporary<Worker> {
+        reflect_dom_object(box Worker::new_inherited(global, sender, closing),
                            global,
                            WorkerBinding::Wrap)
     }
@@ -65,35 +89,58 @@ impl Worker {
             Err(_) => return Err(Syntax),
         };
 
+        // Step 5. data: URLs are allowed too, and the worker gets an opaque
+        // origin.
+        if worker_url.scheme.as_slice() != "data" &&
+           !global.get_origin().same_origin(&Origin::from_url(&worker_url)) {
+            return Err(Security);
+        }
+
         let resource_task = global.resource_task();
         let (receiver, sender) = ScriptChan::new();
+        let closing = Arc::new(AtomicBool::new(false));
 
-        let worker = Worker::new(global, sender.clone()).root();
+        // The worker keeps the object alive until its event loop stops, at
+        // which point it sends a WorkerRelease.
+        let worker = Worker::new(global, sender.clone(), closing.clone()).root();
         let worker_ref = worker.addref();
 
         DedicatedWorkerGlobalScope::run_worker_scope(
             worker_url, worker_ref, resource_task, global.script_chan().clone(),
-            sender, receiver);
+            sender, receiver, closing);
 
         Ok(Temporary::from_rooted(*worker))
     }
 
     pub fn handle_message(address: TrustedWorkerAddress,
-                          data: *mut u64, nbytes: size_t) {
+                          data: StructuredCloneData, ports: Vec<PortEndpoint>) {
         let worker = unsafe { JS::from_trusted_worker_address(address).root() };
+        if worker.terminated.get() {
+            return;
+        }
 
         let global = worker.global.root();
+        let message = data.read(&global.root_ref());
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(*worker);
+        MessageEvent::dispatch_jsval(target, &global.root_ref(), message, "".to_string(), ports);
+    }
 
-        let mut message = UndefinedValue();
-        unsafe {
-            assert!(JS_ReadStructuredClone(
-                global.root_ref().get_cx(), data as *const u64, nbytes,
-                JS_STRUCTURED_CLONE_VERSION, &mut message,
-                ptr::null(), ptr::null_mut()) != 0);
+    // http://www.whatwg.org/html/#runtime-script-errors-2
+    pub fn handle_error(address: TrustedWorkerAddress, error: UncaughtError) {
+        let worker = unsafe { JS::from_trusted_worker_address(address).root() };
+        if worker.terminated.get() {
+            return;
         }
 
+        // FIXME: An error that is not canceled should be reported in this
+        // global too, as if it had been thrown here.
+        let global = worker.global.root();
+        let errorevent = ErrorEvent::new(&global.root_ref(), "error".to_string(), false, true,
+                                         error.message, error.filename,
+                                         error.lineno, error.colno).root();
         let target: JSRef<EventTarget> = EventTargetCast::from_ref(*worker);
-        MessageEvent::dispatch_jsval(target, &global.root_ref(), message);
+        let event: JSRef<Event> = EventCast::from_ref(*errorevent);
+        target.dispatch_event_with_target(None, event).ok();
     }
 }
 
@@ -130,17 +177,36 @@ impl Worker {
 }
 
 impl<'a> WorkerMethods for JSRef<'a, Worker> {
-    fn PostMessage(self, cx: *mut JSContext, message: JSVal) {
-        let mut data = ptr::null_mut();
-        let mut nbytes = 0;
-        unsafe {
-            assert!(JS_WriteStructuredClone(cx, message, &mut data, &mut nbytes,
-                                            ptr::null(), ptr::null_mut()) != 0);
+    // http://www.whatwg.org/html/#dom-worker-postmessage
+    fn PostMessage(self, cx: *mut JSContext, message: JSVal,
+                   transfer: Option<Vec<JS<MessagePort>>>) -> ErrorResult {
+        let (data, ports) = try!(write_message(cx, message, transfer, None));
+
+        // The worker may have closed itself already.
+        self.sender.queue_task(PostedMessageTaskSource, DOMMessage(data, ports));
+        Ok(())
+    }
+
+    // http://www.whatwg.org/html/#dom-worker-terminate
+    fn Terminate(self) {
+        if self.terminated.get() {
+            return;
         }
+        self.terminated.set(true);
+        self.closing.store(true, SeqCst);
+
+        // Wake the event loop up, in case it is waiting for a message.
+        self.sender.queue_task(DOMManipulationTaskSource, CloseWorkerMsg);
+    }
 
-        self.addref();
-        let ScriptChan(ref sender) = self.sender;
-        sender.send(DOMMessage(data, nbytes));
+    fn GetOnerror(self) -> Option<EventHandlerNonNull> {
+        let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        eventtarget.get_event_handler_common("error")
+    }
+
+    fn SetOnerror(self, listener: Option<EventHandlerNonNull>) {
+        let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        eventtarget.set_event_handler_common("error", listener)
     }
 
     fn GetOnmessage(self) -> Option<EventHandlerNonNull> {
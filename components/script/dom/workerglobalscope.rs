@@ -2,33 +2,57 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use dom::bindings::codegen::Bindings::EventHandlerBinding::OnErrorEventHandlerNonNull;
+use dom::bindings::codegen::Bindings::RequestBinding::RequestInit;
 use dom::bindings::codegen::Bindings::WorkerGlobalScopeBinding::WorkerGlobalScopeMethods;
+use dom::bindings::codegen::InheritTypes::EventTargetCast;
+use dom::bindings::codegen::UnionTypes::RequestOrString::RequestOrString;
 use dom::bindings::error::{ErrorResult, Fallible, Syntax, Network, FailureUnknown};
 use dom::bindings::trace::Untraceable;
 use dom::bindings::global;
 use dom::bindings::js::{JS, JSRef, Temporary, OptionalSettable};
 use dom::bindings::utils::{Reflectable, Reflector};
 use dom::console::Console;
-use dom::eventtarget::{EventTarget, WorkerGlobalScopeTypeId};
+use dom::eventsource::EventSource;
+use dom::eventtarget::{EventTarget, EventTargetHelpers, WorkerGlobalScopeTypeId};
+use dom::filereader::FileReader;
+use dom::messageport::MessagePort;
+use dom::promise::Promise;
+use dom::sharedworkerglobalscope::SharedWorkerOwner;
+use dom::websocket::WebSocket;
+use dom::worker::Worker;
 use dom::workerlocation::WorkerLocation;
 use dom::workernavigator::WorkerNavigator;
 use dom::window::{base64_atob, base64_btoa};
-use script_task::ScriptChan;
+use dom::xmlhttprequest::XMLHttpRequest;
+use fetch;
+use script_task::{ScriptChan, ScriptMsg, CloseWorkerMsg, FireTimerMsg, XHRProgressMsg};
+use script_task::{FileReaderMsg, WebSocketMsg, EventSourceMsg, WorkerPostMessage};
+use script_task::{WorkerRelease, WorkerUncaughtError, MessagePortMsg, MessagePortRelease};
+use script_task::PromiseMsg;
+use security::Origin;
+use task_source::DOMManipulationTaskSource;
+use timers::{FromWorker, TimerId, TimerManager, spawn_timer_scheduler};
 
-use servo_net::resource_task::{ResourceTask, load_whole_resource};
+use servo_net::blob_loader::BlobURLOwner;
+use servo_net::resource_task::{ResourceTask, RevokeBlobURLsOf, load_whole_resource};
 use servo_util::str::DOMString;
 
 use js::jsapi::JSContext;
+use js::jsval::JSVal;
 use js::rust::Cx;
 
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
+use std::sync::atomics::{AtomicBool, SeqCst};
+use sync::Arc;
 use url::{Url, UrlParser};
 
 #[deriving(PartialEq)]
 #[jstraceable]
 pub enum WorkerGlobalScopeId {
     DedicatedGlobalScope,
+    SharedGlobalScope,
 }
 
 #[jstraceable]
@@ -36,12 +60,23 @@ pub enum WorkerGlobalScopeId {
 pub struct WorkerGlobalScope {
     pub eventtarget: EventTarget,
     worker_url: Untraceable<Url>,
+    origin: Untraceable<Origin>,
     js_context: Untraceable<Rc<Cx>>,
     resource_task: Untraceable<ResourceTask>,
     script_chan: ScriptChan,
     location: Cell<Option<JS<WorkerLocation>>>,
     navigator: Cell<Option<JS<WorkerNavigator>>>,
     console: Cell<Option<JS<Console>>>,
+    timers: TimerManager,
+    /// Set by close() and by Worker.terminate(); the event loop stops as soon
+    /// as it sees it.
+    closing: Untraceable<Arc<AtomicBool>>,
+    /// The shared workers this worker connected to, which keep running for
+    /// as long as it does.
+    shared_worker_owners: Untraceable<RefCell<Vec<SharedWorkerOwner>>>,
+    /// What the `blob:` URLs created in this worker are registered under,
+    /// which are revoked when it stops.
+    blob_url_owner: Untraceable<BlobURLOwner>,
 }
 
 impl WorkerGlobalScope {
@@ -49,16 +84,22 @@ impl WorkerGlobalScope {
                          worker_url: Url,
                          cx: Rc<Cx>,
                          resource_task: ResourceTask,
-                         script_chan: ScriptChan) -> WorkerGlobalScope {
+                         script_chan: ScriptChan,
+                         closing: Arc<AtomicBool>) -> WorkerGlobalScope {
         WorkerGlobalScope {
             eventtarget: EventTarget::new_inherited(WorkerGlobalScopeTypeId(type_id)),
+            origin: Untraceable::new(Origin::from_url(&worker_url)),
             worker_url: Untraceable::new(worker_url),
             js_context: Untraceable::new(cx),
             resource_task: Untraceable::new(resource_task),
+            timers: TimerManager::new(FromWorker, spawn_timer_scheduler(script_chan.clone())),
             script_chan: script_chan,
             location: Cell::new(None),
             navigator: Cell::new(None),
             console: Cell::new(None),
+            closing: Untraceable::new(closing),
+            shared_worker_owners: Untraceable::new(RefCell::new(vec!())),
+            blob_url_owner: Untraceable::new(BlobURLOwner::new()),
         }
     }
 
@@ -74,9 +115,63 @@ impl WorkerGlobalScope {
         &*self.worker_url
     }
 
+    pub fn origin<'a>(&'a self) -> &'a Origin {
+        &*self.origin
+    }
+
     pub fn script_chan<'a>(&'a self) -> &'a ScriptChan {
         &self.script_chan
     }
+
+    pub fn blob_url_owner(&self) -> BlobURLOwner {
+        self.blob_url_owner.deref().clone()
+    }
+
+    /// Whether close() or Worker.terminate() was called.
+    pub fn is_closing(&self) -> bool {
+        self.closing.load(SeqCst)
+    }
+
+    pub fn add_shared_worker_owner(&self, owner: SharedWorkerOwner) {
+        self.shared_worker_owners.borrow_mut().push(owner);
+    }
+}
+
+pub trait WorkerGlobalScopeHelpers {
+    fn handle_fire_timer(self, timer_id: TimerId);
+    fn tear_down(self);
+    fn handle_script_msg(self, msg: ScriptMsg);
+}
+
+impl<'a> WorkerGlobalScopeHelpers for JSRef<'a, WorkerGlobalScope> {
+    fn handle_fire_timer(self, timer_id: TimerId) {
+        self.timers.fire_timer(timer_id, self, self.get_cx());
+    }
+
+    /// Stops what the worker started, once its event loop stopped.
+    fn tear_down(self) {
+        self.timers.cancel_all();
+        self.resource_task.send(RevokeBlobURLsOf(self.blob_url_owner()));
+    }
+
+    // Handles the messages that every kind of worker receives.
+    fn handle_script_msg(self, msg: ScriptMsg) {
+        match msg {
+            FireTimerMsg(FromWorker, timer_id) => self.handle_fire_timer(timer_id),
+            XHRProgressMsg(addr, progress) => XMLHttpRequest::handle_xhr_progress(addr, progress),
+            FileReaderMsg(addr, progress) => FileReader::handle_progress(addr, progress),
+            WebSocketMsg(addr, event) => WebSocket::handle_event(addr, event),
+            EventSourceMsg(addr, event) => EventSource::handle_event(addr, event),
+            WorkerPostMessage(addr, data, ports) => Worker::handle_message(addr, data, ports),
+            WorkerRelease(addr) => Worker::handle_release(addr),
+            WorkerUncaughtError(addr, error) => Worker::handle_error(addr, error),
+            MessagePortMsg(addr) => MessagePort::handle_message(addr),
+            MessagePortRelease(addr) => MessagePort::handle_release(addr),
+            PromiseMsg(addr, settlement) => Promise::handle_settlement(addr, settlement),
+            CloseWorkerMsg => {},
+            _ => fail!("Unexpected message"),
+        }
+    }
 }
 
 impl<'a> WorkerGlobalScopeMethods for JSRef<'a, WorkerGlobalScope> {
@@ -84,6 +179,24 @@ impl<'a> WorkerGlobalScopeMethods for JSRef<'a, WorkerGlobalScope> {
         Temporary::from_rooted(self)
     }
 
+    // http://www.whatwg.org/html/#dom-workerglobalscope-close
+    fn Close(self) {
+        self.closing.store(true, SeqCst);
+        // The script that called close() runs to the end, but nothing queued
+        // after it does.
+        self.script_chan.queue_task(DOMManipulationTaskSource, CloseWorkerMsg);
+    }
+
+    fn GetOnerror(self) -> Option<OnErrorEventHandlerNonNull> {
+        let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        eventtarget.get_event_handler_common("error")
+    }
+
+    fn SetOnerror(self, listener: Option<OnErrorEventHandlerNonNull>) {
+        let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(self);
+        eventtarget.set_event_handler_common("error", listener)
+    }
+
     fn Location(self) -> Temporary<WorkerLocation> {
         if self.location.get().is_none() {
             let location = WorkerLocation::new(self, self.worker_url.deref().clone());
@@ -147,6 +260,26 @@ impl<'a> WorkerGlobalScopeMethods for JSRef<'a, WorkerGlobalScope> {
     fn Atob(self, atob: DOMString) -> Fallible<DOMString> {
         base64_atob(atob)
     }
+
+    fn Fetch(self, input: RequestOrString, init: &RequestInit) -> Fallible<Temporary<Promise>> {
+        fetch::fetch(&global::Worker(self), input, init)
+    }
+
+    fn SetTimeout(self, _cx: *mut JSContext, handler: JSVal, timeout: i32) -> i32 {
+        self.timers.set_timeout_or_interval(handler, timeout, false)
+    }
+
+    fn ClearTimeout(self, handle: i32) {
+        self.timers.clear_timeout_or_interval(handle);
+    }
+
+    fn SetInterval(self, _cx: *mut JSContext, handler: JSVal, timeout: i32) -> i32 {
+        self.timers.set_timeout_or_interval(handler, timeout, true)
+    }
+
+    fn ClearInterval(self, handle: i32) {
+        self.ClearTimeout(handle);
+    }
 }
 
 impl Reflectable for WorkerGlobalScope {
@@ -42,6 +42,30 @@ impl<'a> WorkerLocationMethods for JSRef<'a, WorkerLocation> {
         UrlHelper::Href(self.url.deref())
     }
 
+    fn Origin(self) -> DOMString {
+        UrlHelper::Origin(self.url.deref())
+    }
+
+    fn Protocol(self) -> DOMString {
+        UrlHelper::Protocol(self.url.deref())
+    }
+
+    fn Host(self) -> DOMString {
+        UrlHelper::Host(self.url.deref())
+    }
+
+    fn Hostname(self) -> DOMString {
+        UrlHelper::Hostname(self.url.deref())
+    }
+
+    fn Port(self) -> DOMString {
+        UrlHelper::Port(self.url.deref())
+    }
+
+    fn Pathname(self) -> DOMString {
+        UrlHelper::Pathname(self.url.deref())
+    }
+
     fn Search(self) -> DOMString {
         UrlHelper::Search(self.url.deref())
     }
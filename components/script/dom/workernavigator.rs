@@ -51,6 +51,18 @@ impl<'a> WorkerNavigatorMethods for JSRef<'a, WorkerNavigator> {
     fn Platform(self) -> DOMString {
         NavigatorInfo::Platform()
     }
+
+    fn UserAgent(self) -> DOMString {
+        NavigatorInfo::UserAgent()
+    }
+
+    fn GetLanguage(self) -> Option<DOMString> {
+        NavigatorInfo::Language()
+    }
+
+    fn OnLine(self) -> bool {
+        NavigatorInfo::OnLine()
+    }
 }
 
 impl Reflectable for WorkerNavigator {
@@ -7,20 +7,24 @@ use dom::bindings::codegen::Bindings::XMLHttpRequestBinding;
 use dom::bindings::codegen::Bindings::XMLHttpRequestBinding::XMLHttpRequestMethods;
 use dom::bindings::codegen::Bindings::XMLHttpRequestBinding::XMLHttpRequestResponseType;
 use dom::bindings::codegen::Bindings::XMLHttpRequestBinding::XMLHttpRequestResponseTypeValues;
-use dom::bindings::codegen::Bindings::XMLHttpRequestBinding::XMLHttpRequestResponseTypeValues::{_empty, Json, Text};
+use dom::bindings::codegen::Bindings::XMLHttpRequestBinding::XMLHttpRequestResponseTypeValues::{_empty, Arraybuffer, Json, Text};
 use dom::bindings::codegen::InheritTypes::{EventCast, EventTargetCast, XMLHttpRequestDerived};
 use dom::bindings::conversions::ToJSValConvertible;
 use dom::bindings::error::{Error, ErrorResult, Fallible, InvalidState, InvalidAccess};
 use dom::bindings::error::{Network, Syntax, Security, Abort, Timeout};
-use dom::bindings::global::{GlobalField, GlobalRef, WorkerField};
+use dom::bindings::global::{GlobalField, GlobalRef, WindowField, WorkerField};
+use dom::bindings::global;
 use dom::bindings::js::{JS, JSRef, Temporary, OptionalRootedRootable};
 use dom::bindings::str::ByteString;
 use dom::bindings::trace::{Traceable, Untraceable};
-use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
-use dom::document::Document;
+use dom::bindings::utils::{Reflectable, Reflector, new_array_buffer, reflect_dom_object};
+use dom::document::{Document, HTMLDocument};
 use dom::event::Event;
 use dom::eventtarget::{EventTarget, EventTargetHelpers, XMLHttpRequestTargetTypeId};
+use dom::formdata::FormDataHelpers;
+use dom::headers::is_forbidden_header_name;
 use dom::progressevent::ProgressEvent;
+use dom::window::{WindowHelpers, XMLHttpRequestOwner};
 use dom::urlsearchparams::URLSearchParamsHelpers;
 use dom::xmlhttprequesteventtarget::XMLHttpRequestEventTarget;
 use dom::xmlhttprequestupload::XMLHttpRequestUpload;
@@ -46,24 +50,28 @@ use libc::c_void;
 
 use net::resource_task::{ResourceTask, ResourceCORSData, Load, LoadData, Payload, Done};
 use cors::{allow_cross_origin_request, CORSRequest, CORSMode, ForcedPreflightMode};
+use html::hubbub_html_parser::{HtmlDiscoveredScript, HtmlParserResult, InputString, parse_html};
 use script_task::{ScriptChan, XHRProgressMsg};
+use task_source::NetworkingTaskSource;
 use servo_util::str::DOMString;
 use servo_util::task::spawn_named;
 
 use std::ascii::StrAsciiExt;
 use std::cell::{Cell, RefCell};
-use std::comm::{Sender, Receiver, channel};
+use std::comm::{Sender, Receiver, Select, channel};
 use std::io::{BufReader, MemWriter, Timer};
 use std::from_str::FromStr;
 use std::path::BytesContainer;
 use std::task::TaskBuilder;
 use std::time::duration::Duration;
 use std::num::Zero;
-use time;
+use time::precise_time_ns;
 use url::{Url, UrlParser};
+use uuid::Uuid;
 
-use dom::bindings::codegen::UnionTypes::StringOrURLSearchParams::{eString, eURLSearchParams, StringOrURLSearchParams};
-pub type SendParam = StringOrURLSearchParams;
+use dom::bindings::codegen::UnionTypes::FormDataOrStringOrURLSearchParams::{eFormData, eString, eURLSearchParams};
+use dom::bindings::codegen::UnionTypes::FormDataOrStringOrURLSearchParams::FormDataOrStringOrURLSearchParams;
+pub type SendParam = FormDataOrStringOrURLSearchParams;
 
 
 #[deriving(PartialEq)]
@@ -83,17 +91,34 @@ enum XMLHttpRequestState {
     XHRDone = 4, // So as not to conflict with the ProgressMsg `Done`
 }
 
+/// Identifies the fetch a progress message belongs to, so that messages still
+/// queued for a fetch that open() or abort() terminated can be ignored.
+#[deriving(PartialEq, Clone, Show)]
+pub struct GenerationId(uint);
+
 pub enum XHRProgress {
     /// Notify that headers have been received
-    HeadersReceivedMsg(Option<ResponseHeaderCollection>, Status),
+    HeadersReceivedMsg(GenerationId, Url, Option<ResponseHeaderCollection>, Status),
     /// Partial progress (after receiving headers), containing portion of the response
-    LoadingMsg(ByteString),
+    LoadingMsg(GenerationId, ByteString),
     /// Loading is done
-    DoneMsg,
+    DoneMsg(GenerationId),
     /// There was an error (Abort or Timeout). For a network or other error, just pass None
-    ErroredMsg(Option<Error>),
+    ErroredMsg(GenerationId, Option<Error>),
     /// Timeout was reached
-    TimeoutMsg
+    TimeoutMsg(GenerationId)
+}
+
+impl XHRProgress {
+    fn generation_id(&self) -> GenerationId {
+        match *self {
+            HeadersReceivedMsg(id, _, _, _) |
+            LoadingMsg(id, _) |
+            DoneMsg(id) |
+            ErroredMsg(id, _) |
+            TimeoutMsg(id) => id
+        }
+    }
 }
 
 enum SyncOrAsync<'a> {
@@ -110,11 +135,13 @@ pub struct XMLHttpRequest {
     timeout: Traceable<Cell<u32>>,
     with_credentials: Traceable<Cell<bool>>,
     upload: JS<XMLHttpRequestUpload>,
-    response_url: DOMString,
+    response_url: Traceable<RefCell<DOMString>>,
     status: Traceable<Cell<u16>>,
     status_text: Traceable<RefCell<ByteString>>,
     response: Traceable<RefCell<ByteString>>,
     response_type: Traceable<Cell<XMLHttpRequestResponseType>>,
+    /// The value of `response` for the "json" and "arraybuffer" types, once computed
+    response_object: Traceable<Cell<Traceable<JSVal>>>,
     response_xml: Cell<Option<JS<Document>>>,
     response_headers: Untraceable<RefCell<ResponseHeaderCollection>>,
 
@@ -131,9 +158,11 @@ pub struct XMLHttpRequest {
     global: GlobalField,
     pinned_count: Traceable<Cell<uint>>,
     timer: Untraceable<RefCell<Timer>>,
-    fetch_time: Traceable<Cell<i64>>,
+    /// When the fetch started, in milliseconds.
+    fetch_time: Traceable<Cell<u64>>,
     timeout_pinned: Traceable<Cell<bool>>,
     terminate_sender: Untraceable<RefCell<Option<Sender<Error>>>>,
+    generation_id: Traceable<Cell<GenerationId>>,
 }
 
 impl XMLHttpRequest {
@@ -144,11 +173,12 @@ impl XMLHttpRequest {
             timeout: Traceable::new(Cell::new(0u32)),
             with_credentials: Traceable::new(Cell::new(false)),
             upload: JS::from_rooted(XMLHttpRequestUpload::new(global)),
-            response_url: "".to_string(),
+            response_url: Traceable::new(RefCell::new("".to_string())),
             status: Traceable::new(Cell::new(0)),
             status_text: Traceable::new(RefCell::new(ByteString::new(vec!()))),
             response: Traceable::new(RefCell::new(ByteString::new(vec!()))),
             response_type: Traceable::new(Cell::new(_empty)),
+            response_object: Traceable::new(Cell::new(Traceable::new(UndefinedValue()))),
             response_xml: Cell::new(None),
             response_headers: Untraceable::new(RefCell::new(ResponseHeaderCollection::new())),
 
@@ -168,6 +198,7 @@ impl XMLHttpRequest {
             fetch_time: Traceable::new(Cell::new(0)),
             timeout_pinned: Traceable::new(Cell::new(false)),
             terminate_sender: Untraceable::new(RefCell::new(None)),
+            generation_id: Traceable::new(Cell::new(GenerationId(0))),
         }
     }
     pub fn new(global: &GlobalRef) -> Temporary<XMLHttpRequest> {
@@ -188,19 +219,35 @@ impl XMLHttpRequest {
 
     fn fetch(fetch_type: &SyncOrAsync, resource_task: ResourceTask,
              mut load_data: LoadData, terminate_receiver: Receiver<Error>,
-             cors_request: Result<Option<CORSRequest>,()>) -> ErrorResult {
+             cors_request: Result<Option<CORSRequest>,()>, gen_id: GenerationId) -> ErrorResult {
         fn notify_partial_progress(fetch_type: &SyncOrAsync, msg: XHRProgress) {
             match *fetch_type {
                 Sync(xhr) => {
                     xhr.process_partial_response(msg);
                 },
                 Async(addr, ref script_chan) => {
-                    let ScriptChan(ref chan) = *script_chan;
-                    chan.send(XHRProgressMsg(addr, msg));
+                    script_chan.queue_task(NetworkingTaskSource, XHRProgressMsg(addr, msg));
                 }
             }
         }
 
+        // Waits for what `port` delivers next, unless the fetch is terminated
+        // first.
+        fn recv_or_terminate<T: Send>(port: &Receiver<T>,
+                                      terminate_receiver: &Receiver<Error>) -> Result<T, Error> {
+            let select = Select::new();
+            let mut port_handle = select.handle(port);
+            unsafe { port_handle.add() };
+            let mut terminate_handle = select.handle(terminate_receiver);
+            unsafe { terminate_handle.add() };
+            select.wait();
+            match terminate_receiver.try_recv() {
+                Ok(e) => Err(e),
+                // Nothing terminated the fetch, or nothing can any more.
+                Err(_) => Ok(port.recv()),
+            }
+        }
+
         match cors_request {
             Err(_) => return Err(Network), // Happens in case of cross-origin non-http URIs
             Ok(Some(ref req)) => {
@@ -220,11 +267,7 @@ impl XMLHttpRequest {
         // Step 10, 13
         let (start_chan, start_port) = channel();
         resource_task.send(Load(load_data, start_chan));
-        let response = start_port.recv();
-        match terminate_receiver.try_recv() {
-            Ok(e) => return Err(e),
-            _ => {}
-        }
+        let response = try!(recv_or_terminate(&start_port, &terminate_receiver));
         match cors_request {
             Ok(Some(ref req)) => {
                     match response.metadata.headers {
@@ -236,26 +279,34 @@ impl XMLHttpRequest {
         }
         // XXXManishearth Clear cache entries in case of a network error
 
-        notify_partial_progress(fetch_type, HeadersReceivedMsg(
-            response.metadata.headers.clone(), response.metadata.status.clone()));
+        // Non-HTTP loads have no headers, but may still know their MIME type.
+        let headers = match (&response.metadata.headers, &response.metadata.content_type) {
+            (&None, &Some((ref type_, ref subtype))) => {
+                let mut headers = ResponseHeaderCollection::new();
+                headers.content_type = Some(MediaType {
+                    type_: type_.clone(),
+                    subtype: subtype.clone(),
+                    parameters: vec!(),
+                });
+                Some(headers)
+            }
+            (headers, _) => headers.clone(),
+        };
+        notify_partial_progress(fetch_type, HeadersReceivedMsg(gen_id,
+            response.metadata.final_url.clone(), headers, response.metadata.status.clone()));
         let mut buf = vec!();
         loop {
-            let progress = response.progress_port.recv();
-            match terminate_receiver.try_recv() {
-                Ok(e) => return Err(e),
-                _ => {}
-            }
+            let progress = try!(recv_or_terminate(&response.progress_port, &terminate_receiver));
             match progress {
                 Payload(data) => {
                     buf.push_all(data.as_slice());
-                    notify_partial_progress(fetch_type, LoadingMsg(ByteString::new(buf.clone())));
+                    notify_partial_progress(fetch_type, LoadingMsg(gen_id, ByteString::new(buf.clone())));
                 },
                 Done(Ok(()))  => {
-                    notify_partial_progress(fetch_type, DoneMsg);
+                    notify_partial_progress(fetch_type, DoneMsg(gen_id));
                     return Ok(());
                 },
                 Done(Err(_))  => {
-                    notify_partial_progress(fetch_type, ErroredMsg(None));
                     return Err(Network)
                 }
             }
@@ -279,6 +330,10 @@ impl<'a> XMLHttpRequestMethods for JSRef<'a, XMLHttpRequest> {
     }
 
     fn Open(self, method: ByteString, url: DOMString) -> ErrorResult {
+        self.Open_(method, url, true, None, None)
+    }
+    fn Open_(self, method: ByteString, url: DOMString, async: bool,
+                 _username: Option<DOMString>, _password: Option<DOMString>) -> ErrorResult {
         // Clean up from previous requests, if any:
         self.cancel_timeout();
         let uppercase_method = method.as_str().map(|s| {
@@ -314,19 +369,29 @@ impl<'a> XMLHttpRequestMethods for JSRef<'a, XMLHttpRequest> {
                     Err(_) => return Err(Syntax) // Step 7
                 };
                 // XXXManishearth Do some handling of username/passwords
-                if self.sync.deref().get() {
-                    // FIXME: This should only happen if the global environment is a document environment
+                // Only workers, whose event loop blocking doesn't freeze a
+                // page, get to make any kind of synchronous request
+                if !async && self.in_window() {
                     if self.timeout.deref().get() != 0 || self.with_credentials.deref().get() || self.response_type.deref().get() != _empty {
                         return Err(InvalidAccess)
                     }
                 }
-                // XXXManishearth abort existing requests
+                // Step 11
+                self.terminate_ongoing_fetch();
+                if self.send_flag.deref().get() {
+                    // The terminated fetch won't get to release the object itself
+                    self.release_once();
+                }
                 // Step 12
+                self.sync.deref().set(!async);
                 *self.request_url.deref().borrow_mut() = Some(parsed_url);
                 *self.request_headers.deref().borrow_mut() = RequestHeaderCollection::new();
                 self.send_flag.deref().set(false);
+                *self.response_url.deref().borrow_mut() = "".to_string();
                 *self.status_text.deref().borrow_mut() = ByteString::new(vec!());
                 self.status.deref().set(0);
+                *self.response.deref().borrow_mut() = ByteString::new(vec!());
+                self.clear_response_objects();
 
                 // Step 13
                 if self.ready_state.deref().get() != Opened {
@@ -339,11 +404,6 @@ impl<'a> XMLHttpRequestMethods for JSRef<'a, XMLHttpRequest> {
             _ => Err(Syntax), // Step 3
         }
     }
-    fn Open_(self, method: ByteString, url: DOMString, async: bool,
-                 _username: Option<DOMString>, _password: Option<DOMString>) -> ErrorResult {
-        self.sync.deref().set(!async);
-        self.Open(method, url)
-    }
     fn SetRequestHeader(self, name: ByteString, mut value: ByteString) -> ErrorResult {
         if self.ready_state.deref().get() != Opened || self.send_flag.deref().get() {
             return Err(InvalidState); // Step 1, 2
@@ -352,22 +412,8 @@ impl<'a> XMLHttpRequestMethods for JSRef<'a, XMLHttpRequest> {
             return Err(Syntax); // Step 3, 4
         }
         let name_str = match name.to_lower().as_str() {
-            Some(s) => {
-                match s {
-                    // Disallowed headers
-                    "accept-charset" | "accept-encoding" |
-                    "access-control-request-headers" |
-                    "access-control-request-method" |
-                    "connection" | "content-length" |
-                    "cookie" | "cookie2" | "date" |"dnt" |
-                    "expect" | "host" | "keep-alive" | "origin" |
-                    "referer" | "te" | "trailer" | "transfer-encoding" |
-                    "upgrade" | "user-agent" | "via" => {
-                        return Ok(()); // Step 5
-                    },
-                    _ => String::from_str(s)
-                }
-            },
+            Some(_) if is_forbidden_header_name(&name) => return Ok(()), // Step 5
+            Some(s) => String::from_str(s),
             None => return Err(Syntax)
         };
         let mut collection = self.request_headers.deref().borrow_mut();
@@ -376,7 +422,7 @@ impl<'a> XMLHttpRequestMethods for JSRef<'a, XMLHttpRequest> {
         // Steps 6,7
         let old_header = collection.iter().find(|ref h| -> bool {
             // XXXManishearth following line waiting on the rust upgrade:
-            ByteString::new(h.header_name().into_bytes()).eq_ignore_case(&value)
+            ByteString::new(h.header_name().into_bytes()).eq_ignore_case(&name)
         });
         match old_header {
             Some(h) => {
@@ -412,9 +458,8 @@ impl<'a> XMLHttpRequestMethods for JSRef<'a, XMLHttpRequest> {
         self.timeout.deref().get()
     }
     fn SetTimeout(self, timeout: u32) -> ErrorResult {
-        if self.sync.deref().get() {
-            // FIXME: Not valid for a worker environment
-            Err(InvalidState)
+        if self.sync.deref().get() && self.in_window() {
+            Err(InvalidAccess)
         } else {
             self.timeout.deref().set(timeout);
             if self.send_flag.deref().get() {
@@ -422,12 +467,14 @@ impl<'a> XMLHttpRequestMethods for JSRef<'a, XMLHttpRequest> {
                     self.cancel_timeout();
                     return Ok(());
                 }
-                let progress = time::now().to_timespec().sec - self.fetch_time.deref().get();
-                if timeout > (progress * 1000) as u32 {
-                    self.set_timeout(timeout - (progress * 1000) as u32);
+                let progress = precise_time_ns() / 1000000 - self.fetch_time.deref().get();
+                if timeout as u64 > progress {
+                    self.set_timeout(timeout - progress as u32);
                 } else {
-                    // Immediately execute the timeout steps
-                    self.set_timeout(0);
+                    // Immediately execute the timeout steps, before whatever
+                    // the fetch has already queued up
+                    let gen_id = self.generation_id.deref().get();
+                    self.process_partial_response(TimeoutMsg(gen_id));
                 }
             }
             Ok(())
@@ -451,7 +498,10 @@ impl<'a> XMLHttpRequestMethods for JSRef<'a, XMLHttpRequest> {
             Get | Head => None, // Step 3
             _ => data
         };
-        let extracted = data.as_ref().map(|d| d.extract());
+        let (extracted, extracted_type) = match data.as_ref().map(|d| d.extract()) {
+            Some((bytes, content_type)) => (Some(bytes), Some(content_type)),
+            None => (None, None),
+        };
         self.request_body_len.set(extracted.as_ref().map(|e| e.len()).unwrap_or(0));
 
         // Step 6
@@ -484,11 +534,12 @@ impl<'a> XMLHttpRequestMethods for JSRef<'a, XMLHttpRequest> {
             if !self.upload_complete.deref().get() {
                 self.dispatch_upload_progress_event("loadstart".to_string(), Some(0));
             }
-        }
 
-        if self.ready_state.deref().get() == Unsent {
-            // The progress events above might have run abort(), in which case we terminate the fetch.
-            return Ok(());
+            // The progress events above might have run abort() or open(), in which case we
+            // terminate the fetch.
+            if self.ready_state.deref().get() != Opened || !self.send_flag.deref().get() {
+                return Ok(());
+            }
         }
 
         let global = self.global.root();
@@ -499,22 +550,7 @@ impl<'a> XMLHttpRequestMethods for JSRef<'a, XMLHttpRequest> {
         // Default headers
         let request_headers = self.request_headers.deref();
         if request_headers.borrow().content_type.is_none() {
-            let parameters = vec!((String::from_str("charset"), String::from_str("UTF-8")));
-            request_headers.borrow_mut().content_type = match data {
-                Some(eString(_)) =>
-                    Some(MediaType {
-                        type_: String::from_str("text"),
-                        subtype: String::from_str("plain"),
-                        parameters: parameters
-                    }),
-                Some(eURLSearchParams(_)) =>
-                    Some(MediaType {
-                        type_: String::from_str("application"),
-                        subtype: String::from_str("x-www-form-urlencoded"),
-                        parameters: parameters
-                    }),
-                None => None
-            }
+            request_headers.borrow_mut().content_type = extracted_type;
         }
 
         if request_headers.borrow().accept.is_none() {
@@ -527,14 +563,16 @@ impl<'a> XMLHttpRequestMethods for JSRef<'a, XMLHttpRequest> {
         *self.terminate_sender.deref().borrow_mut() = Some(terminate_sender);
 
         // CORS stuff
-        let referer_url = self.global.root().root_ref().get_url();
+        let referer_url = global.root_ref().get_url();
+        let origin = global.root_ref().get_origin();
         let mode = if self.upload_events.deref().get() {
             ForcedPreflightMode
         } else {
             CORSMode
         };
-        let cors_request = CORSRequest::maybe_new(referer_url.clone(), load_data.url.clone(), mode,
-                                                  load_data.method.clone(), load_data.headers.clone());
+        let cors_request = CORSRequest::maybe_new(referer_url.clone(), &origin, load_data.url.clone(),
+                                                  mode, load_data.method.clone(),
+                                                  load_data.headers.clone());
         match cors_request {
             Ok(None) => {
                 let mut buf = String::new();
@@ -548,21 +586,48 @@ impl<'a> XMLHttpRequestMethods for JSRef<'a, XMLHttpRequest> {
                 referer_url.serialize_path().map(|ref h| buf.push_str(h.as_slice()));
                 self.request_headers.deref().borrow_mut().referer = Some(buf);
             },
-            Ok(Some(ref req)) => self.insert_trusted_header("origin".to_string(),
-                                                            format!("{}", req.origin)),
+            Ok(Some(_)) => self.insert_trusted_header("origin".to_string(), origin.serialize()),
             _ => {}
         }
 
+        let gen_id = self.generation_id.deref().get();
         if self.sync.deref().get() {
-            return XMLHttpRequest::fetch(&mut Sync(self), resource_task, load_data,
-                                         terminate_receiver, cors_request);
+            // The event loop is blocked until the fetch is done, so a timeout
+            // terminates the fetch itself.
+            let timeout = self.timeout.deref().get();
+            if timeout > 0 {
+                let oneshot = self.timer.deref().borrow_mut()
+                                  .oneshot(Duration::milliseconds(timeout as i64));
+                let terminate_sender = self.terminate_sender.deref().borrow().as_ref().unwrap().clone();
+                spawn_named("XHR:SyncTimer", proc() {
+                    // The timer is dropped once the fetch is done.
+                    if oneshot.recv_opt().is_ok() {
+                        let _ = terminate_sender.send_opt(Timeout);
+                    }
+                });
+            }
+            let result = XMLHttpRequest::fetch(&mut Sync(self), resource_task, load_data,
+                                               terminate_receiver, cors_request, gen_id);
+            self.cancel_timeout();
+            if result.is_err() {
+                self.process_partial_response(ErroredMsg(gen_id, None));
+            }
+            return result;
         } else {
             let builder = TaskBuilder::new().named("XHRTask");
-            self.fetch_time.deref().set(time::now().to_timespec().sec);
+            self.fetch_time.deref().set(precise_time_ns() / 1000000);
             let script_chan = global.root_ref().script_chan().clone();
             builder.spawn(proc() {
-                let _ = XMLHttpRequest::fetch(&mut Async(addr.unwrap(), script_chan),
-                                              resource_task, load_data, terminate_receiver, cors_request);
+                let async = Async(addr.unwrap(), script_chan.clone());
+                match XMLHttpRequest::fetch(&async, resource_task, load_data,
+                                            terminate_receiver, cors_request, gen_id) {
+                    // Aborts and timeouts are handled on the script task's side
+                    Err(Network) => {
+                        let msg = XHRProgressMsg(addr.unwrap(), ErroredMsg(gen_id, None));
+                        script_chan.queue_task(NetworkingTaskSource, msg);
+                    },
+                    _ => {}
+                }
             });
             let timeout = self.timeout.deref().get();
             if timeout > 0 {
@@ -572,16 +637,23 @@ impl<'a> XMLHttpRequestMethods for JSRef<'a, XMLHttpRequest> {
         Ok(())
     }
     fn Abort(self) {
-        self.terminate_sender.deref().borrow().as_ref().map(|s| s.send_opt(Abort));
+        // Step 1
+        self.terminate_ongoing_fetch();
+        // Step 2
+        let gen_id = self.generation_id.deref().get();
         match self.ready_state.deref().get() {
-            Opened if self.send_flag.deref().get() => self.process_partial_response(ErroredMsg(Some(Abort))),
-            HeadersReceived | Loading => self.process_partial_response(ErroredMsg(Some(Abort))),
+            Opened if self.send_flag.deref().get() => self.process_partial_response(ErroredMsg(gen_id, Some(Abort))),
+            HeadersReceived | Loading => self.process_partial_response(ErroredMsg(gen_id, Some(Abort))),
             _ => {}
         };
-        self.ready_state.deref().set(Unsent);
+        // Step 3, unless one of the event handlers above called open()
+        if self.ready_state.deref().get() == XHRDone && self.generation_id.deref().get() == gen_id {
+            // No readystatechange event is fired for this change
+            self.ready_state.deref().set(Unsent);
+        }
     }
     fn ResponseURL(self) -> DOMString {
-        self.response_url.clone()
+        self.response_url.deref().borrow().clone()
     }
     fn Status(self) -> u16 {
         self.status.deref().get()
@@ -619,7 +691,7 @@ impl<'a> XMLHttpRequestMethods for JSRef<'a, XMLHttpRequest> {
         }
         match self.ready_state.deref().get() {
             Loading | XHRDone => Err(InvalidState),
-            _ if self.sync.deref().get() => Err(InvalidAccess),
+            _ if self.sync.deref().get() && self.in_window() => Err(InvalidAccess),
             _ => {
                 self.response_type.deref().set(response_type);
                 Ok(())
@@ -637,18 +709,9 @@ impl<'a> XMLHttpRequestMethods for JSRef<'a, XMLHttpRequest> {
                 }
             },
             _ if self.ready_state.deref().get() != XHRDone => NullValue(),
-            Json => {
-                let decoded = UTF_8.decode(self.response.deref().borrow().as_slice(), DecodeReplace).unwrap().to_string();
-                let decoded: Vec<u16> = decoded.as_slice().utf16_units().collect();
-                let mut vp = UndefinedValue();
-                unsafe {
-                    if JS_ParseJSON(cx, decoded.as_ptr(), decoded.len() as u32, &mut vp) == 0 {
-                        JS_ClearPendingException(cx);
-                        return NullValue();
-                    }
-                }
-                vp
-            }
+            Json => self.json_response(cx),
+            Arraybuffer => self.arraybuffer_response(cx),
+            XMLHttpRequestResponseTypeValues::Document => self.document_response().to_jsval(cx),
             _ => {
                 // XXXManishearth handle other response types
                 self.response.deref().borrow().to_jsval(cx)
@@ -666,8 +729,16 @@ impl<'a> XMLHttpRequestMethods for JSRef<'a, XMLHttpRequest> {
             _ => Err(InvalidState)
         }
     }
-    fn GetResponseXML(self) -> Option<Temporary<Document>> {
-        self.response_xml.get().map(|response| Temporary::new(response))
+    fn GetResponseXML(self) -> Fallible<Option<Temporary<Document>>> {
+        match self.response_type.deref().get() {
+            _empty | XMLHttpRequestResponseTypeValues::Document => {
+                match self.ready_state.deref().get() {
+                    XHRDone => Ok(self.document_response().map(|document| Temporary::new(document))),
+                    _ => Ok(None)
+                }
+            },
+            _ => Err(InvalidState)
+        }
     }
 }
 
@@ -696,27 +767,53 @@ impl TrustedXHRAddress {
     }
 }
 
+pub trait XMLHttpRequestHelpers {
+    fn terminate_for_unload(self);
+}
+
+impl<'a> XMLHttpRequestHelpers for JSRef<'a, XMLHttpRequest> {
+    /// Terminates the fetch that is going on, without firing any event,
+    /// because the document that started it is being unloaded.
+    fn terminate_for_unload(self) {
+        if self.sync.deref().get() || !self.send_flag.deref().get() {
+            return;
+        }
+        self.terminate_ongoing_fetch();
+        self.send_flag.deref().set(false);
+        // The fetch won't send the message that would have released it.
+        self.cancel_timeout();
+        self.release_once();
+    }
+}
 
 trait PrivateXMLHttpRequestHelpers {
     unsafe fn to_trusted(self) -> TrustedXHRAddress;
     fn release_once(self);
     fn change_ready_state(self, XMLHttpRequestState);
     fn process_partial_response(self, progress: XHRProgress);
+    fn terminate_ongoing_fetch(self);
     fn insert_trusted_header(self, name: String, value: String);
     fn dispatch_progress_event(self, upload: bool, type_: DOMString, loaded: u64, total: Option<u64>);
     fn dispatch_upload_progress_event(self, type_: DOMString, partial_load: Option<u64>);
     fn dispatch_response_progress_event(self, type_: DOMString);
     fn text_response(self) -> DOMString;
+    fn json_response(self, cx: *mut JSContext) -> JSVal;
+    fn arraybuffer_response(self, cx: *mut JSContext) -> JSVal;
+    fn document_response(self) -> Option<JS<Document>>;
+    fn clear_response_objects(self);
     fn set_timeout(self, timeout:u32);
     fn cancel_timeout(self);
     fn filter_response_headers(self) -> ResponseHeaderCollection;
+    fn in_window(self) -> bool;
 }
 
 impl<'a> PrivateXMLHttpRequestHelpers for JSRef<'a, XMLHttpRequest> {
     // Creates a trusted address to the object, and roots it. Always pair this with a release()
     unsafe fn to_trusted(self) -> TrustedXHRAddress {
         if self.pinned_count.deref().get() == 0 {
-            JS_AddObjectRoot(self.global.root().root_ref().get_cx(), self.reflector().rootable());
+            let global = self.global.root();
+            JS_AddObjectRoot(global.root_ref().get_cx(), self.reflector().rootable());
+            global.root_ref().track_network_owner(XMLHttpRequestOwner(JS::from_rooted(self)));
         }
         let pinned_count = self.pinned_count.deref().get();
         self.pinned_count.deref().set(pinned_count + 1);
@@ -734,9 +831,11 @@ impl<'a> PrivateXMLHttpRequestHelpers for JSRef<'a, XMLHttpRequest> {
         let pinned_count = self.pinned_count.deref().get();
         self.pinned_count.deref().set(pinned_count - 1);
         if self.pinned_count.deref().get() == 0 {
+            let global = self.global.root();
             unsafe {
-                JS_RemoveObjectRoot(self.global.root().root_ref().get_cx(), self.reflector().rootable());
+                JS_RemoveObjectRoot(global.root_ref().get_cx(), self.reflector().rootable());
             }
+            global.root_ref().untrack_network_owner(XMLHttpRequestOwner(JS::from_rooted(self)));
         }
     }
 
@@ -752,8 +851,13 @@ impl<'a> PrivateXMLHttpRequestHelpers for JSRef<'a, XMLHttpRequest> {
     }
 
     fn process_partial_response(self, progress: XHRProgress) {
+        // Ignore whatever is left of a fetch that has been terminated
+        if progress.generation_id() != self.generation_id.deref().get() {
+            return;
+        }
+
         match progress {
-            HeadersReceivedMsg(headers, status) => {
+            HeadersReceivedMsg(_, url, headers, status) => {
                 // For synchronous requests, this should not fire any events, and just store data
                 // XXXManishearth Find a way to track partial progress of the send (onprogresss for XHRUpload)
 
@@ -769,6 +873,7 @@ impl<'a> PrivateXMLHttpRequestHelpers for JSRef<'a, XMLHttpRequest> {
                 // Part of step 13, send() (processing response)
                 // XXXManishearth handle errors, if any (substep 1)
                 // Substep 2
+                *self.response_url.deref().borrow_mut() = url.serialize_no_fragment();
                 *self.status_text.deref().borrow_mut() = ByteString::new(status.reason().container_into_owned_bytes());
                 self.status.deref().set(status.code());
                 match headers {
@@ -782,7 +887,7 @@ impl<'a> PrivateXMLHttpRequestHelpers for JSRef<'a, XMLHttpRequest> {
                     self.change_ready_state(HeadersReceived);
                 }
             },
-            LoadingMsg(partial_response) => {
+            LoadingMsg(_, partial_response) => {
                 // For synchronous requests, this should not fire any events, and just store data
                 // Part of step 13, send() (processing response body)
                 // XXXManishearth handle errors, if any (substep 1)
@@ -798,7 +903,7 @@ impl<'a> PrivateXMLHttpRequestHelpers for JSRef<'a, XMLHttpRequest> {
                     self.dispatch_response_progress_event("progress".to_string());
                 }
             },
-            DoneMsg => {
+            DoneMsg(_) => {
                 // Part of step 13, send() (processing response end of file)
                 // XXXManishearth handle errors, if any (substep 1)
 
@@ -816,9 +921,20 @@ impl<'a> PrivateXMLHttpRequestHelpers for JSRef<'a, XMLHttpRequest> {
                 self.cancel_timeout();
                 self.release_once();
             },
-            ErroredMsg(e) => {
+            ErroredMsg(_, e) => {
                 self.send_flag.deref().set(false);
-                // XXXManishearth set response to NetworkError
+                // A network error has no status, headers nor body
+                self.status.deref().set(0);
+                *self.response_url.deref().borrow_mut() = "".to_string();
+                *self.status_text.deref().borrow_mut() = ByteString::new(vec!());
+                *self.response_headers.deref().borrow_mut() = ResponseHeaderCollection::new();
+                *self.response.deref().borrow_mut() = ByteString::new(vec!());
+                self.clear_response_objects();
+                if self.sync.deref().get() {
+                    // send() throws instead of firing any event
+                    self.ready_state.deref().set(XHRDone);
+                    return;
+                }
                 self.change_ready_state(XHRDone);
                 let errormsg = match e {
                     Some(Abort) => "abort",
@@ -841,16 +957,25 @@ impl<'a> PrivateXMLHttpRequestHelpers for JSRef<'a, XMLHttpRequest> {
                 self.cancel_timeout();
                 self.release_once();
             },
-            TimeoutMsg => {
+            TimeoutMsg(_) => {
+                // Stop the fetch, and ignore whatever it has already sent
+                self.terminate_ongoing_fetch();
+                let gen_id = self.generation_id.deref().get();
                 match self.ready_state.deref().get() {
-                    Opened if self.send_flag.deref().get() => self.process_partial_response(ErroredMsg(Some(Timeout))),
-                    Loading | HeadersReceived => self.process_partial_response(ErroredMsg(Some(Timeout))),
+                    Opened if self.send_flag.deref().get() => self.process_partial_response(ErroredMsg(gen_id, Some(Timeout))),
+                    Loading | HeadersReceived => self.process_partial_response(ErroredMsg(gen_id, Some(Timeout))),
                     _ => self.release_once()
                 };
             }
         }
     }
 
+    fn terminate_ongoing_fetch(self) {
+        let GenerationId(prev_id) = self.generation_id.deref().get();
+        self.generation_id.deref().set(GenerationId(prev_id + 1));
+        self.terminate_sender.deref().borrow().as_ref().map(|s| s.send_opt(Abort));
+    }
+
     fn insert_trusted_header(self, name: String, value: String) {
         // Insert a header without checking spec-compliance
         // Use for hardcoded headers
@@ -906,13 +1031,12 @@ impl<'a> PrivateXMLHttpRequestHelpers for JSRef<'a, XMLHttpRequest> {
         self.timeout_pinned.deref().set(true);
         let global = self.global.root();
         let script_chan = global.root_ref().script_chan().clone();
-        let terminate_sender = (*self.terminate_sender.deref().borrow()).clone();
+        let gen_id = self.generation_id.deref().get();
         spawn_named("XHR:Timer", proc () {
             match oneshot.recv_opt() {
                 Ok(_) => {
-                    let ScriptChan(ref chan) = script_chan;
-                    terminate_sender.map(|s| s.send_opt(Timeout));
-                    chan.send(XHRProgressMsg(addr, TimeoutMsg));
+                    script_chan.queue_task(NetworkingTaskSource,
+                                           XHRProgressMsg(addr, TimeoutMsg(gen_id)));
                 },
                 Err(_) => {
                     // This occurs if xhr.timeout (the sender) goes out of scope (i.e, xhr went out of scope)
@@ -948,6 +1072,83 @@ impl<'a> PrivateXMLHttpRequestHelpers for JSRef<'a, XMLHttpRequest> {
         // the result should be fine. XXXManishearth have a closer look at this later
         encoding.decode(self.response.deref().borrow().as_slice(), DecodeReplace).unwrap().to_string()
     }
+    fn json_response(self, cx: *mut JSContext) -> JSVal {
+        let cached = *self.response_object.deref().get();
+        if !cached.is_undefined() {
+            return cached;
+        }
+
+        let decoded = UTF_8.decode(self.response.deref().borrow().as_slice(), DecodeReplace).unwrap().to_string();
+        let decoded: Vec<u16> = decoded.as_slice().utf16_units().collect();
+        let mut vp = UndefinedValue();
+        unsafe {
+            if JS_ParseJSON(cx, decoded.as_ptr(), decoded.len() as u32, &mut vp) == 0 {
+                JS_ClearPendingException(cx);
+                vp = NullValue();
+            }
+        }
+        self.response_object.deref().set(Traceable::new(vp));
+        vp
+    }
+    fn arraybuffer_response(self, cx: *mut JSContext) -> JSVal {
+        let cached = *self.response_object.deref().get();
+        if !cached.is_undefined() {
+            return cached;
+        }
+
+        let value = new_array_buffer(cx, self.response.deref().borrow().as_slice());
+        self.response_object.deref().set(Traceable::new(value));
+        value
+    }
+    fn document_response(self) -> Option<JS<Document>> {
+        match self.response_xml.get() {
+            Some(document) => return Some(document),
+            None => {}
+        }
+
+        // Only window environments can have a document response
+        let global = self.global.root();
+        let window = match global.root_ref() {
+            global::Window(window) => window,
+            global::Worker(_) => return None
+        };
+
+        // FIXME: Documents with an XML MIME type, which is assumed when there is
+        // no Content-Type, need an XML parser.
+        let is_html = match self.response_headers.deref().borrow().content_type {
+            Some(ref mime) => mime.type_.as_slice().eq_ignore_ascii_case("text") &&
+                              mime.subtype.as_slice().eq_ignore_ascii_case("html"),
+            None => false
+        };
+        if !is_html || self.response_type.deref().get() == _empty {
+            return None;
+        }
+
+        // The document is inert: it has no browsing context, so it loads no
+        // subresources, and its URLs resolve against the response URL.
+        let url = Url::parse(self.response_url.deref().borrow().as_slice()).ok()
+                      .or_else(|| self.request_url.deref().borrow().clone());
+        let document = Document::new(window, url, HTMLDocument,
+                                     Some("text/html".to_string())).root();
+        let HtmlParserResult { discovery_port } =
+            parse_html(&*window.page(), *document, InputString(self.text_response()),
+                       global.root_ref().resource_task());
+        // Scripts never run in the response document.
+        loop {
+            match discovery_port.recv_opt() {
+                Ok(HtmlDiscoveredScript(_)) => {}
+                Err(()) => break
+            }
+        }
+
+        let document = JS::from_rooted(*document);
+        self.response_xml.set(Some(document));
+        Some(document)
+    }
+    fn clear_response_objects(self) {
+        self.response_object.deref().set(Traceable::new(UndefinedValue()));
+        self.response_xml.set(None);
+    }
     fn filter_response_headers(self) -> ResponseHeaderCollection {
         // http://fetch.spec.whatwg.org/#concept-response-header-list
         let mut headers = ResponseHeaderCollection::new();
@@ -960,18 +1161,45 @@ impl<'a> PrivateXMLHttpRequestHelpers for JSRef<'a, XMLHttpRequest> {
         }
         headers
     }
+    fn in_window(self) -> bool {
+        match self.global {
+            WindowField(_) => true,
+            WorkerField(_) => false,
+        }
+    }
 }
 
-trait Extractable {
-    fn extract(&self) -> Vec<u8>;
+pub trait Extractable {
+    /// Returns the bytes of the body and their type.
+    fn extract(&self) -> (Vec<u8>, MediaType);
 }
 impl Extractable for SendParam {
-    fn extract(&self) -> Vec<u8> {
+    fn extract(&self) -> (Vec<u8>, MediaType) {
         // http://fetch.spec.whatwg.org/#concept-fetchbodyinit-extract
         let encoding = UTF_8 as EncodingRef;
         match *self {
-            eString(ref s) => encoding.encode(s.as_slice(), EncodeReplace).unwrap(),
-            eURLSearchParams(ref usp) => usp.root().serialize(None) // Default encoding is UTF8
+            eString(ref s) => {
+                (encoding.encode(s.as_slice(), EncodeReplace).unwrap(),
+                 media_type("text", "plain", "charset", String::from_str("UTF-8")))
+            },
+            eURLSearchParams(ref usp) => {
+                // Default encoding is UTF8
+                (usp.root().serialize(None),
+                 media_type("application", "x-www-form-urlencoded", "charset", String::from_str("UTF-8")))
+            },
+            eFormData(ref form_data) => {
+                let boundary = format!("----ServoFormBoundary{}", Uuid::new_v4().to_simple_string());
+                (form_data.root().encode_multipart(boundary.as_slice()),
+                 media_type("multipart", "form-data", "boundary", boundary))
+            },
         }
     }
 }
+
+fn media_type(type_: &str, subtype: &str, parameter: &str, value: String) -> MediaType {
+    MediaType {
+        type_: String::from_str(type_),
+        subtype: String::from_str(subtype),
+        parameters: vec!((String::from_str(parameter), value))
+    }
+}
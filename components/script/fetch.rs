@@ -0,0 +1,243 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The fetch() method of the Fetch API, which loads a request on a task of
+//! its own and resolves the promise it returns with the response.
+//! http://fetch.spec.whatwg.org/#fetch-method
+
+use dom::bindings::codegen::Bindings::RequestBinding::{RequestInit, RequestMethods};
+use dom::bindings::codegen::Bindings::RequestBinding::RequestModeValues::Same_origin;
+use dom::bindings::codegen::Bindings::ResponseBinding::{ResponseType, ResponseTypeValues};
+use dom::bindings::codegen::UnionTypes::RequestOrString::RequestOrString;
+use dom::bindings::error::{Error, Fallible, Type};
+use dom::bindings::global::GlobalRef;
+use dom::bindings::global;
+use dom::bindings::js::{JSRef, Temporary};
+use dom::bindings::str::ByteString;
+use dom::bindings::utils::Reflectable;
+use dom::body::PendingBody;
+use dom::headers::{Headers, HeadersHelpers, ImmutableGuard};
+use dom::promise::{Promise, PromiseHelpers, PromiseSettler, TrustedPromise};
+use dom::request::{Request, RequestHelpers};
+use dom::response::Response;
+use dom::window::WindowHelpers;
+use cors::{allow_cross_origin_request, CORSRequest, CORSMode};
+
+use http::headers::{HeaderEnum, HeaderValueByteIterator};
+use http::headers::request::Header;
+use js::jsval::ObjectValue;
+use servo_net::resource_task::{ResourceTask, ResourceCORSData, Load, LoadData, Metadata};
+use servo_net::resource_task::{Payload, Done};
+use servo_util::task::spawn_named;
+
+use std::ascii::StrAsciiExt;
+use std::comm::{channel, Disconnected, Receiver, Select, Sender};
+use std::io::BufReader;
+use std::path::BytesContainer;
+
+/// What a fetch rejects with when it fails.
+fn network_error() -> Error {
+    Type("Network error".to_string())
+}
+
+// http://fetch.spec.whatwg.org/#dom-global-fetch
+pub fn fetch(global: &GlobalRef, input: RequestOrString,
+             init: &RequestInit) -> Fallible<Temporary<Promise>> {
+    let request = try!(Request::Constructor(global, input, init)).root();
+    let mut load_data = request.load_data();
+
+    // Like XMLHttpRequest, a request to another origin is a CORS request,
+    // which can only be made over HTTP.
+    let origin = global.get_origin();
+    let cors_request = match CORSRequest::maybe_new(global.get_url(), &origin,
+                                                    load_data.url.clone(), CORSMode,
+                                                    load_data.method.clone(),
+                                                    load_data.headers.clone()) {
+        Ok(cors_request) => cors_request,
+        Err(()) => return Err(network_error()),
+    };
+    let type_ = match cors_request {
+        Some(_) if request.Mode() == Same_origin => return Err(network_error()),
+        Some(_) => {
+            let value = origin.serialize().into_bytes();
+            let mut reader = BufReader::new(value.as_slice());
+            let header: Option<Header> =
+                HeaderEnum::value_from_stream("origin".to_string(),
+                                              &mut HeaderValueByteIterator::new(&mut reader));
+            load_data.headers.insert(header.unwrap());
+            ResponseTypeValues::Cors
+        },
+        None => ResponseTypeValues::Basic,
+    };
+
+    let promise = Promise::new(global).root();
+    let trusted = promise.trusted();
+    let resource_task = global.resource_task();
+    let (abort_chan, abort_port) = channel();
+    let (done_chan, done_port) = channel();
+    match *global {
+        global::Window(window) => window.track_fetch(FetchCanceller {
+            abort: abort_chan,
+            done: done_port,
+        }),
+        global::Worker(_) => {},
+    }
+    spawn_named("Fetch", proc() {
+        // Dropped once the fetch is over, which the window notices.
+        let _done: Sender<()> = done_chan;
+        run_fetch(resource_task, load_data, cors_request, type_, abort_port, trusted);
+    });
+    Ok(Temporary::from_rooted(*promise))
+}
+
+/// Stops a fetch that fetch() started in a window, when the document of the
+/// window is unloaded.
+pub struct FetchCanceller {
+    abort: Sender<()>,
+    /// Disconnected once the fetch is over.
+    done: Receiver<()>,
+}
+
+impl FetchCanceller {
+    /// Whether the fetch is over, so that there is nothing left to stop.
+    pub fn is_done(&self) -> bool {
+        match self.done.try_recv() {
+            Err(Disconnected) => true,
+            _ => false,
+        }
+    }
+
+    /// Stops the fetch. If its promise wasn't settled yet, it never is, and
+    /// the body of a response that arrived already ends with an error.
+    pub fn cancel(&self) {
+        let _ = self.abort.send_opt(());
+    }
+}
+
+/// Waits for what `port` delivers next, unless the fetch is aborted first.
+fn recv_or_abort<T: Send>(port: &Receiver<T>, abort_port: &Receiver<()>) -> Option<T> {
+    let select = Select::new();
+    let mut port_handle = select.handle(port);
+    unsafe { port_handle.add() };
+    let mut abort_handle = select.handle(abort_port);
+    unsafe { abort_handle.add() };
+    select.wait();
+    match abort_port.try_recv() {
+        Ok(()) => None,
+        // Nothing aborted the fetch, or nothing can any more.
+        Err(_) => Some(port.recv()),
+    }
+}
+
+/// Loads `load_data`, resolves `promise` once the headers of the response
+/// arrived, and goes on to receive its body.
+fn run_fetch(resource_task: ResourceTask, mut load_data: LoadData,
+             cors_request: Option<CORSRequest>, type_: ResponseType,
+             abort_port: Receiver<()>, promise: TrustedPromise) {
+    match cors_request {
+        Some(ref req) => {
+            if req.http_fetch().network_error {
+                return promise.reject(network_error());
+            }
+            load_data.cors = Some(ResourceCORSData {
+                preflight: req.preflight_flag,
+                origin: req.origin.clone()
+            });
+        },
+        None => {}
+    }
+
+    let (start_chan, start_port) = channel();
+    resource_task.send(Load(load_data, start_chan));
+    let response = match recv_or_abort(&start_port, &abort_port) {
+        Some(response) => response,
+        None => return,
+    };
+    match cors_request {
+        Some(ref req) => {
+            match response.metadata.headers {
+                Some(ref h) if allow_cross_origin_request(req, h) => {},
+                _ => return promise.reject(network_error()),
+            }
+        },
+        None => {}
+    }
+
+    // The loaders report a resource they can't get with an error in place
+    // of its body rather than in its metadata, so the fetch has only
+    // succeeded once the first part of the body arrived.
+    let mut progress = match recv_or_abort(&response.progress_port, &abort_port) {
+        Some(progress) => progress,
+        None => return,
+    };
+    match progress {
+        Done(Err(_)) => return promise.reject(network_error()),
+        _ => {}
+    }
+
+    let (body_chan, body_port) = channel();
+    promise.settle_with(box FetchedResponse {
+        type_: type_,
+        metadata: response.metadata,
+        body: body_port,
+    });
+
+    let mut body = vec!();
+    loop {
+        match progress {
+            Payload(data) => body.push_all(data.as_slice()),
+            Done(Ok(())) => break,
+            Done(Err(_)) => {
+                let _ = body_chan.send_opt(Err(()));
+                return;
+            }
+        }
+        progress = match recv_or_abort(&response.progress_port, &abort_port) {
+            Some(progress) => progress,
+            None => {
+                let _ = body_chan.send_opt(Err(()));
+                return;
+            }
+        };
+    }
+    let _ = body_chan.send_opt(Ok(body));
+}
+
+/// A response whose headers arrived, and whose body is still being received.
+struct FetchedResponse {
+    type_: ResponseType,
+    metadata: Metadata,
+    body: Receiver<Result<Vec<u8>, ()>>,
+}
+
+impl PromiseSettler for FetchedResponse {
+    fn settle(self: Box<Self>, global: &GlobalRef, promise: JSRef<Promise>) {
+        let FetchedResponse { type_, metadata, body } = *self;
+
+        // http://fetch.spec.whatwg.org/#concept-filtered-response-basic
+        let headers = Headers::new(global, ImmutableGuard).root();
+        match metadata.headers {
+            Some(ref list) => {
+                for header in list.iter() {
+                    let name = header.header_name();
+                    match name.as_slice().to_ascii_lower().as_slice() {
+                        "set-cookie" | "set-cookie2" => continue,
+                        _ => {}
+                    }
+                    // rust-http decodes the value as Latin-1.
+                    let value = header.header_value().as_slice().chars().map(|c| c as u8).collect();
+                    headers.append(from_str(name.as_slice()).unwrap(), ByteString::new(value));
+                }
+            },
+            None => {}
+        }
+
+        let status = metadata.status;
+        let response = Response::new(global, type_, Some(metadata.final_url), status.code(),
+                                     ByteString::new(status.reason().container_into_owned_bytes()),
+                                     *headers, PendingBody(body)).root();
+        promise.resolve_native(global.get_cx(),
+                               ObjectValue(&*response.reflector().get_jsobject()));
+    }
+}
@@ -0,0 +1,69 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Parses stylesheets on a task of their own, so that big ones don't hold up
+//! the script task.
+
+use script_task::{ScriptChan, StylesheetParsedMsg};
+use task_source::NetworkingTaskSource;
+
+use encoding::EncodingRef;
+use encoding::all::UTF_8;
+use native::task::NativeTaskBuilder;
+use servo_msg::constellation_msg::PipelineId;
+use servo_net::resource_task::{ResourceTask, load_bytes_iter};
+use std::comm::{channel, Receiver};
+use std::task::TaskBuilder;
+use style::Stylesheet;
+use url::Url;
+
+/// Where the text of a stylesheet comes from.
+pub enum StylesheetProvenance {
+    /// The resource at a URL, which `<link>` elements point at.
+    UrlProvenance(Url, ResourceTask),
+    /// The contents of a `<style>` element, along with the URL of its
+    /// document.
+    InlineProvenance(Url, String),
+}
+
+/// Returns a port that already holds the stylesheet, for the ones that are
+/// small enough to be parsed right away but have to wait for those before
+/// them in the document all the same.
+pub fn parsed_stylesheet(sheet: Stylesheet) -> Receiver<Stylesheet> {
+    let (chan, port) = channel();
+    chan.send(sheet);
+    port
+}
+
+/// Loads and parses the stylesheet that `provenance` describes on a native
+/// task, which sends it on the returned port and tells the script task of
+/// pipeline `id` through `script_chan` once it's done. If the task fails,
+/// the port is closed without a stylesheet.
+pub fn spawn_css_parser(provenance: StylesheetProvenance, script_chan: ScriptChan,
+                        id: PipelineId) -> Receiver<Stylesheet> {
+    let (sheet_chan, sheet_port) = channel();
+    TaskBuilder::new()
+        .native()
+        .named("CSS parser")
+        .spawn(proc() {
+        let sheet = match provenance {
+            UrlProvenance(url, resource_task) => {
+                debug!("cssparse: loading stylesheet at {:s}", url.serialize());
+                // TODO: Get the actual value. http://dev.w3.org/csswg/css-syntax/#environment-encoding
+                let environment_encoding = UTF_8 as EncodingRef;
+                let (metadata, iter) = load_bytes_iter(&resource_task, url);
+                let protocol_encoding_label = metadata.charset.as_ref().map(|s| s.as_slice());
+                let final_url = metadata.final_url;
+                Stylesheet::from_bytes_iter(iter, final_url, protocol_encoding_label,
+                                            Some(environment_encoding))
+            },
+            InlineProvenance(base_url, data) => {
+                Stylesheet::from_str(data.as_slice(), base_url)
+            },
+        };
+        let _ = sheet_chan.send_opt(sheet);
+        script_chan.queue_task(NetworkingTaskSource, StylesheetParsedMsg(id));
+    });
+    sheet_port
+}
@@ -60,7 +60,8 @@ pub type JSResult = Vec<JSFile>;
 
 pub enum HTMLInput {
     InputString(String),
-    InputUrl(Url),
+    /// A URL to fetch, and the referrer to send along.
+    InputUrl(Url, Option<Url>),
 }
 
 enum JSMessage {
@@ -173,7 +174,7 @@ pub fn build_element_from_tag(tag: DOMString, ns: Namespace, document: JSRef<Doc
     handle_element!(document, tag, "bdo",       HTMLElement);
     handle_element!(document, tag, "bgsound",   HTMLElement);
     handle_element!(document, tag, "big",       HTMLElement);
-    handle_element!(document, tag, "blockquote",HTMLElement);
+    handle_element!(document, tag, "blockquote",HTMLQuoteElement);
     handle_element!(document, tag, "body",      HTMLBodyElement);
     handle_element!(document, tag, "br",        HTMLBRElement);
     handle_element!(document, tag, "button",    HTMLButtonElement);
@@ -188,8 +189,9 @@ pub fn build_element_from_tag(tag: DOMString, ns: Namespace, document: JSRef<Doc
     handle_element!(document, tag, "datalist",  HTMLDataListElement);
     handle_element!(document, tag, "dd",        HTMLElement);
     handle_element!(document, tag, "del",       HTMLModElement);
-    handle_element!(document, tag, "details",   HTMLElement);
+    handle_element!(document, tag, "details",   HTMLDetailsElement);
     handle_element!(document, tag, "dfn",       HTMLElement);
+    handle_element!(document, tag, "dialog",    HTMLDialogElement);
     handle_element!(document, tag, "dir",       HTMLDirectoryElement);
     handle_element!(document, tag, "div",       HTMLDivElement);
     handle_element!(document, tag, "dl",        HTMLDListElement);
@@ -243,6 +245,7 @@ pub fn build_element_from_tag(tag: DOMString, ns: Namespace, document: JSRef<Doc
     handle_element!(document, tag, "output",    HTMLOutputElement);
     handle_element!(document, tag, "p",         HTMLParagraphElement);
     handle_element!(document, tag, "param",     HTMLParamElement);
+    handle_element!(document, tag, "picture",   HTMLPictureElement);
     handle_element!(document, tag, "pre",       HTMLPreElement);
     handle_element!(document, tag, "progress",  HTMLProgressElement);
     handle_element!(document, tag, "q",         HTMLQuoteElement);
@@ -301,10 +304,12 @@ pub fn parse_html(page: &Page,
     });
 
     let (base_url, load_response) = match input {
-        InputUrl(ref url) => {
+        InputUrl(ref url, ref referrer) => {
             // Wait for the LoadResponse so that the parser knows the final URL.
             let (input_chan, input_port) = channel();
-            resource_task.send(Load(LoadData::new(url.clone()), input_chan));
+            let mut load_data = LoadData::new(url.clone());
+            load_data.headers.referer = referrer.as_ref().map(|referrer| referrer.serialize());
+            resource_task.send(Load(load_data, input_chan));
             let load_response = input_port.recv();
 
             debug!("Fetched page; metadata is {:?}", load_response.metadata);
@@ -332,6 +337,11 @@ pub fn parse_html(page: &Page,
 
             (Some(base_url), Some(load_response))
         },
+        // A document without a browsing context, such as an XMLHttpRequest
+        // response, resolves against its own URL rather than the page's.
+        InputString(_) if !document.has_browsing_context() => {
+            (Some(document.url().clone()), None)
+        },
         InputString(_) => {
             match *page.url() {
                 Some((ref page_url, _)) => (Some(page_url.clone()), None),
@@ -515,7 +525,7 @@ pub fn parse_html(page: &Page,
         InputString(s) => {
             parser.parse_chunk(s.into_bytes().as_slice());
         },
-        InputUrl(url) => {
+        InputUrl(url, _) => {
             let load_response = load_response.unwrap();
             match load_response.metadata.content_type {
                 Some((ref t, _)) if t.as_slice().eq_ignore_ascii_case("image") => {
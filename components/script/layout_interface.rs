@@ -29,9 +29,6 @@ pub enum Msg {
     /// Adds the given stylesheet to the document.
     AddStylesheetMsg(Stylesheet),
 
-    /// Adds the given stylesheet to the document.
-    LoadStylesheetMsg(Url),
-
     /// Requests a reflow.
     ReflowMsg(Box<Reflow>),
 
@@ -69,6 +66,8 @@ pub trait LayoutRPC {
     /// Requests the node containing the point of interest
     fn hit_test(&self, node: TrustedNodeAddress, point: Point2D<f32>) -> Result<HitTestResponse, ()>;
     fn mouse_over(&self, node: TrustedNodeAddress, point: Point2D<f32>) -> Result<MouseOverResponse, ()>;
+    /// Requests the memory taken up by the stylesheets of the document.
+    fn stylesheet_heap_size(&self) -> uint;
 }
 
 /// The address of a node known to be valid. These must only be sent from content -> layout,
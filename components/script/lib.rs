@@ -24,6 +24,8 @@ extern crate hubbub;
 extern crate encoding;
 extern crate http;
 extern crate js;
+#[phase(plugin)]
+extern crate lazy_static;
 extern crate libc;
 extern crate msg;
 extern crate native;
@@ -44,6 +46,7 @@ extern crate url;
 extern crate uuid;
 
 pub mod cors;
+pub mod fetch;
 
 /// The implementation of the DOM.
 #[macro_escape]
@@ -59,8 +62,10 @@ pub mod dom {
         pub mod callback;
         pub mod error;
         pub mod conversions;
+        pub mod iterable;
         mod proxyhandler;
         pub mod str;
+        pub mod structuredclone;
         pub mod trace;
 
         /// Generated JS-Rust bindings.
@@ -79,10 +84,13 @@ pub mod dom {
     pub mod types;
 
     pub mod attr;
+    pub mod beforeunloadevent;
     pub mod blob;
+    pub mod body;
     pub mod browsercontext;
     pub mod canvasrenderingcontext2d;
     pub mod characterdata;
+    pub mod closeevent;
     pub mod domrect;
     pub mod domrectlist;
     pub mod comment;
@@ -97,11 +105,15 @@ pub mod dom {
     pub mod domparser;
     pub mod domtokenlist;
     pub mod element;
+    pub mod errorevent;
     pub mod event;
     pub mod eventdispatcher;
+    pub mod eventsource;
     pub mod eventtarget;
     pub mod file;
+    pub mod filereader;
     pub mod formdata;
+    pub mod headers;
     pub mod htmlanchorelement;
     pub mod htmlappletelement;
     pub mod htmlareaelement;
@@ -114,6 +126,8 @@ pub mod dom {
     pub mod htmlcollection;
     pub mod htmldataelement;
     pub mod htmldatalistelement;
+    pub mod htmldetailselement;
+    pub mod htmldialogelement;
     pub mod htmldirectoryelement;
     pub mod htmldivelement;
     pub mod htmldlistelement;
@@ -121,6 +135,7 @@ pub mod dom {
     pub mod htmlembedelement;
     pub mod htmlfieldsetelement;
     pub mod htmlfontelement;
+    pub mod htmlformcontrolscollection;
     pub mod htmlformelement;
     pub mod htmlframeelement;
     pub mod htmlframesetelement;
@@ -147,6 +162,7 @@ pub mod dom {
     pub mod htmloutputelement;
     pub mod htmlparagraphelement;
     pub mod htmlparamelement;
+    pub mod htmlpictureelement;
     pub mod htmlpreelement;
     pub mod htmlprogresselement;
     pub mod htmlquoteelement;
@@ -172,8 +188,13 @@ pub mod dom {
     pub mod htmlulistelement;
     pub mod htmlvideoelement;
     pub mod htmlunknownelement;
+    pub mod keyboardevent;
     pub mod location;
+    pub mod mediaerror;
+    pub mod messagechannel;
     pub mod messageevent;
+    pub mod messageport;
+    pub mod messageportlist;
     pub mod mouseevent;
     pub mod namednodemap;
     pub mod navigator;
@@ -181,19 +202,28 @@ pub mod dom {
     pub mod node;
     pub mod nodeiterator;
     pub mod nodelist;
+    pub mod pagetransitionevent;
     pub mod processinginstruction;
     pub mod performance;
     pub mod performancetiming;
     pub mod progressevent;
+    pub mod promise;
+    pub mod radionodelist;
     pub mod range;
+    pub mod request;
+    pub mod response;
     pub mod screen;
+    pub mod sharedworker;
+    pub mod sharedworkerglobalscope;
     pub mod text;
     pub mod treewalker;
     pub mod uievent;
+    pub mod url;
     pub mod urlhelper;
     pub mod urlsearchparams;
     pub mod validitystate;
     pub mod virtualmethods;
+    pub mod websocket;
     pub mod window;
     pub mod worker;
     pub mod workerglobalscope;
@@ -208,9 +238,15 @@ pub mod dom {
 
 /// Parsers for HTML and CSS.
 pub mod html {
+    pub mod cssparse;
     pub mod hubbub_html_parser;
 }
 
 pub mod layout_interface;
+pub mod microtask;
 pub mod page;
 pub mod script_task;
+pub mod security;
+pub mod task_source;
+pub mod timers;
+pub mod textinput;
@@ -0,0 +1,43 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The microtask queue of a script task or worker, which its event loop
+//! empties every time it is done with a message.
+//! http://www.whatwg.org/html/#microtask-queue
+
+use dom::promise::{Promise, TrustedPromiseAddress};
+
+use collections::{Deque, RingBuf};
+use std::cell::RefCell;
+
+/// What can be queued on the microtask queue.
+pub enum Microtask {
+    /// Runs the next job the promise queued.
+    PromiseJobMicrotask(TrustedPromiseAddress),
+}
+
+local_data_key!(Microtasks: RefCell<RingBuf<Microtask>>)
+
+pub fn enqueue_microtask(microtask: Microtask) {
+    if Microtasks.get().is_none() {
+        Microtasks.replace(Some(RefCell::new(RingBuf::new())));
+    }
+    Microtasks.get().unwrap().borrow_mut().push_back(microtask);
+}
+
+/// Runs the microtasks until none are left, including those they queue.
+/// http://www.whatwg.org/html/#perform-a-microtask-checkpoint
+pub fn perform_microtask_checkpoint() {
+    loop {
+        // The queue can't stay borrowed, as microtasks queue more of them.
+        let microtask = match Microtasks.get() {
+            Some(microtasks) => microtasks.borrow_mut().pop_front(),
+            None => None,
+        };
+        match microtask {
+            Some(PromiseJobMicrotask(addr)) => Promise::run_job(addr),
+            None => break,
+        }
+    }
+}
@@ -13,13 +13,15 @@ use dom::document::{Document, DocumentHelpers};
 use dom::element::{Element, AttributeHandlers};
 use dom::node::{Node, NodeHelpers};
 use dom::window::Window;
-use layout_interface::{DocumentDamage, ReflowForDisplay};
+use layout_interface::{AddStylesheetMsg, DocumentDamage, MatchSelectorsDocumentDamage};
+use layout_interface::ReflowForDisplay;
 use layout_interface::{DocumentDamageLevel, HitTestResponse, MouseOverResponse};
 use layout_interface::{GetRPCMsg, LayoutChan, LayoutRPC};
 use layout_interface::{Reflow, ReflowGoal, ReflowMsg};
 use layout_interface::UntrustedNodeAddress;
 use script_traits::ScriptControlChan;
 
+use collections::{Deque, RingBuf};
 use geom::point::Point2D;
 use js::rust::Cx;
 use servo_msg::compositor_msg::PerformingLayout;
@@ -27,12 +29,14 @@ use servo_msg::compositor_msg::ScriptListener;
 use servo_msg::constellation_msg::{ConstellationChan, WindowSizeData};
 use servo_msg::constellation_msg::{PipelineId, SubpageId};
 use servo_net::resource_task::ResourceTask;
+use servo_util::memory::MemoryReport;
 use servo_util::namespace::Null;
 use servo_util::str::DOMString;
 use std::cell::{Cell, RefCell, Ref, RefMut};
 use std::comm::{channel, Receiver, Empty, Disconnected};
 use std::mem::replace;
 use std::rc::Rc;
+use style::Stylesheet;
 use url::Url;
 
 /// Encapsulates a handle to a frame and its associated layout information.
@@ -95,6 +99,15 @@ pub struct Page {
 
     /// Number of unnecessary potential reflows that were skipped since the last reflow
     pub avoided_reflows: Cell<int>,
+
+    /// The stylesheets of the document that layout doesn't have yet, in the
+    /// order they have to be handed to it in. Each of them is still being
+    /// parsed, or waits for one before it.
+    pending_stylesheets: Untraceable<RefCell<RingBuf<Receiver<Stylesheet>>>>,
+
+    /// Whether a task for the page panicked. A crashed page handles no more
+    /// tasks, and waits for the constellation to tell it to exit.
+    pub crashed: Cell<bool>,
 }
 
 pub struct PageIterator {
@@ -104,6 +117,7 @@ pub struct PageIterator {
 pub trait IterablePage {
     fn iter(&self) -> PageIterator;
     fn find(&self, id: PipelineId) -> Option<Rc<Page>>;
+    fn find_parent(&self, id: PipelineId) -> Option<Rc<Page>>;
 }
 
 impl IterablePage for Rc<Page> {
@@ -120,6 +134,14 @@ impl IterablePage for Rc<Page> {
         }
         None
     }
+    fn find_parent(&self, id: PipelineId) -> Option<Rc<Page>> {
+        for page in self.children.deref().borrow().iter() {
+            if page.id == id { return Some(self.clone()); }
+            let found = page.find_parent(id);
+            if found.is_some() { return found; }
+        }
+        None
+    }
 
 }
 
@@ -160,12 +182,21 @@ impl Page {
             children: Traceable::new(RefCell::new(vec!())),
             pending_reflows: Cell::new(0),
             avoided_reflows: Cell::new(0),
+            pending_stylesheets: Untraceable::new(RefCell::new(RingBuf::new())),
+            crashed: Cell::new(false),
         }
     }
 
+    /// Whether the document was damaged since the last reflow was sent to
+    /// layout.
+    pub fn has_pending_reflow(&self) -> bool {
+        self.damage.borrow().is_some()
+    }
+
+    /// Sends the damage that the document accumulated to layout, if any. This
+    /// doesn't wait for layout to be done with it.
     pub fn flush_layout(&self, goal: ReflowGoal) {
-        let damaged = self.damage.borrow().is_some();
-        if damaged {
+        if self.has_pending_reflow() {
             let frame = self.frame();
             let window = frame.as_ref().unwrap().window.root();
             self.reflow(goal, window.control_chan.clone(), &**window.compositor);
@@ -174,7 +205,78 @@ impl Page {
         }
     }
 
+    /// Queues a stylesheet of the document, which `sheet` receives once it's
+    /// parsed, after those that were queued before it.
+    pub fn add_stylesheet(&self, sheet: Receiver<Stylesheet>) {
+        self.pending_stylesheets.deref().borrow_mut().push_back(sheet);
+        self.register_parsed_stylesheets();
+    }
+
+    /// The number of stylesheets that layout doesn't have yet.
+    pub fn pending_stylesheet_count(&self) -> uint {
+        self.pending_stylesheets.deref().borrow().len()
+    }
+
+    /// Forgets the stylesheets that are still being parsed, which belong to a
+    /// document that was replaced.
+    pub fn clear_pending_stylesheets(&self) {
+        self.pending_stylesheets.deref().borrow_mut().clear();
+    }
+
+    /// Hands layout the stylesheets that were parsed, up to the first one
+    /// that is still being parsed, so that they take effect in document
+    /// order however fast each of them is parsed.
+    pub fn register_parsed_stylesheets(&self) {
+        loop {
+            let sheet = match self.pending_stylesheets.deref().borrow().front() {
+                None => return,
+                Some(port) => port.try_recv(),
+            };
+            match sheet {
+                Ok(sheet) => self.send_stylesheet(sheet),
+                Err(Empty) => return,
+                Err(Disconnected) => debug!("script: a stylesheet failed to parse"),
+            }
+            self.pending_stylesheets.deref().borrow_mut().pop_front();
+        }
+    }
+
+    /// Waits for every stylesheet of the document to be parsed and hands
+    /// them to layout.
+    pub fn wait_for_stylesheets(&self) {
+        loop {
+            let port = match self.pending_stylesheets.deref().borrow_mut().pop_front() {
+                None => return,
+                Some(port) => port,
+            };
+            match port.recv_opt() {
+                Ok(sheet) => self.send_stylesheet(sheet),
+                Err(()) => debug!("script: a stylesheet failed to parse"),
+            }
+        }
+    }
+
+    fn send_stylesheet(&self, sheet: Stylesheet) {
+        let LayoutChan(ref chan) = *self.layout_chan;
+        chan.send(AddStylesheetMsg(sheet));
+        self.damage(MatchSelectorsDocumentDamage);
+    }
+
+    /// What the document of this page and its layout use memory for.
+    pub fn memory_report(&self) -> MemoryReport {
+        let mut report = match *self.frame() {
+            None => MemoryReport::new(),
+            Some(ref frame) => frame.document.root().memory_report(),
+        };
+        report.stylesheets += self.layout_rpc.stylesheet_heap_size();
+        report
+    }
+
     pub fn layout(&self) -> &LayoutRPC {
+        // The answer has to take every stylesheet of the document into account, even those that
+        // are still being parsed.
+        self.wait_for_stylesheets();
+
         // FIXME This should probably be ReflowForQuery, not Display. All queries currently
         // currently rely on the display list, which means we can't destroy it by
         // doing a query reflow.
@@ -8,25 +8,41 @@
 use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
 use dom::bindings::codegen::Bindings::DOMRectBinding::DOMRectMethods;
 use dom::bindings::codegen::Bindings::ElementBinding::ElementMethods;
+use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use dom::bindings::codegen::InheritTypes::{EventTargetCast, NodeCast, EventCast, ElementCast};
+use dom::bindings::codegen::InheritTypes::{HTMLElementCast, HTMLIFrameElementCast};
 use dom::bindings::conversions;
 use dom::bindings::conversions::{FromJSValConvertible, Empty};
 use dom::bindings::global;
 use dom::bindings::js::{JS, JSRef, RootCollection, Temporary, OptionalSettable};
-use dom::bindings::js::OptionalRootable;
+use dom::bindings::js::{OptionalRootable, trace_rooted_collections};
+use dom::bindings::structuredclone::StructuredCloneData;
 use dom::bindings::trace::JSTraceable;
 use dom::bindings::utils::Reflectable;
-use dom::bindings::utils::{wrap_for_same_compartment, pre_wrap};
+use dom::bindings::utils::{wrap_for_same_compartment, pre_wrap, take_panicked_pipelines};
 use dom::document::{Document, HTMLDocument, DocumentHelpers};
-use dom::element::{Element, HTMLButtonElementTypeId, HTMLInputElementTypeId};
+use dom::element::{Element, ElementHelpers, InActiveState};
+use dom::element::{HTMLButtonElementTypeId, HTMLInputElementTypeId};
 use dom::element::{HTMLSelectElementTypeId, HTMLTextAreaElementTypeId, HTMLOptionElementTypeId};
 use dom::event::Event;
+use dom::htmlelement::HTMLElementHelpers;
+use dom::keyboardevent::KeyboardEvent;
+use dom::messageevent::MessageEvent;
+use dom::messageport::{MessagePort, PortEndpoint, TrustedMessagePortAddress};
+use dom::mouseevent::MouseEvent;
 use dom::uievent::UIEvent;
+use dom::eventsource::{EventSource, TrustedEventSourceAddress};
 use dom::eventtarget::{EventTarget, EventTargetHelpers};
+use dom::filereader::{FileReader, FileReaderProgress, TrustedFileReaderAddress};
+use dom::htmliframeelement::{HTMLIFrameElement, HTMLIFrameElementHelpers};
+use dom::htmlserializer::serialize;
 use dom::node;
-use dom::node::{ElementNodeTypeId, Node, NodeHelpers};
-use dom::window::{TimerId, Window, WindowHelpers};
-use dom::worker::{Worker, TrustedWorkerAddress};
+use dom::node::{ElementNodeTypeId, Node, NodeHelpers, NodeIterator};
+use dom::promise::{Promise, PromiseSettlement, TrustedPromiseAddress};
+use dom::websocket::{WebSocket, TrustedWebSocketAddress};
+use dom::window::{Window, WindowHelpers};
+use dom::worker::{Worker, TrustedWorkerAddress, UncaughtError};
 use dom::xmlhttprequest::{TrustedXHRAddress, XMLHttpRequest, XHRProgress};
 use html::hubbub_html_parser::{InputString, InputUrl, HtmlParserResult, HtmlDiscoveredScript};
 use html::hubbub_html_parser;
@@ -34,41 +50,57 @@ use layout_interface::{ScriptLayoutChan, LayoutChan, MatchSelectorsDocumentDamag
 use layout_interface::{ReflowDocumentDamage, ReflowForDisplay};
 use layout_interface::ContentChangedDocumentDamage;
 use layout_interface;
+use microtask::perform_microtask_checkpoint;
 use page::{Page, IterablePage, Frame};
+use security::Origin;
+use task_source::{TaskSource, TaskQueues, UserInteractionTaskSource};
+use timers::{TimerId, TimerSource, FromWindow, FromWorker};
+use timers::{TimerSchedulerChan, spawn_timer_scheduler};
 
 use devtools_traits;
 use devtools_traits::{DevtoolsControlChan, DevtoolsControlPort, NewGlobal, NodeInfo, GetRootNode};
 use devtools_traits::{DevtoolScriptControlMsg, EvaluateJS, EvaluateJSReply, GetDocumentElement};
-use devtools_traits::{GetChildren, GetLayout};
+use devtools_traits::{GetChildren, GetLayout, GetOuterHTML};
 use script_traits::{CompositorEvent, ResizeEvent, ReflowEvent, ClickEvent, MouseDownEvent};
 use script_traits::{MouseMoveEvent, MouseUpEvent, ConstellationControlMsg, ScriptTaskFactory};
 use script_traits::{ResizeMsg, AttachLayoutMsg, LoadMsg, SendEventMsg, ResizeInactiveMsg};
 use script_traits::{ExitPipelineMsg, NewLayoutInfo, OpaqueScriptLayoutChannel, ScriptControlChan};
-use script_traits::ReflowCompleteMsg;
+use script_traits::{ReflowCompleteMsg, KeyEvent, Key, CharacterKey, BackspaceKey, TabKey};
+use script_traits::{EnterKey, EscapeKey, UnloadMsg, SubframeLoadedMsg};
+use script_traits::CollectMemoryReportMsg;
 use servo_msg::compositor_msg::{FinishedLoading, LayerId, Loading};
 use servo_msg::compositor_msg::{ScriptListener};
-use servo_msg::constellation_msg::{ConstellationChan, LoadCompleteMsg, LoadUrlMsg, NavigationDirection};
-use servo_msg::constellation_msg::{PipelineId, Failure, FailureMsg, WindowSizeData};
+use servo_msg::constellation_msg::{ConstellationChan, CrashMsg, LoadCompleteMsg, LoadData, LoadUrlMsg};
+use servo_msg::constellation_msg::NavigationDirection;
+use servo_msg::constellation_msg::{PipelineId, SubpageId, Failure, FailureMsg, WindowSizeData};
 use servo_msg::constellation_msg;
+use servo_net::event_source_task::EventSourceEvent;
 use servo_net::image_cache_task::ImageCacheTask;
 use servo_net::resource_task::ResourceTask;
+use servo_net::websocket_task::WebSocketEvent;
 use servo_util::geometry::to_frac_px;
+use servo_util::memory::MemoryReport;
+use servo_util::opts;
+use servo_util::str::DOMString;
 use servo_util::task::spawn_named_with_send_on_failure;
 
 use geom::point::Point2D;
 use js::jsapi::{JS_SetWrapObjectCallbacks, JS_SetGCZeal, JS_DEFAULT_ZEAL_FREQ, JS_GC};
 use js::jsapi::{JSContext, JSRuntime, JSTracer};
-use js::jsapi::{JS_SetGCParameter, JSGC_MAX_BYTES};
+use js::jsapi::{JS_SetGCParameter, JSGC_MAX_BYTES, JS_SetExtraGCRootsTracer};
 use js::rust::{Cx, RtUtils};
 use js::rust::with_compartment;
 use js;
 use url::Url;
 
-use libc::size_t;
+use libc;
+use collections::{Deque, RingBuf};
+use rustrt::unwind;
 use std::any::{Any, AnyRefExt};
 use std::cell::RefCell;
 use std::comm::{channel, Sender, Receiver, Select};
 use std::mem::replace;
+use std::ptr;
 use std::rc::Rc;
 use std::u32;
 
@@ -82,38 +114,132 @@ pub enum ScriptMsg {
     TriggerFragmentMsg(PipelineId, Url),
     /// Begins a content-initiated load on the specified pipeline (only
     /// dispatched to ScriptTask).
-    TriggerLoadMsg(PipelineId, Url),
+    TriggerLoadMsg(PipelineId, LoadData),
     /// Instructs the script task to send a navigate message to
     /// the constellation (only dispatched to ScriptTask).
     NavigateMsg(NavigationDirection),
-    /// Fires a JavaScript timeout (only dispatched to ScriptTask).
-    FireTimerMsg(PipelineId, TimerId),
+    /// Fires a JavaScript timeout (dispatched to all tasks).
+    FireTimerMsg(TimerSource, TimerId),
+    /// Fires the events queued on the window of the specified pipeline (only
+    /// dispatched to ScriptTask).
+    FireQueuedEventsMsg(PipelineId),
+    /// Notifies the document of the specified pipeline that an image it
+    /// requested is done loading (only dispatched to ScriptTask).
+    ImageLoadedMsg(PipelineId, Url, bool),
     /// Notifies the script that a window associated with a particular pipeline
     /// should be closed (only dispatched to ScriptTask).
     ExitWindowMsg(PipelineId),
     /// Notifies the script of progress on a fetch (dispatched to all tasks).
     XHRProgressMsg(TrustedXHRAddress, XHRProgress),
-    /// Message sent through Worker.postMessage (only dispatched to
-    /// DedicatedWorkerGlobalScope).
-    DOMMessage(*mut u64, size_t),
-    /// Posts a message to the Worker object (dispatched to all tasks).
-    WorkerPostMessage(TrustedWorkerAddress, *mut u64, size_t),
+    /// Notifies a FileReader of the progress of a read (dispatched to all tasks).
+    FileReaderMsg(TrustedFileReaderAddress, FileReaderProgress),
+    /// Notifies a WebSocket of what happened on its connection (dispatched to
+    /// all tasks).
+    WebSocketMsg(TrustedWebSocketAddress, WebSocketEvent),
+    /// Notifies an EventSource of what happened on its connection (dispatched
+    /// to all tasks).
+    EventSourceMsg(TrustedEventSourceAddress, EventSourceEvent),
+    /// Message sent through Worker.postMessage, with the ports it transfers
+    /// (only dispatched to DedicatedWorkerGlobalScope).
+    DOMMessage(StructuredCloneData, Vec<PortEndpoint>),
+    /// Posts a message to the Worker object, with the ports it transfers
+    /// (dispatched to all tasks).
+    WorkerPostMessage(TrustedWorkerAddress, StructuredCloneData, Vec<PortEndpoint>),
     /// Releases one reference to the Worker object (dispatched to all tasks).
     WorkerRelease(TrustedWorkerAddress),
+    /// Reports an exception that the worker's script did not catch to the
+    /// Worker object (dispatched to all tasks).
+    WorkerUncaughtError(TrustedWorkerAddress, UncaughtError),
+    /// Wakes the event loop of a worker up so that it notices it was closed
+    /// (only dispatched to worker scopes).
+    CloseWorkerMsg,
+    /// Connects a new SharedWorker object to the shared worker through the
+    /// given end of a channel (only dispatched to SharedWorkerGlobalScope).
+    ConnectMsg(PortEndpoint),
+    /// Message sent through Window.postMessage to the window of the specified
+    /// pipeline, with the serialized origin of the script that sent it, the
+    /// origin its document must have if any, and the ports it transfers
+    /// (only dispatched to ScriptTask).
+    WindowPostMessage(PipelineId, StructuredCloneData, DOMString, Option<Origin>, Vec<PortEndpoint>),
+    /// Tells a started MessagePort that a message arrived for it, or that its
+    /// channel was closed (dispatched to all tasks).
+    MessagePortMsg(TrustedMessagePortAddress),
+    /// Releases the MessagePort object once it stopped receiving messages
+    /// (dispatched to all tasks).
+    MessagePortRelease(TrustedMessagePortAddress),
+    /// Settles a promise from another task as a TrustedPromise asked, and
+    /// releases it (dispatched to all tasks).
+    PromiseMsg(TrustedPromiseAddress, PromiseSettlement),
+    /// Notifies the page of the specified pipeline that a stylesheet it
+    /// queued is parsed (only dispatched to ScriptTask).
+    StylesheetParsedMsg(PipelineId),
+    /// Panics while handling a task for the specified pipeline, as a bug in
+    /// DOM code would. Only tests send it (only dispatched to ScriptTask).
+    PanicMsg(PipelineId),
+    /// Handles an input event for the specified pipeline as if the
+    /// compositor sent it. Only tests send it (only dispatched to ScriptTask).
+    SendTestEventMsg(PipelineId, CompositorEvent),
 }
 
-/// Encapsulates internal communication within the script task.
+/// Encapsulates internal communication within the script task. Every
+/// message is a task, sent along with the source that queued it.
 #[deriving(Clone)]
-pub struct ScriptChan(pub Sender<ScriptMsg>);
+pub struct ScriptChan(pub Sender<(TaskSource, ScriptMsg)>);
 
 untraceable!(ScriptChan)
 
 impl ScriptChan {
     /// Creates a new script chan.
-    pub fn new() -> (Receiver<ScriptMsg>, ScriptChan) {
+    pub fn new() -> (Receiver<(TaskSource, ScriptMsg)>, ScriptChan) {
         let (chan, port) = channel();
         (port, ScriptChan(chan))
     }
+
+    /// Queues `msg` as a task from `source` on the event loop at the other
+    /// end. Returns false if that event loop is gone.
+    pub fn queue_task(&self, source: TaskSource, msg: ScriptMsg) -> bool {
+        let ScriptChan(ref chan) = *self;
+        chan.send_opt((source, msg)).is_ok()
+    }
+}
+
+/// A message that the script task received on one of its ports.
+enum MixedMessage {
+    FromConstellation(ConstellationControlMsg),
+    FromScript(ScriptMsg),
+    FromDevtools(DevtoolScriptControlMsg),
+}
+
+impl MixedMessage {
+    /// The pipeline whose page the message is handled for, if it names one.
+    /// A panic while handling such a message only crashes that page.
+    fn pipeline_id(&self) -> Option<PipelineId> {
+        match *self {
+            FromConstellation(LoadMsg(id, _)) |
+            FromConstellation(SendEventMsg(id, _)) |
+            FromConstellation(ReflowCompleteMsg(id, _)) |
+            FromConstellation(CollectMemoryReportMsg(id, _)) |
+            FromConstellation(ResizeInactiveMsg(id, _)) |
+            FromConstellation(UnloadMsg(id)) |
+            FromConstellation(SubframeLoadedMsg(id, _)) |
+            FromScript(TriggerLoadMsg(id, _)) |
+            FromScript(TriggerFragmentMsg(id, _)) |
+            FromScript(FireTimerMsg(FromWindow(id), _)) |
+            FromScript(FireQueuedEventsMsg(id)) |
+            FromScript(ImageLoadedMsg(id, _, _)) |
+            FromScript(WindowPostMessage(id, _, _, _, _)) |
+            FromScript(StylesheetParsedMsg(id)) |
+            FromScript(PanicMsg(id)) |
+            FromScript(SendTestEventMsg(id, _)) |
+            FromDevtools(EvaluateJS(id, _, _)) |
+            FromDevtools(GetRootNode(id, _)) |
+            FromDevtools(GetDocumentElement(id, _)) |
+            FromDevtools(GetChildren(id, _, _)) |
+            FromDevtools(GetOuterHTML(id, _, _)) |
+            FromDevtools(GetLayout(id, _, _)) => Some(id),
+            _ => None,
+        }
+    }
 }
 
 pub struct StackRootTLS;
@@ -144,7 +270,7 @@ pub struct ScriptTask {
     resource_task: ResourceTask,
 
     /// The port on which the script task receives messages (load URL, exit, etc.)
-    port: Receiver<ScriptMsg>,
+    port: Receiver<(TaskSource, ScriptMsg)>,
     /// A channel to hand out to script task-based entities that need to be able to enqueue
     /// events in the event queue.
     chan: ScriptChan,
@@ -167,12 +293,24 @@ pub struct ScriptTask {
     /// no such server exists.
     devtools_port: DevtoolsControlPort,
 
+    /// The scheduler that fires the timers of every window of this task.
+    timer_scheduler: TimerSchedulerChan,
+
+    /// The messages from the constellation and devtools that aren't input
+    /// events, which are handled before any task in the order they came.
+    control_msgs: RefCell<RingBuf<MixedMessage>>,
+    /// The tasks that were received but didn't run yet, by source.
+    task_queues: RefCell<TaskQueues<MixedMessage>>,
+
     /// The JavaScript runtime.
     js_runtime: js::rust::rt,
     /// The JSContext.
     js_context: RefCell<Option<Rc<Cx>>>,
 
-    mouse_over_targets: RefCell<Option<Vec<JS<Node>>>>
+    mouse_over_targets: RefCell<Option<Vec<JS<Node>>>>,
+
+    /// The elements that are being activated by a pressed mouse button.
+    active_targets: RefCell<Vec<JS<Element>>>
 }
 
 /// In the event of task failure, all data on the stack runs its destructor. However, there
@@ -282,7 +420,7 @@ impl ScriptTask {
     pub fn new(id: PipelineId,
                compositor: Box<ScriptListener+'static>,
                layout_chan: LayoutChan,
-               port: Receiver<ScriptMsg>,
+               port: Receiver<(TaskSource, ScriptMsg)>,
                chan: ScriptChan,
                control_chan: ScriptControlChan,
                control_port: Receiver<ConstellationControlMsg>,
@@ -320,6 +458,8 @@ impl ScriptTask {
             chan.send(NewGlobal(id, devtools_sender.clone()));
         });
 
+        let timer_scheduler = spawn_timer_scheduler(chan.clone());
+
         Rc::new(ScriptTask {
             page: RefCell::new(Rc::new(page)),
 
@@ -334,10 +474,14 @@ impl ScriptTask {
             compositor: compositor,
             devtools_chan: devtools_chan,
             devtools_port: devtools_receiver,
+            timer_scheduler: timer_scheduler,
+            control_msgs: RefCell::new(RingBuf::new()),
+            task_queues: RefCell::new(TaskQueues::new()),
 
             js_runtime: js_runtime,
             js_context: RefCell::new(Some(js_context)),
-            mouse_over_targets: RefCell::new(None)
+            mouse_over_targets: RefCell::new(None),
+            active_targets: RefCell::new(vec!())
         })
     }
 
@@ -355,6 +499,7 @@ impl ScriptTask {
         // last-ditch GCs from within the GC's allocator.
         unsafe {
             JS_SetGCParameter(js_runtime.ptr, JSGC_MAX_BYTES, u32::MAX);
+            JS_SetExtraGCRootsTracer(js_runtime.ptr, Some(trace_rust_roots), ptr::null_mut());
         }
 
         let js_context = js_runtime.cx();
@@ -364,8 +509,11 @@ impl ScriptTask {
         });
         js_context.set_default_options_and_version();
         js_context.set_logging_error_reporter();
+        // With --gc-zeal, collect garbage as often as the engine allows, so
+        // that values that should have been rooted go away.
+        let zeal = if opts::gc_zeal_enabled() { 2 } else { 0 };
         unsafe {
-            JS_SetGCZeal((*js_context).ptr, 0, JS_DEFAULT_ZEAL_FREQ);
+            JS_SetGCZeal((*js_context).ptr, zeal, JS_DEFAULT_ZEAL_FREQ);
         }
 
         (js_runtime, js_context)
@@ -383,7 +531,9 @@ impl ScriptTask {
         }
     }
 
-    /// Handle incoming control messages.
+    /// Sorts the messages that arrived by where they come from, then handles
+    /// the one that should come next. Returns false once the script task
+    /// should exit.
     fn handle_msgs(&self) -> bool {
         let roots = RootCollection::new();
         let _stack_roots_tls = StackRootTLS::new(&roots);
@@ -412,17 +562,9 @@ impl ScriptTask {
             self.handle_event(id, ResizeEvent(size));
         }
 
-        enum MixedMessage {
-            FromConstellation(ConstellationControlMsg),
-            FromScript(ScriptMsg),
-            FromDevtools(DevtoolScriptControlMsg),
-        }
-
-        // Store new resizes, and gather all other events.
-        let mut sequential = vec!();
-
-        // Receive at least one message so we don't spinloop.
-        let mut event = {
+        // Receive at least one message so we don't spinloop, unless some
+        // are still waiting to be handled.
+        if self.control_msgs.borrow().is_empty() && self.task_queues.borrow().is_empty() {
             let sel = Select::new();
             let mut port1 = sel.handle(&self.port);
             let mut port2 = sel.handle(&self.control_port);
@@ -436,81 +578,222 @@ impl ScriptTask {
             }
             let ret = sel.wait();
             if ret == port1.id() {
-                FromScript(self.port.recv())
+                let (source, msg) = self.port.recv();
+                self.task_queues.borrow_mut().push(source, FromScript(msg));
             } else if ret == port2.id() {
-                FromConstellation(self.control_port.recv())
+                self.queue_control_msg(self.control_port.recv());
             } else if ret == port3.id() {
-                FromDevtools(self.devtools_port.recv())
+                self.control_msgs.borrow_mut().push_back(FromDevtools(self.devtools_port.recv()));
             } else {
                 fail!("unexpected select result")
             }
-        };
+        }
 
-        // Squash any pending resize events in the queue.
+        // Gather whatever else arrived in the meantime, so that input events
+        // get ahead of the tasks that were queued before them, and resizes
+        // are squashed.
         loop {
-            match event {
-                // This has to be handled before the ResizeMsg below,
-                // otherwise the page may not have been added to the
-                // child list yet, causing the find() to fail.
-                FromConstellation(AttachLayoutMsg(new_layout_info)) => {
-                    self.handle_new_layout(new_layout_info);
-                }
-                FromConstellation(ResizeMsg(id, size)) => {
-                    let mut page = self.page.borrow_mut();
-                    let page = page.find(id).expect("resize sent to nonexistent pipeline");
-                    page.resize_event.deref().set(Some(size));
-                }
-                _ => {
-                    sequential.push(event);
-                }
-            }
-
-            // If any of our input sources has an event pending, we'll perform another iteration
-            // and check for more resize events. If there are no events pending, we'll move
-            // on and execute the sequential non-resize events we've seen.
             match self.control_port.try_recv() {
-                Err(_) => match self.port.try_recv() {
-                    Err(_) => match self.devtools_port.try_recv() {
-                        Err(_) => break,
-                        Ok(ev) => event = FromDevtools(ev),
-                    },
-                    Ok(ev) => event = FromScript(ev),
+                Ok(msg) => {
+                    self.queue_control_msg(msg);
+                    continue;
+                },
+                Err(_) => {},
+            }
+            match self.port.try_recv() {
+                Ok((source, msg)) => {
+                    self.task_queues.borrow_mut().push(source, FromScript(msg));
+                    continue;
                 },
-                Ok(ev) => event = FromConstellation(ev),
+                Err(_) => {},
+            }
+            match self.devtools_port.try_recv() {
+                Ok(msg) => self.control_msgs.borrow_mut().push_back(FromDevtools(msg)),
+                Err(_) => break,
             }
         }
 
-        // Process the gathered events.
-        for msg in sequential.into_iter() {
-            match msg {
-                // TODO(tkuehn) need to handle auxiliary layouts for iframes
-                FromConstellation(AttachLayoutMsg(_)) => fail!("should have handled AttachLayoutMsg already"),
-                FromConstellation(LoadMsg(id, url)) => self.load(id, url),
-                FromScript(TriggerLoadMsg(id, url)) => self.trigger_load(id, url),
-                FromScript(TriggerFragmentMsg(id, url)) => self.trigger_fragment(id, url),
-                FromConstellation(SendEventMsg(id, event)) => self.handle_event(id, event),
-                FromScript(FireTimerMsg(id, timer_id)) => self.handle_fire_timer_msg(id, timer_id),
-                FromScript(NavigateMsg(direction)) => self.handle_navigate_msg(direction),
-                FromConstellation(ReflowCompleteMsg(id, reflow_id)) => self.handle_reflow_complete_msg(id, reflow_id),
-                FromConstellation(ResizeInactiveMsg(id, new_size)) => self.handle_resize_inactive_msg(id, new_size),
-                FromConstellation(ExitPipelineMsg(id)) => if self.handle_exit_pipeline_msg(id) { return false },
-                FromScript(ExitWindowMsg(id)) => self.handle_exit_window_msg(id),
-                FromConstellation(ResizeMsg(..)) => fail!("should have handled ResizeMsg already"),
-                FromScript(XHRProgressMsg(addr, progress)) => XMLHttpRequest::handle_xhr_progress(addr, progress),
-                FromScript(DOMMessage(..)) => fail!("unexpected message"),
-                FromScript(WorkerPostMessage(addr, data, nbytes)) => Worker::handle_message(addr, data, nbytes),
-                FromScript(WorkerRelease(addr)) => Worker::handle_release(addr),
-                FromDevtools(EvaluateJS(id, s, reply)) => self.handle_evaluate_js(id, s, reply),
-                FromDevtools(GetRootNode(id, reply)) => self.handle_get_root_node(id, reply),
-                FromDevtools(GetDocumentElement(id, reply)) => self.handle_get_document_element(id, reply),
-                FromDevtools(GetChildren(id, node_id, reply)) => self.handle_get_children(id, node_id, reply),
-                FromDevtools(GetLayout(id, node_id, reply)) => self.handle_get_layout(id, node_id, reply),
+        // Handle a single message, so that whatever arrives while it runs
+        // is sorted before the next one is picked.
+        let control_msg = self.control_msgs.borrow_mut().pop_front();
+        let msg = match control_msg {
+            Some(msg) => msg,
+            None => match self.task_queues.borrow_mut().pop() {
+                Some(msg) => msg,
+                // Only resizes or new layouts came, which are already handled.
+                None => return true,
+            },
+        };
+
+        let keep_running = match msg.pipeline_id() {
+            // A page that crashed only waits to exit.
+            Some(id) if self.is_crashed(id) => true,
+            // If handling the message for a page panics, only that page goes
+            // away. A DOM method that script called catches its own panic
+            // instead (see catch_dom_panic), since it can't unwind through
+            // the frames of the JS engine.
+            Some(id) => {
+                let mut msg = Some(msg);
+                let mut keep_running = true;
+                let result = unsafe {
+                    unwind::try(|| keep_running = self.handle_msg(msg.take().unwrap()))
+                };
+                if result.is_err() {
+                    self.handle_pipeline_crash(id);
+                }
+                keep_running
+            }
+            None => self.handle_msg(msg),
+        };
+        if !keep_running {
+            return false;
+        }
+        perform_microtask_checkpoint();
+        // The script that called a DOM method that panicked went on until it
+        // returned, and only now can its page go away.
+        for id in take_panicked_pipelines().into_iter() {
+            if !self.is_crashed(id) {
+                self.handle_pipeline_crash(id);
             }
         }
+        self.flush_pending_reflows();
 
         true
     }
 
+    /// Handles a message that `handle_msgs` picked. Returns false once the
+    /// script task should exit.
+    fn handle_msg(&self, msg: MixedMessage) -> bool {
+        match msg {
+            // TODO(tkuehn) need to handle auxiliary layouts for iframes
+            FromConstellation(AttachLayoutMsg(_)) => fail!("should have handled AttachLayoutMsg already"),
+            FromConstellation(LoadMsg(id, load_data)) => self.load(id, load_data),
+            FromScript(TriggerLoadMsg(id, load_data)) => self.trigger_load(id, load_data),
+            FromScript(TriggerFragmentMsg(id, url)) => self.trigger_fragment(id, url),
+            FromConstellation(SendEventMsg(id, event)) => self.handle_event(id, event),
+            FromScript(FireTimerMsg(FromWindow(id), timer_id)) => self.handle_fire_timer_msg(id, timer_id),
+            FromScript(FireTimerMsg(FromWorker, _)) => fail!("unexpected worker timer message"),
+            FromScript(FireQueuedEventsMsg(id)) => self.handle_fire_queued_events_msg(id),
+            FromScript(ImageLoadedMsg(id, url, succeeded)) => self.handle_image_loaded_msg(id, url, succeeded),
+            FromScript(NavigateMsg(direction)) => self.handle_navigate_msg(direction),
+            FromConstellation(ReflowCompleteMsg(id, reflow_id)) => self.handle_reflow_complete_msg(id, reflow_id),
+            FromConstellation(CollectMemoryReportMsg(id, response_chan)) =>
+                self.handle_collect_memory_report_msg(id, response_chan),
+            FromConstellation(ResizeInactiveMsg(id, new_size)) => self.handle_resize_inactive_msg(id, new_size),
+            FromConstellation(ExitPipelineMsg(id)) => return !self.handle_exit_pipeline_msg(id),
+            FromConstellation(UnloadMsg(id)) => self.handle_unload_msg(id),
+            FromConstellation(SubframeLoadedMsg(id, subpage_id)) =>
+                self.handle_subframe_loaded_msg(id, subpage_id),
+            FromScript(ExitWindowMsg(id)) => self.handle_exit_window_msg(id),
+            FromConstellation(ResizeMsg(..)) => fail!("should have handled ResizeMsg already"),
+            FromScript(XHRProgressMsg(addr, progress)) => XMLHttpRequest::handle_xhr_progress(addr, progress),
+            FromScript(FileReaderMsg(addr, progress)) => FileReader::handle_progress(addr, progress),
+            FromScript(WebSocketMsg(addr, event)) => WebSocket::handle_event(addr, event),
+            FromScript(EventSourceMsg(addr, event)) => EventSource::handle_event(addr, event),
+            FromScript(DOMMessage(..)) => fail!("unexpected message"),
+            FromScript(WorkerPostMessage(addr, data, ports)) => Worker::handle_message(addr, data, ports),
+            FromScript(WorkerRelease(addr)) => Worker::handle_release(addr),
+            FromScript(WorkerUncaughtError(addr, error)) => Worker::handle_error(addr, error),
+            FromScript(CloseWorkerMsg) => fail!("unexpected message"),
+            FromScript(ConnectMsg(..)) => fail!("unexpected message"),
+            FromScript(WindowPostMessage(id, data, origin, target_origin, ports)) =>
+                self.handle_window_post_message(id, data, origin, target_origin, ports),
+            FromScript(MessagePortMsg(addr)) => MessagePort::handle_message(addr),
+            FromScript(MessagePortRelease(addr)) => MessagePort::handle_release(addr),
+            FromScript(PromiseMsg(addr, settlement)) => Promise::handle_settlement(addr, settlement),
+            FromScript(StylesheetParsedMsg(id)) => self.handle_stylesheet_parsed_msg(id),
+            FromScript(PanicMsg(id)) => fail!("panicking in pipeline {:?} as asked", id),
+            FromScript(SendTestEventMsg(id, event)) => self.handle_event(id, event),
+            FromDevtools(EvaluateJS(id, s, reply)) => self.handle_evaluate_js(id, s, reply),
+            FromDevtools(GetRootNode(id, reply)) => self.handle_get_root_node(id, reply),
+            FromDevtools(GetDocumentElement(id, reply)) => self.handle_get_document_element(id, reply),
+            FromDevtools(GetChildren(id, node_id, reply)) => self.handle_get_children(id, node_id, reply),
+            FromDevtools(GetOuterHTML(id, node_id, reply)) => self.handle_get_outer_html(id, node_id, reply),
+            FromDevtools(GetLayout(id, node_id, reply)) => self.handle_get_layout(id, node_id, reply),
+        }
+        true
+    }
+
+    /// Whether the page of the specified pipeline crashed.
+    fn is_crashed(&self, id: PipelineId) -> bool {
+        match self.page.borrow().find(id) {
+            Some(page) => page.crashed.get(),
+            None => false,
+        }
+    }
+
+    /// Tears down the page of the specified pipeline and the ones inside it
+    /// after handling a task for it panicked, without running any more of
+    /// their script, and tells the constellation so that it shows an error
+    /// page instead. The other pages of the task keep running.
+    fn handle_pipeline_crash(&self, id: PipelineId) {
+        let page = match self.page.borrow().find(id) {
+            Some(page) => page,
+            None => {
+                debug!("pipeline {:?} crashed after it went away", id);
+                return;
+            }
+        };
+        for page in page.iter() {
+            page.crashed.set(true);
+            let window = page.frame().as_ref().map(|frame| Temporary::new(frame.window.clone()));
+            match window.root() {
+                Some(window) => window.tear_down(),
+                None => {}
+            }
+        }
+
+        let ConstellationChan(ref chan) = self.constellation_chan;
+        chan.send(CrashMsg(Failure { pipeline_id: id, subpage_id: page.subpage_id }));
+    }
+
+    /// Sorts a message from the constellation: new layouts and resizes are
+    /// handled right away, input events are queued as user interaction tasks,
+    /// and the rest is handled before any task.
+    fn queue_control_msg(&self, msg: ConstellationControlMsg) {
+        match msg {
+            // This has to be handled before the ResizeMsg below,
+            // otherwise the page may not have been added to the
+            // child list yet, causing the find() to fail.
+            AttachLayoutMsg(new_layout_info) => {
+                self.handle_new_layout(new_layout_info);
+            }
+            ResizeMsg(id, size) => {
+                let mut page = self.page.borrow_mut();
+                let page = page.find(id).expect("resize sent to nonexistent pipeline");
+                page.resize_event.deref().set(Some(size));
+            }
+            SendEventMsg(id, event) => {
+                self.task_queues.borrow_mut().push(UserInteractionTaskSource,
+                                                   FromConstellation(SendEventMsg(id, event)));
+            }
+            msg => {
+                self.control_msgs.borrow_mut().push_back(FromConstellation(msg));
+            }
+        }
+    }
+
+    /// Hands layout the stylesheets of the page of the specified pipeline that
+    /// are ready, unless the page went away in the meantime.
+    fn handle_stylesheet_parsed_msg(&self, id: PipelineId) {
+        let page = self.page.borrow();
+        match page.find(id) {
+            Some(page) => page.register_parsed_stylesheets(),
+            None => {},
+        }
+    }
+
+    /// Sends layout a single reflow for every page that was damaged during
+    /// the task that just ran, however many mutations did it.
+    fn flush_pending_reflows(&self) {
+        let page = self.page.borrow();
+        for page in page.iter() {
+            if page.has_pending_reflow() && !page.crashed.get() {
+                page.reflow(ReflowForDisplay, self.control_chan.clone(), &*self.compositor);
+            }
+        }
+    }
+
     fn handle_evaluate_js(&self, pipeline: PipelineId, eval: String, reply: Sender<EvaluateJSReply>) {
         let page = get_page(&*self.page.borrow(), pipeline);
         let frame = page.frame();
@@ -574,6 +857,11 @@ impl ScriptTask {
         reply.send(children);
     }
 
+    fn handle_get_outer_html(&self, pipeline: PipelineId, node_id: String, reply: Sender<String>) {
+        let node = self.find_node_by_unique_id(pipeline, node_id).root();
+        reply.send(serialize(&mut NodeIterator::new(*node, true, false)));
+    }
+
     fn handle_get_layout(&self, pipeline: PipelineId, node_id: String, reply: Sender<(f32, f32)>) {
         let node = self.find_node_by_unique_id(pipeline, node_id).root();
         let elem: JSRef<Element> = ElementCast::to_ref(*node).expect("should be getting layout of element");
@@ -616,6 +904,63 @@ impl ScriptTask {
         window.handle_fire_timer(timer_id, self.get_cx());
     }
 
+    /// Dispatches a message posted to the window of the specified pipeline,
+    /// unless its document no longer has the origin the message was meant for.
+    /// http://www.whatwg.org/html/#dom-window-postmessage
+    fn handle_window_post_message(&self, id: PipelineId, data: StructuredCloneData,
+                                  origin: DOMString, target_origin: Option<Origin>,
+                                  ports: Vec<PortEndpoint>) {
+        let page = get_page(&*self.page.borrow(), id);
+        let frame = page.frame();
+        let window = match *frame {
+            Some(ref frame) => frame.window.root(),
+            None => return,
+        };
+        match target_origin {
+            Some(ref target_origin) => {
+                if !window.Document().root().origin().same_origin(target_origin) {
+                    return;
+                }
+            },
+            None => {},
+        }
+        let global = global::Window(*window);
+        let message = data.read(&global);
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(*window);
+        MessageEvent::dispatch_jsval(target, &global, message, origin, ports);
+    }
+
+    /// Handles a request to fire the events queued on a window.
+    fn handle_fire_queued_events_msg(&self, id: PipelineId) {
+        let page = get_page(&*self.page.borrow(), id);
+        let frame = page.frame();
+        match *frame {
+            Some(ref frame) => frame.window.root().fire_queued_events(),
+            None => (),
+        }
+    }
+
+    fn handle_image_loaded_msg(&self, id: PipelineId, url: Url, succeeded: bool) {
+        let page = get_page(&*self.page.borrow(), id);
+        let frame = page.frame();
+        match *frame {
+            Some(ref frame) => frame.document.root().complete_image_request(url, succeeded),
+            None => (),
+        }
+    }
+
+    /// Reports what the page of the specified pipeline uses memory for. A
+    /// page that went away in the meantime doesn't report anything.
+    fn handle_collect_memory_report_msg(&self, id: PipelineId, response_chan: Sender<MemoryReport>) {
+        let page = self.page.borrow();
+        match page.find(id) {
+            Some(page) => {
+                let _ = response_chan.send_opt(page.memory_report());
+            }
+            None => {}
+        }
+    }
+
     /// Handles a notification that reflow completed.
     fn handle_reflow_complete_msg(&self, pipeline_id: PipelineId, reflow_id: uint) {
         debug!("Script: Reflow {:?} complete for {:?}", reflow_id, pipeline_id);
@@ -671,9 +1016,43 @@ impl ScriptTask {
         self.compositor.close();
     }
 
+    /// Unloads the document of the specified pipeline, and the ones inside
+    /// it, because the browsing context navigated away from it.
+    fn handle_unload_msg(&self, id: PipelineId) {
+        let page = self.page.borrow();
+        match page.find(id) {
+            Some(ref page) => unload_page_tree(page, &*self.compositor),
+            None => {}
+        }
+        // The frame shows a document of another script task now, if not a
+        // new one of this task.
+        forget_content_window(&*page, id);
+    }
+
+    /// Fires a load event at the iframe of the page `id` whose document
+    /// finished loading.
+    fn handle_subframe_loaded_msg(&self, id: PipelineId, subpage_id: SubpageId) {
+        let page = get_page(&*self.page.borrow(), id);
+        let iframe = match find_frame_element(&*page, subpage_id).root() {
+            Some(iframe) => iframe,
+            None => return,
+        };
+        let window = page.frame().as_ref().unwrap().window.root();
+        let event = Event::new(&global::Window(*window), "load".to_string(), false, false).root();
+        let target: JSRef<EventTarget> = EventTargetCast::from_ref(*iframe);
+        let _ = target.dispatch_event_with_target(None, *event);
+    }
+
     /// Handles a request to exit the script task and shut down layout.
     /// Returns true if the script task should shut down and false otherwise.
     fn handle_exit_pipeline_msg(&self, id: PipelineId) -> bool {
+        // Unload the documents of the pages that are going away while they
+        // can still run script.
+        match self.page.borrow().find(id) {
+            Some(ref page) => unload_page_tree(page, &*self.compositor),
+            None => {}
+        }
+
         // If root is being exited, shut down all pages
         let mut page = self.page.borrow_mut();
         if page.id == id {
@@ -683,6 +1062,8 @@ impl ScriptTask {
             return true
         }
 
+        forget_content_window(&*page, id);
+
         // otherwise find just the matching page and exit all sub-pages
         match page.remove(id) {
             Some(ref mut page) => {
@@ -699,10 +1080,12 @@ impl ScriptTask {
 
     /// The entry point to document loading. Defines bindings, sets up the window and document
     /// objects, parses HTML and CSS, and kicks off initial layout.
-    fn load(&self, pipeline_id: PipelineId, url: Url) {
+    fn load(&self, pipeline_id: PipelineId, load_data: LoadData) {
+        let url = load_data.url.clone();
         debug!("ScriptTask: loading {} on page {:?}", url, pipeline_id);
 
         let mut page = self.page.borrow_mut();
+        let parent_page = page.find_parent(pipeline_id);
         let page = page.find(pipeline_id).expect("ScriptTask: received a load
             message for a layout channel that is not associated with this script task. This
             is a bug.");
@@ -720,18 +1103,59 @@ impl ScriptTask {
             _ => (),
         }
 
+        // The iframe whose nested browsing context the page is, if the
+        // document around it is in this task.
+        let frame_element = match (parent_page.as_ref(), page.subpage_id) {
+            (Some(parent_page), Some(subpage_id)) => find_frame_element(&**parent_page, subpage_id),
+            _ => None,
+        }.root();
+
+        // The document that the browsing context showed so far goes away. An
+        // iframe that navigates gets a page of its own for the new document.
+        let last_window = match *page.frame() {
+            Some(ref frame) => Some(Temporary::new(frame.window.clone())),
+            None => frame_element.as_ref().and_then(|iframe| iframe.content_window()),
+        }.root();
+        match last_window {
+            Some(ref window) => window.unload(&*self.compositor),
+            None => {}
+        }
+
         let is_javascript = url.scheme.as_slice() == "javascript";
         let last_url = last_loaded_url.map(|(ref loaded, _)| loaded.clone());
 
+        // A same-origin document takes the window of the initial about:blank
+        // document over, so that what scripts put on it stays there.
+        // http://www.whatwg.org/html/#initialise-the-document-object
+        let reused_window = match last_window {
+            Some(ref window) if !is_javascript &&
+                                load_data.sandbox == constellation_msg::IFrameUnsandboxed => {
+                let last_document = window.Document().root();
+                if last_document.is_initial_about_blank() &&
+                        last_document.origin().same_origin(&Origin::from_url(&url)) {
+                    Some(Temporary::from_rooted(**window))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+        let reuses_window = reused_window.is_some();
+
         let cx = self.js_context.borrow();
         let cx = cx.as_ref().unwrap();
         // Create the window and document objects.
-        let window = Window::new(cx.deref().ptr,
-                                 page.clone(),
-                                 self.chan.clone(),
-                                 self.control_chan.clone(),
-                                 self.compositor.dup(),
-                                 self.image_cache_task.clone()).root();
+        let window = match reused_window {
+            Some(window) => window,
+            None => Window::new(cx.deref().ptr,
+                                page.clone(),
+                                self.chan.clone(),
+                                self.control_chan.clone(),
+                                self.compositor.dup(),
+                                self.devtools_chan.clone(),
+                                self.timer_scheduler.clone(),
+                                self.image_cache_task.clone()),
+        }.root();
         let doc_url = if is_javascript {
             let doc_url = match last_url {
                 Some(url) => Some(url.clone()),
@@ -744,12 +1168,46 @@ impl ScriptTask {
         };
         let document = Document::new(*window, doc_url, HTMLDocument, None).root();
 
-        window.deref().init_browser_context(*document);
+        // http://www.whatwg.org/html/#origin
+        // Sandboxed documents get an origin of their own, and the about:blank
+        // document of an iframe gets the origin of the document around it.
+        if load_data.sandbox == constellation_msg::IFrameSandboxed {
+            document.set_origin(Origin::opaque());
+        } else if !is_javascript && url.serialize().as_slice() == "about:blank" {
+            match parent_page {
+                Some(ref parent_page) => match *parent_page.frame() {
+                    Some(ref frame) => document.set_origin(frame.document.root().origin()),
+                    None => {},
+                },
+                None => {},
+            }
+        }
+
+        // An iframe shows about:blank until something else is loaded in it.
+        if frame_element.is_some() && last_window.is_none() && !is_javascript &&
+                url.serialize().as_slice() == "about:blank" {
+            document.set_initial_about_blank(true);
+        }
+
+        if reuses_window {
+            // The page of the about:blank document hands the window over, so
+            // that it doesn't unload it when it goes away.
+            *window.page().mut_frame() = None;
+            window.deref().replace_document(page.clone(), *document);
+        } else {
+            // The new window takes over the WindowProxy of the last one.
+            window.deref().init_browser_context(*document,
+                                                last_window.as_ref().map(|window| window.window_proxy()));
+        }
+        match frame_element {
+            Some(ref iframe) => iframe.set_content_window(Some(*window)),
+            None => {}
+        }
 
         self.compositor.set_ready_state(pipeline_id, Loading);
 
         let parser_input = if !is_javascript {
-            InputUrl(url.clone())
+            InputUrl(url.clone(), load_data.referrer.clone())
         } else {
             let evalstr = url.non_relative_scheme_data().unwrap();
             let jsval = window.evaluate_js_with_result(evalstr);
@@ -757,6 +1215,9 @@ impl ScriptTask {
             InputString(strval.unwrap_or("".to_string()))
         };
 
+        // The stylesheets of the previous document are of no use any more.
+        page.clear_pending_stylesheets();
+
         // Parse HTML.
         //
         // Note: We can parse the next document in parallel with any previous documents.
@@ -779,11 +1240,6 @@ impl ScriptTask {
             });
         }
 
-        // Send style sheets over to layout.
-        //
-        // FIXME: These should be streamed to layout as they're parsed. We don't need to stop here
-        // in the script task.
-
         let mut js_scripts = None;
         loop {
             match discovery_port.recv_opt() {
@@ -795,9 +1251,13 @@ impl ScriptTask {
             }
         }
 
+        // The stylesheets are parsed on tasks of their own while the parser
+        // goes on, but the first reflow and the scripts need all of them.
+        page.wait_for_stylesheets();
+
         // Kick off the initial reflow of the page.
         debug!("kicking off initial reflow of {}", url);
-        document.deref().content_changed();
+        window.damage_and_reflow(ContentChangedDocumentDamage);
         window.flush_layout(ReflowForDisplay);
 
         let fragment = url.fragment.as_ref().map(|ref fragment| fragment.to_string());
@@ -835,13 +1295,18 @@ impl ScriptTask {
 
         // We have no concept of a document loader right now, so just dispatch the
         // "load" event as soon as we've finished executing all scripts parsed during
-        // the initial load.
+        // the initial load, and the stylesheets they added.
+        page.wait_for_stylesheets();
         let event = Event::new(&global::Window(*window), "load".to_string(), false, false).root();
         let doctarget: JSRef<EventTarget> = EventTargetCast::from_ref(*document);
         let wintarget: JSRef<EventTarget> = EventTargetCast::from_ref(*window);
         let _ = wintarget.dispatch_event_with_target(Some(doctarget), *event);
 
         page.fragment_node.assign(fragment.map_or(None, |fragid| page.find_fragment_node(fragid)));
+        match page.fragment_node.get().map(|node| node.root()) {
+            Some(node) => document.set_target_element(Some(*node)),
+            None => (),
+        }
 
         let ConstellationChan(ref chan) = self.constellation_chan;
         chan.send(LoadCompleteMsg(page.id, url));
@@ -888,6 +1353,9 @@ impl ScriptTask {
 
                 match window.root() {
                     Some(window) => {
+                        let document = window.Document().root();
+                        document.viewport_changed();
+
                         // http://dev.w3.org/csswg/cssom-view/#resizing-viewports
                         // https://dvcs.w3.org/hg/dom3events/raw-file/tip/html/DOM3-Events.html#event-type-resize
                         let uievent = UIEvent::new(window.clone(),
@@ -936,15 +1404,23 @@ impl ScriptTask {
                                 debug!("clicked on {:s}", node.debug_str());
                                 // Prevent click event if form control element is disabled.
                                 if node.click_event_filter_by_disabled_state() { return; }
+                                // Prevent click event if a modal dialog blocks the element.
+                                let document = node.owner_doc().root();
+                                if document.is_inert(node) { return; }
                                 match *page.frame() {
                                     Some(ref frame) => {
                                         let window = frame.window.root();
+                                        let x = point.x as i32;
+                                        let y = point.y as i32;
                                         let event =
-                                            Event::new(&global::Window(*window),
-                                                       "click".to_string(),
-                                                       true, true).root();
+                                            MouseEvent::new(*window, "click".to_string(),
+                                                            true, true, Some(*window), 0,
+                                                            x, y, x, y,
+                                                            false, false, false, false,
+                                                            0, None).root();
+                                        let event: JSRef<Event> = EventCast::from_ref(*event);
                                         let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(node);
-                                        let _ = eventtarget.dispatch_event_with_target(None, *event);
+                                        let _ = eventtarget.dispatch_event_with_target(None, event);
                                     }
                                     None => {}
                                 }
@@ -956,8 +1432,12 @@ impl ScriptTask {
                     None => {}
                 }
             }
-            MouseDownEvent(..) => {}
-            MouseUpEvent(..) => {}
+            MouseDownEvent(_button, point) => {
+                self.update_active_targets(pipeline_id, Some(point));
+            }
+            MouseUpEvent(..) => {
+                self.update_active_targets(pipeline_id, None);
+            }
             MouseMoveEvent(point) => {
                 let page = get_page(&*self.page.borrow(), pipeline_id);
                 match page.get_nodes_under_mouse(&point) {
@@ -1022,29 +1502,172 @@ impl ScriptTask {
                     None => {}
               }
             }
+
+            KeyEvent(key) => {
+                self.handle_key_event(pipeline_id, key);
+            }
+        }
+    }
+
+    /// Puts the element at `point` and its ancestors in the active state,
+    /// taking the previously active elements out of it. Without a point, no
+    /// element remains active.
+    /// http://www.whatwg.org/html/#selector-active
+    fn update_active_targets(&self, pipeline_id: PipelineId, point: Option<Point2D<f32>>) {
+        let page = get_page(&*self.page.borrow(), pipeline_id);
+        let old_targets = replace(&mut *self.active_targets.borrow_mut(), vec!());
+        for target in old_targets.iter() {
+            target.root().set_state(InActiveState, false);
+        }
+
+        let new_targets: Vec<JS<Element>> = match point.and_then(|point| page.hit_test(&point)) {
+            Some(node_address) => {
+                let node = node::from_untrusted_node_address(self.js_runtime.deref().ptr,
+                                                            node_address).root();
+                Some(*node).into_iter().chain(node.ancestors())
+                    .filter_map(|node| ElementCast::to_ref(node))
+                    .map(|element| JS::from_rooted(element))
+                    .collect()
+            }
+            None => vec!(),
+        };
+        for target in new_targets.iter() {
+            target.root().set_state(InActiveState, true);
+        }
+
+        if !old_targets.is_empty() || !new_targets.is_empty() {
+            page.damage(MatchSelectorsDocumentDamage);
+            page.reflow(ReflowForDisplay, self.control_chan.clone(), &*self.compositor);
+        }
+        *self.active_targets.borrow_mut() = new_targets;
+    }
+
+    /// Fires the keyboard events for a key press at the focused element, and
+    /// navigates back for Backspace or cancels the modal dialog for Escape
+    /// when the page doesn't use the key.
+    fn handle_key_event(&self, pipeline_id: PipelineId, key: Key) {
+        let page = get_page(&*self.page.borrow(), pipeline_id);
+        let navigate_back = match *page.frame() {
+            Some(ref frame) => {
+                let window = frame.window.root();
+                let document = frame.document.root();
+                let target = match document.GetActiveElement().root() {
+                    Some(target) => target,
+                    None => return,
+                };
+                let eventtarget: JSRef<EventTarget> = EventTargetCast::from_ref(*target);
+                let key_name = match key {
+                    CharacterKey(character) => String::from_char(1, character),
+                    BackspaceKey => "Backspace".to_string(),
+                    TabKey => "Tab".to_string(),
+                    EnterKey => "Enter".to_string(),
+                    EscapeKey => "Escape".to_string(),
+                };
+
+                let keydown = KeyboardEvent::new(*window, "keydown".to_string(), true, true,
+                                                 Some(*window), 0, key_name.clone(),
+                                                 false, false, false, false, false).root();
+                let keydown: JSRef<Event> = EventCast::from_ref(*keydown);
+                let _ = eventtarget.dispatch_event_with_target(None, keydown);
+
+                match key {
+                    CharacterKey(_) if !keydown.DefaultPrevented() => {
+                        let keypress = KeyboardEvent::new(*window, "keypress".to_string(), true,
+                                                          true, Some(*window), 0, key_name,
+                                                          false, false, false, false,
+                                                          false).root();
+                        let keypress: JSRef<Event> = EventCast::from_ref(*keypress);
+                        let _ = eventtarget.dispatch_event_with_target(None, keypress);
+                        false
+                    },
+                    BackspaceKey if !keydown.DefaultPrevented() => {
+                        // Backspace only navigates back when the focus isn't
+                        // in a text field or editable content, where users
+                        // would expect it to delete text. Nothing here edits
+                        // them yet.
+                        let node: JSRef<Node> = NodeCast::from_ref(*target);
+                        match node.type_id() {
+                            ElementNodeTypeId(HTMLInputElementTypeId) |
+                            ElementNodeTypeId(HTMLTextAreaElementTypeId) => false,
+                            _ => !HTMLElementCast::to_ref(*target).map_or(false, |element| {
+                                element.is_editable()
+                            }),
+                        }
+                    },
+                    TabKey if !keydown.DefaultPrevented() => {
+                        document.focus_next();
+                        false
+                    },
+                    EscapeKey if !keydown.DefaultPrevented() => {
+                        document.cancel_modal_dialog();
+                        false
+                    },
+                    _ => false,
+                }
+            }
+            None => return,
+        };
+
+        if navigate_back {
+            self.handle_navigate_msg(constellation_msg::Back);
+        } else {
+            page.damage(ContentChangedDocumentDamage);
+            page.reflow(ReflowForDisplay, self.control_chan.clone(), &*self.compositor);
         }
     }
 
     /// The entry point for content to notify that a new load has been requested
     /// for the given pipeline.
-    fn trigger_load(&self, pipeline_id: PipelineId, url: Url) {
+    fn trigger_load(&self, pipeline_id: PipelineId, load_data: LoadData) {
         let ConstellationChan(ref const_chan) = self.constellation_chan;
-        const_chan.send(LoadUrlMsg(pipeline_id, url));
+        const_chan.send(LoadUrlMsg(pipeline_id, load_data));
     }
 
     /// The entry point for content to notify that a fragment url has been requested
     /// for the given pipeline.
     fn trigger_fragment(&self, pipeline_id: PipelineId, url: Url) {
         let page = get_page(&*self.page.borrow(), pipeline_id);
-        match page.find_fragment_node(url.fragment.unwrap()).root() {
+        let node = page.find_fragment_node(url.fragment.unwrap()).root();
+        match *page.frame() {
+            Some(ref frame) => {
+                let document = frame.document.root();
+                document.set_target_element(node.as_ref().map(|node| **node));
+                // http://www.whatwg.org/html/#scroll-to-fragid
+                let window = frame.window.root();
+                let target: JSRef<EventTarget> = EventTargetCast::from_ref(*window);
+                window.queue_simple_event(target, "hashchange".to_string());
+            }
+            None => {}
+        }
+        match node {
             Some(node) => {
                 self.scroll_fragment_point(pipeline_id, *node);
             }
             None => {}
-         }
+        }
+        page.reflow(ReflowForDisplay, self.control_chan.clone(), &*self.compositor);
      }
 }
 
+/// Unloads the documents of the given page tree, parents before their
+/// children.
+fn unload_page_tree(page_tree: &Rc<Page>, compositor: &ScriptListener) {
+    for page in page_tree.iter() {
+        let window = page.frame().as_ref().map(|frame| Temporary::new(frame.window.clone()));
+        match window.root() {
+            // A window that another page took over isn't this page's to unload.
+            Some(ref window) if window.page().id == page.id => window.unload(compositor),
+            _ => {}
+        }
+    }
+}
+
+/// Traces the JS-managed values that Rust code keeps rooted, such as those in
+/// `RootedVec`s.
+extern fn trace_rust_roots(trc: *mut JSTracer, _data: *mut libc::c_void) {
+    trace_rooted_collections(trc);
+}
+
 /// Shuts down layout for the given page tree.
 fn shut_down_layout(page_tree: &Rc<Page>, rt: *mut JSRuntime) {
     for page in page_tree.iter() {
@@ -1082,6 +1705,49 @@ fn shut_down_layout(page_tree: &Rc<Page>, rt: *mut JSRuntime) {
 }
 
 
+/// Finds the iframe in the document of `parent` whose nested browsing context
+/// is shown by the subpage `subpage_id`.
+/// Makes the iframe that showed the page `id` of the tree of `root` no longer
+/// have a window, unless the page handed it over to the one of the next
+/// document.
+fn forget_content_window(root: &Rc<Page>, id: PipelineId) {
+    match (root.find_parent(id), root.find(id).and_then(|page| page.subpage_id)) {
+        (Some(ref parent), Some(subpage_id)) => {
+            match find_frame_element(&**parent, subpage_id).root() {
+                Some(iframe) => {
+                    let shows_page = iframe.content_window().root().map_or(false, |window| {
+                        window.page().id == id
+                    });
+                    if shows_page {
+                        iframe.set_content_window(None);
+                    }
+                }
+                None => {}
+            }
+        }
+        _ => {}
+    }
+}
+
+fn find_frame_element(parent: &Page, subpage_id: SubpageId) -> Option<Temporary<HTMLIFrameElement>> {
+    let frame = parent.frame();
+    let document = match *frame {
+        Some(ref frame) => frame.document.root(),
+        None => return None,
+    };
+    let node: JSRef<Node> = NodeCast::from_ref(*document);
+    for candidate in node.traverse_preorder() {
+        let iframe: Option<JSRef<HTMLIFrameElement>> = HTMLIFrameElementCast::to_ref(candidate);
+        match iframe {
+            Some(iframe) if iframe.size.deref().get().map(|size| size.subpage_id) == Some(subpage_id) => {
+                return Some(Temporary::from_rooted(iframe));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
 fn get_page(page: &Rc<Page>, pipeline_id: PipelineId) -> Rc<Page> {
     page.find(pipeline_id).expect("ScriptTask: received an event \
         message for a layout channel that is not associated with this script task.\
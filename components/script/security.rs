@@ -0,0 +1,197 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Origins, and the checks that decide whether documents and workers may
+//! access each other.
+//! http://www.whatwg.org/html/#origin
+
+use std::sync::atomics::{AtomicUint, INIT_ATOMIC_UINT, SeqCst};
+use url::{Host, Url, RelativeSchemeData, NonRelativeSchemeData};
+
+static mut next_opaque_id: AtomicUint = INIT_ATOMIC_UINT;
+
+#[deriving(Clone, PartialEq, Show)]
+enum OriginData {
+    /// A scheme, a host and a port, which is the default port of the scheme
+    /// if the URL had none
+    Tuple(String, String, Option<u16>),
+    /// An origin that is only the same as itself, told apart from the others
+    /// by a serial number
+    Opaque(uint),
+}
+
+/// The origin of a document, a worker or a URL.
+#[deriving(Clone, Show)]
+pub struct Origin {
+    data: OriginData,
+    /// The domain set with document.domain, if any
+    domain: Option<String>,
+}
+
+impl Origin {
+    /// Returns the origin of `url`. URLs that don't have a host, such as
+    /// data: URLs, get a new opaque origin every time.
+    /// http://url.spec.whatwg.org/#concept-url-origin
+    pub fn from_url(url: &Url) -> Origin {
+        match url.scheme.as_slice() {
+            // A blob: URL has the origin of the document that created it,
+            // which its path starts with.
+            "blob" => {
+                match url.non_relative_scheme_data().and_then(|data| Url::parse(data).ok()) {
+                    Some(ref url) => Origin::from_url(url),
+                    None => Origin::opaque(),
+                }
+            },
+            // The origin of file: URLs is left to implementations. They all
+            // get the same one, so that local documents can use each other.
+            "ftp" | "gopher" | "http" | "https" | "ws" | "wss" | "file" => {
+                match url.scheme_data {
+                    RelativeSchemeData(ref data) => Origin {
+                        data: Tuple(url.scheme.clone(), data.host.serialize(),
+                                    data.port.or(data.default_port)),
+                        domain: None,
+                    },
+                    NonRelativeSchemeData(_) => Origin::opaque(),
+                }
+            },
+            _ => Origin::opaque(),
+        }
+    }
+
+    /// Returns a new opaque origin, which is different from every other.
+    pub fn opaque() -> Origin {
+        let id = unsafe { next_opaque_id.fetch_add(1, SeqCst) };
+        Origin {
+            data: Opaque(id),
+            domain: None,
+        }
+    }
+
+    pub fn is_opaque(&self) -> bool {
+        match self.data {
+            Opaque(_) => true,
+            Tuple(..) => false,
+        }
+    }
+
+    /// Whether `self` and `other` are the same origin, whatever their
+    /// domains are.
+    /// http://www.whatwg.org/html/#same-origin
+    pub fn same_origin(&self, other: &Origin) -> bool {
+        self.data == other.data
+    }
+
+    /// Whether `self` and `other` are the same origin once their domains are
+    /// taken into account, which is what decides whether documents may
+    /// access each other.
+    /// http://www.whatwg.org/html/#same-origin-domain
+    pub fn same_origin_domain(&self, other: &Origin) -> bool {
+        match (&self.data, &other.data, &self.domain, &other.domain) {
+            (&Tuple(ref scheme, _, _), &Tuple(ref other_scheme, _, _),
+             &Some(ref domain), &Some(ref other_domain)) => {
+                scheme == other_scheme && domain == other_domain
+            },
+            (_, _, &None, &None) => self.same_origin(other),
+            _ => false,
+        }
+    }
+
+    /// The domain of the origin: the one document.domain set, or else its
+    /// host. Opaque origins have none.
+    /// http://www.whatwg.org/html/#effective-script-origin
+    pub fn effective_domain(&self) -> Option<String> {
+        match self.data {
+            Tuple(_, ref host, _) => Some(self.domain.clone().unwrap_or(host.clone())),
+            Opaque(_) => None,
+        }
+    }
+
+    /// Sets the domain of the origin to `value`, which must be its effective
+    /// domain or a parent domain of it, as document.domain does.
+    /// http://www.whatwg.org/html/#dom-document-domain
+    pub fn set_domain(&mut self, value: &str) -> Result<(), ()> {
+        let effective_domain = match self.effective_domain() {
+            Some(domain) => domain,
+            None => return Err(()),
+        };
+        let value = match Host::parse(value) {
+            Ok(host) => host.serialize(),
+            Err(_) => return Err(()),
+        };
+        if value != effective_domain {
+            if is_ip_address(effective_domain.as_slice()) {
+                return Err(());
+            }
+            let suffix = format!(".{:s}", value);
+            if !effective_domain.as_slice().ends_with(suffix.as_slice()) {
+                return Err(());
+            }
+            // FIXME: This should refuse any public suffix, not only top-level
+            // domains, but there is no public suffix list to check yet.
+            if !value.as_slice().contains_char('.') {
+                return Err(());
+            }
+        }
+        self.domain = Some(value);
+        Ok(())
+    }
+
+    /// Returns the serialization of the origin, as sent in Origin headers
+    /// and used in blob: URLs.
+    /// http://www.whatwg.org/html/#ascii-serialisation-of-an-origin
+    pub fn serialize(&self) -> String {
+        match self.data {
+            Tuple(ref scheme, ref host, Some(port)) if Some(port) != default_port(scheme.as_slice()) => {
+                format!("{:s}://{:s}:{:u}", *scheme, *host, port)
+            },
+            Tuple(ref scheme, ref host, _) => format!("{:s}://{:s}", *scheme, *host),
+            Opaque(_) => "null".to_string(),
+        }
+    }
+}
+
+fn default_port(scheme: &str) -> Option<u16> {
+    match scheme {
+        "ftp" => Some(21),
+        "gopher" => Some(70),
+        "http" | "ws" => Some(80),
+        "https" | "wss" => Some(443),
+        _ => None,
+    }
+}
+
+fn is_ip_address(host: &str) -> bool {
+    host.starts_with("[") || host.chars().all(|c| c == '.' || (c >= '0' && c <= '9'))
+}
+
+#[test]
+fn test_document_domain_lets_parent_and_child_access_each_other() {
+    let mut parent = Origin::from_url(&Url::parse("http://example.com/").unwrap());
+    let mut child = Origin::from_url(&Url::parse("http://www.example.com/frame").unwrap());
+    assert!(!parent.same_origin_domain(&child));
+    assert!(!child.same_origin_domain(&parent));
+
+    // Relaxing only one side isn't enough, even to the host of the other.
+    assert_eq!(child.set_domain("example.com"), Ok(()));
+    assert!(!parent.same_origin_domain(&child));
+    assert!(!child.same_origin_domain(&parent));
+
+    assert_eq!(parent.set_domain("example.com"), Ok(()));
+    assert!(parent.same_origin_domain(&child));
+    assert!(child.same_origin_domain(&parent));
+    assert!(!parent.same_origin(&child));
+
+    // The port doesn't matter once the domains are set, but the scheme does.
+    let mut other_port = Origin::from_url(&Url::parse("http://a.example.com:8000/").unwrap());
+    let mut other_scheme = Origin::from_url(&Url::parse("https://b.example.com/").unwrap());
+    assert_eq!(other_port.set_domain("example.com"), Ok(()));
+    assert_eq!(other_scheme.set_domain("example.com"), Ok(()));
+    assert!(other_port.same_origin_domain(&parent));
+    assert!(!other_scheme.same_origin_domain(&parent));
+
+    // Only the host itself or one of its parent domains can be set.
+    assert_eq!(child.set_domain("www.example.com"), Err(()));
+    assert_eq!(child.set_domain("com"), Err(()));
+    assert_eq!(child.set_domain("example.org"), Err(()));
+}
@@ -0,0 +1,138 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The task sources of an event loop, and the queues that hold the tasks
+//! they queued until the event loop picks one.
+//! http://www.whatwg.org/html/#task-source
+
+use collections::{Deque, RingBuf};
+
+/// Where a task comes from, which decides when it runs relative to those
+/// from other sources. Tasks from a single source always run in the order
+/// they were queued.
+#[deriving(Clone, PartialEq, Show)]
+pub enum TaskSource {
+    /// Tasks that react to the user, such as input events.
+    UserInteractionTaskSource,
+    /// Tasks that react to DOM manipulation, such as the events an element
+    /// fires when it is inserted.
+    DOMManipulationTaskSource,
+    /// Tasks that react to network activity, including reads of files.
+    NetworkingTaskSource,
+    /// Tasks that deliver the messages of postMessage and message ports.
+    PostedMessageTaskSource,
+    /// Tasks that fire timers.
+    TimerTaskSource,
+}
+
+/// The sources that take turns once no user interaction task is left.
+static ROUND_ROBIN_SOURCES: [TaskSource, ..3] = [
+    DOMManipulationTaskSource,
+    NetworkingTaskSource,
+    PostedMessageTaskSource,
+];
+
+/// One queue per task source. User interaction tasks run first so that
+/// input is handled however busy the page is, then DOM manipulation,
+/// networking and posted message tasks take turns, and timers only fire
+/// when nothing else is left to do.
+pub struct TaskQueues<T> {
+    user_interaction: RingBuf<T>,
+    dom_manipulation: RingBuf<T>,
+    networking: RingBuf<T>,
+    posted_message: RingBuf<T>,
+    timer: RingBuf<T>,
+    /// The index in `ROUND_ROBIN_SOURCES` of the source whose turn it is.
+    next_round_robin: uint,
+}
+
+impl<T> TaskQueues<T> {
+    pub fn new() -> TaskQueues<T> {
+        TaskQueues {
+            user_interaction: RingBuf::new(),
+            dom_manipulation: RingBuf::new(),
+            networking: RingBuf::new(),
+            posted_message: RingBuf::new(),
+            timer: RingBuf::new(),
+            next_round_robin: 0,
+        }
+    }
+
+    fn queue_mut<'a>(&'a mut self, source: TaskSource) -> &'a mut RingBuf<T> {
+        match source {
+            UserInteractionTaskSource => &mut self.user_interaction,
+            DOMManipulationTaskSource => &mut self.dom_manipulation,
+            NetworkingTaskSource => &mut self.networking,
+            PostedMessageTaskSource => &mut self.posted_message,
+            TimerTaskSource => &mut self.timer,
+        }
+    }
+
+    /// Queues `task` behind the other tasks from `source`.
+    pub fn push(&mut self, source: TaskSource, task: T) {
+        self.queue_mut(source).push_back(task);
+    }
+
+    /// Takes the task that should run next out of its queue, if any.
+    pub fn pop(&mut self) -> Option<T> {
+        match self.user_interaction.pop_front() {
+            Some(task) => return Some(task),
+            None => {},
+        }
+
+        let count = ROUND_ROBIN_SOURCES.len();
+        for offset in range(0, count) {
+            let index = (self.next_round_robin + offset) % count;
+            match self.queue_mut(ROUND_ROBIN_SOURCES[index]).pop_front() {
+                Some(task) => {
+                    self.next_round_robin = (index + 1) % count;
+                    return Some(task);
+                },
+                None => {},
+            }
+        }
+
+        self.timer.pop_front()
+    }
+
+    /// The number of tasks waiting in all of the queues.
+    pub fn len(&self) -> uint {
+        self.user_interaction.len() + self.dom_manipulation.len() + self.networking.len() +
+            self.posted_message.len() + self.timer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[test]
+fn test_user_interaction_tasks_run_first() {
+    let mut queues = TaskQueues::new();
+    for i in range(0i, 100) {
+        queues.push(NetworkingTaskSource, i);
+    }
+    queues.push(UserInteractionTaskSource, -1);
+    assert_eq!(queues.len(), 101);
+    assert_eq!(queues.pop(), Some(-1));
+    for i in range(0i, 100) {
+        assert_eq!(queues.pop(), Some(i));
+    }
+    assert_eq!(queues.pop(), None);
+    assert!(queues.is_empty());
+}
+
+#[test]
+fn test_sources_take_turns_and_timers_run_last() {
+    let mut queues = TaskQueues::new();
+    queues.push(TimerTaskSource, "timer");
+    queues.push(NetworkingTaskSource, "network 1");
+    queues.push(NetworkingTaskSource, "network 2");
+    queues.push(DOMManipulationTaskSource, "dom 1");
+    queues.push(DOMManipulationTaskSource, "dom 2");
+    queues.push(PostedMessageTaskSource, "message");
+    let order: Vec<&str> = range(0u, 6).map(|_| queues.pop().unwrap()).collect();
+    assert_eq!(order, vec!("dom 1", "network 1", "message", "dom 2", "network 2", "timer"));
+    assert_eq!(queues.pop(), None);
+}
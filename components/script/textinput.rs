@@ -0,0 +1,155 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The editing state shared by the text controls, i.e. textarea elements and
+//! the text-like input types. Offsets are counted in UTF-16 code units, like
+//! everywhere else in the DOM.
+
+use dom::bindings::codegen::Bindings::HTMLInputElementBinding::SelectionMode;
+use dom::bindings::codegen::Bindings::HTMLInputElementBinding::SelectionModeValues;
+use dom::bindings::error::{Fallible, IndexSize};
+
+use servo_util::str::DOMString;
+
+use std::cell::Cell;
+use std::cmp::min;
+
+/// http://www.whatwg.org/html/#dom-textarea/input-selectiondirection
+#[jstraceable]
+#[deriving(PartialEq)]
+pub enum SelectionDirection {
+    SelectionForward,
+    SelectionBackward,
+    SelectionNone,
+}
+
+impl SelectionDirection {
+    pub fn from_dom_string(direction: &str) -> SelectionDirection {
+        match direction {
+            "forward" => SelectionForward,
+            "backward" => SelectionBackward,
+            _ => SelectionNone,
+        }
+    }
+
+    pub fn to_dom_string(&self) -> DOMString {
+        match *self {
+            SelectionForward => "forward",
+            SelectionBackward => "backward",
+            SelectionNone => "none",
+        }.to_string()
+    }
+}
+
+/// Returns the length of `value` in UTF-16 code units.
+pub fn utf16_length(value: &str) -> u32 {
+    value.utf16_units().count() as u32
+}
+
+/// The selection of a text control. A collapsed selection is the position of
+/// the text entry cursor.
+#[jstraceable]
+pub struct TextSelection {
+    start: Cell<u32>,
+    end: Cell<u32>,
+    direction: Cell<SelectionDirection>,
+}
+
+impl TextSelection {
+    pub fn new() -> TextSelection {
+        TextSelection {
+            start: Cell::new(0),
+            end: Cell::new(0),
+            direction: Cell::new(SelectionNone),
+        }
+    }
+
+    pub fn start(&self) -> u32 {
+        self.start.get()
+    }
+
+    pub fn end(&self) -> u32 {
+        self.end.get()
+    }
+
+    pub fn direction(&self) -> SelectionDirection {
+        self.direction.get()
+    }
+
+    /// Selects the given range of a value of `length` code units, clamping it
+    /// to the value.
+    /// http://www.whatwg.org/html/#dom-textarea/input-setselectionrange
+    pub fn set_range(&self, start: u32, end: u32, direction: SelectionDirection, length: u32) {
+        let end = min(end, length);
+        let start = min(start, end);
+        self.start.set(start);
+        self.end.set(end);
+        self.direction.set(direction);
+    }
+
+    /// Selects the whole value.
+    pub fn select_all(&self, length: u32) {
+        self.set_range(0, length, SelectionNone, length);
+    }
+
+    /// Keeps the selection within the value after it changed to `length` code
+    /// units.
+    pub fn clamp(&self, length: u32) {
+        self.set_range(self.start(), self.end(), self.direction(), length);
+    }
+
+    /// Replaces the range from `start` to `end` of `value` with `replacement`,
+    /// returning the new value and updating the selection according to `mode`.
+    /// http://www.whatwg.org/html/#dom-textarea/input-setrangetext
+    pub fn set_range_text(&self, value: &str, replacement: &str, start: u32, end: u32,
+                          mode: SelectionMode) -> Fallible<DOMString> {
+        if start > end {
+            return Err(IndexSize);
+        }
+
+        let value: Vec<u16> = value.utf16_units().collect();
+        let length = value.len() as u32;
+        let start = min(start, length);
+        let end = min(end, length);
+
+        let replacement: Vec<u16> = replacement.utf16_units().collect();
+        let new_length = replacement.len() as u32;
+        let new_end = start + new_length;
+
+        let mut new_value = value.slice_to(start as uint).to_vec();
+        new_value.push_all(replacement.as_slice());
+        new_value.push_all(value.slice_from(end as uint));
+        let new_value = String::from_utf16_lossy(new_value.as_slice());
+
+        let (selection_start, selection_end) = match mode {
+            SelectionModeValues::Select => (start, new_end),
+            SelectionModeValues::Start => (start, start),
+            SelectionModeValues::End => (new_end, new_end),
+            SelectionModeValues::Preserve => {
+                // The selection keeps its place relative to the text around
+                // the replaced range, and covers the replacement wherever it
+                // overlapped the replaced range.
+                let old_length = end - start;
+                let selection_start = if self.start() > end {
+                    self.start() - old_length + new_length
+                } else if self.start() > start {
+                    start
+                } else {
+                    self.start()
+                };
+                let selection_end = if self.end() > end {
+                    self.end() - old_length + new_length
+                } else if self.end() > start {
+                    new_end
+                } else {
+                    self.end()
+                };
+                (selection_start, selection_end)
+            },
+        };
+        self.set_range(selection_start, selection_end, SelectionNone,
+                       utf16_length(new_value.as_slice()));
+        Ok(new_value)
+    }
+}
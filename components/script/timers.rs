@@ -0,0 +1,486 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The timers behind setTimeout and setInterval, which windows and workers
+//! both have. Each event loop has a single scheduler task that waits for
+//! whichever of its timers is due first.
+//! http://www.whatwg.org/html/#timers
+
+use dom::bindings::js::JSRef;
+use dom::bindings::trace::{Traceable, Untraceable};
+use dom::bindings::utils::Reflectable;
+use script_task::{FireTimerMsg, ScriptChan};
+use task_source::TimerTaskSource;
+
+use servo_msg::constellation_msg::PipelineId;
+use servo_util::task::spawn_named;
+
+use js::jsapi::{JS_CallFunctionValue, JSContext};
+use js::jsval::{JSVal, NullValue};
+use js::rust::with_compartment;
+
+use std::cell::{Cell, RefCell};
+use std::cmp;
+use std::collections::PriorityQueue;
+use std::collections::hashmap::HashMap;
+use std::comm::{channel, Receiver, Sender, Select};
+use std::hash::{Hash, sip};
+use std::io::timer::Timer;
+use std::ptr;
+use std::time::duration::Duration;
+use time::precise_time_ns;
+
+#[deriving(Clone, PartialEq, Eq, Show)]
+#[jstraceable]
+pub struct TimerId(i32);
+
+impl Hash for TimerId {
+    fn hash(&self, state: &mut sip::SipState) {
+        let TimerId(id) = *self;
+        id.hash(state);
+    }
+}
+
+// Holder for the various JS values associated with setTimeout
+// (ie. function value to invoke and all arguments to pass
+//      to the function when calling it)
+#[jstraceable]
+struct TimerData {
+    is_interval: bool,
+    /// How long to wait before the timer fires, in milliseconds.
+    timeout: u64,
+    funval: Traceable<JSVal>,
+}
+
+/// The global object a timer was set on, which tells the tasks that receive
+/// its FireTimerMsg whether it is theirs.
+#[deriving(Clone, PartialEq, Eq, Hash, Show)]
+pub enum TimerSource {
+    /// The window of the given pipeline.
+    FromWindow(PipelineId),
+    /// The global scope of the worker whose task receives the message.
+    FromWorker,
+}
+
+/// What a timer scheduler is asked to do.
+pub enum TimerSchedulerMsg {
+    /// Fire the given timer once after the given number of milliseconds.
+    ScheduleTimerMsg(TimerSource, TimerId, u64),
+    /// Forget the given timer if it didn't fire yet.
+    CancelTimerMsg(TimerSource, TimerId),
+}
+
+/// The channel to the timer scheduler of an event loop.
+pub type TimerSchedulerChan = Sender<TimerSchedulerMsg>;
+
+/// A timer that the scheduler waits for.
+type TimerKey = (TimerSource, TimerId);
+
+struct QueuedTimer {
+    /// When the timer is due, in milliseconds.
+    due: u64,
+    /// Tells apart the times a timer was queued, and orders timers that are
+    /// due at the same time by when they were queued.
+    sequence: u64,
+    key: TimerKey,
+}
+
+impl PartialEq for QueuedTimer {
+    fn eq(&self, other: &QueuedTimer) -> bool {
+        self.due == other.due && self.sequence == other.sequence
+    }
+}
+
+impl Eq for QueuedTimer {}
+
+impl PartialOrd for QueuedTimer {
+    fn partial_cmp(&self, other: &QueuedTimer) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedTimer {
+    /// The priority queue pops the greatest entry first, so the timer that is
+    /// due first has to compare greatest.
+    fn cmp(&self, other: &QueuedTimer) -> Ordering {
+        (other.due, other.sequence).cmp(&(self.due, self.sequence))
+    }
+}
+
+/// What the wakeup of a timer scheduler should do after its queue changed.
+#[deriving(PartialEq, Show)]
+pub enum Wakeup {
+    /// Stay armed for the deadline it already has.
+    KeepWakeup,
+    /// Get armed for the given deadline instead, in milliseconds.
+    ArmWakeup(u64),
+    /// Not go off at all, since no timer is left.
+    DisarmWakeup,
+}
+
+/// The timers of an event loop that didn't fire yet, ordered by when they
+/// are due. Cancelled timers stay in the heap until they come up, and are
+/// skipped then.
+pub struct TimerQueue {
+    heap: PriorityQueue<QueuedTimer>,
+    /// The sequence number of the entry in `heap` that is live for each timer
+    /// that wasn't cancelled.
+    pending: HashMap<TimerKey, u64>,
+    next_sequence: u64,
+    /// The deadline the wakeup is armed for, if any.
+    armed: Option<u64>,
+}
+
+impl TimerQueue {
+    pub fn new() -> TimerQueue {
+        TimerQueue {
+            heap: PriorityQueue::new(),
+            pending: HashMap::new(),
+            next_sequence: 0,
+            armed: None,
+        }
+    }
+
+    /// Queues a timer that is due at `due`. A timer that was queued before
+    /// and didn't fire yet is replaced.
+    pub fn insert(&mut self, key: TimerKey, due: u64) {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.pending.insert(key.clone(), sequence);
+        self.heap.push(QueuedTimer {
+            due: due,
+            sequence: sequence,
+            key: key,
+        });
+    }
+
+    /// Makes sure the timer doesn't fire, if it didn't already.
+    pub fn cancel(&mut self, key: &TimerKey) {
+        self.pending.remove(key);
+    }
+
+    /// The number of timers that are waiting to fire.
+    pub fn len(&self) -> uint {
+        self.pending.len()
+    }
+
+    /// Whether the heap entry `timer` was neither cancelled nor replaced.
+    fn is_live(&self, timer: &QueuedTimer) -> bool {
+        self.pending.find(&timer.key) == Some(&timer.sequence)
+    }
+
+    /// Takes the timers that are due by `now` out of the queue, in the order
+    /// they have to fire.
+    pub fn pop_due(&mut self, now: u64) -> Vec<TimerKey> {
+        let mut due = vec!();
+        loop {
+            match self.heap.top() {
+                Some(timer) if timer.due <= now => {},
+                _ => break,
+            }
+            let timer = self.heap.pop().unwrap();
+            if self.is_live(&timer) {
+                self.pending.remove(&timer.key);
+                due.push(timer.key);
+            }
+        }
+        due
+    }
+
+    /// When the first timer that is still waiting is due, if any. The
+    /// cancelled timers in front of it are dropped on the way.
+    pub fn next_deadline(&mut self) -> Option<u64> {
+        loop {
+            match self.heap.top() {
+                None => return None,
+                Some(timer) if self.is_live(timer) => return Some(timer.due),
+                Some(_) => {},
+            }
+            self.heap.pop();
+        }
+    }
+
+    /// Tells the queue that its wakeup went off, so that it is armed again
+    /// for whatever is left.
+    pub fn wakeup_fired(&mut self) {
+        self.armed = None;
+    }
+
+    /// Decides what the wakeup has to do for it to go off when the first
+    /// timer is due, and assumes it does that.
+    pub fn rearm(&mut self) -> Wakeup {
+        let deadline = self.next_deadline();
+        if deadline == self.armed {
+            return KeepWakeup;
+        }
+        self.armed = deadline;
+        match deadline {
+            Some(deadline) => ArmWakeup(deadline),
+            None => DisarmWakeup,
+        }
+    }
+}
+
+/// The current time on the clock of timer deadlines, in milliseconds.
+fn now() -> u64 {
+    precise_time_ns() / 1000000
+}
+
+/// Spawns the task that fires the timers of the event loop at the other end
+/// of `script_chan`, by queueing a FireTimerMsg there as a timer task once
+/// each of them is due. It waits for the earliest deadline with a single
+/// oneshot timer, and goes away once every channel to it is dropped or the
+/// event loop is gone.
+pub fn spawn_timer_scheduler(script_chan: ScriptChan) -> TimerSchedulerChan {
+    let (chan, port) = channel();
+    spawn_named("TimerScheduler", proc() {
+        let mut queue = TimerQueue::new();
+        let mut timer = Timer::new().unwrap();
+        let mut wakeup: Option<Receiver<()>> = None;
+        loop {
+            let msg = match wakeup {
+                None => match port.recv_opt() {
+                    Ok(msg) => Some(msg),
+                    Err(()) => break,
+                },
+                Some(ref wakeup_port) => {
+                    let select = Select::new();
+                    let mut msg_handle = select.handle(&port);
+                    let mut wakeup_handle = select.handle(wakeup_port);
+                    unsafe {
+                        msg_handle.add();
+                        wakeup_handle.add();
+                    }
+                    if select.wait() == msg_handle.id() {
+                        match port.recv_opt() {
+                            Ok(msg) => Some(msg),
+                            Err(()) => break,
+                        }
+                    } else {
+                        let _ = wakeup_port.recv_opt();
+                        None
+                    }
+                }
+            };
+
+            match msg {
+                Some(ScheduleTimerMsg(source, id, timeout)) => {
+                    queue.insert((source, id), now() + timeout)
+                }
+                Some(CancelTimerMsg(source, id)) => queue.cancel(&(source, id)),
+                // The wakeup went off.
+                None => {
+                    wakeup = None;
+                    queue.wakeup_fired();
+                }
+            }
+
+            for (source, id) in queue.pop_due(now()).into_iter() {
+                if !script_chan.queue_task(TimerTaskSource, FireTimerMsg(source, id)) {
+                    return;
+                }
+            }
+
+            match queue.rearm() {
+                KeepWakeup => {},
+                ArmWakeup(deadline) => {
+                    let delay = deadline - cmp::min(deadline, now());
+                    wakeup = Some(timer.oneshot(Duration::milliseconds(delay as i64)));
+                }
+                DisarmWakeup => wakeup = None,
+            }
+        }
+    });
+    chan
+}
+
+/// The timers of one global object.
+#[jstraceable]
+pub struct TimerManager {
+    active_timers: Traceable<RefCell<HashMap<TimerId, TimerData>>>,
+    next_timer_handle: Traceable<Cell<i32>>,
+    /// The global object the timers are set on.
+    source: Untraceable<Cell<TimerSource>>,
+    /// The scheduler that fires the timers.
+    scheduler: Untraceable<TimerSchedulerChan>,
+}
+
+impl Drop for TimerManager {
+    fn drop(&mut self) {
+        self.cancel_all();
+    }
+}
+
+impl TimerManager {
+    /// Creates the timers of the global object `source`, which `scheduler`
+    /// fires.
+    pub fn new(source: TimerSource, scheduler: TimerSchedulerChan) -> TimerManager {
+        TimerManager {
+            active_timers: Traceable::new(RefCell::new(HashMap::new())),
+            next_timer_handle: Traceable::new(Cell::new(0)),
+            source: Untraceable::new(Cell::new(source)),
+            scheduler: Untraceable::new(scheduler),
+        }
+    }
+
+    fn schedule(&self, timer_id: TimerId, timeout: u64) {
+        let msg = ScheduleTimerMsg(self.source.deref().get(), timer_id, timeout);
+        let _ = self.scheduler.deref().send_opt(msg);
+    }
+
+    fn cancel(&self, timer_id: TimerId) {
+        let msg = CancelTimerMsg(self.source.deref().get(), timer_id);
+        let _ = self.scheduler.deref().send_opt(msg);
+    }
+
+    /// Starts a timer that calls `callback` after `timeout` milliseconds, or
+    /// every `timeout` milliseconds if `is_interval`, and returns its handle.
+    pub fn set_timeout_or_interval(&self, callback: JSVal, timeout: i32,
+                                   is_interval: bool) -> i32 {
+        let timeout = cmp::max(0, timeout) as u64;
+        let handle = self.next_timer_handle.deref().get();
+        self.next_timer_handle.deref().set(handle + 1);
+
+        let timer_id = TimerId(handle);
+        let data = TimerData {
+            is_interval: is_interval,
+            timeout: timeout,
+            funval: Traceable::new(callback),
+        };
+        self.active_timers.deref().borrow_mut().insert(timer_id, data);
+        self.schedule(timer_id, timeout);
+        handle
+    }
+
+    pub fn clear_timeout_or_interval(&self, handle: i32) {
+        let timer_id = TimerId(handle);
+        if self.active_timers.deref().borrow_mut().remove(&timer_id) {
+            self.cancel(timer_id);
+        }
+    }
+
+    /// Cancels every timer, for a global that is going away.
+    pub fn cancel_all(&self) {
+        let mut timers = self.active_timers.deref().borrow_mut();
+        for (timer_id, _) in timers.iter() {
+            self.cancel(*timer_id);
+        }
+        timers.clear();
+    }
+
+    /// Makes the timers set from now on fire for `source`, once the window
+    /// shows the page of another pipeline. The timers set before are
+    /// cancelled.
+    pub fn set_source(&self, source: TimerSource) {
+        self.cancel_all();
+        self.source.deref().set(source);
+    }
+
+    /// Calls the callback of the timer `timer_id` with `this` as its this
+    /// value, unless the timer was cleared in the meantime. Intervals are
+    /// scheduled again once the callback returns.
+    pub fn fire_timer<T: Reflectable>(&self, timer_id: TimerId, this: JSRef<T>, cx: *mut JSContext) {
+        let this_value = this.reflector().get_jsobject();
+
+        let data = match self.active_timers.deref().borrow().find(&timer_id) {
+            None => return,
+            Some(data) => *data,
+        };
+
+        // TODO: Support extra arguments. This requires passing a `*JSVal` array as `argv`.
+        with_compartment(cx, this_value, || {
+            let mut rval = NullValue();
+            unsafe {
+                JS_CallFunctionValue(cx, this_value, *data.funval,
+                                     0, ptr::null_mut(), &mut rval);
+            }
+        });
+
+        // The callback may have cleared the timer itself.
+        if !self.active_timers.deref().borrow().contains_key(&timer_id) {
+            return;
+        }
+        if data.is_interval {
+            self.schedule(timer_id, data.timeout);
+        } else {
+            self.active_timers.deref().borrow_mut().remove(&timer_id);
+        }
+    }
+}
+
+#[test]
+fn test_cancelled_timers_dont_fire() {
+    let mut queue = TimerQueue::new();
+    let count = 10000i32;
+    for i in range(0, count) {
+        // Spread the deadlines out of order, some of them equal.
+        let due = ((i * 7919) % 1000) as u64;
+        queue.insert((FromWorker, TimerId(i)), due);
+    }
+    for i in range(0, count) {
+        if i % 2 == 1 {
+            queue.cancel(&(FromWorker, TimerId(i)));
+        }
+    }
+    assert_eq!(queue.len(), (count / 2) as uint);
+
+    let fired = queue.pop_due(1000);
+    assert_eq!(fired.len(), (count / 2) as uint);
+    let mut last = (0u64, 0i32);
+    for &(_, TimerId(i)) in fired.iter() {
+        assert_eq!(i % 2, 0);
+        // Timers fire by deadline, and in the order they were set when the
+        // deadlines are equal.
+        let current = (((i * 7919) % 1000) as u64, i);
+        assert!(last <= current);
+        last = current;
+    }
+    assert_eq!(queue.len(), 0);
+    assert_eq!(queue.next_deadline(), None);
+}
+
+#[test]
+fn test_only_due_timers_fire() {
+    let mut queue = TimerQueue::new();
+    queue.insert((FromWorker, TimerId(0)), 20);
+    queue.insert((FromWorker, TimerId(1)), 10);
+    queue.insert((FromWorker, TimerId(2)), 30);
+    assert_eq!(queue.pop_due(5), vec!());
+    assert_eq!(queue.pop_due(20), vec!((FromWorker, TimerId(1)), (FromWorker, TimerId(0))));
+    assert_eq!(queue.next_deadline(), Some(30));
+}
+
+#[test]
+fn test_earlier_deadline_rearms_wakeup() {
+    let mut queue = TimerQueue::new();
+    assert_eq!(queue.rearm(), KeepWakeup);
+
+    queue.insert((FromWorker, TimerId(0)), 100);
+    assert_eq!(queue.rearm(), ArmWakeup(100));
+
+    // A later deadline doesn't need the wakeup to change, an earlier one
+    // does.
+    queue.insert((FromWorker, TimerId(1)), 200);
+    assert_eq!(queue.rearm(), KeepWakeup);
+    queue.insert((FromWorker, TimerId(2)), 50);
+    assert_eq!(queue.rearm(), ArmWakeup(50));
+
+    // Neither does cancelling a timer that isn't first, but cancelling the
+    // first one does.
+    queue.cancel(&(FromWorker, TimerId(1)));
+    assert_eq!(queue.rearm(), KeepWakeup);
+    queue.cancel(&(FromWorker, TimerId(2)));
+    assert_eq!(queue.rearm(), ArmWakeup(100));
+
+    // Once the wakeup went off for the last timer, it isn't armed again.
+    queue.wakeup_fired();
+    assert_eq!(queue.pop_due(100), vec!((FromWorker, TimerId(0))));
+    assert_eq!(queue.rearm(), KeepWakeup);
+
+    // Cancelling the only timer disarms the wakeup.
+    queue.insert((FromWorker, TimerId(3)), 300);
+    assert_eq!(queue.rearm(), ArmWakeup(300));
+    queue.cancel(&(FromWorker, TimerId(3)));
+    assert_eq!(queue.rearm(), DisarmWakeup);
+}
@@ -11,6 +11,7 @@ extern crate devtools_traits;
 extern crate geom;
 extern crate "msg" as servo_msg;
 extern crate "net" as servo_net;
+extern crate "util" as servo_util;
 extern crate url;
 extern crate serialize;
 
@@ -21,12 +22,12 @@ extern crate serialize;
 
 use devtools_traits::DevtoolsControlChan;
 use servo_msg::constellation_msg::{ConstellationChan, PipelineId, Failure, WindowSizeData};
-use servo_msg::constellation_msg::SubpageId;
+use servo_msg::constellation_msg::{LoadData, SubpageId};
 use servo_msg::compositor_msg::ScriptListener;
 use servo_net::image_cache_task::ImageCacheTask;
 use servo_net::resource_task::ResourceTask;
+use servo_util::memory::MemoryReport;
 use std::any::Any;
-use url::Url;
 
 use geom::point::Point2D;
 
@@ -42,7 +43,7 @@ pub struct NewLayoutInfo {
 /// Messages sent from the constellation to the script task
 pub enum ConstellationControlMsg {
     /// Loads a new URL on the specified pipeline.
-    LoadMsg(PipelineId, Url),
+    LoadMsg(PipelineId, LoadData),
     /// Gives a channel and ID to a layout task, as well as the ID of that layout's parent
     AttachLayoutMsg(NewLayoutInfo),
     /// Window resized.  Sends a DOM event eventually, but first we combine events.
@@ -51,10 +52,20 @@ pub enum ConstellationControlMsg {
     ResizeInactiveMsg(PipelineId, WindowSizeData),
     /// Notifies the script that a pipeline should be closed.
     ExitPipelineMsg(PipelineId),
+    /// Notifies the script that the document of a pipeline was navigated
+    /// away from, and should be unloaded. The pipeline stays around for the
+    /// session history.
+    UnloadMsg(PipelineId),
+    /// Notifies the script that the document of a frame of the document of
+    /// the pipeline, the one with the given subpage id, finished loading.
+    SubframeLoadedMsg(PipelineId, SubpageId),
     /// Sends a DOM event.
     SendEventMsg(PipelineId, CompositorEvent),
     /// Notifies script that reflow is finished.
     ReflowCompleteMsg(PipelineId, uint),
+    /// Requests a report of the memory used by the document of the specified
+    /// pipeline and its layout.
+    CollectMemoryReportMsg(PipelineId, Sender<MemoryReport>),
 }
 
 /// Events from the compositor that the script task needs to know about
@@ -64,7 +75,19 @@ pub enum CompositorEvent {
     ClickEvent(uint, Point2D<f32>),
     MouseDownEvent(uint, Point2D<f32>),
     MouseUpEvent(uint, Point2D<f32>),
-    MouseMoveEvent(Point2D<f32>)
+    MouseMoveEvent(Point2D<f32>),
+    KeyEvent(Key),
+}
+
+/// The keyboard input the script task reacts to.
+pub enum Key {
+    /// A key that types the given character.
+    CharacterKey(char),
+    BackspaceKey,
+    TabKey,
+    EnterKey,
+    /// A key that cancels what the page shows, such as a modal dialog.
+    EscapeKey,
 }
 
 /// An opaque wrapper around script<->layout channels to avoid leaking message types into
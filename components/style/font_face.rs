@@ -18,7 +18,7 @@ static SUPPORTED_FORMATS: &'static [&'static str] = &["truetype", "opentype"];
 
 
 pub fn iter_font_face_rules_inner(rules: &[CSSRule], callback: |family: &str, source: &Url|) {
-    let device = &Device { media_type: Screen };  // TODO, use Print when printing
+    let device = &Device { media_type: Screen, viewport_size: None };  // TODO, use Print when printing
     for rule in rules.iter() {
         match *rule {
             CSSStyleRule(_) => {},
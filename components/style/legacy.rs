@@ -0,0 +1,509 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Presentational hints: the style given by the legacy attributes of HTML
+//! elements, such as `align`, `bgcolor` or `width`. They apply before the
+//! author style sheets, as if they were author rules with zero specificity.
+//! http://www.whatwg.org/html/#presentational-hints
+
+use std::ascii::StrAsciiExt;
+use sync::Arc;
+
+use cssparser::ast::Ident;
+use cssparser::{Color, RGBA, RGBAColor};
+
+use servo_util::namespace;
+use servo_util::smallvec::VecLike;
+use servo_util::str::{HTML_SPACE_CHARACTERS, is_ascii_digit, parse_unsigned_integer};
+
+use node::{TElement, TNode};
+use properties::longhands::{border_spacing, border_top_style, font_family, text_align};
+use properties::*;
+use selector_matching::DeclarationBlock;
+
+/// Parses a dimension value, as used by the width and height attributes.
+/// http://www.whatwg.org/html/#rules-for-parsing-dimension-values
+pub fn parse_legacy_length(input: &str) -> Option<specified::LengthOrPercentageOrAuto> {
+    let input = input.trim_left_chars(HTML_SPACE_CHARACTERS);
+    let integer_length = input.chars().take_while(is_ascii_digit).count();
+    if integer_length == 0 {
+        return None;
+    }
+    let fraction_length = if input.slice_from(integer_length).starts_with(".") {
+        input.slice_from(integer_length + 1).chars().take_while(is_ascii_digit).count()
+    } else {
+        0
+    };
+    let (number, rest) = if fraction_length == 0 {
+        (input.slice_to(integer_length), input.slice_from(integer_length))
+    } else {
+        let length = integer_length + 1 + fraction_length;
+        (input.slice_to(length), input.slice_from(length))
+    };
+
+    let value: f64 = from_str(number).unwrap();
+    if rest.starts_with("%") {
+        Some(specified::LPA_Percentage(value / 100.))
+    } else {
+        Some(specified::LPA_Length(specified::Length::from_px(value)))
+    }
+}
+
+/// Parses a dimension value, treating zero as an error, as some of the
+/// attributes do.
+fn parse_nonzero_legacy_length(input: &str) -> Option<specified::LengthOrPercentageOrAuto> {
+    match parse_legacy_length(input) {
+        Some(specified::LPA_Percentage(value)) if value == 0. => None,
+        Some(specified::LPA_Length(specified::Au_(Au(0)))) => None,
+        length => length,
+    }
+}
+
+/// Parses a color the way the bgcolor and color attributes do, which gives a
+/// color to just about any string.
+/// http://www.whatwg.org/html/#rules-for-parsing-a-legacy-colour-value
+pub fn parse_legacy_color(input: &str) -> Option<RGBA> {
+    fn rgb(red: uint, green: uint, blue: uint) -> RGBA {
+        RGBA {
+            red: red as f32 / 255.,
+            green: green as f32 / 255.,
+            blue: blue as f32 / 255.,
+            alpha: 1.,
+        }
+    }
+
+    // Steps 1-3.
+    if input.is_empty() {
+        return None;
+    }
+    let input = input.trim_chars(HTML_SPACE_CHARACTERS);
+    if input.eq_ignore_ascii_case("transparent") {
+        return None;
+    }
+
+    // Step 4.
+    match Color::parse(&Ident(input.to_string())) {
+        Ok(RGBAColor(rgba)) => return Some(rgba),
+        _ => (),
+    }
+
+    // Step 5.
+    if input.len() == 4 && input.starts_with("#") {
+        let digits: Vec<Option<uint>> = input.chars().skip(1).map(|c| c.to_digit(16)).collect();
+        match digits.as_slice() {
+            [Some(red), Some(green), Some(blue)] => return Some(rgb(red * 17, green * 17, blue * 17)),
+            _ => (),
+        }
+    }
+
+    // Steps 6-9.
+    let mut digits = vec!();
+    for c in input.chars() {
+        if c as u32 > 0xFFFF {
+            digits.push_all(['0', '0']);
+        } else {
+            digits.push(c);
+        }
+    }
+    digits.truncate(128);
+    if digits.as_slice().head() == Some(&'#') {
+        digits.remove(0);
+    }
+    let mut digits: Vec<uint> = digits.into_iter().map(|c| c.to_digit(16).unwrap_or(0)).collect();
+
+    // Steps 10-11.
+    while digits.is_empty() || digits.len() % 3 != 0 {
+        digits.push(0);
+    }
+    let length = digits.len() / 3;
+    let mut components: Vec<&[uint]> = digits.as_slice().chunks(length).collect();
+
+    // Steps 12-14.
+    if length > 8 {
+        components = components.into_iter().map(|component| component.slice_from(length - 8)).collect();
+    }
+    while components[0].len() > 2 && components.iter().all(|component| component[0] == 0) {
+        components = components.into_iter().map(|component| component.slice_from(1)).collect();
+    }
+    let components: Vec<uint> = components.into_iter().map(|component| {
+        component.iter().take(2).fold(0, |value, &digit| value * 16 + digit)
+    }).collect();
+
+    // Step 15.
+    Some(rgb(components[0], components[1], components[2]))
+}
+
+/// Parses the size attribute of font elements, returning a size from 1 to 7,
+/// where 3 is the default.
+/// http://www.whatwg.org/html/#rules-for-parsing-a-legacy-font-size
+pub fn parse_legacy_font_size(input: &str) -> Option<uint> {
+    let input = input.trim_left_chars(HTML_SPACE_CHARACTERS);
+    let (sign, digits) = if input.starts_with("+") {
+        (1, input.slice_from(1))
+    } else if input.starts_with("-") {
+        (-1, input.slice_from(1))
+    } else {
+        (0, input)
+    };
+    let digits: String = digits.chars().take_while(is_ascii_digit).collect();
+    if digits.is_empty() {
+        return None;
+    }
+
+    // Very long numbers end up clamped anyway.
+    let value: int = from_str(digits.as_slice()).unwrap_or(100);
+    let size = match sign {
+        1 => 3 + value,
+        -1 => 3 - value,
+        _ => value,
+    };
+    Some(if size > 7 { 7 } else if size < 1 { 1 } else { size as uint })
+}
+
+/// Returns the font size corresponding to a legacy font size, following the
+/// sizes of the font-size keywords.
+fn legacy_font_size_to_length(size: uint) -> specified::Length {
+    let medium = Au::from_px(16);
+    specified::Au_(match size {
+        1 => medium * 3 / 4,  // x-small
+        2 => medium * 8 / 9,  // small
+        3 => medium,  // medium
+        4 => medium * 6 / 5,  // large
+        5 => medium * 3 / 2,  // x-large
+        6 => medium * 2,  // xx-large
+        _ => medium * 3,  // xxx-large
+    })
+}
+
+/// Adds the presentational hints of `node` to `applicable_declarations`,
+/// returning whether there were any.
+pub fn synthesize_presentational_hints_for_legacy_attributes<'a,
+                                                             E: TElement<'a>,
+                                                             N: TNode<'a, E>,
+                                                             V: VecLike<DeclarationBlock>>(
+                                                             node: &N,
+                                                             applicable_declarations: &mut V)
+                                                             -> bool {
+    let hints = presentational_hints(node);
+    if hints.is_empty() {
+        return false;
+    }
+    applicable_declarations.vec_push(DeclarationBlock::from_declarations(Arc::new(hints)));
+    true
+}
+
+/// Whether `node` may have presentational hints. This only looks at which
+/// attributes are there, without parsing them, so that it's cheap enough for
+/// deciding whether elements can share their style; an attribute with a value
+/// that gives no hint still counts.
+pub fn has_presentational_hints<'a, E: TElement<'a>, N: TNode<'a, E>>(node: &N) -> bool {
+    let element = node.as_element();
+    if *element.get_namespace() != namespace::HTML {
+        return false;
+    }
+
+    let has_hints = match element.get_local_name().as_slice() {
+        "div" | "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => has_any_attr(&element, &["align"]),
+        "hr" => has_any_attr(&element, &["align", "width", "color", "noshade", "size"]),
+        "font" => has_any_attr(&element, &["color", "face", "size"]),
+        "body" => has_any_attr(&element, &["bgcolor", "text"]),
+        "img" => has_any_attr(&element, &["border", "width", "height"]),
+        "table" => has_any_attr(&element, &["border", "width", "height", "bgcolor", "cellspacing"]),
+        "td" | "th" => {
+            has_any_attr(&element, &["width", "height", "bgcolor"]) ||
+                table_ancestor(node).map_or(false, |table| {
+                    has_any_attr(&table.as_element(), &["cellpadding"])
+                })
+        },
+        _ => false,
+    };
+    has_hints || (element.get_link().is_some() && body_ancestor(node).map_or(false, |body| {
+        has_any_attr(&body.as_element(), &["link"])
+    }))
+}
+
+fn has_any_attr<'a, E: TElement<'a>>(element: &E, names: &[&str]) -> bool {
+    names.iter().any(|name| element.get_attr(&namespace::Null, *name).is_some())
+}
+
+fn presentational_hints<'a, E: TElement<'a>, N: TNode<'a, E>>(node: &N) -> Vec<PropertyDeclaration> {
+    let mut hints = vec!();
+    let element = node.as_element();
+    if *element.get_namespace() != namespace::HTML {
+        return hints;
+    }
+
+    match element.get_local_name().as_slice() {
+        // http://www.whatwg.org/html/#flow-content-3
+        "div" | "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            match element.get_attr(&namespace::Null, "align") {
+                Some(align) => push_text_align(&mut hints, align),
+                None => (),
+            }
+        },
+
+        // http://www.whatwg.org/html/#the-hr-element-2
+        "hr" => {
+            match element.get_attr(&namespace::Null, "align") {
+                Some(align) => {
+                    let auto = specified::LPA_Auto;
+                    let zero = specified::LPA_Length(specified::Length::from_px(0.));
+                    let margins = match align.to_ascii_lower().as_slice() {
+                        "left" => Some((zero, auto)),
+                        "right" => Some((auto, zero)),
+                        "center" => Some((auto, auto)),
+                        _ => None,
+                    };
+                    match margins {
+                        Some((left, right)) => {
+                            hints.push(MarginLeftDeclaration(SpecifiedValue(left)));
+                            hints.push(MarginRightDeclaration(SpecifiedValue(right)));
+                        },
+                        None => (),
+                    }
+                },
+                None => (),
+            }
+            push_width(&mut hints, element.get_attr(&namespace::Null, "width"), true);
+
+            let color = element.get_attr(&namespace::Null, "color");
+            let solid = color.is_some() || element.get_attr(&namespace::Null, "noshade").is_some();
+            match color.and_then(parse_legacy_color) {
+                Some(color) => {
+                    push_border_color(&mut hints, color);
+                    hints.push(BackgroundColorDeclaration(SpecifiedValue(RGBAColor(color))));
+                },
+                None => (),
+            }
+            if solid {
+                push_border_style(&mut hints, border_top_style::solid);
+            }
+
+            let size = element.get_attr(&namespace::Null, "size").and_then(|size| {
+                parse_unsigned_integer(size.chars())
+            });
+            match size {
+                Some(size) if solid => push_border_width(&mut hints, size as f64 / 2.),
+                Some(1) => {
+                    let zero = specified::Length::from_px(0.);
+                    hints.push(BorderBottomWidthDeclaration(SpecifiedValue(zero)));
+                },
+                Some(size) if size > 1 => {
+                    let height = specified::Length::from_px((size - 2) as f64);
+                    hints.push(HeightDeclaration(SpecifiedValue(specified::LPA_Length(height))));
+                },
+                _ => (),
+            }
+        },
+
+        // http://www.whatwg.org/html/#phrasing-content-3
+        "font" => {
+            push_color(&mut hints, element.get_attr(&namespace::Null, "color"));
+            match element.get_attr(&namespace::Null, "face") {
+                Some(face) => {
+                    let families: Vec<font_family::FontFamily> =
+                        face.split(',')
+                            .map(|family| family.trim_chars(HTML_SPACE_CHARACTERS))
+                            .filter(|family| !family.is_empty())
+                            .map(|family| font_family::FamilyName(family.to_string()))
+                            .collect();
+                    if !families.is_empty() {
+                        hints.push(FontFamilyDeclaration(SpecifiedValue(families)));
+                    }
+                },
+                None => (),
+            }
+            match element.get_attr(&namespace::Null, "size").and_then(parse_legacy_font_size) {
+                Some(size) => {
+                    let size = legacy_font_size_to_length(size);
+                    hints.push(FontSizeDeclaration(SpecifiedValue(size)));
+                },
+                None => (),
+            }
+        },
+
+        // http://www.whatwg.org/html/#the-page
+        "body" => {
+            push_background_color(&mut hints, element.get_attr(&namespace::Null, "bgcolor"));
+            push_color(&mut hints, element.get_attr(&namespace::Null, "text"));
+        },
+
+        // http://www.whatwg.org/html/#images-3
+        "img" => {
+            let border = element.get_attr(&namespace::Null, "border").and_then(|border| {
+                parse_unsigned_integer(border.chars())
+            });
+            match border {
+                Some(border) => {
+                    push_border_width(&mut hints, border as f64);
+                    push_border_style(&mut hints, border_top_style::solid);
+                },
+                None => (),
+            }
+            push_width(&mut hints, element.get_attr(&namespace::Null, "width"), false);
+            push_height(&mut hints, element.get_attr(&namespace::Null, "height"), false);
+        },
+
+        // http://www.whatwg.org/html/#tables-2
+        "table" => {
+            let border = element.get_attr(&namespace::Null, "border").map(|border| {
+                // An empty or invalid border attribute means a 1px border.
+                parse_unsigned_integer(border.chars()).unwrap_or(1)
+            });
+            match border {
+                Some(border) if border > 0 => {
+                    push_border_width(&mut hints, border as f64);
+                    push_border_style(&mut hints, border_top_style::outset);
+                },
+                _ => (),
+            }
+            push_width(&mut hints, element.get_attr(&namespace::Null, "width"), true);
+            push_height(&mut hints, element.get_attr(&namespace::Null, "height"), true);
+            push_background_color(&mut hints, element.get_attr(&namespace::Null, "bgcolor"));
+            let spacing = element.get_attr(&namespace::Null, "cellspacing").and_then(|spacing| {
+                parse_unsigned_integer(spacing.chars())
+            });
+            match spacing {
+                Some(spacing) => {
+                    let spacing = specified::Length::from_px(spacing as f64);
+                    let spacing = border_spacing::SpecifiedValue {
+                        horizontal: spacing.clone(),
+                        vertical: spacing,
+                    };
+                    hints.push(BorderSpacingDeclaration(SpecifiedValue(spacing)));
+                },
+                None => (),
+            }
+        },
+        "td" | "th" => {
+            push_width(&mut hints, element.get_attr(&namespace::Null, "width"), true);
+            push_height(&mut hints, element.get_attr(&namespace::Null, "height"), true);
+            push_background_color(&mut hints, element.get_attr(&namespace::Null, "bgcolor"));
+
+            let padding = table_ancestor(node).and_then(|table| {
+                table.as_element().get_attr(&namespace::Null, "cellpadding").and_then(|padding| {
+                    parse_unsigned_integer(padding.chars())
+                })
+            });
+            match padding {
+                Some(padding) => {
+                    let padding = specified::LP_Length(specified::Length::from_px(padding as f64));
+                    hints.push(PaddingTopDeclaration(SpecifiedValue(padding)));
+                    hints.push(PaddingRightDeclaration(SpecifiedValue(padding)));
+                    hints.push(PaddingBottomDeclaration(SpecifiedValue(padding)));
+                    hints.push(PaddingLeftDeclaration(SpecifiedValue(padding)));
+                },
+                None => (),
+            }
+        },
+
+        _ => (),
+    }
+
+    // The link attribute of the body gives the color of the links in it.
+    if element.get_link().is_some() {
+        match body_ancestor(node) {
+            Some(body) => push_color(&mut hints, body.as_element().get_attr(&namespace::Null, "link")),
+            None => (),
+        }
+    }
+
+    hints
+}
+
+/// Returns the nearest table element containing `node`.
+fn table_ancestor<'a, E: TElement<'a>, N: TNode<'a, E>>(node: &N) -> Option<N> {
+    html_ancestor(node, "table")
+}
+
+/// Returns the body element containing `node`.
+fn body_ancestor<'a, E: TElement<'a>, N: TNode<'a, E>>(node: &N) -> Option<N> {
+    html_ancestor(node, "body")
+}
+
+fn html_ancestor<'a, E: TElement<'a>, N: TNode<'a, E>>(node: &N, local_name: &str) -> Option<N> {
+    let mut ancestor = node.parent_node();
+    loop {
+        match ancestor {
+            Some(node) => {
+                if node.is_element() {
+                    let element = node.as_element();
+                    if *element.get_namespace() == namespace::HTML &&
+                       element.get_local_name().as_slice() == local_name {
+                        return Some(node.clone());
+                    }
+                }
+                ancestor = node.parent_node();
+            },
+            None => return None,
+        }
+    }
+}
+
+fn push_text_align(hints: &mut Vec<PropertyDeclaration>, align: &str) {
+    let align = match align.to_ascii_lower().as_slice() {
+        "left" => text_align::left,
+        "right" => text_align::right,
+        "center" | "middle" => text_align::center,
+        "justify" => text_align::justify,
+        _ => return,
+    };
+    hints.push(TextAlignDeclaration(SpecifiedValue(align)));
+}
+
+fn push_width(hints: &mut Vec<PropertyDeclaration>, width: Option<&str>, ignore_zero: bool) {
+    let width = width.and_then(|width| {
+        if ignore_zero { parse_nonzero_legacy_length(width) } else { parse_legacy_length(width) }
+    });
+    match width {
+        Some(width) => hints.push(WidthDeclaration(SpecifiedValue(width))),
+        None => (),
+    }
+}
+
+fn push_height(hints: &mut Vec<PropertyDeclaration>, height: Option<&str>, ignore_zero: bool) {
+    let height = height.and_then(|height| {
+        if ignore_zero { parse_nonzero_legacy_length(height) } else { parse_legacy_length(height) }
+    });
+    match height {
+        Some(height) => hints.push(HeightDeclaration(SpecifiedValue(height))),
+        None => (),
+    }
+}
+
+fn push_color(hints: &mut Vec<PropertyDeclaration>, color: Option<&str>) {
+    match color.and_then(parse_legacy_color) {
+        Some(color) => hints.push(ColorDeclaration(SpecifiedValue(color))),
+        None => (),
+    }
+}
+
+fn push_background_color(hints: &mut Vec<PropertyDeclaration>, color: Option<&str>) {
+    match color.and_then(parse_legacy_color) {
+        Some(color) => hints.push(BackgroundColorDeclaration(SpecifiedValue(RGBAColor(color)))),
+        None => (),
+    }
+}
+
+fn push_border_width(hints: &mut Vec<PropertyDeclaration>, px: f64) {
+    let width = specified::Length::from_px(px);
+    hints.push(BorderTopWidthDeclaration(SpecifiedValue(width)));
+    hints.push(BorderRightWidthDeclaration(SpecifiedValue(width)));
+    hints.push(BorderBottomWidthDeclaration(SpecifiedValue(width)));
+    hints.push(BorderLeftWidthDeclaration(SpecifiedValue(width)));
+}
+
+fn push_border_style(hints: &mut Vec<PropertyDeclaration>, style: border_top_style::SpecifiedValue) {
+    hints.push(BorderTopStyleDeclaration(SpecifiedValue(style)));
+    hints.push(BorderRightStyleDeclaration(SpecifiedValue(style)));
+    hints.push(BorderBottomStyleDeclaration(SpecifiedValue(style)));
+    hints.push(BorderLeftStyleDeclaration(SpecifiedValue(style)));
+}
+
+fn push_border_color(hints: &mut Vec<PropertyDeclaration>, color: RGBA) {
+    hints.push(BorderTopColorDeclaration(SpecifiedValue(RGBAColor(color))));
+    hints.push(BorderRightColorDeclaration(SpecifiedValue(RGBAColor(color))));
+    hints.push(BorderBottomColorDeclaration(SpecifiedValue(RGBAColor(color))));
+    hints.push(BorderLeftColorDeclaration(SpecifiedValue(RGBAColor(color))));
+}
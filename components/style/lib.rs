@@ -39,9 +39,11 @@ pub use properties::{PropertyDeclarationBlock, parse_style_attribute};  // Style
 pub use properties::{CSSFloat, DeclaredValue, PropertyDeclarationParseResult};
 pub use properties::longhands;
 pub use node::{TElement, TNode};
+pub use legacy::has_presentational_hints;
 pub use selectors::{PseudoElement, Before, After, SelectorList, parse_selector_list_from_str};
 pub use selectors::{AttrSelector, NamespaceConstraint, SpecificNamespace, AnyNamespace};
 pub use selectors::{SimpleSelector,LocalNameSelector};
+pub use media_queries::{Device, Screen, MediaQueryList, parse_media_query_list_from_str};
 pub use cssparser::{Color, RGBA};
 
 mod stylesheets;
@@ -54,3 +56,4 @@ mod node;
 mod media_queries;
 mod parsing_utils;
 mod font_face;
+mod legacy;
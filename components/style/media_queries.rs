@@ -3,12 +3,15 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use std::ascii::StrAsciiExt;
-use cssparser::parse_rule_list;
+use cssparser::{parse_rule_list, tokenize};
 use cssparser::ast::*;
+use geom::size::Size2D;
 
 use errors::{ErrorLoggerIterator, log_css_error};
 use stylesheets::{CSSRule, CSSMediaRule, parse_style_rule, parse_nested_at_rule};
 use namespaces::NamespaceMap;
+use properties::common_types::specified;
+use servo_util::geometry::Au;
 use url::Url;
 
 
@@ -24,10 +27,11 @@ pub struct MediaQueryList {
     media_queries: Vec<MediaQuery>
 }
 
-// For now, this is a "Level 2 MQ", ie. a media type.
+// For now, this is a "Level 2 MQ", ie. a media type, with Level 3 width expressions.
 pub struct MediaQuery {
     media_type: MediaQueryType,
-    // TODO: Level 3 MQ expressions
+    expressions: Vec<Expression>,
+    // TODO: more Level 3 MQ features
 }
 
 
@@ -42,9 +46,33 @@ pub enum MediaType {
     Print,
 }
 
+/// A media feature expression such as `(min-width: 600px)`.
+pub enum Expression {
+    Width(Range<Au>),
+}
+
+pub enum Range<T> {
+    Min(T),
+    Max(T),
+    Exact(T),
+}
+
+impl<T: PartialOrd> Range<T> {
+    fn evaluate(&self, value: T) -> bool {
+        match *self {
+            Min(ref bound) => value >= *bound,
+            Max(ref bound) => value <= *bound,
+            Exact(ref bound) => value == *bound,
+        }
+    }
+}
+
 pub struct Device {
     pub media_type: MediaType,
-    // TODO: Level 3 MQ data: viewport size, etc.
+    /// The size of the viewport, or None if it is unknown, in which case no
+    /// expression matches.
+    pub viewport_size: Option<Size2D<Au>>,
+    // TODO: more Level 3 MQ data
 }
 
 
@@ -77,43 +105,116 @@ pub fn parse_media_query_list(input: &[ComponentValue]) -> MediaQueryList {
     let iter = &mut input.skip_whitespace();
     let mut next = iter.next();
     if next.is_none() {
-        return MediaQueryList{ media_queries: vec!(MediaQuery{media_type: All}) }
+        return MediaQueryList{ media_queries: vec!(MediaQuery{ media_type: All, expressions: vec!() }) }
     }
     let mut queries = vec!();
     loop {
-        let mq = match next {
-            Some(&Ident(ref value)) => {
-                match value.as_slice().to_ascii_lower().as_slice() {
-                    "screen" => Some(MediaQuery{ media_type: MediaType_(Screen) }),
-                    "print" => Some(MediaQuery{ media_type: MediaType_(Print) }),
-                    "all" => Some(MediaQuery{ media_type: All }),
-                    _ => None
-                }
-            },
-            _ => None
-        };
-        match iter.next() {
-            None => {
-                for mq in mq.into_iter() {
-                    queries.push(mq);
-                }
-                return MediaQueryList{ media_queries: queries }
-            },
-            Some(&Comma) => {
-                for mq in mq.into_iter() {
-                    queries.push(mq);
-                }
-            },
-            // Ingnore this comma-separated part
-            _ => loop {
+        let mut query = vec!();
+        loop {
+            match next {
+                None | Some(&Comma) => break,
+                Some(value) => query.push(value),
+            }
+            next = iter.next();
+        }
+        // Ignore the comma-separated parts that don't parse.
+        for mq in parse_media_query(query.as_slice()).into_iter() {
+            queries.push(mq);
+        }
+        if next.is_none() {
+            return MediaQueryList{ media_queries: queries }
+        }
+        next = iter.next();
+    }
+}
+
+pub fn parse_media_query_list_from_str(input: &str) -> MediaQueryList {
+    let tokens: Vec<ComponentValue> = tokenize(input).map(|(token, _)| token).collect();
+    parse_media_query_list(tokens.as_slice())
+}
+
+fn parse_media_query(input: &[&ComponentValue]) -> Option<MediaQuery> {
+    let mut iter = input.iter().map(|value| *value);
+    let mut expressions = vec!();
+    let media_type = match iter.next() {
+        Some(&Ident(ref value)) => {
+            let mut value = value.as_slice().to_ascii_lower();
+            if value.as_slice() == "only" {
                 match iter.next() {
-                    Some(&Comma) => break,
-                    None => return MediaQueryList{ media_queries: queries },
-                    _ => (),
+                    Some(&Ident(ref media_type)) => value = media_type.as_slice().to_ascii_lower(),
+                    _ => return None,
                 }
+            }
+            let media_type = match value.as_slice() {
+                "screen" => MediaType_(Screen),
+                "print" => MediaType_(Print),
+                "all" => All,
+                _ => return None,
+            };
+            match iter.next() {
+                None => return Some(MediaQuery{ media_type: media_type, expressions: expressions }),
+                Some(&Ident(ref value)) if value.as_slice().eq_ignore_ascii_case("and") => (),
+                _ => return None,
+            }
+            media_type
+        },
+        Some(&ParenthesisBlock(ref block)) => {
+            expressions.push(match parse_expression(block.as_slice()) {
+                Some(expression) => expression,
+                None => return None,
+            });
+            match iter.next() {
+                None => return Some(MediaQuery{ media_type: All, expressions: expressions }),
+                Some(&Ident(ref value)) if value.as_slice().eq_ignore_ascii_case("and") => (),
+                _ => return None,
+            }
+            All
+        },
+        _ => return None,
+    };
+    loop {
+        match iter.next() {
+            Some(&ParenthesisBlock(ref block)) => {
+                expressions.push(match parse_expression(block.as_slice()) {
+                    Some(expression) => expression,
+                    None => return None,
+                });
             },
+            _ => return None,
+        }
+        match iter.next() {
+            None => return Some(MediaQuery{ media_type: media_type, expressions: expressions }),
+            Some(&Ident(ref value)) if value.as_slice().eq_ignore_ascii_case("and") => (),
+            _ => return None,
         }
-        next = iter.next();
+    }
+}
+
+fn parse_expression(input: &[ComponentValue]) -> Option<Expression> {
+    let iter = &mut input.skip_whitespace();
+    let name = match iter.next() {
+        Some(&Ident(ref name)) => name.as_slice().to_ascii_lower(),
+        _ => return None,
+    };
+    match iter.next() {
+        Some(&Colon) => (),
+        _ => return None,
+    }
+    let width = match iter.next().map(specified::Length::parse_non_negative) {
+        // Relative lengths are relative to the initial font size.
+        Some(Ok(specified::Au_(width))) => width,
+        Some(Ok(specified::Em(value))) => Au::from_frac_px(value * 16.),
+        Some(Ok(specified::Ex(value))) => Au::from_frac_px(value * 8.),
+        _ => return None,
+    };
+    if iter.next().is_some() {
+        return None
+    }
+    match name.as_slice() {
+        "min-width" => Some(Width(Min(width))),
+        "max-width" => Some(Width(Max(width))),
+        "width" => Some(Width(Exact(width))),
+        _ => None,
     }
 }
 
@@ -121,11 +222,60 @@ pub fn parse_media_query_list(input: &[ComponentValue]) -> MediaQueryList {
 impl MediaQueryList {
     pub fn evaluate(&self, device: &Device) -> bool {
         self.media_queries.iter().any(|mq| {
-            match mq.media_type {
+            let media_type_matches = match mq.media_type {
                 MediaType_(media_type) => media_type == device.media_type,
                 All => true,
-            }
-            // TODO: match Level 3 expressions
+            };
+            media_type_matches && mq.expressions.iter().all(|expression| {
+                match (expression, device.viewport_size) {
+                    (&Width(ref range), Some(viewport_size)) => range.evaluate(viewport_size.width),
+                    (_, None) => false,
+                }
+            })
         })
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use geom::size::Size2D;
+    use servo_util::geometry::Au;
+    use super::{Device, Screen, Print, parse_media_query_list_from_str};
+
+    fn matches(query: &str, media_type: super::MediaType, width: Option<int>) -> bool {
+        let device = Device {
+            media_type: media_type,
+            viewport_size: width.map(|width| Size2D(Au::from_px(width), Au::from_px(600))),
+        };
+        parse_media_query_list_from_str(query).evaluate(&device)
+    }
+
+    #[test]
+    fn test_media_types() {
+        assert!(matches("", Screen, None))
+        assert!(matches("screen", Screen, None))
+        assert!(matches("print, SCREEN", Screen, None))
+        assert!(!matches("print", Screen, None))
+        assert!(matches("only screen", Screen, None))
+        assert!(matches("print", Print, None))
+        assert!(!matches("tv", Screen, None))
+    }
+
+    #[test]
+    fn test_width_expressions() {
+        assert!(matches("(min-width: 600px)", Screen, Some(800)))
+        assert!(!matches("(min-width: 600px)", Screen, Some(400)))
+        assert!(matches("(max-width: 40em)", Screen, Some(640)))
+        assert!(!matches("(max-width: 40em)", Screen, Some(641)))
+        assert!(matches("screen and (width: 800px)", Screen, Some(800)))
+        assert!(!matches("print and (min-width: 0)", Screen, Some(800)))
+        assert!(matches("(min-width: 600px) and (max-width: 900px)", Screen, Some(800)))
+        assert!(!matches("(min-width: 600px) and (max-width: 700px)", Screen, Some(800)))
+        assert!(matches("(min-width: 1000px), (max-width: 900px)", Screen, Some(800)))
+        assert!(!matches("(min-width: 600px)", Screen, None))
+        assert!(!matches("(min-width: -1px), (min-height: 1px), (min-width 1px)", Screen, Some(800)))
+        assert!(!matches("screen (min-width: 1px)", Screen, Some(800)))
+        assert!(!matches("screen and", Screen, Some(800)))
+    }
+}
@@ -29,8 +29,16 @@ pub trait TElement<'a> {
     fn get_local_name<'b>(&'b self) -> &'b Atom;
     fn get_namespace<'b>(&'b self) -> &'b Namespace;
     fn get_hover_state(&self) -> bool;
+    fn get_active_state(&self) -> bool;
+    fn get_focus_state(&self) -> bool;
     fn get_id(&self) -> Option<Atom>;
     fn get_disabled_state(&self) -> bool;
     fn get_enabled_state(&self) -> bool;
-    fn has_class(&self, name: &str) -> bool;
+    fn get_checked_state(&self) -> bool;
+    fn get_indeterminate_state(&self) -> bool;
+    fn get_target_state(&self) -> bool;
+    fn has_class(&self, name: &Atom) -> bool;
+
+    /// Calls `callback` with each class of the element, in order.
+    fn each_class(&self, callback: |&Atom|);
 }
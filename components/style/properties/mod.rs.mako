@@ -1044,6 +1044,64 @@ pub mod longhands {
 
     ${single_keyword("table-layout", "auto fixed")}
 
+    ${new_style_struct("InheritedTable", is_inherited=True)}
+
+    <%self:longhand name="border-spacing">
+        pub mod computed_value {
+            use super::super::Au;
+
+            #[deriving(PartialEq, Clone)]
+            pub struct T {
+                pub horizontal: Au,
+                pub vertical: Au,
+            }
+        }
+
+        #[deriving(Clone)]
+        pub struct SpecifiedValue {
+            pub horizontal: specified::Length,
+            pub vertical: specified::Length,
+        }
+
+        #[inline]
+        pub fn get_initial_value() -> computed_value::T {
+            computed_value::T {
+                horizontal: Au(0),
+                vertical: Au(0),
+            }
+        }
+
+        #[inline]
+        pub fn to_computed_value(value: SpecifiedValue, context: &computed::Context)
+                                 -> computed_value::T {
+            computed_value::T {
+                horizontal: computed::compute_Au(value.horizontal, context),
+                vertical: computed::compute_Au(value.vertical, context),
+            }
+        }
+
+        /// One length gives both spacings, two give the horizontal one and
+        /// then the vertical one.
+        pub fn parse(input: &[ComponentValue], _: &Url) -> Result<SpecifiedValue, ()> {
+            let mut input_iter = input.skip_whitespace();
+            let horizontal = match input_iter.next() {
+                Some(value) => try!(specified::Length::parse_non_negative(value)),
+                None => return Err(()),
+            };
+            let vertical = match input_iter.next() {
+                Some(value) => try!(specified::Length::parse_non_negative(value)),
+                None => horizontal.clone(),
+            };
+            if input_iter.next().is_some() {
+                return Err(())
+            }
+            Ok(SpecifiedValue {
+                horizontal: horizontal,
+                vertical: vertical,
+            })
+        }
+    </%self:longhand>
+
     // CSS 2.1, Section 18 - User interface
 
 
@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use std::ascii::StrAsciiExt;
 use std::collections::hashmap::HashMap;
 use std::hash::Hash;
 use std::num::div_rem;
@@ -15,6 +16,7 @@ use servo_util::namespace;
 use servo_util::smallvec::VecLike;
 use servo_util::sort;
 
+use legacy::synthesize_presentational_hints_for_legacy_attributes;
 use media_queries::{Device, Screen};
 use node::{TElement, TNode};
 use properties::{PropertyDeclaration, PropertyDeclarationBlock};
@@ -107,20 +109,14 @@ impl SelectorMap {
             None => {}
         }
 
-        match element.get_attr(&namespace::Null, "class") {
-            Some(ref class_attr) => {
-                // FIXME: Store classes pre-split as atoms to make the loop below faster.
-                for class in class_attr.split(SELECTOR_WHITESPACE) {
-                    SelectorMap::get_matching_rules_from_hash(node,
-                                                              parent_bf,
-                                                              &self.class_hash,
-                                                              &Atom::from_slice(class),
-                                                              matching_rules_list,
-                                                              shareable);
-                }
-            }
-            None => {}
-        }
+        element.each_class(|class| {
+            SelectorMap::get_matching_rules_from_hash(node,
+                                                      parent_bf,
+                                                      &self.class_hash,
+                                                      class,
+                                                      matching_rules_list,
+                                                      shareable);
+        });
 
         let local_name_hash = if node.is_html_element_in_html_document() {
             &self.lower_local_name_hash
@@ -332,7 +328,7 @@ impl Stylist {
             };
         );
 
-        let device = &Device { media_type: Screen };  // TODO, use Print when printing
+        let device = &Device { media_type: Screen, viewport_size: None };  // TODO, use Print when printing
         iter_stylesheet_style_rules(&stylesheet, device, |style_rule| {
             append!(style_rule, normal);
             append!(style_rule, important);
@@ -370,12 +366,20 @@ impl Stylist {
 
         let mut shareable = true;
 
-        // Step 1: Normal rules.
+        // Step 1: Normal rules, including presentational hints.
         map.user_agent.normal.get_all_matching_rules(element,
                                                      parent_bf,
                                                      applicable_declarations,
                                                      &mut shareable);
         map.user.normal.get_all_matching_rules(element, parent_bf, applicable_declarations, &mut shareable);
+
+        // Presentational hints come before the author rules, and don't apply
+        // to pseudo-elements.
+        if pseudo_element.is_none() &&
+           synthesize_presentational_hints_for_legacy_attributes(element, applicable_declarations) {
+            shareable = false;
+        }
+
         map.author.normal.get_all_matching_rules(element, parent_bf, applicable_declarations, &mut shareable);
 
         // Step 2: Normal style attributes.
@@ -713,10 +717,9 @@ pub fn matches_simple_selector<'a, E:TElement<'a>,
                 attr == *id
             })
         }
-        // TODO: cache and intern class names on elements.
         ClassSelector(ref class) => {
             let element = element.as_element();
-            element.has_class(class.as_slice())
+            element.has_class(class)
         }
 
         AttrExists(ref attr) => {
@@ -792,6 +795,18 @@ pub fn matches_simple_selector<'a, E:TElement<'a>,
             let elem = element.as_element();
             elem.get_hover_state()
         },
+        // http://www.whatwg.org/html/#selector-active
+        Active => {
+            *shareable = false;
+            let elem = element.as_element();
+            elem.get_active_state()
+        },
+        // http://www.whatwg.org/html/#selector-focus
+        Focus => {
+            *shareable = false;
+            let elem = element.as_element();
+            elem.get_focus_state()
+        },
         // http://www.whatwg.org/html/#selector-disabled
         Disabled => {
             *shareable = false;
@@ -804,6 +819,24 @@ pub fn matches_simple_selector<'a, E:TElement<'a>,
             let elem = element.as_element();
             elem.get_enabled_state()
         },
+        // http://www.whatwg.org/html/#selector-checked
+        Checked => {
+            *shareable = false;
+            let elem = element.as_element();
+            elem.get_checked_state()
+        },
+        // http://www.whatwg.org/html/#selector-indeterminate
+        Indeterminate => {
+            *shareable = false;
+            let elem = element.as_element();
+            elem.get_indeterminate_state()
+        },
+        // http://www.whatwg.org/html/#selector-target
+        Target => {
+            *shareable = false;
+            let elem = element.as_element();
+            elem.get_target_state()
+        },
         FirstChild => {
             *shareable = false;
             matches_first_child(element)
@@ -822,6 +855,12 @@ pub fn matches_simple_selector<'a, E:TElement<'a>,
             matches_root(element)
         }
 
+        // http://www.whatwg.org/html/#selector-lang
+        Lang(ref lang) => {
+            *shareable = false;
+            matches_lang(element, lang.as_slice())
+        }
+
         NthChild(a, b) => {
             *shareable = false;
             matches_generic_nth_child(element, a, b, false, false)
@@ -932,6 +971,38 @@ fn matches_root<'a, E:TElement<'a>,N:TNode<'a, E>>(element: &N) -> bool {
     }
 }
 
+/// Whether the language of `element` is `lang` or a sublanguage of it. The
+/// language is given by the nearest `xml:lang` or `lang` attribute on the
+/// element or its ancestors; `lang` is lower case.
+fn matches_lang<'a, E:TElement<'a>,N:TNode<'a, E>>(element: &N, lang: &str) -> bool {
+    let mut node = element.clone();
+    loop {
+        if node.is_element() {
+            let element = node.as_element();
+            let language = element.get_attr(&namespace::XML, "lang").or_else(|| {
+                if *element.get_namespace() == namespace::HTML {
+                    element.get_attr(&namespace::Null, "lang")
+                } else {
+                    None
+                }
+            });
+            match language {
+                Some(language) => {
+                    let language = language.to_ascii_lower();
+                    return language.as_slice() == lang ||
+                           (language.as_slice().starts_with(lang) &&
+                            language.as_slice().char_at(lang.len()) == '-')
+                },
+                None => (),
+            }
+        }
+        match node.parent_node() {
+            Some(parent) => node = parent,
+            None => return false,
+        }
+    }
+}
+
 #[inline]
 fn matches_first_child<'a, E:TElement<'a>,N:TNode<'a, E>>(element: &N) -> bool {
     let mut node = element.clone();
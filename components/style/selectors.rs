@@ -10,6 +10,7 @@ use cssparser::ast::*;
 use cssparser::{tokenize, parse_nth};
 
 use servo_util::atom::Atom;
+use servo_util::memory::HeapSizeOf;
 use servo_util::namespace::Namespace;
 use servo_util::namespace;
 
@@ -46,6 +47,22 @@ pub struct CompoundSelector {
     pub next: Option<(Box<CompoundSelector>, Combinator)>,  // c.next is left of c
 }
 
+impl HeapSizeOf for Selector {
+    fn heap_size_of_children(&self) -> uint {
+        self.compound_selectors.heap_size_of_children()
+    }
+}
+
+impl HeapSizeOf for CompoundSelector {
+    fn heap_size_of_children(&self) -> uint {
+        let next = match self.next {
+            None => 0,
+            Some((ref next, _)) => next.heap_size_of_children(),
+        };
+        self.simple_selectors.heap_size_of_children() + next
+    }
+}
+
 #[deriving(PartialEq, Clone)]
 pub enum Combinator {
     Child,  //  >
@@ -76,12 +93,17 @@ pub enum SimpleSelector {
     Link,
     Visited,
     Hover,
+    Active,
+    Focus,
     Disabled,
     Enabled,
+    Checked,
+    Indeterminate,
+    Target,
     FirstChild, LastChild, OnlyChild,
 //    Empty,
     Root,
-//    Lang(String),
+    Lang(String),
     NthChild(i32, i32),
     NthLastChild(i32, i32),
     NthOfType(i32, i32),
@@ -92,6 +114,22 @@ pub enum SimpleSelector {
     // ...
 }
 
+/// Doesn't count the URLs of namespaces, which few selectors have.
+impl HeapSizeOf for SimpleSelector {
+    fn heap_size_of_children(&self) -> uint {
+        match *self {
+            AttrEqual(_, ref value) | AttrIncludes(_, ref value) | AttrPrefixMatch(_, ref value) |
+            AttrSubstringMatch(_, ref value) | AttrSuffixMatch(_, ref value) |
+            Lang(ref value) => value.heap_size_of_children(),
+            AttrDashMatch(_, ref value, ref dashed) => {
+                value.heap_size_of_children() + dashed.heap_size_of_children()
+            }
+            Negation(ref selectors) => selectors.heap_size_of_children(),
+            _ => 0,
+        }
+    }
+}
+
 #[deriving(Eq, PartialEq, Clone, Hash)]
 pub struct LocalName {
     pub name: Atom,
@@ -100,8 +138,8 @@ pub struct LocalName {
 
 #[deriving(Eq, PartialEq, Clone, Hash)]
 pub struct AttrSelector {
-    pub name: String,
-    pub lower_name: String,
+    pub name: Atom,
+    pub lower_name: Atom,
     pub namespace: NamespaceConstraint,
 }
 
@@ -227,9 +265,10 @@ fn compute_specificity(mut selector: &CompoundSelector,
                 &ClassSelector(..)
                 | &AttrExists(..) | &AttrEqual(..) | &AttrIncludes(..) | &AttrDashMatch(..)
                 | &AttrPrefixMatch(..) | &AttrSubstringMatch(..) | &AttrSuffixMatch(..)
-                | &AnyLink | &Link | &Visited | &Hover | &Disabled | &Enabled
-                | &FirstChild | &LastChild | &OnlyChild | &Root
-//                | &Empty | &Lang(*)
+                | &AnyLink | &Link | &Visited | &Hover | &Active | &Focus
+                | &Disabled | &Enabled | &Checked | &Indeterminate | &Target
+                | &FirstChild | &LastChild | &OnlyChild | &Root | &Lang(..)
+//                | &Empty
                 | &NthChild(..) | &NthLastChild(..)
                 | &NthOfType(..) | &NthLastOfType(..)
                 | &FirstOfType | &LastOfType | &OnlyOfType
@@ -442,8 +481,8 @@ fn parse_attribute_selector(content: Vec<ComponentValue>, namespaces: &Namespace
         Some((_, None)) => fail!("Implementation error, this should not happen."),
         Some((namespace, Some(local_name))) => AttrSelector {
             namespace: namespace,
-            lower_name: local_name.as_slice().to_ascii_lower(),
-            name: local_name,
+            lower_name: Atom::from_slice(local_name.as_slice().to_ascii_lower().as_slice()),
+            name: Atom::from_slice(local_name.as_slice()),
         },
     };
     skip_whitespace(iter);
@@ -480,8 +519,13 @@ fn parse_simple_pseudo_class(name: &str) -> Result<SimpleSelector, ()> {
         "link" => Ok(Link),
         "visited" => Ok(Visited),
         "hover" => Ok(Hover),
+        "active" => Ok(Active),
+        "focus" => Ok(Focus),
         "disabled" => Ok(Disabled),
         "enabled" => Ok(Enabled),
+        "checked" => Ok(Checked),
+        "indeterminate" => Ok(Indeterminate),
+        "target" => Ok(Target),
         "first-child" => Ok(FirstChild),
         "last-child"  => Ok(LastChild),
         "only-child"  => Ok(OnlyChild),
@@ -499,7 +543,7 @@ fn parse_functional_pseudo_class(name: String, arguments: Vec<ComponentValue>,
                                  namespaces: &NamespaceMap, inside_negation: bool)
                                  -> Result<SimpleSelector, ()> {
     match name.as_slice().to_ascii_lower().as_slice() {
-//        "lang" => parse_lang(arguments),
+        "lang" => parse_lang(arguments),
         "nth-child"        => parse_nth(arguments.as_slice()).map(|(a, b)| NthChild(a, b)),
         "nth-last-child"   => parse_nth(arguments.as_slice()).map(|(a, b)| NthLastChild(a, b)),
         "nth-of-type"      => parse_nth(arguments.as_slice()).map(|(a, b)| NthOfType(a, b)),
@@ -522,16 +566,16 @@ fn parse_pseudo_element(name: String) -> Result<PseudoElement, ()> {
 }
 
 
-//fn parse_lang(arguments: vec!(ComponentValue)) -> Result<SimpleSelector, ()> {
-//    let mut iter = arguments.move_skip_whitespace();
-//    match iter.next() {
-//        Some(Ident(value)) => {
-//            if "" == value || iter.next().is_some() { None }
-//            else { Ok(Lang(value)) }
-//        },
-//        _ => Err(()),
-//    }
-//}
+fn parse_lang(arguments: Vec<ComponentValue>) -> Result<SimpleSelector, ()> {
+    let mut iter = arguments.move_skip_whitespace();
+    match iter.next() {
+        Some(Ident(value)) => {
+            if value.is_empty() || iter.next().is_some() { Err(()) }
+            else { Ok(Lang(value.into_ascii_lower())) }
+        },
+        _ => Err(()),
+    }
+}
 
 
 /// Level 3: Parse **one** simple_selector
@@ -4,6 +4,7 @@
 
 use std::iter::Iterator;
 use std::ascii::StrAsciiExt;
+use std::mem::size_of;
 use url::Url;
 
 use encoding::EncodingRef;
@@ -16,7 +17,8 @@ use errors::{ErrorLoggerIterator, log_css_error};
 use namespaces::{NamespaceMap, parse_namespace_rule};
 use media_queries::{MediaRule, parse_media_rule};
 use media_queries;
-use font_face::{FontFaceRule, parse_font_face_rule, iter_font_face_rules_inner};
+use font_face::{FontFaceRule, UrlSource, parse_font_face_rule, iter_font_face_rules_inner};
+use servo_util::memory::HeapSizeOf;
 
 
 pub struct Stylesheet {
@@ -39,6 +41,41 @@ pub struct StyleRule {
 }
 
 
+impl HeapSizeOf for Stylesheet {
+    fn heap_size_of_children(&self) -> uint {
+        self.rules.heap_size_of_children()
+    }
+}
+
+impl HeapSizeOf for CSSRule {
+    fn heap_size_of_children(&self) -> uint {
+        match *self {
+            CSSStyleRule(ref rule) => {
+                rule.selectors.heap_size_of_children() + rule.declarations.heap_size_of_children()
+            }
+            CSSMediaRule(ref rule) => rule.rules.heap_size_of_children(),
+            CSSFontFaceRule(ref rule) => {
+                rule.family.heap_size_of_children() +
+                    rule.sources.capacity() * size_of::<UrlSource>()
+            }
+        }
+    }
+}
+
+impl HeapSizeOf for properties::PropertyDeclarationBlock {
+    fn heap_size_of_children(&self) -> uint {
+        self.important.heap_size_of_children() + self.normal.heap_size_of_children()
+    }
+}
+
+/// Only the declaration itself is counted, not the few values that point
+/// somewhere else, like URLs.
+impl HeapSizeOf for properties::PropertyDeclaration {
+    fn heap_size_of_children(&self) -> uint {
+        0
+    }
+}
+
 impl Stylesheet {
     pub fn from_bytes_iter<I: Iterator<Vec<u8>>>(
             mut input: I, base_url: Url, protocol_encoding_label: Option<&str>,
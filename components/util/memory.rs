@@ -4,7 +4,12 @@
 
 //! Memory profiling functions.
 
+use atom::Atom;
+
 use libc::{c_char,c_int,c_void,size_t};
+use std::cell::RefCell;
+use std::collections::hashmap::HashMap;
+use std::hash::Hash;
 use std::io::timer::sleep;
 #[cfg(target_os="linux")]
 use std::io::File;
@@ -12,11 +17,13 @@ use std::mem::size_of;
 #[cfg(target_os="linux")]
 use std::os::page_size;
 use std::ptr::null_mut;
+use sync::Arc;
 use std::time::duration::Duration;
 use task::spawn_named;
 #[cfg(target_os="macos")]
 use task_info::task_basic_info::{virtual_size,resident_size};
 
+#[deriving(Clone)]
 pub struct MemoryProfilerChan(pub Sender<MemoryProfilerMsg>);
 
 impl MemoryProfilerChan {
@@ -29,12 +36,153 @@ impl MemoryProfilerChan {
 pub enum MemoryProfilerMsg {
     /// Message used to force print the memory profiling metrics.
     PrintMsg,
+    /// Gives the memory profiler something to ask for a report of what the
+    /// heap is used for each time it prints the metrics.
+    RegisterReporterMsg(Box<MemoryReporter + Send>),
     /// Tells the memory profiler to shut down.
     ExitMsg,
 }
 
+/// The heap memory owned by a value, not counting the value itself.
+///
+/// This is what the value allocated, rounded down to what it asked for: the
+/// slack the allocator adds isn't known here.
+pub trait HeapSizeOf {
+    fn heap_size_of_children(&self) -> uint;
+}
+
+macro_rules! plain_heap_size_of(
+    ($($ty:ty),+) => (
+        $(
+            impl HeapSizeOf for $ty {
+                fn heap_size_of_children(&self) -> uint {
+                    0
+                }
+            }
+        )+
+    )
+)
+
+plain_heap_size_of!(bool, char, u8, u16, u32, u64, uint, i8, i16, i32, i64, int, f32, f64)
+
+/// Atoms are interned, and the table that holds them isn't owned by anyone.
+impl HeapSizeOf for Atom {
+    fn heap_size_of_children(&self) -> uint {
+        0
+    }
+}
+
+impl HeapSizeOf for String {
+    fn heap_size_of_children(&self) -> uint {
+        self.capacity()
+    }
+}
+
+impl<T: HeapSizeOf> HeapSizeOf for Box<T> {
+    fn heap_size_of_children(&self) -> uint {
+        size_of::<T>() + (**self).heap_size_of_children()
+    }
+}
+
+/// The value is counted for every `Arc` that points to it, along with the
+/// two reference counts next to it.
+impl<T: HeapSizeOf + Send + Sync> HeapSizeOf for Arc<T> {
+    fn heap_size_of_children(&self) -> uint {
+        2 * size_of::<uint>() + size_of::<T>() + (**self).heap_size_of_children()
+    }
+}
+
+impl<T: HeapSizeOf> HeapSizeOf for Option<T> {
+    fn heap_size_of_children(&self) -> uint {
+        match *self {
+            None => 0,
+            Some(ref value) => value.heap_size_of_children(),
+        }
+    }
+}
+
+impl<T: HeapSizeOf> HeapSizeOf for RefCell<T> {
+    fn heap_size_of_children(&self) -> uint {
+        self.borrow().heap_size_of_children()
+    }
+}
+
+impl<T: HeapSizeOf> HeapSizeOf for Vec<T> {
+    fn heap_size_of_children(&self) -> uint {
+        self.iter().fold(self.capacity() * size_of::<T>(),
+                         |size, item| size + item.heap_size_of_children())
+    }
+}
+
+/// Each bucket of the table stores the hash of its key besides the entry.
+impl<K: Eq + Hash + HeapSizeOf, V: HeapSizeOf> HeapSizeOf for HashMap<K, V> {
+    fn heap_size_of_children(&self) -> uint {
+        let buckets = self.capacity() * (size_of::<u64>() + size_of::<K>() + size_of::<V>());
+        self.iter().fold(buckets, |size, (key, value)| {
+            size + key.heap_size_of_children() + value.heap_size_of_children()
+        })
+    }
+}
+
+/// What some of the heap is used for, in bytes.
+#[deriving(Clone, PartialEq, Show)]
+pub struct MemoryReport {
+    /// The nodes of the documents, and what they own that doesn't fall in
+    /// another category.
+    pub dom_nodes: uint,
+    /// The text of the documents and the values of their attributes.
+    pub strings: uint,
+    /// The event listeners of the nodes.
+    pub event_listeners: uint,
+    /// The stylesheets that layout styles the documents with.
+    pub stylesheets: uint,
+    /// The images that were loaded and decoded.
+    pub image_cache: uint,
+}
+
+impl MemoryReport {
+    pub fn new() -> MemoryReport {
+        MemoryReport {
+            dom_nodes: 0,
+            strings: 0,
+            event_listeners: 0,
+            stylesheets: 0,
+            image_cache: 0,
+        }
+    }
+
+    /// Adds the sizes of `other` to those of this report.
+    pub fn add(&mut self, other: &MemoryReport) {
+        self.dom_nodes += other.dom_nodes;
+        self.strings += other.strings;
+        self.event_listeners += other.event_listeners;
+        self.stylesheets += other.stylesheets;
+        self.image_cache += other.image_cache;
+    }
+
+    /// The number of bytes that are accounted for.
+    pub fn total(&self) -> uint {
+        self.dom_nodes + self.strings + self.event_listeners + self.stylesheets + self.image_cache
+    }
+
+    /// The bytes of a heap of `allocated` bytes that this report doesn't
+    /// account for.
+    pub fn unclassified(&self, allocated: u64) -> u64 {
+        let total = self.total() as u64;
+        if total > allocated { 0 } else { allocated - total }
+    }
+}
+
+/// Something that knows what some of the heap is used for.
+pub trait MemoryReporter {
+    /// Returns a report, or None if whatever it asks for the report went
+    /// away.
+    fn collect_report(&self) -> Option<MemoryReport>;
+}
+
 pub struct MemoryProfiler {
     pub port: Receiver<MemoryProfilerMsg>,
+    reporter: Option<Box<MemoryReporter + Send>>,
 }
 
 impl MemoryProfiler {
@@ -54,7 +202,7 @@ impl MemoryProfiler {
                 });
                 // Spawn the memory profiler.
                 spawn_named("Memory profiler", proc() {
-                    let memory_profiler = MemoryProfiler::new(port);
+                    let mut memory_profiler = MemoryProfiler::new(port);
                     memory_profiler.start();
                 });
             }
@@ -77,11 +225,12 @@ impl MemoryProfiler {
 
     pub fn new(port: Receiver<MemoryProfilerMsg>) -> MemoryProfiler {
         MemoryProfiler {
-            port: port
+            port: port,
+            reporter: None,
         }
     }
 
-    pub fn start(&self) {
+    pub fn start(&mut self) {
         loop {
             match self.port.recv_opt() {
                Ok(msg) => {
@@ -94,12 +243,16 @@ impl MemoryProfiler {
         }
     }
 
-    fn handle_msg(&self, msg: MemoryProfilerMsg) -> bool {
+    fn handle_msg(&mut self, msg: MemoryProfilerMsg) -> bool {
         match msg {
             PrintMsg => {
                 self.handle_print_msg();
                 true
             },
+            RegisterReporterMsg(reporter) => {
+                self.reporter = Some(reporter);
+                true
+            },
             ExitMsg => false
         }
     }
@@ -127,7 +280,8 @@ impl MemoryProfiler {
         // from the jemalloc documentation.
 
         // Total number of bytes allocated by the application.
-        MemoryProfiler::print_measurement("heap-allocated", get_jemalloc_stat("stats.allocated"));
+        let heap_allocated = get_jemalloc_stat("stats.allocated");
+        MemoryProfiler::print_measurement("heap-allocated", heap_allocated);
 
         // Total number of bytes in active pages allocated by the application.
         // This is a multiple of the page size, and greater than or equal to
@@ -139,6 +293,31 @@ impl MemoryProfiler {
         // |stats.active|. This does not include inactive chunks.
         MemoryProfiler::print_measurement("heap-mapped",    get_jemalloc_stat("stats.mapped"));
 
+        // What the allocated bytes are used for, as far as we know.
+        let report = match self.reporter {
+            Some(ref reporter) => reporter.collect_report(),
+            None => None,
+        };
+        match report {
+            Some(report) => {
+                let print_size = |path: &str, size: uint| {
+                    MemoryProfiler::print_measurement(path, Some(size as u64))
+                };
+                print_size("dom-nodes",       report.dom_nodes);
+                print_size("strings",         report.strings);
+                print_size("event-listeners", report.event_listeners);
+                print_size("stylesheets",     report.stylesheets);
+                print_size("image-cache",     report.image_cache);
+
+                // Everything else the application allocated.
+                MemoryProfiler::print_measurement("heap-unclassified",
+                                                  heap_allocated.map(|allocated| {
+                                                      report.unclassified(allocated)
+                                                  }));
+            }
+            None => {}
+        }
+
         println!("");
     }
 }
@@ -208,3 +387,40 @@ fn get_resident() -> Option<u64> {
     None
 }
 
+
+#[test]
+fn test_heap_size_of_collections() {
+    let mut string = String::with_capacity(16);
+    string.push_str("four");
+    assert_eq!(string.heap_size_of_children(), 16);
+
+    let strings = vec!(string.clone(), string);
+    assert_eq!(strings.heap_size_of_children(),
+               strings.capacity() * size_of::<String>() + 2 * 16);
+
+    let boxed = box 5u32;
+    assert_eq!(boxed.heap_size_of_children(), size_of::<u32>());
+    assert_eq!(Some(boxed).heap_size_of_children(), size_of::<u32>());
+    assert_eq!((None as Option<Box<u32>>).heap_size_of_children(), 0);
+}
+
+#[test]
+fn test_memory_report_unclassified() {
+    let mut report = MemoryReport::new();
+    assert_eq!(report.total(), 0);
+    assert_eq!(report.unclassified(100), 100);
+
+    report.dom_nodes = 10;
+    report.strings = 20;
+    let mut other = MemoryReport::new();
+    other.dom_nodes = 1;
+    other.image_cache = 4;
+    report.add(&other);
+    assert_eq!(report.dom_nodes, 11);
+    assert_eq!(report.total(), 35);
+    assert_eq!(report.unclassified(100), 65);
+
+    // What was measured can be more than the allocator says, since the two
+    // aren't read at the same time.
+    assert_eq!(report.unclassified(30), 0);
+}
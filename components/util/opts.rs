@@ -85,8 +85,14 @@ pub struct Opts {
     /// and render.
     pub trace_layout: bool,
 
-    /// True if we should start a server to listen to remote Firefox devtools connections.
-    pub devtools_server: bool,
+    /// The port to start a server on that listens to remote Firefox devtools connections, if
+    /// any. The system picks one if it is 0.
+    pub devtools_port: Option<u16>,
+
+    /// True if the JavaScript garbage collector should run as often as it can,
+    /// to catch DOM objects that aren't rooted when they should be
+    /// (`--gc-zeal`). This only has an effect with a debug build of SpiderMonkey.
+    pub gc_zeal: bool,
 
     /// The initial requested size of the window.
     pub initial_window_size: TypedSize2D<ScreenPx, uint>,
@@ -124,7 +130,8 @@ pub fn from_cmdline_args(args: &[String]) -> Option<Opts> {
         getopts::optflag("", "show-debug-borders", "Show debugging borders on layers and tiles."),
         getopts::optflag("", "disable-text-aa", "Disable antialiasing for text rendering."),
         getopts::optflag("", "trace-layout", "Write layout trace to external file for debugging."),
-        getopts::optflag("", "devtools", "Start remote devtools server"),
+        getopts::optflagopt("", "devtools", "Start remote devtools server on port", "6000"),
+        getopts::optflag("", "gc-zeal", "Collect garbage as often as possible, to find rooting bugs."),
         getopts::optopt("", "resolution", "Set window resolution.", "800x600"),
         getopts::optflag("h", "help", "Print this message")
     );
@@ -207,6 +214,11 @@ pub fn from_cmdline_args(args: &[String]) -> Option<Opts> {
         bubble_inline_sizes_separately = true;
     }
 
+    // If only the flag is present, listen on the port Firefox connects to by default.
+    let devtools_port = opt_match.opt_default("devtools", "6000").map(|port| {
+        from_str(port.as_slice()).unwrap()
+    });
+
     let initial_window_size = match opt_match.opt_str("resolution") {
         Some(res_string) => {
             let res: Vec<uint> = res_string.as_slice().split('x').map(|r| from_str(r).unwrap()).collect();
@@ -236,7 +248,8 @@ pub fn from_cmdline_args(args: &[String]) -> Option<Opts> {
         show_debug_borders: opt_match.opt_present("show-debug-borders"),
         enable_text_antialiasing: !opt_match.opt_present("disable-text-aa"),
         trace_layout: trace_layout,
-        devtools_server: opt_match.opt_present("devtools"),
+        devtools_port: devtools_port,
+        gc_zeal: opt_match.opt_present("gc-zeal"),
         initial_window_size: initial_window_size,
     })
 }
@@ -254,3 +267,17 @@ pub fn experimental_enabled() -> bool {
         EXPERIMENTAL_ENABLED
     }
 }
+
+static mut GC_ZEAL_ENABLED: bool = false;
+
+pub fn set_gc_zeal_enabled(new_value: bool) {
+    unsafe {
+        GC_ZEAL_ENABLED = new_value;
+    }
+}
+
+pub fn gc_zeal_enabled() -> bool {
+    unsafe {
+        GC_ZEAL_ENABLED
+    }
+}
@@ -42,6 +42,19 @@ pub static HTML_SPACE_CHARACTERS: StaticCharVec = &[
     '\u000d',
 ];
 
+/// Whether `c` is a space character, as defined above.
+pub fn is_html_space(c: char) -> bool {
+    HTML_SPACE_CHARACTERS.iter().any(|&s| s == c)
+}
+
+/// Whether `c` is one of the ASCII digits, U+0030 to U+0039.
+pub fn is_ascii_digit(c: &char) -> bool {
+    match *c {
+        '0'..'9' => true,
+        _ => false,
+    }
+}
+
 pub fn split_html_space_chars<'a>(s: &'a str) -> Filter<'a, &'a str, CharSplits<'a, StaticCharVec>> {
     s.split(HTML_SPACE_CHARACTERS).filter(|&split| !split.is_empty())
 }
@@ -50,14 +63,6 @@ pub fn split_html_space_chars<'a>(s: &'a str) -> Filter<'a, &'a str, CharSplits<
 /// <http://www.whatwg.org/html/#rules-for-parsing-integers> or
 /// <http://www.whatwg.org/html/#rules-for-parsing-non-negative-integers>.
 fn do_parse_integer<T: Iterator<char>>(input: T) -> Option<i64> {
-    fn is_ascii_digit(c: &char) -> bool {
-        match *c {
-            '0'..'9' => true,
-            _ => false,
-        }
-    }
-
-
     let mut input = input.skip_while(|c| {
         HTML_SPACE_CHARACTERS.iter().any(|s| s == c)
     }).peekable();
@@ -108,3 +113,312 @@ pub fn parse_unsigned_integer<T: Iterator<char>>(input: T) -> Option<u32> {
         result.to_u32()
     })
 }
+
+/// Parse a floating-point number according to
+/// <http://www.whatwg.org/html/#rules-for-parsing-floating-point-number-values>.
+pub fn parse_floating_point_number(input: &str) -> Option<f64> {
+    let mut input = input.chars().skip_while(|c| {
+        HTML_SPACE_CHARACTERS.iter().any(|s| s == c)
+    }).peekable();
+
+    let mut number = String::new();
+    match input.peek() {
+        Some(&'-') => {
+            number.push('-');
+            input.next();
+        },
+        Some(&'+') => {
+            input.next();
+        },
+        _ => (),
+    }
+
+    let mut has_digits = false;
+    while input.peek().map_or(false, is_ascii_digit) {
+        number.push(input.next().unwrap());
+        has_digits = true;
+    }
+    if !has_digits {
+        number.push('0');
+    }
+
+    if input.peek() == Some(&'.') {
+        input.next();
+        let mut fraction = String::new();
+        while input.peek().map_or(false, is_ascii_digit) {
+            fraction.push(input.next().unwrap());
+        }
+        if fraction.is_empty() {
+            // A lone full stop is only valid after some integer digits.
+            if !has_digits {
+                return None;
+            }
+        } else {
+            number.push('.');
+            number.push_str(fraction.as_slice());
+            has_digits = true;
+        }
+    }
+
+    if !has_digits {
+        return None;
+    }
+
+    match input.peek() {
+        Some(&'e') | Some(&'E') => {
+            input.next();
+            let mut exponent = String::new();
+            match input.peek() {
+                Some(&'-') => {
+                    exponent.push('-');
+                    input.next();
+                },
+                Some(&'+') => {
+                    input.next();
+                },
+                _ => (),
+            }
+            let mut has_exponent_digits = false;
+            while input.peek().map_or(false, is_ascii_digit) {
+                exponent.push(input.next().unwrap());
+                has_exponent_digits = true;
+            }
+            if has_exponent_digits {
+                number.push('e');
+                number.push_str(exponent.as_slice());
+            }
+        },
+        _ => (),
+    }
+
+    from_str::<f64>(number.as_slice()).and_then(|value| {
+        if value.is_finite() { Some(value) } else { None }
+    })
+}
+
+/// Parse a list of floating-point numbers according to
+/// <http://www.whatwg.org/html/#rules-for-parsing-a-list-of-floating-point-numbers>.
+/// Numbers may be separated by any mix of whitespace, commas and semicolons,
+/// and anything that doesn't parse as a number counts as zero.
+pub fn parse_floating_point_number_list(input: &str) -> Vec<f64> {
+    input.split(|c: char| {
+        c == ',' || c == ';' || HTML_SPACE_CHARACTERS.iter().any(|&s| s == c)
+    }).filter(|token| !token.is_empty()).map(|token| {
+        parse_floating_point_number(token).unwrap_or(0.)
+    }).collect()
+}
+
+/// Whether `input` is a valid floating-point number, as defined by
+/// <http://www.whatwg.org/html/#valid-floating-point-number>. Unlike the
+/// parsing rules, this doesn't allow leading whitespace, a leading plus sign
+/// or trailing garbage.
+pub fn is_valid_floating_point_number(input: &str) -> bool {
+    let mut input = input.chars().peekable();
+    if input.peek() == Some(&'-') {
+        input.next();
+    }
+
+    let mut integer_digits = 0u;
+    while input.peek().map_or(false, is_ascii_digit) {
+        input.next();
+        integer_digits += 1;
+    }
+
+    if input.peek() == Some(&'.') {
+        input.next();
+        let mut fraction_digits = 0u;
+        while input.peek().map_or(false, is_ascii_digit) {
+            input.next();
+            fraction_digits += 1;
+        }
+        if fraction_digits == 0 {
+            return false;
+        }
+    } else if integer_digits == 0 {
+        return false;
+    }
+
+    match input.peek() {
+        Some(&'e') | Some(&'E') => {
+            input.next();
+            match input.peek() {
+                Some(&'-') | Some(&'+') => { input.next(); },
+                _ => (),
+            }
+            let mut exponent_digits = 0u;
+            while input.peek().map_or(false, is_ascii_digit) {
+                input.next();
+                exponent_digits += 1;
+            }
+            if exponent_digits == 0 {
+                return false;
+            }
+        },
+        _ => (),
+    }
+
+    input.next().is_none()
+}
+
+/// Parses a fixed number of ASCII digits.
+fn parse_digits(input: &str, length: uint) -> Option<u32> {
+    if input.len() != length || !input.chars().all(|c| c >= '0' && c <= '9') {
+        return None;
+    }
+    from_str(input)
+}
+
+/// Returns the number of days in the given month of the proleptic Gregorian
+/// calendar.
+pub fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        _ if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        _ => 28,
+    }
+}
+
+/// Parse a date string according to
+/// <http://www.whatwg.org/html/#parse-a-date-string>, returning the year,
+/// month and day.
+pub fn parse_date_string(input: &str) -> Option<(u32, u32, u32)> {
+    let components: Vec<&str> = input.split('-').collect();
+    if components.len() != 3 || components[0].len() < 4 {
+        return None;
+    }
+
+    let year = match parse_digits(components[0], components[0].len()) {
+        Some(year) if year > 0 => year,
+        _ => return None,
+    };
+    let month = match parse_digits(components[1], 2) {
+        Some(month) if month >= 1 && month <= 12 => month,
+        _ => return None,
+    };
+    match parse_digits(components[2], 2) {
+        Some(day) if day >= 1 && day <= days_in_month(year, month) => Some((year, month, day)),
+        _ => None,
+    }
+}
+
+/// Parse a time string according to
+/// <http://www.whatwg.org/html/#parse-a-time-string>, returning the number of
+/// milliseconds since midnight.
+pub fn parse_time_string(input: &str) -> Option<f64> {
+    let components: Vec<&str> = input.split(':').collect();
+    if components.len() < 2 || components.len() > 3 {
+        return None;
+    }
+
+    let hour = match parse_digits(components[0], 2) {
+        Some(hour) if hour <= 23 => hour,
+        _ => return None,
+    };
+    let minute = match parse_digits(components[1], 2) {
+        Some(minute) if minute <= 59 => minute,
+        _ => return None,
+    };
+
+    let mut milliseconds = 0f64;
+    let mut second = 0;
+    if components.len() == 3 {
+        let mut parts = components[2].splitn('.', 1);
+        second = match parts.next().and_then(|second| parse_digits(second, 2)) {
+            Some(second) if second <= 59 => second,
+            _ => return None,
+        };
+        match parts.next() {
+            Some(fraction) => {
+                if fraction.is_empty() || fraction.len() > 3 {
+                    return None;
+                }
+                let digits = match parse_digits(fraction, fraction.len()) {
+                    Some(digits) => digits,
+                    None => return None,
+                };
+                milliseconds = digits as f64 * 10f64.powi(3 - fraction.len() as i32);
+            },
+            None => (),
+        }
+    }
+
+    Some(((hour * 60 + minute) * 60 + second) as f64 * 1000. + milliseconds)
+}
+
+/// What an image candidate string says about its image.
+#[deriving(Clone, PartialEq, Show)]
+pub enum ImageDescriptor {
+    /// A pixel density, e.g. `2x`.
+    PixelDensity(f64),
+    /// The width of the image in pixels, e.g. `640w`.
+    Width(u32),
+}
+
+/// An image candidate string, as found in a srcset attribute.
+/// <http://www.whatwg.org/html/#image-candidate-string>
+#[deriving(Clone, PartialEq, Show)]
+pub struct ImageCandidate {
+    pub url: DOMString,
+    pub descriptor: ImageDescriptor,
+}
+
+/// Parse a srcset attribute according to
+/// <http://www.whatwg.org/html/#parse-a-srcset-attribute>, dropping the
+/// candidates whose descriptors don't parse. A candidate without descriptors
+/// has a pixel density of 1.
+pub fn parse_srcset(input: &str) -> Vec<ImageCandidate> {
+    fn parse_descriptors(input: &str) -> Option<ImageDescriptor> {
+        let mut descriptor = None;
+        for token in split_html_space_chars(input) {
+            if descriptor.is_some() {
+                return None;
+            }
+            let unit = token.char_at_reverse(token.len());
+            let value = token.slice_to(token.len() - unit.len_utf8_bytes());
+            descriptor = match unit {
+                'x' => match parse_floating_point_number(value) {
+                    Some(density) if density >= 0. => Some(PixelDensity(density)),
+                    _ => return None,
+                },
+                'w' => match parse_unsigned_integer(value.chars()) {
+                    Some(width) if width > 0 => Some(Width(width)),
+                    _ => return None,
+                },
+                _ => return None,
+            };
+        }
+        Some(descriptor.unwrap_or(PixelDensity(1.)))
+    }
+
+    let mut candidates = vec!();
+    let mut input = input;
+    loop {
+        input = input.trim_left_chars(|c: char| c == ',' || is_html_space(c));
+        if input.is_empty() {
+            return candidates;
+        }
+
+        let url_end = input.find(|c: char| is_html_space(c)).unwrap_or(input.len());
+        let url = input.slice_to(url_end);
+        input = input.slice_from(url_end);
+
+        // A URL followed by a comma has no descriptors.
+        let (url, descriptors) = if url.ends_with(",") {
+            (url.trim_right_chars(','), "")
+        } else {
+            let descriptors_end = input.find(',').unwrap_or(input.len());
+            let descriptors = input.slice_to(descriptors_end);
+            input = input.slice_from(descriptors_end);
+            (url, descriptors)
+        };
+
+        match parse_descriptors(descriptors) {
+            Some(descriptor) => candidates.push(ImageCandidate {
+                url: url.to_string(),
+                descriptor: descriptor,
+            }),
+            None => (),
+        }
+    }
+}
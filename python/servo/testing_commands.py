@@ -47,7 +47,7 @@ class MachCommands(CommandBase):
              category='testing')
     def test(self):
         test_start = time()
-        for t in ["tidy", "unit", "ref", "content", "wpt"]:
+        for t in ["tidy", "unit", "ref", "content", "devtools", "wpt"]:
             Registrar.dispatch("test-%s" % t, context=self.context)
         elapsed = time() - test_start
 
@@ -129,6 +129,22 @@ class MachCommands(CommandBase):
         print("Content tests completed in %0.2fs" % elapsed)
         return ret
 
+    @Command('test-devtools',
+             description='Run the devtools server test',
+             category='testing')
+    def test_devtools(self):
+        self.ensure_bootstrapped()
+        self.ensure_built_tests()
+
+        test_page = path.join(self.context.topdir, "tests", "devtools", "inspect.html")
+
+        test_start = time()
+        ret = self.run_test("devtoolstest", [test_page])
+        elapsed = time() - test_start
+
+        print("Devtools test completed in %0.2fs" % elapsed)
+        return ret
+
     @Command('test-tidy',
              description='Run the source code tidiness check',
              category='testing')
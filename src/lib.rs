@@ -86,6 +86,7 @@ pub extern "C" fn android_start(argc: int, argv: *const *const u8) -> int {
 #[cfg(not(test))]
 pub fn run(opts: opts::Opts) {
     ::servo_util::opts::set_experimental_enabled(opts.enable_experimental);
+    ::servo_util::opts::set_gc_zeal_enabled(opts.gc_zeal);
     RegisterBindings::RegisterProxyHandlers();
 
     let mut pool_config = green::PoolConfig::new();
@@ -95,14 +96,14 @@ pub fn run(opts: opts::Opts) {
     let (compositor_port, compositor_chan) = CompositorChan::new();
     let time_profiler_chan = TimeProfiler::create(opts.time_profiler_period);
     let memory_profiler_chan = MemoryProfiler::create(opts.memory_profiler_period);
-    let devtools_chan = if opts.devtools_server {
-        Some(devtools::start_server())
-    } else {
-        None
-    };
+    let devtools_chan = opts.devtools_port.map(|port| {
+        let (chan, _) = devtools::start_server(port);
+        chan
+    });
 
     let opts_clone = opts.clone();
     let time_profiler_chan_clone = time_profiler_chan.clone();
+    let memory_profiler_chan_clone = memory_profiler_chan.clone();
 
     let (result_chan, result_port) = channel();
     TaskBuilder::new()
@@ -128,6 +129,7 @@ pub fn run(opts: opts::Opts) {
                                                       image_cache_task,
                                                       font_cache_task,
                                                       time_profiler_chan_clone,
+                                                      memory_profiler_chan_clone,
                                                       devtools_chan);
 
         // Send the URL command to the constellation.
@@ -0,0 +1,6 @@
+onmessage = function(event) {
+  postMessage("before close");
+  close();
+  postMessage("after close");
+  setTimeout(function() { postMessage("timeout after close"); }, 0);
+};
@@ -0,0 +1,4 @@
+setTimeout(function() {
+  postMessage(btoa("worker"));
+  throw new Error("thrown from a worker");
+}, 0);
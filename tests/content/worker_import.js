@@ -0,0 +1,4 @@
+var order = [];
+importScripts("worker_import_a.js", "worker_import_b.js");
+order.push("main");
+postMessage(order.join(","));
@@ -0,0 +1 @@
+order.push("b");
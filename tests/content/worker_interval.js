@@ -0,0 +1,5 @@
+var count = 0;
+setInterval(function() {
+  count++;
+  postMessage(count);
+}, 10);
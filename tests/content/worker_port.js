@@ -0,0 +1,8 @@
+// Answers whatever arrives on the port it was sent.
+onmessage = function(event) {
+  var port = event.ports[0];
+  port.onmessage = function(event) {
+    port.postMessage("worker:" + event.data);
+  };
+  postMessage("ready:" + event.data + ":" + event.ports.length);
+};
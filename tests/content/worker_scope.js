@@ -0,0 +1,16 @@
+var result = {
+  self: self === this && self.self === self,
+  href: location.href,
+  protocol: location.protocol,
+  pathname: location.pathname,
+  userAgent: navigator.userAgent,
+  language: navigator.language,
+  onLine: navigator.onLine,
+};
+try {
+  importScripts("nonexistent_worker_script.js");
+  result.importError = "none";
+} catch (e) {
+  result.importError = e.name;
+}
+postMessage(result);
@@ -0,0 +1,14 @@
+var errors = [];
+onerror = function(event) {
+  errors.push(event.message);
+  if (event.message.indexOf("handled in the worker") != -1) {
+    event.preventDefault();
+  }
+};
+onmessage = function(event) {
+  if (event.data == "report") {
+    postMessage(errors.length);
+    return;
+  }
+  throw new Error(event.data);
+};
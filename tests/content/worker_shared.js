@@ -0,0 +1,15 @@
+// Numbers its connections, and tells each client which one it is and how
+// many there were so far.
+var connections = 0;
+onconnect = function(event) {
+  connections++;
+  var connection = connections;
+  var port = event.ports[0];
+  port.onmessage = function(event) {
+    port.postMessage({
+      name: name,
+      connection: connection,
+      connections: connections,
+    });
+  };
+};
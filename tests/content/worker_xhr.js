@@ -0,0 +1,57 @@
+var result = {};
+
+// A synchronous request is done by the time send() returns, and only
+// changes state when it is opened and when it is done.
+var sync = new XMLHttpRequest();
+var states = [];
+sync.onreadystatechange = function() {
+  states.push(sync.readyState);
+};
+sync.open("GET", "test_xmlhttprequest.txt", false);
+sync.send();
+result.syncState = sync.readyState;
+result.syncStatus = sync.status;
+result.syncText = sync.responseText;
+result.syncStates = states.join();
+
+// Unlike in a window, synchronous requests can have a timeout and a
+// response type.
+var timed = new XMLHttpRequest();
+try {
+  timed.timeout = 10000;
+  timed.responseType = "text";
+  timed.open("GET", "test_xmlhttprequest.txt", false);
+  timed.send();
+  result.timedText = timed.response;
+} catch (e) {
+  result.timedText = e.name;
+}
+
+// Asynchronous requests are delivered by the worker's event loop.
+var async = new XMLHttpRequest();
+var asyncStates = [];
+async.onreadystatechange = function() {
+  asyncStates.push(async.readyState);
+};
+async.onload = function() {
+  result.asyncStates = asyncStates.join();
+  result.asyncText = async.responseText;
+
+  // fetch() is there too, and settles on the worker's event loop.
+  fetch("test_xmlhttprequest.txt").then(function(response) {
+    result.fetchStatus = response.status;
+    return response.text();
+  }).then(function(text) {
+    result.fetchText = text;
+    return fetch("does_not_exist.txt");
+  }).then(function() {
+    result.fetchError = "resolved";
+  }, function(e) {
+    result.fetchError = e.name;
+  }).then(function() {
+    postMessage(result);
+  });
+};
+async.open("GET", "test_xmlhttprequest.txt");
+async.send();
+result.asyncStateAfterSend = async.readyState;
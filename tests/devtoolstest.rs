@@ -0,0 +1,155 @@
+// Copyright 2014 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Inspects a page, reads what it logs and evaluates script in it through the devtools server of
+//! a headless Servo that loads it, the way Firefox would.
+
+#![deny(unused_imports, unused_variable)]
+
+extern crate serialize;
+
+use serialize::json;
+use std::io::{BufferedReader, TcpStream};
+use std::io::process::{Command, Ignored, CreatePipe, InheritFd};
+use std::io::timer::sleep;
+use std::{num, os, task};
+use std::time::Duration;
+
+fn main() {
+    let args = os::args();
+    let path = os::make_absolute(&Path::new(args[1].as_slice()));
+    // FIXME (#1094): not the right way to transform a path
+    let url = format!("file://{}", path.display());
+
+    let mut prc = match Command::new("target/servo")
+        .args(["-z", "-f", "--devtools", "0", url.as_slice()])
+        .stdin(Ignored)
+        .stdout(CreatePipe(false, true))
+        .stderr(InheritFd(2))
+        .spawn()
+    {
+        Ok(p) => p,
+        _ => fail!("Unable to spawn process."),
+    };
+
+    // Servo tells which port the server listens on, and goes on printing the
+    // packets it receives.
+    let mut stdout = BufferedReader::new(prc.stdout.take().unwrap());
+    let prefix = "devtools server listening on port ";
+    let mut port = None;
+    while port.is_none() {
+        let line = stdout.read_line().unwrap();
+        print!("{}", line);
+        if line.as_slice().starts_with(prefix) {
+            port = from_str(line.as_slice().slice_from(prefix.len()).trim());
+        }
+    }
+    spawn(proc() {
+        for line in stdout.lines() {
+            print!("{}", line.unwrap());
+        }
+    });
+
+    let port = port.unwrap();
+    let result = task::try(proc() { inspect(port) });
+    prc.signal_kill().unwrap();
+    let _ = prc.wait();
+    if result.is_err() {
+        os::set_exit_status(1);
+    }
+}
+
+fn inspect(port: u16) {
+    let mut stream = TcpStream::connect("127.0.0.1", port).unwrap();
+    assert_eq!(string_field(&read_packet(&mut stream), "from").as_slice(), "root");
+
+    // The server only learns about the page while it waits for connections.
+    let mut tabs = vec!();
+    while tabs.is_empty() {
+        let reply = request(&mut stream, r#"{"to":"root","type":"listTabs"}"#);
+        tabs = reply.find(&"tabs".to_string()).unwrap().as_list().unwrap().clone();
+        sleep(Duration::milliseconds(100));
+    }
+    let tab = tabs[0].as_object().unwrap().clone();
+    let console = string_field(&tab, "consoleActor");
+    let inspector = string_field(&tab, "inspectorActor");
+
+    // The markup of the body, as the serializer writes it.
+    let reply = request(&mut stream, format!(r#"{{"to":"{:s}","type":"getWalker"}}"#,
+                                             inspector).as_slice());
+    let walker = string_field(object_field(&reply, "walker"), "actor");
+    let reply = request(&mut stream, format!(r#"{{"to":"{:s}","type":"documentElement"}}"#,
+                                             walker).as_slice());
+    let html = string_field(object_field(&reply, "node"), "actor");
+    let reply = request(&mut stream, format!(r#"{{"to":"{:s}","type":"children","node":"{:s}"}}"#,
+                                             walker, html).as_slice());
+    let children = reply.find(&"nodes".to_string()).unwrap().as_list().unwrap().clone();
+    assert_eq!(children.len(), 2);
+    let body = children[1].as_object().unwrap().clone();
+    assert_eq!(string_field(&body, "nodeName").as_slice(), "BODY");
+    let reply = request(&mut stream, format!(r#"{{"to":"{:s}","type":"outerHTML","node":"{:s}"}}"#,
+                                             walker, string_field(&body, "actor")).as_slice());
+    assert!(string_field(&reply, "value").as_slice().starts_with("<body><p>Hello</p>"));
+
+    // Script evaluated in the page.
+    let result = evaluate(&mut stream, console.as_slice(), "document.body.firstChild.textContent");
+    assert_eq!(result.as_string(), Some("Hello"));
+    let result = evaluate(&mut stream, console.as_slice(), "document.title == 'devtools'");
+    assert_eq!(result.as_boolean(), Some(true));
+
+    // What the page logs once the client listens for it, which may arrive
+    // before the reply to the request to listen.
+    write_packet(&mut stream, format!(r#"{{"to":"{:s}","type":"startListeners","listeners":["ConsoleAPI"]}}"#,
+                                      console).as_slice());
+    let mut packet = read_packet(&mut stream);
+    while packet.find(&"type".to_string()).and_then(|t| t.as_string()) != Some("consoleAPICall") {
+        packet = read_packet(&mut stream);
+    }
+    let message = object_field(&packet, "message");
+    assert_eq!(string_field(message, "level").as_slice(), "log");
+    let arguments = message.find(&"arguments".to_string()).unwrap().as_list().unwrap().clone();
+    assert_eq!(arguments[0].as_string().unwrap(), "ticked");
+}
+
+fn write_packet(stream: &mut TcpStream, packet: &str) {
+    stream.write_str(format!("{:u}:{:s}", packet.len(), packet).as_slice()).unwrap();
+}
+
+fn read_packet(stream: &mut TcpStream) -> json::JsonObject {
+    let mut buffer = vec!();
+    loop {
+        match stream.read_byte().unwrap() {
+            c if c == ':' as u8 => break,
+            c => buffer.push(c),
+        }
+    }
+    let packet_len = num::from_str_radix(String::from_utf8(buffer).unwrap().as_slice(), 10).unwrap();
+    let packet = String::from_utf8(stream.read_exact(packet_len).unwrap()).unwrap();
+    json::from_str(packet.as_slice()).unwrap().as_object().unwrap().clone()
+}
+
+fn request(stream: &mut TcpStream, packet: &str) -> json::JsonObject {
+    write_packet(stream, packet);
+    read_packet(stream)
+}
+
+/// Evaluates `text` in the page through the console actor `console`, and returns the result.
+fn evaluate(stream: &mut TcpStream, console: &str, text: &str) -> json::Json {
+    let reply = request(stream, format!(r#"{{"to":"{:s}","type":"evaluateJS","text":"{:s}"}}"#,
+                                        console, text).as_slice());
+    reply.find(&"result".to_string()).unwrap().clone()
+}
+
+fn string_field(obj: &json::JsonObject, key: &str) -> String {
+    obj.find(&key.to_string()).unwrap().as_string().unwrap().to_string()
+}
+
+fn object_field<'a>(obj: &'a json::JsonObject, key: &str) -> &'a json::JsonObject {
+    obj.find(&key.to_string()).unwrap().as_object().unwrap()
+}